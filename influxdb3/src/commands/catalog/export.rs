@@ -0,0 +1,36 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use tokio::io::AsyncWriteExt;
+
+use super::HostConfig;
+
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(flatten)]
+    host_config: HostConfig,
+
+    /// File to write the exported catalog to, otherwise it is written to stdout
+    #[clap(short = 'o', long = "output")]
+    output: Option<PathBuf>,
+}
+
+pub(super) async fn command(config: Config) -> Result<(), Box<dyn Error>> {
+    let client = config.host_config.client()?;
+    let export = client.api_v3_configure_catalog_export().await?;
+
+    match config.output {
+        Some(path) => {
+            tokio::fs::write(&path, &export).await?;
+            println!("catalog exported to {}", path.display());
+        }
+        None => {
+            let mut stdout = tokio::io::stdout();
+            stdout.write_all(&export).await?;
+            stdout.write_all(b"\n").await?;
+            stdout.flush().await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use super::HostConfig;
+
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(flatten)]
+    host_config: HostConfig,
+
+    /// File to read the exported catalog from
+    #[clap(short = 'f', long = "file")]
+    file_path: PathBuf,
+
+    /// Show the changes that the import would make, and any conflicts, without making them
+    #[clap(long = "dry-run")]
+    dry_run: bool,
+}
+
+pub(super) async fn command(config: Config) -> Result<(), Box<dyn Error>> {
+    let client = config.host_config.client()?;
+    let export = tokio::fs::read(&config.file_path).await?;
+    let diff = client
+        .api_v3_configure_catalog_import(export, config.dry_run)
+        .await?;
+
+    for change in &diff.changes {
+        println!("+ {}: {}", change.database, change.description);
+    }
+    for conflict in &diff.conflicts {
+        match &conflict.table {
+            Some(table) => println!(
+                "! {}, table {}: {}",
+                conflict.database, table, conflict.description
+            ),
+            None => println!("! {}: {}", conflict.database, conflict.description),
+        }
+    }
+
+    if !diff.conflicts.is_empty() {
+        return Err(format!(
+            "{} conflicts with the catalog, nothing was imported",
+            diff.conflicts.len()
+        )
+        .into());
+    }
+    if diff.applied {
+        println!(
+            "catalog imported successfully, {} changes made",
+            diff.changes.len()
+        );
+    } else if diff.changes.is_empty() {
+        println!("catalog already up to date");
+    } else {
+        println!("dry run, {} changes not made", diff.changes.len());
+    }
+
+    Ok(())
+}
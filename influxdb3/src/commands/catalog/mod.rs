@@ -0,0 +1,60 @@
+//! Commands for moving the schema of one server to another, by exporting its catalog to a JSON
+//! document and importing that document into the other server.
+
+use std::error::Error;
+
+use clap::Parser;
+use secrecy::{ExposeSecret, Secret};
+use url::Url;
+
+pub mod export;
+pub mod import;
+
+#[derive(Debug, clap::Parser)]
+pub(crate) struct Config {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, clap::Parser)]
+enum Command {
+    /// Export the databases, tables and last caches in the catalog to a JSON document
+    Export(export::Config),
+
+    /// Import an exported catalog, creating whatever it has that is missing from the catalog
+    Import(import::Config),
+}
+
+/// The server to export the catalog from, or import it into
+#[derive(Debug, Parser)]
+pub struct HostConfig {
+    /// The host URL of the running InfluxDB 3.0 server
+    #[clap(
+        short = 'h',
+        long = "host",
+        env = "INFLUXDB3_HOST_URL",
+        default_value = "http://127.0.0.1:8181"
+    )]
+    pub host_url: Url,
+
+    /// The token for authentication with the InfluxDB 3.0 server
+    #[clap(long = "token", env = "INFLUXDB3_AUTH_TOKEN")]
+    pub auth_token: Option<Secret<String>>,
+}
+
+impl HostConfig {
+    fn client(self) -> Result<influxdb3_client::Client, influxdb3_client::Error> {
+        let mut client = influxdb3_client::Client::new(self.host_url)?;
+        if let Some(t) = self.auth_token {
+            client = client.with_auth_token(t.expose_secret());
+        }
+        Ok(client)
+    }
+}
+
+pub(crate) async fn command(config: Config) -> Result<(), Box<dyn Error>> {
+    match config.command {
+        Command::Export(config) => export::command(config).await,
+        Command::Import(config) => import::command(config).await,
+    }
+}
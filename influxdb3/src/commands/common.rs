@@ -1,4 +1,4 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use secrecy::Secret;
 use url::Url;
 
@@ -21,3 +21,25 @@ pub struct InfluxDb3Config {
     #[clap(long = "token", env = "INFLUXDB3_AUTH_TOKEN")]
     pub auth_token: Option<Secret<String>>,
 }
+
+/// How writes that would add new columns to an existing table are handled
+#[derive(Debug, ValueEnum, Clone, Copy)]
+#[clap(rename_all = "snake_case")]
+pub enum SchemaPolicy {
+    /// New columns are added to the table
+    Open,
+    /// Lines that would add new columns are rejected
+    Locked,
+    /// Lines are accepted, but the values of any new columns are dropped
+    DropUnknown,
+}
+
+impl From<SchemaPolicy> for influxdb3_client::SchemaPolicy {
+    fn from(this: SchemaPolicy) -> Self {
+        match this {
+            SchemaPolicy::Open => Self::Open,
+            SchemaPolicy::Locked => Self::Locked,
+            SchemaPolicy::DropUnknown => Self::DropUnknown,
+        }
+    }
+}
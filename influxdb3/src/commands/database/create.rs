@@ -0,0 +1,34 @@
+use std::error::Error;
+
+use secrecy::ExposeSecret;
+
+use crate::commands::common::InfluxDb3Config;
+
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(flatten)]
+    influxdb3_config: InfluxDb3Config,
+
+    /// How long data is kept for, e.g. "30d", otherwise it is kept forever
+    #[clap(long = "retention-period")]
+    retention_period: Option<humantime::Duration>,
+}
+
+pub(super) async fn command(config: Config) -> Result<(), Box<dyn Error>> {
+    let InfluxDb3Config {
+        host_url,
+        database_name,
+        auth_token,
+    } = config.influxdb3_config;
+    let mut client = influxdb3_client::Client::new(host_url)?;
+    if let Some(t) = auth_token {
+        client = client.with_auth_token(t.expose_secret());
+    }
+    client
+        .api_v3_configure_database_create(&database_name, config.retention_period.map(Into::into))
+        .await?;
+
+    println!("database {database_name} created successfully");
+
+    Ok(())
+}
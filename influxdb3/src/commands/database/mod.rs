@@ -0,0 +1,42 @@
+use std::error::Error;
+
+pub mod create;
+pub mod delete;
+pub mod quotas;
+pub mod retention;
+pub mod schema_policy;
+
+#[derive(Debug, clap::Parser)]
+pub(crate) struct Config {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, clap::Parser)]
+enum Command {
+    /// Create a database that has no tables, optionally with a retention period
+    Create(create::Config),
+
+    /// Delete a database, along with all of its tables and their data
+    Delete(delete::Config),
+
+    /// Set the default schema policy of the tables in a database
+    SchemaPolicy(schema_policy::Config),
+
+    /// Set or remove the retention period of a database
+    Retention(retention::Config),
+
+    /// Set the quotas of a database, replacing the ones it had. The quotas that are not given are
+    /// unlimited.
+    Quotas(quotas::Config),
+}
+
+pub(crate) async fn command(config: Config) -> Result<(), Box<dyn Error>> {
+    match config.command {
+        Command::Create(config) => create::command(config).await,
+        Command::Delete(config) => delete::command(config).await,
+        Command::SchemaPolicy(config) => schema_policy::command(config).await,
+        Command::Retention(config) => retention::command(config).await,
+        Command::Quotas(config) => quotas::command(config).await,
+    }
+}
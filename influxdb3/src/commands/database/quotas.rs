@@ -0,0 +1,58 @@
+use std::error::Error;
+
+use influxdb3_client::DatabaseQuotas;
+use secrecy::ExposeSecret;
+
+use crate::commands::common::InfluxDb3Config;
+
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(flatten)]
+    influxdb3_config: InfluxDb3Config,
+
+    /// The maximum number of tables in the database
+    #[clap(long = "max-tables")]
+    max_tables: Option<u64>,
+
+    /// The maximum number of columns, summed over all tables in the database
+    #[clap(long = "max-columns")]
+    max_columns: Option<u64>,
+
+    /// The maximum number of distinct series written to the database
+    #[clap(long = "max-series")]
+    max_series: Option<u64>,
+
+    /// The maximum size in bytes of the parquet files persisted for the database
+    #[clap(long = "max-stored-bytes")]
+    max_stored_bytes: Option<u64>,
+
+    /// The maximum number of bytes of line protocol written to the database each second
+    #[clap(long = "max-write-bytes-per-second")]
+    max_write_bytes_per_second: Option<u64>,
+}
+
+pub(super) async fn command(config: Config) -> Result<(), Box<dyn Error>> {
+    let InfluxDb3Config {
+        host_url,
+        database_name,
+        auth_token,
+    } = config.influxdb3_config;
+    let mut client = influxdb3_client::Client::new(host_url)?;
+    if let Some(t) = auth_token {
+        client = client.with_auth_token(t.expose_secret());
+    }
+    let quotas = DatabaseQuotas {
+        max_tables: config.max_tables,
+        max_columns: config.max_columns,
+        max_series: config.max_series,
+        max_stored_bytes: config.max_stored_bytes,
+        max_write_bytes_per_second: config.max_write_bytes_per_second,
+    };
+    client
+        .api_v3_configure_quotas(&database_name, quotas)
+        .await?;
+
+    println!("quotas of database {database_name} set successfully");
+
+    Ok(())
+}
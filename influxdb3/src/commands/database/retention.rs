@@ -0,0 +1,39 @@
+use std::error::Error;
+
+use secrecy::ExposeSecret;
+
+use crate::commands::common::InfluxDb3Config;
+
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(flatten)]
+    influxdb3_config: InfluxDb3Config,
+
+    /// How long data is kept for, e.g. "30d". Data older than this is no longer queried, and is
+    /// deleted in the background.
+    #[clap(long = "period", required_unless_present = "forever")]
+    period: Option<humantime::Duration>,
+
+    /// Remove the retention period, so that data is kept forever
+    #[clap(long = "forever", conflicts_with = "period")]
+    forever: bool,
+}
+
+pub(super) async fn command(config: Config) -> Result<(), Box<dyn Error>> {
+    let InfluxDb3Config {
+        host_url,
+        database_name,
+        auth_token,
+    } = config.influxdb3_config;
+    let mut client = influxdb3_client::Client::new(host_url)?;
+    if let Some(t) = auth_token {
+        client = client.with_auth_token(t.expose_secret());
+    }
+    client
+        .api_v3_configure_retention_period(&database_name, config.period.map(Into::into))
+        .await?;
+
+    println!("retention period of database {database_name} set successfully");
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use std::error::Error;
+
+use secrecy::ExposeSecret;
+
+use crate::commands::common::{InfluxDb3Config, SchemaPolicy};
+
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(flatten)]
+    influxdb3_config: InfluxDb3Config,
+
+    /// The schema policy used by tables in the database that do not have their own
+    #[clap(value_enum, long = "policy")]
+    policy: SchemaPolicy,
+}
+
+pub(super) async fn command(config: Config) -> Result<(), Box<dyn Error>> {
+    let InfluxDb3Config {
+        host_url,
+        database_name,
+        auth_token,
+    } = config.influxdb3_config;
+    let mut client = influxdb3_client::Client::new(host_url)?;
+    if let Some(t) = auth_token {
+        client = client.with_auth_token(t.expose_secret());
+    }
+    client
+        .api_v3_configure_schema_policy(&database_name, None, config.policy.into())
+        .await?;
+
+    println!("schema policy of database {database_name} set successfully");
+
+    Ok(())
+}
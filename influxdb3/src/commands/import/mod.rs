@@ -0,0 +1,23 @@
+//! Commands for importing data into a server without writing it through the WAL.
+
+use std::error::Error;
+
+pub mod parquet;
+
+#[derive(Debug, clap::Parser)]
+pub(crate) struct Config {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, clap::Parser)]
+enum Command {
+    /// Import parquet files into a table, persisting their rows directly to object store
+    Parquet(parquet::Config),
+}
+
+pub(crate) async fn command(config: Config) -> Result<(), Box<dyn Error>> {
+    match config.command {
+        Command::Parquet(config) => parquet::command(config).await,
+    }
+}
@@ -0,0 +1,76 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use secrecy::ExposeSecret;
+
+use crate::commands::common::InfluxDb3Config;
+
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(flatten)]
+    influxdb3_config: InfluxDb3Config,
+
+    /// The table to import the files into, which is created if it does not exist
+    #[clap(short = 't', long = "table")]
+    table_name: String,
+
+    /// The parquet files to import. Each file is imported in turn, and its rows are persisted in a
+    /// snapshot of its own, so a file that fails to import does not undo the ones before it.
+    ///
+    /// Columns are marked as tags or fields in the Arrow schema of files persisted by InfluxDB 3,
+    /// and columns of other files that are not marked take their type from the table. Every file
+    /// needs a `time` column.
+    #[clap(required = true, num_args = 1..)]
+    files: Vec<PathBuf>,
+
+    /// The files are paths in the object store of the server, which reads them from there, rather
+    /// than local files that are sent to it. Use this for files that are larger than a request to
+    /// the server may be.
+    #[clap(long = "from-object-store")]
+    from_object_store: bool,
+}
+
+pub(super) async fn command(config: Config) -> Result<(), Box<dyn Error>> {
+    let InfluxDb3Config {
+        host_url,
+        database_name,
+        auth_token,
+    } = config.influxdb3_config;
+    let mut client = influxdb3_client::Client::new(host_url)?;
+    if let Some(t) = auth_token {
+        client = client.with_auth_token(t.expose_secret());
+    }
+
+    for path in &config.files {
+        let summary = if config.from_object_store {
+            client
+                .api_v3_import_parquet_from_object_store(
+                    database_name.as_str(),
+                    config.table_name.as_str(),
+                    path.to_string_lossy(),
+                )
+                .await
+        } else {
+            let file = tokio::fs::read(path).await?;
+            client
+                .api_v3_import_parquet(database_name.as_str(), config.table_name.as_str(), file)
+                .await
+        }
+        .map_err(|e| format!("failed to import {}: {e}", path.display()))?;
+        println!(
+            "imported {} rows from {} into {} parquet files in snapshot {}",
+            summary.row_count,
+            path.display(),
+            summary.persisted_file_count,
+            summary.snapshot_sequence_number
+        );
+        if summary.persisted_row_count < summary.row_count {
+            println!(
+                "  {} duplicate rows were removed",
+                summary.row_count - summary.persisted_row_count
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! Command for restoring the state of a host as of an earlier point in time into a new host.
+
+use std::time::UNIX_EPOCH;
+
+use clap_blocks::object_store::{make_object_store, ObjectStoreConfig};
+use influxdb3_wal::WalFileSequenceNumber;
+use influxdb3_write::restore::{restore, RestorePoint};
+use iox_time::Time;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Cannot parse object store config: {0}")]
+    ObjectStoreParsing(#[from] clap_blocks::object_store::ParseError),
+
+    #[error("restore failed: {0}")]
+    Restore(#[from] influxdb3_write::restore::Error),
+}
+
+pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, clap::Parser)]
+pub(crate) struct Config {
+    /// object store options
+    #[clap(flatten)]
+    object_store_config: ObjectStoreConfig,
+
+    /// The host identifier prefix of the host to restore
+    #[clap(long = "host-id", env = "INFLUXDB3_HOST_IDENTIFIER_PREFIX", action)]
+    host_identifier_prefix: String,
+
+    /// The new host identifier prefix to restore into, which must not have been used yet. Start
+    /// the server with this host identifier prefix to run the restored host.
+    #[clap(long = "target-host-id", action)]
+    target_host_identifier_prefix: String,
+
+    /// The point to restore to: either a WAL file sequence number, to include the writes up to and
+    /// including that file, or an RFC3339 time, to include the writes in the WAL files persisted at
+    /// or before it. The WAL files after the last snapshot before this point must still be
+    /// retained, see `--wal-snapshotted-retention` on the `serve` command.
+    #[clap(long = "until", value_parser = parse_restore_point, action)]
+    until: RestorePoint,
+}
+
+pub(crate) async fn command(config: Config) -> Result<()> {
+    let object_store = make_object_store(&config.object_store_config)?;
+    let summary = restore(
+        object_store,
+        &config.host_identifier_prefix,
+        &config.target_host_identifier_prefix,
+        config.until,
+    )
+    .await?;
+
+    println!(
+        "restored host {} to wal file {} as host {}",
+        config.host_identifier_prefix,
+        summary.wal_file_number,
+        config.target_host_identifier_prefix
+    );
+    match summary.snapshot_sequence_number {
+        Some(snapshot) => println!(
+            "  copied snapshots up to {snapshot} with {} parquet files",
+            summary.parquet_files
+        ),
+        None => println!("  no snapshots were copied"),
+    }
+    println!(
+        "  copied {} wal files to replay on startup",
+        summary.wal_files.len()
+    );
+
+    Ok(())
+}
+
+fn parse_restore_point(s: &str) -> Result<RestorePoint, String> {
+    if let Ok(wal_file_number) = s.parse::<u64>() {
+        return Ok(RestorePoint::WalFile(WalFileSequenceNumber::new(
+            wal_file_number,
+        )));
+    }
+    let time = humantime::parse_rfc3339_weak(s)
+        .map_err(|e| format!("expected a wal file sequence number or an RFC3339 time: {e}"))?;
+    let since_epoch = time
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| "the time must be after the unix epoch".to_string())?;
+    Ok(RestorePoint::Time(Time::from_timestamp_nanos(
+        since_epoch.as_nanos() as i64,
+    )))
+}
@@ -8,6 +8,7 @@ use clap_blocks::{
     tokio::TokioDatafusionConfig,
 };
 use datafusion_util::config::register_iox_object_store;
+use influxdb3_catalog::catalog::Catalog;
 use influxdb3_process::{
     build_malloc_conf, setup_metric_registry, INFLUXDB3_GIT_HASH, INFLUXDB3_VERSION, PROCESS_UUID,
 };
@@ -18,12 +19,16 @@ use influxdb3_server::{
     serve, CommonServerState,
 };
 use influxdb3_telemetry::store::TelemetryStore;
-use influxdb3_wal::{Gen1Duration, WalConfig};
+use influxdb3_wal::{
+    Gen1Duration, LocalDiskConfig, ReplicaConfig, WalBackend, WalCompression, WalConfig,
+};
 use influxdb3_write::{
     last_cache::LastCacheProvider,
     parquet_cache::create_cached_obj_store_and_oracle,
     persister::Persister,
-    write_buffer::{persisted_files::PersistedFiles, WriteBufferImpl},
+    write_buffer::{
+        persisted_files::PersistedFiles, rejected_writes::RejectedWritesConfig, WriteBufferImpl,
+    },
     WriteBuffer,
 };
 use iox_query::exec::{DedicatedExecutor, Executor, ExecutorConfig};
@@ -32,7 +37,11 @@ use object_store::ObjectStore;
 use observability_deps::tracing::*;
 use panic_logging::SendPanicsToTracing;
 use parquet_file::storage::{ParquetStorage, StorageId};
-use std::{collections::HashMap, path::Path, str::FromStr};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 use std::{num::NonZeroUsize, sync::Arc};
 use thiserror::Error;
 use tokio::net::TcpListener;
@@ -79,6 +88,9 @@ pub enum Error {
 
     #[error("failed to initialize last cache: {0}")]
     InitializeLastCache(#[source] influxdb3_write::last_cache::Error),
+
+    #[error("no catalog found for host {0}, which must be started before its read replicas")]
+    ReadReplicaHostNotFound(String),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -187,6 +199,61 @@ pub struct Config {
     )]
     pub wal_snapshot_size: usize,
 
+    /// Compression applied to the contents of WAL files before they are written. One of none,
+    /// zstd or snappy. Files written with any compression can be replayed regardless of this
+    /// setting.
+    #[clap(
+        long = "wal-compression",
+        env = "INFLUXDB3_WAL_COMPRESSION",
+        default_value = "none",
+        action
+    )]
+    pub wal_compression: WalCompression,
+
+    /// Move WAL files that cannot be deserialized during replay to a `quarantine/` prefix under
+    /// the host identifier and continue startup, rather than failing. Writes in quarantined files
+    /// are lost, and are listed in the `system.quarantined_wal_files` table.
+    #[clap(
+        long = "wal-quarantine-corrupt-files",
+        env = "INFLUXDB3_WAL_QUARANTINE_CORRUPT_FILES",
+        default_value_t = false,
+        action
+    )]
+    pub wal_quarantine_corrupt_files: bool,
+
+    /// How long WAL files are kept under a `wal_retained/` prefix after a snapshot covers them,
+    /// so that `influxdb3 restore` can rebuild state as of a point within this window. By
+    /// default they are deleted as soon as the snapshot is done.
+    #[clap(
+        long = "wal-snapshotted-retention",
+        env = "INFLUXDB3_WAL_SNAPSHOTTED_RETENTION",
+        default_value = "0s",
+        action
+    )]
+    pub wal_snapshotted_retention: humantime::Duration,
+
+    /// The number of the most recently persisted catalog files to keep. Older files are deleted
+    /// when a new catalog is persisted, except for those needed to restore from the retained WAL
+    /// files. All catalog files are kept if set to 0.
+    #[clap(
+        long = "catalog-files-to-keep",
+        env = "INFLUXDB3_CATALOG_FILES_TO_KEEP",
+        default_value = "10",
+        action
+    )]
+    pub catalog_files_to_keep: usize,
+
+    /// Load an older catalog file when the newest one can't be read, even if WAL files needed to
+    /// catch it up with the newest one are gone. Changes to the catalog made in the missing WAL
+    /// files are lost. By default startup fails instead.
+    #[clap(
+        long = "allow-catalog-fallback-across-wal-gap",
+        env = "INFLUXDB3_ALLOW_CATALOG_FALLBACK_ACROSS_WAL_GAP",
+        default_value_t = false,
+        action
+    )]
+    pub allow_catalog_fallback_across_wal_gap: bool,
+
     /// The maximum number of writes requests that can be buffered before a flush must be run
     /// and succeed.
     #[clap(
@@ -197,6 +264,52 @@ pub struct Config {
     )]
     pub wal_max_write_buffer_size: usize,
 
+    /// The maximum estimated size in bytes of the buffered writes. The buffer is flushed to a WAL
+    /// file early once it reaches this size, rather than waiting for the flush interval.
+    #[clap(
+        long = "wal-max-write-buffer-bytes",
+        env = "INFLUXDB3_WAL_MAX_WRITE_BUFFER_BYTES",
+        default_value = "104857600",
+        action
+    )]
+    pub wal_max_write_buffer_bytes: usize,
+
+    /// How long a write waits for a full write buffer to be flushed before it is rejected. Rejected
+    /// writes get a 503 response with a `Retry-After` header.
+    #[clap(
+        long = "wal-buffer-full-timeout",
+        env = "INFLUXDB3_WAL_BUFFER_FULL_TIMEOUT",
+        default_value = "5s",
+        action
+    )]
+    pub wal_buffer_full_timeout: humantime::Duration,
+
+    /// Write the WAL to segment files in this directory on a local volume, rather than writing
+    /// each flush of the buffer directly to object store. Sealed segments are uploaded to object
+    /// store in the background.
+    #[clap(long = "wal-local-dir", env = "INFLUXDB3_WAL_LOCAL_DIR", action)]
+    pub wal_local_dir: Option<PathBuf>,
+
+    /// The size in bytes a local WAL segment can grow to before it is sealed and uploaded to
+    /// object store. Only used along with `--wal-local-dir`.
+    #[clap(
+        long = "wal-max-segment-size-bytes",
+        env = "INFLUXDB3_WAL_MAX_SEGMENT_SIZE_BYTES",
+        default_value = "67108864", // 64 MiB
+        action
+    )]
+    pub wal_max_segment_size_bytes: u64,
+
+    /// How long a local WAL segment is appended to before it is sealed and uploaded to object
+    /// store, however small it is. Only used along with `--wal-local-dir`.
+    #[clap(
+        long = "wal-max-segment-age",
+        env = "INFLUXDB3_WAL_MAX_SEGMENT_AGE",
+        default_value = "10s",
+        action
+    )]
+    pub wal_max_segment_age: humantime::Duration,
+
     // TODO - tune this default:
     /// The size of the query log. Up to this many queries will remain in the log before
     /// old queries are evicted to make room for new ones.
@@ -208,6 +321,27 @@ pub struct Config {
     )]
     pub query_log_size: usize,
 
+    /// The number of lines rejected from writes that are kept, and listed in the
+    /// `system.rejected_writes` table. Once this many are kept, the oldest are dropped to make room
+    /// for new ones.
+    #[clap(
+        long = "rejected-writes-capacity",
+        env = "INFLUXDB3_REJECTED_WRITES_CAPACITY",
+        default_value = "1000",
+        action
+    )]
+    pub rejected_writes_capacity: usize,
+
+    /// Also persist the lines rejected from writes to object store, as newline-delimited JSON
+    /// files under the `rejected_writes/` prefix of the host identifier, so they can be replayed.
+    #[clap(
+        long = "persist-rejected-writes",
+        env = "INFLUXDB3_PERSIST_REJECTED_WRITES",
+        default_value_t = false,
+        action
+    )]
+    pub persist_rejected_writes: bool,
+
     // TODO - make this default to 70% of available memory:
     /// The size limit of the buffered data. If this limit is passed a snapshot will be forced.
     #[clap(
@@ -223,6 +357,27 @@ pub struct Config {
     #[clap(long = "host-id", env = "INFLUXDB3_HOST_IDENTIFIER_PREFIX", action)]
     pub host_identifier_prefix: String,
 
+    /// Run as a read replica of the host with this identifier, which must share the object store
+    /// configuration. The replica follows the WAL files and snapshots that the host writes to
+    /// object store, serves queries, and rejects all writes.
+    #[clap(
+        long = "read-replica-of",
+        env = "INFLUXDB3_READ_REPLICA_OF",
+        conflicts_with = "wal_local_dir",
+        action
+    )]
+    pub read_replica_of: Option<String>,
+
+    /// How often a read replica checks object store for new WAL files and snapshots from the
+    /// host it follows. Only used along with `--read-replica-of`.
+    #[clap(
+        long = "read-replica-poll-interval",
+        env = "INFLUXDB3_READ_REPLICA_POLL_INTERVAL",
+        default_value = "1s",
+        action
+    )]
+    pub read_replica_poll_interval: humantime::Duration,
+
     /// The size of the in-memory Parquet cache in megabytes (MB).
     #[clap(
         long = "parquet-mem-cache-size-mb",
@@ -409,28 +564,69 @@ pub async fn command(config: Config) -> Result<()> {
         )
         .with_jaeger_debug_name(config.tracing_config.traces_jaeger_debug_name);
 
-    let persister = Arc::new(Persister::new(
-        Arc::clone(&object_store),
-        config.host_identifier_prefix,
-    ));
+    // a read replica reads the files of the host it follows, and writes none of its own
+    let persister = Arc::new(
+        Persister::new(
+            Arc::clone(&object_store),
+            config
+                .read_replica_of
+                .clone()
+                .unwrap_or(config.host_identifier_prefix),
+        )
+        .with_catalog_files_to_keep(config.catalog_files_to_keep)
+        .with_catalog_fallback_across_wal_gap(config.allow_catalog_fallback_across_wal_gap),
+    );
     let wal_config = WalConfig {
         gen1_duration: config.gen1_duration,
         max_write_buffer_size: config.wal_max_write_buffer_size,
+        max_write_buffer_bytes: config.wal_max_write_buffer_bytes,
+        buffer_full_timeout: config.wal_buffer_full_timeout.into(),
         flush_interval: config.wal_flush_interval.into(),
         snapshot_size: config.wal_snapshot_size,
+        compression: config.wal_compression,
+        quarantine_corrupt_files: config.wal_quarantine_corrupt_files,
+        snapshotted_wal_retention: config.wal_snapshotted_retention.into(),
+    };
+    let wal_backend = match (config.read_replica_of.as_ref(), config.wal_local_dir) {
+        (Some(host), _) => {
+            info!(%host, "Running as a read replica");
+            WalBackend::ReadReplica(ReplicaConfig {
+                poll_interval: config.read_replica_poll_interval.into(),
+            })
+        }
+        (None, Some(directory)) => {
+            info!(directory = %directory.display(), "Writing the WAL to a local volume");
+            WalBackend::LocalDisk(LocalDiskConfig {
+                directory,
+                max_segment_size_bytes: config.wal_max_segment_size_bytes,
+                max_segment_age: config.wal_max_segment_age.into(),
+            })
+        }
+        (None, None) => WalBackend::ObjectStore,
     };
 
-    let catalog = Arc::new(
-        persister
+    let catalog = match config.read_replica_of {
+        Some(host) => persister
+            .load_catalog()
+            .await
+            .map_err(Error::InitializePersistedCatalog)?
+            .map(|persisted| Catalog::from_inner(persisted.catalog))
+            .ok_or(Error::ReadReplicaHostNotFound(host))?,
+        None => persister
             .load_or_create_catalog()
             .await
             .map_err(Error::InitializePersistedCatalog)?,
-    );
+    };
+    let catalog = Arc::new(catalog);
 
     let last_cache = LastCacheProvider::new_from_db_schema_provider(Arc::clone(&catalog) as _)
         .map_err(Error::InitializeLastCache)?;
     info!(instance_id = ?catalog.instance_id(), "Catalog initialized with");
 
+    let rejected_writes_config = RejectedWritesConfig {
+        capacity: config.rejected_writes_capacity,
+        persist: config.persist_rejected_writes,
+    };
     let write_buffer_impl = Arc::new(
         WriteBufferImpl::new(
             Arc::clone(&persister),
@@ -439,7 +635,10 @@ pub async fn command(config: Config) -> Result<()> {
             Arc::<SystemProvider>::clone(&time_provider),
             Arc::clone(&exec),
             wal_config,
+            wal_backend,
+            Arc::clone(&metrics),
             parquet_cache,
+            rejected_writes_config,
         )
         .await
         .map_err(|e| Error::WriteBufferInit(e.into()))?,
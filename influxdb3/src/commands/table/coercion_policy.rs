@@ -0,0 +1,55 @@
+use std::error::Error;
+
+use secrecy::ExposeSecret;
+
+use crate::commands::common::InfluxDb3Config;
+
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(flatten)]
+    influxdb3_config: InfluxDb3Config,
+
+    /// The table to set the coercion policy of
+    #[clap(short = 't', long = "table")]
+    table: String,
+
+    /// Convert integer and unsigned integer values written to float columns to floats
+    #[clap(long = "integer-to-float")]
+    integer_to_float: bool,
+
+    /// Convert unsigned integer values written to integer columns to integers, as long as they
+    /// are not too large
+    #[clap(long = "uinteger-to-integer")]
+    uinteger_to_integer: bool,
+
+    /// Convert values of any type written to string columns to strings
+    #[clap(long = "any-to-string")]
+    any_to_string: bool,
+}
+
+pub(super) async fn command(config: Config) -> Result<(), Box<dyn Error>> {
+    let InfluxDb3Config {
+        host_url,
+        database_name,
+        auth_token,
+    } = config.influxdb3_config;
+    let mut client = influxdb3_client::Client::new(host_url)?;
+    if let Some(t) = auth_token {
+        client = client.with_auth_token(t.expose_secret());
+    }
+    client
+        .api_v3_configure_coercion_policy(
+            database_name,
+            config.table.clone(),
+            influxdb3_client::CoercionPolicy {
+                integer_to_float: config.integer_to_float,
+                uinteger_to_integer: config.uinteger_to_integer,
+                any_to_string: config.any_to_string,
+            },
+        )
+        .await?;
+
+    println!("coercion policy of table {} set successfully", config.table);
+
+    Ok(())
+}
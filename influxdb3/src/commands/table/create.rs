@@ -0,0 +1,68 @@
+use std::error::Error;
+
+use influxdb3_client::FieldType;
+use secrecy::ExposeSecret;
+
+use crate::commands::common::InfluxDb3Config;
+
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(flatten)]
+    influxdb3_config: InfluxDb3Config,
+
+    /// The table being created
+    #[clap(short = 't', long = "table")]
+    table: String,
+
+    /// The tag columns of the table, which make up its series key
+    #[clap(long = "tags", value_delimiter = ',')]
+    tags: Vec<String>,
+
+    /// The fields of the table, each given as `<name>:<type>`, where the type is one of `string`,
+    /// `integer`, `uinteger`, `float`, or `boolean`
+    #[clap(long = "fields", value_delimiter = ',', value_parser = parse_field)]
+    fields: Vec<(String, FieldType)>,
+}
+
+fn parse_field(s: &str) -> Result<(String, FieldType), String> {
+    let (name, field_type) = s
+        .rsplit_once(':')
+        .ok_or_else(|| format!("field '{s}' must be given as <name>:<type>"))?;
+    let field_type = match field_type {
+        "string" => FieldType::String,
+        "integer" => FieldType::Integer,
+        "uinteger" => FieldType::Uinteger,
+        "float" => FieldType::Float,
+        "boolean" => FieldType::Boolean,
+        other => {
+            return Err(format!(
+                "invalid type '{other}' for field '{name}', expected one of string, integer, \
+                uinteger, float, or boolean"
+            ))
+        }
+    };
+    Ok((name.to_string(), field_type))
+}
+
+pub(super) async fn command(config: Config) -> Result<(), Box<dyn Error>> {
+    let InfluxDb3Config {
+        host_url,
+        database_name,
+        auth_token,
+    } = config.influxdb3_config;
+    let mut client = influxdb3_client::Client::new(host_url)?;
+    if let Some(t) = auth_token {
+        client = client.with_auth_token(t.expose_secret());
+    }
+    let mut b = client
+        .api_v3_configure_table_create(database_name, &config.table)
+        .tags(config.tags);
+    for (name, field_type) in config.fields {
+        b = b.field(name, field_type);
+    }
+    b.send().await?;
+
+    println!("table {} created successfully", config.table);
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::error::Error;
+
+pub mod coercion_policy;
+pub mod create;
+pub mod delete;
+pub mod rename;
+pub mod rename_column;
+pub mod schema_policy;
+
+#[derive(Debug, clap::Parser)]
+pub(crate) struct Config {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, clap::Parser)]
+enum Command {
+    /// Create a table with the given tags and fields
+    Create(create::Config),
+
+    /// Delete a table, along with all of its data
+    Delete(delete::Config),
+
+    /// Set the schema policy of a table
+    SchemaPolicy(schema_policy::Config),
+
+    /// Set which field values written to a table are converted to the type of their column,
+    /// rather than rejected. Values are not converted unless a flag allows it.
+    CoercionPolicy(coercion_policy::Config),
+
+    /// Rename a table, keeping its data
+    Rename(rename::Config),
+
+    /// Rename a column of a table, keeping its data
+    RenameColumn(rename_column::Config),
+}
+
+pub(crate) async fn command(config: Config) -> Result<(), Box<dyn Error>> {
+    match config.command {
+        Command::Create(config) => create::command(config).await,
+        Command::Delete(config) => delete::command(config).await,
+        Command::SchemaPolicy(config) => schema_policy::command(config).await,
+        Command::CoercionPolicy(config) => coercion_policy::command(config).await,
+        Command::Rename(config) => rename::command(config).await,
+        Command::RenameColumn(config) => rename_column::command(config).await,
+    }
+}
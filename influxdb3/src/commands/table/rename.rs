@@ -0,0 +1,41 @@
+use std::error::Error;
+
+use secrecy::ExposeSecret;
+
+use crate::commands::common::InfluxDb3Config;
+
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(flatten)]
+    influxdb3_config: InfluxDb3Config,
+
+    /// The table to rename
+    #[clap(short = 't', long = "table")]
+    table: String,
+
+    /// The new name of the table
+    #[clap(long = "new-name")]
+    new_name: String,
+}
+
+pub(super) async fn command(config: Config) -> Result<(), Box<dyn Error>> {
+    let InfluxDb3Config {
+        host_url,
+        database_name,
+        auth_token,
+    } = config.influxdb3_config;
+    let mut client = influxdb3_client::Client::new(host_url)?;
+    if let Some(t) = auth_token {
+        client = client.with_auth_token(t.expose_secret());
+    }
+    client
+        .api_v3_configure_table_rename(database_name, &config.table, &config.new_name)
+        .await?;
+
+    println!(
+        "table {} renamed to {} successfully",
+        config.table, config.new_name
+    );
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use std::error::Error;
+
+use secrecy::ExposeSecret;
+
+use crate::commands::common::{InfluxDb3Config, SchemaPolicy};
+
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(flatten)]
+    influxdb3_config: InfluxDb3Config,
+
+    /// The table to set the schema policy of
+    #[clap(short = 't', long = "table")]
+    table: String,
+
+    /// The schema policy of the table, which overrides the default of its database
+    #[clap(value_enum, long = "policy")]
+    policy: SchemaPolicy,
+}
+
+pub(super) async fn command(config: Config) -> Result<(), Box<dyn Error>> {
+    let InfluxDb3Config {
+        host_url,
+        database_name,
+        auth_token,
+    } = config.influxdb3_config;
+    let mut client = influxdb3_client::Client::new(host_url)?;
+    if let Some(t) = auth_token {
+        client = client.with_auth_token(t.expose_secret());
+    }
+    client
+        .api_v3_configure_schema_policy(
+            database_name,
+            Some(config.table.clone()),
+            config.policy.into(),
+        )
+        .await?;
+
+    println!("schema policy of table {} set successfully", config.table);
+
+    Ok(())
+}
@@ -0,0 +1,268 @@
+use std::fmt::Write;
+use std::sync::Arc;
+
+use influxdb3_catalog::catalog::Catalog;
+use influxdb3_catalog::DatabaseSchemaProvider;
+use influxdb3_wal::{
+    CatalogBatch, CatalogOp, DeleteBatch, DeletePredicate, SnapshotDetails, WalContents, WalOp,
+    WriteBatch,
+};
+use influxdb3_write::persister::Persister;
+use iox_time::Time;
+use serde::Serialize;
+
+use super::{read_wal_file, sequence_number, Format, Result, WalStoreConfig};
+
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(flatten)]
+    store: WalStoreConfig,
+
+    /// The format to output the contents of the files in
+    #[clap(value_enum, long = "format", default_value = "text")]
+    format: Format,
+}
+
+/// A summary of the contents of a single WAL file
+#[derive(Debug, Serialize)]
+struct WalFileSummary {
+    path: String,
+    wal_file_number: Option<u64>,
+    size_bytes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contents: Option<ContentsSummary>,
+}
+
+#[derive(Debug, Serialize)]
+struct ContentsSummary {
+    min_timestamp_ns: i64,
+    max_timestamp_ns: i64,
+    writes: Vec<TableWriteSummary>,
+    catalog_batches: Vec<CatalogBatchSummary>,
+    deletes: Vec<DeleteSummary>,
+    snapshot: Option<SnapshotDetails>,
+}
+
+#[derive(Debug, Serialize)]
+struct TableWriteSummary {
+    database: String,
+    table_id: u32,
+    /// The name of the table, if it could be found in the catalog or the WAL
+    table: Option<String>,
+    rows: usize,
+    min_time_ns: i64,
+    max_time_ns: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct CatalogBatchSummary {
+    database: String,
+    time_ns: i64,
+    ops: Vec<CatalogOp>,
+}
+
+#[derive(Debug, Serialize)]
+struct DeleteSummary {
+    database: String,
+    table: String,
+    time_ns: i64,
+    predicate: DeletePredicate,
+}
+
+pub(super) async fn command(config: Config) -> Result<()> {
+    let object_store = config.store.object_store()?;
+    let files = config.store.list_files(object_store.as_ref()).await?;
+
+    // the catalog is used to resolve the names of tables that are written to, and is updated
+    // with the catalog ops in the WAL as they are seen
+    let persister = Persister::new(
+        Arc::clone(&object_store),
+        config.store.host_identifier_prefix.as_str(),
+    );
+    let catalog = match persister.load_catalog().await? {
+        Some(persisted) => Catalog::from_inner(persisted.catalog),
+        None => Catalog::new(
+            config.store.host_identifier_prefix.as_str().into(),
+            "".into(),
+        ),
+    };
+
+    let mut summaries = Vec::with_capacity(files.len());
+    for meta in &files {
+        let (error, contents) = match read_wal_file(object_store.as_ref(), meta).await {
+            Ok(contents) => (None, Some(summarize(&catalog, contents))),
+            Err(e) => (Some(e.to_string()), None),
+        };
+        summaries.push(WalFileSummary {
+            path: meta.location.to_string(),
+            wal_file_number: sequence_number(meta).map(|n| n.as_u64()),
+            size_bytes: meta.size,
+            error,
+            contents,
+        });
+    }
+
+    match config.format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&summaries)?),
+        Format::Text => {
+            for summary in &summaries {
+                print!("{}", format_summary(summary));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn summarize(catalog: &Catalog, contents: WalContents) -> ContentsSummary {
+    let mut writes = vec![];
+    let mut catalog_batches = vec![];
+    let mut deletes = vec![];
+    for op in contents.ops {
+        match op {
+            WalOp::Write(batch) => writes.extend(summarize_write(catalog, batch)),
+            WalOp::Catalog(batch) => {
+                // the batch may already be in the persisted catalog, or may be for a catalog that
+                // was since replaced, either way it is only used for names so errors are ignored
+                let _ = catalog.apply_catalog_batch(&batch);
+                let CatalogBatch {
+                    database_name,
+                    time_ns,
+                    ops,
+                    ..
+                } = batch;
+                catalog_batches.push(CatalogBatchSummary {
+                    database: database_name.to_string(),
+                    time_ns,
+                    ops,
+                });
+            }
+            WalOp::Delete(batch) => {
+                let DeleteBatch {
+                    database_name,
+                    table_name,
+                    time_ns,
+                    predicate,
+                    ..
+                } = batch;
+                deletes.push(DeleteSummary {
+                    database: database_name.to_string(),
+                    table: table_name.to_string(),
+                    time_ns,
+                    predicate,
+                });
+            }
+        }
+    }
+
+    ContentsSummary {
+        min_timestamp_ns: contents.min_timestamp_ns,
+        max_timestamp_ns: contents.max_timestamp_ns,
+        writes,
+        catalog_batches,
+        deletes,
+        snapshot: contents.snapshot,
+    }
+}
+
+fn summarize_write(catalog: &Catalog, batch: WriteBatch) -> Vec<TableWriteSummary> {
+    let db_schema = catalog.db_schema_by_id(batch.database_id);
+    let mut writes: Vec<TableWriteSummary> = batch
+        .table_chunks
+        .into_iter()
+        .map(|(table_id, chunks)| TableWriteSummary {
+            database: batch.database_name.to_string(),
+            table_id: table_id.as_u32(),
+            table: db_schema
+                .as_ref()
+                .and_then(|db| db.table_id_to_name(table_id))
+                .map(|name| name.to_string()),
+            rows: chunks.row_count(),
+            min_time_ns: chunks.min_time,
+            max_time_ns: chunks.max_time,
+        })
+        .collect();
+    writes.sort_by_key(|w| w.table_id);
+    writes
+}
+
+fn format_summary(summary: &WalFileSummary) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{path} ({size} bytes)",
+        path = summary.path,
+        size = summary.size_bytes
+    );
+    if let Some(e) = &summary.error {
+        let _ = writeln!(out, "  invalid: {e}");
+    }
+    let Some(contents) = &summary.contents else {
+        return out;
+    };
+
+    let _ = writeln!(
+        out,
+        "  time range: {} to {}",
+        format_time(contents.min_timestamp_ns),
+        format_time(contents.max_timestamp_ns)
+    );
+    for batch in &contents.catalog_batches {
+        let _ = writeln!(out, "  catalog ops on database {}:", batch.database);
+        for op in &batch.ops {
+            let _ = writeln!(out, "    {}", op);
+        }
+    }
+    for write in &contents.writes {
+        let table = write
+            .table
+            .clone()
+            .unwrap_or_else(|| format!("<table id {}>", write.table_id));
+        let _ = writeln!(
+            out,
+            "  write to {}.{table}: {} rows from {} to {}",
+            write.database,
+            write.rows,
+            format_time(write.min_time_ns),
+            format_time(write.max_time_ns)
+        );
+    }
+    for delete in &contents.deletes {
+        let _ = write!(
+            out,
+            "  delete from {}.{}: rows from {} to {}",
+            delete.database,
+            delete.table,
+            format_time(delete.predicate.min_time_ns),
+            format_time(delete.predicate.max_time_ns),
+        );
+        if !delete.predicate.tags.is_empty() {
+            let tags = delete
+                .predicate
+                .tags
+                .iter()
+                .map(|(tag, value)| format!("{tag} = '{value}'"))
+                .collect::<Vec<_>>()
+                .join(" and ");
+            let _ = write!(out, " where {tags}");
+        }
+        let _ = writeln!(out);
+    }
+    if let Some(snapshot) = &contents.snapshot {
+        let _ = writeln!(
+            out,
+            "  snapshot {}: persist data before {}, then remove wal files up to {}",
+            snapshot.snapshot_sequence_number,
+            format_time(snapshot.end_time_marker),
+            snapshot.last_wal_sequence_number
+        );
+    }
+
+    out
+}
+
+fn format_time(timestamp_ns: i64) -> String {
+    Time::from_timestamp_nanos(timestamp_ns).to_rfc3339()
+}
@@ -0,0 +1,53 @@
+use serde::Serialize;
+
+use super::{sequence_number, Format, Result, WalStoreConfig};
+
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(flatten)]
+    store: WalStoreConfig,
+
+    /// The format to output the list of files in
+    #[clap(value_enum, long = "format", default_value = "text")]
+    format: Format,
+}
+
+#[derive(Debug, Serialize)]
+struct WalFileListing {
+    path: String,
+    wal_file_number: Option<u64>,
+    size_bytes: usize,
+    last_modified: String,
+}
+
+pub(super) async fn command(config: Config) -> Result<()> {
+    let object_store = config.store.object_store()?;
+    let files = config.store.list_files(object_store.as_ref()).await?;
+
+    let listings: Vec<WalFileListing> = files
+        .iter()
+        .map(|meta| WalFileListing {
+            path: meta.location.to_string(),
+            wal_file_number: sequence_number(meta).map(|n| n.as_u64()),
+            size_bytes: meta.size,
+            last_modified: meta.last_modified.to_rfc3339(),
+        })
+        .collect();
+
+    match config.format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&listings)?),
+        Format::Text => {
+            for listing in &listings {
+                println!(
+                    "{path}\t{size_bytes} bytes\t{last_modified}",
+                    path = listing.path,
+                    size_bytes = listing.size_bytes,
+                    last_modified = listing.last_modified,
+                );
+            }
+            println!("{} wal files", listings.len());
+        }
+    }
+
+    Ok(())
+}
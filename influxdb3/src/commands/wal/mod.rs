@@ -0,0 +1,143 @@
+//! Commands for inspecting and verifying the WAL files that a server has written to object store.
+
+use std::sync::Arc;
+
+use clap::ValueEnum;
+use clap_blocks::object_store::{make_object_store, ObjectStoreConfig};
+use influxdb3_wal::{
+    object_store::list_wal_files, serialize::verify_file_type_and_deserialize, WalContents,
+    WalFileSequenceNumber,
+};
+use object_store::{ObjectMeta, ObjectStore};
+
+pub mod inspect;
+pub mod list;
+pub mod verify;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Cannot parse object store config: {0}")]
+    ObjectStoreParsing(#[from] clap_blocks::object_store::ParseError),
+
+    #[error("error listing wal files: {0}")]
+    Wal(#[from] influxdb3_wal::Error),
+
+    #[error("object store error: {0}")]
+    ObjectStore(#[from] object_store::Error),
+
+    #[error("invalid wal file: {0}")]
+    InvalidWalFile(#[from] influxdb3_wal::serialize::Error),
+
+    #[error("wal file contains sequence number {found} but its path has {expected}")]
+    SequenceNumberMismatch {
+        expected: WalFileSequenceNumber,
+        found: WalFileSequenceNumber,
+    },
+
+    #[error("error loading the catalog: {0}")]
+    Catalog(#[from] influxdb3_write::persister::Error),
+
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("{failed} of {total} wal files failed verification")]
+    VerificationFailed { failed: usize, total: usize },
+}
+
+pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, clap::Parser)]
+pub(crate) struct Config {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, clap::Parser)]
+enum Command {
+    /// List the WAL files written for a host
+    List(list::Config),
+    /// Check the identifier and checksum of WAL files, and that they can be deserialized
+    Verify(verify::Config),
+    /// Print a summary of the ops contained in WAL files
+    Inspect(inspect::Config),
+}
+
+pub(crate) async fn command(config: Config) -> Result<()> {
+    match config.command {
+        Command::List(config) => list::command(config).await,
+        Command::Verify(config) => verify::command(config).await,
+        Command::Inspect(config) => inspect::command(config).await,
+    }
+}
+
+/// Where to find the WAL files, and which of them to operate on
+#[derive(Debug, clap::Parser)]
+pub(crate) struct WalStoreConfig {
+    /// object store options
+    #[clap(flatten)]
+    object_store_config: ObjectStoreConfig,
+
+    /// The host identifier prefix that the server was started with, which WAL files are
+    /// written under
+    #[clap(long = "host-id", env = "INFLUXDB3_HOST_IDENTIFIER_PREFIX", action)]
+    host_identifier_prefix: String,
+
+    /// Only include WAL files with a sequence number greater than or equal to this
+    #[clap(long = "start", action)]
+    start: Option<u64>,
+
+    /// Only include WAL files with a sequence number less than or equal to this
+    #[clap(long = "end", action)]
+    end: Option<u64>,
+}
+
+impl WalStoreConfig {
+    fn object_store(&self) -> Result<Arc<dyn ObjectStore>> {
+        Ok(make_object_store(&self.object_store_config)?)
+    }
+
+    /// List the WAL files in the configured sequence number range, in order
+    async fn list_files(&self, object_store: &dyn ObjectStore) -> Result<Vec<ObjectMeta>> {
+        let files = list_wal_files(object_store, &self.host_identifier_prefix).await?;
+        Ok(files
+            .into_iter()
+            .filter(|meta| match sequence_number(meta) {
+                Some(n) => {
+                    self.start.map_or(true, |start| n.as_u64() >= start)
+                        && self.end.map_or(true, |end| n.as_u64() <= end)
+                }
+                // files that don't look like wal files are always included, so that they show
+                // up as invalid
+                None => true,
+            })
+            .collect())
+    }
+}
+
+/// The output format of the `wal` commands
+#[derive(Debug, ValueEnum, Clone, Copy)]
+#[clap(rename_all = "snake_case")]
+pub(crate) enum Format {
+    Text,
+    Json,
+}
+
+/// The sequence number of a WAL file, taken from its path
+fn sequence_number(meta: &ObjectMeta) -> Option<WalFileSequenceNumber> {
+    meta.location.filename()?.strip_suffix(".wal")?.parse().ok()
+}
+
+/// Fetch a WAL file, verify its identifier and checksum, and deserialize its contents
+async fn read_wal_file(object_store: &dyn ObjectStore, meta: &ObjectMeta) -> Result<WalContents> {
+    let bytes = object_store.get(&meta.location).await?.bytes().await?;
+    let contents = verify_file_type_and_deserialize(bytes)?;
+    if let Some(expected) = sequence_number(meta) {
+        if expected != contents.wal_file_number {
+            return Err(Error::SequenceNumberMismatch {
+                expected,
+                found: contents.wal_file_number,
+            });
+        }
+    }
+    Ok(contents)
+}
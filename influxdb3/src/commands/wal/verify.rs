@@ -0,0 +1,69 @@
+use serde::Serialize;
+
+use super::{read_wal_file, sequence_number, Error, Format, Result, WalStoreConfig};
+
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(flatten)]
+    store: WalStoreConfig,
+
+    /// The format to output the verification results in
+    #[clap(value_enum, long = "format", default_value = "text")]
+    format: Format,
+}
+
+#[derive(Debug, Serialize)]
+struct Verification {
+    path: String,
+    wal_file_number: Option<u64>,
+    size_bytes: usize,
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub(super) async fn command(config: Config) -> Result<()> {
+    let object_store = config.store.object_store()?;
+    let files = config.store.list_files(object_store.as_ref()).await?;
+
+    let mut verifications = Vec::with_capacity(files.len());
+    for meta in &files {
+        let error = match read_wal_file(object_store.as_ref(), meta).await {
+            Ok(_) => None,
+            Err(e) => Some(e.to_string()),
+        };
+        verifications.push(Verification {
+            path: meta.location.to_string(),
+            wal_file_number: sequence_number(meta).map(|n| n.as_u64()),
+            size_bytes: meta.size,
+            valid: error.is_none(),
+            error,
+        });
+    }
+
+    match config.format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&verifications)?),
+        Format::Text => {
+            for verification in &verifications {
+                match &verification.error {
+                    None => println!("{}\tok", verification.path),
+                    Some(e) => println!("{}\tinvalid: {e}", verification.path),
+                }
+            }
+        }
+    }
+
+    let failed = verifications.iter().filter(|v| !v.valid).count();
+    if failed > 0 {
+        return Err(Error::VerificationFailed {
+            failed,
+            total: verifications.len(),
+        });
+    }
+
+    if matches!(config.format, Format::Text) {
+        println!("{} wal files verified", verifications.len());
+    }
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use secrecy::ExposeSecret;
 use tokio::{
     fs::File,
@@ -26,11 +26,45 @@ pub struct Config {
     influxdb3_config: InfluxDb3Config,
 
     /// File path to load the write data from
-    ///
-    /// Currently, only files containing line protocol are supported.
     #[clap(short = 'f', long = "file")]
     file_path: String,
 
+    /// The format of the write data
+    ///
+    /// CSV can be annotated CSV, or plain CSV with a header row, whose column roles are given
+    /// with the `--measurement`, `--measurement-column`, `--tag`, `--field`, `--time-column`, and
+    /// `--time-format` options.
+    #[clap(long = "format", value_enum, default_value_t = Format::Lp)]
+    format: Format,
+
+    /// The measurement of every row of plain CSV
+    #[clap(long = "measurement")]
+    measurement: Option<String>,
+
+    /// The column with the measurement of each row of plain CSV
+    #[clap(long = "measurement-column")]
+    measurement_column: Option<String>,
+
+    /// The tag columns of plain CSV
+    #[clap(long = "tag", value_delimiter = ',')]
+    tags: Vec<String>,
+
+    /// The field columns of plain CSV, as `name:type` with a type of `float`, `integer`,
+    /// `uinteger`, `string`, or `boolean`
+    #[clap(long = "field", value_delimiter = ',', value_parser = parse_field)]
+    fields: Vec<(String, String)>,
+
+    /// The column with the time of each row of plain CSV
+    ///
+    /// Rows are written at the time of the write if no time column is given.
+    #[clap(long = "time-column")]
+    time_column: Option<String>,
+
+    /// The format of the times of plain CSV: `rfc3339`, `unix_seconds`, `unix_millis`,
+    /// `unix_micros`, or `unix_nanos`
+    #[clap(long = "time-format")]
+    time_format: Option<String>,
+
     /// Flag to request the server accept partial writes
     ///
     /// Invalid lines in the input data will be ignored by the server.
@@ -38,6 +72,22 @@ pub struct Config {
     accept_partial_writes: bool,
 }
 
+#[derive(Debug, ValueEnum, Clone, Copy)]
+#[clap(rename_all = "snake_case")]
+enum Format {
+    /// Line protocol
+    Lp,
+    /// Annotated or plain CSV
+    Csv,
+}
+
+fn parse_field(field: &str) -> std::result::Result<(String, String), String> {
+    field
+        .rsplit_once(':')
+        .map(|(name, field_type)| (name.to_string(), field_type.to_string()))
+        .ok_or_else(|| format!("field {field} is not given as name:type"))
+}
+
 pub(crate) async fn command(config: Config) -> Result<()> {
     let InfluxDb3Config {
         host_url,
@@ -53,11 +103,40 @@ pub(crate) async fn command(config: Config) -> Result<()> {
     let mut writes = Vec::new();
     f.read_to_end(&mut writes).await?;
 
-    let mut req = client.api_v3_write_lp(database_name);
-    if config.accept_partial_writes {
-        req = req.accept_partial(true);
+    match config.format {
+        Format::Lp => {
+            let mut req = client.api_v3_write_lp(database_name);
+            if config.accept_partial_writes {
+                req = req.accept_partial(true);
+            }
+            req.body(writes).send().await?;
+        }
+        Format::Csv => {
+            let mut req = client.api_v3_write_csv(database_name);
+            if config.accept_partial_writes {
+                req = req.accept_partial(true);
+            }
+            if let Some(measurement) = config.measurement {
+                req = req.measurement(measurement);
+            }
+            if let Some(column) = config.measurement_column {
+                req = req.measurement_column(column);
+            }
+            for column in config.tags {
+                req = req.tag(column);
+            }
+            for (column, field_type) in config.fields {
+                req = req.field(column, field_type);
+            }
+            if let Some(column) = config.time_column {
+                req = req.time_column(column);
+            }
+            if let Some(format) = config.time_format {
+                req = req.time_format(format);
+            }
+            req.body(writes).send().await?;
+        }
     }
-    req.body(writes).send().await?;
 
     println!("success");
 
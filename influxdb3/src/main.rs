@@ -25,11 +25,17 @@ use trogging::{
 };
 
 mod commands {
+    pub mod catalog;
     pub(crate) mod common;
+    pub mod database;
+    pub mod import;
     pub mod last_cache;
     pub mod query;
+    pub mod restore;
     pub mod serve;
+    pub mod table;
     pub mod token;
+    pub mod wal;
     pub mod write;
 }
 
@@ -91,6 +97,24 @@ enum Command {
 
     /// Manage last-n-value caches
     LastCache(commands::last_cache::Config),
+
+    /// Manage databases
+    Database(commands::database::Config),
+
+    /// Manage tables
+    Table(commands::table::Config),
+
+    /// Export the catalog of a server, or import it into another
+    Catalog(commands::catalog::Config),
+
+    /// Inspect and verify the WAL files written by a server
+    Wal(commands::wal::Config),
+
+    /// Restore the state of a host as of an earlier point in time into a new host
+    Restore(commands::restore::Config),
+
+    /// Import data into a server without writing it through the WAL
+    Import(commands::import::Config),
 }
 
 fn main() -> Result<(), std::io::Error> {
@@ -148,6 +172,42 @@ fn main() -> Result<(), std::io::Error> {
                     std::process::exit(ReturnCode::Failure as _)
                 }
             }
+            Some(Command::Database(config)) => {
+                if let Err(e) = commands::database::command(config).await {
+                    eprintln!("Database command failed: {e}");
+                    std::process::exit(ReturnCode::Failure as _)
+                }
+            }
+            Some(Command::Table(config)) => {
+                if let Err(e) = commands::table::command(config).await {
+                    eprintln!("Table command failed: {e}");
+                    std::process::exit(ReturnCode::Failure as _)
+                }
+            }
+            Some(Command::Catalog(config)) => {
+                if let Err(e) = commands::catalog::command(config).await {
+                    eprintln!("Catalog command failed: {e}");
+                    std::process::exit(ReturnCode::Failure as _)
+                }
+            }
+            Some(Command::Wal(config)) => {
+                if let Err(e) = commands::wal::command(config).await {
+                    eprintln!("WAL command failed: {e}");
+                    std::process::exit(ReturnCode::Failure as _)
+                }
+            }
+            Some(Command::Restore(config)) => {
+                if let Err(e) = commands::restore::command(config).await {
+                    eprintln!("Restore command failed: {e}");
+                    std::process::exit(ReturnCode::Failure as _)
+                }
+            }
+            Some(Command::Import(config)) => {
+                if let Err(e) = commands::import::command(config).await {
+                    eprintln!("Import command failed: {e}");
+                    std::process::exit(ReturnCode::Failure as _)
+                }
+            }
         }
     });
 
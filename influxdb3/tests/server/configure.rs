@@ -360,3 +360,897 @@ async fn api_v3_configure_last_cache_delete() {
         }
     }
 }
+
+#[tokio::test]
+async fn api_v3_configure_database_and_table_delete() {
+    let server = TestServer::spawn().await;
+
+    for db in ["foo", "bar"] {
+        server
+            .write_lp_to_db(
+                db,
+                "cpu,host=a usage=0.9 1\n\
+                mem,host=a used=10i 1",
+                influxdb3_client::Precision::Nanosecond,
+            )
+            .await
+            .expect("write to db");
+    }
+
+    struct TestCase {
+        request: serde_json::Value,
+        expected: StatusCode,
+    }
+
+    let table_test_cases = [
+        // Missing table name:
+        TestCase {
+            request: serde_json::json!({ "db": "foo" }),
+            expected: StatusCode::BAD_REQUEST,
+        },
+        // Table does not exist:
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "table": "disk" }),
+            expected: StatusCode::INTERNAL_SERVER_ERROR,
+        },
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "table": "mem" }),
+            expected: StatusCode::OK,
+        },
+        // Already deleted:
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "table": "mem" }),
+            expected: StatusCode::INTERNAL_SERVER_ERROR,
+        },
+    ];
+    for (i, t) in table_test_cases.iter().enumerate() {
+        let status = server
+            .api_v3_configure_table_delete(&t.request)
+            .await
+            .status();
+        assert_eq!(t.expected, status, "table test case ({i}) failed");
+    }
+
+    let database_test_cases = [
+        // Missing database name:
+        TestCase {
+            request: serde_json::json!({}),
+            expected: StatusCode::BAD_REQUEST,
+        },
+        TestCase {
+            request: serde_json::json!({ "db": "bar" }),
+            expected: StatusCode::OK,
+        },
+        // Already deleted:
+        TestCase {
+            request: serde_json::json!({ "db": "bar" }),
+            expected: StatusCode::INTERNAL_SERVER_ERROR,
+        },
+    ];
+    for (i, t) in database_test_cases.iter().enumerate() {
+        let status = server
+            .api_v3_configure_database_delete(&t.request)
+            .await
+            .status();
+        assert_eq!(t.expected, status, "database test case ({i}) failed");
+    }
+
+    // only the tables that were not deleted can be queried:
+    let resp = server
+        .api_v3_query_sql(&[
+            ("db", "foo"),
+            (
+                "q",
+                "SELECT table_name FROM information_schema.tables \
+                WHERE table_schema = 'iox' ORDER BY table_name",
+            ),
+            ("format", "pretty"),
+        ])
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(
+        "+------------+\n\
+        | table_name |\n\
+        +------------+\n\
+        | cpu        |\n\
+        +------------+",
+        resp
+    );
+    let resp = server
+        .api_v3_query_sql(&[("db", "bar"), ("q", "SELECT * FROM cpu")])
+        .await;
+    assert!(!resp.status().is_success());
+
+    // a deleted database can be written to again, starting out empty:
+    server
+        .write_lp_to_db(
+            "bar",
+            "cpu,host=b usage=0.5 2",
+            influxdb3_client::Precision::Nanosecond,
+        )
+        .await
+        .expect("write to db");
+    let resp = server
+        .api_v3_query_sql(&[
+            ("db", "bar"),
+            ("q", "SELECT host, usage FROM cpu"),
+            ("format", "pretty"),
+        ])
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(
+        "+------+-------+\n\
+        | host | usage |\n\
+        +------+-------+\n\
+        | b    | 0.5   |\n\
+        +------+-------+",
+        resp
+    );
+}
+
+#[tokio::test]
+async fn api_v3_configure_database_and_table_create() {
+    let server = TestServer::spawn().await;
+
+    struct TestCase {
+        request: serde_json::Value,
+        expected: StatusCode,
+    }
+
+    let database_test_cases = [
+        // Invalid database name:
+        TestCase {
+            request: serde_json::json!({ "db": "_foo" }),
+            expected: StatusCode::BAD_REQUEST,
+        },
+        TestCase {
+            request: serde_json::json!({ "db": "foo" }),
+            expected: StatusCode::OK,
+        },
+        // Already exists:
+        TestCase {
+            request: serde_json::json!({ "db": "foo" }),
+            expected: StatusCode::CONFLICT,
+        },
+    ];
+    for (i, t) in database_test_cases.iter().enumerate() {
+        let status = server
+            .api_v3_configure_database_create(&t.request)
+            .await
+            .status();
+        assert_eq!(t.expected, status, "database test case ({i}) failed");
+    }
+
+    let table_test_cases = [
+        // Missing fields:
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "table": "cpu" }),
+            expected: StatusCode::BAD_REQUEST,
+        },
+        // Invalid field type:
+        TestCase {
+            request: serde_json::json!({
+                "db": "foo",
+                "table": "cpu",
+                "fields": [{ "name": "usage", "type": "decimal" }],
+            }),
+            expected: StatusCode::BAD_REQUEST,
+        },
+        // Column defined twice:
+        TestCase {
+            request: serde_json::json!({
+                "db": "foo",
+                "table": "cpu",
+                "tags": ["host"],
+                "fields": [{ "name": "host", "type": "string" }],
+            }),
+            expected: StatusCode::BAD_REQUEST,
+        },
+        TestCase {
+            request: serde_json::json!({
+                "db": "foo",
+                "table": "cpu",
+                "tags": ["region", "host"],
+                "fields": [{ "name": "usage", "type": "float" }],
+            }),
+            expected: StatusCode::OK,
+        },
+        // Already exists:
+        TestCase {
+            request: serde_json::json!({
+                "db": "foo",
+                "table": "cpu",
+                "fields": [{ "name": "usage", "type": "float" }],
+            }),
+            expected: StatusCode::CONFLICT,
+        },
+        // The database is created along with the table:
+        TestCase {
+            request: serde_json::json!({
+                "db": "bar",
+                "table": "mem",
+                "fields": [{ "name": "used", "type": "integer" }],
+            }),
+            expected: StatusCode::OK,
+        },
+    ];
+    for (i, t) in table_test_cases.iter().enumerate() {
+        let status = server
+            .api_v3_configure_table_create(&t.request)
+            .await
+            .status();
+        assert_eq!(t.expected, status, "table test case ({i}) failed");
+    }
+
+    // the declared columns are in the schema before anything is written:
+    let resp = server
+        .api_v3_query_sql(&[
+            ("db", "foo"),
+            (
+                "q",
+                "SELECT column_name, data_type FROM information_schema.columns \
+                WHERE table_name = 'cpu' ORDER BY column_name",
+            ),
+            ("format", "json"),
+        ])
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::json!([
+            { "column_name": "host", "data_type": "Dictionary(Int32, Utf8)" },
+            { "column_name": "region", "data_type": "Dictionary(Int32, Utf8)" },
+            { "column_name": "time", "data_type": "Timestamp(Nanosecond, None)" },
+            { "column_name": "usage", "data_type": "Float64" },
+        ]),
+        resp
+    );
+
+    // writes that do not match the declared field types are rejected:
+    let err = server
+        .write_lp_to_db(
+            "bar",
+            "mem used=1.5 1",
+            influxdb3_client::Precision::Nanosecond,
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, influxdb3_client::Error::ApiError { code, .. } if code.as_u16() == 400),
+        "unexpected error: {err}"
+    );
+    server
+        .write_lp_to_db(
+            "bar",
+            "mem used=10i 1",
+            influxdb3_client::Precision::Nanosecond,
+        )
+        .await
+        .expect("write to db");
+}
+
+#[tokio::test]
+async fn api_v3_configure_schema_policy() {
+    let server = TestServer::spawn().await;
+
+    server
+        .write_lp_to_db(
+            "foo",
+            "cpu,host=a usage=0.5 1",
+            influxdb3_client::Precision::Nanosecond,
+        )
+        .await
+        .expect("write to db");
+
+    struct TestCase {
+        request: serde_json::Value,
+        expected: StatusCode,
+    }
+
+    let test_cases = [
+        // Invalid policy:
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "policy": "strict" }),
+            expected: StatusCode::BAD_REQUEST,
+        },
+        // Table does not exist:
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "table": "mem", "policy": "locked" }),
+            expected: StatusCode::INTERNAL_SERVER_ERROR,
+        },
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "policy": "locked" }),
+            expected: StatusCode::OK,
+        },
+    ];
+    for (i, t) in test_cases.iter().enumerate() {
+        let status = server
+            .api_v3_configure_schema_policy(&t.request)
+            .await
+            .status();
+        assert_eq!(t.expected, status, "test case ({i}) failed");
+    }
+
+    // writes that add columns, or tables, to the locked database are rejected:
+    for lp in ["cpu,host=a usage=0.5,temp=20 2", "mem,host=a used=1i 2"] {
+        let err = server
+            .write_lp_to_db("foo", lp, influxdb3_client::Precision::Nanosecond)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, influxdb3_client::Error::ApiError { code, .. } if code.as_u16() == 400),
+            "unexpected error: {err}"
+        );
+    }
+
+    // unknown fields written to a table that drops them are not stored:
+    let resp = server
+        .api_v3_configure_schema_policy(&serde_json::json!({
+            "db": "foo",
+            "table": "cpu",
+            "policy": "drop_unknown",
+        }))
+        .await;
+    assert_eq!(StatusCode::OK, resp.status());
+    server
+        .write_lp_to_db(
+            "foo",
+            "cpu,host=a usage=0.7,temp=20 2",
+            influxdb3_client::Precision::Nanosecond,
+        )
+        .await
+        .expect("write to db");
+    let resp = server
+        .api_v3_query_sql(&[
+            ("db", "foo"),
+            ("q", "SELECT * FROM cpu ORDER BY time"),
+            ("format", "json"),
+        ])
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::json!([
+            { "host": "a", "time": "1970-01-01T00:00:00.000000001", "usage": 0.5 },
+            { "host": "a", "time": "1970-01-01T00:00:00.000000002", "usage": 0.7 },
+        ]),
+        resp
+    );
+}
+
+#[tokio::test]
+async fn api_v3_configure_coercion_policy() {
+    let server = TestServer::spawn().await;
+    server
+        .write_lp_to_db(
+            "foo",
+            "cpu,host=a usage=0.5,status=\"ok\" 1",
+            influxdb3_client::Precision::Nanosecond,
+        )
+        .await
+        .expect("write to db");
+    let client = reqwest::Client::new();
+    let write_lp = |lp: &'static str| {
+        client
+            .post(format!(
+                "{base}/api/v3/write_lp",
+                base = server.client_addr()
+            ))
+            .query(&[("db", "foo"), ("precision", "nanosecond")])
+            .body(lp)
+            .send()
+    };
+
+    // values that do not match the type of their column are rejected by default:
+    let resp = write_lp("cpu,host=a usage=1i,status=true 2").await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, resp.status());
+
+    struct TestCase {
+        request: serde_json::Value,
+        expected: StatusCode,
+    }
+    let test_cases = [
+        // Table does not exist:
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "table": "mem", "integer_to_float": true }),
+            expected: StatusCode::INTERNAL_SERVER_ERROR,
+        },
+        // Invalid policy:
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "table": "cpu", "integer_to_float": 1 }),
+            expected: StatusCode::BAD_REQUEST,
+        },
+        TestCase {
+            request: serde_json::json!({
+                "db": "foo",
+                "table": "cpu",
+                "integer_to_float": true,
+                "any_to_string": true,
+            }),
+            expected: StatusCode::OK,
+        },
+    ];
+    for (i, t) in test_cases.iter().enumerate() {
+        let status = server
+            .api_v3_configure_coercion_policy(&t.request)
+            .await
+            .status();
+        assert_eq!(t.expected, status, "test case ({i}) failed");
+    }
+
+    // the values are converted, and counted in the response:
+    let resp = write_lp("cpu,host=a usage=1i,status=true 2").await.unwrap();
+    assert_eq!(StatusCode::OK, resp.status());
+    assert_eq!(
+        "2",
+        resp.headers()
+            .get("x-influxdb3-coerced-values")
+            .unwrap()
+            .to_str()
+            .unwrap()
+    );
+    let resp = server
+        .api_v3_query_sql(&[
+            ("db", "foo"),
+            ("q", "SELECT usage, status FROM cpu ORDER BY time"),
+            ("format", "json"),
+        ])
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::json!([
+            { "usage": 0.5, "status": "ok" },
+            { "usage": 1.0, "status": "true" },
+        ]),
+        resp
+    );
+}
+
+#[tokio::test]
+async fn api_v3_configure_retention_period() {
+    let server = TestServer::spawn().await;
+
+    let resp = server
+        .api_v3_configure_database_create(&serde_json::json!({
+            "db": "foo",
+            "retention_period": 3600,
+        }))
+        .await;
+    assert_eq!(StatusCode::OK, resp.status());
+
+    // a row from long ago, and one from now:
+    let now_ns = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    server
+        .write_lp_to_db(
+            "foo",
+            &format!("cpu,host=a usage=0.5 1\ncpu,host=a usage=0.7 {now_ns}"),
+            influxdb3_client::Precision::Nanosecond,
+        )
+        .await
+        .expect("write to db");
+    let server = &server;
+    let query_usage = move || async move {
+        server
+            .api_v3_query_sql(&[
+                ("db", "foo"),
+                ("q", "SELECT usage FROM cpu ORDER BY time"),
+                ("format", "json"),
+            ])
+            .await
+            .json::<serde_json::Value>()
+            .await
+            .unwrap()
+    };
+    let show_retention_policies = move || async move {
+        server
+            .api_v3_query_influxql(&[
+                ("db", "foo"),
+                ("q", "SHOW RETENTION POLICIES"),
+                ("format", "json"),
+            ])
+            .await
+            .json::<serde_json::Value>()
+            .await
+            .unwrap()
+    };
+
+    // only the row within the retention period is queried:
+    assert_eq!(serde_json::json!([{ "usage": 0.7 }]), query_usage().await);
+    assert_eq!(
+        serde_json::json!([{
+            "iox::database": "foo",
+            "name": "autogen",
+            "duration": 3_600_000_000_000_i64,
+        }]),
+        show_retention_policies().await
+    );
+
+    struct TestCase {
+        request: serde_json::Value,
+        expected: StatusCode,
+    }
+    let test_cases = [
+        // Database does not exist:
+        TestCase {
+            request: serde_json::json!({ "db": "bar", "retention_period": 60 }),
+            expected: StatusCode::INTERNAL_SERVER_ERROR,
+        },
+        // Invalid period:
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "retention_period": "1h" }),
+            expected: StatusCode::BAD_REQUEST,
+        },
+        // Remove the retention period:
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "retention_period": null }),
+            expected: StatusCode::OK,
+        },
+    ];
+    for (i, t) in test_cases.iter().enumerate() {
+        let status = server
+            .api_v3_configure_retention_period(&t.request)
+            .await
+            .status();
+        assert_eq!(t.expected, status, "test case ({i}) failed");
+    }
+
+    // all of the rows are queried once data is kept forever:
+    assert_eq!(
+        serde_json::json!([{ "usage": 0.5 }, { "usage": 0.7 }]),
+        query_usage().await
+    );
+    assert_eq!(
+        serde_json::json!([{ "iox::database": "foo", "name": "autogen" }]),
+        show_retention_policies().await
+    );
+}
+
+#[tokio::test]
+async fn api_v3_configure_quotas() {
+    let server = TestServer::spawn().await;
+    server
+        .write_lp_to_db(
+            "foo",
+            "cpu,host=a usage=0.5 1",
+            influxdb3_client::Precision::Nanosecond,
+        )
+        .await
+        .expect("write to db");
+    let client = reqwest::Client::new();
+    let write_lp = |lp: &'static str| {
+        client
+            .post(format!(
+                "{base}/api/v3/write_lp",
+                base = server.client_addr()
+            ))
+            .query(&[("db", "foo"), ("precision", "nanosecond")])
+            .body(lp)
+            .send()
+    };
+
+    struct TestCase {
+        request: serde_json::Value,
+        expected: StatusCode,
+    }
+    let test_cases = [
+        // Database does not exist:
+        TestCase {
+            request: serde_json::json!({ "db": "bar", "max_tables": 1 }),
+            expected: StatusCode::INTERNAL_SERVER_ERROR,
+        },
+        // Invalid quota:
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "max_tables": -1 }),
+            expected: StatusCode::BAD_REQUEST,
+        },
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "max_tables": 1, "max_series": 2 }),
+            expected: StatusCode::OK,
+        },
+    ];
+    for (i, t) in test_cases.iter().enumerate() {
+        let status = server.api_v3_configure_quotas(&t.request).await.status();
+        assert_eq!(t.expected, status, "test case ({i}) failed");
+    }
+
+    // writes that would exceed a quota are rejected with an error that names it:
+    let resp = write_lp("mem,host=a used=1i 2").await.unwrap();
+    assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, resp.status());
+    let body = resp.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(
+        serde_json::json!({ "quota": "max_tables", "limit": 1 }),
+        body["data"]
+    );
+    let resp = write_lp("cpu,host=b usage=0.6 2").await.unwrap();
+    assert_eq!(StatusCode::OK, resp.status());
+    let resp = write_lp("cpu,host=c usage=0.7 3").await.unwrap();
+    assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, resp.status());
+    let body = resp.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(
+        serde_json::json!({ "quota": "max_series", "limit": 2 }),
+        body["data"]
+    );
+
+    // the usage of each quota is in a system table:
+    let resp = server
+        .api_v3_query_sql(&[
+            ("db", "foo"),
+            (
+                "q",
+                "SELECT quota, \"limit\", usage FROM system.quotas \
+                WHERE quota IN ('max_tables', 'max_columns', 'max_series') ORDER BY quota",
+            ),
+            ("format", "json"),
+        ])
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::json!([
+            { "quota": "max_columns", "usage": 3 },
+            { "quota": "max_series", "limit": 2, "usage": 2 },
+            { "quota": "max_tables", "limit": 1, "usage": 1 },
+        ]),
+        resp
+    );
+}
+
+#[tokio::test]
+async fn api_v3_configure_catalog_export_and_import() {
+    let source = TestServer::spawn().await;
+    source
+        .write_lp_to_db(
+            "foo",
+            "cpu,host=a usage=0.5 1",
+            influxdb3_client::Precision::Nanosecond,
+        )
+        .await
+        .expect("write to db");
+    let resp = source
+        .api_v3_configure_last_cache_create(&serde_json::json!({
+            "db": "foo",
+            "table": "cpu",
+            "name": "cpu_cache",
+            "key_columns": ["host"],
+        }))
+        .await;
+    assert_eq!(StatusCode::CREATED, resp.status());
+
+    let resp = source.api_v3_configure_catalog_export().await;
+    assert_eq!(StatusCode::OK, resp.status());
+    let export = resp.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(
+        serde_json::json!({
+            "databases": [{
+                "name": "foo",
+                "tables": [{
+                    "name": "cpu",
+                    "columns": [
+                        { "name": "host", "type": "tag" },
+                        { "name": "time", "type": "time" },
+                        { "name": "usage", "type": "float" },
+                    ],
+                    "last_caches": [{
+                        "name": "cpu_cache",
+                        "key_columns": ["host"],
+                        "value_columns": { "type": "all_non_key_columns" },
+                        "count": 1,
+                        "ttl": 14400,
+                    }],
+                }],
+            }],
+        }),
+        export
+    );
+
+    // a dry run describes the changes without making them:
+    let target = TestServer::spawn().await;
+    let resp = target.api_v3_configure_catalog_import(&export, true).await;
+    assert_eq!(StatusCode::OK, resp.status());
+    let diff = resp.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(
+        serde_json::json!({
+            "changes": [
+                {
+                    "database": "foo",
+                    "op": "create_database",
+                    "description": "create database foo",
+                },
+                {
+                    "database": "foo",
+                    "table": "cpu",
+                    "op": "create_table",
+                    "description": "create table cpu with 3 columns",
+                },
+                {
+                    "database": "foo",
+                    "table": "cpu",
+                    "op": "create_last_cache",
+                    "description": "create last cache cpu_cache on table cpu",
+                },
+            ],
+            "conflicts": [],
+            "applied": false,
+        }),
+        diff
+    );
+    let empty = target
+        .api_v3_configure_catalog_export()
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(serde_json::json!({ "databases": [] }), empty);
+
+    // the import makes the catalogs match:
+    let resp = target.api_v3_configure_catalog_import(&export, false).await;
+    assert_eq!(StatusCode::OK, resp.status());
+    let diff = resp.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(serde_json::json!(true), diff["applied"]);
+    let imported = target
+        .api_v3_configure_catalog_export()
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(export, imported);
+
+    // a column of a different type is a conflict, and nothing is imported:
+    let conflicting = TestServer::spawn().await;
+    conflicting
+        .write_lp_to_db(
+            "foo",
+            "cpu,host=a usage=1i 1",
+            influxdb3_client::Precision::Nanosecond,
+        )
+        .await
+        .expect("write to db");
+    let resp = conflicting
+        .api_v3_configure_catalog_import(&export, false)
+        .await;
+    assert_eq!(StatusCode::CONFLICT, resp.status());
+    let diff = resp.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(serde_json::json!(false), diff["applied"]);
+    assert_eq!(serde_json::json!("foo"), diff["conflicts"][0]["database"]);
+    assert_eq!(serde_json::json!("cpu"), diff["conflicts"][0]["table"]);
+    assert!(diff["conflicts"][0]["description"]
+        .as_str()
+        .unwrap()
+        .starts_with("Field type mismatch on table cpu column usage"));
+}
+
+#[tokio::test]
+async fn api_v3_configure_table_and_column_rename() {
+    let server = TestServer::spawn().await;
+
+    server
+        .write_lp_to_db(
+            "foo",
+            "cpu,host=a usage=0.5 1\nmem,host=a used=10i 1",
+            influxdb3_client::Precision::Nanosecond,
+        )
+        .await
+        .expect("write to db");
+
+    struct TestCase {
+        request: serde_json::Value,
+        expected: StatusCode,
+    }
+
+    let table_test_cases = [
+        // Missing new name:
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "table": "cpu" }),
+            expected: StatusCode::BAD_REQUEST,
+        },
+        // Table does not exist:
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "table": "disk", "new_name": "disks" }),
+            expected: StatusCode::INTERNAL_SERVER_ERROR,
+        },
+        // Name is taken by another table:
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "table": "cpu", "new_name": "mem" }),
+            expected: StatusCode::CONFLICT,
+        },
+        TestCase {
+            request: serde_json::json!({ "db": "foo", "table": "cpu", "new_name": "cpu_metrics" }),
+            expected: StatusCode::OK,
+        },
+    ];
+    for (i, t) in table_test_cases.iter().enumerate() {
+        let status = server
+            .api_v3_configure_table_rename(&t.request)
+            .await
+            .status();
+        assert_eq!(t.expected, status, "table test case ({i}) failed");
+    }
+
+    let column_test_cases = [
+        // Column does not exist:
+        TestCase {
+            request: serde_json::json!({
+                "db": "foo",
+                "table": "cpu_metrics",
+                "column": "temp",
+                "new_name": "temperature",
+            }),
+            expected: StatusCode::BAD_REQUEST,
+        },
+        // The time column cannot be renamed:
+        TestCase {
+            request: serde_json::json!({
+                "db": "foo",
+                "table": "cpu_metrics",
+                "column": "time",
+                "new_name": "timestamp",
+            }),
+            expected: StatusCode::BAD_REQUEST,
+        },
+        // Name is taken by another column:
+        TestCase {
+            request: serde_json::json!({
+                "db": "foo",
+                "table": "cpu_metrics",
+                "column": "usage",
+                "new_name": "host",
+            }),
+            expected: StatusCode::CONFLICT,
+        },
+        TestCase {
+            request: serde_json::json!({
+                "db": "foo",
+                "table": "cpu_metrics",
+                "column": "usage",
+                "new_name": "cpu_usage",
+            }),
+            expected: StatusCode::OK,
+        },
+    ];
+    for (i, t) in column_test_cases.iter().enumerate() {
+        let status = server
+            .api_v3_configure_column_rename(&t.request)
+            .await
+            .status();
+        assert_eq!(t.expected, status, "column test case ({i}) failed");
+    }
+
+    // data written before and after the renames is queried under the new names:
+    server
+        .write_lp_to_db(
+            "foo",
+            "cpu_metrics,host=b cpu_usage=0.7 2",
+            influxdb3_client::Precision::Nanosecond,
+        )
+        .await
+        .expect("write to db");
+    let resp = server
+        .api_v3_query_sql(&[
+            ("db", "foo"),
+            ("q", "SELECT * FROM cpu_metrics ORDER BY time"),
+            ("format", "json"),
+        ])
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::json!([
+            { "cpu_usage": 0.5, "host": "a", "time": "1970-01-01T00:00:00.000000001" },
+            { "cpu_usage": 0.7, "host": "b", "time": "1970-01-01T00:00:00.000000002" },
+        ]),
+        resp
+    );
+}
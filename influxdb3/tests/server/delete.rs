@@ -0,0 +1,93 @@
+use hyper::StatusCode;
+use influxdb3_client::Precision;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+use crate::TestServer;
+
+#[tokio::test]
+async fn api_v3_delete() {
+    let server = TestServer::spawn().await;
+
+    server
+        .write_lp_to_db(
+            "foo",
+            "cpu,host=a,region=us-east usage=0.9 1\n\
+            cpu,host=b,region=us-east usage=0.5 1\n\
+            cpu,host=a,region=us-east usage=0.8 2\n\
+            cpu,host=b,region=us-east usage=0.6 2\n\
+            cpu,host=a,region=us-east usage=0.7 3",
+            Precision::Nanosecond,
+        )
+        .await
+        .unwrap();
+
+    struct TestCase {
+        request: serde_json::Value,
+        expected_status: StatusCode,
+    }
+
+    let test_cases = [
+        // Database does not exist:
+        TestCase {
+            request: json!({ "db": "bar", "table": "cpu" }),
+            expected_status: StatusCode::INTERNAL_SERVER_ERROR,
+        },
+        // Missing table name:
+        TestCase {
+            request: json!({ "db": "foo" }),
+            expected_status: StatusCode::BAD_REQUEST,
+        },
+        // Predicate on a column that is not a tag:
+        TestCase {
+            request: json!({ "db": "foo", "table": "cpu", "tags": { "usage": "0.9" } }),
+            expected_status: StatusCode::BAD_REQUEST,
+        },
+        // Start time after end time:
+        TestCase {
+            request: json!({ "db": "foo", "table": "cpu", "min_time": 3, "max_time": 2 }),
+            expected_status: StatusCode::BAD_REQUEST,
+        },
+        // Delete the rows for host a before time 3:
+        TestCase {
+            request: json!({
+                "db": "foo",
+                "table": "cpu",
+                "max_time": 2,
+                "tags": { "host": "a" }
+            }),
+            expected_status: StatusCode::OK,
+        },
+    ];
+
+    for t in test_cases {
+        let resp = server.api_v3_delete(&t.request).await;
+        assert_eq!(
+            t.expected_status,
+            resp.status(),
+            "unexpected status for request: {}",
+            t.request
+        );
+    }
+
+    let resp = server
+        .api_v3_query_sql(&[
+            ("db", "foo"),
+            ("q", "SELECT host, time, usage FROM cpu ORDER BY time, host"),
+            ("format", "pretty"),
+        ])
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(
+        "+------+-------------------------------+-------+\n\
+        | host | time                          | usage |\n\
+        +------+-------------------------------+-------+\n\
+        | b    | 1970-01-01T00:00:00.000000001 | 0.5   |\n\
+        | b    | 1970-01-01T00:00:00.000000002 | 0.6   |\n\
+        | a    | 1970-01-01T00:00:00.000000003 | 0.7   |\n\
+        +------+-------------------------------+-------+",
+        resp
+    );
+}
@@ -1,6 +1,14 @@
+use std::sync::Arc;
+
+use arrow::array::{Float64Array, StringArray, TimestampNanosecondArray};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::error::FlightError;
 use arrow_flight::sql::SqlInfo;
-use arrow_flight::Ticket;
+use arrow_flight::{FlightClient, FlightDescriptor, PutResult, Ticket};
 use arrow_util::assert_batches_sorted_eq;
+use futures::TryStreamExt;
 use influxdb3_client::Precision;
 use test_helpers::assert_contains;
 
@@ -108,19 +116,23 @@ async fn flight() -> Result<(), influxdb3_client::Error> {
 
         assert_batches_sorted_eq!(
             [
-                "+--------------+--------------------+---------------+------------+",
-                "| catalog_name | db_schema_name     | table_name    | table_type |",
-                "+--------------+--------------------+---------------+------------+",
-                "| public       | information_schema | columns       | VIEW       |",
-                "| public       | information_schema | df_settings   | VIEW       |",
-                "| public       | information_schema | schemata      | VIEW       |",
-                "| public       | information_schema | tables        | VIEW       |",
-                "| public       | information_schema | views         | VIEW       |",
-                "| public       | iox                | cpu           | BASE TABLE |",
-                "| public       | system             | last_caches   | BASE TABLE |",
-                "| public       | system             | parquet_files | BASE TABLE |",
-                "| public       | system             | queries       | BASE TABLE |",
-                "+--------------+--------------------+---------------+------------+",
+                "+--------------+--------------------+-----------------------+------------+",
+                "| catalog_name | db_schema_name     | table_name            | table_type |",
+                "+--------------+--------------------+-----------------------+------------+",
+                "| public       | information_schema | columns               | VIEW       |",
+                "| public       | information_schema | df_settings           | VIEW       |",
+                "| public       | information_schema | schemata              | VIEW       |",
+                "| public       | information_schema | tables                | VIEW       |",
+                "| public       | information_schema | views                 | VIEW       |",
+                "| public       | iox                | cpu                   | BASE TABLE |",
+                "| public       | system             | last_caches           | BASE TABLE |",
+                "| public       | system             | parquet_files         | BASE TABLE |",
+                "| public       | system             | quarantined_wal_files | BASE TABLE |",
+                "| public       | system             | queries               | BASE TABLE |",
+                "| public       | system             | quotas                | BASE TABLE |",
+                "| public       | system             | rejected_writes       | BASE TABLE |",
+                "| public       | system             | schema_history        | BASE TABLE |",
+                "+--------------+--------------------+-----------------------+------------+",
             ],
             &batches
         );
@@ -219,3 +231,90 @@ async fn flight_influxql() {
         );
     }
 }
+
+#[tokio::test]
+async fn flight_do_put() {
+    let server = TestServer::spawn().await;
+    let mut client = server.flight_client().await;
+
+    let column = |name: &str, data_type: DataType, column_type: &str| {
+        Field::new(name, data_type, true)
+            .with_metadata([("iox::column::type".to_string(), column_type.to_string())].into())
+    };
+    let schema = Arc::new(Schema::new(vec![
+        column("host", DataType::Utf8, "iox::column_type::tag"),
+        column("usage", DataType::Float64, "iox::column_type::field::float"),
+        Field::new(
+            "time",
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            false,
+        ),
+    ]));
+    let batch = RecordBatch::try_new(
+        Arc::clone(&schema),
+        vec![
+            Arc::new(StringArray::from(vec!["a", "b"])),
+            Arc::new(Float64Array::from(vec![0.5, 0.7])),
+            Arc::new(TimestampNanosecondArray::from(vec![1, 2])),
+        ],
+    )
+    .unwrap();
+    let results = do_put(&mut client, batch.clone(), &["foo", "cpu"])
+        .await
+        .unwrap();
+    assert_eq!(1, results.len());
+
+    let resp = server
+        .api_v3_query_sql(&[
+            ("db", "foo"),
+            ("q", "SELECT host, usage FROM cpu ORDER BY host"),
+            ("format", "json"),
+        ])
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::json!([
+            { "host": "a", "usage": 0.5 },
+            { "host": "b", "usage": 0.7 },
+        ]),
+        resp
+    );
+
+    // a descriptor that does not name the database and table:
+    let error = do_put(&mut client, batch, &["foo"]).await.unwrap_err();
+    assert!(
+        matches!(&error, FlightError::Tonic(s) if s.code() == tonic::Code::InvalidArgument),
+        "unexpected error: {error}"
+    );
+
+    // a column that is not marked as a tag or field:
+    let batch = RecordBatch::try_new(
+        Arc::new(Schema::new(vec![Field::new(
+            "usage",
+            DataType::Float64,
+            true,
+        )])),
+        vec![Arc::new(Float64Array::from(vec![0.9]))],
+    )
+    .unwrap();
+    let error = do_put(&mut client, batch, &["foo", "cpu"])
+        .await
+        .unwrap_err();
+    assert_contains!(error.to_string(), "is not marked as a tag or field");
+}
+
+/// Put the batch to the path of the flight descriptor, collecting the results
+async fn do_put(
+    client: &mut FlightClient,
+    batch: RecordBatch,
+    path: &[&str],
+) -> Result<Vec<PutResult>, FlightError> {
+    let flight_data = FlightDataEncoderBuilder::new()
+        .with_flight_descriptor(Some(FlightDescriptor::new_path(
+            path.iter().map(|p| p.to_string()).collect(),
+        )))
+        .build(futures::stream::iter([Ok(batch)]));
+    client.do_put(flight_data).await?.try_collect().await
+}
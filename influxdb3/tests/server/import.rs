@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Float64Array, StringArray, TimestampNanosecondArray};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use influxdb3_client::Precision;
+use parquet::arrow::ArrowWriter;
+use pretty_assertions::assert_eq;
+use reqwest::StatusCode;
+use test_helpers::assert_contains;
+
+use crate::TestServer;
+
+/// A parquet file with a single record batch of the given columns
+fn parquet_file(fields: Vec<Field>, columns: Vec<ArrayRef>) -> Vec<u8> {
+    let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap();
+    let mut file = vec![];
+    let mut writer = ArrowWriter::try_new(&mut file, batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+    file
+}
+
+#[test_log::test(tokio::test)]
+async fn api_v3_import_parquet() {
+    let server = TestServer::spawn().await;
+    server
+        .write_lp_to_db("foo", "cpu,host=c usage=0.9 120", Precision::Second)
+        .await
+        .unwrap();
+    let client = influxdb3_client::Client::new(server.client_addr()).unwrap();
+
+    let host = Field::new("host", DataType::Utf8, true);
+    let usage = Field::new("usage", DataType::Float64, true).with_metadata(
+        [(
+            "iox::column::type".to_string(),
+            "iox::column_type::field::float".to_string(),
+        )]
+        .into(),
+    );
+    let time = Field::new(
+        "time",
+        DataType::Timestamp(TimeUnit::Nanosecond, None),
+        false,
+    );
+    // the first two rows are duplicates, and the last is in a later chunk of time
+    let file = parquet_file(
+        vec![host.clone(), usage.clone(), time],
+        vec![
+            Arc::new(StringArray::from(vec!["a", "a", "b"])),
+            Arc::new(Float64Array::from(vec![0.5, 0.5, 0.7])),
+            Arc::new(TimestampNanosecondArray::from(vec![
+                1_000_000_000,
+                1_000_000_000,
+                3_601_000_000_000,
+            ])),
+        ],
+    );
+    let summary = client
+        .api_v3_import_parquet("foo", "cpu", file)
+        .await
+        .unwrap();
+    assert_eq!(summary.row_count, 3);
+    assert_eq!(summary.persisted_row_count, 2);
+    assert_eq!(summary.persisted_file_count, 2);
+    assert_eq!(summary.min_time, 1_000_000_000);
+    assert_eq!(summary.max_time, 3_601_000_000_000);
+
+    let resp = server
+        .api_v3_query_sql(&[
+            ("db", "foo"),
+            ("q", "SELECT host, usage FROM cpu ORDER BY host"),
+            ("format", "json"),
+        ])
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::json!([
+            {"host": "a", "usage": 0.5},
+            {"host": "b", "usage": 0.7},
+            {"host": "c", "usage": 0.9},
+        ]),
+        resp
+    );
+
+    // a file without a time column is rejected
+    let file = parquet_file(
+        vec![host, usage],
+        vec![
+            Arc::new(StringArray::from(vec!["d"])),
+            Arc::new(Float64Array::from(vec![0.1])),
+        ],
+    );
+    match client.api_v3_import_parquet("foo", "cpu", file).await {
+        Err(influxdb3_client::Error::ApiError { code, message }) => {
+            assert_eq!(code, StatusCode::BAD_REQUEST);
+            assert_contains!(message, "the parquet file has no time column");
+        }
+        other => panic!("expected the import to be rejected, got {other:?}"),
+    }
+}
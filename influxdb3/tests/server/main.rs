@@ -16,11 +16,17 @@ use reqwest::Response;
 mod auth;
 mod client;
 mod configure;
+mod delete;
 mod flight;
+mod import;
 mod limits;
 mod ping;
 mod query;
+mod replica;
+mod restore;
+mod subscribe;
 mod system_tables;
+mod wal;
 mod write;
 
 trait ConfigProvider {
@@ -47,6 +53,8 @@ trait ConfigProvider {
 pub struct TestConfig {
     auth_token: Option<(String, String)>,
     host_id: Option<String>,
+    object_store_dir: Option<String>,
+    read_replica_of: Option<String>,
 }
 
 impl TestConfig {
@@ -65,6 +73,19 @@ impl TestConfig {
         self.host_id = Some(host_id.into());
         self
     }
+
+    /// Use a local file object store in the given directory for the spawned [`TestServer`],
+    /// instead of an in-memory one
+    pub fn with_object_store_dir<S: Into<String>>(mut self, dir: S) -> Self {
+        self.object_store_dir = Some(dir.into());
+        self
+    }
+
+    /// Run the spawned [`TestServer`] as a read replica of the given host
+    pub fn with_read_replica_of<S: Into<String>>(mut self, host_id: S) -> Self {
+        self.read_replica_of = Some(host_id.into());
+        self
+    }
 }
 
 impl ConfigProvider for TestConfig {
@@ -79,10 +100,27 @@ impl ConfigProvider for TestConfig {
         } else {
             args.push("test-server".to_string());
         }
-        args.append(&mut vec![
-            "--object-store".to_string(),
-            "memory".to_string(),
-        ]);
+        if let Some(dir) = &self.object_store_dir {
+            args.append(&mut vec![
+                "--object-store".to_string(),
+                "file".to_string(),
+                "--data-dir".to_string(),
+                dir.to_owned(),
+            ]);
+        } else {
+            args.append(&mut vec![
+                "--object-store".to_string(),
+                "memory".to_string(),
+            ]);
+        }
+        if let Some(host) = &self.read_replica_of {
+            args.append(&mut vec![
+                "--read-replica-of".to_string(),
+                host.to_owned(),
+                "--read-replica-poll-interval".to_string(),
+                "10ms".to_string(),
+            ]);
+        }
         args
     }
 
@@ -286,6 +324,15 @@ impl TestServer {
             .expect("failed to send request to create last cache")
     }
 
+    pub async fn api_v3_delete(&self, request: &serde_json::Value) -> Response {
+        self.http_client
+            .post(format!("{base}/api/v3/delete", base = self.client_addr()))
+            .json(request)
+            .send()
+            .await
+            .expect("failed to send request to delete rows")
+    }
+
     pub async fn api_v3_configure_last_cache_delete(
         &self,
         request: &serde_json::Value,
@@ -300,6 +347,166 @@ impl TestServer {
             .await
             .expect("failed to send request to delete last cache")
     }
+
+    pub async fn api_v3_configure_database_create(&self, request: &serde_json::Value) -> Response {
+        self.http_client
+            .post(format!(
+                "{base}/api/v3/configure/database",
+                base = self.client_addr()
+            ))
+            .json(request)
+            .send()
+            .await
+            .expect("failed to send request to create database")
+    }
+
+    pub async fn api_v3_configure_table_create(&self, request: &serde_json::Value) -> Response {
+        self.http_client
+            .post(format!(
+                "{base}/api/v3/configure/table",
+                base = self.client_addr()
+            ))
+            .json(request)
+            .send()
+            .await
+            .expect("failed to send request to create table")
+    }
+
+    pub async fn api_v3_configure_schema_policy(&self, request: &serde_json::Value) -> Response {
+        self.http_client
+            .post(format!(
+                "{base}/api/v3/configure/schema_policy",
+                base = self.client_addr()
+            ))
+            .json(request)
+            .send()
+            .await
+            .expect("failed to send request to set schema policy")
+    }
+
+    pub async fn api_v3_configure_coercion_policy(&self, request: &serde_json::Value) -> Response {
+        self.http_client
+            .post(format!(
+                "{base}/api/v3/configure/table/coercion_policy",
+                base = self.client_addr()
+            ))
+            .json(request)
+            .send()
+            .await
+            .expect("failed to send request to set coercion policy")
+    }
+
+    pub async fn api_v3_configure_json_mapping(&self, request: &serde_json::Value) -> Response {
+        self.http_client
+            .post(format!(
+                "{base}/api/v3/configure/table/json_mapping",
+                base = self.client_addr()
+            ))
+            .json(request)
+            .send()
+            .await
+            .expect("failed to send request to set json mapping")
+    }
+
+    pub async fn api_v3_configure_quotas(&self, request: &serde_json::Value) -> Response {
+        self.http_client
+            .post(format!(
+                "{base}/api/v3/configure/database/quotas",
+                base = self.client_addr()
+            ))
+            .json(request)
+            .send()
+            .await
+            .expect("failed to send request to set quotas")
+    }
+
+    pub async fn api_v3_configure_retention_period(&self, request: &serde_json::Value) -> Response {
+        self.http_client
+            .post(format!(
+                "{base}/api/v3/configure/database/retention",
+                base = self.client_addr()
+            ))
+            .json(request)
+            .send()
+            .await
+            .expect("failed to send request to set retention period")
+    }
+
+    pub async fn api_v3_configure_catalog_export(&self) -> Response {
+        self.http_client
+            .get(format!(
+                "{base}/api/v3/configure/catalog",
+                base = self.client_addr()
+            ))
+            .send()
+            .await
+            .expect("failed to send request to export catalog")
+    }
+
+    pub async fn api_v3_configure_catalog_import(
+        &self,
+        export: &serde_json::Value,
+        dry_run: bool,
+    ) -> Response {
+        self.http_client
+            .post(format!(
+                "{base}/api/v3/configure/catalog",
+                base = self.client_addr()
+            ))
+            .query(&[("dry_run", dry_run)])
+            .json(export)
+            .send()
+            .await
+            .expect("failed to send request to import catalog")
+    }
+
+    pub async fn api_v3_configure_table_rename(&self, request: &serde_json::Value) -> Response {
+        self.http_client
+            .post(format!(
+                "{base}/api/v3/configure/table/rename",
+                base = self.client_addr()
+            ))
+            .json(request)
+            .send()
+            .await
+            .expect("failed to send request to rename table")
+    }
+
+    pub async fn api_v3_configure_column_rename(&self, request: &serde_json::Value) -> Response {
+        self.http_client
+            .post(format!(
+                "{base}/api/v3/configure/column/rename",
+                base = self.client_addr()
+            ))
+            .json(request)
+            .send()
+            .await
+            .expect("failed to send request to rename column")
+    }
+
+    pub async fn api_v3_configure_database_delete(&self, request: &serde_json::Value) -> Response {
+        self.http_client
+            .delete(format!(
+                "{base}/api/v3/configure/database",
+                base = self.client_addr()
+            ))
+            .json(request)
+            .send()
+            .await
+            .expect("failed to send request to delete database")
+    }
+
+    pub async fn api_v3_configure_table_delete(&self, request: &serde_json::Value) -> Response {
+        self.http_client
+            .delete(format!(
+                "{base}/api/v3/configure/table",
+                base = self.client_addr()
+            ))
+            .json(request)
+            .send()
+            .await
+            .expect("failed to send request to delete table")
+    }
 }
 
 /// Get an available bind address on localhost
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use hyper::StatusCode;
+use influxdb3_client::{Error, Precision};
+use pretty_assertions::assert_eq;
+
+use crate::TestServer;
+
+const QUERY: &str = "SELECT host, time, usage FROM cpu ORDER BY time";
+
+async fn query_pretty(server: &TestServer) -> String {
+    server
+        .api_v3_query_sql(&[("db", "foo"), ("q", QUERY), ("format", "pretty")])
+        .await
+        .text()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn read_replica_follows_host() {
+    let data_dir = test_helpers::tmp_dir().unwrap();
+    let data_dir_str = data_dir.path().to_str().unwrap();
+    let host = TestServer::configure()
+        .with_host_id("writer")
+        .with_object_store_dir(data_dir_str)
+        .spawn()
+        .await;
+
+    host.write_lp_to_db(
+        "foo",
+        "cpu,host=a usage=0.9 1\ncpu,host=b usage=0.5 2",
+        Precision::Nanosecond,
+    )
+    .await
+    .unwrap();
+
+    let replica = TestServer::configure()
+        .with_host_id("replica")
+        .with_object_store_dir(data_dir_str)
+        .with_read_replica_of("writer")
+        .spawn()
+        .await;
+
+    // the existing wal is replayed when the replica starts
+    assert_eq!(
+        "+------+-------------------------------+-------+\n\
+        | host | time                          | usage |\n\
+        +------+-------------------------------+-------+\n\
+        | a    | 1970-01-01T00:00:00.000000001 | 0.9   |\n\
+        | b    | 1970-01-01T00:00:00.000000002 | 0.5   |\n\
+        +------+-------------------------------+-------+",
+        query_pretty(&replica).await
+    );
+
+    // new writes to the host are followed, including the new column
+    host.write_lp_to_db(
+        "foo",
+        "cpu,host=c usage=0.7,idle=0.3 3",
+        Precision::Nanosecond,
+    )
+    .await
+    .unwrap();
+    let expected = query_pretty(&host).await;
+    let mut resp = String::new();
+    for _ in 0..50 {
+        resp = query_pretty(&replica).await;
+        if resp == expected {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(expected, resp);
+
+    // writes to the replica are rejected
+    let Err(Error::ApiError { code, .. }) = replica
+        .write_lp_to_db("foo", "cpu,host=d usage=0.1 4", Precision::Nanosecond)
+        .await
+    else {
+        panic!("write to read replica did not fail");
+    };
+    assert_eq!(code, StatusCode::FORBIDDEN);
+}
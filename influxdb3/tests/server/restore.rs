@@ -0,0 +1,86 @@
+use std::process::Command;
+
+use assert_cmd::cargo::CommandCargoExt;
+use influxdb3_client::Precision;
+use pretty_assertions::assert_eq;
+
+use crate::TestServer;
+
+const QUERY: &str = "SELECT host, time, usage FROM cpu ORDER BY time";
+
+fn run_restore_command(until: &str, target_host: &str, data_dir: &str) -> std::process::Output {
+    Command::cargo_bin("influxdb3")
+        .expect("create the influxdb3 command")
+        .arg("restore")
+        .args([
+            "--object-store",
+            "file",
+            "--data-dir",
+            data_dir,
+            "--host-id",
+            "writer",
+            "--target-host-id",
+            target_host,
+            "--until",
+            until,
+        ])
+        .output()
+        .expect("run the restore command")
+}
+
+#[tokio::test]
+async fn restore_to_wal_file() {
+    let data_dir = test_helpers::tmp_dir().unwrap();
+    let data_dir_str = data_dir.path().to_str().unwrap();
+    let host = TestServer::configure()
+        .with_host_id("writer")
+        .with_object_store_dir(data_dir_str)
+        .spawn()
+        .await;
+
+    // each write is in its own wal file
+    host.write_lp_to_db("foo", "cpu,host=a usage=0.9 1", Precision::Nanosecond)
+        .await
+        .unwrap();
+    host.write_lp_to_db("foo", "cpu,host=b usage=0.5 2", Precision::Nanosecond)
+        .await
+        .unwrap();
+
+    // restore to before the second write
+    let output = run_restore_command("1", "restored", data_dir_str);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("restored host writer to wal file 1 as host restored"),
+        "{stdout}"
+    );
+
+    let restored = TestServer::configure()
+        .with_host_id("restored")
+        .with_object_store_dir(data_dir_str)
+        .spawn()
+        .await;
+    assert_eq!(
+        "+------+-------------------------------+-------+\n\
+        | host | time                          | usage |\n\
+        +------+-------------------------------+-------+\n\
+        | a    | 1970-01-01T00:00:00.000000001 | 0.9   |\n\
+        +------+-------------------------------+-------+",
+        restored
+            .api_v3_query_sql(&[("db", "foo"), ("q", QUERY), ("format", "pretty")])
+            .await
+            .text()
+            .await
+            .unwrap()
+    );
+
+    // a host that is already in use can't be restored into
+    let output = run_restore_command("1", "restored", data_dir_str);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("already has a catalog"), "{stderr}");
+}
@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use hyper::StatusCode;
+use influxdb3_client::Precision;
+use pretty_assertions::assert_eq;
+
+use crate::TestServer;
+
+/// Reads lines from the streaming response until there are `n` of them
+async fn read_lines(response: &mut reqwest::Response, n: usize) -> Vec<String> {
+    let mut buf = String::new();
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while buf.lines().count() < n {
+            let chunk = response.chunk().await.unwrap().expect("subscription ended");
+            buf.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    })
+    .await
+    .expect("timed out waiting for subscription");
+    buf.lines().map(String::from).collect()
+}
+
+#[tokio::test]
+async fn api_v3_subscribe() {
+    let server = TestServer::spawn().await;
+    let client = reqwest::Client::new();
+    let url = format!("{base}/api/v3/subscribe", base = server.client_addr());
+
+    server
+        .write_lp_to_db(
+            "foo",
+            "cpu,host=a usage=0.9 1\n\
+            mem,host=a used=10i 1",
+            Precision::Nanosecond,
+        )
+        .await
+        .unwrap();
+
+    // resume from the first wal file, which is still retained, and stream line protocol:
+    let mut response = client
+        .get(&url)
+        .query(&[
+            ("db", "foo"),
+            ("table", "cpu"),
+            ("from", "1"),
+            ("format", "lp"),
+        ])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        read_lines(&mut response, 1).await,
+        ["cpu,host=a usage=0.9 1"]
+    );
+
+    // new writes are streamed as they happen, filtered by table:
+    server
+        .write_lp_to_db(
+            "foo",
+            "mem,host=b used=20i 2\n\
+            cpu,host=b usage=0.5 2",
+            Precision::Nanosecond,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        read_lines(&mut response, 1).await,
+        ["cpu,host=b usage=0.5 2"]
+    );
+
+    // stream as json from the start, for all tables:
+    let mut response = client
+        .get(&url)
+        .query(&[("db", "foo"), ("from", "1")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let records = read_lines(&mut response, 4)
+        .await
+        .into_iter()
+        .map(|line| serde_json::from_str::<serde_json::Value>(&line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        records,
+        [
+            serde_json::json!({
+                "wal_file_number": 1,
+                "database": "foo",
+                "table": "cpu",
+                "time": 1,
+                "tags": { "host": "a" },
+                "fields": { "usage": 0.9 }
+            }),
+            serde_json::json!({
+                "wal_file_number": 1,
+                "database": "foo",
+                "table": "mem",
+                "time": 1,
+                "tags": { "host": "a" },
+                "fields": { "used": 10 }
+            }),
+            serde_json::json!({
+                "wal_file_number": 2,
+                "database": "foo",
+                "table": "cpu",
+                "time": 2,
+                "tags": { "host": "b" },
+                "fields": { "usage": 0.5 }
+            }),
+            serde_json::json!({
+                "wal_file_number": 2,
+                "database": "foo",
+                "table": "mem",
+                "time": 2,
+                "tags": { "host": "b" },
+                "fields": { "used": 20 }
+            }),
+        ]
+    );
+
+    // wal files before the first one are not retained:
+    let response = client
+        .get(&url)
+        .query(&[("db", "foo"), ("from", "0")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::GONE);
+
+    // the database must exist:
+    let response = client
+        .get(&url)
+        .query(&[("db", "bar")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
@@ -1,4 +1,4 @@
-use arrow_util::assert_batches_sorted_eq;
+use arrow_util::{assert_batches_eq, assert_batches_sorted_eq};
 use influxdb3_client::Precision;
 use serde_json::json;
 
@@ -278,3 +278,102 @@ async fn last_caches_table() {
         );
     }
 }
+
+#[tokio::test]
+async fn schema_history_table() {
+    let server = TestServer::spawn().await;
+
+    server
+        .write_lp_to_db("foo", "cpu,host=a usage=0.5 1", Precision::Second)
+        .await
+        .unwrap();
+    server
+        .write_lp_to_db(
+            "foo",
+            "cpu,host=a usage=0.6,system=0.1 2",
+            Precision::Second,
+        )
+        .await
+        .unwrap();
+    // writes that do not change the schema are not in the history:
+    server
+        .write_lp_to_db("foo", "cpu,host=b usage=0.7 3", Precision::Second)
+        .await
+        .unwrap();
+    server
+        .write_lp_to_db("foo", "mem,host=a used=10i 4", Precision::Second)
+        .await
+        .unwrap();
+    // changes to other databases are not in the history of this one:
+    server
+        .write_lp_to_db("bar", "disk,host=a free=1i 1", Precision::Second)
+        .await
+        .unwrap();
+
+    let resp = server
+        .flight_sql_client("foo")
+        .await
+        .query(
+            "SELECT table_name, op, description FROM system.schema_history \
+            ORDER BY sequence_number",
+        )
+        .await
+        .unwrap();
+    let batches = collect_stream(resp).await;
+    assert_batches_eq!(
+        [
+            "+------------+--------------+----------------------------------+",
+            "| table_name | op           | description                      |",
+            "+------------+--------------+----------------------------------+",
+            "| cpu        | create_table | create table cpu with 3 columns  |",
+            "| cpu        | add_fields   | add columns to table cpu: system |",
+            "| mem        | create_table | create table mem with 3 columns  |",
+            "+------------+--------------+----------------------------------+",
+        ],
+        &batches
+    );
+}
+
+#[tokio::test]
+async fn rejected_writes_table() {
+    let server = TestServer::spawn().await;
+
+    // a write that is partially accepted:
+    server
+        .write_lp_to_db(
+            "foo",
+            "cpu,host=a usage=0.5 1\n\
+            cpu,host=a usage= 2\n\
+            cpu,host=a usage=0.7 3",
+            Precision::Nanosecond,
+        )
+        .await
+        .expect_err("write has an invalid line");
+    // a write that is rejected entirely, to another database:
+    server
+        .write_lp_to_db("bar", "mem,host=a 1", Precision::Nanosecond)
+        .await
+        .expect_err("write has an invalid line");
+
+    let resp = server
+        .flight_sql_client("foo")
+        .await
+        .query(
+            "SELECT database_name, line_number, original_line FROM system.rejected_writes \
+            ORDER BY time, line_number",
+        )
+        .await
+        .unwrap();
+    let batches = collect_stream(resp).await;
+    assert_batches_eq!(
+        [
+            "+---------------+-------------+---------------------+",
+            "| database_name | line_number | original_line       |",
+            "+---------------+-------------+---------------------+",
+            "| foo           | 2           | cpu,host=a usage= 2 |",
+            "| bar           | 1           | mem,host=a 1        |",
+            "+---------------+-------------+---------------------+",
+        ],
+        &batches
+    );
+}
@@ -0,0 +1,83 @@
+use std::process::Command;
+
+use assert_cmd::cargo::CommandCargoExt;
+use influxdb3_client::Precision;
+use serde_json::Value;
+
+use crate::{ConfigProvider, TestServer};
+
+fn run_wal_command(args: &[&str], data_dir: &str) -> std::process::Output {
+    Command::cargo_bin("influxdb3")
+        .expect("create the influxdb3 command")
+        .arg("wal")
+        .args(args)
+        .args([
+            "--object-store",
+            "file",
+            "--data-dir",
+            data_dir,
+            "--host-id",
+            "test-server",
+        ])
+        .output()
+        .expect("run the wal command")
+}
+
+#[tokio::test]
+async fn wal_list_verify_and_inspect() {
+    let data_dir = test_helpers::tmp_dir().unwrap();
+    let data_dir_str = data_dir.path().to_str().unwrap();
+    let server = TestServer::configure()
+        .with_object_store_dir(data_dir_str)
+        .spawn()
+        .await;
+
+    server
+        .write_lp_to_db(
+            "foo",
+            "cpu,host=a usage=1 1\ncpu,host=b usage=2 2\nmem,host=a free=3 3",
+            Precision::Nanosecond,
+        )
+        .await
+        .unwrap();
+
+    let output = run_wal_command(&["list", "--format", "json"], data_dir_str);
+    assert!(output.status.success());
+    let listing: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(listing.as_array().unwrap().len(), 1);
+    assert_eq!(listing[0]["wal_file_number"], 1);
+
+    let output = run_wal_command(&["verify"], data_dir_str);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("00000000001.wal\tok"), "{stdout}");
+
+    let output = run_wal_command(&["inspect", "--format", "json"], data_dir_str);
+    assert!(output.status.success());
+    let summaries: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let contents = &summaries[0]["contents"];
+    assert_eq!(contents["catalog_batches"][0]["database"], "foo");
+    let writes = contents["writes"].as_array().unwrap();
+    assert_eq!(writes.len(), 2);
+    assert_eq!(writes[0]["table"], "cpu");
+    assert_eq!(writes[0]["rows"], 2);
+    assert_eq!(writes[0]["min_time_ns"], 1);
+    assert_eq!(writes[0]["max_time_ns"], 2);
+    assert_eq!(writes[1]["table"], "mem");
+    assert_eq!(writes[1]["rows"], 1);
+
+    // corrupt the file and check that verification fails
+    let wal_file = data_dir.path().join("test-server/wal/00000000001.wal");
+    let mut bytes = std::fs::read(&wal_file).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    std::fs::write(&wal_file, bytes).unwrap();
+
+    let output = run_wal_command(&["verify"], data_dir_str);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("invalid: invalid wal file: crc32 checksum mismatch"),
+        "{stdout}"
+    );
+}
@@ -113,6 +113,237 @@ async fn api_v3_write() {
     }
 }
 
+#[tokio::test]
+async fn api_v3_write_json() {
+    let server = TestServer::spawn().await;
+    let client = reqwest::Client::new();
+    let url = format!("{base}/api/v3/write_json", base = server.client_addr());
+    let mapping = serde_json::json!({
+        "measurement_path": "kind",
+        "tags": { "host": "meta.host" },
+        "fields": {
+            "usage": { "path": "readings.0" },
+            "count": { "path": "count", "type": "integer" },
+        },
+        "timestamp": { "path": "time", "format": "unix_seconds" },
+    });
+
+    // newline-delimited documents with an inline mapping:
+    let resp = client
+        .post(&url)
+        .query(&[("db", "foo"), ("mapping", mapping.to_string().as_str())])
+        .body(
+            r#"{"kind": "cpu", "meta": {"host": "a"}, "readings": [0.5], "count": 1, "time": 1}
+            {"kind": "cpu", "meta": {"host": "b"}, "readings": [0.7], "time": 2}
+            {"meta": {"host": "c"}, "readings": [0.9], "time": 3}"#,
+        )
+        .send()
+        .await
+        .expect("send write request");
+    assert_eq!(StatusCode::BAD_REQUEST, resp.status());
+    let body = resp.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(
+        serde_json::json!({
+            "error": "partial write of JSON documents occurred",
+            "data": [{
+                "original_line": "{\"meta\":{\"host\":\"c\"},\"readings\":[0.9],\"time\":3}",
+                "line_number": 3,
+                "error_message": "no measurement was found at path 'kind'",
+            }],
+        }),
+        body
+    );
+
+    // a mapping stored for the table is used when the write does not give one:
+    let resp = server
+        .api_v3_configure_json_mapping(&serde_json::json!({
+            "db": "foo",
+            "table": "cpu",
+            "mapping": {
+                "tags": { "host": "host" },
+                "fields": { "usage": { "path": "usage" } },
+            },
+        }))
+        .await;
+    assert_eq!(StatusCode::OK, resp.status());
+    let resp = client
+        .post(&url)
+        .query(&[("db", "foo"), ("table", "cpu")])
+        .body(r#"[{"host": "d", "usage": 1.5}]"#)
+        .send()
+        .await
+        .expect("send write request");
+    assert_eq!(StatusCode::OK, resp.status());
+
+    let resp = server
+        .api_v3_query_sql(&[
+            ("db", "foo"),
+            ("q", "SELECT host, usage, count FROM cpu ORDER BY host"),
+            ("format", "json"),
+        ])
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::json!([
+            { "host": "a", "usage": 0.5, "count": 1 },
+            { "host": "b", "usage": 0.7 },
+            { "host": "d", "usage": 1.5 },
+        ]),
+        resp
+    );
+
+    struct TestCase {
+        params: &'static [(&'static str, &'static str)],
+        body: &'static str,
+        expected: StatusCode,
+    }
+    let test_cases = [
+        // No stored mapping for the table:
+        TestCase {
+            params: &[("db", "foo"), ("table", "mem")],
+            body: "{}",
+            expected: StatusCode::BAD_REQUEST,
+        },
+        // Mapping without fields:
+        TestCase {
+            params: &[("db", "foo"), ("table", "cpu"), ("mapping", "{}")],
+            body: "{}",
+            expected: StatusCode::BAD_REQUEST,
+        },
+        // Invalid JSON array:
+        TestCase {
+            params: &[("db", "foo"), ("table", "cpu")],
+            body: "[{\"host\": \"e\"",
+            expected: StatusCode::BAD_REQUEST,
+        },
+    ];
+    for (i, t) in test_cases.iter().enumerate() {
+        let status = client
+            .post(&url)
+            .query(t.params)
+            .body(t.body)
+            .send()
+            .await
+            .expect("send write request")
+            .status();
+        assert_eq!(t.expected, status, "test case ({i}) failed");
+    }
+}
+
+#[tokio::test]
+async fn api_v3_write_csv() {
+    let server = TestServer::spawn().await;
+    let client = reqwest::Client::new();
+    let url = format!("{base}/api/v3/write_csv", base = server.client_addr());
+
+    // annotated CSV, as exported from a query:
+    let resp = client
+        .post(&url)
+        .query(&[("db", "foo")])
+        .body(
+            "#group,false,false,false,false,true,true,true\n\
+            #datatype,string,long,dateTime:RFC3339,double,string,string,string\n\
+            #default,_result,,,,,,\n\
+            ,result,table,_time,_value,_field,_measurement,host\n\
+            ,,0,1970-01-01T00:00:01Z,0.5,usage,cpu,a\n\
+            ,,0,1970-01-01T00:00:02Z,high,usage,cpu,b\n",
+        )
+        .send()
+        .await
+        .expect("send write request");
+    assert_eq!(StatusCode::BAD_REQUEST, resp.status());
+    let body = resp.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(
+        serde_json::json!({
+            "error": "partial write of CSV rows occurred",
+            "data": [{
+                "original_line": ",,0,1970-01-01T00:00:02Z,high,usage,cpu,b",
+                "line_number": 6,
+                "error_message": "the value high of field usage is not a valid float",
+            }],
+        }),
+        body
+    );
+
+    // plain CSV with the roles of its columns:
+    let resp = client
+        .post(&url)
+        .query(&[
+            ("db", "foo"),
+            ("measurement", "cpu"),
+            ("tags", "host"),
+            ("fields", "usage:float"),
+            ("time_column", "time"),
+            ("time_format", "unix_seconds"),
+        ])
+        .body("time,host,usage\n3,c,0.7\n")
+        .send()
+        .await
+        .expect("send write request");
+    assert_eq!(StatusCode::OK, resp.status());
+
+    let resp = server
+        .api_v3_query_sql(&[
+            ("db", "foo"),
+            ("q", "SELECT host, usage FROM cpu ORDER BY host"),
+            ("format", "json"),
+        ])
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::json!([
+            { "host": "a", "usage": 0.5 },
+            { "host": "c", "usage": 0.7 },
+        ]),
+        resp
+    );
+
+    struct TestCase {
+        params: &'static [(&'static str, &'static str)],
+        body: &'static str,
+        expected: StatusCode,
+    }
+    let test_cases = [
+        // Plain CSV without column roles:
+        TestCase {
+            params: &[("db", "foo")],
+            body: "host,usage\nd,1\n",
+            expected: StatusCode::BAD_REQUEST,
+        },
+        // A field without a type:
+        TestCase {
+            params: &[("db", "foo"), ("measurement", "cpu"), ("fields", "usage")],
+            body: "host,usage\nd,1\n",
+            expected: StatusCode::BAD_REQUEST,
+        },
+        // A column that is not in the header:
+        TestCase {
+            params: &[
+                ("db", "foo"),
+                ("measurement", "cpu"),
+                ("fields", "load:float"),
+            ],
+            body: "host,usage\nd,1\n",
+            expected: StatusCode::BAD_REQUEST,
+        },
+    ];
+    for (i, t) in test_cases.iter().enumerate() {
+        let status = client
+            .post(&url)
+            .query(t.params)
+            .body(t.body)
+            .send()
+            .await
+            .expect("send write request")
+            .status();
+        assert_eq!(t.expected, status, "test case ({i}) failed");
+    }
+}
+
 #[tokio::test]
 async fn api_v1_write_request_parsing() {
     let server = TestServer::spawn().await;
@@ -6,14 +6,18 @@ use arrow::datatypes::SchemaRef;
 use bimap::BiHashMap;
 use influxdb3_id::{ColumnId, DbId, TableId};
 use influxdb3_wal::{
-    CatalogBatch, CatalogOp, FieldAdditions, LastCacheDefinition, LastCacheDelete,
+    CatalogBatch, CatalogOp, CoercionPolicy, ColumnRename, DatabaseDefinition, DatabaseQuotas,
+    FieldAdditions, FieldDataType, FieldDefinition, JsonMapping, LastCacheDefinition,
+    LastCacheDelete, LastCacheValueColumnsDef, Quota, RetentionPeriodDefinition, SchemaPolicy,
+    SchemaPolicyDefinition,
 };
 use influxdb_line_protocol::FieldValue;
 use observability_deps::tracing::info;
 use parking_lot::RwLock;
 use schema::{InfluxColumnType, InfluxFieldType, Schema, SchemaBuilder};
 use serde::{Deserialize, Serialize, Serializer};
-use std::collections::{BTreeMap, HashMap};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -40,6 +44,18 @@ pub enum Error {
     )]
     TooManyDbs,
 
+    #[error(
+        "Update to schema would exceed the {} quota of {} for database {}",
+        quota,
+        limit,
+        db_name
+    )]
+    QuotaExceeded {
+        db_name: String,
+        quota: Quota,
+        limit: u64,
+    },
+
     #[error("Table {} not in DB schema for {}", table_name, db_name)]
     TableNotFound { db_name: String, table_name: String },
 
@@ -68,6 +84,68 @@ pub enum Error {
         existing: String,
         attempted: String,
     },
+
+    #[error("Database {} already exists", db_name)]
+    DatabaseAlreadyExists { db_name: String },
+
+    #[error("Table {} already exists in database {}", table_name, db_name)]
+    TableAlreadyExists { db_name: String, table_name: String },
+
+    #[error(
+        "Column {} is defined more than once on table {}",
+        column_name,
+        table_name
+    )]
+    DuplicateColumn {
+        table_name: String,
+        column_name: String,
+    },
+
+    #[error("Column {} not in table {}", column_name, table_name)]
+    ColumnNotFound {
+        table_name: String,
+        column_name: String,
+    },
+
+    #[error("Column {} already exists on table {}", column_name, table_name)]
+    ColumnAlreadyExists {
+        table_name: String,
+        column_name: String,
+    },
+
+    #[error(
+        "Column {} of table {} cannot be renamed: {}",
+        column_name,
+        table_name,
+        reason
+    )]
+    CannotRenameColumn {
+        table_name: String,
+        column_name: String,
+        reason: String,
+    },
+
+    #[error(
+        "Last cache {} on table {} already exists with a different definition",
+        cache_name,
+        table_name
+    )]
+    LastCacheMismatch {
+        table_name: String,
+        cache_name: String,
+    },
+
+    #[error(
+        "Invalid last cache {} on table {}: {}",
+        cache_name,
+        table_name,
+        source
+    )]
+    InvalidLastCache {
+        table_name: String,
+        cache_name: String,
+        source: influxdb3_wal::Error,
+    },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -123,6 +201,9 @@ impl Catalog {
     pub(crate) const NUM_COLUMNS_PER_TABLE_LIMIT: usize = 500;
     /// Limit for the number of tables across all DBs that InfluxDB Edge can have
     pub(crate) const NUM_TABLES_LIMIT: usize = 2000;
+    /// Limit for the number of entries kept in the schema history of each database, beyond
+    /// which the oldest entries are dropped
+    pub(crate) const SCHEMA_HISTORY_PER_DB_LIMIT: usize = 1000;
 
     pub fn new(host_id: Arc<str>, instance_id: Arc<str>) -> Self {
         Self {
@@ -165,6 +246,127 @@ impl Catalog {
         Ok(db)
     }
 
+    /// Create a new database that has no tables. Returns the `CatalogBatch` that created it, which
+    /// must be written to the WAL so that the database is created again on replay.
+    pub fn create_database(
+        &self,
+        db_name: &str,
+        retention_period_ns: Option<u64>,
+        time_ns: i64,
+    ) -> Result<CatalogBatch> {
+        let mut inner = self.inner.write();
+        if inner.db_map.contains_right(db_name) {
+            return Err(Error::DatabaseAlreadyExists {
+                db_name: db_name.to_string(),
+            });
+        }
+
+        let database_id = DbId::new();
+        let database_name: Arc<str> = db_name.into();
+        let mut ops = vec![CatalogOp::CreateDatabase(DatabaseDefinition {
+            database_id,
+            database_name: Arc::clone(&database_name),
+        })];
+        if retention_period_ns.is_some() {
+            ops.push(CatalogOp::SetRetentionPeriod(RetentionPeriodDefinition {
+                retention_period_ns,
+            }));
+        }
+        let catalog_batch = CatalogBatch {
+            database_id,
+            database_name,
+            time_ns,
+            ops,
+        };
+        inner.apply_catalog_batch(&catalog_batch)?;
+
+        Ok(catalog_batch)
+    }
+
+    /// Create a new table with the given tags and fields, along with the database if it does not
+    /// exist yet. The tags make up the series key of the table, and a `time` column is always
+    /// added. Returns the `CatalogBatch` that created the table, which must be written to the WAL
+    /// so that the table is created again on replay.
+    pub fn create_table(
+        &self,
+        db_name: &str,
+        table_name: &str,
+        tags: &[impl AsRef<str>],
+        fields: &[(impl AsRef<str>, InfluxFieldType)],
+        time_ns: i64,
+    ) -> Result<CatalogBatch> {
+        let mut columns: BTreeMap<&str, InfluxColumnType> = BTreeMap::new();
+        let all_columns =
+            tags.iter()
+                .map(|tag| (tag.as_ref(), InfluxColumnType::Tag))
+                .chain(fields.iter().map(|(name, field_type)| {
+                    (name.as_ref(), InfluxColumnType::Field(*field_type))
+                }))
+                .chain([(TIME_COLUMN_NAME, InfluxColumnType::Timestamp)]);
+        for (name, column_type) in all_columns {
+            if columns.insert(name, column_type).is_some() {
+                return Err(Error::DuplicateColumn {
+                    table_name: table_name.to_string(),
+                    column_name: name.to_string(),
+                });
+            }
+        }
+        if columns.len() > Self::NUM_COLUMNS_PER_TABLE_LIMIT {
+            return Err(Error::TooManyColumns);
+        }
+
+        let mut inner = self.inner.write();
+        let mut ops = Vec::with_capacity(2);
+        let database_id = match inner.db_map.get_by_right(db_name).copied() {
+            Some(database_id) => {
+                let table_exists = inner
+                    .databases
+                    .get(&database_id)
+                    .is_some_and(|db| db.table_name_to_id(table_name).is_some());
+                if table_exists {
+                    return Err(Error::TableAlreadyExists {
+                        db_name: db_name.to_string(),
+                        table_name: table_name.to_string(),
+                    });
+                }
+                database_id
+            }
+            None => {
+                let database_id = DbId::new();
+                ops.push(CatalogOp::CreateDatabase(DatabaseDefinition {
+                    database_id,
+                    database_name: db_name.into(),
+                }));
+                database_id
+            }
+        };
+        ops.push(CatalogOp::CreateTable(influxdb3_wal::TableDefinition {
+            database_id,
+            database_name: db_name.into(),
+            table_name: table_name.into(),
+            table_id: TableId::new(),
+            field_definitions: columns
+                .iter()
+                .map(|(name, column_type)| FieldDefinition {
+                    name: (*name).into(),
+                    data_type: FieldDataType::from(column_type),
+                })
+                .collect(),
+            // tables use the v1 data model, like those created by line protocol writes, so that
+            // they can be written to with line protocol:
+            key: None,
+        }));
+        let catalog_batch = CatalogBatch {
+            database_id,
+            database_name: db_name.into(),
+            time_ns,
+            ops,
+        };
+        inner.apply_catalog_batch(&catalog_batch)?;
+
+        Ok(catalog_batch)
+    }
+
     pub fn sequence_number(&self) -> SequenceNumber {
         self.inner.read().sequence
     }
@@ -173,42 +375,29 @@ impl Catalog {
         self.inner.read().clone()
     }
 
-    pub fn add_last_cache(&self, db_id: DbId, table_id: TableId, last_cache: LastCacheDefinition) {
-        let mut inner = self.inner.write();
-        let mut db = inner
-            .databases
-            .get(&db_id)
-            .expect("db should exist")
-            .as_ref()
-            .clone();
-        let table = db.tables.get_mut(&table_id).expect("table should exist");
-        table.add_last_cache(last_cache);
-        inner.databases.insert(db_id, Arc::new(db));
-        inner.sequence = inner.sequence.next();
-        inner.updated = true;
+    pub fn instance_id(&self) -> Arc<str> {
+        Arc::clone(&self.inner.read().instance_id)
     }
 
-    pub fn delete_last_cache(&self, db_id: DbId, table_id: TableId, name: &str) {
+    pub fn host_id(&self) -> Arc<str> {
+        Arc::clone(&self.inner.read().host_id)
+    }
+
+    /// Sets the host and instance ids, for when the catalog is copied to a new host
+    pub fn set_host_and_instance_id(&self, host_id: Arc<str>, instance_id: Arc<str>) {
         let mut inner = self.inner.write();
-        let mut db = inner
-            .databases
-            .get(&db_id)
-            .expect("db should exist")
-            .as_ref()
-            .clone();
-        let table = db.tables.get_mut(&table_id).expect("table should exist");
-        table.remove_last_cache(name);
-        inner.databases.insert(db_id, Arc::new(db));
-        inner.sequence = inner.sequence.next();
-        inner.updated = true;
+        inner.host_id = host_id;
+        inner.instance_id = instance_id;
     }
 
-    pub fn instance_id(&self) -> Arc<str> {
-        Arc::clone(&self.inner.read().instance_id)
+    /// Returns true if the database with this id was deleted
+    pub fn db_is_deleted(&self, db_id: DbId) -> bool {
+        self.inner.read().deleted_databases.contains(&db_id)
     }
 
-    pub fn host_id(&self) -> Arc<str> {
-        Arc::clone(&self.inner.read().host_id)
+    /// Returns true if the table with this id was deleted, either on its own or with its database
+    pub fn table_is_deleted(&self, table_id: TableId) -> bool {
+        self.inner.read().deleted_tables.contains(&table_id)
     }
 
     #[cfg(test)]
@@ -281,6 +470,10 @@ impl DatabaseSchemaProvider for Catalog {
     fn list_db_schema(&self) -> Vec<Arc<DatabaseSchema>> {
         self.inner.read().databases.values().cloned().collect()
     }
+
+    fn schema_history(&self, db_id: DbId) -> Vec<SchemaHistoryEntry> {
+        self.inner.read().schema_history(db_id)
+    }
 }
 
 #[serde_with::serde_as]
@@ -299,6 +492,30 @@ pub struct InnerCatalog {
     updated: bool,
     #[serde_as(as = "DbMapAsArray")]
     db_map: BiHashMap<DbId, Arc<str>>,
+    /// The ids of the databases that have been deleted. Catalog ops for them are ignored, as
+    /// they can be replayed from WAL files written before the delete.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    deleted_databases: BTreeSet<DbId>,
+    /// The ids of the tables that have been deleted, including those of deleted databases
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    deleted_tables: BTreeSet<TableId>,
+    /// The catalog batches that changed the catalog, in the order they were applied. Only the
+    /// most recent entries of each database are kept, and those of deleted databases are dropped.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    history: Vec<SchemaHistoryEntry>,
+}
+
+/// A catalog batch that changed the catalog, which is kept so that it can be found when, and by
+/// which ops, the schema of a database was changed
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct SchemaHistoryEntry {
+    /// The sequence number of the catalog after the batch was applied
+    pub sequence: SequenceNumber,
+    /// The time of the batch, which is when the write or request that made it was received
+    pub time_ns: i64,
+    pub database_id: DbId,
+    pub database_name: Arc<str>,
+    pub ops: Vec<CatalogOp>,
 }
 
 serde_with::serde_conv!(
@@ -363,6 +580,9 @@ serde_with::serde_conv!(
                 id: db.id,
                 name: Arc::clone(&db.name),
                 tables: db.tables.values().cloned().collect(),
+                schema_policy: db.schema_policy,
+                retention_period_ns: db.retention_period_ns,
+                quotas: db.quotas,
             });
             acc
         })
@@ -383,7 +603,10 @@ serde_with::serde_conv!(
                     }
                     Ok(acc)
                 })?,
-                table_map
+                table_map,
+                schema_policy: db.schema_policy,
+                retention_period_ns: db.retention_period_ns,
+                quotas: db.quotas,
             })) {
                 return Err(format!("found duplicate db: {}", db.name));
             }
@@ -397,6 +620,12 @@ struct DatabasesSerialized {
     pub id: DbId,
     pub name: Arc<str>,
     pub tables: Vec<TableDefinition>,
+    #[serde(default, skip_serializing_if = "SchemaPolicy::is_open")]
+    pub schema_policy: SchemaPolicy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_period_ns: Option<u64>,
+    #[serde(default, skip_serializing_if = "DatabaseQuotas::is_unlimited")]
+    pub quotas: DatabaseQuotas,
 }
 
 impl InnerCatalog {
@@ -408,6 +637,9 @@ impl InnerCatalog {
             instance_id,
             updated: false,
             db_map: BiHashMap::new(),
+            deleted_databases: BTreeSet::new(),
+            deleted_tables: BTreeSet::new(),
+            history: Vec::new(),
         }
     }
 
@@ -419,16 +651,72 @@ impl InnerCatalog {
         self.databases.values().map(|db| db.tables.len()).sum()
     }
 
+    pub fn db_count(&self) -> usize {
+        self.databases.len()
+    }
+
+    pub(crate) fn db_schema(&self, db_name: &str) -> Option<&Arc<DatabaseSchema>> {
+        self.db_map
+            .get_by_right(db_name)
+            .and_then(|db_id| self.databases.get(db_id))
+    }
+
     /// Applies the `CatalogBatch` while validating that all updates are compatible. If updates
     /// have already been applied, the sequence number and updated tracker are not updated.
+    ///
+    /// Ops on databases and tables that have been deleted are ignored. Batches that change the
+    /// catalog of a database that exists are added to its history.
     pub fn apply_catalog_batch(&mut self, catalog_batch: &CatalogBatch) -> Result<()> {
+        let sequence = self.sequence;
+        self.apply_catalog_batch_ops(catalog_batch)?;
+        let database_id = catalog_batch.database_id;
+        if self.sequence != sequence && !self.deleted_databases.contains(&database_id) {
+            self.history.push(SchemaHistoryEntry {
+                sequence: self.sequence,
+                time_ns: catalog_batch.time_ns,
+                database_id,
+                database_name: Arc::clone(&catalog_batch.database_name),
+                ops: catalog_batch.ops.clone(),
+            });
+            let entries = self
+                .history
+                .iter()
+                .filter(|entry| entry.database_id == database_id)
+                .count();
+            if entries > Catalog::SCHEMA_HISTORY_PER_DB_LIMIT {
+                if let Some(oldest) = self
+                    .history
+                    .iter()
+                    .position(|entry| entry.database_id == database_id)
+                {
+                    self.history.remove(oldest);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_catalog_batch_ops(&mut self, catalog_batch: &CatalogBatch) -> Result<()> {
+        if self.deleted_databases.contains(&catalog_batch.database_id) {
+            return Ok(());
+        }
+        if catalog_batch
+            .ops
+            .iter()
+            .any(|op| matches!(op, CatalogOp::DeleteDatabase(_)))
+        {
+            self.delete_database(catalog_batch.database_id);
+            return Ok(());
+        }
+        let catalog_batch = self.without_deleted_tables(catalog_batch);
         let table_count = self.table_count();
 
         if let Some(db) = self.databases.get(&catalog_batch.database_id) {
             let existing_table_count = db.tables.len();
 
-            if let Some(new_db) = db.new_if_updated_from_batch(catalog_batch)? {
-                let new_table_count = new_db.tables.len() - existing_table_count;
+            if let Some(new_db) = db.new_if_updated_from_batch(&catalog_batch)? {
+                let new_table_count = new_db.tables.len().saturating_sub(existing_table_count);
                 if table_count + new_table_count > Catalog::NUM_TABLES_LIMIT {
                     return Err(Error::TooManyTables);
                 }
@@ -439,11 +727,19 @@ impl InnerCatalog {
                 self.db_map.insert(new_db.id, Arc::clone(&new_db.name));
             }
         } else {
+            // there is nothing to delete from a database that does not exist
+            if catalog_batch
+                .ops
+                .iter()
+                .all(|op| matches!(op, CatalogOp::DeleteTable(_)))
+            {
+                return Ok(());
+            }
             if self.databases.len() >= Catalog::NUM_DBS_LIMIT {
                 return Err(Error::TooManyDbs);
             }
 
-            let new_db = DatabaseSchema::new_from_batch(catalog_batch)?;
+            let new_db = DatabaseSchema::new_from_batch(&catalog_batch)?;
             if table_count + new_db.tables.len() > Catalog::NUM_TABLES_LIMIT {
                 return Err(Error::TooManyTables);
             }
@@ -455,12 +751,72 @@ impl InnerCatalog {
             self.db_map.insert(new_db.id, Arc::clone(&new_db.name));
         }
 
+        for op in &catalog_batch.ops {
+            if let CatalogOp::DeleteTable(table_delete) = op {
+                self.deleted_tables.insert(table_delete.table_id);
+            }
+        }
+
         Ok(())
     }
 
+    /// Removes the database and all of its tables, and records their ids as deleted
+    fn delete_database(&mut self, db_id: DbId) {
+        if let Some(db) = self.databases.remove(&db_id) {
+            self.deleted_tables.extend(db.tables.keys().copied());
+        }
+        self.db_map.remove_by_left(&db_id);
+        self.deleted_databases.insert(db_id);
+        self.history.retain(|entry| entry.database_id != db_id);
+        self.sequence = self.sequence.next();
+        self.updated = true;
+    }
+
+    /// Returns the batch without the ops on tables that have been deleted
+    fn without_deleted_tables<'a>(&self, catalog_batch: &'a CatalogBatch) -> Cow<'a, CatalogBatch> {
+        let is_deleted = |op: &CatalogOp| {
+            catalog_op_table_id(op).is_some_and(|table_id| self.deleted_tables.contains(&table_id))
+        };
+        if !catalog_batch.ops.iter().any(is_deleted) {
+            return Cow::Borrowed(catalog_batch);
+        }
+        let mut catalog_batch = catalog_batch.clone();
+        catalog_batch.ops.retain(|op| !is_deleted(op));
+        Cow::Owned(catalog_batch)
+    }
+
     pub fn db_exists(&self, db_id: DbId) -> bool {
         self.databases.contains_key(&db_id)
     }
+
+    /// The batches that changed the database with this id, in the order they were applied
+    pub fn schema_history(&self, db_id: DbId) -> Vec<SchemaHistoryEntry> {
+        self.history
+            .iter()
+            .filter(|entry| entry.database_id == db_id)
+            .cloned()
+            .collect()
+    }
+}
+
+/// The id of the table a catalog op applies to, if it applies to one
+fn catalog_op_table_id(op: &CatalogOp) -> Option<TableId> {
+    match op {
+        CatalogOp::CreateDatabase(_)
+        | CatalogOp::DeleteDatabase(_)
+        | CatalogOp::SetRetentionPeriod(_)
+        | CatalogOp::SetQuotas(_) => None,
+        CatalogOp::CreateTable(table_definition) => Some(table_definition.table_id),
+        CatalogOp::AddFields(field_additions) => Some(field_additions.table_id),
+        CatalogOp::CreateLastCache(last_cache_definition) => Some(last_cache_definition.table_id),
+        CatalogOp::DeleteLastCache(last_cache_deletion) => Some(last_cache_deletion.table_id),
+        CatalogOp::DeleteTable(table_delete) => Some(table_delete.table_id),
+        CatalogOp::SetSchemaPolicy(definition) => definition.table_id,
+        CatalogOp::RenameTable(table_rename) => Some(table_rename.table_id),
+        CatalogOp::RenameColumn(column_rename) => Some(column_rename.table_id),
+        CatalogOp::SetCoercionPolicy(definition) => Some(definition.table_id),
+        CatalogOp::SetJsonMapping(definition) => Some(definition.table_id),
+    }
 }
 
 #[serde_with::serde_as]
@@ -472,6 +828,15 @@ pub struct DatabaseSchema {
     pub tables: BTreeMap<TableId, TableDefinition>,
     #[serde_as(as = "TableMapAsArray")]
     pub table_map: BiHashMap<TableId, Arc<str>>,
+    /// The schema policy of tables that do not have their own
+    #[serde(default, skip_serializing_if = "SchemaPolicy::is_open")]
+    pub schema_policy: SchemaPolicy,
+    /// How long data is kept for, or `None` if it is kept forever
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_period_ns: Option<u64>,
+    /// The limits on the resources the database can use
+    #[serde(default, skip_serializing_if = "DatabaseQuotas::is_unlimited")]
+    pub quotas: DatabaseQuotas,
 }
 
 impl DatabaseSchema {
@@ -481,6 +846,9 @@ impl DatabaseSchema {
             name,
             tables: BTreeMap::new(),
             table_map: BiHashMap::new(),
+            schema_policy: SchemaPolicy::default(),
+            retention_period_ns: None,
+            quotas: DatabaseQuotas::default(),
         }
     }
 
@@ -489,6 +857,10 @@ impl DatabaseSchema {
     /// returned, otherwise a new `DatabaseSchema` will be returned with the updates applied.
     pub fn new_if_updated_from_batch(&self, catalog_batch: &CatalogBatch) -> Result<Option<Self>> {
         let mut updated_or_new_tables = BTreeMap::new();
+        let mut deleted_tables = BTreeSet::new();
+        let mut schema_policy = self.schema_policy;
+        let mut retention_period_ns = self.retention_period_ns;
+        let mut quotas = self.quotas;
 
         for catalog_op in &catalog_batch.ops {
             match catalog_op {
@@ -565,14 +937,139 @@ impl DatabaseSchema {
                         updated_or_new_tables.insert(new_table.table_id, new_table);
                     }
                 }
+                // deleting the database is handled by the catalog
+                CatalogOp::DeleteDatabase(_) => (),
+                CatalogOp::DeleteTable(table_delete) => {
+                    updated_or_new_tables.remove(&table_delete.table_id);
+                    if self.tables.contains_key(&table_delete.table_id) {
+                        deleted_tables.insert(table_delete.table_id);
+                    }
+                }
+                CatalogOp::SetSchemaPolicy(SchemaPolicyDefinition {
+                    table_id: None,
+                    policy,
+                    ..
+                }) => schema_policy = *policy,
+                CatalogOp::SetSchemaPolicy(SchemaPolicyDefinition {
+                    table_id: Some(table_id),
+                    table_name,
+                    policy,
+                }) => {
+                    let new_or_existing_table = updated_or_new_tables
+                        .get(table_id)
+                        .or_else(|| self.tables.get(table_id));
+
+                    let table = new_or_existing_table.ok_or_else(|| TableNotFound {
+                        db_name: self.name.to_string(),
+                        table_name: table_name.as_deref().unwrap_or_default().to_string(),
+                    })?;
+
+                    if let Some(new_table) = table.new_if_schema_policy_changes(*policy) {
+                        updated_or_new_tables.insert(new_table.table_id, new_table);
+                    }
+                }
+                CatalogOp::RenameTable(table_rename) => {
+                    let new_or_existing_table = updated_or_new_tables
+                        .get(&table_rename.table_id)
+                        .or_else(|| self.tables.get(&table_rename.table_id));
+
+                    let table = new_or_existing_table.ok_or_else(|| TableNotFound {
+                        db_name: self.name.to_string(),
+                        table_name: table_rename.old_name.to_string(),
+                    })?;
+
+                    // the rename is already applied if the catalog is ahead of the wal that is
+                    // being replayed
+                    if table.table_name != table_rename.old_name
+                        || table.table_name == table_rename.new_name
+                    {
+                        continue;
+                    }
+                    let name_taken = self
+                        .tables
+                        .keys()
+                        .chain(updated_or_new_tables.keys())
+                        .filter(|id| **id != table_rename.table_id && !deleted_tables.contains(*id))
+                        .filter_map(|id| {
+                            updated_or_new_tables
+                                .get(id)
+                                .or_else(|| self.tables.get(id))
+                        })
+                        .any(|t| t.table_name == table_rename.new_name);
+                    if name_taken {
+                        return Err(Error::TableAlreadyExists {
+                            db_name: self.name.to_string(),
+                            table_name: table_rename.new_name.to_string(),
+                        });
+                    }
+
+                    let new_table = table.renamed(Arc::clone(&table_rename.new_name));
+                    updated_or_new_tables.insert(new_table.table_id, new_table);
+                }
+                CatalogOp::RenameColumn(column_rename) => {
+                    let new_or_existing_table = updated_or_new_tables
+                        .get(&column_rename.table_id)
+                        .or_else(|| self.tables.get(&column_rename.table_id));
+
+                    let table = new_or_existing_table.ok_or_else(|| TableNotFound {
+                        db_name: self.name.to_string(),
+                        table_name: column_rename.table_name.to_string(),
+                    })?;
+
+                    if let Some(new_table) = table.new_if_column_rename_changes(column_rename)? {
+                        updated_or_new_tables.insert(new_table.table_id, new_table);
+                    }
+                }
+                CatalogOp::SetRetentionPeriod(definition) => {
+                    retention_period_ns = definition.retention_period_ns;
+                }
+                CatalogOp::SetCoercionPolicy(definition) => {
+                    let new_or_existing_table = updated_or_new_tables
+                        .get(&definition.table_id)
+                        .or_else(|| self.tables.get(&definition.table_id));
+
+                    let table = new_or_existing_table.ok_or_else(|| TableNotFound {
+                        db_name: self.name.to_string(),
+                        table_name: definition.table_name.to_string(),
+                    })?;
+
+                    if let Some(new_table) = table.new_if_coercion_policy_changes(definition.policy)
+                    {
+                        updated_or_new_tables.insert(new_table.table_id, new_table);
+                    }
+                }
+                CatalogOp::SetQuotas(definition) => quotas = definition.quotas,
+                CatalogOp::SetJsonMapping(definition) => {
+                    let new_or_existing_table = updated_or_new_tables
+                        .get(&definition.table_id)
+                        .or_else(|| self.tables.get(&definition.table_id));
+
+                    let table = new_or_existing_table.ok_or_else(|| TableNotFound {
+                        db_name: self.name.to_string(),
+                        table_name: definition.table_name.to_string(),
+                    })?;
+
+                    if let Some(new_table) =
+                        table.new_if_json_mapping_changes(definition.mapping.as_ref())
+                    {
+                        updated_or_new_tables.insert(new_table.table_id, new_table);
+                    }
+                }
             }
         }
 
-        if updated_or_new_tables.is_empty() {
+        if updated_or_new_tables.is_empty()
+            && deleted_tables.is_empty()
+            && schema_policy == self.schema_policy
+            && retention_period_ns == self.retention_period_ns
+            && quotas == self.quotas
+        {
             Ok(None)
         } else {
             for (table_id, table_def) in &self.tables {
-                if !updated_or_new_tables.contains_key(table_id) {
+                if !updated_or_new_tables.contains_key(table_id)
+                    && !deleted_tables.contains(table_id)
+                {
                     updated_or_new_tables.insert(*table_id, table_def.clone());
                 }
             }
@@ -583,24 +1080,50 @@ impl DatabaseSchema {
                 .map(|(table_id, table_def)| (*table_id, Arc::clone(&table_def.table_name)))
                 .collect();
 
-            Ok(Some(Self {
+            let new_db = Self {
                 id: self.id,
                 name: Arc::clone(&self.name),
                 tables: updated_or_new_tables,
                 table_map: new_table_maps,
-            }))
+                schema_policy,
+                retention_period_ns,
+                quotas,
+            };
+            new_db.check_schema_quotas(self)?;
+
+            Ok(Some(new_db))
         }
     }
 
+    /// Returns an error if this schema has more tables or columns than its quotas allow, and more
+    /// than the previous schema had. A schema that already exceeds a quota that was lowered is
+    /// still allowed to change, as long as it does not grow.
+    fn check_schema_quotas(&self, previous: &Self) -> Result<()> {
+        for (quota, count, previous_count) in [
+            (Quota::Tables, self.table_count(), previous.table_count()),
+            (Quota::Columns, self.column_count(), previous.column_count()),
+        ] {
+            if let Some(limit) = self.quotas.limit(quota) {
+                if count > previous_count && count > limit {
+                    return Err(Error::QuotaExceeded {
+                        db_name: self.name.to_string(),
+                        quota,
+                        limit,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn new_from_batch(catalog_batch: &CatalogBatch) -> Result<Self> {
         let db_schema = Self::new(
             catalog_batch.database_id,
             Arc::clone(&catalog_batch.database_name),
         );
-        let new_db = db_schema
-            .new_if_updated_from_batch(catalog_batch)?
-            .expect("database must be new");
-        Ok(new_db)
+        // a batch that only creates the database does not update the empty schema:
+        let new_db = db_schema.new_if_updated_from_batch(catalog_batch)?;
+        Ok(new_db.unwrap_or(db_schema))
     }
 
     pub fn table_schema(&self, table_name: impl Into<Arc<str>>) -> Option<Schema> {
@@ -664,6 +1187,29 @@ impl DatabaseSchema {
     pub fn table_id_to_name(&self, table_id: TableId) -> Option<Arc<str>> {
         self.table_map.get_by_left(&table_id).map(Arc::clone)
     }
+
+    /// The number of tables in the database
+    pub fn table_count(&self) -> u64 {
+        self.tables.len() as u64
+    }
+
+    /// The number of columns in the database, summed over all of its tables
+    pub fn column_count(&self) -> u64 {
+        self.tables.values().map(|t| t.num_columns() as u64).sum()
+    }
+
+    /// The schema policy of the table, which is the default of the database unless the table has
+    /// its own
+    pub fn table_schema_policy(&self, table: &TableDefinition) -> SchemaPolicy {
+        table.schema_policy.unwrap_or(self.schema_policy)
+    }
+
+    /// The time before which data in the database has expired, given the current time, or `None`
+    /// if the database keeps data forever
+    pub fn retention_cutoff_ns(&self, now_ns: i64) -> Option<i64> {
+        self.retention_period_ns
+            .map(|period| now_ns.saturating_sub(i64::try_from(period).unwrap_or(i64::MAX)))
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -672,6 +1218,13 @@ pub struct TableDefinition {
     pub table_name: Arc<str>,
     pub schema: TableSchema,
     pub last_caches: BTreeMap<String, LastCacheDefinition>,
+    /// The schema policy of the table, if it does not use the default of its database
+    pub schema_policy: Option<SchemaPolicy>,
+    /// Which field values written to the table are converted to the type of their column
+    pub coercion_policy: CoercionPolicy,
+    /// How JSON documents written to the table are mapped to rows, if they can be written without
+    /// giving a mapping
+    pub json_mapping: Option<JsonMapping>,
 }
 
 impl TableDefinition {
@@ -709,6 +1262,9 @@ impl TableDefinition {
             table_name,
             schema,
             last_caches: BTreeMap::new(),
+            schema_policy: None,
+            coercion_policy: CoercionPolicy::default(),
+            json_mapping: None,
         })
     }
 
@@ -839,6 +1395,165 @@ impl TableDefinition {
         }
     }
 
+    pub(crate) fn new_if_schema_policy_changes(&self, policy: SchemaPolicy) -> Option<Self> {
+        if self.schema_policy == Some(policy) {
+            None
+        } else {
+            let mut new_table = self.clone();
+            new_table.schema_policy = Some(policy);
+            Some(new_table)
+        }
+    }
+
+    pub(crate) fn new_if_coercion_policy_changes(&self, policy: CoercionPolicy) -> Option<Self> {
+        if self.coercion_policy == policy {
+            None
+        } else {
+            let mut new_table = self.clone();
+            new_table.coercion_policy = policy;
+            Some(new_table)
+        }
+    }
+
+    pub(crate) fn new_if_json_mapping_changes(
+        &self,
+        mapping: Option<&JsonMapping>,
+    ) -> Option<Self> {
+        if self.json_mapping.as_ref() == mapping {
+            None
+        } else {
+            let mut new_table = self.clone();
+            new_table.json_mapping = mapping.cloned();
+            Some(new_table)
+        }
+    }
+
+    /// Returns a copy of this table with the given name, keeping its id and columns
+    pub(crate) fn renamed(&self, table_name: Arc<str>) -> Self {
+        let mut new_table = self.clone();
+        new_table.schema.schema = self.schema_with_names(&table_name, None);
+        for last_cache in new_table.last_caches.values_mut() {
+            last_cache.table = table_name.to_string();
+        }
+        new_table.table_name = table_name;
+        new_table
+    }
+
+    /// Validates that the column can be renamed, and returns a new `TableDefinition` if the
+    /// column still has the old name.
+    pub(crate) fn new_if_column_rename_changes(
+        &self,
+        column_rename: &ColumnRename,
+    ) -> Result<Option<Self>> {
+        let current_name = self
+            .schema
+            .id_to_name(column_rename.column_id)
+            .ok_or_else(|| Error::ColumnNotFound {
+                table_name: self.table_name.to_string(),
+                column_name: column_rename.old_name.to_string(),
+            })?;
+        // the rename is already applied if the catalog is ahead of the wal that is being replayed
+        if current_name != column_rename.old_name || current_name == column_rename.new_name {
+            return Ok(None);
+        }
+        if self.column_exists(&column_rename.new_name) {
+            return Err(Error::ColumnAlreadyExists {
+                table_name: self.table_name.to_string(),
+                column_name: column_rename.new_name.to_string(),
+            });
+        }
+        let cannot_rename = |reason: &str| Error::CannotRenameColumn {
+            table_name: self.table_name.to_string(),
+            column_name: current_name.to_string(),
+            reason: reason.to_string(),
+        };
+        if self.field_type_by_name(&current_name) == Some(InfluxColumnType::Timestamp) {
+            return Err(cannot_rename("it is the time column"));
+        }
+        // last caches hold their columns by name, so they would no longer line up with the table
+        if let Some(last_cache) = self.last_caches.values().find(|last_cache| {
+            last_cache
+                .key_columns
+                .iter()
+                .any(|c| c.as_str() == current_name.as_ref())
+                || match &last_cache.value_columns {
+                    LastCacheValueColumnsDef::Explicit { columns } => {
+                        columns.iter().any(|c| c.as_str() == current_name.as_ref())
+                    }
+                    LastCacheValueColumnsDef::AllNonKeyColumns => true,
+                }
+        }) {
+            return Err(cannot_rename(&format!(
+                "it is used by last cache {}, which must be deleted first",
+                last_cache.name
+            )));
+        }
+
+        let mut new_table = self.clone();
+        new_table.schema.schema = self.schema_with_names(
+            &self.table_name,
+            Some((&current_name, &column_rename.new_name)),
+        );
+        new_table
+            .schema
+            .rename_column(column_rename.column_id, &column_rename.new_name);
+        Ok(Some(new_table))
+    }
+
+    /// Builds the schema of this table with the given table name, and with a column renamed if
+    /// an `(old, new)` pair of column names is given
+    fn schema_with_names(&self, table_name: &str, column_rename: Option<(&str, &str)>) -> Schema {
+        let name_of = |name: &str| match column_rename {
+            Some((old, new)) if old == name => new.to_string(),
+            _ => name.to_string(),
+        };
+        // Use BTree to keep the columns ordered, as when they are added:
+        let mut cols = BTreeMap::new();
+        for (col_type, field) in self.influx_schema().iter() {
+            cols.insert(name_of(field.name()), col_type);
+        }
+
+        let mut schema_builder = SchemaBuilder::with_capacity(cols.len());
+        schema_builder.measurement(table_name);
+        if let Some(series_key) = self.influx_schema().series_key() {
+            schema_builder.with_series_key(series_key.into_iter().map(name_of));
+        }
+        for (name, col_type) in cols {
+            schema_builder.influx_column(name.as_str(), col_type);
+        }
+        schema_builder
+            .build()
+            .expect("renaming a table or column keeps the schema valid")
+    }
+
+    /// The names of the columns, in data written with the given column ids, that have been
+    /// renamed since, paired with the names the columns have now
+    pub fn renamed_columns(
+        &self,
+        column_ids: &BTreeMap<Arc<str>, ColumnId>,
+    ) -> Vec<(Arc<str>, Arc<str>)> {
+        column_ids
+            .iter()
+            .filter_map(|(name, id)| {
+                let current_name = self.schema.id_to_name(*id)?;
+                (current_name != *name).then(|| (Arc::clone(name), current_name))
+            })
+            .collect()
+    }
+
+    /// The names of the columns, not in data written with the given column ids, that have the
+    /// name that another column had in that data, i.e. that reuse the name of a column that has
+    /// been renamed since
+    pub fn reused_column_names(&self, column_ids: &BTreeMap<Arc<str>, ColumnId>) -> Vec<Arc<str>> {
+        let file_ids: BTreeSet<ColumnId> = column_ids.values().copied().collect();
+        self.schema
+            .column_map()
+            .iter()
+            .filter(|(id, name)| column_ids.contains_key(*name) && !file_ids.contains(id))
+            .map(|(_, name)| Arc::clone(name))
+            .collect()
+    }
+
     /// Check if the column exists in the [`TableDefinition`]s schema
     pub fn column_exists(&self, column: &str) -> bool {
         self.influx_schema().find_index_of(column).is_some()
@@ -934,6 +1649,9 @@ pub struct TableSchema {
     schema: Schema,
     column_map: BiHashMap<ColumnId, Arc<str>>,
     next_column_id: ColumnId,
+    /// The names that columns had before they were renamed, which data written before the
+    /// rename still has them under
+    previous_names: BTreeMap<Arc<str>, ColumnId>,
 }
 
 impl TableSchema {
@@ -953,6 +1671,7 @@ impl TableSchema {
             schema,
             next_column_id: ColumnId::from(column_map.len() as u16),
             column_map,
+            previous_names: BTreeMap::new(),
         }
     }
 
@@ -960,11 +1679,13 @@ impl TableSchema {
         schema: Schema,
         column_map: BiHashMap<ColumnId, Arc<str>>,
         next_column_id: ColumnId,
+        previous_names: BTreeMap<Arc<str>, ColumnId>,
     ) -> Self {
         Self {
             schema,
             column_map,
             next_column_id,
+            previous_names,
         }
     }
 
@@ -990,6 +1711,17 @@ impl TableSchema {
         self.column_map.insert(id, column_name.into());
     }
 
+    pub(crate) fn previous_names(&self) -> &BTreeMap<Arc<str>, ColumnId> {
+        &self.previous_names
+    }
+
+    fn rename_column(&mut self, id: ColumnId, column_name: &str) {
+        if let Some(old_name) = self.column_map.get_by_left(&id) {
+            self.previous_names.insert(Arc::clone(old_name), id);
+        }
+        self.column_map.insert(id, column_name.into());
+    }
+
     pub fn series_key(&self) -> Option<Vec<&str>> {
         self.schema.series_key()
     }
@@ -1002,6 +1734,15 @@ impl TableSchema {
         self.column_map.get_by_right(&name).copied()
     }
 
+    /// The id of the column that has, or had before it was renamed, the given name. Columns
+    /// that currently have the name take precedence over those that used to have it.
+    pub fn column_id_for_name(&self, name: &str) -> Option<ColumnId> {
+        self.column_map
+            .get_by_right(name)
+            .or_else(|| self.previous_names.get(name))
+            .copied()
+    }
+
     pub fn id_to_name(&self, id: ColumnId) -> Option<Arc<str>> {
         self.column_map.get_by_left(&id).cloned()
     }
@@ -1034,6 +1775,10 @@ pub fn influx_column_type_from_field_value(fv: &FieldValue<'_>) -> InfluxColumnT
 
 #[cfg(test)]
 mod tests {
+    use influxdb3_wal::{
+        CoercionPolicyDefinition, JsonFieldMapping, JsonMappingDefinition, QuotasDefinition,
+        TableRename,
+    };
     use insta::assert_json_snapshot;
     use pretty_assertions::assert_eq;
     use test_helpers::assert_contains;
@@ -1058,6 +1803,9 @@ mod tests {
                 map.insert(TableId::from(2), "test_table_2".into());
                 map
             },
+            schema_policy: SchemaPolicy::default(),
+            retention_period_ns: None,
+            quotas: DatabaseQuotas::default(),
         };
         use InfluxColumnType::*;
         use InfluxFieldType::*;
@@ -1218,6 +1966,9 @@ mod tests {
             name: "test".into(),
             tables: BTreeMap::new(),
             table_map: BiHashMap::new(),
+            schema_policy: SchemaPolicy::default(),
+            retention_period_ns: None,
+            quotas: DatabaseQuotas::default(),
         };
         database.tables.insert(
             TableId::from(0),
@@ -1265,6 +2016,9 @@ mod tests {
                 map.insert(TableId::from(1), "test_table_1".into());
                 map
             },
+            schema_policy: SchemaPolicy::default(),
+            retention_period_ns: None,
+            quotas: DatabaseQuotas::default(),
         };
         use InfluxColumnType::*;
         use InfluxFieldType::*;
@@ -1316,6 +2070,9 @@ mod tests {
                 map.insert(TableId::from(0), "test".into());
                 map
             },
+            schema_policy: SchemaPolicy::default(),
+            retention_period_ns: None,
+            quotas: DatabaseQuotas::default(),
         };
         use InfluxColumnType::*;
         use InfluxFieldType::*;
@@ -1359,6 +2116,792 @@ mod tests {
         assert_eq!(catalog, deserialized);
     }
 
+    #[test]
+    fn delete_database_and_table() {
+        let catalog = Catalog::new("sample-host-id".into(), "instance-id".into());
+        let create_table =
+            |database_id: DbId, database_name: &str, table_id: TableId| CatalogBatch {
+                database_id,
+                database_name: database_name.into(),
+                time_ns: 0,
+                ops: vec![CatalogOp::CreateTable(influxdb3_wal::TableDefinition {
+                    database_id,
+                    database_name: database_name.into(),
+                    table_name: format!("table_{}", table_id.as_u32()).into(),
+                    table_id,
+                    field_definitions: vec![influxdb3_wal::FieldDefinition {
+                        name: "time".into(),
+                        data_type: influxdb3_wal::FieldDataType::Timestamp,
+                    }],
+                    key: None,
+                })],
+            };
+        let foo = DbId::from(0);
+        let bar = DbId::from(1);
+        catalog
+            .apply_catalog_batch(&create_table(foo, "foo", TableId::from(0)))
+            .unwrap();
+        catalog
+            .apply_catalog_batch(&create_table(foo, "foo", TableId::from(1)))
+            .unwrap();
+        catalog
+            .apply_catalog_batch(&create_table(bar, "bar", TableId::from(2)))
+            .unwrap();
+
+        // delete a table:
+        let delete_table = CatalogBatch {
+            database_id: foo,
+            database_name: "foo".into(),
+            time_ns: 0,
+            ops: vec![CatalogOp::DeleteTable(influxdb3_wal::TableDelete {
+                table_id: TableId::from(0),
+                table_name: "table_0".into(),
+            })],
+        };
+        catalog.apply_catalog_batch(&delete_table).unwrap();
+        let db = catalog.db_schema("foo").unwrap();
+        assert_eq!(db.table_names(), vec![Arc::<str>::from("table_1")]);
+        assert!(db.table_definition("table_0").is_none());
+        assert!(catalog.table_is_deleted(TableId::from(0)));
+
+        // delete a database:
+        let delete_db = CatalogBatch {
+            database_id: bar,
+            database_name: "bar".into(),
+            time_ns: 0,
+            ops: vec![CatalogOp::DeleteDatabase(influxdb3_wal::DatabaseDelete {
+                database_id: bar,
+                database_name: "bar".into(),
+            })],
+        };
+        catalog.apply_catalog_batch(&delete_db).unwrap();
+        assert!(catalog.db_schema("bar").is_none());
+        assert!(catalog.db_is_deleted(bar));
+        assert!(catalog.table_is_deleted(TableId::from(2)));
+        assert_eq!(catalog.db_names(), vec!["foo".to_string()]);
+
+        // replaying ops from before the deletes does not bring them back, or update the catalog:
+        let sequence = catalog.sequence_number();
+        catalog
+            .apply_catalog_batch(&create_table(foo, "foo", TableId::from(0)))
+            .unwrap();
+        catalog
+            .apply_catalog_batch(&create_table(bar, "bar", TableId::from(2)))
+            .unwrap();
+        catalog.apply_catalog_batch(&delete_table).unwrap();
+        catalog.apply_catalog_batch(&delete_db).unwrap();
+        assert_eq!(sequence, catalog.sequence_number());
+        assert!(catalog.db_schema("bar").is_none());
+        assert!(catalog
+            .db_schema("foo")
+            .unwrap()
+            .table_definition("table_0")
+            .is_none());
+
+        // the deleted ids survive serialization:
+        let serialized = serde_json::to_string(&catalog).unwrap();
+        let deserialized = Catalog::from_inner(serde_json::from_str(&serialized).unwrap());
+        assert_eq!(catalog, deserialized);
+        assert!(deserialized.db_is_deleted(bar));
+        assert!(deserialized.table_is_deleted(TableId::from(0)));
+    }
+
+    #[test]
+    fn create_database_and_table() {
+        let catalog = Catalog::new("sample-host-id".into(), "instance-id".into());
+
+        // a database can be created without any tables, but only once:
+        let create_db = catalog.create_database("foo", None, 0).unwrap();
+        assert_eq!(catalog.db_names(), vec!["foo".to_string()]);
+        assert!(catalog.db_schema("foo").unwrap().tables.is_empty());
+        assert!(matches!(
+            catalog.create_database("foo", None, 0),
+            Err(Error::DatabaseAlreadyExists { .. })
+        ));
+
+        // create a table in that database, and one that creates its database:
+        let create_cpu = catalog
+            .create_table(
+                "foo",
+                "cpu",
+                &["region", "host"],
+                &[("usage", InfluxFieldType::Float)],
+                0,
+            )
+            .unwrap();
+        assert_eq!(create_cpu.ops.len(), 1);
+        let create_mem = catalog
+            .create_table(
+                "bar",
+                "mem",
+                &["host"],
+                &[
+                    ("used", InfluxFieldType::Integer),
+                    ("swapped", InfluxFieldType::Boolean),
+                ],
+                0,
+            )
+            .unwrap();
+        assert_eq!(create_mem.ops.len(), 2);
+
+        let cpu = catalog
+            .db_schema("foo")
+            .unwrap()
+            .table_schema("cpu")
+            .unwrap();
+        assert_eq!(cpu.len(), 4);
+        for (column, expected) in [
+            ("host", InfluxColumnType::Tag),
+            ("region", InfluxColumnType::Tag),
+            ("usage", InfluxColumnType::Field(InfluxFieldType::Float)),
+            ("time", InfluxColumnType::Timestamp),
+        ] {
+            assert_eq!(cpu.field_type_by_name(column), Some(expected));
+        }
+        assert_eq!(
+            catalog
+                .db_schema("bar")
+                .unwrap()
+                .table_schema("mem")
+                .unwrap()
+                .field_type_by_name("used"),
+            Some(InfluxColumnType::Field(InfluxFieldType::Integer))
+        );
+
+        // tables cannot be created twice, or with a column defined more than once:
+        assert!(matches!(
+            catalog.create_table(
+                "foo",
+                "cpu",
+                &["host"],
+                &[("usage", InfluxFieldType::Float)],
+                0
+            ),
+            Err(Error::TableAlreadyExists { .. })
+        ));
+        assert!(matches!(
+            catalog.create_table(
+                "foo",
+                "disk",
+                &["host"],
+                &[("host", InfluxFieldType::Float)],
+                0
+            ),
+            Err(Error::DuplicateColumn { .. })
+        ));
+        assert!(matches!(
+            catalog.create_table(
+                "foo",
+                "disk",
+                &["host"],
+                &[("time", InfluxFieldType::Float)],
+                0
+            ),
+            Err(Error::DuplicateColumn { .. })
+        ));
+
+        // replaying the batches into a new catalog creates the same schema:
+        let replayed = Catalog::new("sample-host-id".into(), "instance-id".into());
+        for batch in [&create_db, &create_cpu, &create_mem] {
+            replayed.apply_catalog_batch(batch).unwrap();
+        }
+        assert_eq!(
+            catalog.db_schema("foo").unwrap(),
+            replayed.db_schema("foo").unwrap()
+        );
+        assert_eq!(
+            catalog.db_schema("bar").unwrap(),
+            replayed.db_schema("bar").unwrap()
+        );
+    }
+
+    #[test]
+    fn schema_policies() {
+        let catalog = Catalog::new("sample-host-id".into(), "instance-id".into());
+        let batch = catalog
+            .create_table(
+                "foo",
+                "cpu",
+                &["host"],
+                &[("usage", InfluxFieldType::Float)],
+                0,
+            )
+            .unwrap();
+        let db_id = batch.database_id;
+        let cpu_id = catalog
+            .db_schema("foo")
+            .unwrap()
+            .table_name_to_id("cpu")
+            .unwrap();
+        let set_policy = |table_id: Option<TableId>, policy: SchemaPolicy| CatalogBatch {
+            database_id: db_id,
+            database_name: "foo".into(),
+            time_ns: 0,
+            ops: vec![CatalogOp::SetSchemaPolicy(SchemaPolicyDefinition {
+                table_id,
+                table_name: table_id.map(|_| "cpu".into()),
+                policy,
+            })],
+        };
+
+        // tables use the default policy of their database, unless they have their own:
+        let policy_of_cpu = || {
+            let db = catalog.db_schema("foo").unwrap();
+            db.table_schema_policy(db.table_definition("cpu").unwrap())
+        };
+        assert_eq!(SchemaPolicy::Open, policy_of_cpu());
+        catalog
+            .apply_catalog_batch(&set_policy(None, SchemaPolicy::Locked))
+            .unwrap();
+        assert_eq!(SchemaPolicy::Locked, policy_of_cpu());
+        catalog
+            .apply_catalog_batch(&set_policy(Some(cpu_id), SchemaPolicy::DropUnknown))
+            .unwrap();
+        assert_eq!(SchemaPolicy::DropUnknown, policy_of_cpu());
+
+        // setting the same policy again does not update the catalog:
+        let sequence = catalog.sequence_number();
+        catalog
+            .apply_catalog_batch(&set_policy(Some(cpu_id), SchemaPolicy::DropUnknown))
+            .unwrap();
+        catalog
+            .apply_catalog_batch(&set_policy(None, SchemaPolicy::Locked))
+            .unwrap();
+        assert_eq!(sequence, catalog.sequence_number());
+
+        // the policy of a table that does not exist cannot be set:
+        assert!(matches!(
+            catalog.apply_catalog_batch(&set_policy(Some(TableId::new()), SchemaPolicy::Open)),
+            Err(Error::TableNotFound { .. })
+        ));
+
+        // the policies survive serialization:
+        let serialized = serde_json::to_string(&catalog).unwrap();
+        let deserialized = Catalog::from_inner(serde_json::from_str(&serialized).unwrap());
+        assert_eq!(catalog, deserialized);
+        let db = deserialized.db_schema("foo").unwrap();
+        assert_eq!(SchemaPolicy::Locked, db.schema_policy);
+        assert_eq!(
+            Some(SchemaPolicy::DropUnknown),
+            db.table_definition("cpu").unwrap().schema_policy
+        );
+    }
+
+    #[test]
+    fn retention_periods() {
+        let catalog = Catalog::new("sample-host-id".into(), "instance-id".into());
+        let batch = catalog
+            .create_database("foo", Some(3_600_000_000_000), 0)
+            .unwrap();
+        assert_eq!(2, batch.ops.len());
+        let db = catalog.db_schema("foo").unwrap();
+        assert_eq!(Some(3_600_000_000_000), db.retention_period_ns);
+        assert_eq!(
+            Some(6_400_000_000_000),
+            db.retention_cutoff_ns(10_000_000_000_000)
+        );
+
+        // the retention period can be changed, or removed:
+        let set_retention = |retention_period_ns: Option<u64>| CatalogBatch {
+            database_id: batch.database_id,
+            database_name: "foo".into(),
+            time_ns: 0,
+            ops: vec![CatalogOp::SetRetentionPeriod(RetentionPeriodDefinition {
+                retention_period_ns,
+            })],
+        };
+        catalog
+            .apply_catalog_batch(&set_retention(Some(60_000_000_000)))
+            .unwrap();
+        assert_eq!(
+            Some(60_000_000_000),
+            catalog.db_schema("foo").unwrap().retention_period_ns
+        );
+        let sequence = catalog.sequence_number();
+        catalog
+            .apply_catalog_batch(&set_retention(Some(60_000_000_000)))
+            .unwrap();
+        assert_eq!(sequence, catalog.sequence_number());
+
+        // the retention period survives serialization:
+        let serialized = serde_json::to_string(&catalog).unwrap();
+        let deserialized = Catalog::from_inner(serde_json::from_str(&serialized).unwrap());
+        assert_eq!(catalog, deserialized);
+
+        catalog.apply_catalog_batch(&set_retention(None)).unwrap();
+        let db = catalog.db_schema("foo").unwrap();
+        assert_eq!(None, db.retention_period_ns);
+        assert_eq!(None, db.retention_cutoff_ns(10_000_000_000_000));
+    }
+
+    #[test]
+    fn coercion_policies() {
+        let catalog = Catalog::new("sample-host-id".into(), "instance-id".into());
+        let batch = catalog
+            .create_table(
+                "foo",
+                "cpu",
+                &["host"],
+                &[("usage", InfluxFieldType::Float)],
+                0,
+            )
+            .unwrap();
+        let cpu_id = catalog
+            .db_schema("foo")
+            .unwrap()
+            .table_name_to_id("cpu")
+            .unwrap();
+        let set_policy = |table_id: TableId, policy: CoercionPolicy| CatalogBatch {
+            database_id: batch.database_id,
+            database_name: "foo".into(),
+            time_ns: 0,
+            ops: vec![CatalogOp::SetCoercionPolicy(CoercionPolicyDefinition {
+                table_id,
+                table_name: "cpu".into(),
+                policy,
+            })],
+        };
+        let policy = CoercionPolicy {
+            integer_to_float: true,
+            uinteger_to_integer: false,
+            any_to_string: true,
+        };
+
+        // tables do not coerce any values by default:
+        let policy_of_cpu = || {
+            catalog
+                .db_schema("foo")
+                .unwrap()
+                .table_definition("cpu")
+                .unwrap()
+                .coercion_policy
+        };
+        assert!(policy_of_cpu().is_strict());
+        catalog
+            .apply_catalog_batch(&set_policy(cpu_id, policy))
+            .unwrap();
+        assert_eq!(policy, policy_of_cpu());
+
+        // setting the same policy again does not update the catalog:
+        let sequence = catalog.sequence_number();
+        catalog
+            .apply_catalog_batch(&set_policy(cpu_id, policy))
+            .unwrap();
+        assert_eq!(sequence, catalog.sequence_number());
+
+        // the policy of a table that does not exist cannot be set:
+        assert!(matches!(
+            catalog.apply_catalog_batch(&set_policy(TableId::new(), policy)),
+            Err(Error::TableNotFound { .. })
+        ));
+
+        // the policy survives serialization:
+        let serialized = serde_json::to_string(&catalog).unwrap();
+        let deserialized = Catalog::from_inner(serde_json::from_str(&serialized).unwrap());
+        assert_eq!(catalog, deserialized);
+    }
+
+    #[test]
+    fn json_mappings() {
+        let catalog = Catalog::new("sample-host-id".into(), "instance-id".into());
+        let batch = catalog
+            .create_table(
+                "foo",
+                "cpu",
+                &["host"],
+                &[("usage", InfluxFieldType::Float)],
+                0,
+            )
+            .unwrap();
+        let cpu_id = catalog
+            .db_schema("foo")
+            .unwrap()
+            .table_name_to_id("cpu")
+            .unwrap();
+        let set_mapping = |mapping: Option<JsonMapping>| CatalogBatch {
+            database_id: batch.database_id,
+            database_name: "foo".into(),
+            time_ns: 0,
+            ops: vec![CatalogOp::SetJsonMapping(JsonMappingDefinition {
+                table_id: cpu_id,
+                table_name: "cpu".into(),
+                mapping,
+            })],
+        };
+        let mapping = JsonMapping {
+            tags: [("host".to_string(), "meta.host".to_string())]
+                .into_iter()
+                .collect(),
+            fields: [(
+                "usage".to_string(),
+                JsonFieldMapping {
+                    path: "usage".to_string(),
+                    field_type: None,
+                },
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let mapping_of_cpu = || {
+            catalog
+                .db_schema("foo")
+                .unwrap()
+                .table_definition("cpu")
+                .unwrap()
+                .json_mapping
+                .clone()
+        };
+
+        assert_eq!(None, mapping_of_cpu());
+        catalog
+            .apply_catalog_batch(&set_mapping(Some(mapping.clone())))
+            .unwrap();
+        assert_eq!(Some(mapping), mapping_of_cpu());
+
+        // the mapping survives serialization:
+        let serialized = serde_json::to_string(&catalog).unwrap();
+        let deserialized = Catalog::from_inner(serde_json::from_str(&serialized).unwrap());
+        assert_eq!(catalog, deserialized);
+
+        catalog.apply_catalog_batch(&set_mapping(None)).unwrap();
+        assert_eq!(None, mapping_of_cpu());
+    }
+
+    #[test]
+    fn database_quotas() {
+        let catalog = Catalog::new("sample-host-id".into(), "instance-id".into());
+        let batch = catalog
+            .create_table(
+                "foo",
+                "cpu",
+                &["host"],
+                &[("usage", InfluxFieldType::Float)],
+                0,
+            )
+            .unwrap();
+        let cpu_id = catalog
+            .db_schema("foo")
+            .unwrap()
+            .table_name_to_id("cpu")
+            .unwrap();
+        let quotas = DatabaseQuotas {
+            max_tables: Some(1),
+            max_columns: Some(4),
+            ..Default::default()
+        };
+        catalog
+            .apply_catalog_batch(&CatalogBatch {
+                database_id: batch.database_id,
+                database_name: "foo".into(),
+                time_ns: 0,
+                ops: vec![CatalogOp::SetQuotas(QuotasDefinition { quotas })],
+            })
+            .unwrap();
+        let db = catalog.db_schema("foo").unwrap();
+        assert_eq!(quotas, db.quotas);
+        assert_eq!(1, db.table_count());
+        assert_eq!(3, db.column_count());
+
+        // a second table would exceed the table quota:
+        let err = catalog
+            .create_table(
+                "foo",
+                "mem",
+                &["host"],
+                &[("used", InfluxFieldType::Integer)],
+                0,
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::QuotaExceeded {
+                quota: Quota::Tables,
+                limit: 1,
+                ..
+            }
+        ));
+
+        // one more column fits, but two do not:
+        let add_fields = |names: &[&str]| CatalogBatch {
+            database_id: batch.database_id,
+            database_name: "foo".into(),
+            time_ns: 0,
+            ops: vec![CatalogOp::AddFields(FieldAdditions {
+                database_name: "foo".into(),
+                database_id: batch.database_id,
+                table_name: "cpu".into(),
+                table_id: cpu_id,
+                field_definitions: names
+                    .iter()
+                    .map(|name| FieldDefinition {
+                        name: (*name).into(),
+                        data_type: FieldDataType::Float,
+                    })
+                    .collect(),
+            })],
+        };
+        let err = catalog
+            .apply_catalog_batch(&add_fields(&["system", "user"]))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::QuotaExceeded {
+                quota: Quota::Columns,
+                limit: 4,
+                ..
+            }
+        ));
+        assert_eq!(3, catalog.db_schema("foo").unwrap().column_count());
+        catalog
+            .apply_catalog_batch(&add_fields(&["system"]))
+            .unwrap();
+        assert_eq!(4, catalog.db_schema("foo").unwrap().column_count());
+
+        // the quotas survive serialization:
+        let serialized = serde_json::to_string(&catalog).unwrap();
+        let deserialized = Catalog::from_inner(serde_json::from_str(&serialized).unwrap());
+        assert_eq!(catalog, deserialized);
+    }
+
+    #[test]
+    fn rename_tables_and_columns() {
+        let catalog = Catalog::new("sample-host-id".into(), "instance-id".into());
+        catalog
+            .create_table(
+                "foo",
+                "cpu",
+                &["host"],
+                &[("usage", InfluxFieldType::Float)],
+                0,
+            )
+            .unwrap();
+        let batch = catalog
+            .create_table(
+                "foo",
+                "mem",
+                &["host"],
+                &[("free", InfluxFieldType::Integer)],
+                0,
+            )
+            .unwrap();
+        let db_id = batch.database_id;
+        let db = catalog.db_schema("foo").unwrap();
+        let cpu = db.table_definition("cpu").unwrap().clone();
+        let usage_id = cpu.schema.name_to_id("usage".into()).unwrap();
+        let time_id = cpu.schema.name_to_id("time".into()).unwrap();
+        let batch_of = |op: CatalogOp| CatalogBatch {
+            database_id: db_id,
+            database_name: "foo".into(),
+            time_ns: 0,
+            ops: vec![op],
+        };
+        let rename_table = |new_name: &str| {
+            batch_of(CatalogOp::RenameTable(TableRename {
+                table_id: cpu.table_id,
+                old_name: "cpu".into(),
+                new_name: new_name.into(),
+            }))
+        };
+        let rename_column = |column_id: ColumnId, old_name: &str, new_name: &str| {
+            batch_of(CatalogOp::RenameColumn(ColumnRename {
+                table_id: cpu.table_id,
+                table_name: "cpu".into(),
+                column_id,
+                old_name: old_name.into(),
+                new_name: new_name.into(),
+            }))
+        };
+
+        // names that are taken cannot be used:
+        assert!(matches!(
+            catalog.apply_catalog_batch(&rename_table("mem")),
+            Err(Error::TableAlreadyExists { .. })
+        ));
+        assert!(matches!(
+            catalog.apply_catalog_batch(&rename_column(usage_id, "usage", "host")),
+            Err(Error::ColumnAlreadyExists { .. })
+        ));
+        assert!(matches!(
+            catalog.apply_catalog_batch(&rename_column(time_id, "time", "timestamp")),
+            Err(Error::CannotRenameColumn { .. })
+        ));
+
+        catalog
+            .apply_catalog_batch(&rename_table("cpu_metrics"))
+            .unwrap();
+        catalog
+            .apply_catalog_batch(&rename_column(usage_id, "usage", "cpu_usage"))
+            .unwrap();
+        let db = catalog.db_schema("foo").unwrap();
+        assert!(db.table_definition("cpu").is_none());
+        let table = db.table_definition("cpu_metrics").unwrap();
+        assert_eq!(cpu.table_id, table.table_id);
+        assert_eq!(Some(usage_id), table.schema.name_to_id("cpu_usage".into()));
+        assert_eq!(
+            Some(InfluxColumnType::Field(InfluxFieldType::Float)),
+            table.field_type_by_name("cpu_usage")
+        );
+        assert!(!table.column_exists("usage"));
+
+        // data written under the old name is found by the id of the column:
+        assert_eq!(Some(usage_id), table.schema.column_id_for_name("usage"));
+        let column_ids = [("usage".into(), usage_id), ("time".into(), time_id)]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            vec![(Arc::<str>::from("usage"), Arc::<str>::from("cpu_usage"))],
+            table.renamed_columns(&column_ids)
+        );
+        assert!(table.reused_column_names(&column_ids).is_empty());
+
+        // applying the renames again, as when the wal is replayed, does not update the catalog:
+        let sequence = catalog.sequence_number();
+        catalog
+            .apply_catalog_batch(&rename_table("cpu_metrics"))
+            .unwrap();
+        catalog
+            .apply_catalog_batch(&rename_column(usage_id, "usage", "cpu_usage"))
+            .unwrap();
+        assert_eq!(sequence, catalog.sequence_number());
+
+        // the renames survive serialization:
+        let serialized = serde_json::to_string(&catalog).unwrap();
+        let deserialized = Catalog::from_inner(serde_json::from_str(&serialized).unwrap());
+        assert_eq!(catalog, deserialized);
+
+        // a new column that takes the old name of the renamed column is not in that data:
+        catalog
+            .apply_catalog_batch(&batch_of(CatalogOp::AddFields(FieldAdditions {
+                database_name: "foo".into(),
+                database_id: db_id,
+                table_name: "cpu_metrics".into(),
+                table_id: cpu.table_id,
+                field_definitions: vec![FieldDefinition {
+                    name: "usage".into(),
+                    data_type: FieldDataType::Float,
+                }],
+            })))
+            .unwrap();
+        let db = catalog.db_schema("foo").unwrap();
+        let table = db.table_definition("cpu_metrics").unwrap();
+        assert_ne!(Some(usage_id), table.schema.name_to_id("usage".into()));
+        assert_eq!(
+            vec![(Arc::<str>::from("usage"), Arc::<str>::from("cpu_usage"))],
+            table.renamed_columns(&column_ids)
+        );
+        assert_eq!(
+            vec![Arc::<str>::from("usage")],
+            table.reused_column_names(&column_ids)
+        );
+    }
+
+    #[test]
+    fn schema_history() {
+        let catalog = Catalog::new("sample-host-id".into(), "instance-id".into());
+        let create_cpu = catalog
+            .create_table(
+                "foo",
+                "cpu",
+                &["host"],
+                &[("usage", InfluxFieldType::Float)],
+                10,
+            )
+            .unwrap();
+        catalog.create_database("bar", None, 20).unwrap();
+        let foo_id = create_cpu.database_id;
+        let CatalogOp::CreateTable(cpu) = &create_cpu.ops[1] else {
+            panic!("expected the table to be created after the database");
+        };
+        let add_fields = CatalogBatch {
+            database_id: foo_id,
+            database_name: "foo".into(),
+            time_ns: 30,
+            ops: vec![CatalogOp::AddFields(FieldAdditions {
+                database_name: "foo".into(),
+                database_id: foo_id,
+                table_name: "cpu".into(),
+                table_id: cpu.table_id,
+                field_definitions: vec![FieldDefinition {
+                    name: "system".into(),
+                    data_type: FieldDataType::Float,
+                }],
+            })],
+        };
+        catalog.apply_catalog_batch(&add_fields).unwrap();
+        // a batch that does not change the catalog, as when the wal is replayed, is not recorded:
+        catalog
+            .apply_catalog_batch(&CatalogBatch {
+                time_ns: 40,
+                ..add_fields.clone()
+            })
+            .unwrap();
+
+        let history = catalog.schema_history(foo_id);
+        assert_eq!(
+            vec![(10, create_cpu.ops.clone()), (30, add_fields.ops.clone())],
+            history
+                .iter()
+                .map(|entry| (entry.time_ns, entry.ops.clone()))
+                .collect::<Vec<_>>()
+        );
+        assert!(history[0].sequence < history[1].sequence);
+        assert_eq!(catalog.sequence_number(), history[1].sequence);
+        assert_eq!(
+            vec![
+                "create database foo",
+                "create table cpu with 3 columns",
+                "add columns to table cpu: system"
+            ],
+            history
+                .iter()
+                .flat_map(|entry| entry.ops.iter().map(ToString::to_string))
+                .collect::<Vec<_>>()
+        );
+        let bar_id = catalog.db_name_to_id("bar").unwrap();
+        assert_eq!(1, catalog.schema_history(bar_id).len());
+
+        // the history survives serialization:
+        let serialized = serde_json::to_string(&catalog).unwrap();
+        let deserialized = Catalog::from_inner(serde_json::from_str(&serialized).unwrap());
+        assert_eq!(catalog, deserialized);
+        assert_eq!(history, deserialized.schema_history(foo_id));
+
+        // only the most recent entries of a database are kept:
+        for i in 0..Catalog::SCHEMA_HISTORY_PER_DB_LIMIT as u64 {
+            catalog
+                .apply_catalog_batch(&CatalogBatch {
+                    database_id: foo_id,
+                    database_name: "foo".into(),
+                    time_ns: 50 + i as i64,
+                    ops: vec![CatalogOp::SetRetentionPeriod(RetentionPeriodDefinition {
+                        retention_period_ns: Some(i + 1),
+                    })],
+                })
+                .unwrap();
+        }
+        let history = catalog.schema_history(foo_id);
+        assert_eq!(Catalog::SCHEMA_HISTORY_PER_DB_LIMIT, history.len());
+        assert_eq!(50, history[0].time_ns);
+        assert_eq!(catalog.sequence_number(), history.last().unwrap().sequence);
+        assert_eq!(1, catalog.schema_history(bar_id).len());
+
+        // and the entries of a deleted database are dropped:
+        catalog
+            .apply_catalog_batch(&CatalogBatch {
+                database_id: foo_id,
+                database_name: "foo".into(),
+                time_ns: 2000,
+                ops: vec![CatalogOp::DeleteDatabase(influxdb3_wal::DatabaseDelete {
+                    database_id: foo_id,
+                    database_name: "foo".into(),
+                })],
+            })
+            .unwrap();
+        assert!(catalog.schema_history(foo_id).is_empty());
+        assert_eq!(1, catalog.schema_history(bar_id).len());
+    }
+
     #[test]
     fn catalog_instance_and_host_ids() {
         let host_id = Arc::from("sample-host-id");
@@ -0,0 +1,739 @@
+//! A portable document of the databases, tables and last caches in a catalog, which can be
+//! exported from one host and imported into another to give it the same schema.
+//!
+//! Everything in the document is identified by name, rather than by the ids the catalog assigns,
+//! as those differ between hosts. Importing a document plans the catalog ops that would create
+//! whatever is missing from the catalog, along with any conflicts with what already exists, so
+//! that the plan can be shown as a diff before it is applied.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use influxdb3_id::{DbId, TableId};
+use influxdb3_wal::{
+    CatalogBatch, CatalogOp, DatabaseDefinition, FieldAdditions, FieldDataType, FieldDefinition,
+    LastCacheDefinition, LastCacheSize, LastCacheValueColumnsDef,
+};
+use schema::{InfluxColumnType, InfluxFieldType};
+use serde::{Deserialize, Serialize};
+
+use crate::catalog::{Catalog, DatabaseSchema, Error, InnerCatalog, Result, TableDefinition};
+use crate::DatabaseSchemaProvider;
+
+/// The databases of a catalog, ordered by name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogExport {
+    pub databases: Vec<DatabaseExport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatabaseExport {
+    pub name: String,
+    #[serde(default)]
+    pub tables: Vec<TableExport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableExport {
+    pub name: String,
+    /// The columns that make up the series key of the table, for tables that have one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series_key: Option<Vec<String>>,
+    pub columns: Vec<ColumnExport>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub last_caches: Vec<LastCacheExport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnExport {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: ColumnType,
+}
+
+/// The type of a column, named the same as the line protocol field value types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnType {
+    Tag,
+    Time,
+    String,
+    Integer,
+    Uinteger,
+    Float,
+    Boolean,
+}
+
+impl From<InfluxColumnType> for ColumnType {
+    fn from(column_type: InfluxColumnType) -> Self {
+        match column_type {
+            InfluxColumnType::Tag => Self::Tag,
+            InfluxColumnType::Timestamp => Self::Time,
+            InfluxColumnType::Field(InfluxFieldType::String) => Self::String,
+            InfluxColumnType::Field(InfluxFieldType::Integer) => Self::Integer,
+            InfluxColumnType::Field(InfluxFieldType::UInteger) => Self::Uinteger,
+            InfluxColumnType::Field(InfluxFieldType::Float) => Self::Float,
+            InfluxColumnType::Field(InfluxFieldType::Boolean) => Self::Boolean,
+        }
+    }
+}
+
+impl From<ColumnType> for InfluxColumnType {
+    fn from(column_type: ColumnType) -> Self {
+        match column_type {
+            ColumnType::Tag => Self::Tag,
+            ColumnType::Time => Self::Timestamp,
+            ColumnType::String => Self::Field(InfluxFieldType::String),
+            ColumnType::Integer => Self::Field(InfluxFieldType::Integer),
+            ColumnType::Uinteger => Self::Field(InfluxFieldType::UInteger),
+            ColumnType::Float => Self::Field(InfluxFieldType::Float),
+            ColumnType::Boolean => Self::Field(InfluxFieldType::Boolean),
+        }
+    }
+}
+
+/// A [`LastCacheDefinition`] without the id of its table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastCacheExport {
+    pub name: String,
+    pub key_columns: Vec<String>,
+    pub value_columns: LastCacheValueColumnsDef,
+    pub count: usize,
+    /// The time-to-live (TTL) in seconds for entries in the cache
+    pub ttl: u64,
+}
+
+impl From<&DatabaseSchema> for DatabaseExport {
+    fn from(db_schema: &DatabaseSchema) -> Self {
+        let mut tables = db_schema
+            .tables()
+            .map(TableExport::from)
+            .collect::<Vec<_>>();
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
+        Self {
+            name: db_schema.name.to_string(),
+            tables,
+        }
+    }
+}
+
+impl From<&TableDefinition> for TableExport {
+    fn from(table: &TableDefinition) -> Self {
+        Self {
+            name: table.table_name.to_string(),
+            series_key: table
+                .schema
+                .series_key()
+                .map(|key| key.into_iter().map(String::from).collect()),
+            columns: table
+                .influx_schema()
+                .iter()
+                .map(|(column_type, field)| ColumnExport {
+                    name: field.name().to_string(),
+                    column_type: column_type.into(),
+                })
+                .collect(),
+            last_caches: table
+                .last_caches()
+                .map(|(_, cache)| LastCacheExport {
+                    name: cache.name.clone(),
+                    key_columns: cache.key_columns.clone(),
+                    value_columns: cache.value_columns.clone(),
+                    count: cache.count.into(),
+                    ttl: cache.ttl,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The catalog batches that would create what is missing from a catalog to match a
+/// [`CatalogExport`], along with the conflicts between the two that stop parts of the export from
+/// being imported
+#[derive(Debug, Default)]
+pub struct ImportPlan {
+    pub batches: Vec<CatalogBatch>,
+    pub conflicts: Vec<ImportConflict>,
+}
+
+impl ImportPlan {
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty()
+    }
+
+    /// Describe the changes and conflicts of the plan
+    pub fn diff(&self, applied: bool) -> ImportDiff {
+        let changes = self
+            .batches
+            .iter()
+            .flat_map(|batch| {
+                batch.ops.iter().map(|op| ImportChange {
+                    database: batch.database_name.to_string(),
+                    table: op.table_name().map(String::from),
+                    op: op.name().to_string(),
+                    description: op.to_string(),
+                })
+            })
+            .collect();
+        ImportDiff {
+            changes,
+            conflicts: self.conflicts.clone(),
+            applied,
+        }
+    }
+}
+
+/// The changes that an import makes, or would make on a dry run, to a catalog
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportDiff {
+    pub changes: Vec<ImportChange>,
+    pub conflicts: Vec<ImportConflict>,
+    /// Whether the changes were applied, which they are not on a dry run, or if there are any
+    /// conflicts
+    pub applied: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportChange {
+    pub database: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table: Option<String>,
+    pub op: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportConflict {
+    pub database: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table: Option<String>,
+    pub description: String,
+}
+
+impl Catalog {
+    /// Export the databases in the catalog, along with their tables and last caches
+    pub fn export(&self) -> CatalogExport {
+        let mut databases = self
+            .list_db_schema()
+            .iter()
+            .map(|db_schema| DatabaseExport::from(db_schema.as_ref()))
+            .collect::<Vec<_>>();
+        databases.sort_by(|a, b| a.name.cmp(&b.name));
+        CatalogExport { databases }
+    }
+
+    /// Plan the import of the export into this catalog. Databases, tables, columns and last caches
+    /// that are in the export but not the catalog are created, and anything in the catalog that
+    /// is not in the export is left as it is. A table whose series key or column types differ
+    /// from those in the catalog is a conflict, as is a last cache with a different definition,
+    /// or a database or table that would exceed the limits of the catalog.
+    ///
+    /// Nothing is changed, so the databases and tables that would be created are given the ids
+    /// that they would get if the import were applied now.
+    pub fn plan_import(&self, export: &CatalogExport, time_ns: i64) -> ImportPlan {
+        plan_import(
+            &self.inner().read(),
+            export,
+            time_ns,
+            &mut ImportIds::placeholders(),
+        )
+    }
+
+    /// Plan the import of the export into this catalog, as [`Catalog::plan_import`] does, and
+    /// apply the batches of the plan if it has no conflicts. The plan is made and applied while
+    /// the catalog is locked, so the batches that are returned are exactly those that were
+    /// applied, and must be written to the WAL. Either all of them are applied or none are.
+    pub fn import(&self, export: &CatalogExport, time_ns: i64) -> Result<ImportPlan> {
+        let mut inner = self.inner().write();
+        let plan = plan_import(&inner, export, time_ns, &mut ImportIds::new_ids());
+        if plan.has_conflicts() || plan.batches.is_empty() {
+            return Ok(plan);
+        }
+        let mut updated = inner.clone();
+        for catalog_batch in &plan.batches {
+            updated.apply_catalog_batch(catalog_batch)?;
+        }
+        *inner = updated;
+        Ok(plan)
+    }
+}
+
+/// Assigns the ids of the databases and tables that an import creates
+#[derive(Debug)]
+struct ImportIds {
+    /// Whether the ids are placeholders for a plan that is not applied, which are the ids that
+    /// would be assigned next, without using them up
+    placeholders: bool,
+    db_ids: u32,
+    table_ids: u32,
+}
+
+impl ImportIds {
+    fn new_ids() -> Self {
+        Self {
+            placeholders: false,
+            db_ids: 0,
+            table_ids: 0,
+        }
+    }
+
+    fn placeholders() -> Self {
+        Self {
+            placeholders: true,
+            ..Self::new_ids()
+        }
+    }
+
+    fn db_id(&mut self) -> DbId {
+        if !self.placeholders {
+            return DbId::new();
+        }
+        self.db_ids += 1;
+        DbId::from(DbId::next_id().as_u32() + self.db_ids - 1)
+    }
+
+    fn table_id(&mut self) -> TableId {
+        if !self.placeholders {
+            return TableId::new();
+        }
+        self.table_ids += 1;
+        TableId::from(TableId::next_id().as_u32() + self.table_ids - 1)
+    }
+}
+
+/// Plan the import of the export into the catalog, giving what it creates ids from `ids`
+fn plan_import(
+    catalog: &InnerCatalog,
+    export: &CatalogExport,
+    time_ns: i64,
+    ids: &mut ImportIds,
+) -> ImportPlan {
+    let mut plan = ImportPlan::default();
+    let mut db_count = catalog.db_count();
+    let mut table_count = catalog.table_count();
+    for database in &export.databases {
+        let database_name: Arc<str> = database.name.as_str().into();
+        let (mut db_schema, mut ops) = match catalog.db_schema(&database.name) {
+            Some(db_schema) => (db_schema.as_ref().clone(), vec![]),
+            None if db_count >= Catalog::NUM_DBS_LIMIT => {
+                plan.conflicts.push(ImportConflict {
+                    database: database.name.clone(),
+                    table: None,
+                    description: Error::TooManyDbs.to_string(),
+                });
+                continue;
+            }
+            None => {
+                db_count += 1;
+                let database_id = ids.db_id();
+                (
+                    DatabaseSchema::new(database_id, Arc::clone(&database_name)),
+                    vec![CatalogOp::CreateDatabase(DatabaseDefinition {
+                        database_id,
+                        database_name: Arc::clone(&database_name),
+                    })],
+                )
+            }
+        };
+
+        for table in &database.tables {
+            // the ops of each table are applied to a copy of the schema as they are planned,
+            // so that those of the tables that follow are checked against them
+            let creates_table = db_schema.table_definition(table.name.as_str()).is_none();
+            let result = if creates_table && table_count >= Catalog::NUM_TABLES_LIMIT {
+                Err(Error::TooManyTables)
+            } else {
+                import_table_ops(&db_schema, table, ids)
+            };
+            let result = result.and_then(|table_ops| {
+                let batch = CatalogBatch {
+                    database_id: db_schema.id,
+                    database_name: Arc::clone(&database_name),
+                    time_ns,
+                    ops: table_ops,
+                };
+                let new_schema = db_schema.new_if_updated_from_batch(&batch)?;
+                Ok((new_schema, batch.ops))
+            });
+            match result {
+                Ok((new_schema, table_ops)) => {
+                    if let Some(new_schema) = new_schema {
+                        db_schema = new_schema;
+                    }
+                    if creates_table {
+                        table_count += 1;
+                    }
+                    ops.extend(table_ops);
+                }
+                Err(e) => plan.conflicts.push(ImportConflict {
+                    database: database.name.clone(),
+                    table: Some(table.name.clone()),
+                    description: e.to_string(),
+                }),
+            }
+        }
+
+        if !ops.is_empty() {
+            plan.batches.push(CatalogBatch {
+                database_id: db_schema.id,
+                database_name,
+                time_ns,
+                ops,
+            });
+        }
+    }
+    plan
+}
+
+/// The ops that create the table, or the columns and last caches that it is missing
+fn import_table_ops(
+    db_schema: &DatabaseSchema,
+    table: &TableExport,
+    ids: &mut ImportIds,
+) -> Result<Vec<CatalogOp>> {
+    let table_name: Arc<str> = table.name.as_str().into();
+    let mut columns: BTreeMap<&str, InfluxColumnType> = BTreeMap::new();
+    for column in &table.columns {
+        if columns
+            .insert(column.name.as_str(), column.column_type.into())
+            .is_some()
+        {
+            return Err(Error::DuplicateColumn {
+                table_name: table.name.clone(),
+                column_name: column.name.clone(),
+            });
+        }
+    }
+    if columns.len() > Catalog::NUM_COLUMNS_PER_TABLE_LIMIT {
+        return Err(Error::TooManyColumns);
+    }
+
+    let existing = db_schema.table_definition(Arc::clone(&table_name));
+    let table_id = match existing {
+        Some(existing) => existing.table_id,
+        None => ids.table_id(),
+    };
+    let definition = influxdb3_wal::TableDefinition {
+        database_id: db_schema.id,
+        database_name: Arc::clone(&db_schema.name),
+        table_name: Arc::clone(&table_name),
+        table_id,
+        field_definitions: columns
+            .iter()
+            .map(|(name, column_type)| FieldDefinition {
+                name: (*name).into(),
+                data_type: FieldDataType::from(column_type),
+            })
+            .collect(),
+        key: table.series_key.clone(),
+    };
+
+    let mut ops = vec![];
+    match existing {
+        None => ops.push(CatalogOp::CreateTable(definition)),
+        Some(existing) => {
+            // checks the series key and column types match those of the existing table:
+            existing.new_if_definition_adds_new_fields(&definition)?;
+            let new_fields = definition
+                .field_definitions
+                .into_iter()
+                .filter(|f| !existing.column_exists(&f.name))
+                .collect::<Vec<_>>();
+            if !new_fields.is_empty() {
+                ops.push(CatalogOp::AddFields(FieldAdditions {
+                    database_name: Arc::clone(&db_schema.name),
+                    database_id: db_schema.id,
+                    table_name: Arc::clone(&table_name),
+                    table_id,
+                    field_definitions: new_fields,
+                }));
+            }
+        }
+    }
+
+    for cache in &table.last_caches {
+        let value_columns = match &cache.value_columns {
+            LastCacheValueColumnsDef::Explicit { columns } => columns.as_slice(),
+            LastCacheValueColumnsDef::AllNonKeyColumns => &[],
+        };
+        for column in cache.key_columns.iter().chain(value_columns) {
+            let column_exists = columns.contains_key(column.as_str())
+                || existing.is_some_and(|t| t.column_exists(column));
+            if !column_exists {
+                return Err(Error::ColumnNotFound {
+                    table_name: table.name.clone(),
+                    column_name: column.clone(),
+                });
+            }
+        }
+        let definition = LastCacheDefinition {
+            table_id,
+            table: table.name.clone(),
+            name: cache.name.clone(),
+            key_columns: cache.key_columns.clone(),
+            value_columns: cache.value_columns.clone(),
+            count: LastCacheSize::new(cache.count).map_err(|source| Error::InvalidLastCache {
+                table_name: table.name.clone(),
+                cache_name: cache.name.clone(),
+                source,
+            })?,
+            ttl: cache.ttl,
+        };
+        let existing_cache = existing
+            .and_then(|t| t.last_caches().find(|(name, _)| **name == cache.name))
+            .map(|(_, existing_cache)| existing_cache);
+        match existing_cache {
+            Some(existing_cache) if *existing_cache == definition => (),
+            Some(_) => {
+                return Err(Error::LastCacheMismatch {
+                    table_name: table.name.clone(),
+                    cache_name: cache.name.clone(),
+                })
+            }
+            None => ops.push(CatalogOp::CreateLastCache(definition)),
+        }
+    }
+
+    Ok(ops)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn export_and_import() {
+        let source = Catalog::new("source".into(), "instance".into());
+        source
+            .create_table(
+                "foo",
+                "cpu",
+                &["region", "host"],
+                &[
+                    ("usage", InfluxFieldType::Float),
+                    ("count", InfluxFieldType::UInteger),
+                ],
+                0,
+            )
+            .unwrap();
+        let db_id = source.db_name_to_id("foo").unwrap();
+        let cpu_id = source
+            .db_schema("foo")
+            .unwrap()
+            .table_name_to_id("cpu")
+            .unwrap();
+        source
+            .apply_catalog_batch(&CatalogBatch {
+                database_id: db_id,
+                database_name: "foo".into(),
+                time_ns: 0,
+                ops: vec![CatalogOp::CreateLastCache(
+                    LastCacheDefinition::new_all_non_key_value_columns(
+                        cpu_id,
+                        "cpu",
+                        "cpu_cache",
+                        ["host"],
+                        1,
+                        600,
+                    )
+                    .unwrap(),
+                )],
+            })
+            .unwrap();
+        source.create_database("bar", None, 0).unwrap();
+
+        let export = source.export();
+        let serialized = serde_json::to_string(&export).unwrap();
+        assert_eq!(
+            export,
+            serde_json::from_str::<CatalogExport>(&serialized).unwrap()
+        );
+        assert_eq!(
+            vec!["bar", "foo"],
+            export
+                .databases
+                .iter()
+                .map(|db| db.name.as_str())
+                .collect::<Vec<_>>()
+        );
+
+        // everything is created in an empty catalog:
+        let target = Catalog::new("target".into(), "instance".into());
+        let plan = target.plan_import(&export, 0);
+        assert!(!plan.has_conflicts());
+        let diff = plan.diff(false);
+        assert_eq!(
+            vec![
+                "create database bar",
+                "create database foo",
+                "create table cpu with 5 columns",
+                "create last cache cpu_cache on table cpu",
+            ],
+            diff.changes
+                .iter()
+                .map(|c| c.description.as_str())
+                .collect::<Vec<_>>()
+        );
+        let applied = target.import(&export, 0).unwrap();
+        assert_eq!(diff.changes, applied.diff(true).changes);
+        assert_eq!(export, target.export());
+
+        // and nothing is planned once the catalogs match:
+        let plan = target.plan_import(&export, 0);
+        assert!(plan.batches.is_empty());
+        assert!(!plan.has_conflicts());
+    }
+
+    #[test]
+    fn import_adds_missing_columns_and_reports_conflicts() {
+        let catalog = Catalog::new("host".into(), "instance".into());
+        catalog
+            .create_table(
+                "foo",
+                "cpu",
+                &["host"],
+                &[("usage", InfluxFieldType::Float)],
+                0,
+            )
+            .unwrap();
+        catalog
+            .create_table(
+                "foo",
+                "mem",
+                &["host"],
+                &[("used", InfluxFieldType::Integer)],
+                0,
+            )
+            .unwrap();
+
+        let column = |name: &str, column_type| ColumnExport {
+            name: name.to_string(),
+            column_type,
+        };
+        let export = CatalogExport {
+            databases: vec![DatabaseExport {
+                name: "foo".to_string(),
+                tables: vec![
+                    // a new column is added:
+                    TableExport {
+                        name: "cpu".to_string(),
+                        series_key: Some(vec!["host".to_string()]),
+                        columns: vec![
+                            column("host", ColumnType::Tag),
+                            column("usage", ColumnType::Float),
+                            column("temp", ColumnType::Float),
+                            column("time", ColumnType::Time),
+                        ],
+                        last_caches: vec![],
+                    },
+                    // the type of a column does not match:
+                    TableExport {
+                        name: "mem".to_string(),
+                        series_key: Some(vec!["host".to_string()]),
+                        columns: vec![
+                            column("host", ColumnType::Tag),
+                            column("used", ColumnType::Float),
+                            column("time", ColumnType::Time),
+                        ],
+                        last_caches: vec![],
+                    },
+                    // a last cache on a column that does not exist:
+                    TableExport {
+                        name: "disk".to_string(),
+                        series_key: None,
+                        columns: vec![column("time", ColumnType::Time)],
+                        last_caches: vec![LastCacheExport {
+                            name: "disk_cache".to_string(),
+                            key_columns: vec!["host".to_string()],
+                            value_columns: LastCacheValueColumnsDef::AllNonKeyColumns,
+                            count: 1,
+                            ttl: 600,
+                        }],
+                    },
+                ],
+            }],
+        };
+
+        let diff = catalog.plan_import(&export, 0).diff(false);
+        assert_eq!(
+            vec!["add columns to table cpu: temp"],
+            diff.changes
+                .iter()
+                .map(|c| c.description.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![
+                ImportConflict {
+                    database: "foo".to_string(),
+                    table: Some("mem".to_string()),
+                    description: Error::FieldTypeMismatch {
+                        table_name: "mem".to_string(),
+                        column_name: "used".to_string(),
+                        existing: InfluxColumnType::Field(InfluxFieldType::Integer),
+                        attempted: InfluxColumnType::Field(InfluxFieldType::Float),
+                    }
+                    .to_string(),
+                },
+                ImportConflict {
+                    database: "foo".to_string(),
+                    table: Some("disk".to_string()),
+                    description: "Column host not in table disk".to_string(),
+                },
+            ],
+            diff.conflicts
+        );
+    }
+
+    #[test]
+    fn import_is_not_applied_past_the_limits_of_the_catalog() {
+        let catalog = Catalog::new("host".into(), "instance".into());
+        for i in 0..Catalog::NUM_DBS_LIMIT {
+            catalog.create_database(&format!("db{i}"), None, 0).unwrap();
+        }
+        let sequence = catalog.sequence_number();
+
+        let table = TableExport {
+            name: "cpu".to_string(),
+            series_key: None,
+            columns: vec![ColumnExport {
+                name: "time".to_string(),
+                column_type: ColumnType::Time,
+            }],
+            last_caches: vec![],
+        };
+        let export = CatalogExport {
+            databases: ["db0", "new_db"]
+                .into_iter()
+                .map(|name| DatabaseExport {
+                    name: name.to_string(),
+                    tables: vec![table.clone()],
+                })
+                .collect(),
+        };
+
+        // the table can be created in an existing database, but a new database is one too many:
+        let plan = catalog.plan_import(&export, 0);
+        assert_eq!(1, plan.batches.len());
+        assert_eq!(
+            vec![ImportConflict {
+                database: "new_db".to_string(),
+                table: None,
+                description: Error::TooManyDbs.to_string(),
+            }],
+            plan.conflicts
+        );
+
+        // so nothing is applied:
+        let plan = catalog.import(&export, 0).unwrap();
+        assert!(plan.has_conflicts());
+        assert_eq!(sequence, catalog.sequence_number());
+        assert!(catalog
+            .db_schema("db0")
+            .unwrap()
+            .table_definition("cpu")
+            .is_none());
+    }
+}
@@ -1,9 +1,10 @@
 use std::sync::Arc;
 
-use catalog::DatabaseSchema;
+use catalog::{DatabaseSchema, SchemaHistoryEntry};
 use influxdb3_id::DbId;
 
 pub mod catalog;
+pub mod export;
 pub(crate) mod serialize;
 
 /// Provide [`DatabaseSchema`] and there derivatives where needed.
@@ -31,4 +32,8 @@ pub trait DatabaseSchemaProvider: std::fmt::Debug + Send + Sync + 'static {
 
     /// List out all [`DatabaseSchema`] in the database
     fn list_db_schema(&self) -> Vec<Arc<DatabaseSchema>>;
+
+    /// The catalog batches that changed the schema of the database with the given [`DbId`], in
+    /// the order they were applied
+    fn schema_history(&self, db_id: DbId) -> Vec<SchemaHistoryEntry>;
 }
@@ -4,7 +4,9 @@ use arrow::datatypes::DataType as ArrowDataType;
 use bimap::BiHashMap;
 use influxdb3_id::ColumnId;
 use influxdb3_id::TableId;
-use influxdb3_wal::{LastCacheDefinition, LastCacheValueColumnsDef};
+use influxdb3_wal::{
+    CoercionPolicy, JsonMapping, LastCacheDefinition, LastCacheValueColumnsDef, SchemaPolicy,
+};
 use schema::{InfluxColumnType, SchemaBuilder};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -49,6 +51,14 @@ struct TableSnapshot<'a> {
     #[serde_as(as = "ColumnMapAsArray")]
     column_map: BiHashMap<ColumnId, Arc<str>>,
     next_column_id: ColumnId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schema_policy: Option<SchemaPolicy>,
+    #[serde(default, skip_serializing_if = "CoercionPolicy::is_strict")]
+    coercion_policy: CoercionPolicy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    json_mapping: Option<JsonMapping>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    previous_column_names: BTreeMap<Arc<str>, ColumnId>,
 }
 
 serde_with::serde_conv!(
@@ -195,6 +205,10 @@ impl<'a> From<&'a TableDefinition> for TableSnapshot<'a> {
             last_caches,
             next_column_id: def.schema.next_column_id(),
             column_map: def.schema.column_map().clone(),
+            schema_policy: def.schema_policy,
+            coercion_policy: def.coercion_policy,
+            json_mapping: def.json_mapping.clone(),
+            previous_column_names: def.schema.previous_names().clone(),
         }
     }
 }
@@ -275,6 +289,7 @@ impl<'a> From<TableSnapshot<'a>> for TableDefinition {
             b.build().expect("valid schema from snapshot"),
             snap.column_map,
             snap.next_column_id,
+            snap.previous_column_names,
         );
         let last_caches = snap
             .last_caches
@@ -287,6 +302,9 @@ impl<'a> From<TableSnapshot<'a>> for TableDefinition {
             table_id,
             schema,
             last_caches,
+            schema_policy: snap.schema_policy,
+            coercion_policy: snap.coercion_policy,
+            json_mapping: snap.json_mapping,
         }
     }
 }
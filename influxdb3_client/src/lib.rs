@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fmt::Display, string::FromUtf8Error};
+use std::{collections::HashMap, fmt::Display, string::FromUtf8Error, time::Duration};
 
 use bytes::Bytes;
 use iox_query_params::StatementParam;
@@ -134,6 +134,45 @@ impl Client {
         }
     }
 
+    /// Compose a request to the `/api/v3/write_csv` API
+    ///
+    /// Annotated CSV is written as it is, while the roles of the columns of plain CSV must be set
+    /// on the builder.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use influxdb3_client::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    /// let client = Client::new("http://localhost:8181")?;
+    /// client
+    ///     .api_v3_write_csv("db_name")
+    ///     .measurement("cpu")
+    ///     .tag("host")
+    ///     .field("usage", "float")
+    ///     .time_column("time")
+    ///     .body("time,host,usage\n2024-01-01T00:00:00Z,s1,0.5")
+    ///     .send()
+    ///     .await
+    ///     .expect("send write_csv request");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn api_v3_write_csv<S: Into<String>>(&self, db: S) -> CsvWriteRequestBuilder<'_, NoBody> {
+        CsvWriteRequestBuilder {
+            client: self,
+            db: db.into(),
+            accept_partial: None,
+            measurement: None,
+            measurement_column: None,
+            tags: vec![],
+            fields: vec![],
+            time_column: None,
+            time_format: None,
+            body: NoBody,
+        }
+    }
+
     /// Compose a request to the `/api/v3/query_sql` API
     ///
     /// # Example
@@ -259,6 +298,482 @@ impl Client {
         }
     }
 
+    /// Make a request to the `POST /api/v3/configure/database` API
+    ///
+    /// The database keeps data for the retention period if one is given, otherwise forever.
+    pub async fn api_v3_configure_database_create(
+        &self,
+        db: impl Into<String> + Send,
+        retention_period: Option<Duration>,
+    ) -> Result<()> {
+        let url = self.base_url.join("/api/v3/configure/database")?;
+        #[derive(Serialize)]
+        struct Req {
+            db: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            retention_period: Option<u64>,
+        }
+        let mut req = self.http_client.post(url).json(&Req {
+            db: db.into(),
+            retention_period: retention_period.map(|d| d.as_secs()),
+        });
+        if let Some(token) = &self.auth_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req
+            .send()
+            .await
+            .map_err(|src| Error::request_send(Method::POST, "/api/v3/configure/database", src))?;
+        let status = resp.status();
+        match status {
+            StatusCode::OK => Ok(()),
+            code => Err(Error::ApiError {
+                code,
+                message: resp.text().await.map_err(Error::Text)?,
+            }),
+        }
+    }
+
+    /// Make a request to the `POST /api/v3/configure/database/retention` API
+    ///
+    /// Sets the retention period of the database, or removes it if `None` is given, so that data
+    /// is kept forever.
+    pub async fn api_v3_configure_retention_period(
+        &self,
+        db: impl Into<String> + Send,
+        retention_period: Option<Duration>,
+    ) -> Result<()> {
+        let url = self.base_url.join("/api/v3/configure/database/retention")?;
+        #[derive(Serialize)]
+        struct Req {
+            db: String,
+            retention_period: Option<u64>,
+        }
+        let mut req = self.http_client.post(url).json(&Req {
+            db: db.into(),
+            retention_period: retention_period.map(|d| d.as_secs()),
+        });
+        if let Some(token) = &self.auth_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req.send().await.map_err(|src| {
+            Error::request_send(Method::POST, "/api/v3/configure/database/retention", src)
+        })?;
+        let status = resp.status();
+        match status {
+            StatusCode::OK => Ok(()),
+            code => Err(Error::ApiError {
+                code,
+                message: resp.text().await.map_err(Error::Text)?,
+            }),
+        }
+    }
+
+    /// Make a request to the `POST /api/v3/configure/database/quotas` API
+    ///
+    /// Sets the quotas of the database, replacing the ones it had. The quotas that are not set are
+    /// unlimited.
+    pub async fn api_v3_configure_quotas(
+        &self,
+        db: impl Into<String> + Send,
+        quotas: DatabaseQuotas,
+    ) -> Result<()> {
+        let url = self.base_url.join("/api/v3/configure/database/quotas")?;
+        #[derive(Serialize)]
+        struct Req {
+            db: String,
+            #[serde(flatten)]
+            quotas: DatabaseQuotas,
+        }
+        let mut req = self.http_client.post(url).json(&Req {
+            db: db.into(),
+            quotas,
+        });
+        if let Some(token) = &self.auth_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req.send().await.map_err(|src| {
+            Error::request_send(Method::POST, "/api/v3/configure/database/quotas", src)
+        })?;
+        let status = resp.status();
+        match status {
+            StatusCode::OK => Ok(()),
+            code => Err(Error::ApiError {
+                code,
+                message: resp.text().await.map_err(Error::Text)?,
+            }),
+        }
+    }
+
+    /// Make a request to the `GET /api/v3/configure/catalog` API
+    ///
+    /// Returns the JSON document of the databases in the catalog, along with their tables and
+    /// last caches, which can be imported into another server with
+    /// [`Client::api_v3_configure_catalog_import`].
+    pub async fn api_v3_configure_catalog_export(&self) -> Result<Bytes> {
+        let url = self.base_url.join("/api/v3/configure/catalog")?;
+        let mut req = self.http_client.get(url);
+        if let Some(token) = &self.auth_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req
+            .send()
+            .await
+            .map_err(|src| Error::request_send(Method::GET, "/api/v3/configure/catalog", src))?;
+        let status = resp.status();
+        match status {
+            StatusCode::OK => resp.bytes().await.map_err(Error::Bytes),
+            code => Err(Error::ApiError {
+                code,
+                message: resp.text().await.map_err(Error::Text)?,
+            }),
+        }
+    }
+
+    /// Make a request to the `POST /api/v3/configure/catalog` API
+    ///
+    /// Imports the exported catalog document, creating whatever it has that is missing from the
+    /// catalog of the server. Nothing is imported on a dry run, or if there are any conflicts,
+    /// which are returned in the diff rather than as an error.
+    pub async fn api_v3_configure_catalog_import(
+        &self,
+        body: impl Into<Bytes> + Send,
+        dry_run: bool,
+    ) -> Result<CatalogImportDiff> {
+        let url = self.base_url.join("/api/v3/configure/catalog")?;
+        let mut req = self
+            .http_client
+            .post(url)
+            .query(&[("dry_run", dry_run)])
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.into());
+        if let Some(token) = &self.auth_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req
+            .send()
+            .await
+            .map_err(|src| Error::request_send(Method::POST, "/api/v3/configure/catalog", src))?;
+        let status = resp.status();
+        match status {
+            StatusCode::OK | StatusCode::CONFLICT => resp.json().await.map_err(Error::Json),
+            code => Err(Error::ApiError {
+                code,
+                message: resp.text().await.map_err(Error::Text)?,
+            }),
+        }
+    }
+
+    /// Make a request to the `POST /api/v3/import/parquet` API
+    ///
+    /// Imports the parquet file into the table, persisting its rows directly to the object store
+    /// of the server rather than writing them to its WAL. The table, or any of its columns that
+    /// are new, are added to the catalog.
+    pub async fn api_v3_import_parquet(
+        &self,
+        db: impl Into<String> + Send,
+        table: impl Into<String> + Send,
+        file: impl Into<Bytes> + Send,
+    ) -> Result<ParquetImportSummary> {
+        let url = self.base_url.join("/api/v3/import/parquet")?;
+        let mut req = self
+            .http_client
+            .post(url)
+            .query(&[("db", db.into()), ("table", table.into())])
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/vnd.apache.parquet",
+            )
+            .body(file.into());
+        if let Some(token) = &self.auth_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req
+            .send()
+            .await
+            .map_err(|src| Error::request_send(Method::POST, "/api/v3/import/parquet", src))?;
+        let status = resp.status();
+        match status {
+            StatusCode::OK => resp.json().await.map_err(Error::Json),
+            code => Err(Error::ApiError {
+                code,
+                message: resp.text().await.map_err(Error::Text)?,
+            }),
+        }
+    }
+    /// Make a request to the `POST /api/v3/import/parquet` API with the path of a parquet file in
+    /// the object store of the server, rather than the file itself
+    ///
+    /// The server streams the file from its object store, so it is not limited to the size of a
+    /// request. It is imported in the same way as by [`Client::api_v3_import_parquet`].
+    pub async fn api_v3_import_parquet_from_object_store(
+        &self,
+        db: impl Into<String> + Send,
+        table: impl Into<String> + Send,
+        path: impl Into<String> + Send,
+    ) -> Result<ParquetImportSummary> {
+        let url = self.base_url.join("/api/v3/import/parquet")?;
+        let mut req = self.http_client.post(url).query(&[
+            ("db", db.into()),
+            ("table", table.into()),
+            ("path", path.into()),
+        ]);
+        if let Some(token) = &self.auth_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req
+            .send()
+            .await
+            .map_err(|src| Error::request_send(Method::POST, "/api/v3/import/parquet", src))?;
+        let status = resp.status();
+        match status {
+            StatusCode::OK => resp.json().await.map_err(Error::Json),
+            code => Err(Error::ApiError {
+                code,
+                message: resp.text().await.map_err(Error::Text)?,
+            }),
+        }
+    }
+
+    /// Compose a request to the `POST /api/v3/configure/table` API
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use influxdb3_client::{Client, FieldType};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    /// let client = Client::new("http://localhost:8181")?;
+    /// client
+    ///     .api_v3_configure_table_create("db_name", "table_name")
+    ///     .tags(["region", "host"])
+    ///     .field("usage", FieldType::Float)
+    ///     .send()
+    ///     .await
+    ///     .expect("send create table request");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn api_v3_configure_table_create(
+        &self,
+        db: impl Into<String>,
+        table: impl Into<String>,
+    ) -> CreateTableRequestBuilder<'_> {
+        CreateTableRequestBuilder::new(self, db, table)
+    }
+
+    /// Make a request to the `POST /api/v3/configure/schema_policy` API
+    ///
+    /// Sets the schema policy of the table, or the default schema policy of the database if no
+    /// table is given.
+    pub async fn api_v3_configure_schema_policy(
+        &self,
+        db: impl Into<String> + Send,
+        table: Option<String>,
+        policy: SchemaPolicy,
+    ) -> Result<()> {
+        let url = self.base_url.join("/api/v3/configure/schema_policy")?;
+        #[derive(Serialize)]
+        struct Req {
+            db: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            table: Option<String>,
+            policy: SchemaPolicy,
+        }
+        let mut req = self.http_client.post(url).json(&Req {
+            db: db.into(),
+            table,
+            policy,
+        });
+        if let Some(token) = &self.auth_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req.send().await.map_err(|src| {
+            Error::request_send(Method::POST, "/api/v3/configure/schema_policy", src)
+        })?;
+        let status = resp.status();
+        match status {
+            StatusCode::OK => Ok(()),
+            code => Err(Error::ApiError {
+                code,
+                message: resp.text().await.map_err(Error::Text)?,
+            }),
+        }
+    }
+
+    /// Make a request to the `POST /api/v3/configure/table/coercion_policy` API
+    ///
+    /// Sets which field values written to the table are converted to the type of their column,
+    /// rather than rejected for not matching it.
+    pub async fn api_v3_configure_coercion_policy(
+        &self,
+        db: impl Into<String> + Send,
+        table: impl Into<String> + Send,
+        policy: CoercionPolicy,
+    ) -> Result<()> {
+        let url = self
+            .base_url
+            .join("/api/v3/configure/table/coercion_policy")?;
+        #[derive(Serialize)]
+        struct Req {
+            db: String,
+            table: String,
+            #[serde(flatten)]
+            policy: CoercionPolicy,
+        }
+        let mut req = self.http_client.post(url).json(&Req {
+            db: db.into(),
+            table: table.into(),
+            policy,
+        });
+        if let Some(token) = &self.auth_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req.send().await.map_err(|src| {
+            Error::request_send(Method::POST, "/api/v3/configure/table/coercion_policy", src)
+        })?;
+        let status = resp.status();
+        match status {
+            StatusCode::OK => Ok(()),
+            code => Err(Error::ApiError {
+                code,
+                message: resp.text().await.map_err(Error::Text)?,
+            }),
+        }
+    }
+
+    /// Make a request to the `POST /api/v3/configure/table/rename` API
+    pub async fn api_v3_configure_table_rename(
+        &self,
+        db: impl Into<String> + Send,
+        table: impl Into<String> + Send,
+        new_name: impl Into<String> + Send,
+    ) -> Result<()> {
+        let url = self.base_url.join("/api/v3/configure/table/rename")?;
+        #[derive(Serialize)]
+        struct Req {
+            db: String,
+            table: String,
+            new_name: String,
+        }
+        let mut req = self.http_client.post(url).json(&Req {
+            db: db.into(),
+            table: table.into(),
+            new_name: new_name.into(),
+        });
+        if let Some(token) = &self.auth_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req.send().await.map_err(|src| {
+            Error::request_send(Method::POST, "/api/v3/configure/table/rename", src)
+        })?;
+        let status = resp.status();
+        match status {
+            StatusCode::OK => Ok(()),
+            code => Err(Error::ApiError {
+                code,
+                message: resp.text().await.map_err(Error::Text)?,
+            }),
+        }
+    }
+
+    /// Make a request to the `POST /api/v3/configure/column/rename` API
+    pub async fn api_v3_configure_column_rename(
+        &self,
+        db: impl Into<String> + Send,
+        table: impl Into<String> + Send,
+        column: impl Into<String> + Send,
+        new_name: impl Into<String> + Send,
+    ) -> Result<()> {
+        let url = self.base_url.join("/api/v3/configure/column/rename")?;
+        #[derive(Serialize)]
+        struct Req {
+            db: String,
+            table: String,
+            column: String,
+            new_name: String,
+        }
+        let mut req = self.http_client.post(url).json(&Req {
+            db: db.into(),
+            table: table.into(),
+            column: column.into(),
+            new_name: new_name.into(),
+        });
+        if let Some(token) = &self.auth_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req.send().await.map_err(|src| {
+            Error::request_send(Method::POST, "/api/v3/configure/column/rename", src)
+        })?;
+        let status = resp.status();
+        match status {
+            StatusCode::OK => Ok(()),
+            code => Err(Error::ApiError {
+                code,
+                message: resp.text().await.map_err(Error::Text)?,
+            }),
+        }
+    }
+
+    /// Make a request to the `DELETE /api/v3/configure/database` API
+    pub async fn api_v3_configure_database_delete(
+        &self,
+        db: impl Into<String> + Send,
+    ) -> Result<()> {
+        let url = self.base_url.join("/api/v3/configure/database")?;
+        #[derive(Serialize)]
+        struct Req {
+            db: String,
+        }
+        let mut req = self.http_client.delete(url).json(&Req { db: db.into() });
+        if let Some(token) = &self.auth_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req.send().await.map_err(|src| {
+            Error::request_send(Method::DELETE, "/api/v3/configure/database", src)
+        })?;
+        let status = resp.status();
+        match status {
+            StatusCode::OK => Ok(()),
+            code => Err(Error::ApiError {
+                code,
+                message: resp.text().await.map_err(Error::Text)?,
+            }),
+        }
+    }
+
+    /// Make a request to the `DELETE /api/v3/configure/table` API
+    pub async fn api_v3_configure_table_delete(
+        &self,
+        db: impl Into<String> + Send,
+        table: impl Into<String> + Send,
+    ) -> Result<()> {
+        let url = self.base_url.join("/api/v3/configure/table")?;
+        #[derive(Serialize)]
+        struct Req {
+            db: String,
+            table: String,
+        }
+        let mut req = self.http_client.delete(url).json(&Req {
+            db: db.into(),
+            table: table.into(),
+        });
+        if let Some(token) = &self.auth_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req
+            .send()
+            .await
+            .map_err(|src| Error::request_send(Method::DELETE, "/api/v3/configure/table", src))?;
+        let status = resp.status();
+        match status {
+            StatusCode::OK => Ok(()),
+            code => Err(Error::ApiError {
+                code,
+                message: resp.text().await.map_err(Error::Text)?,
+            }),
+        }
+    }
+
     /// Send a `/ping` request to the target `influxdb3` server to check its
     /// status and gather `version` and `revision` information
     pub async fn ping(&self) -> Result<PingResponse> {
@@ -402,8 +917,150 @@ impl<'c> WriteRequestBuilder<'c, Body> {
     }
 }
 
+/// The URL parameters of the request to the `/api/v3/write_csv` API
+#[derive(Debug, Serialize)]
+struct CsvWriteParams<'a> {
+    db: &'a str,
+    accept_partial: Option<bool>,
+    measurement: Option<&'a str>,
+    measurement_column: Option<&'a str>,
+    tags: Option<String>,
+    fields: Option<String>,
+    time_column: Option<&'a str>,
+    time_format: Option<&'a str>,
+}
+
+impl<'a, B> From<&'a CsvWriteRequestBuilder<'a, B>> for CsvWriteParams<'a> {
+    fn from(builder: &'a CsvWriteRequestBuilder<'a, B>) -> Self {
+        let list = |list: Vec<String>| (!list.is_empty()).then(|| list.join(","));
+        Self {
+            db: &builder.db,
+            accept_partial: builder.accept_partial,
+            measurement: builder.measurement.as_deref(),
+            measurement_column: builder.measurement_column.as_deref(),
+            tags: list(builder.tags.clone()),
+            fields: list(
+                builder
+                    .fields
+                    .iter()
+                    .map(|(name, field_type)| format!("{name}:{field_type}"))
+                    .collect(),
+            ),
+            time_column: builder.time_column.as_deref(),
+            time_format: builder.time_format.as_deref(),
+        }
+    }
+}
+
+/// Builder type for composing a request to `/api/v3/write_csv`
+///
+/// Produced by [`Client::api_v3_write_csv`]
+#[derive(Debug)]
+pub struct CsvWriteRequestBuilder<'c, B> {
+    client: &'c Client,
+    db: String,
+    accept_partial: Option<bool>,
+    measurement: Option<String>,
+    measurement_column: Option<String>,
+    tags: Vec<String>,
+    fields: Vec<(String, String)>,
+    time_column: Option<String>,
+    time_format: Option<String>,
+    body: B,
+}
+
+impl<'c, B> CsvWriteRequestBuilder<'c, B> {
+    /// Set the `accept_partial` parameter
+    pub fn accept_partial(mut self, set_to: bool) -> Self {
+        self.accept_partial = Some(set_to);
+        self
+    }
+
+    /// Set the measurement of every row of plain CSV
+    pub fn measurement<S: Into<String>>(mut self, measurement: S) -> Self {
+        self.measurement = Some(measurement.into());
+        self
+    }
+
+    /// Set the column with the measurement of each row of plain CSV
+    pub fn measurement_column<S: Into<String>>(mut self, column: S) -> Self {
+        self.measurement_column = Some(column.into());
+        self
+    }
+
+    /// Add a tag column of plain CSV
+    pub fn tag<S: Into<String>>(mut self, column: S) -> Self {
+        self.tags.push(column.into());
+        self
+    }
+
+    /// Add a field column of plain CSV, with the type of its values, one of `float`, `integer`,
+    /// `uinteger`, `string`, or `boolean`
+    pub fn field<S: Into<String>, T: Into<String>>(mut self, column: S, field_type: T) -> Self {
+        self.fields.push((column.into(), field_type.into()));
+        self
+    }
+
+    /// Set the column with the time of each row of plain CSV
+    pub fn time_column<S: Into<String>>(mut self, column: S) -> Self {
+        self.time_column = Some(column.into());
+        self
+    }
+
+    /// Set the format of the times of plain CSV, one of `rfc3339`, `unix_seconds`,
+    /// `unix_millis`, `unix_micros`, or `unix_nanos`
+    pub fn time_format<S: Into<String>>(mut self, format: S) -> Self {
+        self.time_format = Some(format.into());
+        self
+    }
+}
+
+impl<'c> CsvWriteRequestBuilder<'c, NoBody> {
+    /// Set the body of the request to the `/api/v3/write_csv` API
+    pub fn body<T: Into<Body>>(self, body: T) -> CsvWriteRequestBuilder<'c, Body> {
+        CsvWriteRequestBuilder {
+            client: self.client,
+            db: self.db,
+            accept_partial: self.accept_partial,
+            measurement: self.measurement,
+            measurement_column: self.measurement_column,
+            tags: self.tags,
+            fields: self.fields,
+            time_column: self.time_column,
+            time_format: self.time_format,
+            body: body.into(),
+        }
+    }
+}
+
+impl<'c> CsvWriteRequestBuilder<'c, Body> {
+    /// Send the request to the server
+    pub async fn send(self) -> Result<()> {
+        let url = self.client.base_url.join("/api/v3/write_csv")?;
+        let params = CsvWriteParams::from(&self);
+        let mut req = self.client.http_client.post(url).query(&params);
+        if let Some(token) = &self.client.auth_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req
+            .body(self.body)
+            .send()
+            .await
+            .map_err(|src| Error::request_send(Method::POST, "/api/v3/write_csv", src))?;
+        let status = resp.status();
+        let content = resp.bytes().await.map_err(Error::Bytes)?;
+        match status {
+            StatusCode::OK => Ok(()),
+            code => Err(Error::ApiError {
+                code,
+                message: String::from_utf8(content.to_vec())?,
+            }),
+        }
+    }
+}
+
 #[doc(hidden)]
-/// Typestate type for [`WriteRequestBuilder`]
+/// Typestate type for [`WriteRequestBuilder`] and [`CsvWriteRequestBuilder`]
 #[derive(Debug, Copy, Clone)]
 pub struct NoBody;
 
@@ -704,6 +1361,128 @@ impl<'c> CreateLastCacheRequestBuilder<'c> {
     }
 }
 
+#[derive(Debug, Serialize)]
+pub struct CreateTableRequestBuilder<'c> {
+    #[serde(skip_serializing)]
+    client: &'c Client,
+    db: String,
+    table: String,
+    tags: Vec<String>,
+    fields: Vec<FieldDefinition>,
+}
+
+impl<'c> CreateTableRequestBuilder<'c> {
+    /// Create a new [`CreateTableRequestBuilder`]
+    fn new(client: &'c Client, db: impl Into<String>, table: impl Into<String>) -> Self {
+        Self {
+            client,
+            db: db.into(),
+            table: table.into(),
+            tags: vec![],
+            fields: vec![],
+        }
+    }
+
+    /// Specify the tag columns of the table, which make up its series key
+    pub fn tags(mut self, tags: impl IntoIterator<Item: Into<String>>) -> Self {
+        self.tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add a field to the table
+    pub fn field(mut self, name: impl Into<String>, field_type: FieldType) -> Self {
+        self.fields.push(FieldDefinition {
+            name: name.into(),
+            field_type,
+        });
+        self
+    }
+
+    /// Send the request to `POST /api/v3/configure/table`
+    pub async fn send(self) -> Result<()> {
+        let url = self.client.base_url.join("/api/v3/configure/table")?;
+        let mut req = self.client.http_client.post(url).json(&self);
+        if let Some(token) = &self.client.auth_token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req
+            .send()
+            .await
+            .map_err(|src| Error::request_send(Method::POST, "/api/v3/configure/table", src))?;
+        let status = resp.status();
+        match status {
+            StatusCode::OK => Ok(()),
+            code => Err(Error::ApiError {
+                code,
+                message: resp.text().await.map_err(Error::Text)?,
+            }),
+        }
+    }
+}
+
+/// A field declared when creating a table
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct FieldDefinition {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: FieldType,
+}
+
+/// How writes that would add new columns to an existing table are handled
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaPolicy {
+    /// New columns are added to the table
+    Open,
+    /// Lines that would add new columns are rejected
+    Locked,
+    /// Lines are accepted, but the values of any new columns are dropped
+    DropUnknown,
+}
+
+/// Which field values written to a table are converted to the type of their column, rather than
+/// rejected for not matching it
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Copy, Default)]
+pub struct CoercionPolicy {
+    /// Integer and unsigned integer values are converted to floats
+    pub integer_to_float: bool,
+    /// Unsigned integer values are converted to integers, as long as they are not too large
+    pub uinteger_to_integer: bool,
+    /// Values of any type are converted to strings
+    pub any_to_string: bool,
+}
+
+/// Limits on the resources a database can use. A quota that is not set is unlimited.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Copy, Default)]
+pub struct DatabaseQuotas {
+    /// The number of tables in the database
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tables: Option<u64>,
+    /// The number of columns, summed over all tables in the database
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_columns: Option<u64>,
+    /// The number of distinct series written to the database since the server started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_series: Option<u64>,
+    /// The size in bytes of the parquet files persisted for the database
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_stored_bytes: Option<u64>,
+    /// The number of bytes of line protocol written to the database each second
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_write_bytes_per_second: Option<u64>,
+}
+
+/// The type of a field, named the same as the line protocol field value types
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    String,
+    Integer,
+    Uinteger,
+    Float,
+    Boolean,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LastCacheCreatedResponse {
     /// The table name the cache is associated with
@@ -731,12 +1510,57 @@ pub enum LastCacheValueColumnsDef {
     AllNonKeyColumns,
 }
 
+/// The changes that an import of an exported catalog makes, or would make on a dry run
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct CatalogImportDiff {
+    pub changes: Vec<CatalogImportChange>,
+    /// Conflicts between the exported catalog and that of the server, which stop the import
+    pub conflicts: Vec<CatalogImportConflict>,
+    /// Whether the changes were made
+    pub applied: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct CatalogImportChange {
+    pub database: String,
+    pub table: Option<String>,
+    pub op: String,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct CatalogImportConflict {
+    pub database: String,
+    pub table: Option<String>,
+    pub description: String,
+}
+
+/// The summary of a parquet file that was imported into a table
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct ParquetImportSummary {
+    /// The number of rows that were read from the imported file
+    pub row_count: u64,
+    /// The number of rows that were persisted, once duplicates were removed
+    pub persisted_row_count: u64,
+    /// The number of parquet files that were persisted, one for each gen1 chunk of time
+    pub persisted_file_count: usize,
+    pub min_time: i64,
+    pub max_time: i64,
+    /// The sequence number of the snapshot that the persisted files are registered in
+    pub snapshot_sequence_number: u64,
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use mockito::{Matcher, Server};
     use serde_json::json;
 
-    use crate::{Client, Format, Precision};
+    use crate::{
+        CatalogImportConflict, CatalogImportDiff, Client, CoercionPolicy, FieldType, Format,
+        ParquetImportSummary, Precision, SchemaPolicy,
+    };
 
     #[tokio::test]
     async fn api_v3_write_lp() {
@@ -775,6 +1599,43 @@ mod tests {
         mock.assert_async().await;
     }
 
+    #[tokio::test]
+    async fn api_v3_write_csv() {
+        let db = "stats";
+        let body = "time,host,usage\n1,s1,0.5\n";
+
+        let mut mock_server = Server::new_async().await;
+        let mock = mock_server
+            .mock("POST", "/api/v3/write_csv")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("db".into(), db.into()),
+                Matcher::UrlEncoded("measurement".into(), "cpu".into()),
+                Matcher::UrlEncoded("tags".into(), "host".into()),
+                Matcher::UrlEncoded("fields".into(), "usage:float".into()),
+                Matcher::UrlEncoded("time_column".into(), "time".into()),
+                Matcher::UrlEncoded("time_format".into(), "unix_seconds".into()),
+            ]))
+            .match_body(body)
+            .create_async()
+            .await;
+
+        let client = Client::new(mock_server.url()).expect("create client");
+
+        client
+            .api_v3_write_csv(db)
+            .measurement("cpu")
+            .tag("host")
+            .field("usage", "float")
+            .time_column("time")
+            .time_format("unix_seconds")
+            .body(body)
+            .send()
+            .await
+            .expect("send write_csv request");
+
+        mock.assert_async().await;
+    }
+
     #[tokio::test]
     async fn api_v3_query_sql() {
         let token = "super-secret-token";
@@ -1071,4 +1932,389 @@ mod tests {
             .unwrap();
         mock.assert_async().await;
     }
+
+    #[tokio::test]
+    async fn api_v3_configure_database_and_table_delete() {
+        let mut mock_server = Server::new_async().await;
+        let db_mock = mock_server
+            .mock("DELETE", "/api/v3/configure/database")
+            .match_body(Matcher::Json(serde_json::json!({ "db": "db" })))
+            .with_status(200)
+            .create_async()
+            .await;
+        let table_mock = mock_server
+            .mock("DELETE", "/api/v3/configure/table")
+            .match_body(Matcher::Json(serde_json::json!({
+                "db": "db",
+                "table": "table",
+            })))
+            .with_status(200)
+            .create_async()
+            .await;
+        let client = Client::new(mock_server.url()).unwrap();
+        client
+            .api_v3_configure_table_delete("db", "table")
+            .await
+            .unwrap();
+        client.api_v3_configure_database_delete("db").await.unwrap();
+        db_mock.assert_async().await;
+        table_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_v3_configure_database_and_table_create() {
+        let mut mock_server = Server::new_async().await;
+        let db_mock = mock_server
+            .mock("POST", "/api/v3/configure/database")
+            .match_body(Matcher::Json(serde_json::json!({ "db": "db" })))
+            .with_status(200)
+            .create_async()
+            .await;
+        let table_mock = mock_server
+            .mock("POST", "/api/v3/configure/table")
+            .match_body(Matcher::Json(serde_json::json!({
+                "db": "db",
+                "table": "table",
+                "tags": ["region", "host"],
+                "fields": [
+                    { "name": "usage", "type": "float" },
+                    { "name": "count", "type": "uinteger" },
+                ],
+            })))
+            .with_status(200)
+            .create_async()
+            .await;
+        let client = Client::new(mock_server.url()).unwrap();
+        client
+            .api_v3_configure_database_create("db", None)
+            .await
+            .unwrap();
+        client
+            .api_v3_configure_table_create("db", "table")
+            .tags(["region", "host"])
+            .field("usage", FieldType::Float)
+            .field("count", FieldType::Uinteger)
+            .send()
+            .await
+            .unwrap();
+        db_mock.assert_async().await;
+        table_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_v3_configure_schema_policy() {
+        let mut mock_server = Server::new_async().await;
+        let db_mock = mock_server
+            .mock("POST", "/api/v3/configure/schema_policy")
+            .match_body(Matcher::Json(serde_json::json!({
+                "db": "db",
+                "policy": "locked",
+            })))
+            .with_status(200)
+            .create_async()
+            .await;
+        let table_mock = mock_server
+            .mock("POST", "/api/v3/configure/schema_policy")
+            .match_body(Matcher::Json(serde_json::json!({
+                "db": "db",
+                "table": "table",
+                "policy": "drop_unknown",
+            })))
+            .with_status(200)
+            .create_async()
+            .await;
+        let client = Client::new(mock_server.url()).unwrap();
+        client
+            .api_v3_configure_schema_policy("db", None, SchemaPolicy::Locked)
+            .await
+            .unwrap();
+        client
+            .api_v3_configure_schema_policy(
+                "db",
+                Some("table".to_string()),
+                SchemaPolicy::DropUnknown,
+            )
+            .await
+            .unwrap();
+        db_mock.assert_async().await;
+        table_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_v3_configure_coercion_policy() {
+        let mut mock_server = Server::new_async().await;
+        let mock = mock_server
+            .mock("POST", "/api/v3/configure/table/coercion_policy")
+            .match_body(Matcher::Json(serde_json::json!({
+                "db": "db",
+                "table": "table",
+                "integer_to_float": true,
+                "uinteger_to_integer": false,
+                "any_to_string": true,
+            })))
+            .with_status(200)
+            .create_async()
+            .await;
+        let client = Client::new(mock_server.url()).unwrap();
+        client
+            .api_v3_configure_coercion_policy(
+                "db",
+                "table",
+                CoercionPolicy {
+                    integer_to_float: true,
+                    uinteger_to_integer: false,
+                    any_to_string: true,
+                },
+            )
+            .await
+            .unwrap();
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_v3_configure_retention_period() {
+        let mut mock_server = Server::new_async().await;
+        let create_mock = mock_server
+            .mock("POST", "/api/v3/configure/database")
+            .match_body(Matcher::Json(serde_json::json!({
+                "db": "db",
+                "retention_period": 86400,
+            })))
+            .with_status(200)
+            .create_async()
+            .await;
+        let set_mock = mock_server
+            .mock("POST", "/api/v3/configure/database/retention")
+            .match_body(Matcher::Json(serde_json::json!({
+                "db": "db",
+                "retention_period": 3600,
+            })))
+            .with_status(200)
+            .create_async()
+            .await;
+        let remove_mock = mock_server
+            .mock("POST", "/api/v3/configure/database/retention")
+            .match_body(Matcher::Json(serde_json::json!({
+                "db": "db",
+                "retention_period": null,
+            })))
+            .with_status(200)
+            .create_async()
+            .await;
+        let client = Client::new(mock_server.url()).unwrap();
+        client
+            .api_v3_configure_database_create("db", Some(Duration::from_secs(86400)))
+            .await
+            .unwrap();
+        client
+            .api_v3_configure_retention_period("db", Some(Duration::from_secs(3600)))
+            .await
+            .unwrap();
+        client
+            .api_v3_configure_retention_period("db", None)
+            .await
+            .unwrap();
+        create_mock.assert_async().await;
+        set_mock.assert_async().await;
+        remove_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_v3_configure_quotas() {
+        let mut mock_server = Server::new_async().await;
+        let mock = mock_server
+            .mock("POST", "/api/v3/configure/database/quotas")
+            .match_body(Matcher::Json(serde_json::json!({
+                "db": "db",
+                "max_tables": 10,
+                "max_write_bytes_per_second": 1048576,
+            })))
+            .with_status(200)
+            .create_async()
+            .await;
+        let client = Client::new(mock_server.url()).unwrap();
+        client
+            .api_v3_configure_quotas(
+                "db",
+                DatabaseQuotas {
+                    max_tables: Some(10),
+                    max_write_bytes_per_second: Some(1_048_576),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_v3_configure_catalog_export_and_import() {
+        let mut mock_server = Server::new_async().await;
+        let export = r#"{"databases":[{"name":"db","tables":[]}]}"#;
+        let export_mock = mock_server
+            .mock("GET", "/api/v3/configure/catalog")
+            .with_status(200)
+            .with_body(export)
+            .create_async()
+            .await;
+        let import_mock = mock_server
+            .mock("POST", "/api/v3/configure/catalog")
+            .match_query(Matcher::UrlEncoded("dry_run".into(), "true".into()))
+            .match_body(Matcher::JsonString(export.into()))
+            .with_status(409)
+            .with_body(
+                r#"{
+                    "changes": [],
+                    "conflicts": [{
+                        "database": "db",
+                        "table": "cpu",
+                        "description": "Field type mismatch"
+                    }],
+                    "applied": false
+                }"#,
+            )
+            .create_async()
+            .await;
+        let client = Client::new(mock_server.url()).unwrap();
+
+        let body = client.api_v3_configure_catalog_export().await.unwrap();
+        assert_eq!(export.as_bytes(), body.as_ref());
+        let diff = client
+            .api_v3_configure_catalog_import(body, true)
+            .await
+            .unwrap();
+        assert_eq!(
+            CatalogImportDiff {
+                changes: vec![],
+                conflicts: vec![CatalogImportConflict {
+                    database: "db".into(),
+                    table: Some("cpu".into()),
+                    description: "Field type mismatch".into(),
+                }],
+                applied: false,
+            },
+            diff
+        );
+        export_mock.assert_async().await;
+        import_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_v3_import_parquet() {
+        let token = "super-secret-token";
+        let file = "PAR1 not really parquet PAR1";
+        let mut mock_server = Server::new_async().await;
+        let mock = mock_server
+            .mock("POST", "/api/v3/import/parquet")
+            .match_header("Authorization", format!("Bearer {token}").as_str())
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("db".into(), "db".into()),
+                Matcher::UrlEncoded("table".into(), "cpu".into()),
+            ]))
+            .match_body(file)
+            .with_status(200)
+            .with_body(
+                r#"{
+                    "row_count": 3,
+                    "persisted_row_count": 2,
+                    "persisted_file_count": 2,
+                    "min_time": 1000000000,
+                    "max_time": 61000000000,
+                    "snapshot_sequence_number": 4
+                }"#,
+            )
+            .create_async()
+            .await;
+        let client = Client::new(mock_server.url())
+            .expect("create client")
+            .with_auth_token(token);
+
+        let summary = client
+            .api_v3_import_parquet("db", "cpu", file)
+            .await
+            .unwrap();
+        assert_eq!(
+            ParquetImportSummary {
+                row_count: 3,
+                persisted_row_count: 2,
+                persisted_file_count: 2,
+                min_time: 1_000_000_000,
+                max_time: 61_000_000_000,
+                snapshot_sequence_number: 4,
+            },
+            summary
+        );
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_v3_import_parquet_from_object_store() {
+        let mut mock_server = Server::new_async().await;
+        let mock = mock_server
+            .mock("POST", "/api/v3/import/parquet")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("db".into(), "db".into()),
+                Matcher::UrlEncoded("table".into(), "cpu".into()),
+                Matcher::UrlEncoded("path".into(), "imports/cpu.parquet".into()),
+            ]))
+            .with_status(200)
+            .with_body(
+                r#"{
+                    "row_count": 3,
+                    "persisted_row_count": 3,
+                    "persisted_file_count": 1,
+                    "min_time": 1000000000,
+                    "max_time": 3000000000,
+                    "snapshot_sequence_number": 2
+                }"#,
+            )
+            .create_async()
+            .await;
+        let client = Client::new(mock_server.url()).expect("create client");
+
+        let summary = client
+            .api_v3_import_parquet_from_object_store("db", "cpu", "imports/cpu.parquet")
+            .await
+            .unwrap();
+        assert_eq!(summary.persisted_file_count, 1);
+        assert_eq!(summary.snapshot_sequence_number, 2);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_v3_configure_table_and_column_rename() {
+        let mut mock_server = Server::new_async().await;
+        let table_mock = mock_server
+            .mock("POST", "/api/v3/configure/table/rename")
+            .match_body(Matcher::Json(serde_json::json!({
+                "db": "db",
+                "table": "cpu",
+                "new_name": "cpu_metrics",
+            })))
+            .with_status(200)
+            .create_async()
+            .await;
+        let column_mock = mock_server
+            .mock("POST", "/api/v3/configure/column/rename")
+            .match_body(Matcher::Json(serde_json::json!({
+                "db": "db",
+                "table": "cpu_metrics",
+                "column": "usage",
+                "new_name": "cpu_usage",
+            })))
+            .with_status(200)
+            .create_async()
+            .await;
+        let client = Client::new(mock_server.url()).unwrap();
+        client
+            .api_v3_configure_table_rename("db", "cpu", "cpu_metrics")
+            .await
+            .unwrap();
+        client
+            .api_v3_configure_column_rename("db", "cpu_metrics", "usage", "cpu_usage")
+            .await
+            .unwrap();
+        table_mock.assert_async().await;
+        column_mock.assert_async().await;
+    }
 }
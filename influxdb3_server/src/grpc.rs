@@ -1,14 +1,289 @@
 use std::sync::Arc;
+use std::task::{Context, Poll};
 
+use arrow_flight::decode::FlightRecordBatchStream;
+use arrow_flight::error::FlightError;
 use arrow_flight::flight_service_server::{
     FlightService as Flight, FlightServiceServer as FlightServer,
 };
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PollInfo, PutResult, SchemaResult, Ticket,
+};
+use async_trait::async_trait;
 use authz::Authorizer;
+use data_types::NamespaceName;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use influxdb3_telemetry::store::TelemetryStore;
+use influxdb3_write::write_buffer::Error as WriteBufferError;
+use influxdb3_write::WriteBuffer;
 use iox_query::QueryDatabase;
+use iox_time::TimeProvider;
+use observability_deps::tracing::info;
+use tonic::metadata::MetadataMap;
+use tonic::{Request, Response, Status, Streaming};
+use tower::Service;
+
+/// The path of Flight `DoPut` requests, which are served by the [`FlightWriteService`]
+const DO_PUT_PATH: &str = "/arrow.flight.protocol.FlightService/DoPut";
 
 pub(crate) fn make_flight_server<Q: QueryDatabase>(
     server: Arc<Q>,
+    write_service: FlightWriteService,
+    authz: Option<Arc<dyn Authorizer>>,
+) -> FlightRouter<FlightServer<impl Flight>, FlightServer<FlightWriteService>> {
+    FlightRouter {
+        query: service_grpc_flight::make_server(server, authz),
+        write: FlightServer::new(write_service),
+    }
+}
+
+/// Routes Flight `DoPut` requests to the service that writes them to the buffer, and all other
+/// Flight requests to the query service
+#[derive(Debug, Clone)]
+pub(crate) struct FlightRouter<Query, Write> {
+    query: Query,
+    write: Write,
+}
+
+impl<Query, Write, B> Service<hyper::Request<B>> for FlightRouter<Query, Write>
+where
+    Query: Service<hyper::Request<B>>,
+    Write: Service<
+        hyper::Request<B>,
+        Response = Query::Response,
+        Error = Query::Error,
+        Future = Query::Future,
+    >,
+{
+    type Response = Query::Response;
+    type Error = Query::Error;
+    type Future = Query::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match self.query.poll_ready(cx) {
+            Poll::Ready(Ok(())) => self.write.poll_ready(cx),
+            poll => poll,
+        }
+    }
+
+    fn call(&mut self, req: hyper::Request<B>) -> Self::Future {
+        if req.uri().path() == DO_PUT_PATH {
+            self.write.call(req)
+        } else {
+            self.query.call(req)
+        }
+    }
+}
+
+/// Serves Flight `DoPut` requests, which write Arrow record batches to a table. The path of the
+/// [`FlightDescriptor`] of the request names the database and the table, and the columns of the
+/// batches are marked as tags or fields in their metadata; see
+/// [`influxdb3_write::Bufferer::write_record_batches`].
+#[derive(Debug)]
+pub(crate) struct FlightWriteService {
+    write_buffer: Arc<dyn WriteBuffer>,
+    time_provider: Arc<dyn TimeProvider>,
+    telemetry_store: Arc<TelemetryStore>,
     authz: Option<Arc<dyn Authorizer>>,
-) -> FlightServer<impl Flight> {
-    service_grpc_flight::make_server(server, authz)
+}
+
+impl FlightWriteService {
+    pub(crate) fn new(
+        write_buffer: Arc<dyn WriteBuffer>,
+        time_provider: Arc<dyn TimeProvider>,
+        telemetry_store: Arc<TelemetryStore>,
+        authz: Option<Arc<dyn Authorizer>>,
+    ) -> Self {
+        Self {
+            write_buffer,
+            time_provider,
+            telemetry_store,
+            authz,
+        }
+    }
+
+    async fn authorize(&self, metadata: &MetadataMap) -> Result<(), Status> {
+        let Some(authz) = &self.authz else {
+            return Ok(());
+        };
+        let token = metadata
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                value
+                    .strip_prefix("Bearer ")
+                    .or_else(|| value.strip_prefix("Token "))
+            })
+            .map(|token| token.as_bytes().to_vec());
+        match authz.permissions(token, &[]).await {
+            Ok(_) => Ok(()),
+            Err(authz::Error::Forbidden) => Err(Status::permission_denied(
+                "requestor is forbidden from requested resource",
+            )),
+            Err(_) => Err(Status::unauthenticated("the request was not authorized")),
+        }
+    }
+}
+
+/// The database and table named by the path of the descriptor of a `DoPut` request
+fn write_target(
+    descriptor: Option<&FlightDescriptor>,
+) -> Result<(NamespaceName<'static>, String), Status> {
+    match descriptor.map(|d| d.path.as_slice()) {
+        Some([database, table]) => {
+            let database = NamespaceName::new(database.to_string())
+                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+            Ok((database, table.to_string()))
+        }
+        _ => Err(Status::invalid_argument(
+            "the flight descriptor must have a path of the database and table to write to",
+        )),
+    }
+}
+
+fn write_error_status(e: WriteBufferError) -> Status {
+    match e {
+        WriteBufferError::InvalidRecordBatch(_) | WriteBufferError::CatalogUpdateError(_) => {
+            Status::invalid_argument(e.to_string())
+        }
+        WriteBufferError::QuotaExceeded { .. } => Status::resource_exhausted(e.to_string()),
+        WriteBufferError::NoWriteInReadOnly => Status::failed_precondition(e.to_string()),
+        _ => Status::internal(e.to_string()),
+    }
+}
+
+#[async_trait]
+impl Flight for FlightWriteService {
+    type HandshakeStream = BoxStream<'static, Result<HandshakeResponse, Status>>;
+    type ListFlightsStream = BoxStream<'static, Result<FlightInfo, Status>>;
+    type DoGetStream = BoxStream<'static, Result<FlightData, Status>>;
+    type DoPutStream = BoxStream<'static, Result<PutResult, Status>>;
+    type DoExchangeStream = BoxStream<'static, Result<FlightData, Status>>;
+    type DoActionStream = BoxStream<'static, Result<arrow_flight::Result, Status>>;
+    type ListActionsStream = BoxStream<'static, Result<ActionType, Status>>;
+
+    /// Write the record batches of the request, replying with a [`PutResult`] for each batch once
+    /// it has been written
+    async fn do_put(
+        &self,
+        request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoPutStream>, Status> {
+        self.authorize(request.metadata()).await?;
+        let mut stream = request.into_inner();
+        let first = stream
+            .message()
+            .await?
+            .ok_or_else(|| Status::invalid_argument("no flight data was sent"))?;
+        let (database, table) = write_target(first.flight_descriptor.as_ref())?;
+        info!("do_put to {}.{}", database, table);
+
+        let mut batches = FlightRecordBatchStream::new_from_flight_data(
+            futures::stream::once(futures::future::ready(Ok(first)))
+                .chain(stream)
+                .map_err(FlightError::Tonic),
+        );
+        let mut results = vec![];
+        while let Some(batch) = batches.try_next().await.map_err(|e| match e {
+            FlightError::Tonic(status) => status,
+            e => Status::invalid_argument(e.to_string()),
+        })? {
+            let write_bytes = batch.get_array_memory_size();
+            let result = self
+                .write_buffer
+                .write_record_batches(
+                    database.clone(),
+                    &table,
+                    vec![batch],
+                    self.time_provider.now(),
+                )
+                .await
+                .map_err(write_error_status)?;
+            self.telemetry_store
+                .add_write_metrics(result.line_count, write_bytes);
+            results.push(Ok(PutResult::default()));
+        }
+
+        Ok(Response::new(futures::stream::iter(results).boxed()))
+    }
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        Err(Status::unimplemented(
+            "handshake is served by the query service",
+        ))
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> Result<Response<Self::ListFlightsStream>, Status> {
+        Err(Status::unimplemented(
+            "list_flights is served by the query service",
+        ))
+    }
+
+    async fn get_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        Err(Status::unimplemented(
+            "get_flight_info is served by the query service",
+        ))
+    }
+
+    async fn poll_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<PollInfo>, Status> {
+        Err(Status::unimplemented(
+            "poll_flight_info is served by the query service",
+        ))
+    }
+
+    async fn get_schema(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>, Status> {
+        Err(Status::unimplemented(
+            "get_schema is served by the query service",
+        ))
+    }
+
+    async fn do_get(
+        &self,
+        _request: Request<Ticket>,
+    ) -> Result<Response<Self::DoGetStream>, Status> {
+        Err(Status::unimplemented(
+            "do_get is served by the query service",
+        ))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("do_exchange is not supported"))
+    }
+
+    async fn do_action(
+        &self,
+        _request: Request<Action>,
+    ) -> Result<Response<Self::DoActionStream>, Status> {
+        Err(Status::unimplemented(
+            "do_action is served by the query service",
+        ))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::ListActionsStream>, Status> {
+        Err(Status::unimplemented(
+            "list_actions is served by the query service",
+        ))
+    }
 }
@@ -17,16 +17,24 @@ use hyper::header::ACCEPT;
 use hyper::header::AUTHORIZATION;
 use hyper::header::CONTENT_ENCODING;
 use hyper::header::CONTENT_TYPE;
+use hyper::header::RETRY_AFTER;
 use hyper::http::HeaderValue;
 use hyper::HeaderMap;
 use hyper::{Body, Method, Request, Response, StatusCode};
 use influxdb3_catalog::catalog::Error as CatalogError;
+use influxdb3_catalog::export::CatalogExport;
 use influxdb3_process::{INFLUXDB3_GIT_HASH_SHORT, INFLUXDB3_VERSION};
-use influxdb3_wal::LastCacheDefinition;
+use influxdb3_wal::{
+    CoercionPolicy, DatabaseQuotas, DeletePredicate, JsonMapping, LastCacheDefinition, Quota,
+    SchemaPolicy, WalFileSequenceNumber,
+};
 use influxdb3_write::last_cache;
 use influxdb3_write::persister::TrackedMemoryArrowWriter;
+use influxdb3_write::write_buffer::csv_format::{CsvColumns, CsvFieldType, CsvTimeFormat};
+use influxdb3_write::write_buffer::subscriptions::{SubscriptionFilter, WriteRecord};
 use influxdb3_write::write_buffer::Error as WriteBufferError;
 use influxdb3_write::BufferedWriteRequest;
+use influxdb3_write::ParquetImportSource;
 use influxdb3_write::Precision;
 use influxdb3_write::WriteBuffer;
 use iox_http::write::single_tenant::SingleTenantRequestUnifier;
@@ -35,15 +43,19 @@ use iox_http::write::{WriteParseError, WriteRequestUnifier};
 use iox_query_influxql_rewrite as rewrite;
 use iox_query_params::StatementParams;
 use iox_time::TimeProvider;
+use object_store::path::Path as ObjPath;
 use observability_deps::tracing::{debug, error, info};
+use schema::InfluxFieldType;
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use serde::Serialize;
+use std::collections::BTreeMap;
 use std::convert::Infallible;
 use std::fmt::Debug;
 use std::pin::Pin;
 use std::str::Utf8Error;
 use std::string::FromUtf8Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
@@ -51,6 +63,18 @@ use unicode_segmentation::UnicodeSegmentation;
 
 mod v1;
 
+/// The number of seconds clients are told to wait before retrying a write that was rejected
+/// because the WAL buffer was full
+const BUFFER_FULL_RETRY_AFTER_SECONDS: u64 = 1;
+
+/// The number of seconds clients are told to wait before retrying a write that was rejected
+/// because it would exceed the write rate quota of its database
+const WRITE_RATE_RETRY_AFTER_SECONDS: u64 = 1;
+
+/// The header of write responses that has the number of field values in the write that were
+/// converted to the type of their column, if there were any
+const COERCED_VALUES_HEADER: &str = "x-influxdb3-coerced-values";
+
 #[derive(Debug, Error)]
 pub enum Error {
     /// The requested path has no registered handler.
@@ -179,6 +203,12 @@ pub enum Error {
     #[error("partial write of line protocol occurred")]
     PartialLpWrite(BufferedWriteRequest),
 
+    #[error("partial write of JSON documents occurred")]
+    PartialJsonWrite(BufferedWriteRequest),
+
+    #[error("partial write of CSV rows occurred")]
+    PartialCsvWrite(BufferedWriteRequest),
+
     #[error("error in InfluxQL statement: {0}")]
     InfluxqlRewrite(#[from] rewrite::Error),
 
@@ -238,6 +268,28 @@ impl Error {
                     .body(body)
                     .unwrap()
             }
+            Self::WriteBuffer(WriteBufferError::CatalogUpdateError(
+                err @ CatalogError::QuotaExceeded { quota, limit, .. },
+            )) => quota_exceeded_response(err.to_string(), quota, limit),
+            Self::WriteBuffer(err @ WriteBufferError::QuotaExceeded { quota, limit, .. }) => {
+                quota_exceeded_response(err.to_string(), quota, limit)
+            }
+            Self::WriteBuffer(WriteBufferError::CatalogUpdateError(
+                err @ (CatalogError::DatabaseAlreadyExists { .. }
+                | CatalogError::TableAlreadyExists { .. }
+                | CatalogError::ColumnAlreadyExists { .. }),
+            )) => Response::builder()
+                .status(StatusCode::CONFLICT)
+                .body(Body::from(err.to_string()))
+                .unwrap(),
+            Self::WriteBuffer(WriteBufferError::CatalogUpdateError(
+                err @ (CatalogError::DuplicateColumn { .. }
+                | CatalogError::ColumnNotFound { .. }
+                | CatalogError::CannotRenameColumn { .. }),
+            )) => Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(err.to_string()))
+                .unwrap(),
             Self::WriteBuffer(WriteBufferError::ParseError(err)) => {
                 let err = ErrorMessage {
                     error: "parsing failed for write_lp endpoint".into(),
@@ -250,6 +302,32 @@ impl Error {
                     .body(body)
                     .unwrap()
             }
+            Self::WriteBuffer(WriteBufferError::NoWriteInReadOnly) => Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Body::from(self.to_string()))
+                .unwrap(),
+            Self::WriteBuffer(WriteBufferError::WalError(influxdb3_wal::Error::BufferFull(_))) => {
+                Response::builder()
+                    .status(StatusCode::SERVICE_UNAVAILABLE)
+                    .header(RETRY_AFTER, BUFFER_FULL_RETRY_AFTER_SECONDS.to_string())
+                    .body(Body::from(self.to_string()))
+                    .unwrap()
+            }
+            Self::WriteBuffer(WriteBufferError::WalFileNotRetained(_)) => Response::builder()
+                .status(StatusCode::GONE)
+                .body(Body::from(self.to_string()))
+                .unwrap(),
+            Self::WriteBuffer(
+                WriteBufferError::InvalidDeletePredicate(_)
+                | WriteBufferError::NoJsonMapping
+                | WriteBufferError::InvalidJsonMapping(_)
+                | WriteBufferError::InvalidJson(_)
+                | WriteBufferError::InvalidCsv(_)
+                | WriteBufferError::InvalidParquetImport(_),
+            ) => Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(self.to_string()))
+                .unwrap(),
             Self::WriteBuffer(WriteBufferError::LastCacheError(ref lc_err)) => match lc_err {
                 last_cache::Error::InvalidCacheSize
                 | last_cache::Error::CacheAlreadyExists { .. }
@@ -276,6 +354,32 @@ impl Error {
                     .body(body)
                     .unwrap()
             }
+            Self::PartialJsonWrite(data) => {
+                let err = ErrorMessage {
+                    error: "partial write of JSON documents occurred".into(),
+                    data: Some(data.invalid_lines),
+                };
+                let serialized = serde_json::to_string(&err).unwrap();
+                let body = Body::from(serialized);
+                let response = Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(body)
+                    .unwrap();
+                with_coerced_count(response, data.coerced_count)
+            }
+            Self::PartialCsvWrite(data) => {
+                let err = ErrorMessage {
+                    error: "partial write of CSV rows occurred".into(),
+                    data: Some(data.invalid_lines),
+                };
+                let serialized = serde_json::to_string(&err).unwrap();
+                let body = Body::from(serialized);
+                let response = Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(body)
+                    .unwrap();
+                with_coerced_count(response, data.coerced_count)
+            }
             Self::PartialLpWrite(data) => {
                 let err = ErrorMessage {
                     error: "partial write of line protocol occurred".into(),
@@ -283,10 +387,11 @@ impl Error {
                 };
                 let serialized = serde_json::to_string(&err).unwrap();
                 let body = Body::from(serialized);
-                Response::builder()
+                let response = Response::builder()
                     .status(StatusCode::BAD_REQUEST)
                     .body(body)
-                    .unwrap()
+                    .unwrap();
+                with_coerced_count(response, data.coerced_count)
             }
             Self::UnsupportedMethod => {
                 let err: ErrorMessage<()> = ErrorMessage {
@@ -332,8 +437,8 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 #[derive(Debug)]
 pub(crate) struct HttpApi<Q, T> {
     common_state: CommonServerState,
-    write_buffer: Arc<dyn WriteBuffer>,
-    time_provider: Arc<T>,
+    pub(crate) write_buffer: Arc<dyn WriteBuffer>,
+    pub(crate) time_provider: Arc<T>,
     pub(crate) query_executor: Arc<Q>,
     max_request_bytes: usize,
     authorizer: Arc<dyn Authorizer>,
@@ -426,12 +531,139 @@ where
             .add_write_metrics(num_lines, payload_size);
 
         if result.invalid_lines.is_empty() {
-            Ok(Response::new(Body::empty()))
+            Ok(with_coerced_count(
+                Response::new(Body::empty()),
+                result.coerced_count,
+            ))
         } else {
             Err(Error::PartialLpWrite(result))
         }
     }
 
+    /// Write JSON documents, which are mapped to rows by the mapping given in the
+    /// [`JsonWriteParams`], or the one stored for the table
+    async fn write_json(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let query = req.uri().query().ok_or(Error::MissingWriteParams)?;
+        let params: JsonWriteParams = serde_urlencoded::from_str(query)?;
+        validate_db_name(&params.db, false)?;
+        info!("write_json to {}", params.db);
+        let mapping = params
+            .mapping
+            .as_deref()
+            .map(serde_json::from_str::<JsonMapping>)
+            .transpose()?;
+
+        let body = self.read_body(req).await?;
+        let body = std::str::from_utf8(&body).map_err(Error::NonUtf8Body)?;
+
+        let database = NamespaceName::new(params.db)?;
+
+        let result = self
+            .write_buffer
+            .write_json(
+                database,
+                params.table.as_deref(),
+                body,
+                mapping,
+                self.time_provider.now(),
+                params.accept_partial,
+            )
+            .await?;
+
+        self.common_state
+            .telemetry_store
+            .add_write_metrics(result.line_count, body.len());
+
+        if result.invalid_lines.is_empty() {
+            Ok(with_coerced_count(
+                Response::new(Body::empty()),
+                result.coerced_count,
+            ))
+        } else {
+            Err(Error::PartialJsonWrite(result))
+        }
+    }
+
+    /// Write annotated CSV, or plain CSV whose column roles are given in the [`CsvWriteParams`].
+    /// The body is streamed into the CSV reader as its rows are written, rather than read all at
+    /// once.
+    async fn write_csv(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let query = req.uri().query().ok_or(Error::MissingWriteParams)?;
+        let params: CsvWriteParams = serde_urlencoded::from_str(query)?;
+        validate_db_name(&params.db, false)?;
+        info!("write_csv to {}", params.db);
+        let columns = params.columns().map_err(WriteBufferError::InvalidCsv)?;
+
+        // the rows of a write that does not accept partial writes are all held at once, so the
+        // size of its body is limited as it would be if it were read all at once
+        let limit = (!params.accept_partial).then_some(self.max_request_bytes);
+        let bytes_read = Arc::new(AtomicUsize::new(0));
+        let body = self.stream_body(req, limit, Arc::clone(&bytes_read))?;
+
+        let database = NamespaceName::new(params.db)?;
+
+        let result = self
+            .write_buffer
+            .write_csv(
+                database,
+                body,
+                columns,
+                self.time_provider.now(),
+                params.accept_partial,
+            )
+            .await?;
+
+        self.common_state
+            .telemetry_store
+            .add_write_metrics(result.line_count, bytes_read.load(Ordering::Relaxed));
+
+        if result.invalid_lines.is_empty() {
+            Ok(with_coerced_count(
+                Response::new(Body::empty()),
+                result.coerced_count,
+            ))
+        } else {
+            Err(Error::PartialCsvWrite(result))
+        }
+    }
+
+    /// Import a parquet file into a table, either from the request body or, for files that are
+    /// larger than a request may be, from a `path` in the object store of the server, which is
+    /// streamed from it. The rows are persisted directly to object store in a snapshot of their
+    /// own, rather than being buffered and written to the WAL, and the response is the
+    /// [`influxdb3_write::ParquetImportSummary`] of the import.
+    async fn import_parquet(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let query = req.uri().query().ok_or(Error::MissingWriteParams)?;
+        let ParquetImportParams { db, table, path } = serde_urlencoded::from_str(query)?;
+        validate_db_name(&db, false)?;
+        info!("import_parquet to {}.{}", db, table);
+
+        let (source, body_len) = match path {
+            Some(path) => (ParquetImportSource::ObjectStore(ObjPath::from(path)), 0),
+            None => {
+                let body = self.read_body(req).await?;
+                let body_len = body.len();
+                (ParquetImportSource::Bytes(body), body_len)
+            }
+        };
+        let database = NamespaceName::new(db)?;
+
+        let summary = self
+            .write_buffer
+            .import_parquet(database, &table, source, self.time_provider.now())
+            .await?;
+
+        self.common_state
+            .telemetry_store
+            .add_write_metrics(summary.row_count as usize, body_len);
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+            .body(Body::from(serde_json::to_string(&summary).unwrap()))
+            .unwrap())
+    }
+
     async fn query_sql(&self, req: Request<Body>) -> Result<Response<Body>> {
         let QueryRequest {
             database,
@@ -507,16 +739,7 @@ where
     /// Parse the request's body into raw bytes, applying the configured size
     /// limits and decoding any content encoding.
     async fn read_body(&self, req: hyper::Request<Body>) -> Result<Bytes> {
-        let encoding = req
-            .headers()
-            .get(&CONTENT_ENCODING)
-            .map(|v| v.to_str().map_err(Error::NonUtf8ContentEncodingHeader))
-            .transpose()?;
-        let ungzip = match encoding {
-            None | Some("identity") => false,
-            Some("gzip") => true,
-            Some(v) => return Err(Error::InvalidContentEncoding(v.to_string())),
-        };
+        let ungzip = gzip_encoded(req.headers())?;
 
         let mut payload = req.into_body();
 
@@ -561,6 +784,45 @@ where
         Ok(decoded_data.into())
     }
 
+    /// Stream the request's body into a blocking reader, for writes that read their body as they
+    /// are written, decoding any content encoding. The size of the decoded body is limited to
+    /// `limit`, if it is given, and the number of decoded bytes that are read is added to
+    /// `bytes_read`.
+    fn stream_body(
+        &self,
+        req: hyper::Request<Body>,
+        limit: Option<usize>,
+        bytes_read: Arc<AtomicUsize>,
+    ) -> Result<Box<dyn std::io::Read + Send>> {
+        let ungzip = gzip_encoded(req.headers())?;
+
+        let (tx, rx) = tokio::sync::mpsc::channel(STREAMED_BODY_CHUNKS);
+        let mut payload = req.into_body();
+        tokio::spawn(async move {
+            while let Some(chunk) = payload.next().await {
+                // the reader is dropped once the write is done with the body
+                if tx.send(chunk).await.is_err() {
+                    break;
+                }
+            }
+        });
+        let body = StreamedBody {
+            chunks: rx,
+            chunk: Bytes::new(),
+        };
+        let decoded: Box<dyn std::io::Read + Send> = if ungzip {
+            Box::new(flate2::read::GzDecoder::new(body))
+        } else {
+            Box::new(body)
+        };
+
+        Ok(Box::new(LimitedBody {
+            inner: decoded,
+            limit,
+            bytes_read,
+        }))
+    }
+
     async fn authorize_request(&self, req: &mut Request<Body>) -> Result<(), AuthorizationError> {
         // Extend the request with the authorization token; this is used downstream in some
         // APIs, such as write, that need the full header value to authorize a request.
@@ -757,99 +1019,588 @@ where
             .unwrap())
     }
 
-    async fn read_body_json<ReqBody: DeserializeOwned>(
-        &self,
-        req: hyper::Request<Body>,
-    ) -> Result<ReqBody> {
-        if !json_content_type(req.headers()) {
-            return Err(Error::InvalidContentType {
-                expected: mime::APPLICATION_JSON,
-            });
-        }
-        let bytes = self.read_body(req).await?;
-        serde_json::from_slice(&bytes).map_err(Into::into)
+    /// Create a database that has no tables with the given [`DatabaseCreateRequest`]
+    async fn configure_database_create(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let DatabaseCreateRequest {
+            db,
+            retention_period,
+        } = self.read_body_json(req).await?;
+        validate_db_name(&db, false)?;
+
+        self.write_buffer
+            .create_database(
+                NamespaceName::new(db)?,
+                retention_period.map(Duration::from_secs),
+            )
+            .await?;
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::empty())
+            .unwrap())
     }
-}
 
-/// Check that the content type is application/json
-fn json_content_type(headers: &HeaderMap) -> bool {
-    let content_type = if let Some(content_type) = headers.get(CONTENT_TYPE) {
-        content_type
-    } else {
-        return false;
-    };
+    /// Create a table with the tags and fields declared in the given [`TableCreateRequest`]
+    ///
+    /// The database is created if it does not exist. Writes to the table are then validated
+    /// against the declared field types, in the same way as for tables created by a write.
+    async fn configure_table_create(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let TableCreateRequest {
+            db,
+            table,
+            tags,
+            fields,
+        } = self.read_body_json(req).await?;
+        validate_db_name(&db, false)?;
 
-    let content_type = if let Ok(content_type) = content_type.to_str() {
-        content_type
-    } else {
-        return false;
-    };
+        self.write_buffer
+            .create_table(
+                NamespaceName::new(db)?,
+                &table,
+                tags,
+                fields
+                    .into_iter()
+                    .map(|f| (f.name, f.field_type.into()))
+                    .collect(),
+            )
+            .await?;
 
-    let mime = if let Ok(mime) = content_type.parse::<mime::Mime>() {
-        mime
-    } else {
-        return false;
-    };
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::empty())
+            .unwrap())
+    }
 
-    let is_json_content_type = mime.type_() == "application"
-        && (mime.subtype() == "json" || mime.suffix().map_or(false, |name| name == "json"));
+    /// Set the schema policy of a table, or the default of a database, with the given
+    /// [`SchemaPolicyRequest`]
+    async fn configure_schema_policy(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let SchemaPolicyRequest { db, table, policy } = self.read_body_json(req).await?;
 
-    is_json_content_type
-}
+        let (db_id, db_schema) = self
+            .write_buffer
+            .db_schema_provider()
+            .db_schema_and_id(&db)
+            .ok_or_else(|| WriteBufferError::DbDoesNotExist)?;
+        let table_id = table
+            .map(|table| {
+                db_schema
+                    .table_name_to_id(table)
+                    .ok_or(WriteBufferError::TableDoesNotExist)
+            })
+            .transpose()?;
+        self.write_buffer
+            .set_schema_policy(db_id, table_id, policy)
+            .await?;
 
-#[derive(Debug, Deserialize)]
-struct V1AuthParameters {
-    #[serde(rename = "p")]
-    password: Option<String>,
-}
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::empty())
+            .unwrap())
+    }
 
-/// Extract the authentication token for v1 API requests, which may use the `p` query
-/// parameter to pass the authentication token.
-fn extract_v1_auth_token(req: &mut Request<Body>) -> Option<Vec<u8>> {
-    req.uri()
-        .path_and_query()
-        .and_then(|pq| match pq.path() {
-            "/query" | "/write" => pq.query(),
-            _ => None,
-        })
-        .map(serde_urlencoded::from_str::<V1AuthParameters>)
-        .transpose()
-        .ok()
-        .flatten()
-        .and_then(|params| params.password)
-        .map(String::into_bytes)
-}
+    /// Set the coercion policy of a table with the given [`CoercionPolicyRequest`]
+    async fn configure_coercion_policy(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let CoercionPolicyRequest { db, table, policy } = self.read_body_json(req).await?;
 
-fn validate_auth_header(header: HeaderValue) -> Result<Vec<u8>, AuthorizationError> {
-    // Split the header value into two parts
-    let mut header = header.to_str()?.split(' ');
+        let (db_id, db_schema) = self
+            .write_buffer
+            .db_schema_provider()
+            .db_schema_and_id(&db)
+            .ok_or_else(|| WriteBufferError::DbDoesNotExist)?;
+        let table_id = db_schema
+            .table_name_to_id(table)
+            .ok_or(WriteBufferError::TableDoesNotExist)?;
+        self.write_buffer
+            .set_coercion_policy(db_id, table_id, policy)
+            .await?;
 
-    // Check that the header is the 'Bearer' or 'Token' auth scheme
-    let auth_scheme = header.next().ok_or(AuthorizationError::MalformedRequest)?;
-    if auth_scheme != "Bearer" && auth_scheme != "Token" {
-        return Err(AuthorizationError::MalformedRequest);
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::empty())
+            .unwrap())
     }
 
-    // Get the token that we want to hash to check the request is valid
-    let token = header.next().ok_or(AuthorizationError::MalformedRequest)?;
+    /// Set or remove the JSON mapping of a table with the given [`JsonMappingRequest`]
+    async fn configure_json_mapping(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let JsonMappingRequest { db, table, mapping } = self.read_body_json(req).await?;
 
-    // There should only be two parts the 'Bearer' scheme and the actual
-    // token, error otherwise
-    if header.next().is_some() {
-        return Err(AuthorizationError::MalformedRequest);
+        let (db_id, db_schema) = self
+            .write_buffer
+            .db_schema_provider()
+            .db_schema_and_id(&db)
+            .ok_or_else(|| WriteBufferError::DbDoesNotExist)?;
+        let table_id = db_schema
+            .table_name_to_id(table)
+            .ok_or(WriteBufferError::TableDoesNotExist)?;
+        self.write_buffer
+            .set_json_mapping(db_id, table_id, mapping)
+            .await?;
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::empty())
+            .unwrap())
     }
 
-    Ok(token.as_bytes().to_vec())
-}
+    /// Set or remove the retention period of a database with the given
+    /// [`RetentionPeriodRequest`]
+    async fn configure_retention_period(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let RetentionPeriodRequest {
+            db,
+            retention_period,
+        } = self.read_body_json(req).await?;
 
-impl From<authz::Error> for AuthorizationError {
-    fn from(auth_error: authz::Error) -> Self {
-        match auth_error {
-            authz::Error::Forbidden => Self::Forbidden,
-            _ => Self::Unauthorized,
-        }
-    }
-}
+        let db_id = self
+            .write_buffer
+            .db_schema_provider()
+            .db_name_to_id(&db)
+            .ok_or_else(|| WriteBufferError::DbDoesNotExist)?;
+        self.write_buffer
+            .set_retention_period(db_id, retention_period.map(Duration::from_secs))
+            .await?;
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::empty())
+            .unwrap())
+    }
+
+    /// Set the quotas of a database with the given [`QuotasRequest`], replacing the ones it had
+    async fn configure_quotas(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let QuotasRequest { db, quotas } = self.read_body_json(req).await?;
+
+        let db_id = self
+            .write_buffer
+            .db_schema_provider()
+            .db_name_to_id(&db)
+            .ok_or_else(|| WriteBufferError::DbDoesNotExist)?;
+        self.write_buffer.set_quotas(db_id, quotas).await?;
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::empty())
+            .unwrap())
+    }
+
+    /// Export the databases in the catalog, along with their tables and last caches, as a
+    /// [`CatalogExport`] document
+    fn configure_catalog_export(&self) -> Result<Response<Body>> {
+        let export = self.write_buffer.export_catalog();
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+            .body(Body::from(serde_json::to_string(&export).unwrap()))
+            .unwrap())
+    }
+
+    /// Import a [`CatalogExport`] document from the request body, creating whatever it has that
+    /// is missing from the catalog
+    ///
+    /// The response is the diff of the import. Nothing is imported on a dry run, nor if there are
+    /// any conflicts with the catalog, which get a `409 Conflict` response.
+    async fn configure_catalog_import(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let CatalogImportParams { dry_run } = match req.uri().query() {
+            Some(query) => serde_urlencoded::from_str(query)?,
+            None => CatalogImportParams::default(),
+        };
+        let export: CatalogExport = self.read_body_json(req).await?;
+
+        let diff = self.write_buffer.import_catalog(&export, dry_run).await?;
+        let status = if diff.conflicts.is_empty() {
+            StatusCode::OK
+        } else {
+            StatusCode::CONFLICT
+        };
+
+        Ok(Response::builder()
+            .status(status)
+            .header(CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+            .body(Body::from(serde_json::to_string(&diff).unwrap()))
+            .unwrap())
+    }
+
+    /// Rename a table with the given [`TableRenameRequest`]
+    ///
+    /// The table keeps its id, so that its buffered and persisted data is queried under the new
+    /// name.
+    async fn configure_table_rename(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let TableRenameRequest {
+            db,
+            table,
+            new_name,
+        } = self.read_body_json(req).await?;
+
+        let (db_id, db_schema) = self
+            .write_buffer
+            .db_schema_provider()
+            .db_schema_and_id(&db)
+            .ok_or_else(|| WriteBufferError::DbDoesNotExist)?;
+        let table_id = db_schema
+            .table_name_to_id(table)
+            .ok_or(WriteBufferError::TableDoesNotExist)?;
+        self.write_buffer
+            .rename_table(db_id, table_id, &new_name)
+            .await?;
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::empty())
+            .unwrap())
+    }
+
+    /// Rename a column of a table with the given [`ColumnRenameRequest`]
+    ///
+    /// Parquet files persisted before the rename keep the old name of the column, which is
+    /// translated to the new name when they are queried.
+    async fn configure_column_rename(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let ColumnRenameRequest {
+            db,
+            table,
+            column,
+            new_name,
+        } = self.read_body_json(req).await?;
+
+        let (db_id, db_schema) = self
+            .write_buffer
+            .db_schema_provider()
+            .db_schema_and_id(&db)
+            .ok_or_else(|| WriteBufferError::DbDoesNotExist)?;
+        let table_id = db_schema
+            .table_name_to_id(table)
+            .ok_or(WriteBufferError::TableDoesNotExist)?;
+        self.write_buffer
+            .rename_column(db_id, table_id, &column, &new_name)
+            .await?;
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::empty())
+            .unwrap())
+    }
+
+    /// Delete a database, along with all of its tables, with the given [`DatabaseDeleteRequest`]
+    ///
+    /// The parameters are parsed from the URI query string if one is provided, otherwise from the
+    /// request body as JSON. The database is no longer queryable once this returns, and its files
+    /// are removed from object store in the background.
+    async fn configure_database_delete(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let DatabaseDeleteRequest { db } = if let Some(query) = req.uri().query() {
+            serde_urlencoded::from_str(query)?
+        } else {
+            self.read_body_json(req).await?
+        };
+
+        let db_id = self
+            .write_buffer
+            .db_schema_provider()
+            .db_name_to_id(&db)
+            .ok_or_else(|| WriteBufferError::DbDoesNotExist)?;
+        self.write_buffer.delete_database(db_id).await?;
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::empty())
+            .unwrap())
+    }
+
+    /// Delete a table with the given [`TableDeleteRequest`]
+    ///
+    /// The parameters are parsed the same way as for deleting a database.
+    async fn configure_table_delete(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let TableDeleteRequest { db, table } = if let Some(query) = req.uri().query() {
+            serde_urlencoded::from_str(query)?
+        } else {
+            self.read_body_json(req).await?
+        };
+
+        let (db_id, db_schema) = self
+            .write_buffer
+            .db_schema_provider()
+            .db_schema_and_id(&db)
+            .ok_or_else(|| WriteBufferError::DbDoesNotExist)?;
+        let table_id = db_schema
+            .table_name_to_id(table)
+            .ok_or_else(|| WriteBufferError::TableDoesNotExist)?;
+        self.write_buffer.delete_table(db_id, table_id).await?;
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::empty())
+            .unwrap())
+    }
+
+    /// Delete the rows from a table that match the given [`DeleteRequest`]
+    ///
+    /// The delete is applied to buffered data immediately, and to persisted data by tombstones
+    /// that are honored at query time.
+    async fn delete(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let DeleteRequest {
+            db,
+            table,
+            min_time,
+            max_time,
+            tags,
+        } = self.read_body_json(req).await?;
+
+        let (db_id, db_schema) = self
+            .write_buffer
+            .db_schema_provider()
+            .db_schema_and_id(&db)
+            .ok_or_else(|| WriteBufferError::DbDoesNotExist)?;
+        let table_id = db_schema
+            .table_name_to_id(table.as_str())
+            .ok_or_else(|| WriteBufferError::TableDoesNotExist)?;
+
+        let predicate = DeletePredicate {
+            min_time_ns: min_time,
+            max_time_ns: max_time,
+            tags: tags
+                .into_iter()
+                .map(|(tag, value)| (tag.into(), value.into()))
+                .collect(),
+        };
+        self.write_buffer
+            .delete_rows(db_id, table_id, predicate)
+            .await?;
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::empty())
+            .unwrap())
+    }
+
+    /// Stream the writes that match the [`SubscribeRequest`] as they are persisted to the WAL
+    ///
+    /// The stream starts with the writes in the retained WAL files from `from` on, if it is given,
+    /// and continues until the client disconnects.
+    async fn subscribe(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let SubscribeRequest {
+            db,
+            table,
+            from,
+            format,
+        } = serde_urlencoded::from_str(req.uri().query().unwrap_or_default())?;
+
+        if let Some(db) = &db {
+            self.write_buffer
+                .db_schema_provider()
+                .db_schema(db)
+                .ok_or_else(|| WriteBufferError::DbDoesNotExist)?;
+        }
+
+        info!(?db, ?table, ?from, ?format, "handling subscribe");
+        let subscription = self
+            .write_buffer
+            .write_subscriptions()
+            .subscribe(
+                SubscriptionFilter {
+                    database: db,
+                    table,
+                },
+                from.map(WalFileSequenceNumber::new),
+            )
+            .await?;
+        let body = Body::wrap_stream(
+            subscription.map(move |records| Ok::<_, Infallible>(format.records_to_bytes(records))),
+        );
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, format.as_content_type())
+            .body(body)
+            .unwrap())
+    }
+
+    async fn read_body_json<ReqBody: DeserializeOwned>(
+        &self,
+        req: hyper::Request<Body>,
+    ) -> Result<ReqBody> {
+        if !json_content_type(req.headers()) {
+            return Err(Error::InvalidContentType {
+                expected: mime::APPLICATION_JSON,
+            });
+        }
+        let bytes = self.read_body(req).await?;
+        serde_json::from_slice(&bytes).map_err(Into::into)
+    }
+}
+
+/// Whether the body of a request is gzip-encoded, according to its `Content-Encoding` header
+fn gzip_encoded(headers: &HeaderMap) -> Result<bool> {
+    let encoding = headers
+        .get(&CONTENT_ENCODING)
+        .map(|v| v.to_str().map_err(Error::NonUtf8ContentEncodingHeader))
+        .transpose()?;
+    match encoding {
+        None | Some("identity") => Ok(false),
+        Some("gzip") => Ok(true),
+        Some(v) => Err(Error::InvalidContentEncoding(v.to_string())),
+    }
+}
+
+/// The number of chunks of a streamed body that are received before they are read
+const STREAMED_BODY_CHUNKS: usize = 8;
+
+/// A blocking reader of the chunks of a request body, which are sent to it as they are received
+#[derive(Debug)]
+struct StreamedBody {
+    chunks: tokio::sync::mpsc::Receiver<Result<Bytes, hyper::Error>>,
+    /// The rest of the chunk being read
+    chunk: Bytes,
+}
+
+impl std::io::Read for StreamedBody {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.chunks.blocking_recv() {
+                Some(chunk) => self.chunk = chunk.map_err(std::io::Error::other)?,
+                None => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.chunk.len());
+        buf[..n].copy_from_slice(&self.chunk.split_to(n));
+        Ok(n)
+    }
+}
+
+/// A reader of a decoded request body that fails once more than its limit has been read
+struct LimitedBody {
+    inner: Box<dyn std::io::Read + Send>,
+    limit: Option<usize>,
+    bytes_read: Arc<AtomicUsize>,
+}
+
+impl std::io::Read for LimitedBody {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        let bytes_read = self.bytes_read.fetch_add(n, Ordering::Relaxed) + n;
+        match self.limit {
+            Some(limit) if bytes_read > limit => Err(std::io::Error::other(format!(
+                "the request body is larger than the limit of {limit} bytes"
+            ))),
+            _ => Ok(n),
+        }
+    }
+}
+
+/// Add the [`COERCED_VALUES_HEADER`] to a write response
+fn with_coerced_count(mut response: Response<Body>, coerced_count: usize) -> Response<Body> {
+    if coerced_count > 0 {
+        response
+            .headers_mut()
+            .insert(COERCED_VALUES_HEADER, HeaderValue::from(coerced_count));
+    }
+    response
+}
+
+/// The data of the error returned for a request that would exceed a quota of a database
+#[derive(Debug, Serialize)]
+struct QuotaExceededData {
+    quota: &'static str,
+    limit: u64,
+}
+
+/// The response to a request that would exceed a quota of a database. Exceeding the write rate
+/// quota is temporary, so clients are told when to retry, while the other quotas are only freed up
+/// by changing the database or its quotas.
+fn quota_exceeded_response(error: String, quota: Quota, limit: u64) -> Response<Body> {
+    let err = ErrorMessage {
+        error,
+        data: Some(QuotaExceededData {
+            quota: quota.as_str(),
+            limit,
+        }),
+    };
+    let body = Body::from(serde_json::to_string(&err).unwrap());
+    let builder = if quota == Quota::WriteBytesPerSecond {
+        Response::builder()
+            .status(StatusCode::TOO_MANY_REQUESTS)
+            .header(RETRY_AFTER, WRITE_RATE_RETRY_AFTER_SECONDS.to_string())
+    } else {
+        Response::builder().status(StatusCode::UNPROCESSABLE_ENTITY)
+    };
+    builder.body(body).unwrap()
+}
+
+/// Check that the content type is application/json
+fn json_content_type(headers: &HeaderMap) -> bool {
+    let content_type = if let Some(content_type) = headers.get(CONTENT_TYPE) {
+        content_type
+    } else {
+        return false;
+    };
+
+    let content_type = if let Ok(content_type) = content_type.to_str() {
+        content_type
+    } else {
+        return false;
+    };
+
+    let mime = if let Ok(mime) = content_type.parse::<mime::Mime>() {
+        mime
+    } else {
+        return false;
+    };
+
+    let is_json_content_type = mime.type_() == "application"
+        && (mime.subtype() == "json" || mime.suffix().map_or(false, |name| name == "json"));
+
+    is_json_content_type
+}
+
+#[derive(Debug, Deserialize)]
+struct V1AuthParameters {
+    #[serde(rename = "p")]
+    password: Option<String>,
+}
+
+/// Extract the authentication token for v1 API requests, which may use the `p` query
+/// parameter to pass the authentication token.
+fn extract_v1_auth_token(req: &mut Request<Body>) -> Option<Vec<u8>> {
+    req.uri()
+        .path_and_query()
+        .and_then(|pq| match pq.path() {
+            "/query" | "/write" => pq.query(),
+            _ => None,
+        })
+        .map(serde_urlencoded::from_str::<V1AuthParameters>)
+        .transpose()
+        .ok()
+        .flatten()
+        .and_then(|params| params.password)
+        .map(String::into_bytes)
+}
+
+fn validate_auth_header(header: HeaderValue) -> Result<Vec<u8>, AuthorizationError> {
+    // Split the header value into two parts
+    let mut header = header.to_str()?.split(' ');
+
+    // Check that the header is the 'Bearer' or 'Token' auth scheme
+    let auth_scheme = header.next().ok_or(AuthorizationError::MalformedRequest)?;
+    if auth_scheme != "Bearer" && auth_scheme != "Token" {
+        return Err(AuthorizationError::MalformedRequest);
+    }
+
+    // Get the token that we want to hash to check the request is valid
+    let token = header.next().ok_or(AuthorizationError::MalformedRequest)?;
+
+    // There should only be two parts the 'Bearer' scheme and the actual
+    // token, error otherwise
+    if header.next().is_some() {
+        return Err(AuthorizationError::MalformedRequest);
+    }
+
+    Ok(token.as_bytes().to_vec())
+}
+
+impl From<authz::Error> for AuthorizationError {
+    fn from(auth_error: authz::Error) -> Self {
+        match auth_error {
+            authz::Error::Forbidden => Self::Forbidden,
+            _ => Self::Unauthorized,
+        }
+    }
+}
 
 /// Validate a database name
 ///
@@ -1036,6 +1787,84 @@ pub(crate) struct WriteParams {
     pub(crate) precision: Precision,
 }
 
+/// Query parameters for the `POST /api/v3/write_json` API
+#[derive(Debug, Deserialize)]
+struct JsonWriteParams {
+    db: String,
+    /// The table documents are written to, unless the mapping gives a measurement path, and the
+    /// table whose stored mapping is used if no mapping is given
+    table: Option<String>,
+    #[serde(default = "true_fn")]
+    accept_partial: bool,
+    /// The JSON of the mapping to write the documents with
+    mapping: Option<String>,
+}
+
+/// Query parameters for the `POST /api/v3/write_csv` API. The column roles are only given for
+/// plain CSV, as annotated CSV has them in its annotations.
+#[derive(Debug, Deserialize)]
+struct CsvWriteParams {
+    db: String,
+    #[serde(default = "true_fn")]
+    accept_partial: bool,
+    /// The measurement of every row
+    measurement: Option<String>,
+    /// The column with the measurement of each row
+    measurement_column: Option<String>,
+    /// The tag columns, separated by commas
+    tags: Option<String>,
+    /// The field columns with their types, as `name:type` separated by commas
+    fields: Option<String>,
+    time_column: Option<String>,
+    time_format: Option<String>,
+}
+
+impl CsvWriteParams {
+    /// The roles of the columns of plain CSV, or `None` if no roles are given
+    fn columns(&self) -> std::result::Result<Option<CsvColumns>, String> {
+        if self.measurement.is_none()
+            && self.measurement_column.is_none()
+            && self.tags.is_none()
+            && self.fields.is_none()
+            && self.time_column.is_none()
+            && self.time_format.is_none()
+        {
+            return Ok(None);
+        }
+        let list = |list: &Option<String>| {
+            list.iter()
+                .flat_map(|list| list.split(','))
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let fields = list(&self.fields)
+            .into_iter()
+            .map(|field| {
+                let (name, field_type) = field
+                    .rsplit_once(':')
+                    .ok_or_else(|| format!("field {field} is not given as name:type"))?;
+                Ok((name.to_string(), field_type.parse::<CsvFieldType>()?))
+            })
+            .collect::<std::result::Result<_, String>>()?;
+
+        Ok(Some(CsvColumns {
+            measurement: self.measurement.clone(),
+            measurement_column: self.measurement_column.clone(),
+            tags: list(&self.tags),
+            fields,
+            time_column: self.time_column.clone(),
+            time_format: self
+                .time_format
+                .as_deref()
+                .map(str::parse::<CsvTimeFormat>)
+                .transpose()?
+                .unwrap_or_default(),
+        }))
+    }
+}
+
 impl From<iox_http::write::WriteParams> for WriteParams {
     fn from(legacy: iox_http::write::WriteParams) -> Self {
         Self {
@@ -1070,6 +1899,218 @@ struct LastCacheDeleteRequest {
     name: String,
 }
 
+/// Request definition for the `POST /api/v3/configure/database` API
+#[derive(Debug, Deserialize)]
+struct DatabaseCreateRequest {
+    db: String,
+    /// How long data is kept for, in seconds, otherwise it is kept forever
+    retention_period: Option<u64>,
+}
+
+/// Request definition for the `POST /api/v3/configure/table` API
+#[derive(Debug, Deserialize)]
+struct TableCreateRequest {
+    db: String,
+    table: String,
+    /// The tag columns, which make up the series key of the table
+    #[serde(default)]
+    tags: Vec<String>,
+    fields: Vec<FieldDefinitionRequest>,
+}
+
+/// A field declared in a [`TableCreateRequest`]
+#[derive(Debug, Deserialize)]
+struct FieldDefinitionRequest {
+    name: String,
+    #[serde(rename = "type")]
+    field_type: FieldTypeRequest,
+}
+
+/// The type of a field, named the same as the line protocol field value types
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FieldTypeRequest {
+    String,
+    Integer,
+    #[serde(alias = "unsigned_integer")]
+    Uinteger,
+    Float,
+    Boolean,
+}
+
+impl From<FieldTypeRequest> for InfluxFieldType {
+    fn from(field_type: FieldTypeRequest) -> Self {
+        match field_type {
+            FieldTypeRequest::String => Self::String,
+            FieldTypeRequest::Integer => Self::Integer,
+            FieldTypeRequest::Uinteger => Self::UInteger,
+            FieldTypeRequest::Float => Self::Float,
+            FieldTypeRequest::Boolean => Self::Boolean,
+        }
+    }
+}
+
+/// Request definition for the `POST /api/v3/configure/schema_policy` API
+#[derive(Debug, Deserialize)]
+struct SchemaPolicyRequest {
+    db: String,
+    /// The table to set the policy of, otherwise the default policy of the database is set
+    table: Option<String>,
+    policy: SchemaPolicy,
+}
+
+/// Request definition for the `POST /api/v3/configure/table/coercion_policy` API
+#[derive(Debug, Deserialize)]
+struct CoercionPolicyRequest {
+    db: String,
+    table: String,
+    #[serde(flatten)]
+    policy: CoercionPolicy,
+}
+
+/// Request definition for the `POST /api/v3/configure/table/json_mapping` API
+#[derive(Debug, Deserialize)]
+struct JsonMappingRequest {
+    db: String,
+    table: String,
+    /// The mapping to store for the table, or `None` to remove it
+    mapping: Option<JsonMapping>,
+}
+
+/// Request definition for the `POST /api/v3/configure/database/retention` API
+#[derive(Debug, Deserialize)]
+struct RetentionPeriodRequest {
+    db: String,
+    /// How long data is kept for, in seconds, or `None` to keep it forever
+    retention_period: Option<u64>,
+}
+
+/// Request definition for the `POST /api/v3/configure/database/quotas` API
+#[derive(Debug, Deserialize)]
+struct QuotasRequest {
+    db: String,
+    /// The quotas that are not given are unlimited
+    #[serde(flatten)]
+    quotas: DatabaseQuotas,
+}
+
+/// Query parameters for the `POST /api/v3/import/parquet` API
+#[derive(Debug, Deserialize)]
+struct ParquetImportParams {
+    db: String,
+    table: String,
+    /// The path of the file in the object store of the server, if it is not in the request body
+    #[serde(default)]
+    path: Option<String>,
+}
+
+/// Query parameters for the `POST /api/v3/configure/catalog` API
+#[derive(Debug, Default, Deserialize)]
+struct CatalogImportParams {
+    /// Only report the changes that the import would make, without making them
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Request definition for the `POST /api/v3/configure/table/rename` API
+#[derive(Debug, Deserialize)]
+struct TableRenameRequest {
+    db: String,
+    table: String,
+    new_name: String,
+}
+
+/// Request definition for the `POST /api/v3/configure/column/rename` API
+#[derive(Debug, Deserialize)]
+struct ColumnRenameRequest {
+    db: String,
+    table: String,
+    column: String,
+    new_name: String,
+}
+
+/// Request definition for the `DELETE /api/v3/configure/database` API
+#[derive(Debug, Deserialize)]
+struct DatabaseDeleteRequest {
+    db: String,
+}
+
+/// Request definition for the `DELETE /api/v3/configure/table` API
+#[derive(Debug, Deserialize)]
+struct TableDeleteRequest {
+    db: String,
+    table: String,
+}
+
+/// Request definition for the `POST /api/v3/delete` API
+#[derive(Debug, Deserialize)]
+struct DeleteRequest {
+    db: String,
+    table: String,
+    /// The inclusive lower bound of the time range to delete, in nanoseconds
+    #[serde(default = "min_delete_time")]
+    min_time: i64,
+    /// The inclusive upper bound of the time range to delete, in nanoseconds
+    #[serde(default = "max_delete_time")]
+    max_time: i64,
+    /// Only rows with all of these tag values are deleted
+    #[serde(default)]
+    tags: BTreeMap<String, String>,
+}
+
+/// Request definition for the `GET /api/v3/subscribe` API
+#[derive(Debug, Deserialize)]
+struct SubscribeRequest {
+    /// Only stream the writes to this database
+    db: Option<String>,
+    /// Only stream the writes to this table
+    table: Option<String>,
+    /// Resume the stream from this WAL file, if it is still retained
+    from: Option<u64>,
+    #[serde(default)]
+    format: SubscribeFormat,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SubscribeFormat {
+    /// A JSON object per row, separated by newlines
+    #[default]
+    Json,
+    /// A line of line protocol per row
+    Lp,
+}
+
+impl SubscribeFormat {
+    fn as_content_type(&self) -> &str {
+        match self {
+            Self::Json => "application/x-ndjson",
+            Self::Lp => "text/plain; charset=utf-8",
+        }
+    }
+
+    fn records_to_bytes(&self, records: Vec<WriteRecord>) -> Bytes {
+        let mut bytes = Vec::new();
+        for record in records {
+            match self {
+                Self::Json => serde_json::to_writer(&mut bytes, &record)
+                    .expect("write records should serialize to json"),
+                Self::Lp => bytes.extend_from_slice(record.to_line_protocol().as_bytes()),
+            }
+            bytes.push(b'\n');
+        }
+        Bytes::from(bytes)
+    }
+}
+
+fn min_delete_time() -> i64 {
+    i64::MIN
+}
+
+fn max_delete_time() -> i64 {
+    i64::MAX
+}
+
 pub(crate) async fn route_request<Q: QueryExecutor, T: TimeProvider>(
     http_server: Arc<HttpApi<Q, T>>,
     mut req: Request<Body>,
@@ -1134,6 +2175,9 @@ where
         }
         (Method::POST, "/api/v3/write") => http_server.write_v3(req).await,
         (Method::POST, "/api/v3/write_lp") => http_server.write_lp(req).await,
+        (Method::POST, "/api/v3/write_json") => http_server.write_json(req).await,
+        (Method::POST, "/api/v3/write_csv") => http_server.write_csv(req).await,
+        (Method::POST, "/api/v3/import/parquet") => http_server.import_parquet(req).await,
         (Method::GET | Method::POST, "/api/v3/query_sql") => http_server.query_sql(req).await,
         (Method::GET | Method::POST, "/api/v3/query_influxql") => {
             http_server.query_influxql(req).await
@@ -1142,12 +2186,49 @@ where
         (Method::GET, "/health" | "/api/v1/health") => http_server.health(),
         (Method::GET | Method::POST, "/ping") => http_server.ping(),
         (Method::GET, "/metrics") => http_server.handle_metrics(),
+        (Method::POST, "/api/v3/delete") => http_server.delete(req).await,
+        (Method::GET, "/api/v3/subscribe") => http_server.subscribe(req).await,
         (Method::POST, "/api/v3/configure/last_cache") => {
             http_server.configure_last_cache_create(req).await
         }
         (Method::DELETE, "/api/v3/configure/last_cache") => {
             http_server.configure_last_cache_delete(req).await
         }
+        (Method::POST, "/api/v3/configure/database") => {
+            http_server.configure_database_create(req).await
+        }
+        (Method::POST, "/api/v3/configure/database/retention") => {
+            http_server.configure_retention_period(req).await
+        }
+        (Method::POST, "/api/v3/configure/database/quotas") => {
+            http_server.configure_quotas(req).await
+        }
+        (Method::POST, "/api/v3/configure/table") => http_server.configure_table_create(req).await,
+        (Method::GET, "/api/v3/configure/catalog") => http_server.configure_catalog_export(),
+        (Method::POST, "/api/v3/configure/catalog") => {
+            http_server.configure_catalog_import(req).await
+        }
+        (Method::POST, "/api/v3/configure/schema_policy") => {
+            http_server.configure_schema_policy(req).await
+        }
+        (Method::POST, "/api/v3/configure/table/coercion_policy") => {
+            http_server.configure_coercion_policy(req).await
+        }
+        (Method::POST, "/api/v3/configure/table/json_mapping") => {
+            http_server.configure_json_mapping(req).await
+        }
+        (Method::POST, "/api/v3/configure/table/rename") => {
+            http_server.configure_table_rename(req).await
+        }
+        (Method::POST, "/api/v3/configure/column/rename") => {
+            http_server.configure_column_rename(req).await
+        }
+        (Method::DELETE, "/api/v3/configure/database") => {
+            http_server.configure_database_delete(req).await
+        }
+        (Method::DELETE, "/api/v3/configure/table") => {
+            http_server.configure_table_delete(req).await
+        }
         _ => {
             let body = Body::from("not found");
             Ok(Response::builder()
@@ -1189,6 +2270,11 @@ fn legacy_write_error_to_response(e: WriteParseError) -> Response<Body> {
 mod tests {
     use super::validate_db_name;
     use super::ValidateDbNameError;
+    use super::{LimitedBody, StreamedBody, STREAMED_BODY_CHUNKS};
+    use bytes::Bytes;
+    use std::io::Read;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
 
     macro_rules! assert_validate_db_name {
         ($name:literal, $accept_rp:literal, $expected:pat) => {
@@ -1216,4 +2302,38 @@ mod tests {
         assert_validate_db_name!("_foo", false, Err(ValidateDbNameError::InvalidStartChar));
         assert_validate_db_name!("", false, Err(ValidateDbNameError::Empty));
     }
+
+    #[tokio::test]
+    async fn streamed_body_is_read_up_to_its_limit() {
+        let read = |limit: Option<usize>| async move {
+            let (tx, rx) = tokio::sync::mpsc::channel(STREAMED_BODY_CHUNKS);
+            tokio::spawn(async move {
+                for chunk in ["v\n1\n", "2\n", "3\n"] {
+                    tx.send(Ok(Bytes::from(chunk))).await.unwrap();
+                }
+            });
+            let bytes_read = Arc::new(AtomicUsize::new(0));
+            let mut body = LimitedBody {
+                inner: Box::new(StreamedBody {
+                    chunks: rx,
+                    chunk: Bytes::new(),
+                }),
+                limit,
+                bytes_read: Arc::clone(&bytes_read),
+            };
+            let body = tokio::task::spawn_blocking(move || {
+                let mut read = String::new();
+                body.read_to_string(&mut read).map(|_| read)
+            })
+            .await
+            .unwrap();
+            (body, bytes_read.load(Ordering::Relaxed))
+        };
+
+        let (body, bytes_read) = read(None).await;
+        assert_eq!("v\n1\n2\n3\n", body.unwrap());
+        assert_eq!(8, bytes_read);
+        let (body, _) = read(Some(6)).await;
+        assert!(body.is_err());
+    }
 }
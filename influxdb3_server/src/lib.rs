@@ -19,7 +19,7 @@ pub mod query_executor;
 mod service;
 mod system_tables;
 
-use crate::grpc::make_flight_server;
+use crate::grpc::{make_flight_server, FlightWriteService};
 use crate::http::route_request;
 use crate::http::HttpApi;
 use async_trait::async_trait;
@@ -178,8 +178,15 @@ where
         TRACE_SERVER_NAME,
     );
 
+    let write_service = FlightWriteService::new(
+        Arc::clone(&server.http.write_buffer),
+        Arc::<T>::clone(&server.http.time_provider),
+        Arc::clone(&server.common_state.telemetry_store),
+        Some(server.authorizer()),
+    );
     let grpc_service = trace_layer.clone().layer(make_flight_server(
         Arc::clone(&server.http.query_executor),
+        write_service,
         Some(server.authorizer()),
     ));
 
@@ -236,9 +243,10 @@ mod tests {
     use influxdb3_catalog::catalog::Catalog;
     use influxdb3_id::{DbId, TableId};
     use influxdb3_telemetry::store::TelemetryStore;
-    use influxdb3_wal::WalConfig;
+    use influxdb3_wal::{WalBackend, WalConfig};
     use influxdb3_write::parquet_cache::test_cached_obj_store_and_oracle;
     use influxdb3_write::persister::Persister;
+    use influxdb3_write::write_buffer::rejected_writes::RejectedWritesConfig;
     use influxdb3_write::WriteBuffer;
     use influxdb3_write::{
         last_cache::LastCacheProvider, write_buffer::persisted_files::PersistedFiles,
@@ -783,7 +791,10 @@ mod tests {
                 Arc::<MockProvider>::clone(&time_provider),
                 Arc::clone(&exec),
                 WalConfig::test_config(),
+                WalBackend::ObjectStore,
+                Arc::new(metric::Registry::default()),
                 Some(parquet_cache),
+                RejectedWritesConfig::default(),
             )
             .await
             .unwrap(),
@@ -14,6 +14,7 @@ use datafusion::datasource::{TableProvider, TableType};
 use datafusion::error::DataFusionError;
 use datafusion::execution::SendableRecordBatchStream;
 use datafusion::logical_expr::TableProviderFilterPushDown;
+use datafusion::physical_plan::union::UnionExec;
 use datafusion::physical_plan::ExecutionPlan;
 use datafusion::prelude::Expr;
 use datafusion_util::config::DEFAULT_SCHEMA;
@@ -21,6 +22,7 @@ use datafusion_util::MemoryStream;
 use influxdb3_catalog::catalog::DatabaseSchema;
 use influxdb3_catalog::DatabaseSchemaProvider;
 use influxdb3_telemetry::store::TelemetryStore;
+use influxdb3_write::chunk::scan_rewritten_chunks;
 use influxdb3_write::last_cache::LastCacheFunction;
 use influxdb3_write::WriteBuffer;
 use iox_query::exec::{Executor, IOxSessionContext, QueryConfig};
@@ -205,18 +207,19 @@ impl QueryExecutor for QueryExecutorImpl {
         // sort them to ensure consistent order:
         databases.sort_unstable();
 
+        let _span_recorder = SpanRecorder::new(span_ctx.child_span("get databases"));
         let mut rows = Vec::with_capacity(databases.len());
         for database in databases {
-            let db = self
-                .namespace(&database, span_ctx.child_span("get database"), false)
-                .await
-                .map_err(|_| Error::DatabaseNotFound {
-                    db_name: database.to_string(),
-                })?
+            let db_schema = self
+                .db_schema_provider
+                .db_schema(&database)
                 .ok_or_else(|| Error::DatabaseNotFound {
                     db_name: database.to_string(),
                 })?;
-            let duration = db.retention_time_ns();
+            // the duration is the retention period itself, which is null if data is kept forever
+            let duration = db_schema
+                .retention_period_ns
+                .map(|ns| i64::try_from(ns).unwrap_or(i64::MAX));
             let (db_name, rp_name) = split_database_name(&database);
             rows.push(RetentionPolicyRow {
                 database: db_name,
@@ -421,11 +424,22 @@ impl QueryNamespace for Database {
             return Ok(vec![]);
         };
 
-        table.chunks(&ctx.inner().state(), projection, filters, None)
+        let (chunks, rewritten) = table
+            .chunks(&ctx.inner().state(), projection, filters, None)
+            .await?;
+        // the files with deleted rows or renamed columns can only be read through the scan of
+        // the table, not as chunks
+        if !rewritten.is_empty() {
+            return Err(DataFusionError::NotImplemented(format!(
+                "reading the chunks of table {table_name}, which has persisted files with deleted \
+                rows or renamed columns"
+            )));
+        }
+        Ok(chunks)
     }
 
     fn retention_time_ns(&self) -> Option<i64> {
-        None
+        self.write_buffer.retention_cutoff_ns(self.db_schema.id)
     }
 
     fn record_query(
@@ -540,20 +554,23 @@ pub struct QueryTable {
 }
 
 impl QueryTable {
-    fn chunks(
+    async fn chunks(
         &self,
         ctx: &dyn Session,
         projection: Option<&Vec<usize>>,
         filters: &[Expr],
         _limit: Option<usize>,
-    ) -> Result<Vec<Arc<dyn QueryChunk>>, DataFusionError> {
-        self.write_buffer.get_table_chunks(
+    ) -> Result<(Vec<Arc<dyn QueryChunk>>, Vec<Arc<dyn ExecutionPlan>>), DataFusionError> {
+        let chunks = self.write_buffer.get_table_chunks(
             &self.db_schema.name,
             &self.table_name,
             filters,
             projection,
             ctx,
-        )
+        )?;
+        // persisted files with deleted rows or renamed columns are scanned on their own, to
+        // filter out the deleted rows and rename the columns as they are read
+        scan_rewritten_chunks(ctx, chunks, projection, filters)
     }
 }
 
@@ -594,7 +611,7 @@ impl TableProvider for QueryTable {
         );
         let mut builder = ProviderBuilder::new(Arc::clone(&self.table_name), self.schema.clone());
 
-        let chunks = self.chunks(ctx, projection, &filters, limit)?;
+        let (chunks, rewritten) = self.chunks(ctx, projection, &filters, limit).await?;
         for chunk in chunks {
             builder = builder.add_chunk(chunk);
         }
@@ -604,7 +621,13 @@ impl TableProvider for QueryTable {
             Err(e) => panic!("unexpected error: {e:?}"),
         };
 
-        provider.scan(ctx, projection, &filters, limit).await
+        let plan = provider.scan(ctx, projection, &filters, limit).await?;
+        if rewritten.is_empty() {
+            return Ok(plan);
+        }
+        Ok(Arc::new(UnionExec::new(
+            std::iter::once(plan).chain(rewritten).collect(),
+        )))
     }
 }
 #[cfg(test)]
@@ -617,12 +640,14 @@ mod tests {
     use futures::TryStreamExt;
     use influxdb3_catalog::catalog::Catalog;
     use influxdb3_telemetry::store::TelemetryStore;
-    use influxdb3_wal::{Gen1Duration, WalConfig};
+    use influxdb3_wal::{Gen1Duration, WalBackend, WalCompression, WalConfig};
     use influxdb3_write::{
         last_cache::LastCacheProvider,
         parquet_cache::test_cached_obj_store_and_oracle,
         persister::Persister,
-        write_buffer::{persisted_files::PersistedFiles, WriteBufferImpl},
+        write_buffer::{
+            persisted_files::PersistedFiles, rejected_writes::RejectedWritesConfig, WriteBufferImpl,
+        },
         WriteBuffer,
     };
     use iox_query::exec::{DedicatedExecutor, Executor, ExecutorConfig};
@@ -681,10 +706,18 @@ mod tests {
                 WalConfig {
                     gen1_duration: Gen1Duration::new_1m(),
                     max_write_buffer_size: 100,
+                    max_write_buffer_bytes: 1024 * 1024,
+                    buffer_full_timeout: Duration::from_secs(1),
                     flush_interval: Duration::from_millis(10),
                     snapshot_size: 1,
+                    compression: WalCompression::None,
+                    quarantine_corrupt_files: false,
+                    snapshotted_wal_retention: Duration::ZERO,
                 },
+                WalBackend::ObjectStore,
+                Arc::new(metric::Registry::default()),
                 Some(parquet_cache),
+                RejectedWritesConfig::default(),
             )
             .await
             .unwrap(),
@@ -6,6 +6,10 @@ use influxdb3_write::WriteBuffer;
 use iox_query::query_log::QueryLog;
 use iox_system_tables::SystemTableProvider;
 use parquet_files::ParquetFilesTable;
+use quarantined_wal_files::QuarantinedWalFilesTable;
+use quotas::QuotasTable;
+use rejected_writes::RejectedWritesTable;
+use schema_history::SchemaHistoryTable;
 use tonic::async_trait;
 
 use self::{last_caches::LastCachesTable, queries::QueriesTable};
@@ -14,13 +18,21 @@ mod last_caches;
 mod parquet_files;
 #[cfg(test)]
 pub(crate) use parquet_files::table_name_predicate_error;
+mod quarantined_wal_files;
 mod queries;
+mod quotas;
+mod rejected_writes;
+mod schema_history;
 
 pub const SYSTEM_SCHEMA_NAME: &str = "system";
 
 const QUERIES_TABLE_NAME: &str = "queries";
 const LAST_CACHES_TABLE_NAME: &str = "last_caches";
 const PARQUET_FILES_TABLE_NAME: &str = "parquet_files";
+const QUARANTINED_WAL_FILES_TABLE_NAME: &str = "quarantined_wal_files";
+const SCHEMA_HISTORY_TABLE_NAME: &str = "schema_history";
+const QUOTAS_TABLE_NAME: &str = "quotas";
+const REJECTED_WRITES_TABLE_NAME: &str = "rejected_writes";
 
 pub(crate) struct SystemSchemaProvider {
     tables: HashMap<&'static str, Arc<dyn TableProvider>>,
@@ -49,6 +61,24 @@ impl SystemSchemaProvider {
             buffer.last_cache_provider(),
         ))));
         tables.insert(LAST_CACHES_TABLE_NAME, last_caches);
+        let quarantined_wal_files = Arc::new(SystemTableProvider::new(Arc::new(
+            QuarantinedWalFilesTable::new(Arc::clone(&buffer)),
+        )));
+        tables.insert(QUARANTINED_WAL_FILES_TABLE_NAME, quarantined_wal_files);
+        let schema_history = Arc::new(SystemTableProvider::new(Arc::new(SchemaHistoryTable::new(
+            db_id,
+            buffer.db_schema_provider(),
+        ))));
+        tables.insert(SCHEMA_HISTORY_TABLE_NAME, schema_history);
+        let quotas = Arc::new(SystemTableProvider::new(Arc::new(QuotasTable::new(
+            db_id,
+            Arc::clone(&buffer),
+        ))));
+        tables.insert(QUOTAS_TABLE_NAME, quotas);
+        let rejected_writes = Arc::new(SystemTableProvider::new(Arc::new(
+            RejectedWritesTable::new(Arc::clone(&buffer)),
+        )));
+        tables.insert(REJECTED_WRITES_TABLE_NAME, rejected_writes);
         let parquet_files = Arc::new(SystemTableProvider::new(Arc::new(ParquetFilesTable::new(
             db_id, buffer,
         ))));
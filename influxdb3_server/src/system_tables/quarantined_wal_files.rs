@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use async_trait::async_trait;
+use datafusion::{error::DataFusionError, logical_expr::Expr};
+use influxdb3_write::WriteBuffer;
+use iox_system_tables::IoxSystemTable;
+
+/// The WAL files that were quarantined during replay on startup. These are not specific to a
+/// database, so the same rows are returned in the system schema of every database.
+pub(super) struct QuarantinedWalFilesTable {
+    schema: SchemaRef,
+    buffer: Arc<dyn WriteBuffer>,
+}
+
+impl QuarantinedWalFilesTable {
+    pub(super) fn new(buffer: Arc<dyn WriteBuffer>) -> Self {
+        Self {
+            schema: quarantined_wal_files_schema(),
+            buffer,
+        }
+    }
+}
+
+fn quarantined_wal_files_schema() -> SchemaRef {
+    let columns = vec![
+        Field::new("wal_file_number", DataType::UInt64, false),
+        Field::new("path", DataType::Utf8, false),
+        Field::new("quarantine_path", DataType::Utf8, false),
+        Field::new("error", DataType::Utf8, false),
+    ];
+    Arc::new(Schema::new(columns))
+}
+
+#[async_trait]
+impl IoxSystemTable for QuarantinedWalFilesTable {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    async fn scan(
+        &self,
+        _filters: Option<Vec<Expr>>,
+        _limit: Option<usize>,
+    ) -> Result<RecordBatch, DataFusionError> {
+        let files = self.buffer.quarantined_wal_files();
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(
+                files
+                    .iter()
+                    .map(|f| Some(f.wal_file_number.as_u64()))
+                    .collect::<UInt64Array>(),
+            ),
+            Arc::new(
+                files
+                    .iter()
+                    .map(|f| Some(f.path.as_str()))
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(
+                files
+                    .iter()
+                    .map(|f| Some(f.quarantine_path.as_str()))
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(
+                files
+                    .iter()
+                    .map(|f| Some(f.error.as_str()))
+                    .collect::<StringArray>(),
+            ),
+        ];
+
+        Ok(RecordBatch::try_new(self.schema(), columns)?)
+    }
+}
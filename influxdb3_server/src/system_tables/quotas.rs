@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use async_trait::async_trait;
+use datafusion::{error::DataFusionError, logical_expr::Expr};
+use influxdb3_id::DbId;
+use influxdb3_write::WriteBuffer;
+use iox_system_tables::IoxSystemTable;
+
+/// The quotas of a database, with their limit and how much of each is currently used
+pub(super) struct QuotasTable {
+    db_id: DbId,
+    schema: SchemaRef,
+    buffer: Arc<dyn WriteBuffer>,
+}
+
+impl QuotasTable {
+    pub(super) fn new(db_id: DbId, buffer: Arc<dyn WriteBuffer>) -> Self {
+        Self {
+            db_id,
+            schema: quotas_schema(),
+            buffer,
+        }
+    }
+}
+
+fn quotas_schema() -> SchemaRef {
+    let columns = vec![
+        Field::new("quota", DataType::Utf8, false),
+        Field::new("limit", DataType::UInt64, true),
+        Field::new("usage", DataType::UInt64, false),
+    ];
+    Arc::new(Schema::new(columns))
+}
+
+#[async_trait]
+impl IoxSystemTable for QuotasTable {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    async fn scan(
+        &self,
+        _filters: Option<Vec<Expr>>,
+        _limit: Option<usize>,
+    ) -> Result<RecordBatch, DataFusionError> {
+        let usage = self.buffer.quota_usage(self.db_id);
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(
+                usage
+                    .iter()
+                    .map(|u| Some(u.quota.as_str()))
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(usage.iter().map(|u| u.limit).collect::<UInt64Array>()),
+            Arc::new(usage.iter().map(|u| Some(u.usage)).collect::<UInt64Array>()),
+        ];
+
+        Ok(RecordBatch::try_new(self.schema(), columns)?)
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, RecordBatch, StringArray, TimestampNanosecondArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use async_trait::async_trait;
+use datafusion::{error::DataFusionError, logical_expr::Expr};
+use influxdb3_write::WriteBuffer;
+use iox_system_tables::IoxSystemTable;
+
+/// The lines most recently rejected from writes. Lines can be rejected from writes to databases
+/// that do not exist, so the same rows are returned in the system schema of every database.
+pub(super) struct RejectedWritesTable {
+    schema: SchemaRef,
+    buffer: Arc<dyn WriteBuffer>,
+}
+
+impl RejectedWritesTable {
+    pub(super) fn new(buffer: Arc<dyn WriteBuffer>) -> Self {
+        Self {
+            schema: rejected_writes_schema(),
+            buffer,
+        }
+    }
+}
+
+fn rejected_writes_schema() -> SchemaRef {
+    let columns = vec![
+        Field::new(
+            "time",
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            false,
+        ),
+        Field::new("database_name", DataType::Utf8, false),
+        Field::new("line_number", DataType::UInt64, false),
+        Field::new("original_line", DataType::Utf8, false),
+        Field::new("error_message", DataType::Utf8, false),
+    ];
+    Arc::new(Schema::new(columns))
+}
+
+#[async_trait]
+impl IoxSystemTable for RejectedWritesTable {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    async fn scan(
+        &self,
+        _filters: Option<Vec<Expr>>,
+        _limit: Option<usize>,
+    ) -> Result<RecordBatch, DataFusionError> {
+        let lines = self.buffer.rejected_writes().lines();
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(
+                lines
+                    .iter()
+                    .map(|l| Some(l.time_ns))
+                    .collect::<TimestampNanosecondArray>(),
+            ),
+            Arc::new(
+                lines
+                    .iter()
+                    .map(|l| Some(l.database_name.as_ref()))
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(
+                lines
+                    .iter()
+                    .map(|l| Some(l.line_number as u64))
+                    .collect::<UInt64Array>(),
+            ),
+            Arc::new(
+                lines
+                    .iter()
+                    .map(|l| Some(l.original_line.as_str()))
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(
+                lines
+                    .iter()
+                    .map(|l| Some(l.error_message.as_str()))
+                    .collect::<StringArray>(),
+            ),
+        ];
+
+        Ok(RecordBatch::try_new(self.schema(), columns)?)
+    }
+}
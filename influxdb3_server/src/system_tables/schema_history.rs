@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, RecordBatch, StringArray, TimestampNanosecondArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use async_trait::async_trait;
+use datafusion::{error::DataFusionError, logical_expr::Expr};
+use influxdb3_catalog::DatabaseSchemaProvider;
+use influxdb3_id::DbId;
+use iox_system_tables::IoxSystemTable;
+
+/// The changes made to the schema of a database, with a row for each op of the catalog batches
+/// that changed it
+pub(super) struct SchemaHistoryTable {
+    db_id: DbId,
+    schema: SchemaRef,
+    provider: Arc<dyn DatabaseSchemaProvider>,
+}
+
+impl SchemaHistoryTable {
+    pub(super) fn new(db_id: DbId, provider: Arc<dyn DatabaseSchemaProvider>) -> Self {
+        Self {
+            db_id,
+            schema: schema_history_schema(),
+            provider,
+        }
+    }
+}
+
+fn schema_history_schema() -> SchemaRef {
+    let columns = vec![
+        Field::new("sequence_number", DataType::UInt64, false),
+        Field::new(
+            "time",
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            false,
+        ),
+        Field::new("table_name", DataType::Utf8, true),
+        Field::new("op", DataType::Utf8, false),
+        Field::new("description", DataType::Utf8, false),
+    ];
+    Arc::new(Schema::new(columns))
+}
+
+#[async_trait]
+impl IoxSystemTable for SchemaHistoryTable {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    async fn scan(
+        &self,
+        _filters: Option<Vec<Expr>>,
+        _limit: Option<usize>,
+    ) -> Result<RecordBatch, DataFusionError> {
+        let history = self.provider.schema_history(self.db_id);
+        let ops = history
+            .iter()
+            .flat_map(|entry| entry.ops.iter().map(move |op| (entry, op)))
+            .collect::<Vec<_>>();
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(
+                ops.iter()
+                    .map(|(entry, _)| Some(u64::from(entry.sequence.as_u32())))
+                    .collect::<UInt64Array>(),
+            ),
+            Arc::new(
+                ops.iter()
+                    .map(|(entry, _)| Some(entry.time_ns))
+                    .collect::<TimestampNanosecondArray>(),
+            ),
+            Arc::new(
+                ops.iter()
+                    .map(|(_, op)| op.table_name())
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(
+                ops.iter()
+                    .map(|(_, op)| Some(op.name()))
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(
+                ops.iter()
+                    .map(|(_, op)| Some(op.to_string()))
+                    .collect::<StringArray>(),
+            ),
+        ];
+
+        Ok(RecordBatch::try_new(self.schema(), columns)?)
+    }
+}
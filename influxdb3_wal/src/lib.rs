@@ -3,15 +3,17 @@
 //! writes durable until they can be written in larger batches as Parquet files and other snapshot and
 //! index files in object storage.
 
+pub mod local_disk;
+mod metrics;
 pub mod object_store;
 pub mod serialize;
 mod snapshot_tracker;
 
-use crate::snapshot_tracker::SnapshotInfo;
+pub use crate::snapshot_tracker::SnapshotInfo;
 use async_trait::async_trait;
 use data_types::Timestamp;
 use hashbrown::HashMap;
-use influxdb3_id::{DbId, TableId};
+use influxdb3_id::{ColumnId, DbId, TableId};
 use influxdb_line_protocol::v3::SeriesValue;
 use influxdb_line_protocol::FieldValue;
 use iox_time::Time;
@@ -19,13 +21,15 @@ use observability_deps::tracing::error;
 use schema::{InfluxColumnType, InfluxFieldType};
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 use std::{any::Any, num::ParseIntError};
 use thiserror::Error;
-use tokio::sync::{oneshot, OwnedSemaphorePermit};
+use tokio::sync::{oneshot, Notify, OwnedSemaphorePermit};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -50,8 +54,20 @@ pub enum Error {
     #[error("last cache size must be from 1 to 10")]
     InvalidLastCacheSize,
 
+    #[error("invalid WAL compression: {0}, expected one of none, zstd or snappy")]
+    InvalidWalCompression(String),
+
     #[error("invalid WAL file path")]
     InvalidWalFilePath,
+
+    #[error("local disk error: {0}")]
+    LocalDiskError(#[from] std::io::Error),
+
+    #[error("wal segment {path} has a corrupt record at byte {offset}")]
+    CorruptSegment { path: String, offset: usize },
+
+    #[error("wal is a read replica and does not accept writes")]
+    ReadReplica,
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -91,8 +107,17 @@ pub trait Wal: Debug + Send + Sync + 'static {
     /// Returns the last persisted wal file sequence number
     async fn last_snapshot_sequence_number(&self) -> SnapshotSequenceNumber;
 
+    /// Reserves a wal file sequence number that no wal file will be written with, and the next
+    /// snapshot sequence number, for data that is persisted in a snapshot of its own without
+    /// going through the WAL, such as an import of parquet files.
+    async fn reserve_snapshot(&self) -> Result<(WalFileSequenceNumber, SnapshotSequenceNumber)>;
+
     /// Stop all writes to the WAL and flush the buffer to a WAL file.
     async fn shutdown(&self);
+
+    /// Returns the WAL files that were skipped and quarantined during replay because they could
+    /// not be deserialized
+    fn quarantined_files(&self) -> Vec<QuarantinedWalFile>;
 }
 
 /// When the WAL persists a file with buffered ops, the contents are sent to this
@@ -120,10 +145,24 @@ pub struct WalConfig {
     pub gen1_duration: Gen1Duration,
     /// The maximum number of writes that can be buffered before we must flush to a wal file
     pub max_write_buffer_size: usize,
+    /// The maximum estimated size in bytes of the buffered writes. The buffer is flushed early
+    /// once it reaches this size
+    pub max_write_buffer_bytes: usize,
+    /// How long a write waits for the buffer to be flushed when it is full before the write is
+    /// rejected
+    pub buffer_full_timeout: Duration,
     /// The interval at which to flush the buffer to a wal file
     pub flush_interval: Duration,
     /// The number of wal files to snapshot at a time
     pub snapshot_size: usize,
+    /// The compression applied to the contents of wal files
+    pub compression: WalCompression,
+    /// If true, wal files that fail their checksum or can't be deserialized during replay are
+    /// moved to a quarantine prefix and skipped, instead of failing the replay
+    pub quarantine_corrupt_files: bool,
+    /// How long wal files are kept after a snapshot covers them, so that state can be restored
+    /// to an earlier point in time. If zero, they are deleted as soon as the snapshot is done
+    pub snapshotted_wal_retention: Duration,
 }
 
 impl WalConfig {
@@ -131,8 +170,13 @@ impl WalConfig {
         Self {
             gen1_duration: Gen1Duration::new_5m(),
             max_write_buffer_size: 1000,
+            max_write_buffer_bytes: 100 * 1024 * 1024,
+            buffer_full_timeout: Duration::from_secs(1),
             flush_interval: Duration::from_millis(10),
             snapshot_size: 100,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
         }
     }
 }
@@ -142,12 +186,108 @@ impl Default for WalConfig {
         Self {
             gen1_duration: Default::default(),
             max_write_buffer_size: 100_000,
+            max_write_buffer_bytes: 100 * 1024 * 1024,
+            buffer_full_timeout: Duration::from_secs(5),
             flush_interval: Duration::from_secs(1),
             snapshot_size: 600,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
+        }
+    }
+}
+
+/// The compression applied to the serialized contents of a wal file. The compression used is
+/// recorded in the header of each file, so files written with any compression can be replayed
+/// regardless of how the WAL is currently configured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WalCompression {
+    /// The contents are written uncompressed
+    #[default]
+    None,
+    /// The contents are compressed with zstd
+    Zstd,
+    /// The contents are compressed with snappy
+    Snappy,
+}
+
+impl WalCompression {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Zstd => "zstd",
+            Self::Snappy => "snappy",
         }
     }
 }
 
+impl FromStr for WalCompression {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "zstd" => Ok(Self::Zstd),
+            "snappy" => Ok(Self::Snappy),
+            _ => Err(Error::InvalidWalCompression(s.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for WalCompression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A WAL file that could not be deserialized during replay, which was moved out of the way so that
+/// the replay could continue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinedWalFile {
+    /// The sequence number of the file, taken from its path
+    pub wal_file_number: WalFileSequenceNumber,
+    /// The path the file was originally at
+    pub path: String,
+    /// The object store path the file was moved to
+    pub quarantine_path: String,
+    /// The error that caused the file to be quarantined
+    pub error: String,
+}
+
+/// Where the WAL persists its files before writes are confirmed
+#[derive(Debug, Clone, Default)]
+pub enum WalBackend {
+    /// Each flush of the buffer is written as a file to object store
+    #[default]
+    ObjectStore,
+    /// Each flush of the buffer is appended to a segment file on a local volume, which is uploaded
+    /// to object store in the background once it is sealed
+    LocalDisk(LocalDiskConfig),
+    /// No WAL is written. The WAL files and snapshots that another host writes to object store
+    /// are followed, and writes are rejected
+    ReadReplica(ReplicaConfig),
+}
+
+/// The configuration for a WAL that writes to segment files on a local volume
+#[derive(Debug, Clone)]
+pub struct LocalDiskConfig {
+    /// The directory that segment files are written to. Files for a host are placed under
+    /// `{directory}/{host_identifier_prefix}/wal`
+    pub directory: PathBuf,
+    /// The size in bytes a segment can grow to before it is sealed and uploaded
+    pub max_segment_size_bytes: u64,
+    /// How long a segment is appended to before it is sealed and uploaded, so that the WAL files
+    /// of a host that writes little still reach object store
+    pub max_segment_age: Duration,
+}
+
+/// The configuration for a read replica that follows the WAL of another host
+#[derive(Debug, Clone, Copy)]
+pub struct ReplicaConfig {
+    /// How often object store is checked for new WAL files and snapshots from the host
+    pub poll_interval: Duration,
+}
+
 /// The duration of data timestamps, grouped into files persisted into object storage.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Gen1Duration(Duration);
@@ -209,6 +349,42 @@ impl Default for Gen1Duration {
 pub enum WalOp {
     Write(WriteBatch),
     Catalog(CatalogBatch),
+    Delete(DeleteBatch),
+}
+
+impl WalOp {
+    /// A cheap estimate of the size in bytes of the op, used to bound the size of the WAL buffer
+    pub fn estimated_size_bytes(&self) -> usize {
+        match self {
+            Self::Write(write_batch) => write_batch.estimated_size_bytes(),
+            Self::Catalog(catalog_batch) => std::mem::size_of_val(catalog_batch),
+            Self::Delete(delete_batch) => std::mem::size_of_val(delete_batch),
+        }
+    }
+}
+
+/// Deletes the rows of a table that match a predicate. The delete is applied to the data buffered
+/// in memory, and to the parquet files that were persisted before it through tombstones.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DeleteBatch {
+    pub database_id: DbId,
+    pub database_name: Arc<str>,
+    pub table_id: TableId,
+    pub table_name: Arc<str>,
+    pub time_ns: i64,
+    pub predicate: DeletePredicate,
+}
+
+/// The rows to delete from a table: those with a time in the range, and that have all of the given
+/// tag values.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DeletePredicate {
+    /// The inclusive lower bound of the time of rows to delete
+    pub min_time_ns: i64,
+    /// The inclusive upper bound of the time of rows to delete
+    pub max_time_ns: i64,
+    /// Tag column names and the value they must have for a row to be deleted
+    pub tags: Vec<(Arc<str>, Arc<str>)>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -226,6 +402,130 @@ pub enum CatalogOp {
     AddFields(FieldAdditions),
     CreateLastCache(LastCacheDefinition),
     DeleteLastCache(LastCacheDelete),
+    DeleteDatabase(DatabaseDelete),
+    DeleteTable(TableDelete),
+    SetSchemaPolicy(SchemaPolicyDefinition),
+    RenameTable(TableRename),
+    RenameColumn(ColumnRename),
+    SetRetentionPeriod(RetentionPeriodDefinition),
+    SetCoercionPolicy(CoercionPolicyDefinition),
+    SetQuotas(QuotasDefinition),
+    SetJsonMapping(JsonMappingDefinition),
+}
+
+impl CatalogOp {
+    /// A short name for the kind of op
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::CreateDatabase(_) => "create_database",
+            Self::CreateTable(_) => "create_table",
+            Self::AddFields(_) => "add_fields",
+            Self::CreateLastCache(_) => "create_last_cache",
+            Self::DeleteLastCache(_) => "delete_last_cache",
+            Self::DeleteDatabase(_) => "delete_database",
+            Self::DeleteTable(_) => "delete_table",
+            Self::SetSchemaPolicy(_) => "set_schema_policy",
+            Self::RenameTable(_) => "rename_table",
+            Self::RenameColumn(_) => "rename_column",
+            Self::SetRetentionPeriod(_) => "set_retention_period",
+            Self::SetCoercionPolicy(_) => "set_coercion_policy",
+            Self::SetQuotas(_) => "set_quotas",
+            Self::SetJsonMapping(_) => "set_json_mapping",
+        }
+    }
+
+    /// The name of the table the op applies to, if it applies to one. This is the name the table
+    /// had when the op was made.
+    pub fn table_name(&self) -> Option<&str> {
+        match self {
+            Self::CreateDatabase(_)
+            | Self::DeleteDatabase(_)
+            | Self::SetRetentionPeriod(_)
+            | Self::SetQuotas(_) => None,
+            Self::CreateTable(def) => Some(def.table_name.as_ref()),
+            Self::AddFields(def) => Some(def.table_name.as_ref()),
+            Self::CreateLastCache(def) => Some(def.table.as_str()),
+            Self::DeleteLastCache(def) => Some(def.table_name.as_str()),
+            Self::DeleteTable(def) => Some(def.table_name.as_ref()),
+            Self::SetSchemaPolicy(def) => def.table_name.as_deref(),
+            Self::RenameTable(def) => Some(def.old_name.as_ref()),
+            Self::RenameColumn(def) => Some(def.table_name.as_ref()),
+            Self::SetCoercionPolicy(def) => Some(def.table_name.as_ref()),
+            Self::SetJsonMapping(def) => Some(def.table_name.as_ref()),
+        }
+    }
+}
+
+impl std::fmt::Display for CatalogOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CreateDatabase(def) => write!(f, "create database {}", def.database_name),
+            Self::CreateTable(def) => write!(
+                f,
+                "create table {} with {} columns",
+                def.table_name,
+                def.field_definitions.len()
+            ),
+            Self::AddFields(def) => write!(
+                f,
+                "add columns to table {}: {}",
+                def.table_name,
+                def.field_definitions
+                    .iter()
+                    .map(|f| f.name.as_ref())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::CreateLastCache(def) => {
+                write!(f, "create last cache {} on table {}", def.name, def.table)
+            }
+            Self::DeleteLastCache(def) => {
+                write!(
+                    f,
+                    "delete last cache {} on table {}",
+                    def.name, def.table_name
+                )
+            }
+            Self::DeleteDatabase(def) => write!(f, "delete database {}", def.database_name),
+            Self::DeleteTable(def) => write!(f, "delete table {}", def.table_name),
+            Self::SetSchemaPolicy(def) => match &def.table_name {
+                Some(table_name) => {
+                    write!(
+                        f,
+                        "set schema policy of table {table_name} to {}",
+                        def.policy
+                    )
+                }
+                None => write!(f, "set default schema policy to {}", def.policy),
+            },
+            Self::RenameTable(def) => {
+                write!(f, "rename table {} to {}", def.old_name, def.new_name)
+            }
+            Self::RenameColumn(def) => write!(
+                f,
+                "rename column {} of table {} to {}",
+                def.old_name, def.table_name, def.new_name
+            ),
+            Self::SetRetentionPeriod(def) => match def.retention_period_ns {
+                Some(ns) => write!(
+                    f,
+                    "set retention period to {}",
+                    humantime::format_duration(std::time::Duration::from_nanos(ns))
+                ),
+                None => write!(f, "remove retention period"),
+            },
+            Self::SetCoercionPolicy(def) => write!(
+                f,
+                "set coercion policy of table {} to {}",
+                def.table_name, def.policy
+            ),
+            Self::SetQuotas(def) => write!(f, "set quotas to {}", def.quotas),
+            Self::SetJsonMapping(def) => match def.mapping {
+                Some(_) => write!(f, "set json mapping of table {}", def.table_name),
+                None => write!(f, "remove json mapping of table {}", def.table_name),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -437,6 +737,317 @@ pub struct LastCacheDelete {
     pub name: String,
 }
 
+/// Removes a database, along with all of its tables, from the catalog
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DatabaseDelete {
+    pub database_id: DbId,
+    pub database_name: Arc<str>,
+}
+
+/// Removes a table from the catalog of the database in the batch
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TableDelete {
+    pub table_id: TableId,
+    pub table_name: Arc<str>,
+}
+
+/// Renames a table of the database in the batch. The table keeps its id, so its data, both
+/// buffered and persisted, stays with it.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TableRename {
+    pub table_id: TableId,
+    pub old_name: Arc<str>,
+    pub new_name: Arc<str>,
+}
+
+/// Renames a column of a table. The column keeps its id, which is used to find its values in
+/// data that was written under one of its previous names.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ColumnRename {
+    pub table_id: TableId,
+    pub table_name: Arc<str>,
+    pub column_id: ColumnId,
+    pub old_name: Arc<str>,
+    pub new_name: Arc<str>,
+}
+
+/// Sets the retention period of the database in the batch. Data older than the retention period
+/// is not queried, and parquet files that only hold such data are deleted.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RetentionPeriodDefinition {
+    /// The retention period in nanoseconds, or `None` to keep data forever
+    pub retention_period_ns: Option<u64>,
+}
+
+/// Sets the schema policy of a table, or the default policy of the database in the batch if no
+/// table is given
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SchemaPolicyDefinition {
+    pub table_id: Option<TableId>,
+    pub table_name: Option<Arc<str>>,
+    pub policy: SchemaPolicy,
+}
+
+/// How writes that would add new columns to an existing table are handled
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaPolicy {
+    /// New columns are added to the table, up to the column limit
+    #[default]
+    Open,
+    /// Lines that would add new columns are rejected
+    Locked,
+    /// Lines are accepted, but the values of any new columns are dropped
+    DropUnknown,
+}
+
+impl SchemaPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Locked => "locked",
+            Self::DropUnknown => "drop_unknown",
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        matches!(self, Self::Open)
+    }
+}
+
+impl std::fmt::Display for SchemaPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Sets the coercion policy of a table
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CoercionPolicyDefinition {
+    pub table_id: TableId,
+    pub table_name: Arc<str>,
+    pub policy: CoercionPolicy,
+}
+
+/// Which field values are converted to the type of the existing column they are written to,
+/// rather than the line being rejected because the types do not match. By default, no values are
+/// converted.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CoercionPolicy {
+    /// Integer and unsigned integer values are converted to floats
+    #[serde(default)]
+    pub integer_to_float: bool,
+    /// Unsigned integer values are converted to integers, as long as they are not too large
+    #[serde(default)]
+    pub uinteger_to_integer: bool,
+    /// Values of any type are converted to strings
+    #[serde(default)]
+    pub any_to_string: bool,
+}
+
+impl CoercionPolicy {
+    pub fn is_strict(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether values of the `from` type are converted to the `to` type. Note that unsigned
+    /// integers that are too large to be integers cannot be converted, even if this is `true`.
+    pub fn coerces(&self, from: InfluxColumnType, to: InfluxColumnType) -> bool {
+        let (InfluxColumnType::Field(from), InfluxColumnType::Field(to)) = (from, to) else {
+            return false;
+        };
+        match (from, to) {
+            (InfluxFieldType::Integer | InfluxFieldType::UInteger, InfluxFieldType::Float) => {
+                self.integer_to_float
+            }
+            (InfluxFieldType::UInteger, InfluxFieldType::Integer) => self.uinteger_to_integer,
+            (from, InfluxFieldType::String) => {
+                from != InfluxFieldType::String && self.any_to_string
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for CoercionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let coercions = [
+            (self.integer_to_float, "integer_to_float"),
+            (self.uinteger_to_integer, "uinteger_to_integer"),
+            (self.any_to_string, "any_to_string"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect::<Vec<_>>();
+        if coercions.is_empty() {
+            f.write_str("strict")
+        } else {
+            f.write_str(&coercions.join(", "))
+        }
+    }
+}
+
+/// Sets the quotas of the database in the batch, replacing the ones it had
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct QuotasDefinition {
+    pub quotas: DatabaseQuotas,
+}
+
+/// Limits on the resources a single database can use. A quota that is not set is unlimited.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DatabaseQuotas {
+    /// The number of tables in the database
+    #[serde(default)]
+    pub max_tables: Option<u64>,
+    /// The number of columns, summed over all tables in the database
+    #[serde(default)]
+    pub max_columns: Option<u64>,
+    /// The number of distinct series written to the database since the server started
+    #[serde(default)]
+    pub max_series: Option<u64>,
+    /// The size in bytes of the parquet files persisted for the database
+    #[serde(default)]
+    pub max_stored_bytes: Option<u64>,
+    /// The number of bytes of line protocol written to the database each second
+    #[serde(default)]
+    pub max_write_bytes_per_second: Option<u64>,
+}
+
+impl DatabaseQuotas {
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The limit of the quota, or `None` if it is unlimited
+    pub fn limit(&self, quota: Quota) -> Option<u64> {
+        match quota {
+            Quota::Tables => self.max_tables,
+            Quota::Columns => self.max_columns,
+            Quota::Series => self.max_series,
+            Quota::StoredBytes => self.max_stored_bytes,
+            Quota::WriteBytesPerSecond => self.max_write_bytes_per_second,
+        }
+    }
+}
+
+impl std::fmt::Display for DatabaseQuotas {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let limits = Quota::ALL
+            .into_iter()
+            .filter_map(|quota| self.limit(quota).map(|limit| format!("{quota}={limit}")))
+            .collect::<Vec<_>>();
+        if limits.is_empty() {
+            f.write_str("unlimited")
+        } else {
+            f.write_str(&limits.join(", "))
+        }
+    }
+}
+
+/// A resource of a database that can be limited by its [`DatabaseQuotas`]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Quota {
+    Tables,
+    Columns,
+    Series,
+    StoredBytes,
+    WriteBytesPerSecond,
+}
+
+impl Quota {
+    pub const ALL: [Self; 5] = [
+        Self::Tables,
+        Self::Columns,
+        Self::Series,
+        Self::StoredBytes,
+        Self::WriteBytesPerSecond,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tables => "max_tables",
+            Self::Columns => "max_columns",
+            Self::Series => "max_series",
+            Self::StoredBytes => "max_stored_bytes",
+            Self::WriteBytesPerSecond => "max_write_bytes_per_second",
+        }
+    }
+}
+
+impl std::fmt::Display for Quota {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Sets the mapping used for JSON documents written to a table, or removes it if `None`
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct JsonMappingDefinition {
+    pub table_id: TableId,
+    pub table_name: Arc<str>,
+    pub mapping: Option<JsonMapping>,
+}
+
+/// How the JSON documents in a write are mapped to rows. Values are found in a document by their
+/// path, which is a list of object keys and array indexes separated by dots, e.g.,
+/// `readings.0.value`.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct JsonMapping {
+    /// The path of the measurement of each document. If not set, documents are written to the
+    /// table named in the write.
+    #[serde(default)]
+    pub measurement_path: Option<String>,
+    /// The path of each tag, by tag name
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+    /// The path and type of each field, by field name
+    #[serde(default)]
+    pub fields: BTreeMap<String, JsonFieldMapping>,
+    /// Where the time of each document is found. If not set, documents are written at the time
+    /// of the write.
+    #[serde(default)]
+    pub timestamp: Option<JsonTimestampMapping>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct JsonFieldMapping {
+    pub path: String,
+    /// The type the value is written as. If not set, numbers are written as floats, and strings
+    /// and booleans as themselves.
+    #[serde(default, rename = "type")]
+    pub field_type: Option<JsonFieldType>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonFieldType {
+    Float,
+    Integer,
+    UInteger,
+    String,
+    Boolean,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct JsonTimestampMapping {
+    pub path: String,
+    #[serde(default)]
+    pub format: JsonTimestampFormat,
+}
+
+/// The format of the timestamps in JSON documents. The unix formats accept numbers, or strings
+/// that hold numbers.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonTimestampFormat {
+    Rfc3339,
+    UnixSeconds,
+    UnixMillis,
+    UnixMicros,
+    #[default]
+    UnixNanos,
+}
+
 #[serde_as]
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct WriteBatch {
@@ -483,6 +1094,23 @@ serde_with::serde_conv!(
 );
 
 impl WriteBatch {
+    /// A cheap estimate of the size in bytes of the rows in the batch
+    pub fn estimated_size_bytes(&self) -> usize {
+        self.table_chunks
+            .values()
+            .flat_map(|chunks| chunks.chunk_time_to_chunk.values())
+            .flat_map(|chunk| chunk.rows.iter())
+            .map(|row| {
+                std::mem::size_of::<Row>()
+                    + row
+                        .fields
+                        .iter()
+                        .map(|field| field.name.len() + field.value.estimated_size_bytes())
+                        .sum::<usize>()
+            })
+            .sum()
+    }
+
     pub fn new(
         database_id: DbId,
         database_name: Arc<str>,
@@ -593,6 +1221,15 @@ pub enum FieldData {
     Boolean(bool),
 }
 
+impl FieldData {
+    fn estimated_size_bytes(&self) -> usize {
+        match self {
+            Self::Key(s) | Self::Tag(s) | Self::String(s) => std::mem::size_of::<Self>() + s.len(),
+            _ => std::mem::size_of::<Self>(),
+        }
+    }
+}
+
 impl PartialEq for FieldData {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
@@ -641,6 +1278,10 @@ pub struct WalContents {
     pub ops: Vec<WalOp>,
     /// If present, the buffer should be snapshot after the contents of this file are loaded.
     pub snapshot: Option<SnapshotDetails>,
+    /// The time the WAL file was flushed, used to find the files to restore up to a point in
+    /// time. Files written before this was recorded have it set to 0.
+    #[serde(default)]
+    pub persisted_time_ns: i64,
 }
 
 impl WalContents {
@@ -721,13 +1362,20 @@ pub struct SnapshotDetails {
 pub fn background_wal_flush<W: Wal>(
     wal: Arc<W>,
     flush_interval: Duration,
+    flush_requested: Arc<Notify>,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(flush_interval);
         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
         loop {
-            interval.tick().await;
+            // flush on the interval, or early if the buffer has filled up
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = flush_requested.notified() => {
+                    interval.reset();
+                }
+            }
 
             let cleanup_after_snapshot = wal.flush_buffer().await;
 
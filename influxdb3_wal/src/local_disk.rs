@@ -0,0 +1,1354 @@
+//! A WAL that appends to segment files on a local volume instead of doing a PUT to object store
+//! for every flush. Each flushed WAL file is framed and appended to the open segment, which is
+//! fsync'd before writes are acknowledged. Once a segment grows past the configured size, or has
+//! been open for the configured age, it is sealed and a background task uploads the WAL files it
+//! contains into object store, using the same paths that the
+//! [`WalObjectStore`](crate::object_store::WalObjectStore) writes to.
+//!
+//! Segments are removed, both locally and from object store, once a snapshot covers all of the
+//! WAL files they contain. If snapshotted WAL files are retained, the files covered by a snapshot
+//! are first written to the retained prefix in object store. On restart, the WAL is replayed from
+//! the local segments.
+
+use crate::metrics::WalMetrics;
+use crate::object_store::{
+    notify_and_respond, prune_retained_wal_files, quarantine_path, replay_wal_contents,
+    retained_wal_path, wal_path, BufferBackpressure, FlushBuffer, WriteResult,
+};
+use crate::serialize::{serialize_to_file_bytes, verify_file_type_and_deserialize};
+use crate::snapshot_tracker::SnapshotInfo;
+use crate::{
+    background_wal_flush, LocalDiskConfig, QuarantinedWalFile, SnapshotDetails,
+    SnapshotSequenceNumber, Wal, WalCompression, WalConfig, WalFileNotifier, WalFileSequenceNumber,
+    WalOp,
+};
+use bytes::Bytes;
+use iox_time::{Time, TimeProvider};
+use object_store::{ObjectStore, PutPayload};
+use observability_deps::tracing::{debug, error, info, warn};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{mpsc, oneshot, Mutex, OwnedSemaphorePermit};
+
+/// The extension used for segment files
+const SEGMENT_FILE_EXTENSION: &str = "seg";
+
+/// The file in the segment directory that records the last WAL file removed by a snapshot
+const LAST_REMOVED_FILE_NAME: &str = "last_removed_wal_file";
+
+/// Each record in a segment is prefixed by the WAL file sequence number (u64), the length of the
+/// serialized WAL file (u32), and the CRC32 checksum of the serialized WAL file (u32), all big
+/// endian.
+const RECORD_HEADER_LEN: usize = 16;
+
+#[derive(Debug)]
+pub struct WalLocalDisk {
+    object_store: Arc<dyn ObjectStore>,
+    host_identifier_prefix: String,
+    file_notifier: Arc<dyn WalFileNotifier>,
+    time_provider: Arc<dyn TimeProvider>,
+    /// Directory on the local volume that holds the segment files for this host
+    segment_dir: PathBuf,
+    /// Segments are sealed and uploaded once they are at least this size
+    max_segment_size_bytes: u64,
+    /// Segments are sealed and uploaded once they have been open for this long
+    max_segment_age: Duration,
+    /// Buffered wal ops go in here along with the state to track when to snapshot
+    flush_buffer: Mutex<FlushBuffer>,
+    /// Holds writes back while the buffer is full
+    backpressure: BufferBackpressure,
+    /// The open segment and the sealed segments that have not been removed yet
+    segments: Mutex<Segments>,
+    /// All WAL files with a sequence number <= to this have been removed by a snapshot
+    last_removed_wal_file: Arc<AtomicU64>,
+    /// Sealed segments are sent here to be uploaded to object store
+    upload_tx: mpsc::UnboundedSender<SegmentInfo>,
+    /// The compression applied to the contents of wal files as they are written
+    compression: WalCompression,
+    metrics: WalMetrics,
+    /// If set, files that fail to deserialize during replay are quarantined and skipped
+    quarantine_corrupt_files: bool,
+    /// The files that were quarantined during replay, which are not uploaded with their segment
+    quarantined_files: Arc<parking_lot::Mutex<Vec<QuarantinedWalFile>>>,
+    /// How long wal files are retained in object store after a snapshot covers them
+    snapshotted_wal_retention: Duration,
+}
+
+impl WalLocalDisk {
+    /// Creates a new local disk WAL. This will replay the segment files into the notifier and
+    /// trigger any snapshots that exist in them that haven't been cleaned up yet.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn new(
+        object_store: Arc<dyn ObjectStore>,
+        host_identifier_prefix: impl Into<String> + Send,
+        file_notifier: Arc<dyn WalFileNotifier>,
+        time_provider: Arc<dyn TimeProvider>,
+        config: WalConfig,
+        local_disk_config: LocalDiskConfig,
+        metric_registry: Arc<metric::Registry>,
+        last_wal_sequence_number: Option<WalFileSequenceNumber>,
+        last_snapshot_sequence_number: Option<SnapshotSequenceNumber>,
+    ) -> Result<Arc<Self>, crate::Error> {
+        let flush_interval = config.flush_interval;
+        let wal = Self::new_without_replay(
+            object_store,
+            host_identifier_prefix,
+            file_notifier,
+            time_provider,
+            config,
+            local_disk_config,
+            &metric_registry,
+            last_wal_sequence_number,
+            last_snapshot_sequence_number,
+        )
+        .await?;
+
+        wal.replay().await?;
+        let wal = Arc::new(wal);
+        background_wal_flush(
+            Arc::clone(&wal),
+            flush_interval,
+            wal.backpressure.flush_requested(),
+        );
+
+        Ok(wal)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn new_without_replay(
+        object_store: Arc<dyn ObjectStore>,
+        host_identifier_prefix: impl Into<String> + Send,
+        file_notifier: Arc<dyn WalFileNotifier>,
+        time_provider: Arc<dyn TimeProvider>,
+        config: WalConfig,
+        local_disk_config: LocalDiskConfig,
+        metric_registry: &metric::Registry,
+        last_wal_sequence_number: Option<WalFileSequenceNumber>,
+        last_snapshot_sequence_number: Option<SnapshotSequenceNumber>,
+    ) -> Result<Self, crate::Error> {
+        let host_identifier_prefix = host_identifier_prefix.into();
+        let segment_dir = local_disk_config
+            .directory
+            .join(&host_identifier_prefix)
+            .join("wal");
+        tokio::fs::create_dir_all(&segment_dir).await?;
+
+        let last_removed_wal_file = Arc::new(AtomicU64::new(
+            read_last_removed_wal_file(&segment_dir).await?,
+        ));
+
+        let quarantined_files: Arc<parking_lot::Mutex<Vec<QuarantinedWalFile>>> =
+            Default::default();
+        let (upload_tx, upload_rx) = mpsc::unbounded_channel();
+        tokio::spawn(upload_sealed_segments(
+            Arc::clone(&object_store),
+            host_identifier_prefix.clone(),
+            Arc::clone(&last_removed_wal_file),
+            Arc::clone(&quarantined_files),
+            upload_rx,
+        ));
+
+        Ok(Self {
+            object_store,
+            host_identifier_prefix,
+            file_notifier,
+            time_provider,
+            segment_dir,
+            max_segment_size_bytes: local_disk_config.max_segment_size_bytes,
+            max_segment_age: local_disk_config.max_segment_age,
+            flush_buffer: Mutex::new(FlushBuffer::new_from_config(
+                config,
+                last_wal_sequence_number,
+                last_snapshot_sequence_number,
+            )),
+            backpressure: BufferBackpressure::new(config.buffer_full_timeout),
+            segments: Mutex::new(Segments::default()),
+            last_removed_wal_file,
+            upload_tx,
+            compression: config.compression,
+            metrics: WalMetrics::new(metric_registry, config.compression),
+            quarantine_corrupt_files: config.quarantine_corrupt_files,
+            quarantined_files,
+            snapshotted_wal_retention: config.snapshotted_wal_retention,
+        })
+    }
+
+    /// Loads the segment files in order from the local volume, calling the file notifier on each
+    /// WAL file they contain and populating the snapshot tracker with the WAL periods. Any
+    /// segments that existed before the restart are sealed and queued for upload.
+    pub async fn replay(&self) -> crate::Result<()> {
+        let last_removed = self.last_removed_wal_file.load(Ordering::SeqCst);
+
+        for path in self.load_existing_segment_paths().await? {
+            let bytes = Bytes::from(tokio::fs::read(&path).await?);
+            let size_bytes = bytes.len() as u64;
+            let (records, end) = decode_segment(bytes);
+            match end {
+                SegmentEnd::Complete => (),
+                SegmentEnd::TornRecord => {
+                    // the record was never fsync'd, so the write it held was never acknowledged
+                    warn!(
+                        path = %path.display(),
+                        "ignoring partially written record at the end of wal segment"
+                    );
+                }
+                SegmentEnd::Corrupt { offset } if self.quarantine_corrupt_files => {
+                    // the length of the record can't be trusted, so nothing after it can be read
+                    error!(
+                        path = %path.display(),
+                        offset,
+                        "ignoring corrupt record and the rest of wal segment, writes in them are lost"
+                    );
+                }
+                SegmentEnd::Corrupt { offset } => {
+                    return Err(crate::Error::CorruptSegment {
+                        path: path.display().to_string(),
+                        offset,
+                    });
+                }
+            }
+
+            let (Some(first), Some(last)) = (records.first(), records.last()) else {
+                debug!(path = %path.display(), "removing empty wal segment");
+                tokio::fs::remove_file(&path).await?;
+                continue;
+            };
+            let segment = SegmentInfo {
+                path: path.clone(),
+                first_wal_file: first.wal_file_number,
+                last_wal_file: last.wal_file_number,
+                size_bytes,
+            };
+
+            for record in records {
+                // files already covered by a snapshot would have been deleted by the object
+                // store WAL, so don't replay them
+                if record.wal_file_number.as_u64() <= last_removed {
+                    continue;
+                }
+                let wal_contents = match verify_file_type_and_deserialize(record.data.clone()) {
+                    Ok(wal_contents) => wal_contents,
+                    Err(e) if self.quarantine_corrupt_files => {
+                        self.quarantine(&path, record, e).await?;
+                        continue;
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+
+                // if the info is there, we have wal files to delete
+                if let Some((snapshot_info, snapshot_permit)) =
+                    replay_wal_contents(&self.flush_buffer, &self.file_notifier, wal_contents).await
+                {
+                    self.remove_snapshot_wal_files(snapshot_info, snapshot_permit)
+                        .await;
+                }
+            }
+
+            if segment.last_wal_file.as_u64() <= self.last_removed_wal_file.load(Ordering::SeqCst) {
+                remove_segment_file(&segment.path).await;
+                continue;
+            }
+
+            self.segments
+                .lock()
+                .await
+                .sealed
+                .insert(segment.first_wal_file, segment.clone());
+            let _ = self.upload_tx.send(segment);
+        }
+
+        let wal_file_numbers = self
+            .quarantined_files
+            .lock()
+            .iter()
+            .map(|f| f.wal_file_number.to_string())
+            .collect::<Vec<_>>();
+        if !wal_file_numbers.is_empty() {
+            let wal_file_numbers = wal_file_numbers.join(", ");
+            warn!(
+                %wal_file_numbers,
+                "replay skipped wal files that could not be deserialized, writes in them are lost"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Copies a wal file that could not be deserialized from its segment to the quarantine prefix
+    /// in object store, and records it so that it is not uploaded with the rest of the segment.
+    async fn quarantine(
+        &self,
+        segment_path: &Path,
+        record: SegmentRecord,
+        error: crate::serialize::Error,
+    ) -> crate::Result<()> {
+        let wal_file_number = record.wal_file_number;
+        let quarantine_path = quarantine_path(&self.host_identifier_prefix, wal_file_number);
+        warn!(
+            segment_path = %segment_path.display(),
+            %wal_file_number,
+            %quarantine_path,
+            %error,
+            "quarantining wal file that could not be deserialized"
+        );
+        self.object_store
+            .put(&quarantine_path, PutPayload::from_bytes(record.data))
+            .await?;
+
+        // never reuse the sequence number of the quarantined file
+        self.flush_buffer
+            .lock()
+            .await
+            .skip_wal_file(wal_file_number);
+        self.metrics.record_quarantined_file();
+        self.quarantined_files.lock().push(QuarantinedWalFile {
+            wal_file_number,
+            path: segment_path.display().to_string(),
+            quarantine_path: quarantine_path.to_string(),
+            error: error.to_string(),
+        });
+
+        Ok(())
+    }
+
+    /// Stop accepting write operations, flush of buffered writes to a segment and return when done.
+    pub async fn shutdown(&self) {
+        // stop accepting writes
+        self.flush_buffer.lock().await.wal_buffer.is_shutdown = true;
+
+        // do the flush and wait for the snapshot if that's running
+        if let Some((snapshot_done, snapshot_info, snapshot_permit)) = self.flush_buffer().await {
+            let snapshot_details = snapshot_done.await.expect("snapshot should complete");
+            assert_eq!(snapshot_info.snapshot_details, snapshot_details);
+            self.remove_snapshot_wal_files(snapshot_info, snapshot_permit)
+                .await;
+        }
+
+        // seal the open segment so that it gets queued for upload
+        self.segments
+            .lock()
+            .await
+            .seal_open_segment(&self.upload_tx);
+    }
+
+    /// Buffer into a single larger operation in memory. Returns before the operation is persisted.
+    async fn buffer_op_unconfirmed(&self, op: WalOp) -> crate::Result<(), crate::Error> {
+        let mut flush_buffer = self
+            .backpressure
+            .lock_with_capacity(&self.flush_buffer, &self.metrics)
+            .await?;
+        flush_buffer.wal_buffer.buffer_op_unconfirmed(op);
+        self.backpressure
+            .buffered(&flush_buffer.wal_buffer, &self.metrics);
+
+        Ok(())
+    }
+
+    /// Writes the op into the buffer and waits until the WAL file is persisted. When this returns
+    /// the operation is durable on the local volume.
+    async fn write_ops(&self, ops: Vec<WalOp>) -> crate::Result<(), crate::Error> {
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut flush_buffer = self
+                .backpressure
+                .lock_with_capacity(&self.flush_buffer, &self.metrics)
+                .await?;
+            flush_buffer.wal_buffer.buffer_ops_with_response(ops, tx);
+            self.backpressure
+                .buffered(&flush_buffer.wal_buffer, &self.metrics);
+        }
+
+        match rx.await {
+            Ok(WriteResult::Success(())) => Ok(()),
+            Ok(WriteResult::Error(e)) => Err(crate::Error::WriteError(e)),
+            Err(_) => Err(crate::Error::WriteError(
+                "oneshot channel closed".to_string(),
+            )),
+        }
+    }
+
+    async fn flush_buffer(
+        &self,
+    ) -> Option<(
+        oneshot::Receiver<SnapshotDetails>,
+        SnapshotInfo,
+        OwnedSemaphorePermit,
+    )> {
+        // this is called on every flush interval, even if nothing was written since the last one
+        self.seal_expired_segment().await;
+
+        let (mut wal_contents, responses, snapshot) = {
+            let mut flush_buffer = self.flush_buffer.lock().await;
+            if flush_buffer.wal_buffer.is_empty() {
+                return None;
+            }
+            let flushed = flush_buffer
+                .flush_buffer_into_contents_and_responses()
+                .await;
+            self.backpressure.flushed(&self.metrics);
+            flushed
+        };
+        wal_contents.persisted_time_ns = self.time_provider.now().timestamp_nanos();
+        info!(
+            n_ops = %wal_contents.ops.len(),
+            min_timestamp_ns = %wal_contents.min_timestamp_ns,
+            max_timestamp_ns = %wal_contents.max_timestamp_ns,
+            wal_file_number = %wal_contents.wal_file_number,
+            snapshot_details = ?wal_contents.snapshot,
+            "flushing WAL buffer to local segment"
+        );
+
+        let serialized = serialize_to_file_bytes(&wal_contents, self.compression)
+            .expect("unable to serialize wal contents into bytes for file");
+        self.metrics
+            .record_file(serialized.uncompressed_len, serialized.compressed_len);
+        let data = serialized.bytes;
+
+        if let Err(e) = self
+            .append_to_segment(wal_contents.wal_file_number, &data)
+            .await
+        {
+            // the local volume is failing, so drop all these responses and any in the new buffer
+            error!(%e, "error appending wal file to local segment");
+            for response in responses {
+                let _ = response.send(WriteResult::Error(e.to_string()));
+            }
+
+            self.flush_buffer
+                .lock()
+                .await
+                .flush_buffer_with_failure(WriteResult::Error(e.to_string()))
+                .await;
+            self.backpressure.flushed(&self.metrics);
+
+            return None;
+        }
+
+        // now that we've persisted this latest notify and start the snapshot, if set
+        notify_and_respond(&self.file_notifier, wal_contents, snapshot, responses).await
+    }
+
+    /// Appends the serialized WAL file to the open segment, opening a new one if needed, and
+    /// fsyncs it. If the segment has reached its maximum size it is sealed and queued for upload.
+    async fn append_to_segment(
+        &self,
+        wal_file_number: WalFileSequenceNumber,
+        data: &[u8],
+    ) -> std::io::Result<()> {
+        let mut segments = self.segments.lock().await;
+        if segments.open.is_none() {
+            segments.open = Some(
+                OpenSegment::create(&self.segment_dir, wal_file_number, self.time_provider.now())
+                    .await?,
+            );
+        }
+
+        let open = segments.open.as_mut().expect("segment was just opened");
+        if let Err(e) = open.append(wal_file_number, data).await {
+            // a partially written record would hide any records appended after it, so drop it, or
+            // stop appending to the segment if it can't be dropped
+            if let Err(truncate_error) = open.truncate().await {
+                error!(
+                    %truncate_error,
+                    path = %open.info.path.display(),
+                    "error truncating failed write from wal segment, sealing it"
+                );
+                segments.seal_open_segment(&self.upload_tx);
+            }
+            return Err(e);
+        }
+
+        if open.info.size_bytes >= self.max_segment_size_bytes {
+            segments.seal_open_segment(&self.upload_tx);
+        }
+
+        Ok(())
+    }
+
+    /// Seals the open segment if it has been open for longer than the maximum segment age, so
+    /// that the WAL files in it are uploaded even if the segment is not written to again
+    async fn seal_expired_segment(&self) {
+        let mut segments = self.segments.lock().await;
+        let now_ns = self.time_provider.now().timestamp_nanos();
+        let expired = segments.open.as_ref().is_some_and(|open| {
+            let age_ns = now_ns.saturating_sub(open.opened_at.timestamp_nanos());
+            age_ns as u128 >= self.max_segment_age.as_nanos()
+        });
+        if expired {
+            segments.seal_open_segment(&self.upload_tx);
+        }
+    }
+
+    async fn load_existing_segment_paths(&self) -> crate::Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.segment_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path
+                .extension()
+                .is_some_and(|ext| ext == SEGMENT_FILE_EXTENSION)
+            {
+                paths.push(path);
+            }
+        }
+        // segment file names are the zero padded number of the first WAL file they contain
+        paths.sort();
+
+        Ok(paths)
+    }
+
+    async fn remove_snapshot_wal_files(
+        &self,
+        snapshot_info: SnapshotInfo,
+        snapshot_permit: OwnedSemaphorePermit,
+    ) {
+        let last_wal_file = snapshot_info.snapshot_details.last_wal_sequence_number;
+
+        // record the removal first so that replay and the uploader skip these files, even if
+        // the process goes down part way through removing them
+        self.last_removed_wal_file
+            .fetch_max(last_wal_file.as_u64(), Ordering::SeqCst);
+        if let Err(e) = write_last_removed_wal_file(&self.segment_dir, last_wal_file).await {
+            error!(%e, "error recording last removed wal file");
+        }
+
+        let retain = !self.snapshotted_wal_retention.is_zero();
+        if retain {
+            self.retain_wal_files(&snapshot_info).await;
+        }
+
+        let removed_segments = self.segments.lock().await.remove_through(last_wal_file);
+        for segment in removed_segments {
+            remove_segment_file(&segment.path).await;
+        }
+
+        // uploaded copies of the wal files have to be removed from object store as well
+        for period in snapshot_info.wal_periods {
+            let path = wal_path(&self.host_identifier_prefix, period.wal_file_number);
+
+            loop {
+                match self.object_store.delete(&path).await {
+                    Ok(_) | Err(object_store::Error::NotFound { .. }) => break,
+                    Err(object_store::Error::Generic { store, source }) => {
+                        error!(%store, %source, "error deleting wal file");
+                        // hopefully just a temporary error, keep trying until we succeed
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                    Err(e) => {
+                        // this must be configuration or something else, log it and move on
+                        error!(%e, "error deleting wal file");
+                        break;
+                    }
+                }
+            }
+        }
+
+        if retain {
+            prune_retained_wal_files(
+                self.object_store.as_ref(),
+                &self.host_identifier_prefix,
+                self.time_provider.now(),
+                self.snapshotted_wal_retention,
+            )
+            .await;
+        }
+
+        // release the permit so the next snapshot can be run when the time comes
+        drop(snapshot_permit);
+    }
+
+    /// Writes the WAL files covered by a snapshot from the local segments to the retained prefix
+    /// in object store, before the segments are removed.
+    async fn retain_wal_files(&self, snapshot_info: &SnapshotInfo) {
+        let wal_file_numbers: HashSet<WalFileSequenceNumber> = snapshot_info
+            .wal_periods
+            .iter()
+            .map(|period| period.wal_file_number)
+            .collect();
+        let segment_paths = match self.load_existing_segment_paths().await {
+            Ok(paths) => paths,
+            Err(e) => {
+                error!(%e, "error listing wal segments to retain snapshotted wal files");
+                return;
+            }
+        };
+
+        for path in segment_paths {
+            let bytes = match tokio::fs::read(&path).await {
+                Ok(bytes) => Bytes::from(bytes),
+                Err(e) => {
+                    error!(%e, path = %path.display(), "error reading wal segment to retain");
+                    continue;
+                }
+            };
+            let (records, _) = decode_segment(bytes);
+            for record in records {
+                if !wal_file_numbers.contains(&record.wal_file_number) {
+                    continue;
+                }
+                let retained_path =
+                    retained_wal_path(&self.host_identifier_prefix, record.wal_file_number);
+                if let Err(e) = self
+                    .object_store
+                    .put(&retained_path, PutPayload::from_bytes(record.data))
+                    .await
+                {
+                    error!(%e, %retained_path, "error writing retained wal file");
+                }
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Wal for WalLocalDisk {
+    async fn buffer_op_unconfirmed(&self, op: WalOp) -> crate::Result<(), crate::Error> {
+        self.buffer_op_unconfirmed(op).await
+    }
+
+    async fn write_ops(&self, ops: Vec<WalOp>) -> crate::Result<(), crate::Error> {
+        self.write_ops(ops).await
+    }
+
+    async fn flush_buffer(
+        &self,
+    ) -> Option<(
+        oneshot::Receiver<SnapshotDetails>,
+        SnapshotInfo,
+        OwnedSemaphorePermit,
+    )> {
+        self.flush_buffer().await
+    }
+
+    async fn cleanup_snapshot(
+        &self,
+        snapshot_info: SnapshotInfo,
+        snapshot_permit: OwnedSemaphorePermit,
+    ) {
+        self.remove_snapshot_wal_files(snapshot_info, snapshot_permit)
+            .await
+    }
+
+    async fn last_wal_sequence_number(&self) -> WalFileSequenceNumber {
+        self.flush_buffer
+            .lock()
+            .await
+            .snapshot_tracker
+            .last_wal_sequence_number()
+    }
+
+    async fn last_snapshot_sequence_number(&self) -> SnapshotSequenceNumber {
+        self.flush_buffer
+            .lock()
+            .await
+            .snapshot_tracker
+            .last_snapshot_sequence_number()
+    }
+
+    async fn reserve_snapshot(
+        &self,
+    ) -> crate::Result<(WalFileSequenceNumber, SnapshotSequenceNumber)> {
+        Ok(self.flush_buffer.lock().await.reserve_snapshot())
+    }
+
+    async fn shutdown(&self) {
+        self.shutdown().await
+    }
+
+    fn quarantined_files(&self) -> Vec<QuarantinedWalFile> {
+        self.quarantined_files.lock().clone()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Segments {
+    /// The segment that flushed WAL files are currently appended to
+    open: Option<OpenSegment>,
+    /// Segments that are no longer appended to, keyed by the first WAL file they contain
+    sealed: BTreeMap<WalFileSequenceNumber, SegmentInfo>,
+}
+
+impl Segments {
+    fn seal_open_segment(&mut self, upload_tx: &mpsc::UnboundedSender<SegmentInfo>) {
+        if let Some(open) = self.open.take() {
+            debug!(path = %open.info.path.display(), "sealing wal segment");
+            self.sealed
+                .insert(open.info.first_wal_file, open.info.clone());
+            let _ = upload_tx.send(open.info);
+        }
+    }
+
+    /// Removes and returns the sealed segments that only contain WAL files with a sequence number
+    /// <= to the one given.
+    fn remove_through(&mut self, wal_file_number: WalFileSequenceNumber) -> Vec<SegmentInfo> {
+        let removable: Vec<WalFileSequenceNumber> = self
+            .sealed
+            .values()
+            .take_while(|segment| segment.last_wal_file <= wal_file_number)
+            .map(|segment| segment.first_wal_file)
+            .collect();
+
+        removable
+            .into_iter()
+            .filter_map(|first| self.sealed.remove(&first))
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+struct OpenSegment {
+    file: tokio::fs::File,
+    info: SegmentInfo,
+    /// When the segment was created
+    opened_at: Time,
+}
+
+impl OpenSegment {
+    async fn create(
+        segment_dir: &Path,
+        first_wal_file: WalFileSequenceNumber,
+        opened_at: Time,
+    ) -> std::io::Result<Self> {
+        let path = segment_path(segment_dir, first_wal_file);
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        // make sure the directory entry for the new segment is durable
+        tokio::fs::File::open(segment_dir).await?.sync_all().await?;
+
+        Ok(Self {
+            file,
+            info: SegmentInfo {
+                path,
+                first_wal_file,
+                last_wal_file: first_wal_file,
+                size_bytes: 0,
+            },
+            opened_at,
+        })
+    }
+
+    async fn append(
+        &mut self,
+        wal_file_number: WalFileSequenceNumber,
+        data: &[u8],
+    ) -> std::io::Result<()> {
+        let mut record = Vec::with_capacity(RECORD_HEADER_LEN + data.len());
+        record.extend_from_slice(&wal_file_number.as_u64().to_be_bytes());
+        record.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        record.extend_from_slice(&crc32fast::hash(data).to_be_bytes());
+        record.extend_from_slice(data);
+
+        self.file.write_all(&record).await?;
+        self.file.flush().await?;
+        self.file.sync_data().await?;
+
+        self.info.last_wal_file = wal_file_number;
+        self.info.size_bytes += record.len() as u64;
+
+        Ok(())
+    }
+
+    /// Truncates the segment back to the end of its last complete record
+    async fn truncate(&mut self) -> std::io::Result<()> {
+        self.file.set_len(self.info.size_bytes).await?;
+        self.file.sync_data().await
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SegmentInfo {
+    path: PathBuf,
+    first_wal_file: WalFileSequenceNumber,
+    last_wal_file: WalFileSequenceNumber,
+    size_bytes: u64,
+}
+
+#[derive(Debug)]
+struct SegmentRecord {
+    wal_file_number: WalFileSequenceNumber,
+    data: Bytes,
+}
+
+/// How the records of a segment file end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SegmentEnd {
+    /// All of the records were read
+    Complete,
+    /// The last record was only partially written, or does not match its checksum
+    TornRecord,
+    /// The record at the byte offset does not match its checksum, and is not the last one
+    Corrupt { offset: usize },
+}
+
+/// Splits the contents of a segment file into its records, up to the first record that is only
+/// partially written or does not match its checksum. A mismatched record is torn rather than
+/// corrupt if it is the last one, as its write was interrupted before it was fsync'd.
+fn decode_segment(bytes: Bytes) -> (Vec<SegmentRecord>, SegmentEnd) {
+    let mut records = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        if bytes.len() - pos < RECORD_HEADER_LEN {
+            return (records, SegmentEnd::TornRecord);
+        }
+        let wal_file_number = u64::from_be_bytes(
+            bytes[pos..pos + 8]
+                .try_into()
+                .expect("slice is 8 bytes long"),
+        );
+        let len = u32::from_be_bytes(
+            bytes[pos + 8..pos + 12]
+                .try_into()
+                .expect("slice is 4 bytes long"),
+        ) as usize;
+        let crc = u32::from_be_bytes(
+            bytes[pos + 12..pos + RECORD_HEADER_LEN]
+                .try_into()
+                .expect("slice is 4 bytes long"),
+        );
+        let start = pos + RECORD_HEADER_LEN;
+        if bytes.len() - start < len {
+            return (records, SegmentEnd::TornRecord);
+        }
+        if crc32fast::hash(&bytes[start..start + len]) != crc {
+            let end = if start + len == bytes.len() {
+                SegmentEnd::TornRecord
+            } else {
+                SegmentEnd::Corrupt { offset: pos }
+            };
+            return (records, end);
+        }
+
+        records.push(SegmentRecord {
+            wal_file_number: WalFileSequenceNumber::new(wal_file_number),
+            data: bytes.slice(start..start + len),
+        });
+        pos = start + len;
+    }
+
+    (records, SegmentEnd::Complete)
+}
+
+fn segment_path(segment_dir: &Path, first_wal_file: WalFileSequenceNumber) -> PathBuf {
+    segment_dir.join(format!(
+        "{:011}.{SEGMENT_FILE_EXTENSION}",
+        first_wal_file.as_u64()
+    ))
+}
+
+async fn remove_segment_file(path: &Path) {
+    match tokio::fs::remove_file(path).await {
+        Ok(_) => debug!(path = %path.display(), "removed wal segment"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+        Err(e) => error!(%e, path = %path.display(), "error removing wal segment"),
+    }
+}
+
+async fn read_last_removed_wal_file(segment_dir: &Path) -> std::io::Result<u64> {
+    match tokio::fs::read_to_string(segment_dir.join(LAST_REMOVED_FILE_NAME)).await {
+        Ok(s) => s.trim().parse().map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid contents in {LAST_REMOVED_FILE_NAME}: {s}"),
+            )
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e),
+    }
+}
+
+async fn write_last_removed_wal_file(
+    segment_dir: &Path,
+    wal_file_number: WalFileSequenceNumber,
+) -> std::io::Result<()> {
+    // write to a temporary file and rename it, so the marker is never left half written
+    let tmp_path = segment_dir.join(format!("{LAST_REMOVED_FILE_NAME}.tmp"));
+    let mut file = tokio::fs::File::create(&tmp_path).await?;
+    file.write_all(wal_file_number.to_string().as_bytes())
+        .await?;
+    file.sync_all().await?;
+    tokio::fs::rename(&tmp_path, segment_dir.join(LAST_REMOVED_FILE_NAME)).await
+}
+
+/// Background task that uploads the WAL files contained in sealed segments to object store.
+/// Uploads are retried until they succeed, and skipped for any WAL files that have already been
+/// removed by a snapshot.
+async fn upload_sealed_segments(
+    object_store: Arc<dyn ObjectStore>,
+    host_identifier_prefix: String,
+    last_removed_wal_file: Arc<AtomicU64>,
+    quarantined_files: Arc<parking_lot::Mutex<Vec<QuarantinedWalFile>>>,
+    mut upload_rx: mpsc::UnboundedReceiver<SegmentInfo>,
+) {
+    while let Some(segment) = upload_rx.recv().await {
+        let bytes = match tokio::fs::read(&segment.path).await {
+            Ok(bytes) => Bytes::from(bytes),
+            Err(e) => {
+                // the segment was most likely removed by a snapshot before it could be uploaded
+                debug!(%e, path = %segment.path.display(), "unable to read wal segment for upload");
+                continue;
+            }
+        };
+
+        let (records, _) = decode_segment(bytes);
+        for record in records {
+            let quarantined = quarantined_files
+                .lock()
+                .iter()
+                .any(|f| f.wal_file_number == record.wal_file_number);
+            if quarantined {
+                continue;
+            }
+            let path = wal_path(&host_identifier_prefix, record.wal_file_number);
+            loop {
+                if record.wal_file_number.as_u64() <= last_removed_wal_file.load(Ordering::SeqCst) {
+                    break;
+                }
+                match object_store
+                    .put(&path, PutPayload::from_bytes(record.data.clone()))
+                    .await
+                {
+                    Ok(_) => break,
+                    Err(e) => {
+                        error!(%e, "error uploading wal file to object store");
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                }
+            }
+        }
+        debug!(path = %segment.path.display(), "uploaded wal segment to object store");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Field, FieldData, Gen1Duration, Row, TableChunk, TableChunks, WalCompression, WalContents,
+        WriteBatch,
+    };
+    use async_trait::async_trait;
+    use futures_util::StreamExt;
+    use hashbrown::HashMap;
+    use influxdb3_id::{DbId, TableId};
+    use iox_time::{MockProvider, Time};
+    use object_store::memory::InMemory;
+    use std::any::Any;
+    use tokio::sync::oneshot::Receiver;
+
+    fn write_op(time: i64) -> WalOp {
+        WalOp::Write(WriteBatch {
+            database_id: DbId::from(0),
+            database_name: "db1".into(),
+            table_chunks: HashMap::from([(
+                TableId::from(0),
+                TableChunks {
+                    min_time: time,
+                    max_time: time,
+                    chunk_time_to_chunk: HashMap::from([(
+                        0,
+                        TableChunk {
+                            rows: vec![Row {
+                                time,
+                                fields: vec![
+                                    Field {
+                                        name: "f1".into(),
+                                        value: FieldData::Integer(1),
+                                    },
+                                    Field {
+                                        name: "time".into(),
+                                        value: FieldData::Timestamp(time),
+                                    },
+                                ],
+                            }],
+                        },
+                    )]),
+                },
+            )]),
+            min_time_ns: time,
+            max_time_ns: time,
+        })
+    }
+
+    fn wal_config() -> WalConfig {
+        WalConfig {
+            max_write_buffer_size: 100,
+            max_write_buffer_bytes: 1024 * 1024,
+            buffer_full_timeout: Duration::from_secs(1),
+            flush_interval: Duration::from_secs(1),
+            snapshot_size: 2,
+            compression: WalCompression::Snappy,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
+            gen1_duration: Gen1Duration::new_1m(),
+        }
+    }
+
+    async fn list_wal_files(object_store: &Arc<dyn ObjectStore>) -> Vec<object_store::path::Path> {
+        let mut paths: Vec<_> = object_store
+            .list(None)
+            .map(|meta| meta.unwrap().location)
+            .collect()
+            .await;
+        paths.sort();
+        paths
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn write_flush_replay_and_upload() {
+        let dir = test_helpers::tmp_dir().unwrap();
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let notifier: Arc<dyn WalFileNotifier> = Arc::new(TestNotifier::default());
+        let local_disk_config = LocalDiskConfig {
+            directory: dir.path().to_path_buf(),
+            // seal segments as soon as anything is written to them
+            max_segment_size_bytes: 1,
+            max_segment_age: Duration::from_secs(60),
+        };
+        let wal = WalLocalDisk::new_without_replay(
+            Arc::clone(&object_store),
+            "my_host",
+            Arc::clone(&notifier),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+            wal_config(),
+            LocalDiskConfig {
+                max_segment_size_bytes: u64::MAX,
+                ..local_disk_config.clone()
+            },
+            &metric::Registry::default(),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        wal.buffer_op_unconfirmed(write_op(1)).await.unwrap();
+        assert!(wal.flush_buffer().await.is_none());
+        wal.buffer_op_unconfirmed(write_op(2)).await.unwrap();
+        assert!(wal.flush_buffer().await.is_none());
+
+        // both wal files are appended to a single segment
+        let segment_paths = wal.load_existing_segment_paths().await.unwrap();
+        assert_eq!(
+            segment_paths,
+            vec![dir.path().join("my_host/wal/00000000001.seg")]
+        );
+        let notifier = notifier.as_any().downcast_ref::<TestNotifier>().unwrap();
+        let written = notifier.notified_writes.lock().clone();
+        assert_eq!(written.len(), 2);
+
+        // nothing is uploaded until the segment is sealed
+        assert!(list_wal_files(&object_store).await.is_empty());
+
+        // replay with a new wal and notifier, which seals the existing segment and uploads it
+        let replay_notifier: Arc<dyn WalFileNotifier> = Arc::new(TestNotifier::default());
+        let replay_wal = WalLocalDisk::new_without_replay(
+            Arc::clone(&object_store),
+            "my_host",
+            Arc::clone(&replay_notifier),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+            wal_config(),
+            local_disk_config,
+            &metric::Registry::default(),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        replay_wal.replay().await.unwrap();
+        let replay_notifier = replay_notifier
+            .as_any()
+            .downcast_ref::<TestNotifier>()
+            .unwrap();
+        assert_eq!(*replay_notifier.notified_writes.lock(), written);
+        assert_eq!(
+            replay_wal.last_wal_sequence_number().await,
+            WalFileSequenceNumber::new(2)
+        );
+
+        let expected_uploads = vec![
+            object_store::path::Path::from("my_host/wal/00000000001.wal"),
+            object_store::path::Path::from("my_host/wal/00000000002.wal"),
+        ];
+        let mut checks = 0;
+        while list_wal_files(&object_store).await != expected_uploads {
+            checks += 1;
+            assert!(checks < 100, "wal files were not uploaded");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // the uploaded files are valid wal files
+        let bytes = object_store
+            .get(&expected_uploads[0])
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        assert_eq!(verify_file_type_and_deserialize(bytes).unwrap(), written[0]);
+
+        // new writes go into a new segment, which is sealed immediately with this config
+        replay_wal.buffer_op_unconfirmed(write_op(3)).await.unwrap();
+        assert!(replay_wal.flush_buffer().await.is_none());
+        assert_eq!(
+            replay_wal.load_existing_segment_paths().await.unwrap(),
+            vec![
+                dir.path().join("my_host/wal/00000000001.seg"),
+                dir.path().join("my_host/wal/00000000003.seg"),
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn segments_are_sealed_and_uploaded_once_they_reach_max_age() {
+        let dir = test_helpers::tmp_dir().unwrap();
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let wal = WalLocalDisk::new_without_replay(
+            Arc::clone(&object_store),
+            "my_host",
+            Arc::new(TestNotifier::default()),
+            Arc::clone(&time_provider) as _,
+            wal_config(),
+            LocalDiskConfig {
+                directory: dir.path().to_path_buf(),
+                max_segment_size_bytes: u64::MAX,
+                max_segment_age: Duration::from_secs(10),
+            },
+            &metric::Registry::default(),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        wal.buffer_op_unconfirmed(write_op(1)).await.unwrap();
+        assert!(wal.flush_buffer().await.is_none());
+        time_provider.set(Time::from_timestamp_nanos(9_000_000_000));
+        assert!(wal.flush_buffer().await.is_none());
+        assert!(wal.segments.lock().await.open.is_some());
+
+        // the flush interval seals the segment once it is old enough, without any new writes
+        time_provider.set(Time::from_timestamp_nanos(10_000_000_000));
+        assert!(wal.flush_buffer().await.is_none());
+        assert!(wal.segments.lock().await.open.is_none());
+
+        let expected_uploads = vec![object_store::path::Path::from(
+            "my_host/wal/00000000001.wal",
+        )];
+        let mut checks = 0;
+        while list_wal_files(&object_store).await != expected_uploads {
+            checks += 1;
+            assert!(checks < 100, "wal files were not uploaded");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn snapshot_removes_segments() {
+        let dir = test_helpers::tmp_dir().unwrap();
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let notifier: Arc<dyn WalFileNotifier> = Arc::new(TestNotifier::default());
+        let local_disk_config = LocalDiskConfig {
+            directory: dir.path().to_path_buf(),
+            max_segment_size_bytes: 1,
+            max_segment_age: Duration::from_secs(60),
+        };
+        let wal = WalLocalDisk::new_without_replay(
+            Arc::clone(&object_store),
+            "my_host",
+            Arc::clone(&notifier),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+            wal_config(),
+            local_disk_config.clone(),
+            &metric::Registry::default(),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        wal.buffer_op_unconfirmed(write_op(1)).await.unwrap();
+        assert!(wal.flush_buffer().await.is_none());
+        wal.buffer_op_unconfirmed(write_op(62_000000000))
+            .await
+            .unwrap();
+        assert!(wal.flush_buffer().await.is_none());
+
+        // the third file triggers a snapshot of the first two
+        wal.buffer_op_unconfirmed(write_op(128_000000000))
+            .await
+            .unwrap();
+        let (snapshot_done, snapshot_info, snapshot_permit) = wal.flush_buffer().await.unwrap();
+        let details = snapshot_done.await.unwrap();
+        assert_eq!(
+            details.last_wal_sequence_number,
+            WalFileSequenceNumber::new(2)
+        );
+        wal.cleanup_snapshot(snapshot_info, snapshot_permit).await;
+
+        assert_eq!(
+            wal.load_existing_segment_paths().await.unwrap(),
+            vec![dir.path().join("my_host/wal/00000000003.seg")]
+        );
+
+        // replay skips the wal files that were removed by the snapshot
+        let replay_notifier: Arc<dyn WalFileNotifier> = Arc::new(TestNotifier::default());
+        let replay_wal = WalLocalDisk::new_without_replay(
+            Arc::clone(&object_store),
+            "my_host",
+            Arc::clone(&replay_notifier),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+            wal_config(),
+            local_disk_config,
+            &metric::Registry::default(),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        replay_wal.replay().await.unwrap();
+        let replay_notifier = replay_notifier
+            .as_any()
+            .downcast_ref::<TestNotifier>()
+            .unwrap();
+        let replayed: Vec<WalFileSequenceNumber> = replay_notifier
+            .notified_writes
+            .lock()
+            .iter()
+            .map(|c| c.wal_file_number)
+            .collect();
+        assert_eq!(replayed, vec![WalFileSequenceNumber::new(3)]);
+    }
+
+    fn encode_record(bytes: &mut Vec<u8>, wal_file_number: u64, data: &[u8]) {
+        bytes.extend_from_slice(&wal_file_number.to_be_bytes());
+        bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&crc32fast::hash(data).to_be_bytes());
+        bytes.extend_from_slice(data);
+    }
+
+    #[test]
+    fn decode_segment_with_torn_tail() {
+        let mut bytes = Vec::new();
+        encode_record(&mut bytes, 1, b"abc");
+        encode_record(&mut bytes, 2, b"defg");
+
+        let (records, end) = decode_segment(Bytes::from(bytes.clone()));
+        assert_eq!(end, SegmentEnd::Complete);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].wal_file_number, WalFileSequenceNumber::new(2));
+        assert_eq!(records[1].data.as_ref(), b"defg");
+
+        // a record that was only partially written is dropped
+        let mut partial = bytes.clone();
+        partial.truncate(partial.len() - 1);
+        let (records, end) = decode_segment(Bytes::from(partial));
+        assert_eq!(end, SegmentEnd::TornRecord);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].data.as_ref(), b"abc");
+
+        // as is a last record that does not match its checksum
+        let mut garbled = bytes.clone();
+        *garbled.last_mut().unwrap() = b'x';
+        let (records, end) = decode_segment(Bytes::from(garbled));
+        assert_eq!(end, SegmentEnd::TornRecord);
+        assert_eq!(records.len(), 1);
+    }
+
+    #[test]
+    fn decode_segment_with_corrupt_record() {
+        let mut bytes = Vec::new();
+        encode_record(&mut bytes, 1, b"abc");
+        encode_record(&mut bytes, 2, b"defg");
+        encode_record(&mut bytes, 3, b"hi");
+        // flip a byte in the data of the second record
+        bytes[2 * RECORD_HEADER_LEN + 3] = b'x';
+
+        let (records, end) = decode_segment(Bytes::from(bytes));
+        assert_eq!(
+            end,
+            SegmentEnd::Corrupt {
+                offset: RECORD_HEADER_LEN + 3
+            }
+        );
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].data.as_ref(), b"abc");
+    }
+
+    #[tokio::test]
+    async fn truncate_drops_partially_written_record() {
+        let dir = test_helpers::tmp_dir().unwrap();
+        let mut segment = OpenSegment::create(
+            dir.path(),
+            WalFileSequenceNumber::new(1),
+            Time::from_timestamp_nanos(0),
+        )
+        .await
+        .unwrap();
+        segment
+            .append(WalFileSequenceNumber::new(1), b"abc")
+            .await
+            .unwrap();
+
+        // the start of a record whose write failed part way through
+        let mut partial = Vec::new();
+        encode_record(&mut partial, 2, b"defg");
+        segment.file.write_all(&partial[..10]).await.unwrap();
+        segment.file.flush().await.unwrap();
+
+        segment.truncate().await.unwrap();
+        segment
+            .append(WalFileSequenceNumber::new(3), b"hi")
+            .await
+            .unwrap();
+
+        let bytes = Bytes::from(tokio::fs::read(&segment.info.path).await.unwrap());
+        assert_eq!(segment.info.size_bytes, bytes.len() as u64);
+        let (records, end) = decode_segment(bytes);
+        assert_eq!(end, SegmentEnd::Complete);
+        let wal_file_numbers = records
+            .iter()
+            .map(|record| record.wal_file_number.as_u64())
+            .collect::<Vec<_>>();
+        assert_eq!(wal_file_numbers, vec![1, 3]);
+    }
+
+    #[derive(Debug, Default)]
+    struct TestNotifier {
+        notified_writes: parking_lot::Mutex<Vec<WalContents>>,
+    }
+
+    #[async_trait]
+    impl WalFileNotifier for TestNotifier {
+        fn notify(&self, write: WalContents) {
+            self.notified_writes.lock().push(write);
+        }
+
+        async fn notify_and_snapshot(
+            &self,
+            write: WalContents,
+            snapshot_details: SnapshotDetails,
+        ) -> Receiver<SnapshotDetails> {
+            self.notified_writes.lock().push(write);
+
+            let (sender, receiver) = tokio::sync::oneshot::channel();
+            tokio::spawn(async move {
+                sender.send(snapshot_details).unwrap();
+            });
+
+            receiver
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+}
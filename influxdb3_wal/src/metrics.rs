@@ -0,0 +1,179 @@
+//! Metrics recorded by the WAL as it writes files.
+
+use crate::WalCompression;
+use metric::{
+    DurationHistogram, Registry, U64Counter, U64Gauge, U64Histogram, U64HistogramOptions,
+};
+use std::time::Duration;
+
+/// Buckets for the compression ratio histogram. The ratio is recorded as the uncompressed size of
+/// a file's contents divided by the size written, multiplied by 100, so 400 is a ratio of 4:1.
+const COMPRESSION_RATIO_BUCKETS: [u64; 11] = [
+    100,
+    125,
+    150,
+    200,
+    300,
+    400,
+    600,
+    800,
+    1_000,
+    2_000,
+    u64::MAX,
+];
+
+#[derive(Debug)]
+pub(crate) struct WalMetrics {
+    /// Total size of the serialized contents of wal files before compression
+    uncompressed_bytes: U64Counter,
+    /// Total size of the contents of wal files after compression, as written
+    written_bytes: U64Counter,
+    /// Distribution of the compression ratio of individual wal files
+    compression_ratio: U64Histogram,
+    /// Number of wal files that were quarantined during replay
+    quarantined_files: U64Counter,
+    /// Number of ops in the buffer waiting to be flushed to a wal file
+    buffered_ops: U64Gauge,
+    /// Estimated size of the ops in the buffer waiting to be flushed to a wal file
+    buffered_bytes: U64Gauge,
+    /// Time writes spent waiting for a full buffer to be flushed
+    buffer_full_wait: DurationHistogram,
+    /// Number of writes rejected because the buffer stayed full for the timeout
+    buffer_full_rejections: U64Counter,
+}
+
+impl WalMetrics {
+    pub(crate) fn new(registry: &Registry, compression: WalCompression) -> Self {
+        let attributes = [("compression", compression.as_str())];
+        let uncompressed_bytes = registry
+            .register_metric::<U64Counter>(
+                "influxdb3_wal_file_uncompressed_bytes",
+                "total size in bytes of the contents of wal files before compression",
+            )
+            .recorder(&attributes);
+        let written_bytes = registry
+            .register_metric::<U64Counter>(
+                "influxdb3_wal_file_written_bytes",
+                "total size in bytes of the contents of wal files after compression",
+            )
+            .recorder(&attributes);
+        let compression_ratio = registry
+            .register_metric_with_options::<U64Histogram, _>(
+                "influxdb3_wal_file_compression_ratio_percent",
+                "compression ratio of wal files, as uncompressed size over written size times 100",
+                || U64HistogramOptions::new(COMPRESSION_RATIO_BUCKETS),
+            )
+            .recorder(&attributes);
+        let quarantined_files = registry
+            .register_metric::<U64Counter>(
+                "influxdb3_wal_quarantined_files",
+                "number of wal files that could not be deserialized during replay and were \
+                quarantined",
+            )
+            .recorder(&[]);
+        let buffered_ops = registry
+            .register_metric::<U64Gauge>(
+                "influxdb3_wal_buffer_ops",
+                "number of ops buffered in memory waiting to be flushed to a wal file",
+            )
+            .recorder(&[]);
+        let buffered_bytes = registry
+            .register_metric::<U64Gauge>(
+                "influxdb3_wal_buffer_bytes",
+                "estimated size in bytes of the ops buffered in memory waiting to be flushed to a \
+                wal file",
+            )
+            .recorder(&[]);
+        let buffer_full_wait = registry
+            .register_metric::<DurationHistogram>(
+                "influxdb3_wal_buffer_full_wait_duration",
+                "time writes waited for a full wal buffer to be flushed",
+            )
+            .recorder(&[]);
+        let buffer_full_rejections = registry
+            .register_metric::<U64Counter>(
+                "influxdb3_wal_buffer_full_rejections",
+                "number of writes rejected because the wal buffer was full for longer than the \
+                timeout",
+            )
+            .recorder(&[]);
+
+        Self {
+            uncompressed_bytes,
+            written_bytes,
+            compression_ratio,
+            quarantined_files,
+            buffered_ops,
+            buffered_bytes,
+            buffer_full_wait,
+            buffer_full_rejections,
+        }
+    }
+
+    /// Record the size of the contents of a single wal file before and after compression
+    pub(crate) fn record_file(&self, uncompressed_bytes: usize, written_bytes: usize) {
+        self.uncompressed_bytes.inc(uncompressed_bytes as u64);
+        self.written_bytes.inc(written_bytes as u64);
+        if written_bytes > 0 {
+            self.compression_ratio
+                .record(uncompressed_bytes as u64 * 100 / written_bytes as u64);
+        }
+    }
+
+    pub(crate) fn record_quarantined_file(&self) {
+        self.quarantined_files.inc(1);
+    }
+
+    /// Record the number and estimated size of the ops currently in the buffer
+    pub(crate) fn record_buffer_depth(&self, ops: usize, bytes: usize) {
+        self.buffered_ops.set(ops as u64);
+        self.buffered_bytes.set(bytes as u64);
+    }
+
+    /// Record the time a write waited for a full buffer to be flushed, and whether it was rejected
+    /// after waiting
+    pub(crate) fn record_buffer_full_wait(&self, wait: Duration, rejected: bool) {
+        self.buffer_full_wait.record(wait);
+        if rejected {
+            self.buffer_full_rejections.inc(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metric::{Attributes, Metric};
+
+    #[test]
+    fn records_sizes_and_ratio() {
+        let registry = Registry::default();
+        let metrics = WalMetrics::new(&registry, WalCompression::Zstd);
+        metrics.record_file(1_000, 250);
+        metrics.record_file(500, 250);
+
+        let attributes = Attributes::from(&[("compression", "zstd")]);
+        let uncompressed = registry
+            .get_instrument::<Metric<U64Counter>>("influxdb3_wal_file_uncompressed_bytes")
+            .unwrap()
+            .get_observer(&attributes)
+            .unwrap()
+            .fetch();
+        assert_eq!(uncompressed, 1_500);
+        let written = registry
+            .get_instrument::<Metric<U64Counter>>("influxdb3_wal_file_written_bytes")
+            .unwrap()
+            .get_observer(&attributes)
+            .unwrap()
+            .fetch();
+        assert_eq!(written, 500);
+        let ratio = registry
+            .get_instrument::<Metric<U64Histogram>>("influxdb3_wal_file_compression_ratio_percent")
+            .unwrap()
+            .get_observer(&attributes)
+            .unwrap()
+            .fetch();
+        assert_eq!(ratio.sample_count(), 2);
+        assert_eq!(ratio.total, 600);
+    }
+}
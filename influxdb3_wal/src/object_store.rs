@@ -1,38 +1,56 @@
+use crate::metrics::WalMetrics;
 use crate::serialize::verify_file_type_and_deserialize;
 use crate::snapshot_tracker::{SnapshotInfo, SnapshotTracker, WalPeriod};
 use crate::{
-    background_wal_flush, CatalogBatch, SnapshotDetails, SnapshotSequenceNumber, Wal, WalConfig,
-    WalContents, WalFileNotifier, WalFileSequenceNumber, WalOp, WriteBatch,
+    background_wal_flush, QuarantinedWalFile, SnapshotDetails, SnapshotSequenceNumber, Wal,
+    WalCompression, WalConfig, WalContents, WalFileNotifier, WalFileSequenceNumber, WalOp,
+    WriteBatch,
 };
 use bytes::Bytes;
 use data_types::Timestamp;
 use futures_util::stream::StreamExt;
 use hashbrown::HashMap;
+use iox_time::{Time, TimeProvider};
 use object_store::path::{Path, PathPart};
-use object_store::{ObjectStore, PutPayload};
-use observability_deps::tracing::{debug, error, info};
+use object_store::{ObjectMeta, ObjectStore, PutPayload};
+use observability_deps::tracing::{debug, error, info, warn};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Mutex;
-use tokio::sync::{oneshot, OwnedSemaphorePermit, Semaphore};
+use tokio::sync::{oneshot, Notify, OwnedSemaphorePermit, Semaphore};
+use tokio::sync::{Mutex, MutexGuard};
 
 #[derive(Debug)]
 pub struct WalObjectStore {
     object_store: Arc<dyn ObjectStore>,
     host_identifier_prefix: String,
     file_notifier: Arc<dyn WalFileNotifier>,
+    time_provider: Arc<dyn TimeProvider>,
     /// Buffered wal ops go in here along with the state to track when to snapshot
     flush_buffer: Mutex<FlushBuffer>,
+    /// Holds writes back while the buffer is full
+    backpressure: BufferBackpressure,
+    /// The compression applied to the contents of wal files as they are written
+    compression: WalCompression,
+    metrics: WalMetrics,
+    /// If set, files that fail to deserialize during replay are quarantined and skipped
+    quarantine_corrupt_files: bool,
+    /// The files that were quarantined during replay
+    quarantined_files: parking_lot::Mutex<Vec<QuarantinedWalFile>>,
+    /// How long wal files are retained after a snapshot covers them
+    snapshotted_wal_retention: Duration,
 }
 
 impl WalObjectStore {
     /// Creates a new WAL. This will replay files into the notifier and trigger any snapshots that
     /// exist in the WAL files that haven't been cleaned up yet.
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         object_store: Arc<dyn ObjectStore>,
         host_identifier_prefix: impl Into<String> + Send,
         file_notifier: Arc<dyn WalFileNotifier>,
+        time_provider: Arc<dyn TimeProvider>,
         config: WalConfig,
+        metric_registry: Arc<metric::Registry>,
         last_wal_sequence_number: Option<WalFileSequenceNumber>,
         last_snapshot_sequence_number: Option<SnapshotSequenceNumber>,
     ) -> Result<Arc<Self>, crate::Error> {
@@ -41,47 +59,51 @@ impl WalObjectStore {
             object_store,
             host_identifier_prefix,
             file_notifier,
+            time_provider,
             config,
+            &metric_registry,
             last_wal_sequence_number,
             last_snapshot_sequence_number,
         );
 
         wal.replay().await?;
         let wal = Arc::new(wal);
-        background_wal_flush(Arc::clone(&wal), flush_interval);
+        background_wal_flush(
+            Arc::clone(&wal),
+            flush_interval,
+            wal.backpressure.flush_requested(),
+        );
 
         Ok(wal)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn new_without_replay(
         object_store: Arc<dyn ObjectStore>,
         host_identifier_prefix: impl Into<String>,
         file_notifier: Arc<dyn WalFileNotifier>,
+        time_provider: Arc<dyn TimeProvider>,
         config: WalConfig,
+        metric_registry: &metric::Registry,
         last_wal_sequence_number: Option<WalFileSequenceNumber>,
         last_snapshot_sequence_number: Option<SnapshotSequenceNumber>,
     ) -> Self {
-        let wal_file_sequence_number = last_wal_sequence_number.unwrap_or_default().next();
         Self {
             object_store,
             host_identifier_prefix: host_identifier_prefix.into(),
             file_notifier,
-            flush_buffer: Mutex::new(FlushBuffer::new(
-                WalBuffer {
-                    is_shutdown: false,
-                    wal_file_sequence_number,
-                    op_limit: config.max_write_buffer_size,
-                    op_count: 0,
-                    database_to_write_batch: Default::default(),
-                    catalog_batches: vec![],
-                    write_op_responses: vec![],
-                },
-                SnapshotTracker::new(
-                    config.snapshot_size,
-                    config.gen1_duration,
-                    last_snapshot_sequence_number,
-                ),
+            time_provider,
+            flush_buffer: Mutex::new(FlushBuffer::new_from_config(
+                config,
+                last_wal_sequence_number,
+                last_snapshot_sequence_number,
             )),
+            backpressure: BufferBackpressure::new(config.buffer_full_timeout),
+            compression: config.compression,
+            metrics: WalMetrics::new(metric_registry, config.compression),
+            quarantine_corrupt_files: config.quarantine_corrupt_files,
+            quarantined_files: Default::default(),
+            snapshotted_wal_retention: config.snapshotted_wal_retention,
         }
     }
 
@@ -92,51 +114,67 @@ impl WalObjectStore {
 
         for path in paths {
             let file_bytes = self.object_store.get(&path).await?.bytes().await?;
-            let wal_contents = verify_file_type_and_deserialize(file_bytes)?;
-
-            // add this to the snapshot tracker, so we know what to clear out later if the replay
-            // was a wal file that had a snapshot
-            self.flush_buffer
-                .lock()
-                .await
-                .replay_wal_period(WalPeriod::new(
-                    wal_contents.wal_file_number,
-                    Timestamp::new(wal_contents.min_timestamp_ns),
-                    Timestamp::new(wal_contents.max_timestamp_ns),
-                ));
-
-            match wal_contents.snapshot {
-                None => self.file_notifier.notify(wal_contents),
-                Some(snapshot_details) => {
-                    let snapshot_info = {
-                        let mut buffer = self.flush_buffer.lock().await;
-
-                        match buffer.snapshot_tracker.snapshot() {
-                            None => None,
-                            Some(info) => {
-                                let semaphore = Arc::clone(&buffer.snapshot_semaphore);
-                                let permit = semaphore.acquire_owned().await.unwrap();
-
-                                Some((info, permit))
-                            }
-                        }
-                    };
-
-                    let snapshot_done = self
-                        .file_notifier
-                        .notify_and_snapshot(wal_contents, snapshot_details)
-                        .await;
-                    let details = snapshot_done.await.unwrap();
-                    assert_eq!(snapshot_details, details);
-
-                    // if the info is there, we have wal files to delete
-                    if let Some((snapshot_info, snapshot_permit)) = snapshot_info {
-                        self.cleanup_snapshot(snapshot_info, snapshot_permit).await;
-                    }
+            let wal_contents = match verify_file_type_and_deserialize(file_bytes) {
+                Ok(wal_contents) => wal_contents,
+                Err(e) if self.quarantine_corrupt_files => {
+                    self.quarantine(&path, e).await?;
+                    continue;
                 }
+                Err(e) => return Err(e.into()),
+            };
+
+            // if the info is there, we have wal files to delete
+            if let Some((snapshot_info, snapshot_permit)) =
+                replay_wal_contents(&self.flush_buffer, &self.file_notifier, wal_contents).await
+            {
+                self.cleanup_snapshot(snapshot_info, snapshot_permit).await;
             }
         }
 
+        let wal_file_numbers = self
+            .quarantined_files
+            .lock()
+            .iter()
+            .map(|f| f.wal_file_number.to_string())
+            .collect::<Vec<_>>();
+        if !wal_file_numbers.is_empty() {
+            let wal_file_numbers = wal_file_numbers.join(", ");
+            warn!(
+                %wal_file_numbers,
+                "replay skipped wal files that could not be deserialized, writes in them are lost"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Moves a wal file that could not be deserialized to the quarantine prefix, so that it is not
+    /// replayed again, and records it.
+    async fn quarantine(&self, path: &Path, error: crate::serialize::Error) -> crate::Result<()> {
+        let wal_file_number = wal_file_number_from_path(path)?;
+        let quarantine_path = quarantine_path(&self.host_identifier_prefix, wal_file_number);
+        warn!(
+            %path,
+            %wal_file_number,
+            %quarantine_path,
+            %error,
+            "quarantining wal file that could not be deserialized"
+        );
+        self.object_store.rename(path, &quarantine_path).await?;
+
+        // never reuse the sequence number of the quarantined file
+        self.flush_buffer
+            .lock()
+            .await
+            .skip_wal_file(wal_file_number);
+        self.metrics.record_quarantined_file();
+        self.quarantined_files.lock().push(QuarantinedWalFile {
+            wal_file_number,
+            path: path.to_string(),
+            quarantine_path: quarantine_path.to_string(),
+            error: error.to_string(),
+        });
+
         Ok(())
     }
 
@@ -156,22 +194,30 @@ impl WalObjectStore {
 
     /// Buffer into a single larger operation in memory. Returns before the operation is persisted.
     async fn buffer_op_unconfirmed(&self, op: WalOp) -> crate::Result<(), crate::Error> {
-        self.flush_buffer
-            .lock()
-            .await
-            .wal_buffer
-            .buffer_op_unconfirmed(op)
+        let mut flush_buffer = self
+            .backpressure
+            .lock_with_capacity(&self.flush_buffer, &self.metrics)
+            .await?;
+        flush_buffer.wal_buffer.buffer_op_unconfirmed(op);
+        self.backpressure
+            .buffered(&flush_buffer.wal_buffer, &self.metrics);
+
+        Ok(())
     }
 
     /// Writes the op into the buffer and waits until the WAL file is persisted. When this returns
     /// the operation is durable in the configured object store.
     async fn write_ops(&self, ops: Vec<WalOp>) -> crate::Result<(), crate::Error> {
         let (tx, rx) = oneshot::channel();
-        self.flush_buffer
-            .lock()
-            .await
-            .wal_buffer
-            .buffer_ops_with_response(ops, tx)?;
+        {
+            let mut flush_buffer = self
+                .backpressure
+                .lock_with_capacity(&self.flush_buffer, &self.metrics)
+                .await?;
+            flush_buffer.wal_buffer.buffer_ops_with_response(ops, tx);
+            self.backpressure
+                .buffered(&flush_buffer.wal_buffer, &self.metrics);
+        }
 
         match rx.await {
             Ok(WriteResult::Success(())) => Ok(()),
@@ -189,15 +235,18 @@ impl WalObjectStore {
         SnapshotInfo,
         OwnedSemaphorePermit,
     )> {
-        let (wal_contents, responses, snapshot) = {
+        let (mut wal_contents, responses, snapshot) = {
             let mut flush_buffer = self.flush_buffer.lock().await;
             if flush_buffer.wal_buffer.is_empty() {
                 return None;
             }
-            flush_buffer
+            let flushed = flush_buffer
                 .flush_buffer_into_contents_and_responses()
-                .await
+                .await;
+            self.backpressure.flushed(&self.metrics);
+            flushed
         };
+        wal_contents.persisted_time_ns = self.time_provider.now().timestamp_nanos();
         info!(
             n_ops = %wal_contents.ops.len(),
             min_timestamp_ns = %wal_contents.min_timestamp_ns,
@@ -208,9 +257,11 @@ impl WalObjectStore {
         );
 
         let wal_path = wal_path(&self.host_identifier_prefix, wal_contents.wal_file_number);
-        let data = crate::serialize::serialize_to_file_bytes(&wal_contents)
+        let serialized = crate::serialize::serialize_to_file_bytes(&wal_contents, self.compression)
             .expect("unable to serialize wal contents into bytes for file");
-        let data = Bytes::from(data);
+        self.metrics
+            .record_file(serialized.uncompressed_len, serialized.compressed_len);
+        let data = Bytes::from(serialized.bytes);
 
         let mut retry_count = 0;
 
@@ -239,6 +290,7 @@ impl WalObjectStore {
                             .await
                             .flush_buffer_with_failure(WriteResult::Error(e.to_string()))
                             .await;
+                        self.backpressure.flushed(&self.metrics);
 
                         return None;
                     }
@@ -249,61 +301,17 @@ impl WalObjectStore {
         }
 
         // now that we've persisted this latest notify and start the snapshot, if set
-        let snapshot_response = match wal_contents.snapshot {
-            Some(snapshot_details) => {
-                info!(?snapshot_details, "snapshotting wal");
-                let snapshot_done = self
-                    .file_notifier
-                    .notify_and_snapshot(wal_contents, snapshot_details)
-                    .await;
-                let (snapshot_info, snapshot_permit) =
-                    snapshot.expect("snapshot should be set when snapshot details are set");
-                Some((snapshot_done, snapshot_info, snapshot_permit))
-            }
-            None => {
-                debug!(
-                    "notify sent to buffer for wal file {}",
-                    wal_contents.wal_file_number.as_u64()
-                );
-                self.file_notifier.notify(wal_contents);
-                None
-            }
-        };
-
-        // send all the responses back to clients
-        for response in responses {
-            let _ = response.send(WriteResult::Success(()));
-        }
-
-        snapshot_response
+        notify_and_respond(&self.file_notifier, wal_contents, snapshot, responses).await
     }
 
     async fn load_existing_wal_file_paths(&self) -> crate::Result<Vec<Path>> {
-        let mut paths = Vec::new();
-        let mut offset: Option<Path> = None;
-        let path = Path::from(format!("{host}/wal", host = self.host_identifier_prefix));
-        loop {
-            let mut listing = if let Some(offset) = offset {
-                self.object_store.list_with_offset(Some(&path), &offset)
-            } else {
-                self.object_store.list(Some(&path))
-            };
-            let path_count = paths.len();
-
-            while let Some(item) = listing.next().await {
-                paths.push(item?.location);
-            }
-
-            if path_count == paths.len() {
-                break;
-            }
-
-            paths.sort();
-            offset = Some(paths.last().unwrap().clone())
-        }
-        paths.sort();
-
-        Ok(paths)
+        Ok(
+            list_wal_files(self.object_store.as_ref(), &self.host_identifier_prefix)
+                .await?
+                .into_iter()
+                .map(|meta| meta.location)
+                .collect(),
+        )
     }
 
     async fn remove_snapshot_wal_files(
@@ -311,27 +319,45 @@ impl WalObjectStore {
         snapshot_info: SnapshotInfo,
         snapshot_permit: OwnedSemaphorePermit,
     ) {
+        let retain = !self.snapshotted_wal_retention.is_zero();
         for period in snapshot_info.wal_periods {
             let path = wal_path(&self.host_identifier_prefix, period.wal_file_number);
+            let retained_path =
+                retained_wal_path(&self.host_identifier_prefix, period.wal_file_number);
 
             loop {
-                match self.object_store.delete(&path).await {
+                let result = if retain {
+                    retain_wal_file(self.object_store.as_ref(), &path, &retained_path).await
+                } else {
+                    self.object_store.delete(&path).await
+                };
+                match result {
                     Ok(_) => break,
                     Err(object_store::Error::Generic { store, source }) => {
-                        error!(%store, %source, "error deleting wal file");
+                        error!(%store, %source, "error removing wal file");
                         // hopefully just a temporary error, keep trying until we succeed
                         tokio::time::sleep(Duration::from_secs(1)).await;
                     }
                     Err(e) => {
                         // this must be configuration or file not there error or something else,
                         // log it and move on
-                        error!(%e, "error deleting wal file");
+                        error!(%e, "error removing wal file");
                         break;
                     }
                 }
             }
         }
 
+        if retain {
+            prune_retained_wal_files(
+                self.object_store.as_ref(),
+                &self.host_identifier_prefix,
+                self.time_provider.now(),
+                self.snapshotted_wal_retention,
+            )
+            .await;
+        }
+
         // release the permit so the next snapshot can be run when the time comes
         drop(snapshot_permit);
     }
@@ -382,15 +408,112 @@ impl Wal for WalObjectStore {
             .last_snapshot_sequence_number()
     }
 
+    async fn reserve_snapshot(
+        &self,
+    ) -> crate::Result<(WalFileSequenceNumber, SnapshotSequenceNumber)> {
+        Ok(self.flush_buffer.lock().await.reserve_snapshot())
+    }
+
     async fn shutdown(&self) {
         self.shutdown().await
     }
+
+    fn quarantined_files(&self) -> Vec<QuarantinedWalFile> {
+        self.quarantined_files.lock().clone()
+    }
+}
+
+/// Replays the contents of a single WAL file into the notifier and the snapshot tracker. If the
+/// file triggered a snapshot, this waits for the snapshot to complete and returns the info for
+/// the WAL files that can now be removed, along with the permit to release once they are.
+pub(crate) async fn replay_wal_contents(
+    flush_buffer: &Mutex<FlushBuffer>,
+    file_notifier: &Arc<dyn WalFileNotifier>,
+    wal_contents: WalContents,
+) -> Option<(SnapshotInfo, OwnedSemaphorePermit)> {
+    // add this to the snapshot tracker, so we know what to clear out later if the replay
+    // was a wal file that had a snapshot
+    flush_buffer.lock().await.replay_wal_period(WalPeriod::new(
+        wal_contents.wal_file_number,
+        Timestamp::new(wal_contents.min_timestamp_ns),
+        Timestamp::new(wal_contents.max_timestamp_ns),
+    ));
+
+    match wal_contents.snapshot {
+        None => {
+            file_notifier.notify(wal_contents);
+            None
+        }
+        Some(snapshot_details) => {
+            let snapshot_info = {
+                let mut buffer = flush_buffer.lock().await;
+
+                match buffer.snapshot_tracker.snapshot() {
+                    None => None,
+                    Some(info) => {
+                        let semaphore = Arc::clone(&buffer.snapshot_semaphore);
+                        let permit = semaphore.acquire_owned().await.unwrap();
+
+                        Some((info, permit))
+                    }
+                }
+            };
+
+            let snapshot_done = file_notifier
+                .notify_and_snapshot(wal_contents, snapshot_details)
+                .await;
+            let details = snapshot_done.await.unwrap();
+            assert_eq!(snapshot_details, details);
+
+            snapshot_info
+        }
+    }
+}
+
+/// Sends the contents of a freshly persisted WAL file to the notifier, starting the snapshot if
+/// one was set, and then sends the success responses back to any clients waiting on the write.
+pub(crate) async fn notify_and_respond(
+    file_notifier: &Arc<dyn WalFileNotifier>,
+    wal_contents: WalContents,
+    snapshot: Option<(SnapshotInfo, OwnedSemaphorePermit)>,
+    responses: Vec<oneshot::Sender<WriteResult>>,
+) -> Option<(
+    oneshot::Receiver<SnapshotDetails>,
+    SnapshotInfo,
+    OwnedSemaphorePermit,
+)> {
+    let snapshot_response = match wal_contents.snapshot {
+        Some(snapshot_details) => {
+            info!(?snapshot_details, "snapshotting wal");
+            let snapshot_done = file_notifier
+                .notify_and_snapshot(wal_contents, snapshot_details)
+                .await;
+            let (snapshot_info, snapshot_permit) =
+                snapshot.expect("snapshot should be set when snapshot details are set");
+            Some((snapshot_done, snapshot_info, snapshot_permit))
+        }
+        None => {
+            debug!(
+                "notify sent to buffer for wal file {}",
+                wal_contents.wal_file_number.as_u64()
+            );
+            file_notifier.notify(wal_contents);
+            None
+        }
+    };
+
+    // send all the responses back to clients
+    for response in responses {
+        let _ = response.send(WriteResult::Success(()));
+    }
+
+    snapshot_response
 }
 
 #[derive(Debug)]
-struct FlushBuffer {
-    wal_buffer: WalBuffer,
-    snapshot_tracker: SnapshotTracker,
+pub(crate) struct FlushBuffer {
+    pub(crate) wal_buffer: WalBuffer,
+    pub(crate) snapshot_tracker: SnapshotTracker,
     snapshot_semaphore: Arc<Semaphore>,
 }
 
@@ -403,14 +526,62 @@ impl FlushBuffer {
         }
     }
 
+    pub(crate) fn new_from_config(
+        config: WalConfig,
+        last_wal_sequence_number: Option<WalFileSequenceNumber>,
+        last_snapshot_sequence_number: Option<SnapshotSequenceNumber>,
+    ) -> Self {
+        let wal_file_sequence_number = last_wal_sequence_number.unwrap_or_default().next();
+        Self::new(
+            WalBuffer {
+                is_shutdown: false,
+                wal_file_sequence_number,
+                op_limit: config.max_write_buffer_size,
+                op_count: 0,
+                size_limit_bytes: config.max_write_buffer_bytes,
+                size_bytes: 0,
+                ops: vec![],
+                open_write_batches: Default::default(),
+                write_op_responses: vec![],
+            },
+            SnapshotTracker::new(
+                config.snapshot_size,
+                config.gen1_duration,
+                last_snapshot_sequence_number,
+            ),
+        )
+    }
+
     fn replay_wal_period(&mut self, wal_period: WalPeriod) {
-        self.wal_buffer.wal_file_sequence_number = wal_period.wal_file_number.next();
+        // the last snapshot may have reserved a wal file number after the files being replayed,
+        // which must not be written with
+        if self.wal_buffer.wal_file_sequence_number <= wal_period.wal_file_number {
+            self.wal_buffer.wal_file_sequence_number = wal_period.wal_file_number.next();
+        }
         self.snapshot_tracker.add_wal_period(wal_period);
     }
 
+    /// Reserves the wal file number of the buffer, which is written with the next number instead,
+    /// and the next snapshot sequence number, for a snapshot that is persisted outside of the WAL
+    pub(crate) fn reserve_snapshot(&mut self) -> (WalFileSequenceNumber, SnapshotSequenceNumber) {
+        let wal_file_number = self.wal_buffer.wal_file_sequence_number;
+        self.wal_buffer.wal_file_sequence_number = wal_file_number.next();
+        (
+            wal_file_number,
+            self.snapshot_tracker.reserve_snapshot_sequence_number(),
+        )
+    }
+
+    /// Ensures the next wal file written comes after a file that was skipped in replay
+    pub(crate) fn skip_wal_file(&mut self, wal_file_number: WalFileSequenceNumber) {
+        if self.wal_buffer.wal_file_sequence_number <= wal_file_number {
+            self.wal_buffer.wal_file_sequence_number = wal_file_number.next();
+        }
+    }
+
     /// Converts the wal_buffer into contents and resets it. Returns the channels waiting for
     /// responses. If a snapshot should occur with this flush, a semaphore permit is also returned.
-    async fn flush_buffer_into_contents_and_responses(
+    pub(crate) async fn flush_buffer_into_contents_and_responses(
         &mut self,
     ) -> (
         WalContents,
@@ -452,16 +623,18 @@ impl FlushBuffer {
             wal_file_sequence_number: self.wal_buffer.wal_file_sequence_number.next(),
             op_limit: self.wal_buffer.op_limit,
             op_count: 0,
-            database_to_write_batch: Default::default(),
+            size_limit_bytes: self.wal_buffer.size_limit_bytes,
+            size_bytes: 0,
+            ops: vec![],
+            open_write_batches: Default::default(),
             write_op_responses: vec![],
-            catalog_batches: vec![],
         };
         std::mem::swap(&mut self.wal_buffer, &mut new_buffer);
 
         new_buffer.into_wal_contents_and_responses()
     }
 
-    async fn flush_buffer_with_failure(&mut self, error: WriteResult) {
+    pub(crate) async fn flush_buffer_with_failure(&mut self, error: WriteResult) {
         let (_, responses) = self.flush_buffer_with_responses();
         for response in responses {
             let _ = response.send(error.clone());
@@ -470,19 +643,110 @@ impl FlushBuffer {
 }
 
 #[derive(Debug, Default)]
-struct WalBuffer {
-    is_shutdown: bool,
+pub(crate) struct WalBuffer {
+    pub(crate) is_shutdown: bool,
     wal_file_sequence_number: WalFileSequenceNumber,
     op_limit: usize,
     op_count: usize,
-    database_to_write_batch: HashMap<Arc<str>, WriteBatch>,
-    catalog_batches: Vec<CatalogBatch>,
+    size_limit_bytes: usize,
+    size_bytes: usize,
+    /// The buffered ops, in the order they arrived
+    ops: Vec<WalOp>,
+    /// The index in `ops` of the write batch of each database that later writes to it are added
+    /// to. This is cleared whenever a catalog or delete op is buffered, so that no write is moved
+    /// ahead of an op that arrived before it.
+    open_write_batches: HashMap<Arc<str>, usize>,
     write_op_responses: Vec<oneshot::Sender<WriteResult>>,
 }
 
 impl WalBuffer {
-    fn is_empty(&self) -> bool {
-        self.database_to_write_batch.is_empty() && self.catalog_batches.is_empty()
+    pub(crate) fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Returns true if the buffer holds as many ops, or as many bytes, as it is allowed to before
+    /// it must be flushed
+    pub(crate) fn is_full(&self) -> bool {
+        self.op_count >= self.op_limit || self.size_bytes >= self.size_limit_bytes
+    }
+}
+
+/// Applies backpressure to writes when the [`WalBuffer`] is full. Rather than rejecting writes
+/// right away, a write waits for the buffer to be flushed, up to a timeout. The background flush
+/// is also woken up early once the buffer fills, instead of waiting for its next interval.
+#[derive(Debug)]
+pub(crate) struct BufferBackpressure {
+    timeout: Duration,
+    /// Notified to wake up the background flush
+    flush_requested: Arc<Notify>,
+    /// Notified every time the buffer is flushed
+    buffer_flushed: Notify,
+}
+
+impl BufferBackpressure {
+    pub(crate) fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            flush_requested: Default::default(),
+            buffer_flushed: Notify::new(),
+        }
+    }
+
+    /// The notify that the background flush should listen on to flush early
+    pub(crate) fn flush_requested(&self) -> Arc<Notify> {
+        Arc::clone(&self.flush_requested)
+    }
+
+    /// Locks the flush buffer once it has room for more ops. If it is full, this waits for it to
+    /// be flushed and returns [`crate::Error::BufferFull`] if that doesn't happen in time.
+    pub(crate) async fn lock_with_capacity<'a>(
+        &self,
+        flush_buffer: &'a Mutex<FlushBuffer>,
+        metrics: &WalMetrics,
+    ) -> crate::Result<MutexGuard<'a, FlushBuffer>> {
+        let start = tokio::time::Instant::now();
+        let deadline = start + self.timeout;
+        let mut waited = false;
+
+        loop {
+            // register for the flush before checking the buffer, so a flush that happens after
+            // the check isn't missed
+            let flushed = self.buffer_flushed.notified();
+            tokio::pin!(flushed);
+            flushed.as_mut().enable();
+
+            let guard = flush_buffer.lock().await;
+            if !guard.wal_buffer.is_full() {
+                if waited {
+                    metrics.record_buffer_full_wait(start.elapsed(), false);
+                }
+                return Ok(guard);
+            }
+            let op_count = guard.wal_buffer.op_count;
+            drop(guard);
+
+            waited = true;
+            self.flush_requested.notify_one();
+            if tokio::time::timeout_at(deadline, flushed).await.is_err() {
+                warn!(op_count, timeout = ?self.timeout, "wal buffer full, rejecting write");
+                metrics.record_buffer_full_wait(start.elapsed(), true);
+                return Err(crate::Error::BufferFull(op_count));
+            }
+        }
+    }
+
+    /// Called after ops are added to the buffer, to request a flush if it is now full
+    pub(crate) fn buffered(&self, wal_buffer: &WalBuffer, metrics: &WalMetrics) {
+        metrics.record_buffer_depth(wal_buffer.op_count, wal_buffer.size_bytes);
+        if wal_buffer.is_full() {
+            self.flush_requested.notify_one();
+        }
+    }
+
+    /// Called after the buffer is swapped out for an empty one, to wake up waiting writes
+    pub(crate) fn flushed(&self, metrics: &WalMetrics) {
+        metrics.record_buffer_depth(0, 0);
+        self.buffer_flushed.notify_waiters();
     }
 }
 
@@ -496,51 +760,48 @@ pub enum WriteResult {
 }
 
 impl WalBuffer {
-    fn buffer_op_unconfirmed(&mut self, op: WalOp) -> crate::Result<(), crate::Error> {
-        if self.op_count >= self.op_limit {
-            return Err(crate::Error::BufferFull(self.op_count));
-        }
+    /// Adds the op to the buffer. Callers check that the buffer is not full first, an op is
+    /// buffered regardless.
+    pub(crate) fn buffer_op_unconfirmed(&mut self, op: WalOp) {
+        self.op_count += 1;
+        self.size_bytes += op.estimated_size_bytes();
 
         match op {
             WalOp::Write(new_write_batch) => {
                 let db_name = Arc::clone(&new_write_batch.database_name);
 
-                // insert the database write batch or add to existing
-                let write_batch =
-                    self.database_to_write_batch
-                        .entry(db_name)
-                        .or_insert_with(|| WriteBatch {
-                            database_id: new_write_batch.database_id,
-                            database_name: new_write_batch.database_name,
-                            table_chunks: Default::default(),
-                            min_time_ns: i64::MAX,
-                            max_time_ns: i64::MIN,
-                        });
-                write_batch.add_write_batch(
-                    new_write_batch.table_chunks,
-                    new_write_batch.min_time_ns,
-                    new_write_batch.max_time_ns,
-                );
+                // add to the open write batch of the database, or start a new one after the
+                // ops that are already buffered
+                if let Some(&index) = self.open_write_batches.get(&db_name) {
+                    if let WalOp::Write(write_batch) = &mut self.ops[index] {
+                        write_batch.add_write_batch(
+                            new_write_batch.table_chunks,
+                            new_write_batch.min_time_ns,
+                            new_write_batch.max_time_ns,
+                        );
+                        return;
+                    }
+                }
+                self.open_write_batches.insert(db_name, self.ops.len());
+                self.ops.push(WalOp::Write(new_write_batch));
             }
-            WalOp::Catalog(catalog_batch) => {
-                self.catalog_batches.push(catalog_batch);
+            op @ (WalOp::Catalog(_) | WalOp::Delete(_)) => {
+                // writes that arrive after this op must not be added to a batch ahead of it
+                self.open_write_batches.clear();
+                self.ops.push(op);
             }
         }
-
-        Ok(())
     }
 
-    fn buffer_ops_with_response(
+    pub(crate) fn buffer_ops_with_response(
         &mut self,
         ops: Vec<WalOp>,
         response: oneshot::Sender<WriteResult>,
-    ) -> crate::Result<(), crate::Error> {
+    ) {
         self.write_op_responses.push(response);
         for op in ops {
-            self.buffer_op_unconfirmed(op)?;
+            self.buffer_op_unconfirmed(op);
         }
-
-        Ok(())
     }
 
     fn into_wal_contents_and_responses(self) -> (WalContents, Vec<oneshot::Sender<WriteResult>>) {
@@ -548,26 +809,14 @@ impl WalBuffer {
         let mut min_timestamp_ns = i64::MAX;
         let mut max_timestamp_ns = i64::MIN;
 
-        for write_batch in self.database_to_write_batch.values() {
-            min_timestamp_ns = min_timestamp_ns.min(write_batch.min_time_ns);
-            max_timestamp_ns = max_timestamp_ns.max(write_batch.max_time_ns);
-        }
-
-        for catalog_batch in &self.catalog_batches {
-            min_timestamp_ns = min_timestamp_ns.min(catalog_batch.time_ns);
-            max_timestamp_ns = max_timestamp_ns.max(catalog_batch.time_ns);
-        }
-
-        // have the catalog ops come before any writes in ordering
-        let mut ops =
-            Vec::with_capacity(self.database_to_write_batch.len() + self.catalog_batches.len());
-
-        for catalog_batch in self.catalog_batches {
-            ops.push(WalOp::Catalog(catalog_batch));
-        }
-
-        for write_batch in self.database_to_write_batch.into_values() {
-            ops.push(WalOp::Write(write_batch));
+        for op in &self.ops {
+            let (min_time_ns, max_time_ns) = match op {
+                WalOp::Write(write_batch) => (write_batch.min_time_ns, write_batch.max_time_ns),
+                WalOp::Catalog(catalog_batch) => (catalog_batch.time_ns, catalog_batch.time_ns),
+                WalOp::Delete(delete_batch) => (delete_batch.time_ns, delete_batch.time_ns),
+            };
+            min_timestamp_ns = min_timestamp_ns.min(min_time_ns);
+            max_timestamp_ns = max_timestamp_ns.max(max_time_ns);
         }
 
         (
@@ -575,7 +824,8 @@ impl WalBuffer {
                 min_timestamp_ns,
                 max_timestamp_ns,
                 wal_file_number: self.wal_file_sequence_number,
-                ops,
+                persisted_time_ns: 0,
+                ops: self.ops,
                 snapshot: None,
             },
             self.write_op_responses,
@@ -583,6 +833,130 @@ impl WalBuffer {
     }
 }
 
+/// Lists the WAL files written to object store for the given host, in order of their sequence
+/// number.
+pub async fn list_wal_files(
+    object_store: &dyn ObjectStore,
+    host_identifier_prefix: &str,
+) -> crate::Result<Vec<ObjectMeta>> {
+    list_files_in_order(
+        object_store,
+        Path::from(format!("{host_identifier_prefix}/wal")),
+    )
+    .await
+}
+
+/// Lists the WAL files that were kept after a snapshot covered them, in order of their sequence
+/// number.
+pub async fn list_retained_wal_files(
+    object_store: &dyn ObjectStore,
+    host_identifier_prefix: &str,
+) -> crate::Result<Vec<ObjectMeta>> {
+    list_files_in_order(
+        object_store,
+        Path::from(format!("{host_identifier_prefix}/wal_retained")),
+    )
+    .await
+}
+
+/// Lists the WAL files of the host that are still around, both those that a snapshot has not
+/// covered yet and those that were retained after one did, in order of their sequence number.
+pub async fn list_wal_files_with_retained(
+    object_store: &dyn ObjectStore,
+    host_identifier_prefix: &str,
+) -> crate::Result<Vec<(WalFileSequenceNumber, Path)>> {
+    let mut files = vec![];
+    for meta in list_retained_wal_files(object_store, host_identifier_prefix)
+        .await?
+        .into_iter()
+        .chain(list_wal_files(object_store, host_identifier_prefix).await?)
+    {
+        files.push((
+            WalFileSequenceNumber::try_from(&meta.location)?,
+            meta.location,
+        ));
+    }
+    // a file that was being moved to the retained prefix can be listed in both
+    files.sort_by_key(|(n, _)| *n);
+    files.dedup_by_key(|(n, _)| *n);
+
+    Ok(files)
+}
+
+/// Moves a WAL file that a snapshot covered to the retained prefix. It is written there anew,
+/// rather than renamed, so that its last modified time is when it was retained on every object
+/// store, as a rename keeps the time of the original file on some of them.
+async fn retain_wal_file(
+    object_store: &dyn ObjectStore,
+    path: &Path,
+    retained_path: &Path,
+) -> object_store::Result<()> {
+    let bytes = object_store.get(path).await?.bytes().await?;
+    object_store.put(retained_path, bytes.into()).await?;
+    object_store.delete(path).await
+}
+
+/// Deletes the retained WAL files that were written to the retained prefix longer ago than the
+/// retention period, going by their last modified time, which is when they were retained. Errors
+/// are logged and the files are left for the next snapshot to prune.
+pub(crate) async fn prune_retained_wal_files(
+    object_store: &dyn ObjectStore,
+    host_identifier_prefix: &str,
+    now: Time,
+    retention: Duration,
+) {
+    let Some(cutoff) = now.checked_sub(retention) else {
+        return;
+    };
+    let files = match list_retained_wal_files(object_store, host_identifier_prefix).await {
+        Ok(files) => files,
+        Err(e) => {
+            error!(%e, "error listing retained wal files");
+            return;
+        }
+    };
+    for meta in files {
+        if meta.last_modified >= cutoff.date_time() {
+            continue;
+        }
+        debug!(path = %meta.location, "deleting retained wal file past its retention");
+        match object_store.delete(&meta.location).await {
+            Ok(_) | Err(object_store::Error::NotFound { .. }) => (),
+            Err(e) => error!(%e, path = %meta.location, "error deleting retained wal file"),
+        }
+    }
+}
+
+async fn list_files_in_order(
+    object_store: &dyn ObjectStore,
+    path: Path,
+) -> crate::Result<Vec<ObjectMeta>> {
+    let mut files: Vec<ObjectMeta> = Vec::new();
+    let mut offset: Option<Path> = None;
+    loop {
+        let mut listing = if let Some(offset) = offset {
+            object_store.list_with_offset(Some(&path), &offset)
+        } else {
+            object_store.list(Some(&path))
+        };
+        let file_count = files.len();
+
+        while let Some(item) = listing.next().await {
+            files.push(item?);
+        }
+
+        if file_count == files.len() {
+            break;
+        }
+
+        files.sort_by(|a, b| a.location.cmp(&b.location));
+        offset = Some(files.last().unwrap().location.clone())
+    }
+    files.sort_by(|a, b| a.location.cmp(&b.location));
+
+    Ok(files)
+}
+
 pub fn wal_path(host_identifier_prefix: &str, wal_file_number: WalFileSequenceNumber) -> Path {
     Path::from(format!(
         "{host_identifier_prefix}/wal/{:011}.wal",
@@ -590,6 +964,37 @@ pub fn wal_path(host_identifier_prefix: &str, wal_file_number: WalFileSequenceNu
     ))
 }
 
+/// The path that a wal file is moved to after a snapshot covers it, if snapshotted wal files are
+/// retained
+pub fn retained_wal_path(
+    host_identifier_prefix: &str,
+    wal_file_number: WalFileSequenceNumber,
+) -> Path {
+    Path::from(format!(
+        "{host_identifier_prefix}/wal_retained/{:011}.wal",
+        wal_file_number.0
+    ))
+}
+
+/// The path that a wal file is moved to if it can't be deserialized during replay
+pub fn quarantine_path(
+    host_identifier_prefix: &str,
+    wal_file_number: WalFileSequenceNumber,
+) -> Path {
+    Path::from(format!(
+        "{host_identifier_prefix}/quarantine/{:011}.wal",
+        wal_file_number.0
+    ))
+}
+
+/// Parses the sequence number from the file name of a wal file
+pub(crate) fn wal_file_number_from_path(path: &Path) -> crate::Result<WalFileSequenceNumber> {
+    path.filename()
+        .and_then(|name| name.strip_suffix(".wal"))
+        .and_then(|n| n.parse().ok())
+        .ok_or(crate::Error::InvalidWalFilePath)
+}
+
 impl<'a> TryFrom<&'a Path> for WalFileSequenceNumber {
     type Error = crate::Error;
 
@@ -610,10 +1015,13 @@ impl<'a> TryFrom<&'a Path> for WalFileSequenceNumber {
 mod tests {
     use super::*;
     use crate::{
-        Field, FieldData, Gen1Duration, Row, SnapshotSequenceNumber, TableChunk, TableChunks,
+        DeleteBatch, DeletePredicate, Field, FieldData, Gen1Duration, Row, SnapshotSequenceNumber,
+        TableChunk, TableChunks, WalCompression,
     };
     use async_trait::async_trait;
     use influxdb3_id::{DbId, TableId};
+    use iox_time::MockProvider;
+    use metric::{Attributes, Metric, U64Counter};
     use object_store::memory::InMemory;
     use std::any::Any;
     use tokio::sync::oneshot::Receiver;
@@ -624,15 +1032,22 @@ mod tests {
         let notifier: Arc<dyn WalFileNotifier> = Arc::new(TestNotfiier::default());
         let wal_config = WalConfig {
             max_write_buffer_size: 100,
+            max_write_buffer_bytes: 1024 * 1024,
+            buffer_full_timeout: Duration::from_secs(1),
             flush_interval: Duration::from_secs(1),
             snapshot_size: 2,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
             gen1_duration: Gen1Duration::new_1m(),
         };
         let wal = WalObjectStore::new_without_replay(
             Arc::clone(&object_store),
             "my_host",
             Arc::clone(&notifier),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
             wal_config,
+            &metric::Registry::default(),
             None,
             None,
         );
@@ -727,6 +1142,7 @@ mod tests {
             min_timestamp_ns: 1,
             max_timestamp_ns: 62_000000000,
             wal_file_number: WalFileSequenceNumber(1),
+            persisted_time_ns: 0,
             ops: vec![WalOp::Write(WriteBatch {
                 database_id: DbId::from(0),
                 database_name: "db1".into(),
@@ -797,6 +1213,7 @@ mod tests {
             min_timestamp_ns: 62000000000,
             max_timestamp_ns: 62000000000,
             wal_file_number: WalFileSequenceNumber(2),
+            persisted_time_ns: 0,
             ops: vec![WalOp::Write(WriteBatch {
                 database_id: DbId::from(0),
                 database_name: "db1".into(),
@@ -837,12 +1254,19 @@ mod tests {
             Arc::clone(&object_store),
             "my_host",
             Arc::clone(&replay_notifier),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 10,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
                 flush_interval: Duration::from_millis(10),
                 snapshot_size: 2,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
             },
+            &metric::Registry::default(),
             None,
             None,
         );
@@ -929,6 +1353,7 @@ mod tests {
             min_timestamp_ns: 128_000000000,
             max_timestamp_ns: 128_000000000,
             wal_file_number: WalFileSequenceNumber(3),
+            persisted_time_ns: 0,
             ops: vec![WalOp::Write(WriteBatch {
                 database_id: DbId::from(0),
                 database_name: "db1".into(),
@@ -986,7 +1411,9 @@ mod tests {
             object_store,
             "my_host",
             Arc::clone(&replay_notifier),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
             wal_config,
+            &metric::Registry::default(),
             None,
             None,
         );
@@ -1011,15 +1438,22 @@ mod tests {
         let notifier: Arc<dyn WalFileNotifier> = Arc::new(TestNotfiier::default());
         let wal_config = WalConfig {
             max_write_buffer_size: 100,
+            max_write_buffer_bytes: 1024 * 1024,
+            buffer_full_timeout: Duration::from_secs(1),
             flush_interval: Duration::from_secs(1),
             snapshot_size: 2,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
             gen1_duration: Gen1Duration::new_1m(),
         };
         let wal = WalObjectStore::new_without_replay(
             Arc::clone(&object_store),
             "my_host",
             Arc::clone(&notifier),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
             wal_config,
+            &metric::Registry::default(),
             None,
             None,
         );
@@ -1032,6 +1466,501 @@ mod tests {
         assert!(object_store.list(None).next().await.is_none());
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn full_buffer_waits_for_flush_before_rejecting() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let notifier: Arc<dyn WalFileNotifier> = Arc::new(TestNotfiier::default());
+        let registry = metric::Registry::default();
+        let wal_config = WalConfig {
+            max_write_buffer_size: 2,
+            max_write_buffer_bytes: 1024 * 1024,
+            buffer_full_timeout: Duration::from_millis(100),
+            flush_interval: Duration::from_secs(1),
+            snapshot_size: 100,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
+            gen1_duration: Gen1Duration::new_1m(),
+        };
+        let wal = Arc::new(WalObjectStore::new_without_replay(
+            Arc::clone(&object_store),
+            "my_host",
+            Arc::clone(&notifier),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+            wal_config,
+            &registry,
+            None,
+            None,
+        ));
+
+        wal.buffer_op_unconfirmed(write_op(1)).await.unwrap();
+        wal.buffer_op_unconfirmed(write_op(2)).await.unwrap();
+
+        // nothing flushes the full buffer, so the write is rejected after the timeout
+        let err = wal.buffer_op_unconfirmed(write_op(3)).await.unwrap_err();
+        assert!(matches!(err, crate::Error::BufferFull(2)));
+        let rejections = registry
+            .get_instrument::<Metric<U64Counter>>("influxdb3_wal_buffer_full_rejections")
+            .unwrap()
+            .get_observer(&Attributes::from(&[]))
+            .unwrap()
+            .fetch();
+        assert_eq!(rejections, 1);
+
+        // a write waiting on the full buffer is accepted once it is flushed
+        let waiting_wal = Arc::clone(&wal);
+        let waiting =
+            tokio::spawn(async move { waiting_wal.buffer_op_unconfirmed(write_op(4)).await });
+        wal.backpressure.flush_requested().notified().await;
+        assert!(wal.flush_buffer().await.is_none());
+        waiting.await.unwrap().unwrap();
+
+        let notifier = notifier.as_any().downcast_ref::<TestNotfiier>().unwrap();
+        assert_eq!(notifier.notified_writes.lock().len(), 1);
+        assert!(!wal.flush_buffer.lock().await.wal_buffer.is_full());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn buffer_is_full_by_size() {
+        let notifier: Arc<dyn WalFileNotifier> = Arc::new(TestNotfiier::default());
+        let wal_config = WalConfig {
+            max_write_buffer_size: 100,
+            max_write_buffer_bytes: write_op(1).estimated_size_bytes() * 2,
+            buffer_full_timeout: Duration::from_millis(10),
+            flush_interval: Duration::from_secs(1),
+            snapshot_size: 100,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
+            gen1_duration: Gen1Duration::new_1m(),
+        };
+        let wal = WalObjectStore::new_without_replay(
+            Arc::new(InMemory::new()),
+            "my_host",
+            notifier,
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+            wal_config,
+            &metric::Registry::default(),
+            None,
+            None,
+        );
+
+        wal.buffer_op_unconfirmed(write_op(1)).await.unwrap();
+        assert!(!wal.flush_buffer.lock().await.wal_buffer.is_full());
+        wal.buffer_op_unconfirmed(write_op(2)).await.unwrap();
+        assert!(wal.flush_buffer.lock().await.wal_buffer.is_full());
+        assert!(matches!(
+            wal.buffer_op_unconfirmed(write_op(3)).await.unwrap_err(),
+            crate::Error::BufferFull(2)
+        ));
+    }
+
+    #[tokio::test]
+    async fn snapshotted_wal_files_are_retained_then_pruned() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let notifier: Arc<dyn WalFileNotifier> = Arc::new(TestNotfiier::default());
+        let wal_config = WalConfig {
+            max_write_buffer_size: 100,
+            max_write_buffer_bytes: 1024 * 1024,
+            buffer_full_timeout: Duration::from_secs(1),
+            flush_interval: Duration::from_secs(1),
+            snapshot_size: 2,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::from_secs(3600),
+            gen1_duration: Gen1Duration::new_1m(),
+        };
+        let wal = WalObjectStore::new_without_replay(
+            Arc::clone(&object_store),
+            "my_host",
+            notifier,
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(1_000))),
+            wal_config,
+            &metric::Registry::default(),
+            None,
+            None,
+        );
+
+        wal.buffer_op_unconfirmed(write_op(1)).await.unwrap();
+        assert!(wal.flush_buffer().await.is_none());
+        wal.buffer_op_unconfirmed(write_op(62_000000000))
+            .await
+            .unwrap();
+        assert!(wal.flush_buffer().await.is_none());
+        wal.buffer_op_unconfirmed(write_op(128_000000000))
+            .await
+            .unwrap();
+        let (snapshot_done, snapshot_info, snapshot_permit) = wal.flush_buffer().await.unwrap();
+        snapshot_done.await.unwrap();
+        let retained_after = iox_time::SystemProvider::new().now();
+        wal.remove_snapshot_wal_files(snapshot_info, snapshot_permit)
+            .await;
+
+        // the snapshotted files are moved to the retained prefix instead of being deleted
+        let locations = |files: Vec<ObjectMeta>| {
+            files
+                .into_iter()
+                .map(|meta| meta.location)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            locations(
+                list_wal_files(object_store.as_ref(), "my_host")
+                    .await
+                    .unwrap()
+            ),
+            vec![wal_path("my_host", WalFileSequenceNumber(3))]
+        );
+        let retained = list_retained_wal_files(object_store.as_ref(), "my_host")
+            .await
+            .unwrap();
+        assert_eq!(
+            locations(retained.clone()),
+            vec![
+                retained_wal_path("my_host", WalFileSequenceNumber(1)),
+                retained_wal_path("my_host", WalFileSequenceNumber(2)),
+            ]
+        );
+
+        // with the time they were retained as their last modified time
+        assert!(retained
+            .iter()
+            .all(|meta| Time::from_date_time(meta.last_modified) >= retained_after));
+
+        // and record the time they were flushed
+        let bytes = object_store
+            .get(&retained[0].location)
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let contents = verify_file_type_and_deserialize(bytes).unwrap();
+        assert_eq!(contents.persisted_time_ns, 1_000);
+
+        // retained files are kept until they are older than the retention period
+        let moved_at = Time::from_date_time(retained[1].last_modified);
+        prune_retained_wal_files(
+            object_store.as_ref(),
+            "my_host",
+            moved_at.checked_add(Duration::from_secs(60)).unwrap(),
+            Duration::from_secs(3600),
+        )
+        .await;
+        assert_eq!(
+            list_retained_wal_files(object_store.as_ref(), "my_host")
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        prune_retained_wal_files(
+            object_store.as_ref(),
+            "my_host",
+            moved_at.checked_add(Duration::from_secs(7200)).unwrap(),
+            Duration::from_secs(3600),
+        )
+        .await;
+        assert!(list_retained_wal_files(object_store.as_ref(), "my_host")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    fn write_op(time: i64) -> WalOp {
+        WalOp::Write(WriteBatch {
+            database_id: DbId::from(0),
+            database_name: "db1".into(),
+            table_chunks: HashMap::from([(
+                TableId::from(0),
+                TableChunks {
+                    min_time: time,
+                    max_time: time,
+                    chunk_time_to_chunk: HashMap::from([(
+                        0,
+                        TableChunk {
+                            rows: vec![Row {
+                                time,
+                                fields: vec![
+                                    Field {
+                                        name: "f1".into(),
+                                        value: FieldData::Integer(1),
+                                    },
+                                    Field {
+                                        name: "time".into(),
+                                        value: FieldData::Timestamp(time),
+                                    },
+                                ],
+                            }],
+                        },
+                    )]),
+                },
+            )]),
+            min_time_ns: time,
+            max_time_ns: time,
+        })
+    }
+
+    #[test]
+    fn writes_after_a_delete_are_not_moved_ahead_of_it() {
+        let mut wal_buffer = WalBuffer {
+            op_limit: 100,
+            size_limit_bytes: 1024 * 1024,
+            ..Default::default()
+        };
+        let delete = WalOp::Delete(DeleteBatch {
+            database_id: DbId::from(0),
+            database_name: "db1".into(),
+            table_id: TableId::from(0),
+            table_name: "table".into(),
+            time_ns: 2,
+            predicate: DeletePredicate {
+                min_time_ns: i64::MIN,
+                max_time_ns: i64::MAX,
+                tags: vec![],
+            },
+        });
+
+        // a delete and a write arriving after it inside the same flush interval
+        wal_buffer.buffer_op_unconfirmed(write_op(1));
+        wal_buffer.buffer_op_unconfirmed(write_op(2));
+        wal_buffer.buffer_op_unconfirmed(delete.clone());
+        wal_buffer.buffer_op_unconfirmed(write_op(5));
+        let (contents, _) = wal_buffer.into_wal_contents_and_responses();
+
+        // writes before the delete are still put together, but the later one stays after it
+        let mut rows_before_delete = write_op(1);
+        if let (WalOp::Write(batch), WalOp::Write(later)) = (&mut rows_before_delete, write_op(2)) {
+            batch.add_write_batch(later.table_chunks, later.min_time_ns, later.max_time_ns);
+        }
+        assert_eq!(contents.ops, vec![rows_before_delete, delete, write_op(5)]);
+
+        // so applying the ops in order deletes the earlier rows and keeps the later one
+        let mut rows = vec![];
+        for op in &contents.ops {
+            match op {
+                WalOp::Write(batch) => rows.extend(
+                    batch.table_chunks[&TableId::from(0)]
+                        .chunk_time_to_chunk
+                        .values()
+                        .flat_map(|chunk| chunk.rows.iter().map(|row| row.time)),
+                ),
+                WalOp::Delete(delete) => rows.retain(|time| {
+                    *time < delete.predicate.min_time_ns || *time > delete.predicate.max_time_ns
+                }),
+                WalOp::Catalog(_) => {}
+            }
+        }
+        assert_eq!(rows, vec![5]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn replay_quarantines_corrupt_files() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        for n in 1..=3 {
+            let contents = WalContents {
+                min_timestamp_ns: n,
+                max_timestamp_ns: n,
+                wal_file_number: WalFileSequenceNumber::new(n as u64),
+                persisted_time_ns: 0,
+                ops: vec![],
+                snapshot: None,
+            };
+            let bytes = if n == 2 {
+                b"idb3.003garbage".to_vec()
+            } else {
+                crate::serialize::serialize_to_file_bytes(&contents, WalCompression::None)
+                    .unwrap()
+                    .bytes
+            };
+            object_store
+                .put(
+                    &wal_path("my_host", WalFileSequenceNumber::new(n as u64)),
+                    bytes.into(),
+                )
+                .await
+                .unwrap();
+        }
+        let wal_config = WalConfig {
+            max_write_buffer_size: 100,
+            max_write_buffer_bytes: 1024 * 1024,
+            buffer_full_timeout: Duration::from_secs(1),
+            flush_interval: Duration::from_secs(1),
+            snapshot_size: 2,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
+            gen1_duration: Gen1Duration::new_1m(),
+        };
+
+        // without quarantine enabled, the replay fails on the corrupt file
+        let notifier: Arc<dyn WalFileNotifier> = Arc::new(TestNotfiier::default());
+        let wal = WalObjectStore::new_without_replay(
+            Arc::clone(&object_store),
+            "my_host",
+            Arc::clone(&notifier),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+            wal_config,
+            &metric::Registry::default(),
+            None,
+            None,
+        );
+        assert!(wal.replay().await.is_err());
+
+        let notifier: Arc<dyn WalFileNotifier> = Arc::new(TestNotfiier::default());
+        let wal = WalObjectStore::new_without_replay(
+            Arc::clone(&object_store),
+            "my_host",
+            Arc::clone(&notifier),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+            WalConfig {
+                quarantine_corrupt_files: true,
+                snapshotted_wal_retention: Duration::ZERO,
+                ..wal_config
+            },
+            &metric::Registry::default(),
+            None,
+            None,
+        );
+        wal.replay().await.unwrap();
+
+        let replayed: Vec<WalFileSequenceNumber> = notifier
+            .as_any()
+            .downcast_ref::<TestNotfiier>()
+            .unwrap()
+            .notified_writes
+            .lock()
+            .iter()
+            .map(|c| c.wal_file_number)
+            .collect();
+        assert_eq!(
+            replayed,
+            vec![WalFileSequenceNumber::new(1), WalFileSequenceNumber::new(3)]
+        );
+
+        let quarantined = wal.quarantined_files();
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(
+            quarantined[0].wal_file_number,
+            WalFileSequenceNumber::new(2)
+        );
+        assert_eq!(
+            quarantined[0].quarantine_path,
+            "my_host/quarantine/00000000002.wal"
+        );
+
+        // the corrupt file was moved out of the wal prefix
+        assert_eq!(
+            wal.load_existing_wal_file_paths().await.unwrap(),
+            vec![
+                wal_path("my_host", WalFileSequenceNumber::new(1)),
+                wal_path("my_host", WalFileSequenceNumber::new(3)),
+            ]
+        );
+        object_store
+            .head(&quarantine_path("my_host", WalFileSequenceNumber::new(2)))
+            .await
+            .unwrap();
+        // the sequence number of the quarantined file is never written to again
+        assert!(
+            wal.flush_buffer
+                .lock()
+                .await
+                .wal_buffer
+                .wal_file_sequence_number
+                > WalFileSequenceNumber::new(2)
+        );
+    }
+
+    #[tokio::test]
+    async fn reserved_snapshot_numbers_are_not_reused() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        // a wal file that was written before the snapshot that reserved the next number
+        let contents = WalContents {
+            min_timestamp_ns: 1,
+            max_timestamp_ns: 1,
+            wal_file_number: WalFileSequenceNumber::new(3),
+            persisted_time_ns: 0,
+            ops: vec![],
+            snapshot: None,
+        };
+        object_store
+            .put(
+                &wal_path("my_host", WalFileSequenceNumber::new(3)),
+                crate::serialize::serialize_to_file_bytes(&contents, WalCompression::None)
+                    .unwrap()
+                    .bytes
+                    .into(),
+            )
+            .await
+            .unwrap();
+        let wal_config = WalConfig {
+            max_write_buffer_size: 100,
+            max_write_buffer_bytes: 1024 * 1024,
+            buffer_full_timeout: Duration::from_secs(1),
+            flush_interval: Duration::from_secs(1),
+            snapshot_size: 2,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
+            gen1_duration: Gen1Duration::new_1m(),
+        };
+
+        let notifier: Arc<dyn WalFileNotifier> = Arc::new(TestNotfiier::default());
+        let wal = WalObjectStore::new_without_replay(
+            Arc::clone(&object_store),
+            "my_host",
+            Arc::clone(&notifier),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+            wal_config,
+            &metric::Registry::default(),
+            Some(WalFileSequenceNumber::new(3)),
+            Some(SnapshotSequenceNumber::new(1)),
+        );
+        assert_eq!(
+            wal.reserve_snapshot().await.unwrap(),
+            (
+                WalFileSequenceNumber::new(4),
+                SnapshotSequenceNumber::new(2)
+            )
+        );
+        assert_eq!(
+            wal.flush_buffer
+                .lock()
+                .await
+                .wal_buffer
+                .wal_file_sequence_number,
+            WalFileSequenceNumber::new(5)
+        );
+
+        // on restart from the reserved snapshot, replaying the earlier file does not reuse the
+        // reserved number
+        let notifier: Arc<dyn WalFileNotifier> = Arc::new(TestNotfiier::default());
+        let wal = WalObjectStore::new_without_replay(
+            Arc::clone(&object_store),
+            "my_host",
+            Arc::clone(&notifier),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+            wal_config,
+            &metric::Registry::default(),
+            Some(WalFileSequenceNumber::new(4)),
+            Some(SnapshotSequenceNumber::new(2)),
+        );
+        wal.replay().await.unwrap();
+        assert_eq!(
+            wal.flush_buffer
+                .lock()
+                .await
+                .wal_buffer
+                .wal_file_sequence_number,
+            WalFileSequenceNumber::new(5)
+        );
+        assert_eq!(
+            wal.last_snapshot_sequence_number().await,
+            SnapshotSequenceNumber::new(2)
+        );
+    }
+
     #[derive(Debug, Default)]
     struct TestNotfiier {
         notified_writes: parking_lot::Mutex<Vec<WalContents>>,
@@ -1,8 +1,21 @@
 //! Module for serializing and deserializing the contents of a single WAL file. Since the WAL is
 //! buffered in memory before writing it in a single PUT operation to object store, this works
 //! a little differently than a traditional WAL that appends.
+//!
+//! A WAL file is laid out as a file type identifier, followed by a big endian crc32 checksum of
+//! the payload, followed by the payload. The identifier determines how the payload is encoded:
+//!
+//! * `idb3.001`: the payload is [`WalContents`] serialized as JSON. This is no longer written,
+//!   but is still read so that WAL files from older versions can be replayed.
+//! * `idb3.002`: the payload is [`WalContents`] serialized as MessagePack, with structs encoded
+//!   as arrays. Since fields are identified by position, any field added to a type that is
+//!   written to the WAL must be appended to the end of the struct and be marked
+//!   `#[serde(default)]` so that older files can still be read.
+//! * `idb3.003`: the payload is a single byte identifying the [`WalCompression`] used, followed
+//!   by the MessagePack encoded [`WalContents`] compressed with it. The checksum covers the
+//!   compression byte as well as the compressed bytes.
 
-use crate::WalContents;
+use crate::{WalCompression, WalContents};
 use byteorder::{BigEndian, ReadBytesExt};
 use bytes::Bytes;
 use std::io::Cursor;
@@ -17,9 +30,21 @@ pub enum Error {
     #[error("crc32 checksum mismatch")]
     Crc32Mismatch,
 
+    #[error("unknown compression type in wal file: {0}")]
+    UnknownCompression(u8),
+
     #[error("Serde error: {0}")]
     Serde(#[from] serde_json::Error),
 
+    #[error("MessagePack encode error: {0}")]
+    MessagePackEncode(#[from] rmp_serde::encode::Error),
+
+    #[error("MessagePack decode error: {0}")]
+    MessagePackDecode(#[from] rmp_serde::decode::Error),
+
+    #[error("snappy error: {0}")]
+    Snappy(#[from] snap::Error),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -30,25 +55,75 @@ pub enum Error {
 pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
 
 /// The first bytes written into a wal file to identify it and its version.
-const FILE_TYPE_IDENTIFIER: &[u8] = b"idb3.001";
+const FILE_TYPE_IDENTIFIER: &[u8] = b"idb3.003";
+
+/// The identifier of version 2 wal files, which have an uncompressed MessagePack payload.
+const FILE_TYPE_IDENTIFIER_V2: &[u8] = b"idb3.002";
+
+/// The identifier of version 1 wal files, which have a JSON payload.
+const FILE_TYPE_IDENTIFIER_V1: &[u8] = b"idb3.001";
+
+/// The length of the identifier, which is the same for every version.
+const FILE_TYPE_IDENTIFIER_LEN: usize = FILE_TYPE_IDENTIFIER.len();
+
+/// The length of the crc32 checksum that follows the identifier.
+const CHECKSUM_LEN: usize = size_of::<u32>();
+
+/// The zstd level used to compress wal files. Favours speed, since files are written on the
+/// write path.
+const ZSTD_COMPRESSION_LEVEL: i32 = 3;
+
+impl WalCompression {
+    /// The byte recorded in the header of a wal file to identify the compression used.
+    fn header_byte(&self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Zstd => 1,
+            Self::Snappy => 2,
+        }
+    }
+
+    fn from_header_byte(b: u8) -> Result<Self> {
+        match b {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Zstd),
+            2 => Ok(Self::Snappy),
+            _ => Err(Error::UnknownCompression(b)),
+        }
+    }
+}
+
+/// The bytes of a serialized wal file along with the size of its contents before and after
+/// compression
+#[derive(Debug)]
+pub(crate) struct SerializedWalFile {
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) uncompressed_len: usize,
+    pub(crate) compressed_len: usize,
+}
 
 pub fn verify_file_type_and_deserialize(b: Bytes) -> Result<WalContents> {
     let contents = b.to_vec();
 
-    let pos = FILE_TYPE_IDENTIFIER.len();
+    let pos = FILE_TYPE_IDENTIFIER_LEN;
+    if contents.len() < pos + CHECKSUM_LEN {
+        return Err(Error::InvalidWalFile);
+    }
 
     // Read and verify the file type identifier
     let file_type = &contents[..pos];
 
-    if file_type != FILE_TYPE_IDENTIFIER {
+    if file_type != FILE_TYPE_IDENTIFIER
+        && file_type != FILE_TYPE_IDENTIFIER_V2
+        && file_type != FILE_TYPE_IDENTIFIER_V1
+    {
         return Err(Error::InvalidWalFile);
     }
 
     // Read the crc32 checksum
-    const CHECKSUM_LEN: usize = size_of::<u32>();
     let checksum_slice = &contents[pos..pos + CHECKSUM_LEN]; // Ensure this slice covers the 4 bytes for the checksum
     let mut cursor = Cursor::new(checksum_slice);
-    let crc32_checksum = cursor.read_u32::<BigEndian>().unwrap();
+    let crc32_checksum = cursor.read_u32::<BigEndian>()?;
 
     // Validate the data against the checksum
     let data = &contents[pos + CHECKSUM_LEN..];
@@ -60,39 +135,82 @@ pub fn verify_file_type_and_deserialize(b: Bytes) -> Result<WalContents> {
         return Err(Error::Crc32Mismatch);
     }
 
-    // Deserialize the data into a WalContents
-    let contents: WalContents = serde_json::from_slice(data)?;
+    // Deserialize the data into a WalContents, based on the version of the file
+    let contents: WalContents = if file_type == FILE_TYPE_IDENTIFIER_V1 {
+        serde_json::from_slice(data)?
+    } else if file_type == FILE_TYPE_IDENTIFIER_V2 {
+        rmp_serde::from_slice(data)?
+    } else {
+        let (compression, data) = data.split_first().ok_or(Error::InvalidWalFile)?;
+        match WalCompression::from_header_byte(*compression)? {
+            WalCompression::None => rmp_serde::from_slice(data)?,
+            WalCompression::Zstd => rmp_serde::from_slice(&zstd::stream::decode_all(data)?)?,
+            WalCompression::Snappy => {
+                rmp_serde::from_slice(&snap::raw::Decoder::new().decompress_vec(data)?)?
+            }
+        }
+    };
 
     Ok(contents)
 }
 
-pub(crate) fn serialize_to_file_bytes(contents: &WalContents) -> Result<Vec<u8>> {
+/// Serializes the contents of a wal file into the bytes of the file, including its identifier and
+/// checksum
+pub fn serialize_to_bytes(contents: &WalContents, compression: WalCompression) -> Result<Vec<u8>> {
+    serialize_to_file_bytes(contents, compression).map(|serialized| serialized.bytes)
+}
+
+pub(crate) fn serialize_to_file_bytes(
+    contents: &WalContents,
+    compression: WalCompression,
+) -> Result<SerializedWalFile> {
     let mut buf = Vec::new();
     buf.extend_from_slice(FILE_TYPE_IDENTIFIER);
 
-    // serialize the contents into json bytes
-    let data = serde_json::to_vec(contents)?;
+    // serialize the contents into MessagePack bytes and compress them
+    let serialized = rmp_serde::to_vec(contents)?;
+    let uncompressed_len = serialized.len();
+    let compressed = match compression {
+        WalCompression::None => serialized,
+        WalCompression::Zstd => zstd::bulk::compress(&serialized, ZSTD_COMPRESSION_LEVEL)?,
+        WalCompression::Snappy => snap::raw::Encoder::new().compress_vec(&serialized)?,
+    };
+    let compressed_len = compressed.len();
 
-    // calculate the crc32 checksum
+    // calculate the crc32 checksum over the compression type and the compressed data
+    let compression_byte = [compression.header_byte()];
     let mut hasher = crc32fast::Hasher::new();
-    hasher.update(&data);
+    hasher.update(&compression_byte);
+    hasher.update(&compressed);
     let checksum = hasher.finalize();
 
-    // write the checksum and data to the buffer
+    // write the checksum, compression type and data to the buffer
     buf.extend_from_slice(&checksum.to_be_bytes());
-    buf.extend_from_slice(&data);
+    buf.extend_from_slice(&compression_byte);
+    buf.extend_from_slice(&compressed);
 
-    Ok(buf)
+    Ok(SerializedWalFile {
+        bytes: buf,
+        uncompressed_len,
+        compressed_len,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        Field, FieldData, Row, TableChunk, TableChunks, WalFileSequenceNumber, WalOp, WriteBatch,
+        CatalogBatch, CatalogOp, CoercionPolicy, CoercionPolicyDefinition, ColumnRename,
+        DatabaseDefinition, DatabaseDelete, DatabaseQuotas, DeleteBatch, DeletePredicate, Field,
+        FieldAdditions, FieldData, FieldDataType, FieldDefinition, JsonFieldMapping, JsonFieldType,
+        JsonMapping, JsonMappingDefinition, JsonTimestampFormat, JsonTimestampMapping,
+        LastCacheDefinition, LastCacheDelete, QuotasDefinition, RetentionPeriodDefinition, Row,
+        SchemaPolicy, SchemaPolicyDefinition, SnapshotDetails, SnapshotSequenceNumber, TableChunk,
+        TableChunks, TableDefinition, TableDelete, TableRename, WalFileSequenceNumber, WalOp,
+        WriteBatch,
     };
     use hashbrown::HashMap;
-    use influxdb3_id::{DbId, TableId};
+    use influxdb3_id::{ColumnId, DbId, TableId};
 
     #[test]
     fn test_serialize_deserialize() {
@@ -124,6 +242,7 @@ mod tests {
             min_timestamp_ns: 0,
             max_timestamp_ns: 10,
             wal_file_number: WalFileSequenceNumber::new(1),
+            persisted_time_ns: 0,
             ops: vec![WalOp::Write(WriteBatch {
                 database_id: DbId::from(0),
                 database_name: "foo".into(),
@@ -134,9 +253,420 @@ mod tests {
             snapshot: None,
         };
 
-        let bytes = serialize_to_file_bytes(&contents).unwrap();
+        let bytes = serialize_to_file_bytes(&contents, WalCompression::None)
+            .unwrap()
+            .bytes;
         let deserialized = verify_file_type_and_deserialize(Bytes::from(bytes)).unwrap();
 
         assert_eq!(contents, deserialized);
     }
+
+    /// Serialize the contents as a version 1 (JSON) file would have been written
+    fn serialize_to_v1_file_bytes(contents: &WalContents) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(FILE_TYPE_IDENTIFIER_V1);
+        let data = serde_json::to_vec(contents).unwrap();
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&data);
+        buf.extend_from_slice(&hasher.finalize().to_be_bytes());
+        buf.extend_from_slice(&data);
+        buf
+    }
+
+    /// Serialize the contents as a version 2 (uncompressed MessagePack) file would have been
+    /// written
+    fn serialize_to_v2_file_bytes(contents: &WalContents) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(FILE_TYPE_IDENTIFIER_V2);
+        let data = rmp_serde::to_vec(contents).unwrap();
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&data);
+        buf.extend_from_slice(&hasher.finalize().to_be_bytes());
+        buf.extend_from_slice(&data);
+        buf
+    }
+
+    fn all_ops_contents() -> WalContents {
+        let fields = vec![
+            Field {
+                name: "time".into(),
+                value: FieldData::Timestamp(1),
+            },
+            Field {
+                name: "key".into(),
+                value: FieldData::Key("k".to_string()),
+            },
+            Field {
+                name: "tag".into(),
+                value: FieldData::Tag("t".to_string()),
+            },
+            Field {
+                name: "str".into(),
+                value: FieldData::String("s".to_string()),
+            },
+            Field {
+                name: "int".into(),
+                value: FieldData::Integer(-10),
+            },
+            Field {
+                name: "uint".into(),
+                value: FieldData::UInteger(u64::MAX),
+            },
+            Field {
+                name: "float".into(),
+                value: FieldData::Float(1.5),
+            },
+            Field {
+                name: "bool".into(),
+                value: FieldData::Boolean(true),
+            },
+        ];
+        let chunks = TableChunks {
+            min_time: 1,
+            max_time: 1,
+            chunk_time_to_chunk: [(
+                0,
+                TableChunk {
+                    rows: vec![Row { time: 1, fields }],
+                },
+            )]
+            .into_iter()
+            .collect(),
+        };
+        let table_id = TableId::from(1);
+        let field_definitions = vec![
+            FieldDefinition {
+                name: "tag".into(),
+                data_type: FieldDataType::Tag,
+            },
+            FieldDefinition {
+                name: "key".into(),
+                data_type: FieldDataType::Key,
+            },
+            FieldDefinition {
+                name: "str".into(),
+                data_type: FieldDataType::String,
+            },
+            FieldDefinition {
+                name: "int".into(),
+                data_type: FieldDataType::Integer,
+            },
+            FieldDefinition {
+                name: "uint".into(),
+                data_type: FieldDataType::UInteger,
+            },
+            FieldDefinition {
+                name: "float".into(),
+                data_type: FieldDataType::Float,
+            },
+            FieldDefinition {
+                name: "bool".into(),
+                data_type: FieldDataType::Boolean,
+            },
+            FieldDefinition {
+                name: "time".into(),
+                data_type: FieldDataType::Timestamp,
+            },
+        ];
+
+        WalContents {
+            min_timestamp_ns: 1,
+            max_timestamp_ns: 1,
+            wal_file_number: WalFileSequenceNumber::new(3),
+            persisted_time_ns: 0,
+            ops: vec![
+                WalOp::Catalog(CatalogBatch {
+                    database_id: DbId::from(0),
+                    database_name: "foo".into(),
+                    time_ns: 0,
+                    ops: vec![
+                        CatalogOp::CreateDatabase(DatabaseDefinition {
+                            database_id: DbId::from(0),
+                            database_name: "foo".into(),
+                        }),
+                        CatalogOp::CreateTable(TableDefinition {
+                            database_id: DbId::from(0),
+                            database_name: "foo".into(),
+                            table_name: "cpu".into(),
+                            table_id,
+                            field_definitions: field_definitions.clone(),
+                            key: Some(vec!["tag".to_string()]),
+                        }),
+                        CatalogOp::CreateTable(TableDefinition {
+                            database_id: DbId::from(0),
+                            database_name: "foo".into(),
+                            table_name: "mem".into(),
+                            table_id: TableId::from(2),
+                            field_definitions: vec![],
+                            key: None,
+                        }),
+                        CatalogOp::AddFields(FieldAdditions {
+                            database_name: "foo".into(),
+                            database_id: DbId::from(0),
+                            table_name: "cpu".into(),
+                            table_id,
+                            field_definitions,
+                        }),
+                        CatalogOp::CreateLastCache(
+                            LastCacheDefinition::new_with_explicit_value_columns(
+                                table_id,
+                                "cpu",
+                                "explicit_cache",
+                                ["tag"],
+                                ["int", "float"],
+                                1,
+                                600,
+                            )
+                            .unwrap(),
+                        ),
+                        CatalogOp::CreateLastCache(
+                            LastCacheDefinition::new_all_non_key_value_columns(
+                                table_id,
+                                "cpu",
+                                "all_cache",
+                                ["tag"],
+                                5,
+                                3600,
+                            )
+                            .unwrap(),
+                        ),
+                        CatalogOp::DeleteLastCache(LastCacheDelete {
+                            table_name: "cpu".to_string(),
+                            table_id,
+                            name: "explicit_cache".to_string(),
+                        }),
+                        CatalogOp::DeleteTable(TableDelete {
+                            table_id: TableId::from(2),
+                            table_name: "mem".into(),
+                        }),
+                        CatalogOp::SetSchemaPolicy(SchemaPolicyDefinition {
+                            table_id: Some(table_id),
+                            table_name: Some("cpu".into()),
+                            policy: SchemaPolicy::DropUnknown,
+                        }),
+                        CatalogOp::SetSchemaPolicy(SchemaPolicyDefinition {
+                            table_id: None,
+                            table_name: None,
+                            policy: SchemaPolicy::Locked,
+                        }),
+                        CatalogOp::RenameColumn(ColumnRename {
+                            table_id,
+                            table_name: "cpu".into(),
+                            column_id: ColumnId::from(1),
+                            old_name: "usage".into(),
+                            new_name: "cpu_usage".into(),
+                        }),
+                        CatalogOp::RenameTable(TableRename {
+                            table_id,
+                            old_name: "cpu".into(),
+                            new_name: "cpu_metrics".into(),
+                        }),
+                        CatalogOp::SetRetentionPeriod(RetentionPeriodDefinition {
+                            retention_period_ns: Some(3_600_000_000_000),
+                        }),
+                        CatalogOp::SetCoercionPolicy(CoercionPolicyDefinition {
+                            table_id,
+                            table_name: "cpu".into(),
+                            policy: CoercionPolicy {
+                                integer_to_float: true,
+                                uinteger_to_integer: false,
+                                any_to_string: true,
+                            },
+                        }),
+                        CatalogOp::SetQuotas(QuotasDefinition {
+                            quotas: DatabaseQuotas {
+                                max_tables: Some(10),
+                                max_series: Some(1_000),
+                                ..Default::default()
+                            },
+                        }),
+                        CatalogOp::SetJsonMapping(JsonMappingDefinition {
+                            table_id,
+                            table_name: "cpu".into(),
+                            mapping: Some(JsonMapping {
+                                measurement_path: None,
+                                tags: [("host".to_string(), "meta.host".to_string())]
+                                    .into_iter()
+                                    .collect(),
+                                fields: [(
+                                    "usage".to_string(),
+                                    JsonFieldMapping {
+                                        path: "readings.0".to_string(),
+                                        field_type: Some(JsonFieldType::Integer),
+                                    },
+                                )]
+                                .into_iter()
+                                .collect(),
+                                timestamp: Some(JsonTimestampMapping {
+                                    path: "ts".to_string(),
+                                    format: JsonTimestampFormat::Rfc3339,
+                                }),
+                            }),
+                        }),
+                    ],
+                }),
+                WalOp::Catalog(CatalogBatch {
+                    database_id: DbId::from(1),
+                    database_name: "bar".into(),
+                    time_ns: 1,
+                    ops: vec![CatalogOp::DeleteDatabase(DatabaseDelete {
+                        database_id: DbId::from(1),
+                        database_name: "bar".into(),
+                    })],
+                }),
+                WalOp::Write(WriteBatch {
+                    database_id: DbId::from(0),
+                    database_name: "foo".into(),
+                    table_chunks: [(table_id, chunks)].into_iter().collect(),
+                    min_time_ns: 1,
+                    max_time_ns: 1,
+                }),
+                WalOp::Delete(DeleteBatch {
+                    database_id: DbId::from(0),
+                    database_name: "foo".into(),
+                    table_id,
+                    table_name: "cpu".into(),
+                    time_ns: 1,
+                    predicate: DeletePredicate {
+                        min_time_ns: 0,
+                        max_time_ns: 1,
+                        tags: vec![("tag".into(), "a".into())],
+                    },
+                }),
+            ],
+            snapshot: Some(SnapshotDetails {
+                snapshot_sequence_number: SnapshotSequenceNumber::new(1),
+                end_time_marker: 10,
+                last_wal_sequence_number: WalFileSequenceNumber::new(2),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_serialize_deserialize_all_ops() {
+        let contents = all_ops_contents();
+
+        for compression in [
+            WalCompression::None,
+            WalCompression::Zstd,
+            WalCompression::Snappy,
+        ] {
+            let serialized = serialize_to_file_bytes(&contents, compression).unwrap();
+            let bytes = serialized.bytes;
+            assert_eq!(&bytes[..FILE_TYPE_IDENTIFIER_LEN], FILE_TYPE_IDENTIFIER);
+            assert_eq!(
+                bytes[FILE_TYPE_IDENTIFIER_LEN + CHECKSUM_LEN],
+                compression.header_byte()
+            );
+            assert_eq!(
+                bytes.len(),
+                FILE_TYPE_IDENTIFIER_LEN + CHECKSUM_LEN + 1 + serialized.compressed_len
+            );
+            let deserialized = verify_file_type_and_deserialize(Bytes::from(bytes)).unwrap();
+
+            assert_eq!(contents, deserialized, "compression: {compression}");
+        }
+    }
+
+    #[test]
+    fn test_compression_reduces_size() {
+        // repeated tag values, as would come from line protocol, should compress well
+        let mut contents = all_ops_contents();
+        let WalOp::Write(batch) = &contents.ops[1] else {
+            panic!("expected a write batch");
+        };
+        let mut batch = batch.clone();
+        let chunk = batch
+            .table_chunks
+            .values_mut()
+            .next()
+            .unwrap()
+            .chunk_time_to_chunk
+            .values_mut()
+            .next()
+            .unwrap();
+        let row = chunk.rows[0].clone();
+        chunk.rows = (0..1_000).map(|_| row.clone()).collect();
+        contents.ops = vec![WalOp::Write(batch)];
+
+        let uncompressed = serialize_to_file_bytes(&contents, WalCompression::None).unwrap();
+        assert_eq!(uncompressed.uncompressed_len, uncompressed.compressed_len);
+
+        for compression in [WalCompression::Zstd, WalCompression::Snappy] {
+            let compressed = serialize_to_file_bytes(&contents, compression).unwrap();
+            assert_eq!(compressed.uncompressed_len, uncompressed.uncompressed_len);
+            assert!(
+                compressed.compressed_len * 4 < compressed.uncompressed_len,
+                "{compression} compressed {} bytes to {}",
+                compressed.uncompressed_len,
+                compressed.compressed_len
+            );
+            assert_eq!(
+                verify_file_type_and_deserialize(Bytes::from(compressed.bytes)).unwrap(),
+                contents
+            );
+        }
+    }
+
+    #[test]
+    fn test_deserialize_older_versions() {
+        let contents = all_ops_contents();
+
+        let v1_bytes = serialize_to_v1_file_bytes(&contents);
+        let deserialized = verify_file_type_and_deserialize(Bytes::from(v1_bytes)).unwrap();
+        assert_eq!(contents, deserialized);
+
+        let v2_bytes = serialize_to_v2_file_bytes(&contents);
+        let deserialized = verify_file_type_and_deserialize(Bytes::from(v2_bytes)).unwrap();
+        assert_eq!(contents, deserialized);
+
+        // the binary encoding should be smaller than the JSON one
+        assert!(
+            serialize_to_v2_file_bytes(&contents).len()
+                < serialize_to_v1_file_bytes(&contents).len()
+        );
+    }
+
+    #[test]
+    fn test_deserialize_invalid_files() {
+        let contents = all_ops_contents();
+        let mut bytes = serialize_to_file_bytes(&contents, WalCompression::Zstd)
+            .unwrap()
+            .bytes;
+
+        // truncated before the checksum
+        assert!(matches!(
+            verify_file_type_and_deserialize(Bytes::from(bytes[..6].to_vec())),
+            Err(Error::InvalidWalFile)
+        ));
+
+        // corrupted payload
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        assert!(matches!(
+            verify_file_type_and_deserialize(Bytes::from(bytes.clone())),
+            Err(Error::Crc32Mismatch)
+        ));
+        bytes[last] ^= 0xff;
+
+        // unknown compression, with a valid checksum
+        let data_start = FILE_TYPE_IDENTIFIER_LEN + CHECKSUM_LEN;
+        let mut unknown_compression = bytes.clone();
+        unknown_compression[data_start] = 42;
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&unknown_compression[data_start..]);
+        unknown_compression[FILE_TYPE_IDENTIFIER_LEN..data_start]
+            .copy_from_slice(&hasher.finalize().to_be_bytes());
+        assert!(matches!(
+            verify_file_type_and_deserialize(Bytes::from(unknown_compression)),
+            Err(Error::UnknownCompression(42))
+        ));
+
+        // unknown identifier
+        bytes[..FILE_TYPE_IDENTIFIER_LEN].copy_from_slice(b"idb3.999");
+        assert!(matches!(
+            verify_file_type_and_deserialize(Bytes::from(bytes)),
+            Err(Error::InvalidWalFile)
+        ));
+    }
 }
@@ -134,6 +134,11 @@ impl SnapshotTracker {
         self.last_snapshot_sequence_number
     }
 
+    /// Reserves the next [`SnapshotSequenceNumber`] for a snapshot that is not taken of the WAL
+    pub(crate) fn reserve_snapshot_sequence_number(&mut self) -> SnapshotSequenceNumber {
+        self.increment_snapshot_sequence_number()
+    }
+
     fn increment_snapshot_sequence_number(&mut self) -> SnapshotSequenceNumber {
         self.last_snapshot_sequence_number = self.last_snapshot_sequence_number.next();
         self.last_snapshot_sequence_number
@@ -1,12 +1,33 @@
-use arrow::array::RecordBatch;
+use arrow::array::{RecordBatch, StringArray, TimestampNanosecondArray};
+use arrow::compute::kernels::boolean::{and, not};
+use arrow::compute::kernels::cmp::{eq, gt_eq, lt_eq};
+use arrow::compute::{cast, filter_record_batch, prep_null_mask_filter};
+use arrow::datatypes::{DataType, Schema as ArrowSchema};
+use arrow::error::ArrowError;
 use data_types::{ChunkId, ChunkOrder, TransitionPartitionId};
-use datafusion::common::Statistics;
+use datafusion::catalog::Session;
+use datafusion::common::tree_node::{Transformed, TransformedResult, TreeNode};
+use datafusion::common::{Column, DFSchema, Statistics};
+use datafusion::datasource::listing::PartitionedFile;
+use datafusion::datasource::physical_plan::{FileScanConfig, ParquetExec};
+use datafusion::error::DataFusionError;
+use datafusion::logical_expr::Cast;
+use datafusion::physical_expr::expressions::Column as PhysicalColumn;
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion::physical_plan::filter::FilterExec;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::prelude::{lit, lit_timestamp_nano, Expr};
+use influxdb3_id::ColumnId;
+use influxdb3_wal::DeletePredicate;
 use iox_query::chunk_statistics::ChunkStatistics;
 use iox_query::{QueryChunk, QueryChunkData};
 use parquet_file::storage::ParquetExecInput;
 use schema::sort::SortKey;
-use schema::Schema;
+use schema::{Schema, TIME_COLUMN_NAME};
 use std::any::Any;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 #[derive(Debug)]
@@ -71,6 +92,233 @@ pub struct ParquetChunk {
     pub(crate) id: ChunkId,
     pub(crate) chunk_order: ChunkOrder,
     pub(crate) parquet_exec: ParquetExecInput,
+    /// The predicates of the tombstones that apply to this file
+    pub(crate) tombstones: Vec<DeletePredicate>,
+    /// The names of the columns in this file that have been renamed since it was persisted, paired
+    /// with the names they have now
+    pub(crate) column_renames: Vec<(Arc<str>, Arc<str>)>,
+    /// The columns of the table that are not in this file but have the name that another column
+    /// has in it, paired with a name that no column has in the file, which they are read under so
+    /// that they read as null
+    pub(crate) columns_not_in_file: Vec<(Arc<str>, Arc<str>)>,
+}
+
+impl ParquetChunk {
+    /// Whether the file can't be scanned with the other chunks of the table, because it has
+    /// tombstones applying to it or columns that have been renamed since it was persisted, or
+    /// whose names have been reused since
+    fn needs_own_scan(&self) -> bool {
+        !self.tombstones.is_empty()
+            || !self.column_renames.is_empty()
+            || !self.columns_not_in_file.is_empty()
+    }
+
+    /// Sets the columns of the table that reuse the names of columns in the file, which are read
+    /// under names that no column has in the file
+    pub(crate) fn set_columns_not_in_file(
+        &mut self,
+        reused_names: Vec<Arc<str>>,
+        file_column_names: &BTreeMap<Arc<str>, ColumnId>,
+    ) {
+        self.columns_not_in_file = reused_names
+            .into_iter()
+            .map(|name| {
+                let mut placeholder = format!("{name}_not_in_file");
+                while file_column_names.contains_key(placeholder.as_str()) {
+                    placeholder.push('_');
+                }
+                (name, placeholder.into())
+            })
+            .collect();
+    }
+
+    /// Plans a scan of the file that reads its renamed columns by the names they have in the file
+    /// and outputs them under their current names, and that filters out the rows deleted by its
+    /// tombstones. The projection and filters are those of the query on the table. The filters
+    /// and tombstones are pushed down to the scan to prune the row groups of the file.
+    fn scan_plan(
+        &self,
+        ctx: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+    ) -> Result<Arc<dyn ExecutionPlan>, DataFusionError> {
+        let table_schema = self.schema.as_arrow();
+        let file_schema = Arc::new(ArrowSchema::new_with_metadata(
+            table_schema
+                .fields()
+                .iter()
+                .map(|field| match self.file_column_name(field.name()) {
+                    name if name == field.name() => Arc::clone(field),
+                    name => Arc::new(field.as_ref().clone().with_name(name)),
+                })
+                .collect::<Vec<_>>(),
+            table_schema.metadata().clone(),
+        ));
+        let file_df_schema = DFSchema::try_from(file_schema.as_ref().clone())?;
+
+        // a row is kept unless it matches one of the tombstones
+        let keep = self
+            .tombstones
+            .iter()
+            .filter_map(|predicate| self.deleted_rows_expr(predicate, &file_schema))
+            .reduce(Expr::or)
+            .map(|deleted| Expr::Not(Box::new(deleted)));
+
+        // the columns read from the file are the projected ones, followed by those that are only
+        // needed to apply the tombstones
+        let output_columns = projection
+            .cloned()
+            .unwrap_or_else(|| (0..table_schema.fields().len()).collect());
+        let mut scan_columns = output_columns.clone();
+        if let Some(keep) = &keep {
+            for column in keep.column_refs() {
+                let index = file_schema.index_of(&column.name)?;
+                if !scan_columns.contains(&index) {
+                    scan_columns.push(index);
+                }
+            }
+        }
+
+        // the filters are applied again above the scan, so one that can't be pushed down to the
+        // file is left out
+        let predicate = keep
+            .iter()
+            .cloned()
+            .chain(filters.iter().filter_map(|filter| {
+                let filter = self.with_file_column_names(filter).ok()?;
+                ctx.create_physical_expr(filter.clone(), &file_df_schema)
+                    .is_ok()
+                    .then_some(filter)
+            }))
+            .reduce(Expr::and)
+            .map(|predicate| ctx.create_physical_expr(predicate, &file_df_schema))
+            .transpose()?;
+
+        let config = FileScanConfig::new(
+            self.parquet_exec.object_store_url.clone(),
+            Arc::clone(&file_schema),
+        )
+        .with_file(PartitionedFile::from(self.parquet_exec.object_meta.clone()))
+        .with_projection(Some(scan_columns));
+        let mut parquet_exec = ParquetExec::builder(config);
+        if let Some(predicate) = predicate {
+            parquet_exec = parquet_exec.with_predicate(predicate);
+        }
+        let mut plan: Arc<dyn ExecutionPlan> = parquet_exec.build_arc();
+
+        if let Some(keep) = keep {
+            let scanned_schema = DFSchema::try_from(plan.schema().as_ref().clone())?;
+            let keep = ctx.create_physical_expr(keep, &scanned_schema)?;
+            plan = Arc::new(FilterExec::try_new(keep, plan)?);
+        }
+
+        // output the projected columns under their current names
+        let columns = output_columns
+            .iter()
+            .enumerate()
+            .map(|(position, &index)| {
+                (
+                    Arc::new(PhysicalColumn::new(
+                        file_schema.field(index).name(),
+                        position,
+                    )) as Arc<dyn PhysicalExpr>,
+                    table_schema.field(index).name().clone(),
+                )
+            })
+            .collect();
+        Ok(Arc::new(ProjectionExec::try_new(columns, plan)?))
+    }
+
+    /// The name that a column of the table has in the file, which no column in the file has if
+    /// the column is not in the file but its name was reused
+    fn file_column_name<'a>(&'a self, name: &'a str) -> &'a str {
+        let renamed = self
+            .column_renames
+            .iter()
+            .find(|(_, new_name)| new_name.as_ref() == name)
+            .map(|(old_name, _)| old_name);
+        let not_in_file = || {
+            self.columns_not_in_file
+                .iter()
+                .find(|(column, _)| column.as_ref() == name)
+                .map(|(_, placeholder)| placeholder)
+        };
+        renamed
+            .or_else(not_in_file)
+            .map_or(name, |name| name.as_ref())
+    }
+
+    /// The filter with the columns it refers to named by the names they have in the file
+    fn with_file_column_names(&self, filter: &Expr) -> Result<Expr, DataFusionError> {
+        filter
+            .clone()
+            .transform(|expr| {
+                Ok(match expr {
+                    Expr::Column(column) => Transformed::yes(Expr::Column(
+                        Column::new_unqualified(self.file_column_name(&column.name)),
+                    )),
+                    expr => Transformed::no(expr),
+                })
+            })
+            .data()
+    }
+
+    /// An expression that matches the rows of the file that the delete predicate deletes, or
+    /// `None` if the file does not have one of the tag columns of the predicate, in which case
+    /// none of its rows can match
+    fn deleted_rows_expr(
+        &self,
+        predicate: &DeletePredicate,
+        file_schema: &ArrowSchema,
+    ) -> Option<Expr> {
+        let predicate = self.predicate_with_file_names(predicate);
+        let time = || Expr::Column(Column::new_unqualified(TIME_COLUMN_NAME));
+        let mut deleted = time()
+            .gt_eq(lit_timestamp_nano(predicate.min_time_ns))
+            .and(time().lt_eq(lit_timestamp_nano(predicate.max_time_ns)));
+        for (tag, value) in &predicate.tags {
+            file_schema.index_of(tag).ok()?;
+            // rows with a null tag value do not match, rather than the match being null
+            let tag = Expr::Column(Column::new_unqualified(tag.as_ref()));
+            deleted = deleted
+                .and(tag.clone().is_not_null())
+                .and(Expr::Cast(Cast::new(Box::new(tag), DataType::Utf8)).eq(lit(value.as_ref())));
+        }
+        Some(deleted)
+    }
+}
+
+impl ParquetChunk {
+    /// The predicate with the tags that are named by the current name of a renamed column
+    /// changed to the name that the column has in the file. Tags of deletes made before the
+    /// rename already have the name in the file. Deletes only name their tags, so a tag named by
+    /// the old name of a renamed column is taken to be the renamed column, even if the delete was
+    /// made after a new column reused the name.
+    fn predicate_with_file_names<'a>(
+        &self,
+        predicate: &'a DeletePredicate,
+    ) -> Cow<'a, DeletePredicate> {
+        let file_name = |tag: &Arc<str>| {
+            self.column_renames
+                .iter()
+                .find(|(_, new_name)| new_name == tag)
+                .map(|(old_name, _)| Arc::clone(old_name))
+        };
+        if !predicate
+            .tags
+            .iter()
+            .any(|(tag, _)| file_name(tag).is_some())
+        {
+            return Cow::Borrowed(predicate);
+        }
+        let mut predicate = predicate.clone();
+        for (tag, _) in &mut predicate.tags {
+            if let Some(name) = file_name(tag) {
+                *tag = name;
+            }
+        }
+        Cow::Owned(predicate)
+    }
 }
 
 impl QueryChunk for ParquetChunk {
@@ -114,3 +362,72 @@ impl QueryChunk for ParquetChunk {
         self
     }
 }
+
+/// Takes the parquet chunks that have tombstones applying to them, or that have columns that have
+/// been renamed since they were persisted, out of the chunks of a table, and plans a scan for each
+/// of them that filters out the deleted rows and renames the columns. The other chunks are
+/// returned to be scanned as usual. The rows of the files scanned on their own are not
+/// deduplicated against the rows of the other chunks.
+pub fn scan_rewritten_chunks(
+    ctx: &dyn Session,
+    chunks: Vec<Arc<dyn QueryChunk>>,
+    projection: Option<&Vec<usize>>,
+    filters: &[Expr],
+) -> Result<(Vec<Arc<dyn QueryChunk>>, Vec<Arc<dyn ExecutionPlan>>), DataFusionError> {
+    let mut unchanged = Vec::with_capacity(chunks.len());
+    let mut plans = vec![];
+    for chunk in chunks {
+        match chunk.as_any().downcast_ref::<ParquetChunk>() {
+            Some(parquet_chunk) if parquet_chunk.needs_own_scan() => {
+                plans.push(parquet_chunk.scan_plan(ctx, projection, filters)?);
+            }
+            _ => unchanged.push(chunk),
+        }
+    }
+    Ok((unchanged, plans))
+}
+
+/// A delete predicate that matches the rows that are older than the retention cutoff of their
+/// database
+pub(crate) fn expired_rows_predicate(retention_cutoff_ns: i64) -> DeletePredicate {
+    DeletePredicate {
+        min_time_ns: i64::MIN,
+        max_time_ns: retention_cutoff_ns.saturating_sub(1),
+        tags: vec![],
+    }
+}
+
+/// Filters out the rows of the batch that match the delete predicate
+pub(crate) fn filter_deleted_rows(
+    batch: &RecordBatch,
+    predicate: &DeletePredicate,
+) -> Result<RecordBatch, ArrowError> {
+    let Some(time) = batch.column_by_name(TIME_COLUMN_NAME) else {
+        return Ok(batch.clone());
+    };
+    let mut matches = and(
+        &gt_eq(
+            time,
+            &TimestampNanosecondArray::new_scalar(predicate.min_time_ns),
+        )?,
+        &lt_eq(
+            time,
+            &TimestampNanosecondArray::new_scalar(predicate.max_time_ns),
+        )?,
+    )?;
+    for (tag, value) in &predicate.tags {
+        // if the batch does not have the tag column, none of its rows can match
+        let Some(column) = batch.column_by_name(tag) else {
+            return Ok(batch.clone());
+        };
+        let column = cast(column, &DataType::Utf8)?;
+        matches = and(
+            &matches,
+            &eq(&column, &StringArray::new_scalar(value.as_ref()))?,
+        )?;
+    }
+
+    // rows with a null tag value do not match, rather than being filtered out by the null
+    let keep = not(&prep_null_mask_filter(&matches))?;
+    filter_record_batch(batch, &keep)
+}
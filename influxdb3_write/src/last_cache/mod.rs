@@ -441,6 +441,22 @@ impl LastCacheProvider {
         Ok(())
     }
 
+    /// Delete all caches for a database, when the database is deleted
+    pub fn delete_caches_for_db(&self, db_id: DbId) {
+        self.cache_map.write().remove(&db_id);
+    }
+
+    /// Delete all caches for a table, when the table is deleted
+    pub fn delete_caches_for_table(&self, db_id: DbId, table_id: TableId) {
+        let mut lock = self.cache_map.write();
+        if let Some(db) = lock.get_mut(&db_id) {
+            db.remove(&table_id);
+            if db.is_empty() {
+                lock.remove(&db_id);
+            }
+        }
+    }
+
     /// Write the contents from a wal file into the cache by iterating over its database and table batches
     /// to find entries that belong in the cache.
     ///
@@ -468,6 +484,18 @@ impl LastCacheProvider {
                     }
                 }
                 WalOp::Catalog(_) => (),
+                WalOp::Delete(batch) => {
+                    // the cached values that match the delete predicate are not tracked, so the
+                    // caches on the table are cleared, and are filled again by new writes
+                    if let Some(table_cache) = cache_map
+                        .get_mut(&batch.database_id)
+                        .and_then(|db_cache| db_cache.get_mut(&batch.table_id))
+                    {
+                        for (_, last_cache) in table_cache.iter_mut() {
+                            last_cache.clear();
+                        }
+                    }
+                }
             }
         }
     }
@@ -573,6 +601,11 @@ impl LastCache {
         }
     }
 
+    /// Remove all values from the cache
+    fn clear(&mut self) {
+        self.state = LastCacheState::Init;
+    }
+
     /// Compare this cache's configuration with that of another
     fn compare_config(&self, other: &Self) -> Result<(), Error> {
         if self.count != other.count {
@@ -1594,7 +1627,7 @@ mod tests {
         last_cache::{KeyValue, LastCacheProvider, Predicate, DEFAULT_CACHE_TTL},
         parquet_cache::test_cached_obj_store_and_oracle,
         persister::Persister,
-        write_buffer::WriteBufferImpl,
+        write_buffer::{rejected_writes::RejectedWritesConfig, WriteBufferImpl},
         Bufferer, LastCacheManager, Precision,
     };
     use ::object_store::{memory::InMemory, ObjectStore};
@@ -1603,7 +1636,7 @@ mod tests {
     use data_types::NamespaceName;
     use influxdb3_catalog::catalog::{Catalog, DatabaseSchema, TableDefinition};
     use influxdb3_id::{DbId, TableId};
-    use influxdb3_wal::{LastCacheDefinition, WalConfig};
+    use influxdb3_wal::{LastCacheDefinition, SchemaPolicy, WalBackend, WalConfig};
     use insta::assert_json_snapshot;
     use iox_time::{MockProvider, Time, TimeProvider};
 
@@ -1624,7 +1657,10 @@ mod tests {
             time_provider,
             crate::test_help::make_exec(),
             WalConfig::test_config(),
+            WalBackend::ObjectStore,
+            Arc::new(metric::Registry::default()),
             Some(parquet_cache),
+            RejectedWritesConfig::default(),
         )
         .await
         .unwrap()
@@ -3123,6 +3159,9 @@ mod tests {
                 map.insert(TableId::from(1), "test_table_2".into());
                 map
             },
+            schema_policy: SchemaPolicy::default(),
+            retention_period_ns: None,
+            quotas: Default::default(),
         };
         let table_id = TableId::from(0);
         use schema::InfluxColumnType::*;
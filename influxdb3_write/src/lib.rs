@@ -9,24 +9,33 @@ pub mod last_cache;
 pub mod parquet_cache;
 pub mod paths;
 pub mod persister;
+pub mod restore;
 pub mod write_buffer;
 
+use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
+use bytes::Bytes;
 use data_types::{NamespaceName, TimestampMinMax};
 use datafusion::catalog::Session;
 use datafusion::error::DataFusionError;
 use datafusion::prelude::Expr;
 use influxdb3_catalog::catalog::{self, SequenceNumber};
+use influxdb3_catalog::export::{CatalogExport, ImportDiff};
 use influxdb3_catalog::DatabaseSchemaProvider;
+use influxdb3_id::ColumnId;
 use influxdb3_id::DbId;
 use influxdb3_id::ParquetFileId;
 use influxdb3_id::TableId;
-use influxdb3_wal::{LastCacheDefinition, SnapshotSequenceNumber, WalFileSequenceNumber};
+use influxdb3_wal::{
+    CoercionPolicy, DatabaseQuotas, DeletePredicate, JsonMapping, LastCacheDefinition,
+    QuarantinedWalFile, Quota, SchemaPolicy, SnapshotSequenceNumber, WalFileSequenceNumber,
+};
 use iox_query::QueryChunk;
 use iox_time::Time;
 use last_cache::LastCacheProvider;
+use schema::InfluxFieldType;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 use std::sync::Arc;
 use std::time::Duration;
@@ -75,6 +84,56 @@ pub trait Bufferer: Debug + Send + Sync + 'static {
         precision: Precision,
     ) -> write_buffer::Result<BufferedWriteRequest>;
 
+    /// Maps JSON documents to rows with the given mapping, or the one stored for the table if none
+    /// is given, and writes them in the same way as line protocol. Errors are reported with the
+    /// number of the document they are for.
+    async fn write_json(
+        &self,
+        database: NamespaceName<'static>,
+        table: Option<&str>,
+        json: &str,
+        mapping: Option<JsonMapping>,
+        ingest_time: Time,
+        accept_partial: bool,
+    ) -> write_buffer::Result<BufferedWriteRequest>;
+
+    /// Writes annotated CSV, or plain CSV with the given column roles, in the same way as line
+    /// protocol. Errors are reported with the line of the row they are for. The CSV is read from
+    /// the reader as it is written, on a blocking thread, so a reader can stream it in.
+    async fn write_csv(
+        &self,
+        database: NamespaceName<'static>,
+        csv: Box<dyn std::io::Read + Send>,
+        columns: Option<write_buffer::csv_format::CsvColumns>,
+        ingest_time: Time,
+        accept_partial: bool,
+    ) -> write_buffer::Result<BufferedWriteRequest>;
+
+    /// Writes Arrow record batches to the table without converting them to line protocol. Their
+    /// columns are marked as tags or fields by their [`schema::COLUMN_METADATA_KEY`] metadata, and
+    /// a `time` column has the timestamps of the rows. The batches are written as a whole, or not
+    /// at all if any of them is invalid.
+    async fn write_record_batches(
+        &self,
+        database: NamespaceName<'static>,
+        table: &str,
+        batches: Vec<RecordBatch>,
+        ingest_time: Time,
+    ) -> write_buffer::Result<BufferedWriteRequest>;
+
+    /// Imports a parquet file into the table, persisting its rows directly to object store rather
+    /// than buffering them and writing them to the WAL. The rows are validated against the catalog
+    /// like [`Bufferer::write_record_batches`], adding the table or its columns if they are new,
+    /// split into parquet files by gen1 chunk of time, sorted and deduplicated, and registered in
+    /// a new [`PersistedSnapshot`].
+    async fn import_parquet(
+        &self,
+        database: NamespaceName<'static>,
+        table: &str,
+        file: ParquetImportSource,
+        ingest_time: Time,
+    ) -> write_buffer::Result<ParquetImportSummary>;
+
     /// Returns the database schema provider
     fn db_schema_provider(&self) -> Arc<dyn DatabaseSchemaProvider>;
 
@@ -83,6 +142,130 @@ pub trait Bufferer: Debug + Send + Sync + 'static {
 
     /// A channel to watch for when new persisted snapshots are created
     fn watch_persisted_snapshots(&self) -> tokio::sync::watch::Receiver<Option<PersistedSnapshot>>;
+
+    /// Returns the WAL files that were quarantined because they could not be deserialized
+    fn quarantined_wal_files(&self) -> Vec<QuarantinedWalFile>;
+
+    /// Deletes the rows of a table that match the predicate. The delete is written to the WAL, and
+    /// when this returns the rows no longer show up in queries.
+    async fn delete_rows(
+        &self,
+        db_id: DbId,
+        table_id: TableId,
+        predicate: DeletePredicate,
+    ) -> write_buffer::Result<()>;
+
+    /// Creates a database that has no tables, with the retention period if one is given. The
+    /// create is written to the WAL, and fails if the database already exists.
+    async fn create_database(
+        &self,
+        database: NamespaceName<'static>,
+        retention_period: Option<Duration>,
+    ) -> write_buffer::Result<()>;
+
+    /// Creates a table with the given tag columns, which make up its series key, and fields, along
+    /// with its database if that does not exist. The create is written to the WAL, and fails if
+    /// the table already exists.
+    async fn create_table(
+        &self,
+        database: NamespaceName<'static>,
+        table: &str,
+        tags: Vec<String>,
+        fields: Vec<(String, InfluxFieldType)>,
+    ) -> write_buffer::Result<()>;
+
+    /// Sets the schema policy of a table, or the default schema policy of the database if no table
+    /// is given, which determines how writes that would add new columns to the table are handled.
+    async fn set_schema_policy(
+        &self,
+        db_id: DbId,
+        table_id: Option<TableId>,
+        policy: SchemaPolicy,
+    ) -> write_buffer::Result<()>;
+
+    /// Sets the coercion policy of a table, which determines the field values written to the table
+    /// that are converted to the type of their column, rather than rejected for not matching it.
+    async fn set_coercion_policy(
+        &self,
+        db_id: DbId,
+        table_id: TableId,
+        policy: CoercionPolicy,
+    ) -> write_buffer::Result<()>;
+
+    /// Sets the mapping used for JSON documents written to a table without a mapping of their own,
+    /// or removes it if `None` is given.
+    async fn set_json_mapping(
+        &self,
+        db_id: DbId,
+        table_id: TableId,
+        mapping: Option<JsonMapping>,
+    ) -> write_buffer::Result<()>;
+
+    /// Sets the retention period of a database, or removes it if `None` is given, in which case
+    /// data is kept forever. Data older than the retention period is not queried, and the parquet
+    /// files that only hold such data are deleted in the background.
+    async fn set_retention_period(
+        &self,
+        db_id: DbId,
+        retention_period: Option<Duration>,
+    ) -> write_buffer::Result<()>;
+
+    /// The time before which data in the database has expired, or `None` if the database keeps
+    /// data forever
+    fn retention_cutoff_ns(&self, db_id: DbId) -> Option<i64>;
+
+    /// Sets the quotas of a database, replacing the ones it had. Writes, and changes to the schema,
+    /// that would exceed a quota are rejected from then on.
+    async fn set_quotas(&self, db_id: DbId, quotas: DatabaseQuotas) -> write_buffer::Result<()>;
+
+    /// The limit and current usage of each quota of the database, or nothing if it does not exist
+    fn quota_usage(&self, db_id: DbId) -> Vec<QuotaUsage>;
+
+    /// Exports the databases in the catalog, along with their tables and last caches, to a
+    /// document that can be imported into another host
+    fn export_catalog(&self) -> CatalogExport;
+
+    /// Imports the exported catalog by creating the databases, tables, columns and last caches
+    /// that are missing from the catalog. Nothing is imported if this is a dry run or there are
+    /// any conflicts with the catalog, in which case the returned diff only describes the import.
+    async fn import_catalog(
+        &self,
+        export: &CatalogExport,
+        dry_run: bool,
+    ) -> write_buffer::Result<ImportDiff>;
+
+    /// Renames a table. The table keeps its id, and its buffered and persisted data are queried
+    /// under the new name.
+    async fn rename_table(
+        &self,
+        db_id: DbId,
+        table_id: TableId,
+        new_name: &str,
+    ) -> write_buffer::Result<()>;
+
+    /// Renames a column of a table. The column keeps its id, and its values in data that was
+    /// persisted under the old name are queried under the new name.
+    async fn rename_column(
+        &self,
+        db_id: DbId,
+        table_id: TableId,
+        column_name: &str,
+        new_name: &str,
+    ) -> write_buffer::Result<()>;
+
+    /// Deletes a database and all of its tables. The delete is written to the WAL, and when this
+    /// returns the database no longer shows up in queries. Its files are deleted in the background.
+    async fn delete_database(&self, db_id: DbId) -> write_buffer::Result<()>;
+
+    /// Deletes a table. The delete is written to the WAL, and when this returns the table no
+    /// longer shows up in queries. Its files are deleted in the background.
+    async fn delete_table(&self, db_id: DbId, table_id: TableId) -> write_buffer::Result<()>;
+
+    /// Returns the subscriptions to the stream of writes, as they are persisted to the WAL
+    fn write_subscriptions(&self) -> Arc<write_buffer::subscriptions::WriteSubscriptions>;
+
+    /// Returns the lines most recently rejected from writes
+    fn rejected_writes(&self) -> Arc<write_buffer::rejected_writes::RejectedWrites>;
 }
 
 /// ChunkContainer is used by the query engine to get chunks for a given table. Chunks will generally be in the
@@ -149,6 +332,43 @@ pub struct BufferedWriteRequest {
     pub line_count: usize,
     pub field_count: usize,
     pub index_count: usize,
+    /// The number of field values that were converted to the type of their column, as allowed by
+    /// the coercion policy of its table
+    pub coerced_count: usize,
+}
+
+/// The summary of a parquet file that was imported into a table
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ParquetImportSummary {
+    /// The number of rows that were read from the imported file
+    pub row_count: u64,
+    /// The number of rows that were persisted, once duplicates were removed
+    pub persisted_row_count: u64,
+    /// The number of parquet files that were persisted, one for each gen1 chunk of time
+    pub persisted_file_count: usize,
+    pub min_time: i64,
+    pub max_time: i64,
+    /// The sequence number of the snapshot that the persisted files are registered in
+    pub snapshot_sequence_number: SnapshotSequenceNumber,
+}
+
+/// Where a parquet file that is imported into a table is read from
+#[derive(Debug, Clone)]
+pub enum ParquetImportSource {
+    /// The contents of the file, as sent in a request
+    Bytes(Bytes),
+    /// The path of the file in the object store of the server, which is read as a stream rather
+    /// than all at once, for files that are too large to send in a request
+    ObjectStore(object_store::path::Path),
+}
+
+/// The limit of a quota of a database, and how much of it is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaUsage {
+    pub quota: Quota,
+    /// The limit of the quota, or `None` if it is unlimited
+    pub limit: Option<u64>,
+    pub usage: u64,
 }
 
 /// A persisted Catalog that contains the database, table, and column schemas.
@@ -188,6 +408,18 @@ pub struct PersistedSnapshot {
     /// The collection of databases that had tables persisted in this snapshot. The tables will then have their
     /// name and the parquet file.
     pub databases: HashMap<DbId, DatabaseTables>,
+    /// The deletes that were written to the wal files covered by this snapshot
+    #[serde(default)]
+    pub tombstones: Vec<Tombstone>,
+    /// The last wal file whose data is covered by this snapshot. WAL files after this one,
+    /// including the one that triggered the snapshot, still have to be replayed to rebuild the
+    /// buffer. This is 0 for snapshots persisted before it was recorded.
+    #[serde(default)]
+    pub last_wal_sequence_number: WalFileSequenceNumber,
+    /// The estimates of the series written to the databases that have a series quota, as of this
+    /// snapshot, so that the series count of a database is not lost on restart
+    #[serde(default)]
+    pub series: HashMap<DbId, SeriesSketch>,
 }
 
 impl PersistedSnapshot {
@@ -210,6 +442,9 @@ impl PersistedSnapshot {
             min_time: i64::MAX,
             max_time: i64::MIN,
             databases: HashMap::new(),
+            tombstones: vec![],
+            last_wal_sequence_number: WalFileSequenceNumber::default(),
+            series: HashMap::new(),
         }
     }
 
@@ -251,6 +486,16 @@ pub struct ParquetFile {
     pub chunk_time: i64,
     pub min_time: i64,
     pub max_time: i64,
+    /// The wal file sequence number of the snapshot that persisted this file, which is used to
+    /// determine the tombstones that apply to it. Files persisted before this was recorded have
+    /// the default of zero, and so have all tombstones applied.
+    #[serde(default)]
+    pub wal_file_sequence_number: WalFileSequenceNumber,
+    /// The ids of the columns in the file, by the names they have in it, which are used to find
+    /// the columns that have been renamed since it was persisted. Files persisted before this was
+    /// recorded are read with the current names of the columns.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub column_ids: BTreeMap<Arc<str>, ColumnId>,
 }
 
 impl ParquetFile {
@@ -262,6 +507,87 @@ impl ParquetFile {
     }
 }
 
+/// A delete of the rows that match a predicate, recorded so that it can be applied to the parquet
+/// files that were persisted before it when they are queried.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct Tombstone {
+    pub database_id: DbId,
+    pub table_id: TableId,
+    /// The wal file that the delete was written to. Parquet files persisted by snapshots up to and
+    /// including this wal file may contain rows that match the predicate, later files do not.
+    pub wal_file_sequence_number: WalFileSequenceNumber,
+    pub predicate: DeletePredicate,
+}
+
+impl Tombstone {
+    /// Returns true if the file may contain rows that were deleted by this tombstone
+    pub fn applies_to(&self, parquet_file: &ParquetFile) -> bool {
+        parquet_file.wal_file_sequence_number <= self.wal_file_sequence_number
+            && parquet_file.min_time <= self.predicate.max_time_ns
+            && parquet_file.max_time >= self.predicate.min_time_ns
+    }
+}
+
+/// The number of registers of a [`SeriesSketch`], as a power of two
+const SERIES_SKETCH_PRECISION: u32 = 12;
+
+/// A HyperLogLog estimate of the number of distinct series written to a database. It takes the
+/// same 4 KiB however many series are written, and counts of up to tens of thousands of series are
+/// estimated close to exactly. Adding a series that was already added does not change it.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct SeriesSketch {
+    registers: Vec<u8>,
+}
+
+impl Default for SeriesSketch {
+    fn default() -> Self {
+        Self {
+            registers: vec![0; 1 << SERIES_SKETCH_PRECISION],
+        }
+    }
+}
+
+impl SeriesSketch {
+    /// Adds the series with the hash, returning true if that changed the sketch
+    pub fn insert(&mut self, hash: u64) -> bool {
+        let index = (hash >> (64 - SERIES_SKETCH_PRECISION)) as usize;
+        let rank = ((hash << SERIES_SKETCH_PRECISION) | (1 << (SERIES_SKETCH_PRECISION - 1)))
+            .leading_zeros() as u8
+            + 1;
+        if self.registers[index] >= rank {
+            return false;
+        }
+        self.registers[index] = rank;
+        true
+    }
+
+    /// Adds the series that were added to the other sketch
+    pub fn merge(&mut self, other: &Self) {
+        for (register, other) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(*other);
+        }
+    }
+
+    /// The estimated number of distinct series that have been added
+    pub fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|r| 2f64.powi(-i32::from(*r)))
+            .sum();
+        let estimate = 0.7213 / (1.0 + 1.079 / m) * m * m / sum;
+        // small counts are estimated from the number of registers that are still empty
+        let estimate = if estimate <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            estimate
+        };
+        estimate.round() as u64
+    }
+}
+
 /// The precision of the timestamp
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -338,7 +664,7 @@ mod test_helpers {
         lp: &str,
     ) -> WriteBatch {
         let db_name = NamespaceName::new(db_name).unwrap();
-        let result = WriteValidator::initialize(db_name.clone(), catalog, 0)
+        let result = WriteValidator::initialize(db_name.clone(), Arc::clone(&catalog), 0)
             .unwrap()
             .v1_parse_lines_and_update_schema(lp, false)
             .unwrap()
@@ -347,6 +673,9 @@ mod test_helpers {
                 Gen1Duration::new_5m(),
                 Precision::Nanosecond,
             );
+        if let Some(catalog_batch) = &result.catalog_updates {
+            catalog.apply_catalog_batch(catalog_batch).unwrap();
+        }
 
         result.valid_data
     }
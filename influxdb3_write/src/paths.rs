@@ -12,6 +12,9 @@ pub const PARQUET_FILE_EXTENSION: &str = "parquet";
 /// File extension for snapshot info files
 pub const SNAPSHOT_INFO_FILE_EXTENSION: &str = "info.json";
 
+/// File extension for rejected writes files
+pub const REJECTED_WRITES_FILE_EXTENSION: &str = "ndjson";
+
 fn object_store_file_stem(n: u64) -> u64 {
     u64::MAX - n
 }
@@ -120,6 +123,37 @@ impl AsRef<ObjPath> for SnapshotInfoFilePath {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedWritesFilePath(ObjPath);
+
+impl RejectedWritesFilePath {
+    /// Generate the path of the file of rejected lines persisted at the given time. This will
+    /// convert the time into a date string with format `'YYYY-MM-DD'`
+    pub fn new(host_prefix: &str, time_ns: i64) -> Self {
+        let date_time = DateTime::<Utc>::from_timestamp_nanos(time_ns);
+        let path = ObjPath::from(format!(
+            "{host_prefix}/rejected_writes/{date_string}/{time_ns:020}.{ext}",
+            date_string = date_time.format("%Y-%m-%d"),
+            ext = REJECTED_WRITES_FILE_EXTENSION
+        ));
+        Self(path)
+    }
+}
+
+impl Deref for RejectedWritesFilePath {
+    type Target = ObjPath;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<ObjPath> for RejectedWritesFilePath {
+    fn as_ref(&self) -> &ObjPath {
+        &self.0
+    }
+}
+
 #[test]
 fn catalog_file_path_new() {
     assert_eq!(
@@ -175,3 +209,17 @@ fn snapshot_info_file_path_new() {
         ObjPath::from("my_host/snapshots/18446744073709551615.info.json")
     );
 }
+
+#[test]
+fn rejected_writes_file_path_new() {
+    assert_eq!(
+        *RejectedWritesFilePath::new(
+            "my_host",
+            Utc.with_ymd_and_hms(2038, 1, 19, 3, 14, 7)
+                .unwrap()
+                .timestamp_nanos_opt()
+                .unwrap(),
+        ),
+        ObjPath::from("my_host/rejected_writes/2038-01-19/02147483647000000000.ndjson")
+    );
+}
@@ -22,10 +22,12 @@ use futures_util::stream::StreamExt;
 use futures_util::stream::TryStreamExt;
 use influxdb3_catalog::catalog::Catalog;
 use influxdb3_catalog::catalog::InnerCatalog;
-use influxdb3_wal::WalFileSequenceNumber;
+use influxdb3_wal::object_store::{list_retained_wal_files, list_wal_files_with_retained};
+use influxdb3_wal::serialize::verify_file_type_and_deserialize;
+use influxdb3_wal::{WalFileSequenceNumber, WalOp};
 use object_store::path::Path as ObjPath;
 use object_store::ObjectStore;
-use observability_deps::tracing::info;
+use observability_deps::tracing::{info, warn};
 use parquet::arrow::ArrowWriter;
 use parquet::basic::Compression;
 use parquet::file::properties::WriterProperties;
@@ -58,6 +60,20 @@ pub enum Error {
 
     #[error("failed to initialize last cache: {0}")]
     InitializingLastCache(#[from] last_cache::Error),
+
+    #[error("wal error: {0}")]
+    Wal(#[from] influxdb3_wal::Error),
+
+    #[error(
+        "the newest catalog file can't be read, and the previous one, persisted for WAL file \
+        {catalog_wal_file_number}, can't be caught up with it because WAL file \
+        {missing_wal_file_number} is gone or invalid. Allow the catalog to fall back across the \
+        gap to load it without the changes made in the missing WAL files"
+    )]
+    CatalogFallbackAcrossWalGap {
+        catalog_wal_file_number: u64,
+        missing_wal_file_number: u64,
+    },
 }
 
 impl From<Error> for DataFusionError {
@@ -75,6 +91,9 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 pub const DEFAULT_OBJECT_STORE_URL: &str = "iox://influxdb3/";
 
+/// The number of the most recent catalog files that are kept when older ones are deleted
+pub const DEFAULT_CATALOG_FILES_TO_KEEP: usize = 10;
+
 /// The persister is the primary interface with object storage where InfluxDB stores all Parquet
 /// data, catalog information, as well as WAL and snapshot data.
 #[derive(Debug)]
@@ -88,6 +107,13 @@ pub struct Persister {
     /// Prefix used for all paths in the object store for this persister
     host_identifier_prefix: String,
     pub(crate) mem_pool: Arc<dyn MemoryPool>,
+    /// The number of the most recent catalog files to keep, older files are deleted after a new
+    /// catalog is persisted unless they are needed to restore from the retained WAL files. All
+    /// files are kept if this is zero.
+    catalog_files_to_keep: usize,
+    /// Whether a catalog that falls back to an older file is loaded even if WAL files needed to
+    /// catch it up are gone, in which case the changes made in them are missing from it
+    allow_catalog_fallback_across_wal_gap: bool,
 }
 
 impl Persister {
@@ -100,9 +126,24 @@ impl Persister {
             object_store,
             host_identifier_prefix: host_identifier_prefix.into(),
             mem_pool: Arc::new(UnboundedMemoryPool::default()),
+            catalog_files_to_keep: DEFAULT_CATALOG_FILES_TO_KEEP,
+            allow_catalog_fallback_across_wal_gap: false,
         }
     }
 
+    /// Sets the number of the most recent catalog files to keep, or keeps all of them if zero
+    pub fn with_catalog_files_to_keep(mut self, catalog_files_to_keep: usize) -> Self {
+        self.catalog_files_to_keep = catalog_files_to_keep;
+        self
+    }
+
+    /// Sets whether loading the catalog may fall back to an older file when WAL files needed to
+    /// catch it up are gone, rather than failing
+    pub fn with_catalog_fallback_across_wal_gap(mut self, allow: bool) -> Self {
+        self.allow_catalog_fallback_across_wal_gap = allow;
+        self
+    }
+
     /// Get the Object Store URL
     pub fn object_store_url(&self) -> &ObjectStoreUrl {
         &self.object_store_url
@@ -141,60 +182,210 @@ impl Persister {
 
     /// Loads the most recently persisted catalog from object storage.
     ///
-    /// This is used on server start.
+    /// This is used on server start. If the newest catalog file can't be read, the next newest
+    /// one that can is loaded instead, and the catalog ops in the WAL files persisted after it,
+    /// up to the newest one, are applied to it so that it catches up. Loading fails if any of
+    /// those WAL files are gone or invalid, unless falling back across the gap is allowed.
     pub async fn load_catalog(&self) -> Result<Option<PersistedCatalog>> {
+        let files = self.list_catalog_files().await?;
+        let newest = files.first().map(|(number, _)| *number);
+        let mut last_error = None;
+        for (wal_file_sequence_number, path) in files {
+            let mut catalog = match self.read_catalog_file(&path).await {
+                Ok(catalog) => catalog,
+                Err(e) => {
+                    warn!(%e, %path, "could not load catalog file, falling back to the previous one");
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+            if let Some(newest) = newest.filter(|newest| *newest != wal_file_sequence_number) {
+                self.apply_catalog_ops_from_wal_files(
+                    &mut catalog,
+                    wal_file_sequence_number,
+                    newest,
+                )
+                .await?;
+            }
+            return Ok(Some(PersistedCatalog {
+                wal_file_sequence_number,
+                catalog,
+            }));
+        }
+
+        match last_error {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+
+    /// Loads the most recent catalog that was persisted for a WAL file at or before the given one.
+    ///
+    /// This is used to restore the state of a host to an earlier point in time.
+    pub async fn load_catalog_at_or_before(
+        &self,
+        wal_file_sequence_number: WalFileSequenceNumber,
+    ) -> Result<Option<PersistedCatalog>> {
+        let found = self
+            .list_catalog_files()
+            .await?
+            .into_iter()
+            .find(|(number, _)| *number <= wal_file_sequence_number);
+
+        let Some((wal_file_sequence_number, path)) = found else {
+            return Ok(None);
+        };
+        Ok(Some(PersistedCatalog {
+            wal_file_sequence_number,
+            catalog: self.read_catalog_file(&path).await?,
+        }))
+    }
+
+    /// Lists the catalog files of the host along with the WAL file they were persisted for,
+    /// newest first
+    async fn list_catalog_files(&self) -> Result<Vec<(WalFileSequenceNumber, ObjPath)>> {
         let mut list = self
             .object_store
             .list(Some(&CatalogFilePath::dir(&self.host_identifier_prefix)));
-        let mut catalog_path: Option<ObjPath> = None;
+        let mut files = vec![];
         while let Some(item) = list.next().await {
             let item = item?;
-            catalog_path = match catalog_path {
-                Some(old_path) => {
-                    let Some(new_catalog_name) = item.location.filename() else {
-                        // Skip this iteration as this listed file has no
-                        // filename
-                        catalog_path = Some(old_path);
-                        continue;
-                    };
-                    let old_catalog_name = old_path
-                        .filename()
-                        // NOTE: this holds so long as CatalogFilePath is used
-                        // from crate::paths
-                        .expect("catalog file paths are guaranteed to have a filename");
-
-                    // We order catalogs by number starting with u32::MAX and
-                    // then decrease it, therefore if the new catalog file name
-                    // is less than the old one this is the path we want
-                    if new_catalog_name < old_catalog_name {
-                        Some(item.location)
-                    } else {
-                        Some(old_path)
-                    }
+            if let Some(number) = catalog_wal_file_number(&item.location) {
+                files.push((number, item.location));
+            }
+        }
+        files.sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+
+        Ok(files)
+    }
+
+    async fn read_catalog_file(&self, path: &ObjPath) -> Result<InnerCatalog> {
+        let bytes = self.object_store.get(path).await?.bytes().await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Applies the catalog ops in the WAL files after the given one to the catalog. WAL files
+    /// that a snapshot has covered are only around if they were retained, so changes made in
+    /// files that are gone would be missing from the catalog. Every file up to the one the newest
+    /// catalog was persisted for is needed, and it is an error if one is gone or invalid, unless
+    /// falling back across the gap is allowed.
+    async fn apply_catalog_ops_from_wal_files(
+        &self,
+        catalog: &mut InnerCatalog,
+        after: WalFileSequenceNumber,
+        newest: WalFileSequenceNumber,
+    ) -> Result<()> {
+        let wal_files: Vec<(WalFileSequenceNumber, ObjPath)> =
+            list_wal_files_with_retained(self.object_store.as_ref(), &self.host_identifier_prefix)
+                .await?
+                .into_iter()
+                .filter(|(number, _)| *number > after)
+                .collect();
+        let mut expected = after.next();
+        for (number, _) in &wal_files {
+            if *number > expected {
+                break;
+            }
+            expected = expected.next();
+        }
+        if expected <= newest {
+            self.fall_back_across_wal_gap(after, expected)?;
+        }
+
+        for (wal_file_number, path) in wal_files {
+            let bytes = self.object_store.get(&path).await?.bytes().await?;
+            let contents = match verify_file_type_and_deserialize(bytes) {
+                Ok(contents) => contents,
+                Err(e) if wal_file_number <= newest => {
+                    warn!(%e, %path, "invalid WAL file while catching up the catalog");
+                    self.fall_back_across_wal_gap(after, wal_file_number)?;
+                    continue;
+                }
+                Err(e) => {
+                    warn!(%e, %path, "skipping invalid WAL file while catching up the catalog");
+                    continue;
                 }
-                None => Some(item.location),
             };
+            for op in &contents.ops {
+                let WalOp::Catalog(catalog_batch) = op else {
+                    continue;
+                };
+                if let Err(e) = catalog.apply_catalog_batch(catalog_batch) {
+                    warn!(
+                        %e,
+                        wal_file_number = wal_file_number.as_u64(),
+                        "could not apply catalog batch while catching up the catalog"
+                    );
+                }
+            }
         }
+        info!(
+            catalog_wal_file_number = after.as_u64(),
+            "caught up the catalog with the WAL files persisted after it"
+        );
 
-        match catalog_path {
-            None => Ok(None),
-            Some(path) => {
-                let bytes = self.object_store.get(&path).await?.bytes().await?;
-                let catalog: InnerCatalog = serde_json::from_slice(&bytes)?;
-                let file_name = path
-                    .filename()
-                    // NOTE: this holds so long as CatalogFilePath is used
-                    // from crate::paths
-                    .expect("catalog file paths are guaranteed to have a filename");
-                let parsed_number = file_name
-                    .trim_end_matches(format!(".{}", crate::paths::CATALOG_FILE_EXTENSION).as_str())
-                    .parse::<u64>()?;
-                Ok(Some(PersistedCatalog {
-                    wal_file_sequence_number: WalFileSequenceNumber::new(u64::MAX - parsed_number),
-                    catalog,
-                }))
+        Ok(())
+    }
+
+    /// Fails to catch up the catalog persisted for the WAL file `after` because the WAL file
+    /// `missing` is gone or invalid, unless falling back across the gap is allowed, in which case
+    /// the changes made in it are left out
+    fn fall_back_across_wal_gap(
+        &self,
+        after: WalFileSequenceNumber,
+        missing: WalFileSequenceNumber,
+    ) -> Result<()> {
+        if !self.allow_catalog_fallback_across_wal_gap {
+            return Err(Error::CatalogFallbackAcrossWalGap {
+                catalog_wal_file_number: after.as_u64(),
+                missing_wal_file_number: missing.as_u64(),
+            });
+        }
+        warn!(
+            catalog_wal_file_number = after.as_u64(),
+            missing_wal_file_number = missing.as_u64(),
+            "WAL file after the loaded catalog is gone or invalid, changes to the catalog made in \
+            it are missing"
+        );
+        Ok(())
+    }
+
+    /// Deletes the catalog files older than the most recent ones that are kept. The newest file
+    /// persisted at or before the oldest retained WAL file is kept as well, along with those after
+    /// it, as a restore to a point within the retained WAL files starts from it. Returns the
+    /// number of files that were deleted.
+    pub async fn delete_old_catalog_files(&self) -> Result<usize> {
+        if self.catalog_files_to_keep == 0 {
+            return Ok(0);
+        }
+        let files = self.list_catalog_files().await?;
+        let mut keep = self.catalog_files_to_keep;
+        if let Some(oldest_retained) =
+            list_retained_wal_files(self.object_store.as_ref(), &self.host_identifier_prefix)
+                .await?
+                .first()
+        {
+            let oldest_retained = WalFileSequenceNumber::try_from(&oldest_retained.location)?;
+            if let Some(needed) = files
+                .iter()
+                .position(|(number, _)| *number <= oldest_retained)
+            {
+                keep = keep.max(needed + 1);
+            }
+        }
+
+        let mut deleted = 0;
+        for (_, path) in files.iter().skip(keep) {
+            match self.object_store.delete(path).await {
+                Ok(()) | Err(object_store::Error::NotFound { .. }) => deleted += 1,
+                Err(e) => return Err(e.into()),
             }
         }
+        if deleted > 0 {
+            info!(deleted, "deleted old catalog files");
+        }
+
+        Ok(deleted)
     }
 
     /// Loads the most recently persisted N snapshot parquet file lists from object storage.
@@ -323,6 +514,15 @@ impl Persister {
     }
 }
 
+/// Parses the WAL file sequence number from the path of a catalog file
+fn catalog_wal_file_number(path: &ObjPath) -> Option<WalFileSequenceNumber> {
+    path.filename()?
+        .strip_suffix(format!(".{}", crate::paths::CATALOG_FILE_EXTENSION).as_str())?
+        .parse::<u64>()
+        .ok()
+        .map(|n| WalFileSequenceNumber::new(u64::MAX - n))
+}
+
 pub async fn serialize_to_parquet(
     mem_pool: Arc<dyn MemoryPool>,
     batches: SendableRecordBatchStream,
@@ -417,10 +617,13 @@ mod tests {
     use crate::ParquetFileId;
     use influxdb3_catalog::catalog::SequenceNumber;
     use influxdb3_id::{DbId, TableId};
-    use influxdb3_wal::SnapshotSequenceNumber;
+    use influxdb3_wal::object_store::{retained_wal_path, wal_path};
+    use influxdb3_wal::serialize::serialize_to_bytes;
+    use influxdb3_wal::{SnapshotSequenceNumber, WalCompression, WalContents};
     use object_store::memory::InMemory;
     use observability_deps::tracing::info;
     use pretty_assertions::assert_eq;
+    use schema::InfluxFieldType;
     use {
         arrow::array::Int32Array, arrow::datatypes::DataType, arrow::datatypes::Field,
         arrow::datatypes::Schema, chrono::Utc,
@@ -483,6 +686,242 @@ mod tests {
         assert!(!catalog.catalog.db_exists(DbId::from(0)));
     }
 
+    #[tokio::test]
+    async fn load_catalog_at_or_before_wal_file() {
+        let persister = Persister::new(Arc::new(InMemory::new()), "test_host");
+        for (wal_file_number, db_name) in [(0, "first_db"), (3, "second_db"), (7, "third_db")] {
+            let catalog = Catalog::new(Arc::from("test_host"), Arc::from("instance"));
+            let _ = catalog.db_or_create(db_name);
+            persister
+                .persist_catalog(WalFileSequenceNumber::new(wal_file_number), &catalog)
+                .await
+                .unwrap();
+        }
+
+        for (wal_file_number, expected) in [(0, 0), (2, 0), (3, 3), (6, 3), (100, 7)] {
+            let persisted = persister
+                .load_catalog_at_or_before(WalFileSequenceNumber::new(wal_file_number))
+                .await
+                .unwrap()
+                .expect("there was a catalog to load");
+            assert_eq!(
+                persisted.wal_file_sequence_number,
+                WalFileSequenceNumber::new(expected)
+            );
+        }
+
+        let persister = Persister::new(Arc::new(InMemory::new()), "empty_host");
+        assert!(persister
+            .load_catalog_at_or_before(WalFileSequenceNumber::new(1))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn load_catalog_falls_back_to_previous_valid_file() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let persister = Persister::new(Arc::clone(&object_store), "test_host");
+        let catalog = Catalog::new(Arc::from("test_host"), Arc::from("instance"));
+        let _ = catalog.db_or_create("first_db");
+        persister
+            .persist_catalog(WalFileSequenceNumber::new(1), &catalog)
+            .await
+            .unwrap();
+
+        // the table is created in a WAL file after the older catalog, and the newer catalog that
+        // has it is truncated:
+        let create_table = catalog
+            .create_table(
+                "second_db",
+                "cpu",
+                &["host"],
+                &[("usage", InfluxFieldType::Float)],
+                0,
+            )
+            .unwrap();
+        let contents = WalContents {
+            min_timestamp_ns: 0,
+            max_timestamp_ns: 0,
+            wal_file_number: WalFileSequenceNumber::new(2),
+            persisted_time_ns: 0,
+            ops: vec![WalOp::Catalog(create_table)],
+            snapshot: None,
+        };
+        object_store
+            .put(
+                &wal_path("test_host", WalFileSequenceNumber::new(2)),
+                serialize_to_bytes(&contents, WalCompression::None)
+                    .unwrap()
+                    .into(),
+            )
+            .await
+            .unwrap();
+        let json = serde_json::to_vec_pretty(&catalog).unwrap();
+        object_store
+            .put(
+                CatalogFilePath::new("test_host", WalFileSequenceNumber::new(2)).as_ref(),
+                json[..json.len() / 2].to_vec().into(),
+            )
+            .await
+            .unwrap();
+
+        let persisted = persister
+            .load_catalog()
+            .await
+            .unwrap()
+            .expect("there was a catalog to load");
+        assert_eq!(
+            persisted.wal_file_sequence_number,
+            WalFileSequenceNumber::new(1)
+        );
+        let loaded = Catalog::from_inner(persisted.catalog);
+        assert!(loaded.db_schema("first_db").is_some());
+        assert!(loaded
+            .db_schema("second_db")
+            .and_then(|db| db.table_definition("cpu"))
+            .is_some());
+
+        // if no catalog file can be read, loading fails rather than starting over:
+        let persister = Persister::new(Arc::new(InMemory::new()), "test_host");
+        persister
+            .object_store
+            .put(
+                CatalogFilePath::new("test_host", WalFileSequenceNumber::new(0)).as_ref(),
+                Bytes::from_static(b"{").into(),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            persister.load_catalog().await,
+            Err(Error::SerdeJson(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn load_catalog_does_not_fall_back_across_a_wal_gap() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let persister = Persister::new(Arc::clone(&object_store), "test_host");
+        let catalog = Catalog::new(Arc::from("test_host"), Arc::from("instance"));
+        let _ = catalog.db_or_create("first_db");
+        persister
+            .persist_catalog(WalFileSequenceNumber::new(1), &catalog)
+            .await
+            .unwrap();
+
+        // the WAL file after the older catalog is gone, while a later one is still around, and
+        // the newer catalog is truncated:
+        let create_table = catalog
+            .create_table(
+                "second_db",
+                "cpu",
+                &["host"],
+                &[("usage", InfluxFieldType::Float)],
+                0,
+            )
+            .unwrap();
+        let contents = WalContents {
+            min_timestamp_ns: 0,
+            max_timestamp_ns: 0,
+            wal_file_number: WalFileSequenceNumber::new(3),
+            persisted_time_ns: 0,
+            ops: vec![WalOp::Catalog(create_table)],
+            snapshot: None,
+        };
+        object_store
+            .put(
+                &wal_path("test_host", WalFileSequenceNumber::new(3)),
+                serialize_to_bytes(&contents, WalCompression::None)
+                    .unwrap()
+                    .into(),
+            )
+            .await
+            .unwrap();
+        let json = serde_json::to_vec_pretty(&catalog).unwrap();
+        object_store
+            .put(
+                CatalogFilePath::new("test_host", WalFileSequenceNumber::new(3)).as_ref(),
+                json[..json.len() / 2].to_vec().into(),
+            )
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            persister.load_catalog().await,
+            Err(Error::CatalogFallbackAcrossWalGap {
+                catalog_wal_file_number: 1,
+                missing_wal_file_number: 2,
+            })
+        ));
+
+        // unless the operator allows it, in which case the files that are around are applied:
+        let persister = Persister::new(Arc::clone(&object_store), "test_host")
+            .with_catalog_fallback_across_wal_gap(true);
+        let persisted = persister
+            .load_catalog()
+            .await
+            .unwrap()
+            .expect("there was a catalog to load");
+        assert_eq!(
+            persisted.wal_file_sequence_number,
+            WalFileSequenceNumber::new(1)
+        );
+        let loaded = Catalog::from_inner(persisted.catalog);
+        assert!(loaded
+            .db_schema("second_db")
+            .and_then(|db| db.table_definition("cpu"))
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn delete_old_catalog_files() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let persister =
+            Persister::new(Arc::clone(&object_store), "test_host").with_catalog_files_to_keep(2);
+        let catalog = Catalog::new(Arc::from("test_host"), Arc::from("instance"));
+        for wal_file_number in [1, 3, 5, 7, 9] {
+            persister
+                .persist_catalog(WalFileSequenceNumber::new(wal_file_number), &catalog)
+                .await
+                .unwrap();
+        }
+
+        // a retained WAL file needs the catalog that was persisted at or before it:
+        object_store
+            .put(
+                &retained_wal_path("test_host", WalFileSequenceNumber::new(4)),
+                Bytes::from_static(b"wal").into(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(1, persister.delete_old_catalog_files().await.unwrap());
+        assert_eq!(vec![9, 7, 5, 3], catalog_file_numbers(&persister).await);
+
+        object_store
+            .delete(&retained_wal_path(
+                "test_host",
+                WalFileSequenceNumber::new(4),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(2, persister.delete_old_catalog_files().await.unwrap());
+        assert_eq!(vec![9, 7], catalog_file_numbers(&persister).await);
+
+        // all files are kept if none are to be deleted:
+        let persister = Persister::new(object_store, "test_host").with_catalog_files_to_keep(0);
+        assert_eq!(0, persister.delete_old_catalog_files().await.unwrap());
+    }
+
+    async fn catalog_file_numbers(persister: &Persister) -> Vec<u64> {
+        persister
+            .list_catalog_files()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(number, _)| number.as_u64())
+            .collect()
+    }
+
     #[tokio::test]
     async fn persist_snapshot_info_file() {
         let local_disk =
@@ -497,6 +936,8 @@ mod tests {
             wal_file_sequence_number: WalFileSequenceNumber::new(0),
             catalog_sequence_number: SequenceNumber::new(0),
             databases: HashMap::new(),
+            tombstones: vec![],
+            last_wal_sequence_number: WalFileSequenceNumber::new(0),
             min_time: 0,
             max_time: 1,
             row_count: 0,
@@ -520,6 +961,8 @@ mod tests {
             wal_file_sequence_number: WalFileSequenceNumber::new(0),
             catalog_sequence_number: SequenceNumber::default(),
             databases: HashMap::new(),
+            tombstones: vec![],
+            last_wal_sequence_number: WalFileSequenceNumber::new(0),
             min_time: 0,
             max_time: 1,
             row_count: 0,
@@ -534,6 +977,8 @@ mod tests {
             wal_file_sequence_number: WalFileSequenceNumber::new(1),
             catalog_sequence_number: SequenceNumber::default(),
             databases: HashMap::new(),
+            tombstones: vec![],
+            last_wal_sequence_number: WalFileSequenceNumber::new(0),
             max_time: 1,
             min_time: 0,
             row_count: 0,
@@ -548,6 +993,8 @@ mod tests {
             wal_file_sequence_number: WalFileSequenceNumber::new(2),
             catalog_sequence_number: SequenceNumber::default(),
             databases: HashMap::new(),
+            tombstones: vec![],
+            last_wal_sequence_number: WalFileSequenceNumber::new(0),
             min_time: 0,
             max_time: 1,
             row_count: 0,
@@ -583,6 +1030,8 @@ mod tests {
             wal_file_sequence_number: WalFileSequenceNumber::new(0),
             catalog_sequence_number: SequenceNumber::default(),
             databases: HashMap::new(),
+            tombstones: vec![],
+            last_wal_sequence_number: WalFileSequenceNumber::new(0),
             min_time: 0,
             max_time: 1,
             row_count: 0,
@@ -611,6 +1060,8 @@ mod tests {
                 wal_file_sequence_number: WalFileSequenceNumber::new(id),
                 catalog_sequence_number: SequenceNumber::new(id as u32),
                 databases: HashMap::new(),
+                tombstones: vec![],
+                last_wal_sequence_number: WalFileSequenceNumber::new(0),
                 min_time: 0,
                 max_time: 1,
                 row_count: 0,
@@ -658,6 +1109,8 @@ mod tests {
                 chunk_time: 5,
                 min_time: 0,
                 max_time: 1,
+                wal_file_sequence_number: WalFileSequenceNumber::new(0),
+                column_ids: Default::default(),
             },
         );
         persister.persist_snapshot(&info_file).await.unwrap();
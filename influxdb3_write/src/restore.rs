@@ -0,0 +1,342 @@
+//! Restores the state of a host as of an earlier point in time into a new host prefix. This is
+//! done offline, from the files the host left in object store: the snapshots and catalog persisted
+//! at or before the restore point, along with the WAL files after the last of those snapshots,
+//! which are kept after snapshotting if the host was started with a snapshotted WAL retention.
+//!
+//! The restored host is started by running the server with the new host identifier prefix, which
+//! loads the copied snapshots and catalog and replays the copied WAL files into its buffer.
+
+use crate::persister::Persister;
+use crate::PersistedSnapshot;
+use influxdb3_catalog::catalog::Catalog;
+use influxdb3_wal::object_store::{list_wal_files, list_wal_files_with_retained, wal_path};
+use influxdb3_wal::serialize::{serialize_to_bytes, verify_file_type_and_deserialize};
+use influxdb3_wal::{SnapshotSequenceNumber, WalCompression, WalContents, WalFileSequenceNumber};
+use iox_time::Time;
+use object_store::path::Path;
+use object_store::ObjectStore;
+use observability_deps::tracing::info;
+use std::sync::Arc;
+use thiserror::Error;
+use uuid::Uuid;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("host {0} already has a catalog or wal files, restore into a new host")]
+    TargetNotEmpty(String),
+
+    #[error("no wal file of host {host} was persisted at or before {time}")]
+    NoWalFileBefore { host: String, time: String },
+
+    #[error("wal file {0} is needed for the restore but is no longer retained")]
+    WalFileNotRetained(WalFileSequenceNumber),
+
+    #[error("object store error: {0}")]
+    ObjectStore(#[from] object_store::Error),
+
+    #[error("persister error: {0}")]
+    Persister(#[from] crate::persister::Error),
+
+    #[error("wal error: {0}")]
+    Wal(#[from] influxdb3_wal::Error),
+
+    #[error("invalid wal file: {0}")]
+    InvalidWalFile(#[from] influxdb3_wal::serialize::Error),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The point to restore the state of a host to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestorePoint {
+    /// Include the writes in WAL files up to and including this one
+    WalFile(WalFileSequenceNumber),
+    /// Include the writes in WAL files that were persisted at or before this time. WAL files
+    /// written by versions that did not record the time they were persisted are always included.
+    Time(Time),
+}
+
+/// What was copied to the new host by a restore
+#[derive(Debug, PartialEq, Eq)]
+pub struct RestoreSummary {
+    /// The last WAL file included in the restore
+    pub wal_file_number: WalFileSequenceNumber,
+    /// The last snapshot that was copied, if any
+    pub snapshot_sequence_number: Option<SnapshotSequenceNumber>,
+    /// The number of parquet files that were copied
+    pub parquet_files: usize,
+    /// The WAL files that were copied, which are replayed when the new host starts
+    pub wal_files: Vec<WalFileSequenceNumber>,
+}
+
+/// Restores the state of `source_host` as of the `until` point into `target_host`, which must not
+/// have been used yet.
+pub async fn restore(
+    object_store: Arc<dyn ObjectStore>,
+    source_host: &str,
+    target_host: &str,
+    until: RestorePoint,
+) -> Result<RestoreSummary> {
+    let source = Persister::new(Arc::clone(&object_store), source_host);
+    let target = Persister::new(Arc::clone(&object_store), target_host);
+    if target.load_catalog().await?.is_some()
+        || !list_wal_files(object_store.as_ref(), target_host)
+            .await?
+            .is_empty()
+    {
+        return Err(Error::TargetNotEmpty(target_host.to_string()));
+    }
+
+    let wal_files = list_wal_files_with_retained(object_store.as_ref(), source_host).await?;
+    let until = match until {
+        RestorePoint::WalFile(wal_file_number) => wal_file_number,
+        RestorePoint::Time(time) => {
+            last_wal_file_persisted_before(object_store.as_ref(), &wal_files, time)
+                .await?
+                .ok_or_else(|| Error::NoWalFileBefore {
+                    host: source_host.to_string(),
+                    time: time.to_rfc3339(),
+                })?
+        }
+    };
+
+    // the most recent snapshot that was triggered at or before the restore point, which the
+    // restored host is started from
+    let snapshots: Vec<PersistedSnapshot> = source
+        .load_snapshots(usize::MAX)
+        .await?
+        .into_iter()
+        .filter(|snapshot| snapshot.wal_file_sequence_number <= until)
+        .collect();
+    let last_snapshot = snapshots.first();
+
+    // the WAL files after those covered by the snapshot have to be replayed. Snapshots persisted
+    // before the last covered file was recorded need all of the files that are still around.
+    let (first_needed, first_replayed) = match last_snapshot {
+        Some(snapshot) if snapshot.last_wal_sequence_number.as_u64() > 0 => {
+            let first = snapshot.last_wal_sequence_number.next();
+            (first, first)
+        }
+        Some(snapshot) => (
+            snapshot.wal_file_sequence_number,
+            WalFileSequenceNumber::new(0),
+        ),
+        None => (WalFileSequenceNumber::new(1), WalFileSequenceNumber::new(0)),
+    };
+    let replay: Vec<(WalFileSequenceNumber, Path)> = wal_files
+        .into_iter()
+        .filter(|(n, _)| *n >= first_replayed && *n <= until)
+        .collect();
+    if first_needed <= until && replay.first().map_or(true, |(n, _)| *n > first_needed) {
+        return Err(Error::WalFileNotRetained(first_needed));
+    }
+
+    // copy the catalog that was persisted along with the snapshot, the ops in the replayed WAL
+    // files bring it up to the restore point
+    let (catalog_wal_file_number, catalog) = match source
+        .load_catalog_at_or_before(
+            last_snapshot
+                .map(|snapshot| snapshot.wal_file_sequence_number)
+                .unwrap_or_default(),
+        )
+        .await?
+    {
+        Some(persisted) => (
+            persisted.wal_file_sequence_number,
+            Catalog::from_inner(persisted.catalog),
+        ),
+        None => (
+            WalFileSequenceNumber::new(0),
+            Catalog::new(target_host.into(), "".into()),
+        ),
+    };
+    catalog.set_host_and_instance_id(target_host.into(), Uuid::new_v4().to_string().into());
+
+    // copy the snapshots and the parquet files in them, oldest first
+    let mut parquet_files = 0;
+    for mut snapshot in snapshots.iter().rev().cloned() {
+        snapshot.host_id = target_host.to_string();
+        for tables in snapshot.databases.values_mut() {
+            for files in tables.tables.values_mut() {
+                for file in files {
+                    let from = Path::from(file.path.as_str());
+                    let path = file
+                        .path
+                        .strip_prefix(source_host)
+                        .map(|rest| format!("{target_host}{rest}"))
+                        .unwrap_or_else(|| format!("{target_host}/{}", file.path));
+                    object_store.copy(&from, &Path::from(path.as_str())).await?;
+                    file.path = path;
+                    parquet_files += 1;
+                }
+            }
+        }
+        target.persist_snapshot(&snapshot).await?;
+    }
+
+    // copy the WAL files, without the snapshot markers, as their snapshots have either been
+    // copied already or were never persisted
+    let mut copied_wal_files = Vec::with_capacity(replay.len());
+    for (wal_file_number, path) in replay {
+        let bytes = object_store.get(&path).await?.bytes().await?;
+        let contents = WalContents {
+            snapshot: None,
+            ..verify_file_type_and_deserialize(bytes)?
+        };
+        let bytes = serialize_to_bytes(&contents, WalCompression::None)?;
+        object_store
+            .put(&wal_path(target_host, wal_file_number), bytes.into())
+            .await?;
+        copied_wal_files.push(wal_file_number);
+    }
+
+    // the catalog goes last, as it marks the target host as used
+    target
+        .persist_catalog(catalog_wal_file_number, &catalog)
+        .await?;
+
+    let summary = RestoreSummary {
+        wal_file_number: until,
+        snapshot_sequence_number: last_snapshot.map(|snapshot| snapshot.snapshot_sequence_number),
+        parquet_files,
+        wal_files: copied_wal_files,
+    };
+    info!(
+        source_host,
+        target_host,
+        ?summary,
+        "restored host to an earlier point in time"
+    );
+
+    Ok(summary)
+}
+
+/// Returns the last of the WAL files that was persisted at or before the given time
+async fn last_wal_file_persisted_before(
+    object_store: &dyn ObjectStore,
+    wal_files: &[(WalFileSequenceNumber, Path)],
+    time: Time,
+) -> Result<Option<WalFileSequenceNumber>> {
+    let mut last = None;
+    for (wal_file_number, path) in wal_files {
+        let bytes = object_store.get(path).await?.bytes().await?;
+        let contents = verify_file_type_and_deserialize(bytes)?;
+        if contents.persisted_time_ns > time.timestamp_nanos() {
+            break;
+        }
+        last = Some(*wal_file_number);
+    }
+
+    Ok(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use influxdb3_wal::object_store::retained_wal_path;
+    use object_store::memory::InMemory;
+    use pretty_assertions::assert_eq;
+
+    async fn put_wal_file(
+        object_store: &dyn ObjectStore,
+        path: Path,
+        wal_file_number: u64,
+        persisted_time_ns: i64,
+    ) {
+        let contents = WalContents {
+            min_timestamp_ns: 0,
+            max_timestamp_ns: 0,
+            wal_file_number: WalFileSequenceNumber::new(wal_file_number),
+            ops: vec![],
+            snapshot: None,
+            persisted_time_ns,
+        };
+        let bytes = serialize_to_bytes(&contents, WalCompression::None).unwrap();
+        object_store.put(&path, bytes.into()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn restore_to_time() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        for (n, persisted_time_ns) in [(1, 10), (2, 20)] {
+            let path = retained_wal_path("host", WalFileSequenceNumber::new(n));
+            put_wal_file(object_store.as_ref(), path, n, persisted_time_ns).await;
+        }
+        let path = wal_path("host", WalFileSequenceNumber::new(3));
+        put_wal_file(object_store.as_ref(), path, 3, 30).await;
+
+        let summary = restore(
+            Arc::clone(&object_store),
+            "host",
+            "restored",
+            RestorePoint::Time(Time::from_timestamp_nanos(25)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            summary,
+            RestoreSummary {
+                wal_file_number: WalFileSequenceNumber::new(2),
+                snapshot_sequence_number: None,
+                parquet_files: 0,
+                wal_files: vec![WalFileSequenceNumber::new(1), WalFileSequenceNumber::new(2)],
+            }
+        );
+        assert_eq!(
+            list_wal_files(object_store.as_ref(), "restored")
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|meta| meta.location)
+                .collect::<Vec<_>>(),
+            vec![
+                wal_path("restored", WalFileSequenceNumber::new(1)),
+                wal_path("restored", WalFileSequenceNumber::new(2)),
+            ]
+        );
+
+        // the restored host now has a catalog, so it can't be restored into again
+        let err = restore(
+            Arc::clone(&object_store),
+            "host",
+            "restored",
+            RestorePoint::Time(Time::from_timestamp_nanos(25)),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, Error::TargetNotEmpty(_)));
+
+        let err = restore(
+            Arc::clone(&object_store),
+            "host",
+            "too_early",
+            RestorePoint::Time(Time::from_timestamp_nanos(5)),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, Error::NoWalFileBefore { .. }));
+    }
+
+    #[tokio::test]
+    async fn restore_needs_the_wal_files_after_the_last_snapshot() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        for n in [2, 3] {
+            let path = wal_path("host", WalFileSequenceNumber::new(n));
+            put_wal_file(object_store.as_ref(), path, n, 0).await;
+        }
+
+        // without a snapshot, every wal file from the first is needed
+        let err = restore(
+            Arc::clone(&object_store),
+            "host",
+            "restored",
+            RestorePoint::WalFile(WalFileSequenceNumber::new(3)),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::WalFileNotRetained(n) if n == WalFileSequenceNumber::new(1)
+        ));
+    }
+}
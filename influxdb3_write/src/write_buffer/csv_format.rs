@@ -0,0 +1,766 @@
+//! Maps CSV to line protocol. Two kinds of CSV are supported: the annotated CSV of InfluxDB v2,
+//! whose annotations give the role of each column, and plain CSV with a header row, whose column
+//! roles are given with the write.
+//!
+//! Annotated CSV has annotation rows, which start with `#`, before the header row of each table in
+//! it. The `#datatype` annotation gives the role of each column, and the `#default` annotation the
+//! value used for its empty cells. Columns are mapped by name first, which covers CSV exported
+//! from a query:
+//!
+//! * `_measurement`, `_time`, and `_field` with `_value` are the measurement, time, and a field
+//! * `result`, `table`, `_start`, `_stop`, and unnamed columns are ignored
+//!
+//! and otherwise by their datatype:
+//!
+//! * `measurement`, `tag`, and `ignored` are the measurement, a tag, or ignored
+//! * `dateTime`, `dateTime:RFC3339`, `dateTime:RFC3339Nano`, and `dateTime:number` are the time
+//! * `double`, `long`, `unsignedLong`, `boolean`, and `string` are fields of that type
+//! * columns without a datatype, or with the `string` datatype, are tags if the `#group`
+//! annotation is `true` for them, as the tags of query results are in their group key, and
+//! otherwise string fields
+
+use super::mapped_lines::{
+    push_escaped, MappedLines, KEY_ESCAPES, MEASUREMENT_ESCAPES, STRING_ESCAPES,
+};
+use super::{Error, Result};
+use chrono::DateTime;
+use csv::StringRecord;
+use std::fmt::Write;
+use std::io::Read;
+use std::str::FromStr;
+
+/// The number of rows that are mapped and written at a time
+pub(crate) const CSV_WRITE_BATCH_ROWS: usize = 10_000;
+
+/// The roles of the columns of plain CSV, by their names in its header row. Columns without a role
+/// are ignored.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct CsvColumns {
+    /// The measurement of every row, if it is not given by `measurement_column`
+    pub measurement: Option<String>,
+    pub measurement_column: Option<String>,
+    pub tags: Vec<String>,
+    pub fields: Vec<(String, CsvFieldType)>,
+    /// The column with the time of each row. If not set, rows are written at the time of the
+    /// write.
+    pub time_column: Option<String>,
+    pub time_format: CsvTimeFormat,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CsvFieldType {
+    Float,
+    Integer,
+    UInteger,
+    String,
+    Boolean,
+}
+
+impl CsvFieldType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Float => "float",
+            Self::Integer => "integer",
+            Self::UInteger => "uinteger",
+            Self::String => "string",
+            Self::Boolean => "boolean",
+        }
+    }
+}
+
+impl std::fmt::Display for CsvFieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses the name of the type, or the datatype of annotated CSV that has the type
+impl FromStr for CsvFieldType {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "float" | "double" => Ok(Self::Float),
+            "integer" | "long" => Ok(Self::Integer),
+            "uinteger" | "unsignedLong" => Ok(Self::UInteger),
+            "string" => Ok(Self::String),
+            "boolean" => Ok(Self::Boolean),
+            _ => Err(format!("unknown field type {s}")),
+        }
+    }
+}
+
+/// The format of the times in CSV. The unix formats are numbers of the unit since the epoch.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum CsvTimeFormat {
+    #[default]
+    Rfc3339,
+    UnixSeconds,
+    UnixMillis,
+    UnixMicros,
+    UnixNanos,
+}
+
+impl CsvTimeFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Rfc3339 => "rfc3339",
+            Self::UnixSeconds => "unix_seconds",
+            Self::UnixMillis => "unix_millis",
+            Self::UnixMicros => "unix_micros",
+            Self::UnixNanos => "unix_nanos",
+        }
+    }
+}
+
+impl std::fmt::Display for CsvTimeFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CsvTimeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "rfc3339" => Ok(Self::Rfc3339),
+            "unix_seconds" => Ok(Self::UnixSeconds),
+            "unix_millis" => Ok(Self::UnixMillis),
+            "unix_micros" => Ok(Self::UnixMicros),
+            "unix_nanos" => Ok(Self::UnixNanos),
+            _ => Err(format!("unknown time format {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum ColumnRole {
+    Measurement,
+    Tag,
+    Field(CsvFieldType),
+    /// The name of the field whose value is in the `FieldValue` column
+    FieldName,
+    FieldValue(CsvFieldType),
+    Time(CsvTimeFormat),
+    Ignored,
+}
+
+/// The columns of the rows that follow a header row
+#[derive(Debug)]
+struct Table {
+    names: Vec<String>,
+    roles: Vec<ColumnRole>,
+    /// The values used for empty cells, by column
+    defaults: Vec<String>,
+    /// The measurement of rows that do not have one in a column
+    measurement: Option<String>,
+}
+
+/// The annotations of annotated CSV that are used to map its columns, by column
+#[derive(Debug, Default)]
+struct Annotations {
+    datatypes: Vec<String>,
+    groups: Vec<String>,
+    defaults: Vec<String>,
+}
+
+impl Annotations {
+    fn add(&mut self, record: &StringRecord) {
+        // the first column holds the name of the annotation, and in CSV that does not start with
+        // an unnamed column, the value of its first column after a space:
+        let first = record.get(0).unwrap_or_default().trim_start_matches('#');
+        let (name, first_value) = first.split_once(' ').unwrap_or((first, ""));
+        let values = std::iter::once(first_value)
+            .chain(record.iter().skip(1))
+            .map(|value| value.trim().to_string())
+            .collect();
+        match name.trim() {
+            "datatype" => self.datatypes = values,
+            "group" => self.groups = values,
+            "default" => self.defaults = values,
+            _ => (),
+        }
+    }
+}
+
+/// Reads the rows of CSV and maps them to line protocol in batches. The CSV is read as the rows
+/// are mapped, so a reader that streams it in does not need to hold all of it at once.
+#[derive(Debug)]
+pub(crate) struct CsvRows<R> {
+    reader: csv::Reader<R>,
+    record: StringRecord,
+    /// The roles of the columns of plain CSV, or `None` if the CSV is annotated
+    columns: Option<CsvColumns>,
+    /// Whether the first row has been checked to be an annotation, for annotated CSV, or not
+    first_row_checked: bool,
+    annotations: Annotations,
+    /// The columns of the rows being read, or `None` until the header row is read
+    table: Option<Table>,
+}
+
+impl<R: Read> CsvRows<R> {
+    pub(crate) fn new(csv: R, columns: Option<CsvColumns>) -> Result<Self> {
+        if let Some(columns) = &columns {
+            validate_columns(columns)?;
+        }
+
+        Ok(Self {
+            reader: csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_reader(csv),
+            record: StringRecord::new(),
+            columns,
+            first_row_checked: false,
+            annotations: Annotations::default(),
+            table: None,
+        })
+    }
+
+    /// The number of bytes of the CSV that have been read
+    pub(crate) fn bytes_read(&self) -> u64 {
+        self.reader.position().byte()
+    }
+
+    /// Maps up to the given number of rows, or returns `None` once all of them have been mapped.
+    /// Rows are numbered by their line.
+    pub(crate) fn next_batch(&mut self, max_rows: usize) -> Result<Option<MappedLines>> {
+        let mut mapped = MappedLines::default();
+        let mut rows = 0;
+        while rows < max_rows {
+            match self.reader.read_record(&mut self.record) {
+                Ok(true) => (),
+                Ok(false) => break,
+                // the rest of the CSV cannot be read:
+                Err(e) if matches!(e.kind(), csv::ErrorKind::Io(_)) => {
+                    return Err(Error::InvalidCsv(format!("failed to read the CSV: {e}")));
+                }
+                Err(e) => {
+                    let line_number = e.position().map(|p| p.line() as usize);
+                    mapped.push_error(
+                        line_number.unwrap_or_default(),
+                        String::new(),
+                        format!("invalid CSV: {e}"),
+                    );
+                    rows += 1;
+                    continue;
+                }
+            }
+
+            if !self.first_row_checked {
+                let annotated = self
+                    .record
+                    .get(0)
+                    .is_some_and(|c| c.trim_start().starts_with('#'));
+                match &self.columns {
+                    Some(_) if annotated => {
+                        return Err(Error::InvalidCsv(
+                            "column roles cannot be given for annotated CSV".to_string(),
+                        ))
+                    }
+                    None if !annotated => {
+                        return Err(Error::InvalidCsv(
+                            "the roles of the columns of plain CSV must be given".to_string(),
+                        ))
+                    }
+                    _ => self.first_row_checked = true,
+                }
+            }
+            if self.columns.is_none() && self.record.get(0).is_some_and(|c| c.starts_with('#')) {
+                // annotations after rows start a new table:
+                if self.table.take().is_some() {
+                    self.annotations = Annotations::default();
+                }
+                self.annotations.add(&self.record);
+                continue;
+            }
+            if self.table.is_none() {
+                self.table = Some(match &self.columns {
+                    Some(columns) => plain_table(&self.record, columns)?,
+                    None => annotated_table(&self.record, &self.annotations)?,
+                });
+                continue;
+            }
+
+            let table = self.table.as_ref().expect("the header row has been read");
+            let line_number = self
+                .record
+                .position()
+                .map(|p| p.line() as usize)
+                .unwrap_or_default();
+            let row = self.record.iter().collect::<Vec<_>>().join(",");
+            match map_row(table, &self.record) {
+                Ok(line) => mapped.push_line(line_number, row, &line),
+                Err(error_message) => mapped.push_error(line_number, row, error_message),
+            }
+            rows += 1;
+        }
+
+        Ok((rows > 0).then_some(mapped))
+    }
+}
+
+fn validate_columns(columns: &CsvColumns) -> Result<()> {
+    let invalid = |message: &str| Err(Error::InvalidCsv(message.to_string()));
+    match (&columns.measurement, &columns.measurement_column) {
+        (None, None) => invalid("plain CSV needs a measurement or a measurement column"),
+        (Some(_), Some(_)) => {
+            invalid("plain CSV cannot have both a measurement and a measurement column")
+        }
+        (Some(measurement), None) if measurement.is_empty() => {
+            invalid("the measurement cannot be empty")
+        }
+        _ if columns.fields.is_empty() => invalid("plain CSV needs at least one field column"),
+        _ => Ok(()),
+    }
+}
+
+fn plain_table(header: &StringRecord, columns: &CsvColumns) -> Result<Table> {
+    let names = header.iter().map(str::to_string).collect::<Vec<_>>();
+    let mut roles = vec![ColumnRole::Ignored; names.len()];
+    let mut assign = |name: &str, role: ColumnRole| -> Result<()> {
+        let i = names
+            .iter()
+            .position(|n| n == name)
+            .ok_or_else(|| Error::InvalidCsv(format!("column {name} is not in the header")))?;
+        if roles[i] != ColumnRole::Ignored {
+            return Err(Error::InvalidCsv(format!(
+                "column {name} is given more than one role"
+            )));
+        }
+        roles[i] = role;
+        Ok(())
+    };
+
+    if let Some(name) = &columns.measurement_column {
+        assign(name, ColumnRole::Measurement)?;
+    }
+    for name in &columns.tags {
+        assign(name, ColumnRole::Tag)?;
+    }
+    for (name, field_type) in &columns.fields {
+        assign(name, ColumnRole::Field(*field_type))?;
+    }
+    if let Some(name) = &columns.time_column {
+        assign(name, ColumnRole::Time(columns.time_format))?;
+    }
+
+    Ok(Table {
+        names,
+        roles,
+        defaults: vec![],
+        measurement: columns.measurement.clone(),
+    })
+}
+
+fn annotated_table(header: &StringRecord, annotations: &Annotations) -> Result<Table> {
+    let names = header
+        .iter()
+        .map(|name| name.trim().to_string())
+        .collect::<Vec<_>>();
+    let roles = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let datatype = annotations.datatypes.get(i).map(String::as_str);
+            let datatype = datatype.unwrap_or_default();
+            let invalid = |e: String| Error::InvalidCsv(format!("column {name}: {e}"));
+            Ok(match name.as_str() {
+                "" | "result" | "table" | "_start" | "_stop" => ColumnRole::Ignored,
+                "_measurement" => ColumnRole::Measurement,
+                "_field" => ColumnRole::FieldName,
+                "_time" => ColumnRole::Time(time_format(datatype).map_err(invalid)?),
+                "_value" if datatype.is_empty() => ColumnRole::FieldValue(CsvFieldType::String),
+                "_value" => ColumnRole::FieldValue(datatype.parse().map_err(invalid)?),
+                _ => match datatype {
+                    // the group key of query results has the tags:
+                    "" | "string" if annotations.groups.get(i).is_some_and(|g| g == "true") => {
+                        ColumnRole::Tag
+                    }
+                    "" => ColumnRole::Field(CsvFieldType::String),
+                    "measurement" => ColumnRole::Measurement,
+                    "tag" => ColumnRole::Tag,
+                    "ignored" => ColumnRole::Ignored,
+                    datatype if datatype.starts_with("dateTime") => {
+                        ColumnRole::Time(time_format(datatype).map_err(invalid)?)
+                    }
+                    datatype => ColumnRole::Field(datatype.parse().map_err(invalid)?),
+                },
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Table {
+        names,
+        roles,
+        defaults: annotations.defaults.clone(),
+        measurement: None,
+    })
+}
+
+/// The format of the times in a column with the given `dateTime` datatype
+fn time_format(datatype: &str) -> std::result::Result<CsvTimeFormat, String> {
+    match datatype {
+        "" | "dateTime" | "dateTime:RFC3339" | "dateTime:RFC3339Nano" => Ok(CsvTimeFormat::Rfc3339),
+        "dateTime:number" => Ok(CsvTimeFormat::UnixNanos),
+        _ => Err(format!("unsupported time datatype {datatype}")),
+    }
+}
+
+/// Maps a row to a line of line protocol, with its timestamp in nanoseconds
+fn map_row(table: &Table, record: &StringRecord) -> std::result::Result<String, String> {
+    let mut measurement = table.measurement.as_deref();
+    let mut tags = vec![];
+    let mut fields = vec![];
+    let mut field_name = None;
+    let mut field_value = None;
+    let mut time_ns = None;
+    for (i, (name, role)) in table.names.iter().zip(&table.roles).enumerate() {
+        let value = match record.get(i).unwrap_or_default() {
+            "" => table
+                .defaults
+                .get(i)
+                .map(String::as_str)
+                .unwrap_or_default(),
+            value => value,
+        };
+        match role {
+            ColumnRole::Time(format) if value.is_empty() => {
+                return Err(format!("the row has no time in column {name} ({format})"));
+            }
+            ColumnRole::Time(format) => {
+                time_ns = Some(timestamp_ns(value, *format).ok_or_else(|| {
+                    format!("the time {value} in column {name} is not in the {format} format")
+                })?);
+            }
+            _ if value.is_empty() => (),
+            ColumnRole::Measurement => measurement = Some(value),
+            ColumnRole::Tag => tags.push((name.as_str(), value)),
+            ColumnRole::Field(field_type) => fields.push((name.as_str(), *field_type, value)),
+            ColumnRole::FieldName => field_name = Some(value),
+            ColumnRole::FieldValue(field_type) => field_value = Some((*field_type, value)),
+            ColumnRole::Ignored => (),
+        }
+    }
+    if let (Some(name), Some((field_type, value))) = (field_name, field_value) {
+        fields.push((name, field_type, value));
+    }
+
+    let mut line = String::new();
+    let measurement = measurement.ok_or_else(|| "the row has no measurement".to_string())?;
+    push_escaped(&mut line, measurement, MEASUREMENT_ESCAPES);
+    for (tag, value) in tags {
+        line.push(',');
+        push_escaped(&mut line, tag, KEY_ESCAPES);
+        line.push('=');
+        push_escaped(&mut line, value, KEY_ESCAPES);
+    }
+    if fields.is_empty() {
+        return Err("the row has no field values".to_string());
+    }
+    for (i, (field, field_type, value)) in fields.into_iter().enumerate() {
+        line.push(if i == 0 { ' ' } else { ',' });
+        push_escaped(&mut line, field, KEY_ESCAPES);
+        line.push('=');
+        push_field_value(&mut line, value, field_type).ok_or_else(|| {
+            format!("the value {value} of field {field} is not a valid {field_type}")
+        })?;
+    }
+    if let Some(time_ns) = time_ns {
+        write!(line, " {time_ns}").expect("writing to a string does not fail");
+    }
+
+    if line.contains('\n') {
+        return Err("the row has a newline in its measurement, tags, or fields".to_string());
+    }
+
+    Ok(line)
+}
+
+/// Writes the value as a line protocol field value of the given type, or returns `None` if it is
+/// not a value of the type
+fn push_field_value(line: &mut String, value: &str, field_type: CsvFieldType) -> Option<()> {
+    match field_type {
+        CsvFieldType::Float => {
+            let n = value.parse::<f64>().ok().filter(|n| n.is_finite())?;
+            write!(line, "{n}").expect("writing to a string does not fail");
+        }
+        CsvFieldType::Integer => {
+            let n = value.parse::<i64>().ok()?;
+            write!(line, "{n}i").expect("writing to a string does not fail");
+        }
+        CsvFieldType::UInteger => {
+            let n = value.parse::<u64>().ok()?;
+            write!(line, "{n}u").expect("writing to a string does not fail");
+        }
+        CsvFieldType::String => {
+            line.push('"');
+            push_escaped(line, value, STRING_ESCAPES);
+            line.push('"');
+        }
+        CsvFieldType::Boolean => {
+            let b = if value.eq_ignore_ascii_case("true") {
+                true
+            } else if value.eq_ignore_ascii_case("false") {
+                false
+            } else {
+                return None;
+            };
+            write!(line, "{b}").expect("writing to a string does not fail");
+        }
+    }
+    Some(())
+}
+
+/// Converts the time to nanoseconds since the epoch, or returns `None` if it is not in the format
+/// or is out of range
+fn timestamp_ns(value: &str, format: CsvTimeFormat) -> Option<i64> {
+    let nanos_per_unit = match format {
+        CsvTimeFormat::Rfc3339 => {
+            return DateTime::parse_from_rfc3339(value)
+                .ok()
+                .and_then(|time| time.timestamp_nanos_opt());
+        }
+        CsvTimeFormat::UnixSeconds => 1_000_000_000,
+        CsvTimeFormat::UnixMillis => 1_000_000,
+        CsvTimeFormat::UnixMicros => 1_000,
+        CsvTimeFormat::UnixNanos => 1,
+    };
+    value
+        .parse::<i64>()
+        .ok()
+        .and_then(|time| time.checked_mul(nanos_per_unit))
+        .or_else(|| {
+            let time_ns = value.parse::<f64>().ok()? * nanos_per_unit as f64;
+            (time_ns.is_finite() && time_ns >= i64::MIN as f64 && time_ns < i64::MAX as f64)
+                .then(|| time_ns.round() as i64)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn map_all(csv: &str, columns: Option<CsvColumns>) -> Result<MappedLines> {
+        let mut rows = CsvRows::new(csv.as_bytes(), columns)?;
+        Ok(rows.next_batch(usize::MAX)?.unwrap_or_default())
+    }
+
+    fn errors(mapped: &MappedLines) -> Vec<(usize, &str)> {
+        mapped
+            .errors
+            .iter()
+            .map(|e| (e.line_number, e.error_message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn annotated_query_results() {
+        let csv = "\
+            #group,false,false,true,true,false,false,true,true,true\n\
+            #datatype,string,long,dateTime:RFC3339,dateTime:RFC3339,dateTime:RFC3339,double,string,string,string\n\
+            #default,_result,,,,,,,,\n\
+            ,result,table,_start,_stop,_time,_value,_field,_measurement,host\n\
+            ,,0,1970-01-01T00:00:00Z,1970-01-02T00:00:00Z,1970-01-01T00:00:01Z,0.5,usage,cpu,a b\n\
+            ,,0,1970-01-01T00:00:00Z,1970-01-02T00:00:00Z,1970-01-01T00:00:02Z,high,usage,cpu,a\n\
+            \n\
+            #group,false,false,true,false,false,true,true\n\
+            #datatype,string,long,dateTime:RFC3339,long,dateTime:number,string,string\n\
+            #default,_result,,,,,,\n\
+            ,result,table,_start,_value,_time,_field,_measurement\n\
+            ,,1,1970-01-01T00:00:00Z,10,3000000000,count,mem\n";
+        let mapped = map_all(csv, None).unwrap();
+
+        assert_eq!(
+            "cpu,host=a\\ b usage=0.5 1000000000\n\
+            mem count=10i 3000000000\n",
+            mapped.lp
+        );
+        assert_eq!(
+            vec![(6, "the value high of field usage is not a valid float")],
+            errors(&mapped)
+        );
+        assert_eq!(
+            ",,0,1970-01-01T00:00:00Z,1970-01-02T00:00:00Z,1970-01-01T00:00:02Z,high,usage,cpu,a",
+            mapped.errors[0].original_line
+        );
+    }
+
+    #[test]
+    fn annotated_datatypes() {
+        let csv = "\
+            #datatype measurement,tag,double,boolean,dateTime:number\n\
+            #default cpu,,,,\n\
+            m,host,usage,up,time\n\
+            ,a,0.5,true,1\n\
+            mem,b,1,FALSE,2\n\
+            cpu,c,,,3\n\
+            cpu,d,1,yes,4\n\
+            cpu,e,1,true,\n";
+        let mapped = map_all(csv, None).unwrap();
+
+        assert_eq!(
+            "cpu,host=a usage=0.5,up=true 1\n\
+            mem,host=b usage=1,up=false 2\n",
+            mapped.lp
+        );
+        assert_eq!(
+            vec![
+                (6, "the row has no field values"),
+                (7, "the value yes of field up is not a valid boolean"),
+                (8, "the row has no time in column time (unix_nanos)"),
+            ],
+            errors(&mapped)
+        );
+    }
+
+    #[test]
+    fn plain_csv_with_column_roles() {
+        let columns = CsvColumns {
+            measurement: Some("cpu".to_string()),
+            tags: vec!["host".to_string()],
+            fields: vec![
+                ("usage".to_string(), CsvFieldType::Float),
+                ("count".to_string(), CsvFieldType::UInteger),
+                ("note".to_string(), CsvFieldType::String),
+            ],
+            time_column: Some("time".to_string()),
+            time_format: CsvTimeFormat::UnixMillis,
+            ..Default::default()
+        };
+        let csv = "\
+            time,host,usage,count,note,ignored\n\
+            1000,a,0.5,1,\"say \"\"hi\"\"\",x\n\
+            2000,,1.5,,,x\n\
+            3000,b,,-1,,x\n\
+            later,c,1,,,x\n";
+        let mapped = map_all(csv, Some(columns)).unwrap();
+
+        assert_eq!(
+            "cpu,host=a usage=0.5,count=1u,note=\"say \\\"hi\\\"\" 1000000000\n\
+            cpu usage=1.5 2000000000\n",
+            mapped.lp
+        );
+        assert_eq!(
+            vec![
+                (4, "the value -1 of field count is not a valid uinteger"),
+                (
+                    5,
+                    "the time later in column time is not in the unix_millis format"
+                ),
+            ],
+            errors(&mapped)
+        );
+    }
+
+    #[test]
+    fn rows_are_mapped_in_batches() {
+        let columns = CsvColumns {
+            measurement_column: Some("m".to_string()),
+            fields: vec![("v".to_string(), CsvFieldType::Integer)],
+            ..Default::default()
+        };
+        let mut rows =
+            CsvRows::new("m,v\ncpu,1\ncpu,2\nmem,3\n".as_bytes(), Some(columns)).unwrap();
+
+        let batch = rows.next_batch(2).unwrap().unwrap();
+        assert_eq!("cpu v=1i\ncpu v=2i\n", batch.lp);
+        // errors for a batch are reported against the lines of its rows:
+        let error = batch.row_error(crate::WriteLineError {
+            original_line: "cpu v=2i".to_string(),
+            line_number: 2,
+            error_message: "invalid column type".to_string(),
+        });
+        assert_eq!(
+            (3, "cpu,2"),
+            (error.line_number, error.original_line.as_str())
+        );
+
+        let batch = rows.next_batch(2).unwrap().unwrap();
+        assert_eq!("mem v=3i\n", batch.lp);
+        assert!(rows.next_batch(2).unwrap().is_none());
+    }
+
+    #[test]
+    fn csv_that_cannot_be_read_fails_the_write() {
+        #[derive(Debug)]
+        struct HungUp;
+        impl Read for HungUp {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("the client hung up"))
+            }
+        }
+        let columns = CsvColumns {
+            measurement: Some("cpu".to_string()),
+            fields: vec![("v".to_string(), CsvFieldType::Integer)],
+            ..Default::default()
+        };
+        let mut rows = CsvRows::new("v\n1\n".as_bytes().chain(HungUp), Some(columns)).unwrap();
+
+        assert!(matches!(rows.next_batch(10), Err(Error::InvalidCsv(_))));
+    }
+
+    #[test]
+    fn invalid_csv_writes() {
+        let fields = vec![("usage".to_string(), CsvFieldType::Float)];
+        let test_cases = [
+            // plain CSV without column roles:
+            ("host,usage\na,1\n", None),
+            // annotated CSV with column roles:
+            (
+                "#datatype measurement,double\nm,usage\ncpu,1\n",
+                Some(CsvColumns {
+                    measurement: Some("cpu".to_string()),
+                    fields: fields.clone(),
+                    ..Default::default()
+                }),
+            ),
+            // no measurement:
+            (
+                "host,usage\na,1\n",
+                Some(CsvColumns {
+                    fields: fields.clone(),
+                    ..Default::default()
+                }),
+            ),
+            // no fields:
+            (
+                "host,usage\na,1\n",
+                Some(CsvColumns {
+                    measurement: Some("cpu".to_string()),
+                    ..Default::default()
+                }),
+            ),
+            // a column that is not in the header:
+            (
+                "host,usage\na,1\n",
+                Some(CsvColumns {
+                    measurement: Some("cpu".to_string()),
+                    tags: vec!["region".to_string()],
+                    fields: fields.clone(),
+                    ..Default::default()
+                }),
+            ),
+            // a column with two roles:
+            (
+                "host,usage\na,1\n",
+                Some(CsvColumns {
+                    measurement: Some("cpu".to_string()),
+                    tags: vec!["usage".to_string()],
+                    fields: fields.clone(),
+                    ..Default::default()
+                }),
+            ),
+            // an unsupported datatype:
+            ("#datatype measurement,duration\nm,usage\ncpu,1\n", None),
+        ];
+        for (i, (csv, columns)) in test_cases.into_iter().enumerate() {
+            assert!(
+                matches!(map_all(csv, columns), Err(Error::InvalidCsv(_))),
+                "test case ({i}) failed"
+            );
+        }
+    }
+}
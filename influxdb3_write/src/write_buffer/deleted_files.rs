@@ -0,0 +1,420 @@
+//! Cleans up the parquet files of databases and tables that have been deleted, and the files that
+//! only hold data older than the retention period of their database. Once a delete has been
+//! applied to the catalog, or the data in a file has expired, the file is no longer queried, so it
+//! is removed from object store in the background, along with its references in the persisted
+//! snapshots.
+
+use crate::persister::{Persister, Result};
+use crate::write_buffer::persisted_files::PersistedFiles;
+use crate::write_buffer::N_SNAPSHOTS_TO_LOAD_ON_START;
+use crate::{ParquetFile, PersistedSnapshot};
+use influxdb3_catalog::catalog::Catalog;
+use iox_time::TimeProvider;
+use object_store::path::Path as ObjPath;
+use observability_deps::tracing::{error, info};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Notify};
+
+/// How often the persisted snapshots are checked for files whose data has expired
+pub const EXPIRED_FILES_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug)]
+pub struct DeletedFilesCleaner {
+    catalog: Arc<Catalog>,
+    persister: Arc<Persister>,
+    persisted_files: Arc<PersistedFiles>,
+    time_provider: Arc<dyn TimeProvider>,
+    notify: Notify,
+}
+
+impl DeletedFilesCleaner {
+    pub fn new(
+        catalog: Arc<Catalog>,
+        persister: Arc<Persister>,
+        persisted_files: Arc<PersistedFiles>,
+        time_provider: Arc<dyn TimeProvider>,
+    ) -> Self {
+        Self {
+            catalog,
+            persister,
+            persisted_files,
+            time_provider,
+            notify: Notify::new(),
+        }
+    }
+
+    /// Create the cleaner and start its background task. All of the persisted snapshots are
+    /// cleaned on start, to finish any clean up interrupted by a restart, and again whenever the
+    /// cleaner is notified of a delete or a change of retention period, and periodically as data
+    /// expires. Each new snapshot is cleaned as it is persisted, as it may have been persisting
+    /// the data of a table while it was deleted.
+    pub fn new_with_background_task(
+        catalog: Arc<Catalog>,
+        persister: Arc<Persister>,
+        persisted_files: Arc<PersistedFiles>,
+        time_provider: Arc<dyn TimeProvider>,
+        persisted_snapshot_rx: watch::Receiver<Option<PersistedSnapshot>>,
+    ) -> Arc<Self> {
+        let cleaner = Arc::new(Self::new(
+            catalog,
+            persister,
+            persisted_files,
+            time_provider,
+        ));
+        cleaner.notify();
+        background_deleted_files_cleaner(Arc::clone(&cleaner), persisted_snapshot_rx);
+        cleaner
+    }
+
+    /// Notify the background task that a database or table was deleted, or that the retention
+    /// period of a database changed
+    pub fn notify(&self) {
+        self.notify.notify_one();
+    }
+
+    /// Remove the files of deleted databases and tables, and the files whose data has expired, from
+    /// the persisted snapshots and object store. Returns the number of parquet files that were
+    /// deleted.
+    pub async fn clean_snapshots(&self) -> Result<usize> {
+        let snapshots = self
+            .persister
+            .load_snapshots(N_SNAPSHOTS_TO_LOAD_ON_START)
+            .await?;
+        let mut deleted = 0;
+        for snapshot in snapshots {
+            deleted += self.clean_snapshot(snapshot).await?;
+        }
+        Ok(deleted)
+    }
+
+    /// Remove the files of deleted databases and tables, the files whose data has expired, and the
+    /// tombstones that no longer apply to any file, from a single snapshot. The snapshot is persisted without them before they are deleted, so
+    /// that if this fails part way through it never references files that no longer exist.
+    async fn clean_snapshot(&self, mut snapshot: PersistedSnapshot) -> Result<usize> {
+        let mut removed: Vec<ParquetFile> = vec![];
+        let now_ns = self.time_provider.now().timestamp_nanos();
+        snapshot.databases.retain(|db_id, db_tables| {
+            if self.catalog.db_is_deleted(*db_id) {
+                self.persisted_files.remove_database(*db_id);
+                removed.extend(db_tables.tables.drain().flat_map(|(_, files)| files));
+                return false;
+            }
+            let retention_cutoff_ns = self
+                .catalog
+                .db_schema_by_id(*db_id)
+                .and_then(|db_schema| db_schema.retention_cutoff_ns(now_ns));
+            if let Some(cutoff) = retention_cutoff_ns {
+                self.persisted_files.remove_expired_files(*db_id, cutoff);
+            }
+            db_tables.tables.retain(|table_id, files| {
+                if self.catalog.table_is_deleted(*table_id) {
+                    self.persisted_files.remove_table(*db_id, *table_id);
+                    removed.append(files);
+                    return false;
+                }
+                if let Some(cutoff) = retention_cutoff_ns {
+                    let (expired, kept): (Vec<_>, Vec<_>) =
+                        files.drain(..).partition(|f| f.max_time < cutoff);
+                    *files = kept;
+                    removed.extend(expired);
+                }
+                !files.is_empty()
+            });
+            true
+        });
+        // tombstones are dropped along with their table, and once no persisted file is old
+        // enough for them to apply to
+        let tombstone_count = snapshot.tombstones.len();
+        snapshot.tombstones.retain(|t| {
+            !self.catalog.db_is_deleted(t.database_id)
+                && !self.catalog.table_is_deleted(t.table_id)
+                && self.persisted_files.has_tombstone(t)
+        });
+
+        if removed.is_empty() && snapshot.tombstones.len() == tombstone_count {
+            return Ok(0);
+        }
+
+        for file in &removed {
+            snapshot.parquet_size_bytes =
+                snapshot.parquet_size_bytes.saturating_sub(file.size_bytes);
+            snapshot.row_count = snapshot.row_count.saturating_sub(file.row_count);
+        }
+        self.persister.persist_snapshot(&snapshot).await?;
+
+        let object_store = self.persister.object_store();
+        for file in &removed {
+            match object_store
+                .delete(&ObjPath::from(file.path.as_str()))
+                .await
+            {
+                Ok(()) | Err(object_store::Error::NotFound { .. }) => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if removed.is_empty() {
+            return Ok(0);
+        }
+        info!(
+            snapshot_sequence_number = snapshot.snapshot_sequence_number.as_u64(),
+            files = removed.len(),
+            "deleted parquet files of deleted databases and tables, and of expired data"
+        );
+
+        Ok(removed.len())
+    }
+}
+
+/// Cleans the persisted snapshots when notified of a delete and on an interval, and each snapshot
+/// as it is persisted
+fn background_deleted_files_cleaner(
+    cleaner: Arc<DeletedFilesCleaner>,
+    mut persisted_snapshot_rx: watch::Receiver<Option<PersistedSnapshot>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EXPIRED_FILES_CHECK_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // the first tick completes immediately, and the snapshots are already cleaned on start
+        interval.tick().await;
+        loop {
+            let result = tokio::select! {
+                _ = cleaner.notify.notified() => cleaner.clean_snapshots().await,
+                _ = interval.tick() => cleaner.clean_snapshots().await,
+                changed = persisted_snapshot_rx.changed() => {
+                    if changed.is_err() {
+                        info!("persisted snapshot channel closed, stopping deleted file cleaner");
+                        break;
+                    }
+                    let snapshot = persisted_snapshot_rx.borrow_and_update().clone();
+                    match snapshot {
+                        Some(snapshot) => cleaner.clean_snapshot(snapshot).await,
+                        None => Ok(0),
+                    }
+                }
+            };
+            if let Err(e) = result {
+                error!(%e, "error deleting the parquet files of deleted or expired data");
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParquetFileId, Tombstone};
+    use influxdb3_catalog::catalog::SequenceNumber;
+    use influxdb3_id::{DbId, TableId};
+    use influxdb3_wal::{
+        CatalogBatch, CatalogOp, DatabaseDelete, DeletePredicate, SnapshotSequenceNumber,
+        TableDelete, WalFileSequenceNumber,
+    };
+    use iox_time::{MockProvider, Time};
+    use object_store::memory::InMemory;
+    use object_store::ObjectStore;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn deletes_files_of_deleted_tables_and_databases() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let persister = Arc::new(Persister::new(Arc::clone(&object_store), "test_host"));
+        let catalog = Arc::new(Catalog::new("test_host".into(), "instance".into()));
+
+        // a snapshot with files for two tables in one database, and one in another:
+        let mut snapshot = PersistedSnapshot::new(
+            "test_host".to_string(),
+            SnapshotSequenceNumber::new(1),
+            WalFileSequenceNumber::new(1),
+            SequenceNumber::new(1),
+        );
+        for (db_id, table_id, path) in [
+            (0, 0, "test_host/dbs/foo-0/cpu-0/1.parquet"),
+            (0, 1, "test_host/dbs/foo-0/mem-1/1.parquet"),
+            (1, 2, "test_host/dbs/bar-1/cpu-2/1.parquet"),
+        ] {
+            object_store
+                .put(&ObjPath::from(path), "data".into())
+                .await
+                .unwrap();
+            snapshot.add_parquet_file(
+                DbId::from(db_id),
+                TableId::from(table_id),
+                ParquetFile {
+                    id: ParquetFileId::new(),
+                    path: path.to_string(),
+                    size_bytes: 4,
+                    row_count: 1,
+                    chunk_time: 0,
+                    min_time: 0,
+                    max_time: 0,
+                    wal_file_sequence_number: WalFileSequenceNumber::new(1),
+                    column_ids: Default::default(),
+                },
+            );
+        }
+        persister.persist_snapshot(&snapshot).await.unwrap();
+        let persisted_files =
+            Arc::new(PersistedFiles::new_from_persisted_snapshots(vec![snapshot]));
+        let cleaner = DeletedFilesCleaner::new(
+            Arc::clone(&catalog),
+            Arc::clone(&persister),
+            Arc::clone(&persisted_files),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+        );
+
+        // nothing is deleted yet:
+        assert_eq!(0, cleaner.clean_snapshots().await.unwrap());
+
+        catalog
+            .apply_catalog_batch(&CatalogBatch {
+                database_id: DbId::from(0),
+                database_name: "foo".into(),
+                time_ns: 0,
+                ops: vec![CatalogOp::DeleteTable(TableDelete {
+                    table_id: TableId::from(1),
+                    table_name: "mem".into(),
+                })],
+            })
+            .unwrap();
+        catalog
+            .apply_catalog_batch(&CatalogBatch {
+                database_id: DbId::from(1),
+                database_name: "bar".into(),
+                time_ns: 0,
+                ops: vec![CatalogOp::DeleteDatabase(DatabaseDelete {
+                    database_id: DbId::from(1),
+                    database_name: "bar".into(),
+                })],
+            })
+            .unwrap();
+        assert_eq!(2, cleaner.clean_snapshots().await.unwrap());
+
+        // only the file of the table that was not deleted is left, and referenced:
+        let snapshot = persister.load_snapshots(1).await.unwrap().pop().unwrap();
+        assert_eq!(
+            vec![DbId::from(0)],
+            snapshot.databases.keys().copied().collect::<Vec<_>>()
+        );
+        let tables = &snapshot.databases[&DbId::from(0)].tables;
+        assert_eq!(
+            vec![TableId::from(0)],
+            tables.keys().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(1, snapshot.row_count);
+        assert!(object_store
+            .head(&ObjPath::from("test_host/dbs/foo-0/cpu-0/1.parquet"))
+            .await
+            .is_ok());
+        for path in [
+            "test_host/dbs/foo-0/mem-1/1.parquet",
+            "test_host/dbs/bar-1/cpu-2/1.parquet",
+        ] {
+            assert!(matches!(
+                object_store.head(&ObjPath::from(path)).await,
+                Err(object_store::Error::NotFound { .. })
+            ));
+        }
+        assert!(persisted_files
+            .get_files(DbId::from(1), TableId::from(2))
+            .is_empty());
+
+        // cleaning again does nothing:
+        assert_eq!(0, cleaner.clean_snapshots().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn deletes_files_of_expired_data() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let persister = Arc::new(Persister::new(Arc::clone(&object_store), "test_host"));
+        let catalog = Arc::new(Catalog::new("test_host".into(), "instance".into()));
+        let hour_ns = 3_600_000_000_000;
+        let db_id = catalog
+            .create_database("foo", Some(hour_ns as u64), 0)
+            .unwrap()
+            .database_id;
+        let table_id = TableId::new();
+
+        // one file that holds data from more than an hour ago, and one that holds newer data:
+        let mut snapshot = PersistedSnapshot::new(
+            "test_host".to_string(),
+            SnapshotSequenceNumber::new(1),
+            WalFileSequenceNumber::new(1),
+            SequenceNumber::new(1),
+        );
+        for (path, max_time) in [
+            ("test_host/dbs/foo/cpu/old.parquet", hour_ns / 2),
+            ("test_host/dbs/foo/cpu/new.parquet", 2 * hour_ns),
+        ] {
+            object_store
+                .put(&ObjPath::from(path), "data".into())
+                .await
+                .unwrap();
+            snapshot.add_parquet_file(
+                db_id,
+                table_id,
+                ParquetFile {
+                    id: ParquetFileId::new(),
+                    path: path.to_string(),
+                    size_bytes: 4,
+                    row_count: 1,
+                    chunk_time: 0,
+                    min_time: 0,
+                    max_time,
+                    wal_file_sequence_number: WalFileSequenceNumber::new(1),
+                    column_ids: Default::default(),
+                },
+            );
+        }
+        // and a delete in the wal file that the snapshot covers, which applies to both files:
+        let tombstone = Tombstone {
+            database_id: db_id,
+            table_id,
+            wal_file_sequence_number: WalFileSequenceNumber::new(1),
+            predicate: DeletePredicate {
+                min_time_ns: 0,
+                max_time_ns: hour_ns,
+                tags: vec![],
+            },
+        };
+        snapshot.tombstones.push(tombstone.clone());
+        snapshot.last_wal_sequence_number = WalFileSequenceNumber::new(1);
+        persister.persist_snapshot(&snapshot).await.unwrap();
+        let persisted_files =
+            Arc::new(PersistedFiles::new_from_persisted_snapshots(vec![snapshot]));
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(hour_ns)));
+        let cleaner = DeletedFilesCleaner::new(
+            Arc::clone(&catalog),
+            Arc::clone(&persister),
+            Arc::clone(&persisted_files),
+            Arc::clone(&time_provider) as _,
+        );
+
+        // nothing has expired yet:
+        assert_eq!(0, cleaner.clean_snapshots().await.unwrap());
+
+        // the older file expires once its data is more than an hour old:
+        time_provider.set(Time::from_timestamp_nanos(2 * hour_ns));
+        assert_eq!(1, cleaner.clean_snapshots().await.unwrap());
+        let files = persisted_files.get_files(db_id, table_id);
+        assert_eq!(1, files.len());
+        assert_eq!("test_host/dbs/foo/cpu/new.parquet", files[0].path);
+        let snapshot = persister.load_snapshots(1).await.unwrap().pop().unwrap();
+        assert_eq!(1, snapshot.databases[&db_id].tables[&table_id].len());
+        assert!(matches!(
+            object_store
+                .head(&ObjPath::from("test_host/dbs/foo/cpu/old.parquet"))
+                .await,
+            Err(object_store::Error::NotFound { .. })
+        ));
+        // the tombstone is kept while the newer file is left:
+        assert_eq!(vec![tombstone.clone()], snapshot.tombstones);
+
+        // and dropped once that file has expired as well:
+        time_provider.set(Time::from_timestamp_nanos(4 * hour_ns));
+        assert_eq!(1, cleaner.clean_snapshots().await.unwrap());
+        assert!(persisted_files.get_tombstones(db_id, table_id).is_empty());
+        let snapshot = persister.load_snapshots(1).await.unwrap().pop().unwrap();
+        assert!(snapshot.tombstones.is_empty());
+    }
+}
@@ -0,0 +1,474 @@
+//! Maps JSON documents to line protocol with a [`JsonMapping`], so that they are validated against
+//! the catalog and buffered in the same way as line protocol writes.
+
+use super::mapped_lines::{
+    push_escaped, MappedLines, KEY_ESCAPES, MEASUREMENT_ESCAPES, STRING_ESCAPES,
+};
+use super::{Error, Result};
+use crate::WriteLineError;
+use chrono::DateTime;
+use influxdb3_wal::{JsonFieldType, JsonMapping, JsonTimestampFormat};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// Checks that documents can be mapped to rows with the mapping
+pub(crate) fn validate_mapping(mapping: &JsonMapping) -> Result<()> {
+    let invalid = |message: String| Err(Error::InvalidJsonMapping(message));
+    if mapping.fields.is_empty() {
+        return invalid("the mapping has no fields".to_string());
+    }
+    let paths = mapping
+        .measurement_path
+        .iter()
+        .chain(mapping.tags.values())
+        .chain(mapping.fields.values().map(|field| &field.path))
+        .chain(mapping.timestamp.iter().map(|timestamp| &timestamp.path));
+    for path in paths {
+        if path.split('.').any(str::is_empty) {
+            return invalid(format!("the path '{path}' is not valid"));
+        }
+    }
+    for name in mapping.tags.keys().chain(mapping.fields.keys()) {
+        if name.is_empty() {
+            return invalid("tag and field names cannot be empty".to_string());
+        }
+        if mapping.tags.contains_key(name) && mapping.fields.contains_key(name) {
+            return invalid(format!("{name} is mapped as both a tag and a field"));
+        }
+    }
+    Ok(())
+}
+
+/// Maps the documents in the body of a JSON write, which is either a single document, an array of
+/// documents, or newline-delimited documents. Documents without a measurement path in the mapping
+/// are written to the given table. The documents are numbered from one, except for
+/// newline-delimited documents, which are numbered by their line.
+pub(crate) fn map_documents(
+    body: &str,
+    mapping: &JsonMapping,
+    table: Option<&str>,
+) -> Result<MappedLines> {
+    validate_mapping(mapping)?;
+    if mapping.measurement_path.is_none() && table.is_none() {
+        return Err(Error::InvalidJsonMapping(
+            "the mapping has no measurement path, so the write must name a table".to_string(),
+        ));
+    }
+
+    let documents: Vec<(usize, std::result::Result<Value, WriteLineError>)> =
+        if body.trim_start().starts_with('[') {
+            serde_json::from_str::<Vec<Value>>(body)
+                .map_err(Error::InvalidJson)?
+                .into_iter()
+                .enumerate()
+                .map(|(i, document)| (i + 1, Ok(document)))
+                .collect()
+        } else if let Ok(document) = serde_json::from_str::<Value>(body) {
+            vec![(1, Ok(document))]
+        } else {
+            body.lines()
+                .enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(i, line)| {
+                    let document = serde_json::from_str(line).map_err(|e| WriteLineError {
+                        original_line: line.trim().to_string(),
+                        line_number: i + 1,
+                        error_message: format!("invalid JSON: {e}"),
+                    });
+                    (i + 1, document)
+                })
+                .collect()
+        };
+
+    let mut mapped = MappedLines::default();
+    for (number, document) in documents {
+        let document = match document {
+            Ok(document) => document,
+            Err(error) => {
+                mapped.errors.push(error);
+                continue;
+            }
+        };
+        match map_document(&document, mapping, table) {
+            Ok(line) => mapped.push_line(number, document.to_string(), &line),
+            Err(error_message) => mapped.push_error(number, document.to_string(), error_message),
+        }
+    }
+
+    Ok(mapped)
+}
+
+/// Maps a document to a line of line protocol, with its timestamp in nanoseconds
+fn map_document(
+    document: &Value,
+    mapping: &JsonMapping,
+    table: Option<&str>,
+) -> std::result::Result<String, String> {
+    if !document.is_object() {
+        return Err("the document is not a JSON object".to_string());
+    }
+
+    let mut line = String::new();
+    let measurement = match &mapping.measurement_path {
+        Some(path) => match lookup(document, path) {
+            Some(Value::String(measurement)) if !measurement.is_empty() => measurement.as_str(),
+            _ => return Err(format!("no measurement was found at path '{path}'")),
+        },
+        None => table.expect("documents without a measurement path are written to the table"),
+    };
+    push_escaped(&mut line, measurement, MEASUREMENT_ESCAPES);
+
+    for (tag, path) in &mapping.tags {
+        let value = match lookup(document, path) {
+            None | Some(Value::Null) => continue,
+            Some(Value::String(value)) => Cow::Borrowed(value.as_str()),
+            Some(value @ (Value::Number(_) | Value::Bool(_))) => Cow::Owned(value.to_string()),
+            Some(_) => {
+                return Err(format!(
+                    "the value of tag {tag} at path '{path}' is not a string, number, or boolean"
+                ))
+            }
+        };
+        // line protocol has no empty tag values, so these are the same as a missing tag:
+        if value.is_empty() {
+            continue;
+        }
+        line.push(',');
+        push_escaped(&mut line, tag, KEY_ESCAPES);
+        line.push('=');
+        push_escaped(&mut line, &value, KEY_ESCAPES);
+    }
+
+    let mut separator = ' ';
+    for (field, field_mapping) in &mapping.fields {
+        let Some(value) = lookup(document, &field_mapping.path).filter(|value| !value.is_null())
+        else {
+            continue;
+        };
+        line.push(separator);
+        separator = ',';
+        push_escaped(&mut line, field, KEY_ESCAPES);
+        line.push('=');
+        push_field_value(&mut line, value, field_mapping.field_type).map_err(|e| {
+            format!(
+                "the value of field {field} at path '{}' {e}",
+                field_mapping.path
+            )
+        })?;
+    }
+    if separator == ' ' {
+        return Err("none of the fields in the mapping were found in the document".to_string());
+    }
+
+    if let Some(timestamp) = &mapping.timestamp {
+        let time_ns = lookup(document, &timestamp.path)
+            .and_then(|value| timestamp_ns(value, timestamp.format))
+            .ok_or_else(|| {
+                format!(
+                    "no timestamp in the {} format was found at path '{}'",
+                    timestamp_format_name(timestamp.format),
+                    timestamp.path
+                )
+            })?;
+        write!(line, " {time_ns}").expect("writing to a string does not fail");
+    }
+
+    if line.contains('\n') {
+        return Err("the document has a newline in its measurement, tags, or fields".to_string());
+    }
+
+    Ok(line)
+}
+
+/// Finds the value at a path of object keys and array indexes separated by dots
+fn lookup<'a>(document: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(document, |value, key| match value {
+            Value::Object(map) => map.get(key),
+            Value::Array(values) => key.parse::<usize>().ok().and_then(|i| values.get(i)),
+            _ => None,
+        })
+}
+
+/// Writes the value as a line protocol field value of the given type, or of the type of the value
+/// if none is given, in which case numbers are floats. The error describes why the value could not
+/// be written.
+fn push_field_value(
+    line: &mut String,
+    value: &Value,
+    field_type: Option<JsonFieldType>,
+) -> std::result::Result<(), String> {
+    let mismatch = |field_type| Err(format!("cannot be written as a field of type {field_type}"));
+    match (field_type, value) {
+        (_, Value::Object(_) | Value::Array(_)) => {
+            return Err("is not a string, number, or boolean".to_string())
+        }
+        (None | Some(JsonFieldType::Float), Value::Number(n)) => {
+            let Some(n) = n.as_f64() else {
+                return mismatch("float");
+            };
+            write!(line, "{n}").expect("writing to a string does not fail");
+        }
+        (Some(JsonFieldType::Float), Value::String(s)) => {
+            match s.parse::<f64>().ok().filter(|n| n.is_finite()) {
+                Some(n) => write!(line, "{n}").expect("writing to a string does not fail"),
+                None => return mismatch("float"),
+            }
+        }
+        (Some(JsonFieldType::Integer), Value::Number(n)) => {
+            let integer = n.as_i64().or_else(|| {
+                n.as_f64()
+                    .filter(|n| n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64)
+                    .map(|n| n as i64)
+            });
+            match integer {
+                Some(n) => write!(line, "{n}i").expect("writing to a string does not fail"),
+                None => return mismatch("integer"),
+            }
+        }
+        (Some(JsonFieldType::Integer), Value::String(s)) => match s.parse::<i64>() {
+            Ok(n) => write!(line, "{n}i").expect("writing to a string does not fail"),
+            Err(_) => return mismatch("integer"),
+        },
+        (Some(JsonFieldType::UInteger), Value::Number(n)) => {
+            let uinteger = n.as_u64().or_else(|| {
+                n.as_f64()
+                    .filter(|n| n.fract() == 0.0 && *n >= 0.0 && *n < u64::MAX as f64)
+                    .map(|n| n as u64)
+            });
+            match uinteger {
+                Some(n) => write!(line, "{n}u").expect("writing to a string does not fail"),
+                None => return mismatch("uinteger"),
+            }
+        }
+        (Some(JsonFieldType::UInteger), Value::String(s)) => match s.parse::<u64>() {
+            Ok(n) => write!(line, "{n}u").expect("writing to a string does not fail"),
+            Err(_) => return mismatch("uinteger"),
+        },
+        (None | Some(JsonFieldType::String), Value::String(s)) => {
+            line.push('"');
+            push_escaped(line, s, STRING_ESCAPES);
+            line.push('"');
+        }
+        (Some(JsonFieldType::String), Value::Number(_) | Value::Bool(_)) => {
+            write!(line, "\"{value}\"").expect("writing to a string does not fail");
+        }
+        (None | Some(JsonFieldType::Boolean), Value::Bool(b)) => {
+            write!(line, "{b}").expect("writing to a string does not fail");
+        }
+        (Some(JsonFieldType::Boolean), Value::String(s)) => match s.parse::<bool>() {
+            Ok(b) => write!(line, "{b}").expect("writing to a string does not fail"),
+            Err(_) => return mismatch("boolean"),
+        },
+        (Some(JsonFieldType::Float), Value::Bool(_)) => return mismatch("float"),
+        (Some(JsonFieldType::Integer), Value::Bool(_)) => return mismatch("integer"),
+        (Some(JsonFieldType::UInteger), Value::Bool(_)) => return mismatch("uinteger"),
+        (Some(JsonFieldType::Boolean), Value::Number(_)) => return mismatch("boolean"),
+        (_, Value::Null) => unreachable!("null values are skipped"),
+    }
+    Ok(())
+}
+
+/// Converts the timestamp to nanoseconds since the epoch, or returns `None` if it is not in the
+/// format or is out of range
+fn timestamp_ns(value: &Value, format: JsonTimestampFormat) -> Option<i64> {
+    let units_per_second = match format {
+        JsonTimestampFormat::Rfc3339 => {
+            return value
+                .as_str()
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                .and_then(|time| time.timestamp_nanos_opt());
+        }
+        JsonTimestampFormat::UnixSeconds => 1,
+        JsonTimestampFormat::UnixMillis => 1_000,
+        JsonTimestampFormat::UnixMicros => 1_000_000,
+        JsonTimestampFormat::UnixNanos => 1_000_000_000,
+    };
+    let nanos_per_unit = 1_000_000_000 / units_per_second;
+    let from_float = |time: f64| {
+        let time_ns = time * nanos_per_unit as f64;
+        (time_ns.is_finite() && time_ns >= i64::MIN as f64 && time_ns < i64::MAX as f64)
+            .then(|| time_ns.round() as i64)
+    };
+    match value {
+        Value::Number(n) => n
+            .as_i64()
+            .and_then(|time| time.checked_mul(nanos_per_unit))
+            .or_else(|| n.as_f64().and_then(from_float)),
+        Value::String(s) => s
+            .parse::<i64>()
+            .ok()
+            .and_then(|time| time.checked_mul(nanos_per_unit))
+            .or_else(|| s.parse::<f64>().ok().and_then(from_float)),
+        _ => None,
+    }
+}
+
+fn timestamp_format_name(format: JsonTimestampFormat) -> &'static str {
+    match format {
+        JsonTimestampFormat::Rfc3339 => "rfc3339",
+        JsonTimestampFormat::UnixSeconds => "unix_seconds",
+        JsonTimestampFormat::UnixMillis => "unix_millis",
+        JsonTimestampFormat::UnixMicros => "unix_micros",
+        JsonTimestampFormat::UnixNanos => "unix_nanos",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use influxdb3_wal::{JsonFieldMapping, JsonTimestampMapping};
+    use pretty_assertions::assert_eq;
+
+    fn mapping() -> JsonMapping {
+        JsonMapping {
+            measurement_path: None,
+            tags: [
+                ("host".to_string(), "meta.host".to_string()),
+                ("region".to_string(), "meta.region".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+            fields: [
+                ("usage".to_string(), "readings.0", None),
+                ("count".to_string(), "count", Some(JsonFieldType::Integer)),
+                ("note".to_string(), "note", None),
+            ]
+            .into_iter()
+            .map(|(name, path, field_type)| {
+                (
+                    name,
+                    JsonFieldMapping {
+                        path: path.to_string(),
+                        field_type,
+                    },
+                )
+            })
+            .collect(),
+            timestamp: Some(JsonTimestampMapping {
+                path: "time".to_string(),
+                format: JsonTimestampFormat::UnixSeconds,
+            }),
+        }
+    }
+
+    #[test]
+    fn maps_array_of_documents() {
+        let body = r#"[
+            {"meta": {"host": "a b", "region": "us"}, "readings": [0.5], "count": 3, "time": 1},
+            {"meta": {"host": "c"}, "readings": [1], "note": "say \"hi\"", "time": "2"},
+            {"meta": {"host": "d"}, "readings": [], "time": 3},
+            {"meta": {"host": "e"}, "count": "x", "time": 4},
+            {"meta": {"host": "f"}, "count": 1}
+        ]"#;
+        let mapped = map_documents(body, &mapping(), Some("cpu")).unwrap();
+
+        assert_eq!(
+            "cpu,host=a\\ b,region=us count=3i,usage=0.5 1000000000\n\
+            cpu,host=c note=\"say \\\"hi\\\"\",usage=1 2000000000\n",
+            mapped.lp
+        );
+        assert_eq!(
+            vec![
+                (
+                    3,
+                    "none of the fields in the mapping were found in the document".to_string()
+                ),
+                (
+                    4,
+                    "the value of field count at path 'count' cannot be written as a field of \
+                    type integer"
+                        .to_string()
+                ),
+                (
+                    5,
+                    "no timestamp in the unix_seconds format was found at path 'time'".to_string()
+                ),
+            ],
+            mapped
+                .errors
+                .iter()
+                .map(|e| (e.line_number, e.error_message.clone()))
+                .collect::<Vec<_>>()
+        );
+
+        // errors for the mapped lines are reported against their documents:
+        let error = mapped.row_error(WriteLineError {
+            original_line: "cpu,host=c ...".to_string(),
+            line_number: 2,
+            error_message: "invalid column type".to_string(),
+        });
+        assert_eq!(2, error.line_number);
+        assert!(error.original_line.starts_with('{'));
+    }
+
+    #[test]
+    fn maps_single_and_newline_delimited_documents() {
+        let mut mapping = mapping();
+        mapping.measurement_path = Some("kind".to_string());
+        mapping.timestamp = Some(JsonTimestampMapping {
+            path: "time".to_string(),
+            format: JsonTimestampFormat::Rfc3339,
+        });
+
+        let single = r#"{
+            "kind": "mem",
+            "count": 10,
+            "time": "1970-01-01T00:00:01Z"
+        }"#;
+        let mapped = map_documents(single, &mapping, None).unwrap();
+        assert_eq!("mem count=10i 1000000000\n", mapped.lp);
+        assert!(mapped.errors.is_empty());
+
+        let ndjson = "{\"kind\": \"mem\", \"count\": 1, \"time\": \"1970-01-01T00:00:00Z\"}\n\
+            \n\
+            {\"kind\": \"mem\", \"count\": \n\
+            {\"count\": 2, \"time\": \"1970-01-01T00:00:00Z\"}\n";
+        let mapped = map_documents(ndjson, &mapping, None).unwrap();
+        assert_eq!("mem count=1i 0\n", mapped.lp);
+        assert_eq!(
+            vec![3, 4],
+            mapped
+                .errors
+                .iter()
+                .map(|e| e.line_number)
+                .collect::<Vec<_>>()
+        );
+        assert!(mapped.errors[0].error_message.starts_with("invalid JSON"));
+        assert_eq!(
+            "no measurement was found at path 'kind'",
+            mapped.errors[1].error_message
+        );
+    }
+
+    #[test]
+    fn invalid_mappings_and_bodies() {
+        let mut no_fields = mapping();
+        no_fields.fields.clear();
+        assert!(matches!(
+            map_documents("{}", &no_fields, Some("cpu")),
+            Err(Error::InvalidJsonMapping(_))
+        ));
+
+        let mut tag_and_field = mapping();
+        tag_and_field
+            .tags
+            .insert("count".to_string(), "count".to_string());
+        assert!(matches!(
+            map_documents("{}", &tag_and_field, Some("cpu")),
+            Err(Error::InvalidJsonMapping(_))
+        ));
+
+        // without a measurement path, the write must name the table:
+        assert!(matches!(
+            map_documents("{}", &mapping(), None),
+            Err(Error::InvalidJsonMapping(_))
+        ));
+
+        assert!(matches!(
+            map_documents("[{\"count\": 1}", &mapping(), Some("cpu")),
+            Err(Error::InvalidJson(_))
+        ));
+    }
+}
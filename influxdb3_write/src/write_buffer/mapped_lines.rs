@@ -0,0 +1,58 @@
+//! Line protocol mapped from the rows of other write formats, such as JSON documents or CSV rows.
+//! The mapped lines are validated against the catalog and buffered in the same way as line
+//! protocol writes, and the row each line was mapped from is kept so that errors are reported
+//! against the rows rather than the line protocol.
+
+use crate::WriteLineError;
+
+pub(crate) const MEASUREMENT_ESCAPES: &[char] = &[',', ' '];
+pub(crate) const KEY_ESCAPES: &[char] = &[',', '=', ' '];
+pub(crate) const STRING_ESCAPES: &[char] = &['"', '\\'];
+
+#[derive(Debug, Default)]
+pub(crate) struct MappedLines {
+    /// A line of line protocol for each row that could be mapped, with timestamps in nanoseconds
+    pub(crate) lp: String,
+    /// The number of the row each line was mapped from, and the row as it is reported in errors
+    rows: Vec<(usize, String)>,
+    /// The rows that could not be parsed or mapped
+    pub(crate) errors: Vec<WriteLineError>,
+}
+
+impl MappedLines {
+    /// Adds the line mapped from the row with the given number
+    pub(crate) fn push_line(&mut self, row_number: usize, row: String, line: &str) {
+        self.lp.push_str(line);
+        self.lp.push('\n');
+        self.rows.push((row_number, row));
+    }
+
+    /// Adds an error for the row with the given number, which could not be mapped
+    pub(crate) fn push_error(&mut self, row_number: usize, row: String, error_message: String) {
+        self.errors.push(WriteLineError {
+            original_line: row,
+            line_number: row_number,
+            error_message,
+        });
+    }
+
+    /// Reports an error for a line of the mapped line protocol against the row it was mapped from
+    pub(crate) fn row_error(&self, error: WriteLineError) -> WriteLineError {
+        let (row_number, row) = &self.rows[error.line_number - 1];
+        WriteLineError {
+            original_line: row.clone(),
+            line_number: *row_number,
+            error_message: error.error_message,
+        }
+    }
+}
+
+/// Pushes the string to the line, escaping the given characters with a backslash
+pub(crate) fn push_escaped(line: &mut String, s: &str, escapes: &[char]) {
+    for c in s.chars() {
+        if escapes.contains(&c) {
+            line.push('\\');
+        }
+        line.push(c);
+    }
+}
@@ -1,34 +1,60 @@
 //! Implementation of an in-memory buffer for writes that persists data into a wal if it is configured.
 
+pub mod csv_format;
+pub mod deleted_files;
+mod json;
+mod mapped_lines;
+mod parquet_import;
 pub mod persisted_files;
 pub mod queryable_buffer;
+mod quotas;
+pub mod rejected_writes;
+pub mod replica;
+pub mod subscriptions;
 mod table_buffer;
 pub(crate) mod validator;
 
-use crate::chunk::ParquetChunk;
+use crate::chunk::{expired_rows_predicate, ParquetChunk};
 use crate::last_cache::{self, CreateCacheArguments, LastCacheProvider};
 use crate::parquet_cache::ParquetCacheOracle;
 use crate::persister::Persister;
+use crate::write_buffer::csv_format::{CsvColumns, CsvRows, CSV_WRITE_BATCH_ROWS};
+use crate::write_buffer::deleted_files::DeletedFilesCleaner;
+use crate::write_buffer::json::validate_mapping;
+use crate::write_buffer::mapped_lines::MappedLines;
+use crate::write_buffer::parquet_import::{read_parquet_file, read_parquet_object};
 use crate::write_buffer::persisted_files::PersistedFiles;
 use crate::write_buffer::queryable_buffer::QueryableBuffer;
-use crate::write_buffer::validator::WriteValidator;
+use crate::write_buffer::quotas::{quota_exceeded, QuotaTracker};
+use crate::write_buffer::rejected_writes::{RejectedWrites, RejectedWritesConfig};
+use crate::write_buffer::replica::WalReplica;
+use crate::write_buffer::subscriptions::WriteSubscriptions;
+use crate::write_buffer::validator::{ValidatedLines, ValidatedWrite, WriteValidator};
 use crate::{
     BufferedWriteRequest, Bufferer, ChunkContainer, LastCacheManager, ParquetFile,
-    PersistedSnapshot, Precision, WriteBuffer, WriteLineError,
+    ParquetImportSource, ParquetImportSummary, PersistedSnapshot, Precision, QuotaUsage,
+    WriteBuffer, WriteLineError,
 };
+use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
 use data_types::{ChunkId, ChunkOrder, ColumnType, NamespaceName, NamespaceNameError};
 use datafusion::catalog::Session;
 use datafusion::common::DataFusionError;
 use datafusion::datasource::object_store::ObjectStoreUrl;
 use datafusion::logical_expr::Expr;
+use influxdb3_catalog::export::{CatalogExport, ImportDiff};
 use influxdb3_catalog::{catalog::Catalog, DatabaseSchemaProvider};
 use influxdb3_id::{DbId, TableId};
+use influxdb3_wal::local_disk::WalLocalDisk;
 use influxdb3_wal::object_store::WalObjectStore;
 use influxdb3_wal::CatalogOp::CreateLastCache;
 use influxdb3_wal::{
-    CatalogBatch, CatalogOp, LastCacheDefinition, LastCacheDelete, Wal, WalConfig, WalFileNotifier,
-    WalOp,
+    CatalogBatch, CatalogOp, CoercionPolicy, CoercionPolicyDefinition, ColumnRename,
+    DatabaseDelete, DatabaseQuotas, DeleteBatch, DeletePredicate, JsonMapping,
+    JsonMappingDefinition, LastCacheDefinition, LastCacheDelete, QuarantinedWalFile, Quota,
+    QuotasDefinition, RetentionPeriodDefinition, SchemaPolicy, SchemaPolicyDefinition, TableDelete,
+    TableRename, Wal, WalBackend, WalConfig, WalFileNotifier, WalFileSequenceNumber, WalOp,
+    WriteBatch,
 };
 use iox_query::chunk_statistics::{create_chunk_statistics, NoColumnRanges};
 use iox_query::QueryChunk;
@@ -36,8 +62,10 @@ use iox_time::{Time, TimeProvider};
 use object_store::path::Path as ObjPath;
 use object_store::{ObjectMeta, ObjectStore};
 use observability_deps::tracing::{debug, error};
+use parking_lot::Mutex;
 use parquet_file::storage::ParquetExecInput;
-use schema::Schema;
+use schema::{InfluxColumnType, InfluxFieldType, Schema};
+use std::io::Read;
 use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
@@ -90,6 +118,37 @@ pub enum Error {
 
     #[error("cannot write to a read-only server")]
     NoWriteInReadOnly,
+
+    #[error("invalid delete predicate: {0}")]
+    InvalidDeletePredicate(String),
+
+    #[error("wal file {0} is no longer retained, so writes cannot be streamed from it")]
+    WalFileNotRetained(WalFileSequenceNumber),
+
+    #[error("write would exceed the {quota} quota of {limit} for database {db_name}")]
+    QuotaExceeded {
+        db_name: String,
+        quota: Quota,
+        limit: u64,
+    },
+
+    #[error("no mapping was given for the JSON write, and none is stored for its table")]
+    NoJsonMapping,
+
+    #[error("invalid JSON mapping: {0}")]
+    InvalidJsonMapping(String),
+
+    #[error("invalid JSON in write: {0}")]
+    InvalidJson(#[source] serde_json::Error),
+
+    #[error("invalid CSV write: {0}")]
+    InvalidCsv(String),
+
+    #[error("invalid record batch write: {0}")]
+    InvalidRecordBatch(String),
+
+    #[error("invalid parquet import: {0}")]
+    InvalidParquetImport(String),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -115,12 +174,25 @@ pub struct WriteBufferImpl {
     wal: Arc<dyn Wal>,
     time_provider: Arc<dyn TimeProvider>,
     last_cache: Arc<LastCacheProvider>,
+    /// Set if this is a read replica of another host, in which case writes are rejected
+    read_replica: bool,
+    write_subscriptions: Arc<WriteSubscriptions>,
+    /// Deletes the files of deleted databases and tables, and of expired data, which is left to the
+    /// host by a replica
+    deleted_files_cleaner: Option<Arc<DeletedFilesCleaner>>,
+    /// The usage of the database quotas that are not kept in the catalog
+    quota_tracker: Arc<QuotaTracker>,
+    /// Held while the changes of a validated write are applied to the catalog
+    catalog_updates: Mutex<()>,
+    /// The lines most recently rejected from writes
+    rejected_writes: Arc<RejectedWrites>,
 }
 
 /// The maximum number of snapshots to load on start
 pub const N_SNAPSHOTS_TO_LOAD_ON_START: usize = 1_000;
 
 impl WriteBufferImpl {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         persister: Arc<Persister>,
         catalog: Arc<Catalog>,
@@ -128,7 +200,10 @@ impl WriteBufferImpl {
         time_provider: Arc<dyn TimeProvider>,
         executor: Arc<iox_query::exec::Executor>,
         wal_config: WalConfig,
+        wal_backend: WalBackend,
+        metric_registry: Arc<metric::Registry>,
         parquet_cache: Option<Arc<dyn ParquetCacheOracle>>,
+        rejected_writes_config: RejectedWritesConfig,
     ) -> Result<Self> {
         // load snapshots and replay the wal into the in memory buffer
         let persisted_snapshots = persister
@@ -155,9 +230,21 @@ impl WriteBufferImpl {
             .first()
             .map(|s| s.next_file_id.set_next_id())
             .unwrap_or(());
+        // the series are counted from the estimates persisted with the latest snapshot, and then
+        // from the writes replayed from the wal
+        let quota_tracker = Arc::new(QuotaTracker::new(
+            persisted_snapshots
+                .first()
+                .map(|s| s.series.clone())
+                .unwrap_or_default(),
+        ));
         let persisted_files = Arc::new(PersistedFiles::new_from_persisted_snapshots(
             persisted_snapshots,
         ));
+        let write_subscriptions = Arc::new(WriteSubscriptions::new(
+            Arc::clone(&catalog),
+            Arc::clone(&persister),
+        ));
         let queryable_buffer = Arc::new(QueryableBuffer::new(
             executor,
             Arc::clone(&catalog),
@@ -165,19 +252,67 @@ impl WriteBufferImpl {
             Arc::clone(&last_cache),
             Arc::clone(&persisted_files),
             parquet_cache.clone(),
+            Arc::clone(&write_subscriptions),
+            Arc::clone(&quota_tracker),
         ));
 
         // create the wal instance, which will replay into the queryable buffer and start
         // the background flush task.
-        let wal = WalObjectStore::new(
-            persister.object_store(),
-            persister.host_identifier_prefix(),
-            Arc::clone(&queryable_buffer) as Arc<dyn WalFileNotifier>,
-            wal_config,
-            last_wal_sequence_number,
-            last_snapshot_sequence_number,
-        )
-        .await?;
+        let read_replica = matches!(wal_backend, WalBackend::ReadReplica(_));
+        let deleted_files_cleaner = (!read_replica).then(|| {
+            DeletedFilesCleaner::new_with_background_task(
+                Arc::clone(&catalog),
+                Arc::clone(&persister),
+                Arc::clone(&persisted_files),
+                Arc::clone(&time_provider),
+                queryable_buffer.persisted_snapshot_notify_rx(),
+            )
+        });
+        let rejected_writes = RejectedWrites::new_with_background_task(
+            rejected_writes_config,
+            Arc::clone(&persister),
+            Arc::clone(&time_provider),
+        );
+        let wal: Arc<dyn Wal> = match wal_backend {
+            WalBackend::ObjectStore => {
+                WalObjectStore::new(
+                    persister.object_store(),
+                    persister.host_identifier_prefix(),
+                    Arc::clone(&queryable_buffer) as Arc<dyn WalFileNotifier>,
+                    Arc::clone(&time_provider),
+                    wal_config,
+                    metric_registry,
+                    last_wal_sequence_number,
+                    last_snapshot_sequence_number,
+                )
+                .await?
+            }
+            WalBackend::LocalDisk(local_disk_config) => {
+                WalLocalDisk::new(
+                    persister.object_store(),
+                    persister.host_identifier_prefix(),
+                    Arc::clone(&queryable_buffer) as Arc<dyn WalFileNotifier>,
+                    Arc::clone(&time_provider),
+                    wal_config,
+                    local_disk_config,
+                    metric_registry,
+                    last_wal_sequence_number,
+                    last_snapshot_sequence_number,
+                )
+                .await?
+            }
+            WalBackend::ReadReplica(replica_config) => {
+                WalReplica::new(
+                    Arc::clone(&persister),
+                    Arc::clone(&catalog),
+                    Arc::clone(&queryable_buffer),
+                    Arc::clone(&persisted_files),
+                    replica_config,
+                    last_snapshot_sequence_number,
+                )
+                .await?
+            }
+        };
 
         Ok(Self {
             catalog,
@@ -189,6 +324,12 @@ impl WriteBufferImpl {
             last_cache,
             persisted_files,
             buffer: queryable_buffer,
+            read_replica,
+            write_subscriptions,
+            deleted_files_cleaner,
+            quota_tracker,
+            catalog_updates: Mutex::new(()),
+            rejected_writes,
         })
     }
 
@@ -200,6 +341,15 @@ impl WriteBufferImpl {
         Arc::clone(&self.persisted_files)
     }
 
+    /// Returns an error if this is a read replica, which must be checked before the catalog is
+    /// updated for a write, as the catalog of a replica only follows the host
+    fn ensure_writable(&self) -> Result<()> {
+        if self.read_replica {
+            return Err(Error::NoWriteInReadOnly);
+        }
+        Ok(())
+    }
+
     async fn write_lp(
         &self,
         db_name: NamespaceName<'static>,
@@ -209,58 +359,352 @@ impl WriteBufferImpl {
         precision: Precision,
     ) -> Result<BufferedWriteRequest> {
         debug!("write_lp to {} in writebuffer", db_name);
+        self.ensure_writable()?;
+
+        self.check_write_quotas(db_name.as_str(), lp.len(), ingest_time)?;
+
+        let result = self
+            .write_validated(db_name.clone(), || {
+                let validated = WriteValidator::initialize(
+                    db_name.clone(),
+                    self.catalog(),
+                    ingest_time.timestamp_nanos(),
+                )?
+                .v1_parse_lines_and_update_schema(lp, accept_partial);
+                Ok(self
+                    .record_rejected_line(db_name.as_str(), ingest_time, validated)?
+                    .convert_lines_to_buffer(ingest_time, self.wal_config.gen1_duration, precision))
+            })
+            .await?;
+        self.rejected_writes.add(
+            db_name.as_str(),
+            ingest_time.timestamp_nanos(),
+            &result.invalid_lines,
+        );
+
+        Ok(result)
+    }
 
-        // validated lines will update the in-memory catalog, ensuring that all write operations
-        // past this point will be infallible
-        let result = WriteValidator::initialize(
-            db_name.clone(),
-            self.catalog(),
+    async fn write_lp_v3(
+        &self,
+        db_name: NamespaceName<'static>,
+        lp: &str,
+        ingest_time: Time,
+        accept_partial: bool,
+        precision: Precision,
+    ) -> Result<BufferedWriteRequest> {
+        self.ensure_writable()?;
+        self.check_write_quotas(db_name.as_str(), lp.len(), ingest_time)?;
+
+        let result = self
+            .write_validated(db_name.clone(), || {
+                let validated = WriteValidator::initialize(
+                    db_name.clone(),
+                    self.catalog(),
+                    ingest_time.timestamp_nanos(),
+                )?
+                .v3_parse_lines_and_update_schema(lp, accept_partial);
+                Ok(self
+                    .record_rejected_line(db_name.as_str(), ingest_time, validated)?
+                    .convert_lines_to_buffer(ingest_time, self.wal_config.gen1_duration, precision))
+            })
+            .await?;
+        self.rejected_writes.add(
+            db_name.as_str(),
             ingest_time.timestamp_nanos(),
-        )?
-        .v1_parse_lines_and_update_schema(lp, accept_partial)?
-        .convert_lines_to_buffer(ingest_time, self.wal_config.gen1_duration, precision);
+            &result.invalid_lines,
+        );
 
-        // if there were catalog updates, ensure they get persisted to the wal, so they're
-        // replayed on restart
-        let mut ops = Vec::with_capacity(2);
-        if let Some(catalog_batch) = result.catalog_updates {
-            ops.push(WalOp::Catalog(catalog_batch));
+        Ok(result)
+    }
+
+    /// Maps the JSON documents to rows with the given mapping, or the one stored for the table if
+    /// none is given, then validates and buffers them like line protocol
+    async fn write_json(
+        &self,
+        db_name: NamespaceName<'static>,
+        table: Option<&str>,
+        json: &str,
+        mapping: Option<JsonMapping>,
+        ingest_time: Time,
+        accept_partial: bool,
+    ) -> Result<BufferedWriteRequest> {
+        debug!("write_json to {} in writebuffer", db_name);
+        self.ensure_writable()?;
+
+        let mapping = match mapping {
+            Some(mapping) => mapping,
+            None => table
+                .and_then(|table| {
+                    self.catalog
+                        .db_schema(db_name.as_str())?
+                        .table_definition(table)?
+                        .json_mapping
+                        .clone()
+                })
+                .ok_or(Error::NoJsonMapping)?,
+        };
+        self.check_write_quotas(db_name.as_str(), json.len(), ingest_time)?;
+
+        let mapped = json::map_documents(json, &mapping, table)?;
+        self.write_mapped_lines(db_name, mapped, ingest_time, accept_partial)
+            .await
+    }
+
+    /// Maps the rows of annotated CSV, or of plain CSV with the given column roles, then validates
+    /// and buffers them like line protocol. The CSV is read from its reader on a blocking thread
+    /// as its rows are mapped. If partial writes are accepted, the rows are mapped and written in
+    /// batches, so that neither the CSV nor its line protocol is held all at once. Otherwise all
+    /// of the rows are mapped and written in a single write, so that nothing is written if any of
+    /// them has an error.
+    async fn write_csv(
+        &self,
+        db_name: NamespaceName<'static>,
+        csv: Box<dyn Read + Send>,
+        columns: Option<CsvColumns>,
+        ingest_time: Time,
+        accept_partial: bool,
+    ) -> Result<BufferedWriteRequest> {
+        debug!("write_csv to {} in writebuffer", db_name);
+        self.ensure_writable()?;
+        self.check_write_quotas(db_name.as_str(), 0, ingest_time)?;
+
+        let mut rows = CsvRows::new(csv, columns)?;
+        let mut result = BufferedWriteRequest {
+            db_name: db_name.clone(),
+            invalid_lines: vec![],
+            line_count: 0,
+            field_count: 0,
+            index_count: 0,
+            coerced_count: 0,
+        };
+        let max_rows = if accept_partial {
+            CSV_WRITE_BATCH_ROWS
+        } else {
+            usize::MAX
+        };
+        let mut bytes_read = 0;
+        loop {
+            let (returned_rows, mapped) = tokio::task::spawn_blocking(move || {
+                let mapped = rows.next_batch(max_rows);
+                (rows, mapped)
+            })
+            .await
+            .expect("mapping CSV rows does not panic");
+            rows = returned_rows;
+            let Some(mapped) = mapped? else {
+                break;
+            };
+            // the bytes of each batch count towards the write quota as they are read
+            let batch_bytes = rows.bytes_read() - bytes_read;
+            bytes_read = rows.bytes_read();
+            self.check_write_quotas(db_name.as_str(), batch_bytes as usize, ingest_time)?;
+
+            let batch = self
+                .write_mapped_lines(db_name.clone(), mapped, ingest_time, accept_partial)
+                .await?;
+            result.invalid_lines.extend(batch.invalid_lines);
+            result.line_count += batch.line_count;
+            result.field_count += batch.field_count;
+            result.index_count += batch.index_count;
+            result.coerced_count += batch.coerced_count;
         }
-        ops.push(WalOp::Write(result.valid_data));
 
-        // write to the wal. Behind the scenes the ops get buffered in memory and once a second (or
-        // whatever the configured wal flush interval is set to) the buffer is flushed and all the
-        // data is persisted into a single wal file in the configured object store. Then the
-        // contents are sent to the configured notifier, which in this case is the queryable buffer.
-        // Thus, after this returns, the data is both durable and queryable.
-        self.wal.write_ops(ops).await?;
+        Ok(result)
+    }
 
-        Ok(BufferedWriteRequest {
-            db_name,
-            invalid_lines: result.errors,
-            line_count: result.line_count,
-            field_count: result.field_count,
-            index_count: result.index_count,
+    /// Validates Arrow record batches against the catalog, adding the table or its columns if they
+    /// are new, and buffers their rows without converting them to line protocol. The batches are
+    /// written as a whole, or not at all if any of them is invalid.
+    async fn write_record_batches(
+        &self,
+        db_name: NamespaceName<'static>,
+        table_name: &str,
+        batches: Vec<RecordBatch>,
+        ingest_time: Time,
+    ) -> Result<BufferedWriteRequest> {
+        debug!(
+            "write_record_batches to {}.{} in writebuffer",
+            db_name, table_name
+        );
+        self.ensure_writable()?;
+        let write_bytes = batches
+            .iter()
+            .map(|batch| batch.get_array_memory_size())
+            .sum();
+        self.check_write_quotas(db_name.as_str(), write_bytes, ingest_time)?;
+
+        self.write_validated(db_name.clone(), || {
+            Ok(WriteValidator::initialize(
+                db_name.clone(),
+                self.catalog(),
+                ingest_time.timestamp_nanos(),
+            )?
+            .validate_record_batches_and_update_schema(table_name, batches.clone())?
+            .convert_batches_to_buffer(ingest_time, self.wal_config.gen1_duration))
         })
+        .await
     }
 
-    async fn write_lp_v3(
+    /// Imports a parquet file into a table. The rows are validated in the same way as written
+    /// record batches, split into record batches for each gen1 chunk of time, and then persisted
+    /// in a snapshot of their own, with a wal file number that is reserved so that none of the
+    /// wal files or parquet files of the host reuse it. Neither the rows nor the changes to the
+    /// catalog go through the WAL, so the catalog is persisted along with them.
+    async fn import_parquet(
         &self,
         db_name: NamespaceName<'static>,
-        lp: &str,
+        table_name: &str,
+        file: ParquetImportSource,
+        ingest_time: Time,
+    ) -> Result<ParquetImportSummary> {
+        debug!(
+            "import_parquet to {}.{} in writebuffer",
+            db_name, table_name
+        );
+        self.ensure_writable()?;
+        let object_store = self.persister.object_store();
+        let file_size = match &file {
+            ParquetImportSource::Bytes(bytes) => bytes.len(),
+            ParquetImportSource::ObjectStore(path) => {
+                object_store
+                    .head(path)
+                    .await
+                    .map_err(|e| Error::InvalidParquetImport(e.to_string()))?
+                    .size
+            }
+        };
+        self.check_write_quotas(db_name.as_str(), file_size, ingest_time)?;
+
+        let db_schema = self.catalog.db_schema(db_name.as_str());
+        let table_def = db_schema
+            .as_ref()
+            .and_then(|db_schema| db_schema.table_definition(table_name));
+        let batches = match file {
+            ParquetImportSource::Bytes(bytes) => read_parquet_file(bytes, table_def)?,
+            ParquetImportSource::ObjectStore(path) => {
+                read_parquet_object(object_store, &path, table_def).await?
+            }
+        };
+        let result = self.validate_and_update_catalog(|| {
+            Ok(WriteValidator::initialize(
+                db_name.clone(),
+                self.catalog(),
+                ingest_time.timestamp_nanos(),
+            )?
+            .validate_record_batches_and_update_schema(table_name, batches.clone())
+            .map_err(|e| match e {
+                Error::InvalidRecordBatch(message) => Error::InvalidParquetImport(message),
+                e => e,
+            })?
+            .convert_batches_to_chunks(ingest_time, self.wal_config.gen1_duration))
+        })?;
+
+        let (wal_file_number, snapshot_sequence_number) = self.wal.reserve_snapshot().await?;
+        let row_count = result.row_count as u64;
+        debug!(
+            row_count,
+            coerced_count = result.coerced_count,
+            "persisting the rows of a parquet import"
+        );
+        let persisted_snapshot = self
+            .buffer
+            .persist_batches(result, wal_file_number, snapshot_sequence_number)
+            .await?;
+
+        Ok(ParquetImportSummary {
+            row_count,
+            persisted_row_count: persisted_snapshot.row_count,
+            persisted_file_count: persisted_snapshot
+                .databases
+                .values()
+                .flat_map(|db_tables| db_tables.tables.values())
+                .map(Vec::len)
+                .sum(),
+            min_time: persisted_snapshot.min_time,
+            max_time: persisted_snapshot.max_time,
+            snapshot_sequence_number,
+        })
+    }
+
+    /// Validates and buffers line protocol that was mapped from the rows of another write format.
+    /// Errors are reported against the rows they are for, rather than the line protocol they were
+    /// mapped to.
+    async fn write_mapped_lines(
+        &self,
+        db_name: NamespaceName<'static>,
+        mut mapped: MappedLines,
         ingest_time: Time,
         accept_partial: bool,
-        precision: Precision,
     ) -> Result<BufferedWriteRequest> {
-        // validated lines will update the in-memory catalog, ensuring that all write operations
-        // past this point will be infallible
-        let result = WriteValidator::initialize(
-            db_name.clone(),
-            self.catalog(),
-            ingest_time.timestamp_nanos(),
-        )?
-        .v3_parse_lines_and_update_schema(lp, accept_partial)?
-        .convert_lines_to_buffer(ingest_time, self.wal_config.gen1_duration, precision);
+        let mut errors = std::mem::take(&mut mapped.errors);
+        if !accept_partial && !errors.is_empty() {
+            let error = Error::ParseError(errors.swap_remove(0));
+            return self.record_rejected_line(db_name.as_str(), ingest_time, Err(error));
+        }
+        if mapped.lp.is_empty() {
+            self.rejected_writes
+                .add(db_name.as_str(), ingest_time.timestamp_nanos(), &errors);
+            return Ok(BufferedWriteRequest {
+                db_name,
+                invalid_lines: errors,
+                line_count: 0,
+                field_count: 0,
+                index_count: 0,
+                coerced_count: 0,
+            });
+        }
+
+        let mut result = self
+            .write_validated(db_name.clone(), || {
+                let validated = WriteValidator::initialize(
+                    db_name.clone(),
+                    self.catalog(),
+                    ingest_time.timestamp_nanos(),
+                )?
+                .v1_parse_lines_and_update_schema(&mapped.lp, accept_partial)
+                .map_err(|e| match e {
+                    Error::ParseError(line) => Error::ParseError(mapped.row_error(line)),
+                    e => e,
+                });
+                Ok(self
+                    .record_rejected_line(db_name.as_str(), ingest_time, validated)?
+                    .convert_lines_to_buffer(
+                        ingest_time,
+                        self.wal_config.gen1_duration,
+                        Precision::Nanosecond,
+                    ))
+            })
+            .await?;
+        errors.extend(
+            std::mem::take(&mut result.invalid_lines)
+                .into_iter()
+                .map(|line| mapped.row_error(line)),
+        );
+        errors.sort_by_key(|line| line.line_number);
+        self.rejected_writes
+            .add(db_name.as_str(), ingest_time.timestamp_nanos(), &errors);
+        result.invalid_lines = errors;
+
+        Ok(result)
+    }
+
+    /// Validates a write with the given function, checks it against the series quota of its
+    /// database, and then applies its changes to the catalog and writes it to the wal. Nothing is
+    /// applied or written if it exceeds the quota. The lines that were rejected are returned, but
+    /// not recorded.
+    ///
+    /// Behind the scenes the ops get buffered in memory and once a second (or whatever the
+    /// configured wal flush interval is set to) the buffer is flushed and all the data is persisted
+    /// into a single wal file in the configured object store. Then the contents are sent to the
+    /// configured notifier, which in this case is the queryable buffer. Thus, after this returns,
+    /// the data is both durable and queryable.
+    async fn write_validated(
+        &self,
+        db_name: NamespaceName<'static>,
+        validate: impl Fn() -> Result<ValidatedLines>,
+    ) -> Result<BufferedWriteRequest> {
+        let result = self.validate_and_update_catalog(validate)?;
 
         // if there were catalog updates, ensure they get persisted to the wal, so they're
         // replayed on restart
@@ -269,12 +713,6 @@ impl WriteBufferImpl {
             ops.push(WalOp::Catalog(catalog_batch));
         }
         ops.push(WalOp::Write(result.valid_data));
-
-        // write to the wal. Behind the scenes the ops get buffered in memory and once a second (or
-        // whatever the configured wal flush interval is set to) the buffer is flushed and all the
-        // data is persisted into a single wal file in the configured object store. Then the
-        // contents are sent to the configured notifier, which in this case is the queryable buffer.
-        // Thus, after this returns, the data is both durable and queryable.
         self.wal.write_ops(ops).await?;
 
         Ok(BufferedWriteRequest {
@@ -283,306 +721,1029 @@ impl WriteBufferImpl {
             line_count: result.line_count,
             field_count: result.field_count,
             index_count: result.index_count,
+            coerced_count: result.coerced_count,
         })
     }
 
-    fn get_table_chunks(
+    /// Validates a write with the given function and checks it against the series quota of its
+    /// database before applying its changes to the catalog and counting its series. Writes are
+    /// validated concurrently, but their changes are applied one at a time, and a write is
+    /// validated again if the catalog changed while it was being validated, so that a write that
+    /// adds a table or column does not conflict with the changes of another.
+    fn validate_and_update_catalog<W: ValidatedWrite>(
         &self,
-        database_name: &str,
-        table_name: &str,
-        filters: &[Expr],
-        projection: Option<&Vec<usize>>,
-        ctx: &dyn Session,
-    ) -> Result<Vec<Arc<dyn QueryChunk>>, DataFusionError> {
-        let db_schema = self.catalog.db_schema(database_name).ok_or_else(|| {
-            DataFusionError::Execution(format!("database {} not found", database_name))
-        })?;
-
-        let (table_id, table_schema) =
-            db_schema.table_schema_and_id(table_name).ok_or_else(|| {
-                DataFusionError::Execution(format!(
-                    "table {} not found in db {}",
-                    table_name, database_name
-                ))
-            })?;
+        validate: impl Fn() -> Result<W>,
+    ) -> Result<W> {
+        loop {
+            let sequence = self.catalog.sequence_number();
+            let result = validate()?;
+
+            // the database is only missing if it was deleted since the write was validated, which
+            // leaves no quota to check
+            let db_schema = self.catalog.db_schema_by_id(result.database_id());
+            let has_series_quota = db_schema
+                .as_ref()
+                .is_some_and(|db_schema| db_schema.quotas.max_series.is_some());
+            if result.catalog_updates().is_none() && !has_series_quota {
+                return Ok(result);
+            }
 
-        let mut chunks = self.buffer.get_table_chunks(
-            Arc::clone(&db_schema),
-            table_name,
-            filters,
-            projection,
-            ctx,
-        )?;
+            let _catalog_updates = self.catalog_updates.lock();
+            if self.catalog.sequence_number() != sequence {
+                continue;
+            }
+            let series = match &db_schema {
+                Some(db_schema) => result.check_series(&self.quota_tracker, db_schema)?,
+                None => None,
+            };
+            if let Some(catalog_batch) = result.catalog_updates() {
+                self.catalog.apply_catalog_batch(catalog_batch)?;
+            }
+            if let Some(series) = series {
+                self.quota_tracker.add_series(result.database_id(), &series);
+            }
 
-        let parquet_files = self.persisted_files.get_files(db_schema.id, table_id);
+            return Ok(result);
+        }
+    }
 
-        let mut chunk_order = chunks.len() as i64;
+    /// Checks the quotas of the database that do not depend on the lines being written, and counts
+    /// the write against its write rate. Writes that create the database have no quotas to check.
+    fn check_write_quotas(
+        &self,
+        db_name: &str,
+        write_bytes: usize,
+        ingest_time: Time,
+    ) -> Result<()> {
+        let Some(db_schema) = self.catalog.db_schema(db_name) else {
+            return Ok(());
+        };
+        if let Some(limit) = db_schema.quotas.max_stored_bytes {
+            if self.persisted_files.database_size_bytes(db_schema.id) >= limit {
+                return Err(quota_exceeded(&db_schema, Quota::StoredBytes, limit));
+            }
+        }
+        self.quota_tracker.add_write_bytes(
+            &db_schema,
+            write_bytes as u64,
+            ingest_time.timestamp_nanos(),
+        )
+    }
 
-        for parquet_file in parquet_files {
-            let parquet_chunk = parquet_chunk_from_file(
-                &parquet_file,
-                &table_schema,
-                self.persister.object_store_url().clone(),
-                self.persister.object_store(),
-                chunk_order,
+    /// Records the line that caused the write to be rejected, if it was rejected for a line that
+    /// could not be parsed or validated
+    fn record_rejected_line<T>(
+        &self,
+        db_name: &str,
+        ingest_time: Time,
+        result: Result<T>,
+    ) -> Result<T> {
+        if let Err(Error::ParseError(line)) = &result {
+            self.rejected_writes.add(
+                db_name,
+                ingest_time.timestamp_nanos(),
+                std::slice::from_ref(line),
             );
+        }
+        result
+    }
 
-            chunk_order += 1;
+    async fn delete_rows(
+        &self,
+        db_id: DbId,
+        table_id: TableId,
+        predicate: DeletePredicate,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let db_schema = self
+            .catalog
+            .db_schema_by_id(db_id)
+            .ok_or(Error::DbDoesNotExist)?;
+        let table_def = db_schema
+            .table_definition_by_id(table_id)
+            .ok_or(Error::TableDoesNotExist)?;
 
-            chunks.push(Arc::new(parquet_chunk));
+        if predicate.min_time_ns > predicate.max_time_ns {
+            return Err(Error::InvalidDeletePredicate(format!(
+                "the start time ({}) is after the end time ({})",
+                predicate.min_time_ns, predicate.max_time_ns
+            )));
+        }
+        for (tag, _) in &predicate.tags {
+            if table_def.field_type_by_name(tag) != Some(InfluxColumnType::Tag) {
+                return Err(Error::InvalidDeletePredicate(format!(
+                    "{tag} is not a tag column in table {}",
+                    table_def.table_name
+                )));
+            }
         }
 
-        Ok(chunks)
-    }
-}
+        // the delete is applied to the buffer, and its tombstone added for the persisted files,
+        // when the wal file it is in is flushed, so once this returns it is honored by queries
+        self.wal
+            .write_ops(vec![WalOp::Delete(DeleteBatch {
+                database_id: db_id,
+                database_name: Arc::clone(&db_schema.name),
+                table_id,
+                table_name: Arc::clone(&table_def.table_name),
+                time_ns: self.time_provider.now().timestamp_nanos(),
+                predicate,
+            })])
+            .await?;
 
-pub fn parquet_chunk_from_file(
-    parquet_file: &ParquetFile,
-    table_schema: &Schema,
-    object_store_url: ObjectStoreUrl,
-    object_store: Arc<dyn ObjectStore>,
-    chunk_order: i64,
-) -> ParquetChunk {
-    let partition_key = data_types::PartitionKey::from(parquet_file.chunk_time.to_string());
-    let partition_id = data_types::partition::TransitionPartitionId::new(
-        data_types::TableId::new(0),
-        &partition_key,
-    );
+        Ok(())
+    }
 
-    let chunk_stats = create_chunk_statistics(
-        Some(parquet_file.row_count as usize),
-        table_schema,
-        Some(parquet_file.timestamp_min_max()),
-        &NoColumnRanges,
-    );
+    async fn create_database(
+        &self,
+        db_name: NamespaceName<'static>,
+        retention_period: Option<Duration>,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let catalog_batch = self.catalog.create_database(
+            db_name.as_str(),
+            retention_period.map(duration_as_nanos),
+            self.time_provider.now().timestamp_nanos(),
+        )?;
+        self.wal
+            .write_ops(vec![WalOp::Catalog(catalog_batch)])
+            .await?;
 
-    let location = ObjPath::from(parquet_file.path.clone());
+        Ok(())
+    }
 
-    let parquet_exec = ParquetExecInput {
-        object_store_url,
-        object_meta: ObjectMeta {
-            location,
-            last_modified: Default::default(),
-            size: parquet_file.size_bytes as usize,
-            e_tag: None,
-            version: None,
-        },
-        object_store,
-    };
+    async fn create_table(
+        &self,
+        db_name: NamespaceName<'static>,
+        table_name: &str,
+        tags: Vec<String>,
+        fields: Vec<(String, InfluxFieldType)>,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let catalog_batch = self.catalog.create_table(
+            db_name.as_str(),
+            table_name,
+            &tags,
+            &fields,
+            self.time_provider.now().timestamp_nanos(),
+        )?;
+        self.wal
+            .write_ops(vec![WalOp::Catalog(catalog_batch)])
+            .await?;
 
-    ParquetChunk {
-        schema: table_schema.clone(),
-        stats: Arc::new(chunk_stats),
-        partition_id,
-        sort_key: None,
-        id: ChunkId::new(),
-        chunk_order: ChunkOrder::new(chunk_order),
-        parquet_exec,
+        Ok(())
     }
-}
 
-#[async_trait]
-impl Bufferer for WriteBufferImpl {
-    async fn write_lp(
+    async fn set_schema_policy(
         &self,
-        database: NamespaceName<'static>,
-        lp: &str,
-        ingest_time: Time,
-        accept_partial: bool,
-        precision: Precision,
-    ) -> Result<BufferedWriteRequest> {
-        self.write_lp(database, lp, ingest_time, accept_partial, precision)
-            .await
+        db_id: DbId,
+        table_id: Option<TableId>,
+        policy: SchemaPolicy,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let db_schema = self
+            .catalog
+            .db_schema_by_id(db_id)
+            .ok_or(Error::DbDoesNotExist)?;
+        let table_name = table_id
+            .map(|table_id| {
+                db_schema
+                    .table_id_to_name(table_id)
+                    .ok_or(Error::TableDoesNotExist)
+            })
+            .transpose()?;
+
+        let catalog_batch = CatalogBatch {
+            time_ns: self.time_provider.now().timestamp_nanos(),
+            database_id: db_id,
+            database_name: Arc::clone(&db_schema.name),
+            ops: vec![CatalogOp::SetSchemaPolicy(SchemaPolicyDefinition {
+                table_id,
+                table_name,
+                policy,
+            })],
+        };
+        // the policy is applied to the catalog first, so that it is enforced on the writes that
+        // follow this one into the wal
+        self.catalog.apply_catalog_batch(&catalog_batch)?;
+        self.wal
+            .write_ops(vec![WalOp::Catalog(catalog_batch)])
+            .await?;
+
+        Ok(())
     }
 
-    async fn write_lp_v3(
+    async fn set_coercion_policy(
         &self,
-        database: NamespaceName<'static>,
-        lp: &str,
-        ingest_time: Time,
-        accept_partial: bool,
-        precision: Precision,
-    ) -> Result<BufferedWriteRequest> {
-        self.write_lp_v3(database, lp, ingest_time, accept_partial, precision)
-            .await
+        db_id: DbId,
+        table_id: TableId,
+        policy: CoercionPolicy,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let db_schema = self
+            .catalog
+            .db_schema_by_id(db_id)
+            .ok_or(Error::DbDoesNotExist)?;
+        let table_name = db_schema
+            .table_id_to_name(table_id)
+            .ok_or(Error::TableDoesNotExist)?;
+
+        let catalog_batch = CatalogBatch {
+            time_ns: self.time_provider.now().timestamp_nanos(),
+            database_id: db_id,
+            database_name: Arc::clone(&db_schema.name),
+            ops: vec![CatalogOp::SetCoercionPolicy(CoercionPolicyDefinition {
+                table_id,
+                table_name,
+                policy,
+            })],
+        };
+        // the policy is applied to the catalog first, so that the writes that follow this one
+        // into the wal are coerced by it
+        self.catalog.apply_catalog_batch(&catalog_batch)?;
+        self.wal
+            .write_ops(vec![WalOp::Catalog(catalog_batch)])
+            .await?;
+
+        Ok(())
     }
 
-    fn db_schema_provider(&self) -> Arc<dyn DatabaseSchemaProvider> {
-        self.catalog()
+    async fn set_json_mapping(
+        &self,
+        db_id: DbId,
+        table_id: TableId,
+        mapping: Option<JsonMapping>,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        if let Some(mapping) = &mapping {
+            validate_mapping(mapping)?;
+        }
+        let db_schema = self
+            .catalog
+            .db_schema_by_id(db_id)
+            .ok_or(Error::DbDoesNotExist)?;
+        let table_name = db_schema
+            .table_id_to_name(table_id)
+            .ok_or(Error::TableDoesNotExist)?;
+
+        let catalog_batch = CatalogBatch {
+            time_ns: self.time_provider.now().timestamp_nanos(),
+            database_id: db_id,
+            database_name: Arc::clone(&db_schema.name),
+            ops: vec![CatalogOp::SetJsonMapping(JsonMappingDefinition {
+                table_id,
+                table_name,
+                mapping,
+            })],
+        };
+        self.catalog.apply_catalog_batch(&catalog_batch)?;
+        self.wal
+            .write_ops(vec![WalOp::Catalog(catalog_batch)])
+            .await?;
+
+        Ok(())
     }
 
-    fn parquet_files(&self, db_id: DbId, table_id: TableId) -> Vec<ParquetFile> {
-        self.buffer.persisted_parquet_files(db_id, table_id)
+    async fn set_retention_period(
+        &self,
+        db_id: DbId,
+        retention_period: Option<Duration>,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let db_schema = self
+            .catalog
+            .db_schema_by_id(db_id)
+            .ok_or(Error::DbDoesNotExist)?;
+
+        let catalog_batch = CatalogBatch {
+            time_ns: self.time_provider.now().timestamp_nanos(),
+            database_id: db_id,
+            database_name: Arc::clone(&db_schema.name),
+            ops: vec![CatalogOp::SetRetentionPeriod(RetentionPeriodDefinition {
+                retention_period_ns: retention_period.map(duration_as_nanos),
+            })],
+        };
+        self.catalog.apply_catalog_batch(&catalog_batch)?;
+        self.wal
+            .write_ops(vec![WalOp::Catalog(catalog_batch)])
+            .await?;
+        self.notify_deleted_files_cleaner();
+
+        Ok(())
     }
 
-    fn watch_persisted_snapshots(&self) -> Receiver<Option<PersistedSnapshot>> {
-        self.buffer.persisted_snapshot_notify_rx()
+    async fn set_quotas(&self, db_id: DbId, quotas: DatabaseQuotas) -> Result<()> {
+        self.ensure_writable()?;
+        let db_schema = self
+            .catalog
+            .db_schema_by_id(db_id)
+            .ok_or(Error::DbDoesNotExist)?;
+
+        let catalog_batch = CatalogBatch {
+            time_ns: self.time_provider.now().timestamp_nanos(),
+            database_id: db_id,
+            database_name: Arc::clone(&db_schema.name),
+            ops: vec![CatalogOp::SetQuotas(QuotasDefinition { quotas })],
+        };
+        self.catalog.apply_catalog_batch(&catalog_batch)?;
+        self.wal
+            .write_ops(vec![WalOp::Catalog(catalog_batch)])
+            .await?;
+
+        Ok(())
     }
-}
 
-impl ChunkContainer for WriteBufferImpl {
-    fn get_table_chunks(
-        &self,
-        database_name: &str,
-        table_name: &str,
-        filters: &[Expr],
-        projection: Option<&Vec<usize>>,
-        ctx: &dyn Session,
-    ) -> crate::Result<Vec<Arc<dyn QueryChunk>>, DataFusionError> {
-        self.get_table_chunks(database_name, table_name, filters, projection, ctx)
+    fn quota_usage(&self, db_id: DbId) -> Vec<QuotaUsage> {
+        let Some(db_schema) = self.catalog.db_schema_by_id(db_id) else {
+            return vec![];
+        };
+        let now_ns = self.time_provider.now().timestamp_nanos();
+        Quota::ALL
+            .into_iter()
+            .map(|quota| QuotaUsage {
+                quota,
+                limit: db_schema.quotas.limit(quota),
+                usage: match quota {
+                    Quota::Tables => db_schema.table_count(),
+                    Quota::Columns => db_schema.column_count(),
+                    Quota::Series => self.quota_tracker.series_count(db_id),
+                    Quota::StoredBytes => self.persisted_files.database_size_bytes(db_id),
+                    Quota::WriteBytesPerSecond => {
+                        self.quota_tracker.bytes_written_this_second(db_id, now_ns)
+                    }
+                },
+            })
+            .collect()
     }
-}
 
-#[async_trait::async_trait]
-impl LastCacheManager for WriteBufferImpl {
-    fn last_cache_provider(&self) -> Arc<LastCacheProvider> {
-        Arc::clone(&self.last_cache)
+    async fn import_catalog(&self, export: &CatalogExport, dry_run: bool) -> Result<ImportDiff> {
+        let time_ns = self.time_provider.now().timestamp_nanos();
+        if dry_run {
+            return Ok(self.catalog.plan_import(export, time_ns).diff(false));
+        }
+
+        self.ensure_writable()?;
+        // the plan is applied as it is made, so the batches of the plan are those that changed
+        // the catalog
+        let plan = self.catalog.import(export, time_ns)?;
+        if plan.has_conflicts() {
+            return Ok(plan.diff(false));
+        }
+        if !plan.batches.is_empty() {
+            self.wal
+                .write_ops(plan.batches.iter().cloned().map(WalOp::Catalog).collect())
+                .await?;
+        }
+
+        Ok(plan.diff(true))
     }
 
-    /// Create a new last-N-value cache in the specified database and table, along with the given
-    /// parameters.
-    ///
-    /// Returns the name of the newly created cache, or `None` if a cache was not created, but the
-    /// provided parameters match those of an existing cache.
-    #[allow(clippy::too_many_arguments)]
-    async fn create_last_cache(
+    async fn rename_table(&self, db_id: DbId, table_id: TableId, new_name: &str) -> Result<()> {
+        self.ensure_writable()?;
+        let db_schema = self
+            .catalog
+            .db_schema_by_id(db_id)
+            .ok_or(Error::DbDoesNotExist)?;
+        let old_name = db_schema
+            .table_id_to_name(table_id)
+            .ok_or(Error::TableDoesNotExist)?;
+
+        let catalog_batch = CatalogBatch {
+            time_ns: self.time_provider.now().timestamp_nanos(),
+            database_id: db_id,
+            database_name: Arc::clone(&db_schema.name),
+            ops: vec![CatalogOp::RenameTable(TableRename {
+                table_id,
+                old_name,
+                new_name: new_name.into(),
+            })],
+        };
+        // the rename is applied to the catalog first, so that the writes that follow this one
+        // into the wal are validated against the new name
+        self.catalog.apply_catalog_batch(&catalog_batch)?;
+        self.wal
+            .write_ops(vec![WalOp::Catalog(catalog_batch)])
+            .await?;
+
+        Ok(())
+    }
+
+    async fn rename_column(
         &self,
         db_id: DbId,
         table_id: TableId,
-        cache_name: Option<&str>,
-        count: Option<usize>,
-        ttl: Option<Duration>,
-        key_columns: Option<Vec<String>>,
-        value_columns: Option<Vec<String>>,
-    ) -> Result<Option<LastCacheDefinition>, Error> {
-        let cache_name = cache_name.map(Into::into);
-        let catalog = self.catalog();
-        let db_schema = catalog
+        column_name: &str,
+        new_name: &str,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let db_schema = self
+            .catalog
             .db_schema_by_id(db_id)
             .ok_or(Error::DbDoesNotExist)?;
-        let schema = db_schema
-            .table_schema_by_id(table_id)
+        let table_def = db_schema
+            .table_definition_by_id(table_id)
             .ok_or(Error::TableDoesNotExist)?;
+        let column_id = table_def
+            .schema
+            .name_to_id(column_name.into())
+            .ok_or_else(|| influxdb3_catalog::catalog::Error::ColumnNotFound {
+                table_name: table_def.table_name.to_string(),
+                column_name: column_name.to_string(),
+            })?;
 
-        if let Some(info) = self.last_cache.create_cache(CreateCacheArguments {
-            db_id,
-            db_name: db_schema.name.to_string(),
-            table_id,
-            table_name: db_schema
-                .table_id_to_name(table_id)
-                .expect("table exists")
-                .to_string(),
-            schema,
-            cache_name,
-            count,
-            ttl,
-            key_columns,
-            value_columns,
-        })? {
-            self.catalog.add_last_cache(db_id, table_id, info.clone());
-            let add_cache_catalog_batch = WalOp::Catalog(CatalogBatch {
+        let catalog_batch = CatalogBatch {
+            time_ns: self.time_provider.now().timestamp_nanos(),
+            database_id: db_id,
+            database_name: Arc::clone(&db_schema.name),
+            ops: vec![CatalogOp::RenameColumn(ColumnRename {
+                table_id,
+                table_name: Arc::clone(&table_def.table_name),
+                column_id,
+                old_name: column_name.into(),
+                new_name: new_name.into(),
+            })],
+        };
+        // the column is renamed in the catalog and the buffer together, before the rename goes
+        // into the wal, so that the writes that follow it use the new name
+        self.buffer.apply_column_renames(&catalog_batch)?;
+        self.wal
+            .write_ops(vec![WalOp::Catalog(catalog_batch)])
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_database(&self, db_id: DbId) -> Result<()> {
+        self.ensure_writable()?;
+        let db_schema = self
+            .catalog
+            .db_schema_by_id(db_id)
+            .ok_or(Error::DbDoesNotExist)?;
+
+        // the database is removed from the catalog, and its buffer and last caches dropped, when
+        // the wal file the delete is in is flushed, so once this returns it is no longer queryable
+        self.wal
+            .write_ops(vec![WalOp::Catalog(CatalogBatch {
                 time_ns: self.time_provider.now().timestamp_nanos(),
-                database_id: db_schema.id,
+                database_id: db_id,
                 database_name: Arc::clone(&db_schema.name),
-                ops: vec![CreateLastCache(info.clone())],
-            });
-            self.wal.write_ops(vec![add_cache_catalog_batch]).await?;
+                ops: vec![CatalogOp::DeleteDatabase(DatabaseDelete {
+                    database_id: db_id,
+                    database_name: Arc::clone(&db_schema.name),
+                })],
+            })])
+            .await?;
+        self.quota_tracker.remove_database(db_id);
+        self.notify_deleted_files_cleaner();
 
-            Ok(Some(info))
-        } else {
-            Ok(None)
-        }
+        Ok(())
     }
 
-    async fn delete_last_cache(
-        &self,
-        db_id: DbId,
-        tbl_id: TableId,
-        cache_name: &str,
-    ) -> crate::Result<(), self::Error> {
-        let catalog = self.catalog();
-        let db_schema = catalog.db_schema_by_id(db_id).expect("db should exist");
-        self.last_cache.delete_cache(db_id, tbl_id, cache_name)?;
-        catalog.delete_last_cache(db_id, tbl_id, cache_name);
+    async fn delete_table(&self, db_id: DbId, table_id: TableId) -> Result<()> {
+        self.ensure_writable()?;
+        let db_schema = self
+            .catalog
+            .db_schema_by_id(db_id)
+            .ok_or(Error::DbDoesNotExist)?;
+        let table_name = db_schema
+            .table_id_to_name(table_id)
+            .ok_or(Error::TableDoesNotExist)?;
 
-        // NOTE: if this fails then the cache will be gone from the running server, but will be
-        // resurrected on server restart.
         self.wal
             .write_ops(vec![WalOp::Catalog(CatalogBatch {
                 time_ns: self.time_provider.now().timestamp_nanos(),
                 database_id: db_id,
                 database_name: Arc::clone(&db_schema.name),
-                ops: vec![CatalogOp::DeleteLastCache(LastCacheDelete {
-                    table_id: tbl_id,
-                    table_name: db_schema
-                        .table_id_to_name(tbl_id)
-                        .expect("table exists")
-                        .to_string(),
-                    name: cache_name.into(),
+                ops: vec![CatalogOp::DeleteTable(TableDelete {
+                    table_id,
+                    table_name,
                 })],
             })])
             .await?;
+        self.notify_deleted_files_cleaner();
 
         Ok(())
     }
-}
 
-impl WriteBuffer for WriteBufferImpl {}
+    fn notify_deleted_files_cleaner(&self) {
+        if let Some(cleaner) = &self.deleted_files_cleaner {
+            cleaner.notify();
+        }
+    }
 
-#[cfg(test)]
-#[allow(clippy::await_holding_lock)]
-mod tests {
-    use super::*;
-    use crate::parquet_cache::test_cached_obj_store_and_oracle;
-    use crate::paths::{CatalogFilePath, SnapshotInfoFilePath};
-    use crate::persister::Persister;
-    use crate::PersistedSnapshot;
-    use arrow::record_batch::RecordBatch;
-    use arrow_util::{assert_batches_eq, assert_batches_sorted_eq};
-    use bytes::Bytes;
-    use datafusion_util::config::register_iox_object_store;
-    use futures_util::StreamExt;
-    use influxdb3_catalog::catalog::SequenceNumber;
-    use influxdb3_id::{DbId, ParquetFileId};
-    use influxdb3_test_helpers::object_store::RequestCountedObjectStore;
-    use influxdb3_wal::{Gen1Duration, SnapshotSequenceNumber, WalFileSequenceNumber};
-    use iox_query::exec::IOxSessionContext;
-    use iox_time::{MockProvider, Time};
-    use object_store::local::LocalFileSystem;
-    use object_store::memory::InMemory;
-    use object_store::{ObjectStore, PutPayload};
+    fn get_table_chunks(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        filters: &[Expr],
+        projection: Option<&Vec<usize>>,
+        ctx: &dyn Session,
+    ) -> Result<Vec<Arc<dyn QueryChunk>>, DataFusionError> {
+        let db_schema = self.catalog.db_schema(database_name).ok_or_else(|| {
+            DataFusionError::Execution(format!("database {} not found", database_name))
+        })?;
 
-    #[test]
-    fn parse_lp_into_buffer() {
-        let host_id = Arc::from("sample-host-id");
-        let instance_id = Arc::from("sample-instance-id");
-        let catalog = Arc::new(Catalog::new(host_id, instance_id));
-        let db_name = NamespaceName::new("foo").unwrap();
-        let lp = "cpu,region=west user=23.2 100\nfoo f1=1i";
-        WriteValidator::initialize(db_name, Arc::clone(&catalog), 0)
-            .unwrap()
-            .v1_parse_lines_and_update_schema(lp, false)
-            .unwrap()
-            .convert_lines_to_buffer(
-                Time::from_timestamp_nanos(0),
-                Gen1Duration::new_5m(),
-                Precision::Nanosecond,
-            );
+        let (table_id, table_schema) =
+            db_schema.table_schema_and_id(table_name).ok_or_else(|| {
+                DataFusionError::Execution(format!(
+                    "table {} not found in db {}",
+                    table_name, database_name
+                ))
+            })?;
 
-        let db = catalog.db_schema_by_id(DbId::from(0)).unwrap();
+        let retention_cutoff_ns =
+            db_schema.retention_cutoff_ns(self.time_provider.now().timestamp_nanos());
 
-        assert_eq!(db.tables.len(), 2);
-        // cpu table
-        assert_eq!(db.tables.get(&TableId::from(0)).unwrap().num_columns(), 3);
-        // foo table
-        assert_eq!(db.tables.get(&TableId::from(1)).unwrap().num_columns(), 2);
-    }
+        let mut chunks = self.buffer.get_table_chunks(
+            Arc::clone(&db_schema),
+            table_name,
+            filters,
+            retention_cutoff_ns,
+            projection,
+            ctx,
+        )?;
 
-    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
-    async fn writes_data_to_wal_and_is_queryable() {
-        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
-        let time_provider: Arc<dyn TimeProvider> =
-            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let table_def = db_schema
+            .table_definition_by_id(table_id)
+            .expect("table exists");
+        let parquet_files = self.persisted_files.get_files(db_schema.id, table_id);
+        let tombstones = self.persisted_files.get_tombstones(db_schema.id, table_id);
+
+        let mut chunk_order = chunks.len() as i64;
+
+        for parquet_file in parquet_files {
+            // files that only hold expired data are deleted in the background, and are not
+            // queried in the meantime
+            if retention_cutoff_ns.is_some_and(|cutoff| parquet_file.max_time < cutoff) {
+                continue;
+            }
+            let mut parquet_chunk = parquet_chunk_from_file(
+                &parquet_file,
+                &table_schema,
+                self.persister.object_store_url().clone(),
+                self.persister.object_store(),
+                chunk_order,
+            );
+            parquet_chunk.tombstones = tombstones
+                .iter()
+                .filter(|t| t.applies_to(&parquet_file))
+                .map(|t| t.predicate.clone())
+                .collect();
+            if let Some(cutoff) =
+                retention_cutoff_ns.filter(|cutoff| parquet_file.min_time < *cutoff)
+            {
+                parquet_chunk
+                    .tombstones
+                    .push(expired_rows_predicate(cutoff));
+            }
+            parquet_chunk.column_renames = table_def.renamed_columns(&parquet_file.column_ids);
+            parquet_chunk.set_columns_not_in_file(
+                table_def.reused_column_names(&parquet_file.column_ids),
+                &parquet_file.column_ids,
+            );
+
+            chunk_order += 1;
+
+            chunks.push(Arc::new(parquet_chunk));
+        }
+
+        Ok(chunks)
+    }
+}
+
+/// The duration in nanoseconds, saturating at the largest `u64`
+fn duration_as_nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+pub fn parquet_chunk_from_file(
+    parquet_file: &ParquetFile,
+    table_schema: &Schema,
+    object_store_url: ObjectStoreUrl,
+    object_store: Arc<dyn ObjectStore>,
+    chunk_order: i64,
+) -> ParquetChunk {
+    let partition_key = data_types::PartitionKey::from(parquet_file.chunk_time.to_string());
+    let partition_id = data_types::partition::TransitionPartitionId::new(
+        data_types::TableId::new(0),
+        &partition_key,
+    );
+
+    let chunk_stats = create_chunk_statistics(
+        Some(parquet_file.row_count as usize),
+        table_schema,
+        Some(parquet_file.timestamp_min_max()),
+        &NoColumnRanges,
+    );
+
+    let location = ObjPath::from(parquet_file.path.clone());
+
+    let parquet_exec = ParquetExecInput {
+        object_store_url,
+        object_meta: ObjectMeta {
+            location,
+            last_modified: Default::default(),
+            size: parquet_file.size_bytes as usize,
+            e_tag: None,
+            version: None,
+        },
+        object_store,
+    };
+
+    ParquetChunk {
+        schema: table_schema.clone(),
+        stats: Arc::new(chunk_stats),
+        partition_id,
+        sort_key: None,
+        id: ChunkId::new(),
+        chunk_order: ChunkOrder::new(chunk_order),
+        parquet_exec,
+        tombstones: vec![],
+        column_renames: vec![],
+        columns_not_in_file: vec![],
+    }
+}
+
+#[async_trait]
+impl Bufferer for WriteBufferImpl {
+    async fn write_lp(
+        &self,
+        database: NamespaceName<'static>,
+        lp: &str,
+        ingest_time: Time,
+        accept_partial: bool,
+        precision: Precision,
+    ) -> Result<BufferedWriteRequest> {
+        self.write_lp(database, lp, ingest_time, accept_partial, precision)
+            .await
+    }
+
+    async fn write_lp_v3(
+        &self,
+        database: NamespaceName<'static>,
+        lp: &str,
+        ingest_time: Time,
+        accept_partial: bool,
+        precision: Precision,
+    ) -> Result<BufferedWriteRequest> {
+        self.write_lp_v3(database, lp, ingest_time, accept_partial, precision)
+            .await
+    }
+
+    async fn write_json(
+        &self,
+        database: NamespaceName<'static>,
+        table: Option<&str>,
+        json: &str,
+        mapping: Option<JsonMapping>,
+        ingest_time: Time,
+        accept_partial: bool,
+    ) -> Result<BufferedWriteRequest> {
+        self.write_json(database, table, json, mapping, ingest_time, accept_partial)
+            .await
+    }
+
+    async fn write_csv(
+        &self,
+        database: NamespaceName<'static>,
+        csv: Box<dyn Read + Send>,
+        columns: Option<CsvColumns>,
+        ingest_time: Time,
+        accept_partial: bool,
+    ) -> Result<BufferedWriteRequest> {
+        self.write_csv(database, csv, columns, ingest_time, accept_partial)
+            .await
+    }
+
+    async fn write_record_batches(
+        &self,
+        database: NamespaceName<'static>,
+        table: &str,
+        batches: Vec<RecordBatch>,
+        ingest_time: Time,
+    ) -> Result<BufferedWriteRequest> {
+        self.write_record_batches(database, table, batches, ingest_time)
+            .await
+    }
+
+    async fn import_parquet(
+        &self,
+        database: NamespaceName<'static>,
+        table: &str,
+        file: ParquetImportSource,
+        ingest_time: Time,
+    ) -> Result<ParquetImportSummary> {
+        self.import_parquet(database, table, file, ingest_time)
+            .await
+    }
+
+    fn db_schema_provider(&self) -> Arc<dyn DatabaseSchemaProvider> {
+        self.catalog()
+    }
+
+    fn parquet_files(&self, db_id: DbId, table_id: TableId) -> Vec<ParquetFile> {
+        self.buffer.persisted_parquet_files(db_id, table_id)
+    }
+
+    fn watch_persisted_snapshots(&self) -> Receiver<Option<PersistedSnapshot>> {
+        self.buffer.persisted_snapshot_notify_rx()
+    }
+
+    fn quarantined_wal_files(&self) -> Vec<QuarantinedWalFile> {
+        self.wal.quarantined_files()
+    }
+
+    async fn delete_rows(
+        &self,
+        db_id: DbId,
+        table_id: TableId,
+        predicate: DeletePredicate,
+    ) -> Result<()> {
+        self.delete_rows(db_id, table_id, predicate).await
+    }
+
+    async fn create_database(
+        &self,
+        database: NamespaceName<'static>,
+        retention_period: Option<Duration>,
+    ) -> Result<()> {
+        self.create_database(database, retention_period).await
+    }
+
+    async fn create_table(
+        &self,
+        database: NamespaceName<'static>,
+        table: &str,
+        tags: Vec<String>,
+        fields: Vec<(String, InfluxFieldType)>,
+    ) -> Result<()> {
+        self.create_table(database, table, tags, fields).await
+    }
+
+    async fn set_schema_policy(
+        &self,
+        db_id: DbId,
+        table_id: Option<TableId>,
+        policy: SchemaPolicy,
+    ) -> Result<()> {
+        self.set_schema_policy(db_id, table_id, policy).await
+    }
+
+    async fn set_coercion_policy(
+        &self,
+        db_id: DbId,
+        table_id: TableId,
+        policy: CoercionPolicy,
+    ) -> Result<()> {
+        self.set_coercion_policy(db_id, table_id, policy).await
+    }
+
+    async fn set_json_mapping(
+        &self,
+        db_id: DbId,
+        table_id: TableId,
+        mapping: Option<JsonMapping>,
+    ) -> Result<()> {
+        self.set_json_mapping(db_id, table_id, mapping).await
+    }
+
+    async fn set_retention_period(
+        &self,
+        db_id: DbId,
+        retention_period: Option<Duration>,
+    ) -> Result<()> {
+        self.set_retention_period(db_id, retention_period).await
+    }
+
+    async fn set_quotas(&self, db_id: DbId, quotas: DatabaseQuotas) -> Result<()> {
+        self.set_quotas(db_id, quotas).await
+    }
+
+    fn quota_usage(&self, db_id: DbId) -> Vec<QuotaUsage> {
+        self.quota_usage(db_id)
+    }
+
+    fn rejected_writes(&self) -> Arc<RejectedWrites> {
+        Arc::clone(&self.rejected_writes)
+    }
+
+    fn retention_cutoff_ns(&self, db_id: DbId) -> Option<i64> {
+        self.catalog
+            .db_schema_by_id(db_id)?
+            .retention_cutoff_ns(self.time_provider.now().timestamp_nanos())
+    }
+
+    fn export_catalog(&self) -> CatalogExport {
+        self.catalog.export()
+    }
+
+    async fn import_catalog(&self, export: &CatalogExport, dry_run: bool) -> Result<ImportDiff> {
+        self.import_catalog(export, dry_run).await
+    }
+
+    async fn rename_table(&self, db_id: DbId, table_id: TableId, new_name: &str) -> Result<()> {
+        self.rename_table(db_id, table_id, new_name).await
+    }
+
+    async fn rename_column(
+        &self,
+        db_id: DbId,
+        table_id: TableId,
+        column_name: &str,
+        new_name: &str,
+    ) -> Result<()> {
+        self.rename_column(db_id, table_id, column_name, new_name)
+            .await
+    }
+
+    async fn delete_database(&self, db_id: DbId) -> Result<()> {
+        self.delete_database(db_id).await
+    }
+
+    async fn delete_table(&self, db_id: DbId, table_id: TableId) -> Result<()> {
+        self.delete_table(db_id, table_id).await
+    }
+
+    fn write_subscriptions(&self) -> Arc<WriteSubscriptions> {
+        Arc::clone(&self.write_subscriptions)
+    }
+}
+
+impl ChunkContainer for WriteBufferImpl {
+    fn get_table_chunks(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        filters: &[Expr],
+        projection: Option<&Vec<usize>>,
+        ctx: &dyn Session,
+    ) -> crate::Result<Vec<Arc<dyn QueryChunk>>, DataFusionError> {
+        self.get_table_chunks(database_name, table_name, filters, projection, ctx)
+    }
+}
+
+#[async_trait::async_trait]
+impl LastCacheManager for WriteBufferImpl {
+    fn last_cache_provider(&self) -> Arc<LastCacheProvider> {
+        Arc::clone(&self.last_cache)
+    }
+
+    /// Create a new last-N-value cache in the specified database and table, along with the given
+    /// parameters.
+    ///
+    /// Returns the name of the newly created cache, or `None` if a cache was not created, but the
+    /// provided parameters match those of an existing cache.
+    #[allow(clippy::too_many_arguments)]
+    async fn create_last_cache(
+        &self,
+        db_id: DbId,
+        table_id: TableId,
+        cache_name: Option<&str>,
+        count: Option<usize>,
+        ttl: Option<Duration>,
+        key_columns: Option<Vec<String>>,
+        value_columns: Option<Vec<String>>,
+    ) -> Result<Option<LastCacheDefinition>, Error> {
+        self.ensure_writable()?;
+        let cache_name = cache_name.map(Into::into);
+        let catalog = self.catalog();
+        let db_schema = catalog
+            .db_schema_by_id(db_id)
+            .ok_or(Error::DbDoesNotExist)?;
+        let schema = db_schema
+            .table_schema_by_id(table_id)
+            .ok_or(Error::TableDoesNotExist)?;
+
+        if let Some(info) = self.last_cache.create_cache(CreateCacheArguments {
+            db_id,
+            db_name: db_schema.name.to_string(),
+            table_id,
+            table_name: db_schema
+                .table_id_to_name(table_id)
+                .expect("table exists")
+                .to_string(),
+            schema,
+            cache_name,
+            count,
+            ttl,
+            key_columns,
+            value_columns,
+        })? {
+            let add_cache_catalog_batch = CatalogBatch {
+                time_ns: self.time_provider.now().timestamp_nanos(),
+                database_id: db_schema.id,
+                database_name: Arc::clone(&db_schema.name),
+                ops: vec![CreateLastCache(info.clone())],
+            };
+            self.catalog.apply_catalog_batch(&add_cache_catalog_batch)?;
+            self.wal
+                .write_ops(vec![WalOp::Catalog(add_cache_catalog_batch)])
+                .await?;
+
+            Ok(Some(info))
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn delete_last_cache(
+        &self,
+        db_id: DbId,
+        tbl_id: TableId,
+        cache_name: &str,
+    ) -> crate::Result<(), self::Error> {
+        self.ensure_writable()?;
+        let catalog = self.catalog();
+        let db_schema = catalog.db_schema_by_id(db_id).expect("db should exist");
+        self.last_cache.delete_cache(db_id, tbl_id, cache_name)?;
+        let delete_cache_catalog_batch = CatalogBatch {
+            time_ns: self.time_provider.now().timestamp_nanos(),
+            database_id: db_id,
+            database_name: Arc::clone(&db_schema.name),
+            ops: vec![CatalogOp::DeleteLastCache(LastCacheDelete {
+                table_id: tbl_id,
+                table_name: db_schema
+                    .table_id_to_name(tbl_id)
+                    .expect("table exists")
+                    .to_string(),
+                name: cache_name.into(),
+            })],
+        };
+        catalog.apply_catalog_batch(&delete_cache_catalog_batch)?;
+
+        // NOTE: if this fails then the cache will be gone from the running server, but will be
+        // resurrected on server restart.
+        self.wal
+            .write_ops(vec![WalOp::Catalog(delete_cache_catalog_batch)])
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl WriteBuffer for WriteBufferImpl {}
+
+#[cfg(test)]
+#[allow(clippy::await_holding_lock)]
+mod tests {
+    use super::*;
+    use crate::parquet_cache::test_cached_obj_store_and_oracle;
+    use crate::paths::{CatalogFilePath, SnapshotInfoFilePath};
+    use crate::persister::Persister;
+    use crate::restore::{restore, RestorePoint};
+    use crate::PersistedSnapshot;
+    use arrow::record_batch::RecordBatch;
+    use arrow_util::{assert_batches_eq, assert_batches_sorted_eq};
+    use bytes::Bytes;
+    use datafusion_util::config::register_iox_object_store;
+    use futures_util::StreamExt;
+    use influxdb3_catalog::catalog::SequenceNumber;
+    use influxdb3_id::{DbId, ParquetFileId};
+    use influxdb3_test_helpers::object_store::RequestCountedObjectStore;
+    use influxdb3_wal::{
+        Gen1Duration, JsonFieldMapping, JsonTimestampFormat, JsonTimestampMapping, ReplicaConfig,
+        SnapshotSequenceNumber, WalCompression, WalFileSequenceNumber,
+    };
+    use iox_query::exec::IOxSessionContext;
+    use iox_time::{MockProvider, Time};
+    use object_store::local::LocalFileSystem;
+    use object_store::memory::InMemory;
+    use object_store::{ObjectStore, PutPayload};
+
+    #[test]
+    fn parse_lp_into_buffer() {
+        let host_id = Arc::from("sample-host-id");
+        let instance_id = Arc::from("sample-instance-id");
+        let catalog = Arc::new(Catalog::new(host_id, instance_id));
+        let db_name = NamespaceName::new("foo").unwrap();
+        let lp = "cpu,region=west user=23.2 100\nfoo f1=1i";
+        let result = WriteValidator::initialize(db_name, Arc::clone(&catalog), 0)
+            .unwrap()
+            .v1_parse_lines_and_update_schema(lp, false)
+            .unwrap()
+            .convert_lines_to_buffer(
+                Time::from_timestamp_nanos(0),
+                Gen1Duration::new_5m(),
+                Precision::Nanosecond,
+            );
+        catalog
+            .apply_catalog_batch(&result.catalog_updates.unwrap())
+            .unwrap();
+
+        let db = catalog.db_schema_by_id(DbId::from(0)).unwrap();
+
+        assert_eq!(db.tables.len(), 2);
+        // cpu table
+        assert_eq!(db.tables.get(&TableId::from(0)).unwrap().num_columns(), 3);
+        // foo table
+        assert_eq!(db.tables.get(&TableId::from(1)).unwrap().num_columns(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn writes_data_to_wal_and_is_queryable() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let time_provider: Arc<dyn TimeProvider> =
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
         let (object_store, parquet_cache) =
             test_cached_obj_store_and_oracle(object_store, Arc::clone(&time_provider));
         let persister = Arc::new(Persister::new(Arc::clone(&object_store), "test_host"));
@@ -593,22 +1754,320 @@ mod tests {
             Arc::clone(&persister),
             catalog,
             Arc::new(last_cache),
-            Arc::clone(&time_provider),
-            crate::test_help::make_exec(),
-            WalConfig::test_config(),
-            Some(Arc::clone(&parquet_cache)),
+            Arc::clone(&time_provider),
+            crate::test_help::make_exec(),
+            WalConfig::test_config(),
+            WalBackend::ObjectStore,
+            Arc::new(metric::Registry::default()),
+            Some(Arc::clone(&parquet_cache)),
+            RejectedWritesConfig::default(),
+        )
+        .await
+        .unwrap();
+        let session_context = IOxSessionContext::with_testing();
+        let runtime_env = session_context.inner().runtime_env();
+        register_iox_object_store(runtime_env, "influxdb3", Arc::clone(&object_store));
+
+        let _ = write_buffer
+            .write_lp(
+                NamespaceName::new("foo").unwrap(),
+                "cpu bar=1 10",
+                Time::from_timestamp_nanos(123),
+                false,
+                Precision::Nanosecond,
+            )
+            .await
+            .unwrap();
+
+        let expected = [
+            "+-----+--------------------------------+",
+            "| bar | time                           |",
+            "+-----+--------------------------------+",
+            "| 1.0 | 1970-01-01T00:00:00.000000010Z |",
+            "+-----+--------------------------------+",
+        ];
+        let actual = get_table_batches(&write_buffer, "foo", "cpu", &session_context).await;
+        assert_batches_eq!(&expected, &actual);
+
+        // do two more writes to trigger a snapshot
+        let _ = write_buffer
+            .write_lp(
+                NamespaceName::new("foo").unwrap(),
+                "cpu bar=2 20",
+                Time::from_timestamp_nanos(124),
+                false,
+                Precision::Nanosecond,
+            )
+            .await
+            .unwrap();
+
+        let _ = write_buffer
+            .write_lp(
+                NamespaceName::new("foo").unwrap(),
+                "cpu bar=3 30",
+                Time::from_timestamp_nanos(125),
+                false,
+                Precision::Nanosecond,
+            )
+            .await;
+
+        // query the buffer and make sure we get the data back
+        let expected = [
+            "+-----+--------------------------------+",
+            "| bar | time                           |",
+            "+-----+--------------------------------+",
+            "| 1.0 | 1970-01-01T00:00:00.000000010Z |",
+            "| 2.0 | 1970-01-01T00:00:00.000000020Z |",
+            "| 3.0 | 1970-01-01T00:00:00.000000030Z |",
+            "+-----+--------------------------------+",
+        ];
+        let actual = get_table_batches(&write_buffer, "foo", "cpu", &session_context).await;
+        assert_batches_eq!(&expected, &actual);
+
+        // now load a new buffer from object storage
+        let catalog = Arc::new(persister.load_or_create_catalog().await.unwrap());
+        let last_cache =
+            LastCacheProvider::new_from_db_schema_provider(Arc::clone(&catalog) as _).unwrap();
+        let write_buffer = WriteBufferImpl::new(
+            Arc::clone(&persister),
+            catalog,
+            Arc::new(last_cache),
+            Arc::clone(&time_provider),
+            crate::test_help::make_exec(),
+            WalConfig {
+                gen1_duration: Gen1Duration::new_1m(),
+                max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
+                flush_interval: Duration::from_millis(50),
+                snapshot_size: 100,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
+            },
+            WalBackend::ObjectStore,
+            Arc::new(metric::Registry::default()),
+            Some(Arc::clone(&parquet_cache)),
+            RejectedWritesConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        let actual = get_table_batches(&write_buffer, "foo", "cpu", &session_context).await;
+        assert_batches_eq!(&expected, &actual);
+    }
+
+    #[tokio::test]
+    async fn last_cache_create_and_delete_is_durable() {
+        let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let (wbuf, _ctx) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&obj_store),
+            WalConfig {
+                gen1_duration: Gen1Duration::new_1m(),
+                max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
+                flush_interval: Duration::from_millis(10),
+                snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
+            },
+        )
+        .await;
+        let db_name = "db";
+        let db_id = DbId::from(0);
+        let tbl_name = "table";
+        let tbl_id = TableId::from(0);
+        let cache_name = "cache";
+        // Write some data to the current segment and update the catalog:
+        wbuf.write_lp(
+            NamespaceName::new(db_name).unwrap(),
+            format!("{tbl_name},t1=a f1=true").as_str(),
+            Time::from_timestamp(20, 0).unwrap(),
+            false,
+            Precision::Nanosecond,
+        )
+        .await
+        .unwrap();
+        // Create a last cache:
+        wbuf.create_last_cache(db_id, tbl_id, Some(cache_name), None, None, None, None)
+            .await
+            .unwrap();
+
+        // load a new write buffer to ensure its durable
+        let catalog = Arc::new(wbuf.persister.load_or_create_catalog().await.unwrap());
+        let last_cache =
+            LastCacheProvider::new_from_db_schema_provider(Arc::clone(&catalog) as _).unwrap();
+        let wbuf = WriteBufferImpl::new(
+            Arc::clone(&wbuf.persister),
+            catalog,
+            Arc::new(last_cache),
+            Arc::clone(&wbuf.time_provider),
+            Arc::clone(&wbuf.buffer.executor),
+            WalConfig {
+                gen1_duration: Gen1Duration::new_1m(),
+                max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
+                flush_interval: Duration::from_millis(10),
+                snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
+            },
+            WalBackend::ObjectStore,
+            Arc::new(metric::Registry::default()),
+            wbuf.parquet_cache.clone(),
+            RejectedWritesConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        let catalog_json = catalog_to_json(&wbuf.catalog);
+        insta::assert_json_snapshot!("catalog-immediately-after-last-cache-create",
+            catalog_json,
+            { ".instance_id" => "[uuid]" }
+        );
+
+        // Do another write that will update the state of the catalog, specifically, the table
+        // that the last cache was created for, and add a new field to the table/cache `f2`:
+        wbuf.write_lp(
+            NamespaceName::new(db_name).unwrap(),
+            format!("{tbl_name},t1=a f1=false,f2=42i").as_str(),
+            Time::from_timestamp(30, 0).unwrap(),
+            false,
+            Precision::Nanosecond,
+        )
+        .await
+        .unwrap();
+
+        // and do another replay and verification
+        let catalog = Arc::new(wbuf.persister.load_or_create_catalog().await.unwrap());
+        let last_cache =
+            LastCacheProvider::new_from_db_schema_provider(Arc::clone(&catalog) as _).unwrap();
+        let wbuf = WriteBufferImpl::new(
+            Arc::clone(&wbuf.persister),
+            catalog,
+            Arc::new(last_cache),
+            Arc::clone(&wbuf.time_provider),
+            Arc::clone(&wbuf.buffer.executor),
+            WalConfig {
+                gen1_duration: Gen1Duration::new_1m(),
+                max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
+                flush_interval: Duration::from_millis(10),
+                snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
+            },
+            WalBackend::ObjectStore,
+            Arc::new(metric::Registry::default()),
+            wbuf.parquet_cache.clone(),
+            RejectedWritesConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        let catalog_json = catalog_to_json(&wbuf.catalog);
+        insta::assert_json_snapshot!(
+           "catalog-after-last-cache-create-and-new-field",
+           catalog_json,
+           { ".instance_id" => "[uuid]" }
+        );
+
+        // write a new data point to fill the cache
+        wbuf.write_lp(
+            NamespaceName::new(db_name).unwrap(),
+            format!("{tbl_name},t1=a f1=true,f2=53i").as_str(),
+            Time::from_timestamp(40, 0).unwrap(),
+            false,
+            Precision::Nanosecond,
+        )
+        .await
+        .unwrap();
+
+        // Fetch record batches from the last cache directly:
+        let expected = [
+            "+----+------+----+----------------------+",
+            "| t1 | f1   | f2 | time                 |",
+            "+----+------+----+----------------------+",
+            "| a  | true | 53 | 1970-01-01T00:00:40Z |",
+            "+----+------+----+----------------------+",
+        ];
+        let actual = wbuf
+            .last_cache_provider()
+            .get_cache_record_batches(db_id, tbl_id, None, &[])
+            .unwrap()
+            .unwrap();
+        assert_batches_eq!(&expected, &actual);
+        // Delete the last cache:
+        wbuf.delete_last_cache(db_id, tbl_id, cache_name)
+            .await
+            .unwrap();
+
+        // do another reload and verify it's gone
+        let catalog = Arc::new(wbuf.persister.load_or_create_catalog().await.unwrap());
+        let last_cache =
+            LastCacheProvider::new_from_db_schema_provider(Arc::clone(&catalog) as _).unwrap();
+        let wbuf = WriteBufferImpl::new(
+            Arc::clone(&wbuf.persister),
+            catalog,
+            Arc::new(last_cache),
+            Arc::clone(&wbuf.time_provider),
+            Arc::clone(&wbuf.buffer.executor),
+            WalConfig {
+                gen1_duration: Gen1Duration::new_1m(),
+                max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
+                flush_interval: Duration::from_millis(10),
+                snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
+            },
+            WalBackend::ObjectStore,
+            Arc::new(metric::Registry::default()),
+            wbuf.parquet_cache.clone(),
+            RejectedWritesConfig::default(),
         )
         .await
         .unwrap();
-        let session_context = IOxSessionContext::with_testing();
-        let runtime_env = session_context.inner().runtime_env();
-        register_iox_object_store(runtime_env, "influxdb3", Arc::clone(&object_store));
+        let catalog_json = catalog_to_json(&wbuf.catalog);
+        insta::assert_json_snapshot!("catalog-immediately-after-last-cache-delete",
+            catalog_json,
+            { ".instance_id" => "[uuid]" }
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn returns_chunks_across_parquet_and_buffered_data() {
+        let (write_buffer, session_context) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::new(InMemory::new()),
+            WalConfig {
+                gen1_duration: Gen1Duration::new_1m(),
+                max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
+                flush_interval: Duration::from_millis(10),
+                snapshot_size: 2,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
+            },
+        )
+        .await;
 
         let _ = write_buffer
             .write_lp(
                 NamespaceName::new("foo").unwrap(),
-                "cpu bar=1 10",
-                Time::from_timestamp_nanos(123),
+                "cpu bar=1",
+                Time::from_timestamp(10, 0).unwrap(),
                 false,
                 Precision::Nanosecond,
             )
@@ -616,141 +2075,357 @@ mod tests {
             .unwrap();
 
         let expected = [
-            "+-----+--------------------------------+",
-            "| bar | time                           |",
-            "+-----+--------------------------------+",
-            "| 1.0 | 1970-01-01T00:00:00.000000010Z |",
-            "+-----+--------------------------------+",
+            "+-----+----------------------+",
+            "| bar | time                 |",
+            "+-----+----------------------+",
+            "| 1.0 | 1970-01-01T00:00:10Z |",
+            "+-----+----------------------+",
         ];
         let actual = get_table_batches(&write_buffer, "foo", "cpu", &session_context).await;
-        assert_batches_eq!(&expected, &actual);
+        assert_batches_sorted_eq!(&expected, &actual);
 
-        // do two more writes to trigger a snapshot
         let _ = write_buffer
             .write_lp(
                 NamespaceName::new("foo").unwrap(),
-                "cpu bar=2 20",
-                Time::from_timestamp_nanos(124),
+                "cpu bar=2",
+                Time::from_timestamp(65, 0).unwrap(),
+                false,
+                Precision::Nanosecond,
+            )
+            .await
+            .unwrap();
+
+        let expected = [
+            "+-----+----------------------+",
+            "| bar | time                 |",
+            "+-----+----------------------+",
+            "| 1.0 | 1970-01-01T00:00:10Z |",
+            "| 2.0 | 1970-01-01T00:01:05Z |",
+            "+-----+----------------------+",
+        ];
+        let actual = get_table_batches(&write_buffer, "foo", "cpu", &session_context).await;
+        assert_batches_sorted_eq!(&expected, &actual);
+
+        // trigger snapshot with a third write, creating parquet files
+        let _ = write_buffer
+            .write_lp(
+                NamespaceName::new("foo").unwrap(),
+                "cpu bar=3 147000000000",
+                Time::from_timestamp(147, 0).unwrap(),
                 false,
                 Precision::Nanosecond,
             )
             .await
             .unwrap();
 
+        // give the snapshot some time to persist in the background
+        let mut ticks = 0;
+        loop {
+            ticks += 1;
+            let persisted = write_buffer.persister.load_snapshots(1000).await.unwrap();
+            if !persisted.is_empty() {
+                assert_eq!(persisted.len(), 1);
+                assert_eq!(persisted[0].min_time, 10000000000);
+                assert_eq!(persisted[0].row_count, 2);
+                break;
+            } else if ticks > 10 {
+                panic!("not persisting");
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let expected = [
+            "+-----+----------------------+",
+            "| bar | time                 |",
+            "+-----+----------------------+",
+            "| 3.0 | 1970-01-01T00:02:27Z |",
+            "| 2.0 | 1970-01-01T00:01:05Z |",
+            "| 1.0 | 1970-01-01T00:00:10Z |",
+            "+-----+----------------------+",
+        ];
+        let actual = get_table_batches(&write_buffer, "foo", "cpu", &session_context).await;
+        assert_batches_sorted_eq!(&expected, &actual);
+
+        // now validate that buffered data and parquet data are all returned
         let _ = write_buffer
             .write_lp(
                 NamespaceName::new("foo").unwrap(),
-                "cpu bar=3 30",
-                Time::from_timestamp_nanos(125),
+                "cpu bar=4",
+                Time::from_timestamp(250, 0).unwrap(),
                 false,
                 Precision::Nanosecond,
             )
-            .await;
+            .await
+            .unwrap();
+
+        let expected = [
+            "+-----+----------------------+",
+            "| bar | time                 |",
+            "+-----+----------------------+",
+            "| 3.0 | 1970-01-01T00:02:27Z |",
+            "| 4.0 | 1970-01-01T00:04:10Z |",
+            "| 2.0 | 1970-01-01T00:01:05Z |",
+            "| 1.0 | 1970-01-01T00:00:10Z |",
+            "+-----+----------------------+",
+        ];
+        let actual = get_table_batches(&write_buffer, "foo", "cpu", &session_context).await;
+        assert_batches_sorted_eq!(&expected, &actual);
+
+        let actual = get_table_batches(&write_buffer, "foo", "cpu", &session_context).await;
+        assert_batches_sorted_eq!(&expected, &actual);
+        // and now replay in a new write buffer and attempt to write
+        let catalog = Arc::new(
+            write_buffer
+                .persister
+                .load_or_create_catalog()
+                .await
+                .unwrap(),
+        );
+        let last_cache =
+            LastCacheProvider::new_from_db_schema_provider(Arc::clone(&catalog) as _).unwrap();
+        let write_buffer = WriteBufferImpl::new(
+            Arc::clone(&write_buffer.persister),
+            catalog,
+            Arc::new(last_cache),
+            Arc::clone(&write_buffer.time_provider),
+            Arc::clone(&write_buffer.buffer.executor),
+            WalConfig {
+                gen1_duration: Gen1Duration::new_1m(),
+                max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
+                flush_interval: Duration::from_millis(10),
+                snapshot_size: 2,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
+            },
+            WalBackend::ObjectStore,
+            Arc::new(metric::Registry::default()),
+            write_buffer.parquet_cache.clone(),
+            RejectedWritesConfig::default(),
+        )
+        .await
+        .unwrap();
+        let ctx = IOxSessionContext::with_testing();
+        let runtime_env = ctx.inner().runtime_env();
+        register_iox_object_store(
+            runtime_env,
+            "influxdb3",
+            write_buffer.persister.object_store(),
+        );
+
+        // verify the data is still there
+        let actual = get_table_batches(&write_buffer, "foo", "cpu", &ctx).await;
+        assert_batches_sorted_eq!(&expected, &actual);
+
+        // now write some new data
+        let _ = write_buffer
+            .write_lp(
+                NamespaceName::new("foo").unwrap(),
+                "cpu bar=5",
+                Time::from_timestamp(300, 0).unwrap(),
+                false,
+                Precision::Nanosecond,
+            )
+            .await
+            .unwrap();
+
+        // and write more to force another snapshot
+        let _ = write_buffer
+            .write_lp(
+                NamespaceName::new("foo").unwrap(),
+                "cpu bar=6",
+                Time::from_timestamp(330, 0).unwrap(),
+                false,
+                Precision::Nanosecond,
+            )
+            .await
+            .unwrap();
+
+        let expected = [
+            "+-----+----------------------+",
+            "| bar | time                 |",
+            "+-----+----------------------+",
+            "| 1.0 | 1970-01-01T00:00:10Z |",
+            "| 2.0 | 1970-01-01T00:01:05Z |",
+            "| 3.0 | 1970-01-01T00:02:27Z |",
+            "| 4.0 | 1970-01-01T00:04:10Z |",
+            "| 5.0 | 1970-01-01T00:05:00Z |",
+            "| 6.0 | 1970-01-01T00:05:30Z |",
+            "+-----+----------------------+",
+        ];
+        let actual = get_table_batches(&write_buffer, "foo", "cpu", &ctx).await;
+        assert_batches_sorted_eq!(&expected, &actual);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn catalog_snapshots_only_if_updated() {
+        let (write_buffer, _ctx) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::new(InMemory::new()),
+            WalConfig {
+                gen1_duration: Gen1Duration::new_1m(),
+                max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
+                flush_interval: Duration::from_millis(5),
+                snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
+            },
+        )
+        .await;
+
+        let db_name = "foo";
+        // do three writes to force a snapshot
+        do_writes(
+            db_name,
+            &write_buffer,
+            &[
+                TestWrite {
+                    lp: "cpu bar=1",
+                    time_seconds: 10,
+                },
+                TestWrite {
+                    lp: "cpu bar=2",
+                    time_seconds: 20,
+                },
+                TestWrite {
+                    lp: "cpu bar=3",
+                    time_seconds: 30,
+                },
+            ],
+        )
+        .await;
+
+        verify_catalog_count(2, write_buffer.persister.object_store()).await;
+        verify_snapshot_count(1, &write_buffer.persister).await;
+
+        // only another two writes are needed to trigger a snapshot, because there is still one
+        // WAL period left from before:
+        do_writes(
+            db_name,
+            &write_buffer,
+            &[
+                TestWrite {
+                    lp: "cpu bar=4",
+                    time_seconds: 40,
+                },
+                TestWrite {
+                    lp: "cpu bar=5",
+                    time_seconds: 50,
+                },
+            ],
+        )
+        .await;
 
-        // query the buffer and make sure we get the data back
-        let expected = [
-            "+-----+--------------------------------+",
-            "| bar | time                           |",
-            "+-----+--------------------------------+",
-            "| 1.0 | 1970-01-01T00:00:00.000000010Z |",
-            "| 2.0 | 1970-01-01T00:00:00.000000020Z |",
-            "| 3.0 | 1970-01-01T00:00:00.000000030Z |",
-            "+-----+--------------------------------+",
-        ];
-        let actual = get_table_batches(&write_buffer, "foo", "cpu", &session_context).await;
-        assert_batches_eq!(&expected, &actual);
+        // verify the catalog didn't get persisted, but a snapshot did
+        verify_catalog_count(2, write_buffer.persister.object_store()).await;
+        verify_snapshot_count(2, &write_buffer.persister).await;
 
-        // now load a new buffer from object storage
-        let catalog = Arc::new(persister.load_or_create_catalog().await.unwrap());
-        let last_cache =
-            LastCacheProvider::new_from_db_schema_provider(Arc::clone(&catalog) as _).unwrap();
-        let write_buffer = WriteBufferImpl::new(
-            Arc::clone(&persister),
-            catalog,
-            Arc::new(last_cache),
-            Arc::clone(&time_provider),
-            crate::test_help::make_exec(),
-            WalConfig {
-                gen1_duration: Gen1Duration::new_1m(),
-                max_write_buffer_size: 100,
-                flush_interval: Duration::from_millis(50),
-                snapshot_size: 100,
-            },
-            Some(Arc::clone(&parquet_cache)),
+        // and finally, do two more, with a catalog update, forcing persistence
+        do_writes(
+            db_name,
+            &write_buffer,
+            &[
+                TestWrite {
+                    lp: "cpu bar=6,asdf=true",
+                    time_seconds: 60,
+                },
+                TestWrite {
+                    lp: "cpu bar=7,asdf=true",
+                    time_seconds: 70,
+                },
+            ],
         )
-        .await
-        .unwrap();
+        .await;
 
-        let actual = get_table_batches(&write_buffer, "foo", "cpu", &session_context).await;
-        assert_batches_eq!(&expected, &actual);
+        verify_catalog_count(3, write_buffer.persister.object_store()).await;
+        verify_snapshot_count(3, &write_buffer.persister).await;
     }
 
+    /// Check that when a WriteBuffer is initialized with existing snapshot files, that newly
+    /// generated snapshot files use the next sequence number.
     #[tokio::test]
-    async fn last_cache_create_and_delete_is_durable() {
-        let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+    async fn new_snapshots_use_correct_sequence() {
+        // set up a local file system object store:
+        let object_store: Arc<dyn ObjectStore> =
+            Arc::new(LocalFileSystem::new_with_prefix(test_helpers::tmp_dir().unwrap()).unwrap());
+
+        // create a snapshot file that will be loaded on initialization of the write buffer:
+        // Set ParquetFileId to a non zero number for the snapshot
+        ParquetFileId::from(500).set_next_id();
+        let prev_snapshot_seq = SnapshotSequenceNumber::new(42);
+        let prev_snapshot = PersistedSnapshot::new(
+            "test_host".to_string(),
+            prev_snapshot_seq,
+            WalFileSequenceNumber::new(0),
+            SequenceNumber::new(0),
+        );
+        let snapshot_json = serde_json::to_vec(&prev_snapshot).unwrap();
+        // set ParquetFileId to be 0 so that we can make sure when it's loaded from the
+        // snapshot that it becomes the expected number
+        ParquetFileId::from(0).set_next_id();
+
+        // put the snapshot file in object store:
+        object_store
+            .put(
+                &SnapshotInfoFilePath::new("test_host", prev_snapshot_seq),
+                PutPayload::from_bytes(Bytes::from(snapshot_json)),
+            )
+            .await
+            .unwrap();
+
+        // setup the write buffer:
         let (wbuf, _ctx) = setup(
             Time::from_timestamp_nanos(0),
-            Arc::clone(&obj_store),
+            Arc::clone(&object_store),
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 100,
-                flush_interval: Duration::from_millis(10),
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
+                flush_interval: Duration::from_millis(5),
                 snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
             },
         )
         .await;
-        let db_name = "db";
-        let db_id = DbId::from(0);
-        let tbl_name = "table";
-        let tbl_id = TableId::from(0);
-        let cache_name = "cache";
-        // Write some data to the current segment and update the catalog:
+
+        // Assert that loading the snapshots sets ParquetFileId to the correct id number
+        assert_eq!(ParquetFileId::new().as_u64(), 500);
+
+        // there should be one snapshot already, i.e., the one we created above:
+        verify_snapshot_count(1, &wbuf.persister).await;
+        // there is only one initial catalog so far:
+        verify_catalog_count(1, object_store.clone()).await;
+
+        // do three writes to force a new snapshot
         wbuf.write_lp(
-            NamespaceName::new(db_name).unwrap(),
-            format!("{tbl_name},t1=a f1=true").as_str(),
-            Time::from_timestamp(20, 0).unwrap(),
+            NamespaceName::new("foo").unwrap(),
+            "cpu bar=1",
+            Time::from_timestamp(10, 0).unwrap(),
             false,
             Precision::Nanosecond,
         )
         .await
         .unwrap();
-        // Create a last cache:
-        wbuf.create_last_cache(db_id, tbl_id, Some(cache_name), None, None, None, None)
-            .await
-            .unwrap();
-
-        // load a new write buffer to ensure its durable
-        let catalog = Arc::new(wbuf.persister.load_or_create_catalog().await.unwrap());
-        let last_cache =
-            LastCacheProvider::new_from_db_schema_provider(Arc::clone(&catalog) as _).unwrap();
-        let wbuf = WriteBufferImpl::new(
-            Arc::clone(&wbuf.persister),
-            catalog,
-            Arc::new(last_cache),
-            Arc::clone(&wbuf.time_provider),
-            Arc::clone(&wbuf.buffer.executor),
-            WalConfig {
-                gen1_duration: Gen1Duration::new_1m(),
-                max_write_buffer_size: 100,
-                flush_interval: Duration::from_millis(10),
-                snapshot_size: 1,
-            },
-            wbuf.parquet_cache.clone(),
+        wbuf.write_lp(
+            NamespaceName::new("foo").unwrap(),
+            "cpu bar=2",
+            Time::from_timestamp(20, 0).unwrap(),
+            false,
+            Precision::Nanosecond,
         )
         .await
         .unwrap();
-
-        let catalog_json = catalog_to_json(&wbuf.catalog);
-        insta::assert_json_snapshot!("catalog-immediately-after-last-cache-create",
-            catalog_json,
-            { ".instance_id" => "[uuid]" }
-        );
-
-        // Do another write that will update the state of the catalog, specifically, the table
-        // that the last cache was created for, and add a new field to the table/cache `f2`:
         wbuf.write_lp(
-            NamespaceName::new(db_name).unwrap(),
-            format!("{tbl_name},t1=a f1=false,f2=42i").as_str(),
+            NamespaceName::new("foo").unwrap(),
+            "cpu bar=3",
             Time::from_timestamp(30, 0).unwrap(),
             false,
             Precision::Nanosecond,
@@ -758,570 +2433,1103 @@ mod tests {
         .await
         .unwrap();
 
-        // and do another replay and verification
-        let catalog = Arc::new(wbuf.persister.load_or_create_catalog().await.unwrap());
-        let last_cache =
-            LastCacheProvider::new_from_db_schema_provider(Arc::clone(&catalog) as _).unwrap();
-        let wbuf = WriteBufferImpl::new(
-            Arc::clone(&wbuf.persister),
-            catalog,
-            Arc::new(last_cache),
-            Arc::clone(&wbuf.time_provider),
-            Arc::clone(&wbuf.buffer.executor),
-            WalConfig {
-                gen1_duration: Gen1Duration::new_1m(),
-                max_write_buffer_size: 100,
-                flush_interval: Duration::from_millis(10),
-                snapshot_size: 1,
-            },
-            wbuf.parquet_cache.clone(),
-        )
-        .await
-        .unwrap();
+        // Check that there are now 2 snapshots:
+        verify_snapshot_count(2, &wbuf.persister).await;
+        // Check that the next sequence number is used for the new snapshot:
+        assert_eq!(
+            prev_snapshot_seq.next(),
+            wbuf.wal.last_snapshot_sequence_number().await
+        );
+        // There should be a catalog now, since the above writes updated the catalog
+        verify_catalog_count(2, object_store.clone()).await;
+        // Check the catalog sequence number in the latest snapshot is correct:
+        let persisted_snapshot_bytes = object_store
+            .get(&SnapshotInfoFilePath::new(
+                "test_host",
+                prev_snapshot_seq.next(),
+            ))
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let persisted_snapshot =
+            serde_json::from_slice::<PersistedSnapshot>(&persisted_snapshot_bytes).unwrap();
+        // NOTE: it appears that writes which create a new db increment the catalog sequence twice.
+        // This is likely due to the catalog sequence being incremented first for the db creation and
+        // then again for the updates to the table written to. Hence the sequence number is 2 here.
+        // If we manage to make it so that scenario only increments the catalog sequence once, then
+        // this assertion may fail:
+        assert_eq!(
+            SequenceNumber::new(2),
+            persisted_snapshot.catalog_sequence_number
+        );
+    }
+
+    #[tokio::test]
+    async fn next_id_is_correct_number() {
+        // set up a local file system object store:
+        let object_store: Arc<dyn ObjectStore> =
+            Arc::new(LocalFileSystem::new_with_prefix(test_helpers::tmp_dir().unwrap()).unwrap());
+
+        let prev_snapshot_seq = SnapshotSequenceNumber::new(42);
+        let mut prev_snapshot = PersistedSnapshot::new(
+            "test_host".to_string(),
+            prev_snapshot_seq,
+            WalFileSequenceNumber::new(0),
+            SequenceNumber::new(0),
+        );
+
+        assert_eq!(prev_snapshot.next_file_id.as_u64(), 0);
+        assert_eq!(prev_snapshot.next_table_id.as_u32(), 0);
+        assert_eq!(prev_snapshot.next_db_id.as_u32(), 0);
+
+        for _ in 0..=5 {
+            prev_snapshot.add_parquet_file(
+                DbId::from(0),
+                TableId::from(0),
+                ParquetFile {
+                    id: ParquetFileId::new(),
+                    path: "file/path2".into(),
+                    size_bytes: 20,
+                    row_count: 1,
+                    chunk_time: 1,
+                    min_time: 0,
+                    max_time: 1,
+                    wal_file_sequence_number: WalFileSequenceNumber::new(0),
+                    column_ids: Default::default(),
+                },
+            );
+        }
+
+        assert_eq!(prev_snapshot.databases.len(), 1);
+        let files = prev_snapshot.databases[&DbId::from(0)].tables[&TableId::from(0)].clone();
 
-        let catalog_json = catalog_to_json(&wbuf.catalog);
-        insta::assert_json_snapshot!(
-           "catalog-after-last-cache-create-and-new-field",
-           catalog_json,
-           { ".instance_id" => "[uuid]" }
-        );
+        // Assert that all of the files are smaller than the next_file_id field
+        // and that their index corresponds to the order they were added in
+        assert_eq!(prev_snapshot.next_file_id.as_u64(), 6);
+        assert_eq!(files.len(), 6);
+        for (i, file) in files.iter().enumerate() {
+            assert_ne!(file.id, ParquetFileId::from(6));
+            assert!(file.id.as_u64() < 6);
+            assert_eq!(file.id.as_u64(), i as u64);
+        }
 
-        // write a new data point to fill the cache
-        wbuf.write_lp(
-            NamespaceName::new(db_name).unwrap(),
-            format!("{tbl_name},t1=a f1=true,f2=53i").as_str(),
-            Time::from_timestamp(40, 0).unwrap(),
-            false,
-            Precision::Nanosecond,
-        )
-        .await
-        .unwrap();
+        let snapshot_json = serde_json::to_vec(&prev_snapshot).unwrap();
 
-        // Fetch record batches from the last cache directly:
-        let expected = [
-            "+----+------+----+----------------------+",
-            "| t1 | f1   | f2 | time                 |",
-            "+----+------+----+----------------------+",
-            "| a  | true | 53 | 1970-01-01T00:00:40Z |",
-            "+----+------+----+----------------------+",
-        ];
-        let actual = wbuf
-            .last_cache_provider()
-            .get_cache_record_batches(db_id, tbl_id, None, &[])
-            .unwrap()
-            .unwrap();
-        assert_batches_eq!(&expected, &actual);
-        // Delete the last cache:
-        wbuf.delete_last_cache(db_id, tbl_id, cache_name)
+        // put the snapshot file in object store:
+        object_store
+            .put(
+                &SnapshotInfoFilePath::new("test_host", prev_snapshot_seq),
+                PutPayload::from_bytes(Bytes::from(snapshot_json)),
+            )
             .await
             .unwrap();
 
-        // do another reload and verify it's gone
-        let catalog = Arc::new(wbuf.persister.load_or_create_catalog().await.unwrap());
-        let last_cache =
-            LastCacheProvider::new_from_db_schema_provider(Arc::clone(&catalog) as _).unwrap();
-        let wbuf = WriteBufferImpl::new(
-            Arc::clone(&wbuf.persister),
-            catalog,
-            Arc::new(last_cache),
-            Arc::clone(&wbuf.time_provider),
-            Arc::clone(&wbuf.buffer.executor),
+        // setup the write buffer:
+        let (_wbuf, _ctx) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&object_store),
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 100,
-                flush_interval: Duration::from_millis(10),
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
+                flush_interval: Duration::from_millis(5),
                 snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
             },
-            wbuf.parquet_cache.clone(),
         )
-        .await
-        .unwrap();
-        let catalog_json = catalog_to_json(&wbuf.catalog);
-        insta::assert_json_snapshot!("catalog-immediately-after-last-cache-delete",
-            catalog_json,
-            { ".instance_id" => "[uuid]" }
-        );
+        .await;
+
+        // Test that the next_file_id has been set properly
+        assert_eq!(ParquetFileId::next_id().as_u64(), 6);
     }
 
-    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
-    async fn returns_chunks_across_parquet_and_buffered_data() {
-        let (write_buffer, session_context) = setup(
+    /// This is the reproducer for [#25277][see]
+    ///
+    /// [see]: https://github.com/influxdata/influxdb/issues/25277
+    #[tokio::test]
+    async fn writes_not_dropped_on_snapshot() {
+        let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let (wbuf, _) = setup(
             Time::from_timestamp_nanos(0),
-            Arc::new(InMemory::new()),
+            Arc::clone(&obj_store),
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
                 flush_interval: Duration::from_millis(10),
-                snapshot_size: 2,
+                snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
             },
         )
         .await;
 
-        let _ = write_buffer
-            .write_lp(
-                NamespaceName::new("foo").unwrap(),
-                "cpu bar=1",
-                Time::from_timestamp(10, 0).unwrap(),
-                false,
-                Precision::Nanosecond,
-            )
-            .await
-            .unwrap();
+        let db_name = "coffee_shop";
+        let tbl_name = "menu";
 
-        let expected = [
-            "+-----+----------------------+",
-            "| bar | time                 |",
-            "+-----+----------------------+",
-            "| 1.0 | 1970-01-01T00:00:10Z |",
-            "+-----+----------------------+",
-        ];
-        let actual = get_table_batches(&write_buffer, "foo", "cpu", &session_context).await;
-        assert_batches_sorted_eq!(&expected, &actual);
+        // do some writes to get a snapshot:
+        do_writes(
+            db_name,
+            &wbuf,
+            &[
+                TestWrite {
+                    lp: format!("{tbl_name},name=espresso price=2.50"),
+                    time_seconds: 1,
+                },
+                TestWrite {
+                    lp: format!("{tbl_name},name=americano price=3.00"),
+                    time_seconds: 2,
+                },
+                TestWrite {
+                    lp: format!("{tbl_name},name=latte price=4.50"),
+                    time_seconds: 3,
+                },
+            ],
+        )
+        .await;
 
-        let _ = write_buffer
-            .write_lp(
-                NamespaceName::new("foo").unwrap(),
-                "cpu bar=2",
-                Time::from_timestamp(65, 0).unwrap(),
-                false,
-                Precision::Nanosecond,
-            )
-            .await
-            .unwrap();
+        // wait for snapshot to be created:
+        verify_snapshot_count(1, &wbuf.persister).await;
 
-        let expected = [
-            "+-----+----------------------+",
-            "| bar | time                 |",
-            "+-----+----------------------+",
-            "| 1.0 | 1970-01-01T00:00:10Z |",
-            "| 2.0 | 1970-01-01T00:01:05Z |",
-            "+-----+----------------------+",
-        ];
-        let actual = get_table_batches(&write_buffer, "foo", "cpu", &session_context).await;
-        assert_batches_sorted_eq!(&expected, &actual);
+        // Now drop the write buffer, and create a new one that replays:
+        drop(wbuf);
+        let (wbuf, ctx) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&obj_store),
+            WalConfig {
+                gen1_duration: Gen1Duration::new_1m(),
+                max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
+                flush_interval: Duration::from_millis(10),
+                snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
+            },
+        )
+        .await;
 
-        // trigger snapshot with a third write, creating parquet files
-        let _ = write_buffer
-            .write_lp(
-                NamespaceName::new("foo").unwrap(),
-                "cpu bar=3 147000000000",
-                Time::from_timestamp(147, 0).unwrap(),
-                false,
-                Precision::Nanosecond,
-            )
-            .await
-            .unwrap();
+        // Get the record batches from replayed buffer:
+        let batches = get_table_batches(&wbuf, db_name, tbl_name, &ctx).await;
+        assert_batches_sorted_eq!(
+            [
+                "+-----------+-------+----------------------+",
+                "| name      | price | time                 |",
+                "+-----------+-------+----------------------+",
+                "| americano | 3.0   | 1970-01-01T00:00:02Z |",
+                "| espresso  | 2.5   | 1970-01-01T00:00:01Z |",
+                "| latte     | 4.5   | 1970-01-01T00:00:03Z |",
+                "+-----------+-------+----------------------+",
+            ],
+            &batches
+        );
+    }
 
-        // give the snapshot some time to persist in the background
-        let mut ticks = 0;
-        loop {
-            ticks += 1;
-            let persisted = write_buffer.persister.load_snapshots(1000).await.unwrap();
-            if !persisted.is_empty() {
-                assert_eq!(persisted.len(), 1);
-                assert_eq!(persisted[0].min_time, 10000000000);
-                assert_eq!(persisted[0].row_count, 2);
-                break;
-            } else if ticks > 10 {
-                panic!("not persisting");
-            }
-            tokio::time::sleep(Duration::from_millis(10)).await;
-        }
+    #[tokio::test]
+    async fn deleted_rows_are_not_queryable_from_buffer_or_parquet() {
+        let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let wal_config = WalConfig {
+            gen1_duration: Gen1Duration::new_1m(),
+            max_write_buffer_size: 100,
+            max_write_buffer_bytes: 1024 * 1024,
+            buffer_full_timeout: Duration::from_secs(1),
+            flush_interval: Duration::from_millis(10),
+            snapshot_size: 1,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
+        };
+        let (wbuf, ctx) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&obj_store),
+            wal_config,
+        )
+        .await;
 
-        let expected = [
-            "+-----+----------------------+",
-            "| bar | time                 |",
-            "+-----+----------------------+",
-            "| 3.0 | 1970-01-01T00:02:27Z |",
-            "| 2.0 | 1970-01-01T00:01:05Z |",
-            "| 1.0 | 1970-01-01T00:00:10Z |",
-            "+-----+----------------------+",
-        ];
-        let actual = get_table_batches(&write_buffer, "foo", "cpu", &session_context).await;
-        assert_batches_sorted_eq!(&expected, &actual);
+        let db_name = "coffee_shop";
+        let tbl_name = "menu";
 
-        // now validate that buffered data and parquet data are all returned
-        let _ = write_buffer
-            .write_lp(
-                NamespaceName::new("foo").unwrap(),
-                "cpu bar=4",
-                Time::from_timestamp(250, 0).unwrap(),
-                false,
-                Precision::Nanosecond,
+        // do some writes to get a snapshot, so that some of the rows are in parquet:
+        do_writes(
+            db_name,
+            &wbuf,
+            &[
+                TestWrite {
+                    lp: format!("{tbl_name},name=espresso price=2.50"),
+                    time_seconds: 1,
+                },
+                TestWrite {
+                    lp: format!("{tbl_name},name=americano price=3.00"),
+                    time_seconds: 2,
+                },
+                TestWrite {
+                    lp: format!("{tbl_name},name=latte price=4.50"),
+                    time_seconds: 3,
+                },
+                TestWrite {
+                    lp: format!("{tbl_name},name=espresso price=2.75"),
+                    time_seconds: 4,
+                },
+            ],
+        )
+        .await;
+        verify_snapshot_count(1, &wbuf.persister).await;
+
+        let (db_id, db_schema) = wbuf.db_schema_provider().db_schema_and_id(db_name).unwrap();
+        let table_id = db_schema.table_name_to_id(tbl_name).unwrap();
+
+        // a predicate on a column that is not a tag is rejected:
+        let err = wbuf
+            .delete_rows(
+                db_id,
+                table_id,
+                DeletePredicate {
+                    min_time_ns: i64::MIN,
+                    max_time_ns: i64::MAX,
+                    tags: vec![("price".into(), "2.5".into())],
+                },
             )
             .await
-            .unwrap();
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidDeletePredicate(_)));
+
+        // delete the espresso rows, except for the last one:
+        wbuf.delete_rows(
+            db_id,
+            table_id,
+            DeletePredicate {
+                min_time_ns: i64::MIN,
+                max_time_ns: 3_000_000_000,
+                tags: vec![("name".into(), "espresso".into())],
+            },
+        )
+        .await
+        .unwrap();
 
         let expected = [
-            "+-----+----------------------+",
-            "| bar | time                 |",
-            "+-----+----------------------+",
-            "| 3.0 | 1970-01-01T00:02:27Z |",
-            "| 4.0 | 1970-01-01T00:04:10Z |",
-            "| 2.0 | 1970-01-01T00:01:05Z |",
-            "| 1.0 | 1970-01-01T00:00:10Z |",
-            "+-----+----------------------+",
+            "+-----------+-------+----------------------+",
+            "| name      | price | time                 |",
+            "+-----------+-------+----------------------+",
+            "| americano | 3.0   | 1970-01-01T00:00:02Z |",
+            "| espresso  | 2.75  | 1970-01-01T00:00:04Z |",
+            "| latte     | 4.5   | 1970-01-01T00:00:03Z |",
+            "+-----------+-------+----------------------+",
         ];
-        let actual = get_table_batches(&write_buffer, "foo", "cpu", &session_context).await;
-        assert_batches_sorted_eq!(&expected, &actual);
+        let batches = get_table_batches(&wbuf, db_name, tbl_name, &ctx).await;
+        assert_batches_sorted_eq!(expected, &batches);
 
-        let actual = get_table_batches(&write_buffer, "foo", "cpu", &session_context).await;
-        assert_batches_sorted_eq!(&expected, &actual);
-        // and now replay in a new write buffer and attempt to write
-        let catalog = Arc::new(
-            write_buffer
-                .persister
-                .load_or_create_catalog()
-                .await
-                .unwrap(),
-        );
-        let last_cache =
-            LastCacheProvider::new_from_db_schema_provider(Arc::clone(&catalog) as _).unwrap();
-        let write_buffer = WriteBufferImpl::new(
-            Arc::clone(&write_buffer.persister),
-            catalog,
-            Arc::new(last_cache),
-            Arc::clone(&write_buffer.time_provider),
-            Arc::clone(&write_buffer.buffer.executor),
-            WalConfig {
-                gen1_duration: Gen1Duration::new_1m(),
-                max_write_buffer_size: 100,
-                flush_interval: Duration::from_millis(10),
-                snapshot_size: 2,
+        // the delete is still honored after the write buffer is replayed:
+        drop(wbuf);
+        let (wbuf, ctx) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&obj_store),
+            wal_config,
+        )
+        .await;
+        let batches = get_table_batches(&wbuf, db_name, tbl_name, &ctx).await;
+        assert_batches_sorted_eq!(expected, &batches);
+    }
+
+    #[tokio::test]
+    async fn series_quota_is_enforced_after_a_restart() {
+        let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let wal_config = WalConfig {
+            gen1_duration: Gen1Duration::new_1m(),
+            max_write_buffer_size: 100,
+            max_write_buffer_bytes: 1024 * 1024,
+            buffer_full_timeout: Duration::from_secs(1),
+            flush_interval: Duration::from_millis(10),
+            snapshot_size: 1,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
+        };
+        let (wbuf, _) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&obj_store),
+            wal_config,
+        )
+        .await;
+
+        do_writes(
+            "foo",
+            &wbuf,
+            &[TestWrite {
+                lp: "cpu,host=a usage=1",
+                time_seconds: 1,
+            }],
+        )
+        .await;
+        let db_id = wbuf.catalog().db_name_to_id("foo").unwrap();
+        wbuf.set_quotas(
+            db_id,
+            DatabaseQuotas {
+                max_series: Some(2),
+                ..Default::default()
             },
-            write_buffer.parquet_cache.clone(),
         )
         .await
         .unwrap();
-        let ctx = IOxSessionContext::with_testing();
-        let runtime_env = ctx.inner().runtime_env();
-        register_iox_object_store(
-            runtime_env,
-            "influxdb3",
-            write_buffer.persister.object_store(),
-        );
-
-        // verify the data is still there
-        let actual = get_table_batches(&write_buffer, "foo", "cpu", &ctx).await;
-        assert_batches_sorted_eq!(&expected, &actual);
+        // the series counted after the quota is set are rebuilt on restart from the estimates
+        // persisted with the snapshots and from the wal:
+        do_writes(
+            "foo",
+            &wbuf,
+            &[
+                TestWrite {
+                    lp: "cpu,host=a usage=2",
+                    time_seconds: 2,
+                },
+                TestWrite {
+                    lp: "cpu,host=b usage=1",
+                    time_seconds: 3,
+                },
+                TestWrite {
+                    lp: "cpu,host=b usage=2",
+                    time_seconds: 4,
+                },
+            ],
+        )
+        .await;
 
-        // now write some new data
-        let _ = write_buffer
-            .write_lp(
-                NamespaceName::new("foo").unwrap(),
-                "cpu bar=5",
-                Time::from_timestamp(300, 0).unwrap(),
-                false,
-                Precision::Nanosecond,
-            )
-            .await
-            .unwrap();
+        drop(wbuf);
+        let (wbuf, _) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&obj_store),
+            wal_config,
+        )
+        .await;
+        let series_usage = |wbuf: &WriteBufferImpl| {
+            wbuf.quota_usage(db_id)
+                .into_iter()
+                .find(|usage| usage.quota == Quota::Series)
+                .unwrap()
+                .usage
+        };
+        assert_eq!(2, series_usage(&wbuf));
 
-        // and write more to force another snapshot
-        let _ = write_buffer
+        let err = wbuf
             .write_lp(
                 NamespaceName::new("foo").unwrap(),
-                "cpu bar=6",
-                Time::from_timestamp(330, 0).unwrap(),
+                "cpu,host=c usage=1",
+                Time::from_timestamp_nanos(5_000_000_000),
                 false,
                 Precision::Nanosecond,
             )
             .await
-            .unwrap();
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::QuotaExceeded {
+                quota: Quota::Series,
+                limit: 2,
+                ..
+            }
+        ));
+        assert_eq!(2, series_usage(&wbuf));
 
-        let expected = [
-            "+-----+----------------------+",
-            "| bar | time                 |",
-            "+-----+----------------------+",
-            "| 1.0 | 1970-01-01T00:00:10Z |",
-            "| 2.0 | 1970-01-01T00:01:05Z |",
-            "| 3.0 | 1970-01-01T00:02:27Z |",
-            "| 4.0 | 1970-01-01T00:04:10Z |",
-            "| 5.0 | 1970-01-01T00:05:00Z |",
-            "| 6.0 | 1970-01-01T00:05:30Z |",
-            "+-----+----------------------+",
-        ];
-        let actual = get_table_batches(&write_buffer, "foo", "cpu", &ctx).await;
-        assert_batches_sorted_eq!(&expected, &actual);
+        // a write that exceeds the quota does not add its table or columns to the catalog:
+        wbuf.write_lp(
+            NamespaceName::new("foo").unwrap(),
+            "cpu,host=c usage=1,idle=1\nmem,host=a free=1",
+            Time::from_timestamp_nanos(5_000_000_000),
+            false,
+            Precision::Nanosecond,
+        )
+        .await
+        .unwrap_err();
+        let db_schema = wbuf.catalog().db_schema("foo").unwrap();
+        assert!(db_schema.table_definition("mem").is_none());
+        assert!(!db_schema
+            .table_definition("cpu")
+            .unwrap()
+            .column_exists("idle"));
     }
 
-    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
-    async fn catalog_snapshots_only_if_updated() {
-        let (write_buffer, _ctx) = setup(
+    #[tokio::test]
+    async fn renamed_tables_and_columns_are_queryable_from_buffer_and_parquet() {
+        let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let wal_config = WalConfig {
+            gen1_duration: Gen1Duration::new_1m(),
+            max_write_buffer_size: 100,
+            max_write_buffer_bytes: 1024 * 1024,
+            buffer_full_timeout: Duration::from_secs(1),
+            flush_interval: Duration::from_millis(10),
+            snapshot_size: 1,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
+        };
+        let (wbuf, ctx) = setup(
             Time::from_timestamp_nanos(0),
-            Arc::new(InMemory::new()),
-            WalConfig {
-                gen1_duration: Gen1Duration::new_1m(),
-                max_write_buffer_size: 100,
-                flush_interval: Duration::from_millis(5),
-                snapshot_size: 1,
-            },
+            Arc::clone(&obj_store),
+            wal_config,
         )
         .await;
 
-        let db_name = "foo";
-        // do three writes to force a snapshot
+        // do some writes to get a snapshot, so that some of the rows are in parquet:
         do_writes(
-            db_name,
-            &write_buffer,
+            "coffee_shop",
+            &wbuf,
             &[
                 TestWrite {
-                    lp: "cpu bar=1",
-                    time_seconds: 10,
+                    lp: "menu,name=espresso price=2.50",
+                    time_seconds: 1,
                 },
                 TestWrite {
-                    lp: "cpu bar=2",
-                    time_seconds: 20,
+                    lp: "menu,name=americano price=3.00",
+                    time_seconds: 2,
                 },
                 TestWrite {
-                    lp: "cpu bar=3",
-                    time_seconds: 30,
+                    lp: "menu,name=latte price=4.50",
+                    time_seconds: 3,
+                },
+                TestWrite {
+                    lp: "menu,name=espresso price=2.75",
+                    time_seconds: 4,
                 },
             ],
         )
         .await;
+        verify_snapshot_count(1, &wbuf.persister).await;
 
-        verify_catalog_count(2, write_buffer.persister.object_store()).await;
-        verify_snapshot_count(1, &write_buffer.persister).await;
+        let (db_id, db_schema) = wbuf
+            .db_schema_provider()
+            .db_schema_and_id("coffee_shop")
+            .unwrap();
+        let table_id = db_schema.table_name_to_id("menu").unwrap();
 
-        // only another two writes are needed to trigger a snapshot, because there is still one
-        // WAL period left from before:
+        // a column cannot take the name of another column of the table:
+        let err = wbuf
+            .rename_column(db_id, table_id, "price", "name")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::CatalogUpdateError(
+                influxdb3_catalog::catalog::Error::ColumnAlreadyExists { .. }
+            )
+        ));
+
+        wbuf.rename_table(db_id, table_id, "drinks").await.unwrap();
+        wbuf.rename_column(db_id, table_id, "name", "item")
+            .await
+            .unwrap();
+        wbuf.rename_column(db_id, table_id, "price", "cost")
+            .await
+            .unwrap();
+        assert!(wbuf
+            .db_schema_provider()
+            .db_schema("coffee_shop")
+            .unwrap()
+            .table_definition("menu")
+            .is_none());
+
+        // writes and deletes after the rename use the new names:
         do_writes(
-            db_name,
-            &write_buffer,
-            &[
-                TestWrite {
-                    lp: "cpu bar=4",
-                    time_seconds: 40,
-                },
-                TestWrite {
-                    lp: "cpu bar=5",
-                    time_seconds: 50,
-                },
-            ],
+            "coffee_shop",
+            &wbuf,
+            &[TestWrite {
+                lp: "drinks,item=mocha cost=5.00",
+                time_seconds: 5,
+            }],
         )
         .await;
+        wbuf.delete_rows(
+            db_id,
+            table_id,
+            DeletePredicate {
+                min_time_ns: i64::MIN,
+                max_time_ns: 3_000_000_000,
+                tags: vec![("item".into(), "espresso".into())],
+            },
+        )
+        .await
+        .unwrap();
 
-        // verify the catalog didn't get persisted, but a snapshot did
-        verify_catalog_count(2, write_buffer.persister.object_store()).await;
-        verify_snapshot_count(2, &write_buffer.persister).await;
+        let expected = [
+            "+------+-----------+----------------------+",
+            "| cost | item      | time                 |",
+            "+------+-----------+----------------------+",
+            "| 2.75 | espresso  | 1970-01-01T00:00:04Z |",
+            "| 3.0  | americano | 1970-01-01T00:00:02Z |",
+            "| 4.5  | latte     | 1970-01-01T00:00:03Z |",
+            "| 5.0  | mocha     | 1970-01-01T00:00:05Z |",
+            "+------+-----------+----------------------+",
+        ];
+        let batches = get_table_batches(&wbuf, "coffee_shop", "drinks", &ctx).await;
+        assert_batches_sorted_eq!(expected, &batches);
 
-        // and finally, do two more, with a catalog update, forcing persistence
+        // the persisted files still have the old names, which are translated after a replay:
+        drop(wbuf);
+        let (wbuf, ctx) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&obj_store),
+            wal_config,
+        )
+        .await;
+        let batches = get_table_batches(&wbuf, "coffee_shop", "drinks", &ctx).await;
+        assert_batches_sorted_eq!(expected, &batches);
+    }
+
+    #[tokio::test]
+    async fn reused_column_name_reads_as_null_in_data_written_before_the_rename() {
+        let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let wal_config = WalConfig {
+            gen1_duration: Gen1Duration::new_1m(),
+            max_write_buffer_size: 100,
+            max_write_buffer_bytes: 1024 * 1024,
+            buffer_full_timeout: Duration::from_secs(1),
+            flush_interval: Duration::from_millis(10),
+            snapshot_size: 1,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
+        };
+        let (wbuf, ctx) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&obj_store),
+            wal_config,
+        )
+        .await;
+
+        // do some writes to get a snapshot, so that some of the rows are in parquet:
         do_writes(
-            db_name,
-            &write_buffer,
+            "coffee_shop",
+            &wbuf,
             &[
                 TestWrite {
-                    lp: "cpu bar=6,asdf=true",
-                    time_seconds: 60,
+                    lp: "menu,name=espresso price=2.50",
+                    time_seconds: 1,
                 },
                 TestWrite {
-                    lp: "cpu bar=7,asdf=true",
-                    time_seconds: 70,
+                    lp: "menu,name=americano price=3.00",
+                    time_seconds: 2,
+                },
+                TestWrite {
+                    lp: "menu,name=latte price=4.50",
+                    time_seconds: 3,
                 },
             ],
         )
         .await;
+        verify_snapshot_count(1, &wbuf.persister).await;
+
+        let (db_id, db_schema) = wbuf
+            .db_schema_provider()
+            .db_schema_and_id("coffee_shop")
+            .unwrap();
+        let table_id = db_schema.table_name_to_id("menu").unwrap();
+        wbuf.rename_column(db_id, table_id, "price", "cost")
+            .await
+            .unwrap();
+
+        // a write after the rename that uses the old name adds a new column:
+        do_writes(
+            "coffee_shop",
+            &wbuf,
+            &[TestWrite {
+                lp: "menu,name=mocha price=5.00",
+                time_seconds: 4,
+            }],
+        )
+        .await;
 
-        verify_catalog_count(3, write_buffer.persister.object_store()).await;
-        verify_snapshot_count(3, &write_buffer.persister).await;
+        let expected = [
+            "+------+-----------+-------+----------------------+",
+            "| cost | name      | price | time                 |",
+            "+------+-----------+-------+----------------------+",
+            "|      | mocha     | 5.0   | 1970-01-01T00:00:04Z |",
+            "| 2.5  | espresso  |       | 1970-01-01T00:00:01Z |",
+            "| 3.0  | americano |       | 1970-01-01T00:00:02Z |",
+            "| 4.5  | latte     |       | 1970-01-01T00:00:03Z |",
+            "+------+-----------+-------+----------------------+",
+        ];
+        let batches = get_table_batches(&wbuf, "coffee_shop", "menu", &ctx).await;
+        assert_batches_sorted_eq!(expected, &batches);
+
+        // the same is read after a replay:
+        drop(wbuf);
+        let (wbuf, ctx) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&obj_store),
+            wal_config,
+        )
+        .await;
+        let batches = get_table_batches(&wbuf, "coffee_shop", "menu", &ctx).await;
+        assert_batches_sorted_eq!(expected, &batches);
     }
 
-    /// Check that when a WriteBuffer is initialized with existing snapshot files, that newly
-    /// generated snapshot files use the next sequence number.
     #[tokio::test]
-    async fn new_snapshots_use_correct_sequence() {
-        // set up a local file system object store:
-        let object_store: Arc<dyn ObjectStore> =
-            Arc::new(LocalFileSystem::new_with_prefix(test_helpers::tmp_dir().unwrap()).unwrap());
-
-        // create a snapshot file that will be loaded on initialization of the write buffer:
-        // Set ParquetFileId to a non zero number for the snapshot
-        ParquetFileId::from(500).set_next_id();
-        let prev_snapshot_seq = SnapshotSequenceNumber::new(42);
-        let prev_snapshot = PersistedSnapshot::new(
-            "test_host".to_string(),
-            prev_snapshot_seq,
-            WalFileSequenceNumber::new(0),
-            SequenceNumber::new(0),
-        );
-        let snapshot_json = serde_json::to_vec(&prev_snapshot).unwrap();
-        // set ParquetFileId to be 0 so that we can make sure when it's loaded from the
-        // snapshot that it becomes the expected number
-        ParquetFileId::from(0).set_next_id();
+    async fn deleted_databases_and_tables_are_dropped_with_their_files() {
+        let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let wal_config = WalConfig {
+            gen1_duration: Gen1Duration::new_1m(),
+            max_write_buffer_size: 100,
+            max_write_buffer_bytes: 1024 * 1024,
+            buffer_full_timeout: Duration::from_secs(1),
+            flush_interval: Duration::from_millis(10),
+            snapshot_size: 1,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
+        };
+        let (wbuf, ctx) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&obj_store),
+            wal_config,
+        )
+        .await;
 
-        // put the snapshot file in object store:
-        object_store
-            .put(
-                &SnapshotInfoFilePath::new("test_host", prev_snapshot_seq),
-                PutPayload::from_bytes(Bytes::from(snapshot_json)),
+        // do some writes to get a snapshot, so that some of the rows are in parquet:
+        for (db_name, time_seconds) in [("foo", 1), ("bar", 2), ("foo", 3), ("bar", 4)] {
+            do_writes(
+                db_name,
+                &wbuf,
+                &[TestWrite {
+                    lp: "cpu,host=a usage=1\nmem,host=a used=2i",
+                    time_seconds,
+                }],
             )
+            .await;
+        }
+        verify_snapshot_count(1, &wbuf.persister).await;
+
+        let (foo_id, foo_schema) = wbuf.db_schema_provider().db_schema_and_id("foo").unwrap();
+        let foo_mem_id = foo_schema.table_name_to_id("mem").unwrap();
+        let (bar_id, bar_schema) = wbuf.db_schema_provider().db_schema_and_id("bar").unwrap();
+        let bar_cpu_id = bar_schema.table_name_to_id("cpu").unwrap();
+        let deleted_files = [
+            wbuf.persisted_files().get_files(foo_id, foo_mem_id),
+            wbuf.persisted_files().get_files(bar_id, bar_cpu_id),
+        ]
+        .concat();
+        assert!(!deleted_files.is_empty());
+
+        wbuf.delete_table(foo_id, foo_mem_id).await.unwrap();
+        wbuf.delete_database(bar_id).await.unwrap();
+        assert!(matches!(
+            wbuf.delete_database(bar_id).await.unwrap_err(),
+            Error::DbDoesNotExist
+        ));
+
+        // they are no longer queryable:
+        let assert_dropped = |wbuf: &WriteBufferImpl, ctx: &IOxSessionContext| {
+            assert!(wbuf.db_schema_provider().db_schema("bar").is_none());
+            assert!(wbuf
+                .get_table_chunks("foo", "mem", &[], None, &ctx.inner().state())
+                .is_err());
+            assert!(wbuf
+                .get_table_chunks("bar", "cpu", &[], None, &ctx.inner().state())
+                .is_err());
+        };
+        assert_dropped(&wbuf, &ctx);
+        assert!(wbuf
+            .persisted_files()
+            .get_files(foo_id, foo_mem_id)
+            .is_empty());
+
+        // their files are deleted in the background:
+        for file in &deleted_files {
+            let path = ObjPath::from(file.path.as_str());
+            let mut checks = 0;
+            while obj_store.head(&path).await.is_ok() {
+                checks += 1;
+                if checks > 50 {
+                    panic!("file {path} was not deleted");
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        }
+        let snapshot = wbuf
+            .persister
+            .load_snapshots(1)
             .await
+            .unwrap()
+            .pop()
             .unwrap();
+        assert!(!snapshot.databases.contains_key(&bar_id));
+        assert!(!snapshot.databases[&foo_id].tables.contains_key(&foo_mem_id));
 
-        // setup the write buffer:
-        let (wbuf, _ctx) = setup(
+        // the table that was not deleted is still there:
+        let expected = [
+            "+------+-------+----------------------+",
+            "| host | usage | time                 |",
+            "+------+-------+----------------------+",
+            "| a    | 1.0   | 1970-01-01T00:00:01Z |",
+            "| a    | 1.0   | 1970-01-01T00:00:03Z |",
+            "+------+-------+----------------------+",
+        ];
+        let batches = get_table_batches(&wbuf, "foo", "cpu", &ctx).await;
+        assert_batches_sorted_eq!(expected, &batches);
+
+        // they stay dropped after the write buffer is replayed:
+        drop(wbuf);
+        let (wbuf, ctx) = setup(
             Time::from_timestamp_nanos(0),
-            Arc::clone(&object_store),
-            WalConfig {
-                gen1_duration: Gen1Duration::new_1m(),
-                max_write_buffer_size: 100,
-                flush_interval: Duration::from_millis(5),
-                snapshot_size: 1,
-            },
+            Arc::clone(&obj_store),
+            wal_config,
         )
         .await;
+        assert_dropped(&wbuf, &ctx);
+        let batches = get_table_batches(&wbuf, "foo", "cpu", &ctx).await;
+        assert_batches_sorted_eq!(expected, &batches);
 
-        // Assert that loading the snapshots sets ParquetFileId to the correct id number
-        assert_eq!(ParquetFileId::new().as_u64(), 500);
-
-        // there should be one snapshot already, i.e., the one we created above:
-        verify_snapshot_count(1, &wbuf.persister).await;
-        // there is only one initial catalog so far:
-        verify_catalog_count(1, object_store.clone()).await;
-
-        // do three writes to force a new snapshot
-        wbuf.write_lp(
-            NamespaceName::new("foo").unwrap(),
-            "cpu bar=1",
-            Time::from_timestamp(10, 0).unwrap(),
-            false,
-            Precision::Nanosecond,
+        // and the name of a deleted database can be used again, for a new database:
+        do_writes(
+            "bar",
+            &wbuf,
+            &[TestWrite {
+                lp: "cpu,host=b usage=5",
+                time_seconds: 5,
+            }],
         )
-        .await
-        .unwrap();
-        wbuf.write_lp(
-            NamespaceName::new("foo").unwrap(),
-            "cpu bar=2",
-            Time::from_timestamp(20, 0).unwrap(),
-            false,
-            Precision::Nanosecond,
+        .await;
+        let new_bar_id = wbuf.db_schema_provider().db_name_to_id("bar").unwrap();
+        assert_ne!(bar_id, new_bar_id);
+        let batches = get_table_batches(&wbuf, "bar", "cpu", &ctx).await;
+        assert_batches_sorted_eq!(
+            [
+                "+------+-------+----------------------+",
+                "| host | usage | time                 |",
+                "+------+-------+----------------------+",
+                "| b    | 5.0   | 1970-01-01T00:00:05Z |",
+                "+------+-------+----------------------+",
+            ],
+            &batches
+        );
+    }
+
+    #[tokio::test]
+    async fn created_databases_and_tables_are_replayed() {
+        let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let wal_config = WalConfig {
+            gen1_duration: Gen1Duration::new_1m(),
+            max_write_buffer_size: 100,
+            max_write_buffer_bytes: 1024 * 1024,
+            buffer_full_timeout: Duration::from_secs(1),
+            flush_interval: Duration::from_millis(10),
+            snapshot_size: 100,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
+        };
+        let (wbuf, _ctx) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&obj_store),
+            wal_config,
         )
-        .await
-        .unwrap();
-        wbuf.write_lp(
-            NamespaceName::new("foo").unwrap(),
-            "cpu bar=3",
-            Time::from_timestamp(30, 0).unwrap(),
-            false,
-            Precision::Nanosecond,
+        .await;
+
+        wbuf.create_database(NamespaceName::new("foo").unwrap(), None)
+            .await
+            .unwrap();
+        assert!(matches!(
+            wbuf.create_database(NamespaceName::new("foo").unwrap(), None)
+                .await
+                .unwrap_err(),
+            Error::CatalogUpdateError(
+                influxdb3_catalog::catalog::Error::DatabaseAlreadyExists { .. }
+            )
+        ));
+        wbuf.create_table(
+            NamespaceName::new("bar").unwrap(),
+            "cpu",
+            vec!["host".to_string()],
+            vec![("usage".to_string(), InfluxFieldType::Float)],
         )
         .await
         .unwrap();
 
-        // Check that there are now 2 snapshots:
-        verify_snapshot_count(2, &wbuf.persister).await;
-        // Check that the next sequence number is used for the new snapshot:
-        assert_eq!(
-            prev_snapshot_seq.next(),
-            wbuf.wal.last_snapshot_sequence_number().await
-        );
-        // There should be a catalog now, since the above writes updated the catalog
-        verify_catalog_count(2, object_store.clone()).await;
-        // Check the catalog sequence number in the latest snapshot is correct:
-        let persisted_snapshot_bytes = object_store
-            .get(&SnapshotInfoFilePath::new(
-                "test_host",
-                prev_snapshot_seq.next(),
-            ))
+        // writes are validated against the declared schema:
+        let err = wbuf
+            .write_lp(
+                NamespaceName::new("bar").unwrap(),
+                "cpu,host=a usage=1i",
+                Time::from_timestamp_nanos(0),
+                false,
+                Precision::Nanosecond,
+            )
             .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ParseError(_)));
+
+        // the database and table are created again when the wal is replayed:
+        drop(wbuf);
+        let (wbuf, ctx) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&obj_store),
+            wal_config,
+        )
+        .await;
+        let foo = wbuf.db_schema_provider().db_schema("foo").unwrap();
+        assert!(foo.tables.is_empty());
+        let cpu = wbuf
+            .db_schema_provider()
+            .db_schema("bar")
             .unwrap()
-            .bytes()
-            .await
+            .table_schema("cpu")
             .unwrap();
-        let persisted_snapshot =
-            serde_json::from_slice::<PersistedSnapshot>(&persisted_snapshot_bytes).unwrap();
-        // NOTE: it appears that writes which create a new db increment the catalog sequence twice.
-        // This is likely due to the catalog sequence being incremented first for the db creation and
-        // then again for the updates to the table written to. Hence the sequence number is 2 here.
-        // If we manage to make it so that scenario only increments the catalog sequence once, then
-        // this assertion may fail:
         assert_eq!(
-            SequenceNumber::new(2),
-            persisted_snapshot.catalog_sequence_number
+            cpu.field_type_by_name("usage"),
+            Some(InfluxColumnType::Field(InfluxFieldType::Float))
+        );
+        assert_eq!(cpu.field_type_by_name("host"), Some(InfluxColumnType::Tag));
+
+        do_writes(
+            "bar",
+            &wbuf,
+            &[TestWrite {
+                lp: "cpu,host=a usage=1",
+                time_seconds: 1,
+            }],
+        )
+        .await;
+        let batches = get_table_batches(&wbuf, "bar", "cpu", &ctx).await;
+        assert_batches_sorted_eq!(
+            [
+                "+------+-------+----------------------+",
+                "| host | usage | time                 |",
+                "+------+-------+----------------------+",
+                "| a    | 1.0   | 1970-01-01T00:00:01Z |",
+                "+------+-------+----------------------+",
+            ],
+            &batches
         );
     }
 
     #[tokio::test]
-    async fn next_id_is_correct_number() {
-        // set up a local file system object store:
-        let object_store: Arc<dyn ObjectStore> =
-            Arc::new(LocalFileSystem::new_with_prefix(test_helpers::tmp_dir().unwrap()).unwrap());
+    async fn expired_rows_are_not_queried() {
+        let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let wal_config = WalConfig {
+            gen1_duration: Gen1Duration::new_1m(),
+            max_write_buffer_size: 100,
+            max_write_buffer_bytes: 1024 * 1024,
+            buffer_full_timeout: Duration::from_secs(1),
+            flush_interval: Duration::from_millis(10),
+            snapshot_size: 100,
+            compression: WalCompression::None,
+            quarantine_corrupt_files: false,
+            snapshotted_wal_retention: Duration::ZERO,
+        };
+        // the retention cutoff is at 01:00:30, which is part way through the chunk of the rows
+        // written in that minute:
+        let (wbuf, ctx) = setup(
+            Time::from_timestamp_nanos(7_230_000_000_000),
+            Arc::clone(&obj_store),
+            wal_config,
+        )
+        .await;
+        wbuf.create_database(
+            NamespaceName::new("foo").unwrap(),
+            Some(Duration::from_secs(3_600)),
+        )
+        .await
+        .unwrap();
+        do_writes(
+            "foo",
+            &wbuf,
+            &[TestWrite {
+                lp: "cpu,host=a usage=1 1800000000000\n\
+                     cpu,host=a usage=2 3610000000000\n\
+                     cpu,host=a usage=3 3650000000000\n\
+                     cpu,host=a usage=4 5400000000000",
+                time_seconds: 7_230,
+            }],
+        )
+        .await;
+        let db_id = wbuf.db_schema_provider().db_name_to_id("foo").unwrap();
+        assert_eq!(Some(3_630_000_000_000), wbuf.retention_cutoff_ns(db_id));
 
-        let prev_snapshot_seq = SnapshotSequenceNumber::new(42);
-        let mut prev_snapshot = PersistedSnapshot::new(
-            "test_host".to_string(),
-            prev_snapshot_seq,
-            WalFileSequenceNumber::new(0),
-            SequenceNumber::new(0),
+        let batches = get_table_batches(&wbuf, "foo", "cpu", &ctx).await;
+        assert_batches_sorted_eq!(
+            [
+                "+------+-------+----------------------+",
+                "| host | usage | time                 |",
+                "+------+-------+----------------------+",
+                "| a    | 3.0   | 1970-01-01T01:00:50Z |",
+                "| a    | 4.0   | 1970-01-01T01:30:00Z |",
+                "+------+-------+----------------------+",
+            ],
+            &batches
         );
 
-        assert_eq!(prev_snapshot.next_file_id.as_u64(), 0);
-        assert_eq!(prev_snapshot.next_table_id.as_u32(), 0);
-        assert_eq!(prev_snapshot.next_db_id.as_u32(), 0);
+        // without a retention period all of the rows are queried again:
+        wbuf.set_retention_period(db_id, None).await.unwrap();
+        assert_eq!(None, wbuf.retention_cutoff_ns(db_id));
+        let batches = get_table_batches(&wbuf, "foo", "cpu", &ctx).await;
+        assert_batches_sorted_eq!(
+            [
+                "+------+-------+----------------------+",
+                "| host | usage | time                 |",
+                "+------+-------+----------------------+",
+                "| a    | 1.0   | 1970-01-01T00:30:00Z |",
+                "| a    | 2.0   | 1970-01-01T01:00:10Z |",
+                "| a    | 3.0   | 1970-01-01T01:00:50Z |",
+                "| a    | 4.0   | 1970-01-01T01:30:00Z |",
+                "+------+-------+----------------------+",
+            ],
+            &batches
+        );
+    }
 
-        for _ in 0..=5 {
-            prev_snapshot.add_parquet_file(
-                DbId::from(0),
-                TableId::from(0),
-                ParquetFile {
-                    id: ParquetFileId::new(),
-                    path: "file/path2".into(),
-                    size_bytes: 20,
-                    row_count: 1,
-                    chunk_time: 1,
-                    min_time: 0,
-                    max_time: 1,
+    #[tokio::test]
+    async fn json_documents_are_written_with_stored_mapping() {
+        let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let (wbuf, ctx) = setup(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&obj_store),
+            WalConfig {
+                gen1_duration: Gen1Duration::new_1m(),
+                max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
+                flush_interval: Duration::from_millis(10),
+                snapshot_size: 100,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
+            },
+        )
+        .await;
+        let documents = r#"[
+            {"host": "a", "usage": 0.5, "time": "1970-01-01T00:00:10Z"},
+            {"host": "b", "usage": "high", "time": "1970-01-01T00:00:20Z"},
+            {"host": "c", "usage": 2, "time": "1970-01-01T00:00:30Z"}
+        ]"#;
+
+        // there is no mapping to write with until the table has one stored:
+        assert!(matches!(
+            wbuf.write_json(
+                NamespaceName::new("foo").unwrap(),
+                Some("cpu"),
+                documents,
+                None,
+                Time::from_timestamp_nanos(0),
+                true,
+            )
+            .await,
+            Err(Error::NoJsonMapping)
+        ));
+        wbuf.create_table(
+            NamespaceName::new("foo").unwrap(),
+            "cpu",
+            vec!["host".to_string()],
+            vec![("usage".to_string(), InfluxFieldType::Float)],
+        )
+        .await
+        .unwrap();
+        let db_schema = wbuf.catalog().db_schema("foo").unwrap();
+        let table_id = db_schema.table_name_to_id("cpu").unwrap();
+        let mapping = JsonMapping {
+            tags: [("host".to_string(), "host".to_string())]
+                .into_iter()
+                .collect(),
+            fields: [(
+                "usage".to_string(),
+                JsonFieldMapping {
+                    path: "usage".to_string(),
+                    field_type: None,
                 },
-            );
-        }
-
-        assert_eq!(prev_snapshot.databases.len(), 1);
-        let files = prev_snapshot.databases[&DbId::from(0)].tables[&TableId::from(0)].clone();
-
-        // Assert that all of the files are smaller than the next_file_id field
-        // and that their index corresponds to the order they were added in
-        assert_eq!(prev_snapshot.next_file_id.as_u64(), 6);
-        assert_eq!(files.len(), 6);
-        for (i, file) in files.iter().enumerate() {
-            assert_ne!(file.id, ParquetFileId::from(6));
-            assert!(file.id.as_u64() < 6);
-            assert_eq!(file.id.as_u64(), i as u64);
-        }
-
-        let snapshot_json = serde_json::to_vec(&prev_snapshot).unwrap();
+            )]
+            .into_iter()
+            .collect(),
+            timestamp: Some(JsonTimestampMapping {
+                path: "time".to_string(),
+                format: JsonTimestampFormat::Rfc3339,
+            }),
+            ..Default::default()
+        };
+        wbuf.set_json_mapping(db_schema.id, table_id, Some(mapping))
+            .await
+            .unwrap();
 
-        // put the snapshot file in object store:
-        object_store
-            .put(
-                &SnapshotInfoFilePath::new("test_host", prev_snapshot_seq),
-                PutPayload::from_bytes(Bytes::from(snapshot_json)),
+        let result = wbuf
+            .write_json(
+                NamespaceName::new("foo").unwrap(),
+                Some("cpu"),
+                documents,
+                None,
+                Time::from_timestamp_nanos(0),
+                true,
             )
             .await
             .unwrap();
+        // the string usage cannot be written to the float column, and the error is reported
+        // against the document rather than the line protocol it was mapped to:
+        assert_eq!(2, result.line_count);
+        assert_eq!(1, result.invalid_lines.len());
+        assert_eq!(2, result.invalid_lines[0].line_number);
+        assert!(result.invalid_lines[0].original_line.starts_with('{'));
+        assert_eq!(1, wbuf.rejected_writes().lines().len());
+
+        let batches = get_table_batches(&wbuf, "foo", "cpu", &ctx).await;
+        assert_batches_sorted_eq!(
+            [
+                "+------+-------+----------------------+",
+                "| host | usage | time                 |",
+                "+------+-------+----------------------+",
+                "| a    | 0.5   | 1970-01-01T00:00:10Z |",
+                "| c    | 2.0   | 1970-01-01T00:00:30Z |",
+                "+------+-------+----------------------+",
+            ],
+            &batches
+        );
+    }
 
-        // setup the write buffer:
-        let (_wbuf, _ctx) = setup(
+    #[tokio::test]
+    async fn writes_that_change_nothing_in_the_catalog_are_not_serialized() {
+        let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let (wbuf, _) = setup(
             Time::from_timestamp_nanos(0),
-            Arc::clone(&object_store),
+            Arc::clone(&obj_store),
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 100,
-                flush_interval: Duration::from_millis(5),
-                snapshot_size: 1,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
+                flush_interval: Duration::from_millis(10),
+                snapshot_size: 100,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
             },
         )
         .await;
+        do_writes(
+            "foo",
+            &wbuf,
+            &[TestWrite {
+                lp: "cpu,host=a usage=1",
+                time_seconds: 1,
+            }],
+        )
+        .await;
 
-        // Test that the next_file_id has been set properly
-        assert_eq!(ParquetFileId::next_id().as_u64(), 6);
+        // a write to existing columns of a database without a series quota doesn't wait for the
+        // changes of other writes to be applied
+        let _catalog_updates = wbuf.catalog_updates.lock();
+        wbuf.write_lp(
+            NamespaceName::new("foo").unwrap(),
+            "cpu,host=b usage=2",
+            Time::from_timestamp_nanos(2_000_000_000),
+            false,
+            Precision::Nanosecond,
+        )
+        .await
+        .unwrap();
     }
 
-    /// This is the reproducer for [#25277][see]
-    ///
-    /// [see]: https://github.com/influxdata/influxdb/issues/25277
     #[tokio::test]
-    async fn writes_not_dropped_on_snapshot() {
+    async fn csv_is_written_in_one_write_unless_partial_writes_are_accepted() {
         let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
         let (wbuf, _) = setup(
             Time::from_timestamp_nanos(0),
@@ -1329,19 +3537,84 @@ mod tests {
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
+                flush_interval: Duration::from_millis(10),
+                snapshot_size: 100,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
+            },
+        )
+        .await;
+        let columns = CsvColumns {
+            measurement: Some("cpu".to_string()),
+            fields: vec![("v".to_string(), csv_format::CsvFieldType::Integer)],
+            ..Default::default()
+        };
+        // a body with more rows than a batch, with an invalid row after the first batch:
+        let mut csv = "v\n".to_string();
+        for n in 0..CSV_WRITE_BATCH_ROWS {
+            csv.push_str(&format!("{n}\n"));
+        }
+        csv.push_str("x\n");
+
+        wbuf.write_csv(
+            NamespaceName::new("foo").unwrap(),
+            Box::new(std::io::Cursor::new(csv.clone())),
+            Some(columns.clone()),
+            Time::from_timestamp_nanos(0),
+            false,
+        )
+        .await
+        .unwrap_err();
+        // nothing was written, so the database was not created:
+        assert!(wbuf.catalog().db_schema("foo").is_none());
+
+        let result = wbuf
+            .write_csv(
+                NamespaceName::new("foo").unwrap(),
+                Box::new(std::io::Cursor::new(csv)),
+                Some(columns),
+                Time::from_timestamp_nanos(0),
+                true,
+            )
+            .await
+            .unwrap();
+        assert_eq!(CSV_WRITE_BATCH_ROWS, result.line_count);
+        assert_eq!(1, result.invalid_lines.len());
+        assert_eq!(
+            CSV_WRITE_BATCH_ROWS + 2,
+            result.invalid_lines[0].line_number
+        );
+    }
+
+    #[tokio::test]
+    async fn read_replica_follows_wal_and_snapshots_of_host() {
+        let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let (host, host_ctx) = setup_cache_optional(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&obj_store),
+            WalConfig {
+                gen1_duration: Gen1Duration::new_1m(),
+                max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
                 flush_interval: Duration::from_millis(10),
                 snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
             },
+            false,
         )
         .await;
 
         let db_name = "coffee_shop";
         let tbl_name = "menu";
-
-        // do some writes to get a snapshot:
         do_writes(
             db_name,
-            &wbuf,
+            &host,
             &[
                 TestWrite {
                     lp: format!("{tbl_name},name=espresso price=2.50"),
@@ -1351,45 +3624,256 @@ mod tests {
                     lp: format!("{tbl_name},name=americano price=3.00"),
                     time_seconds: 2,
                 },
-                TestWrite {
-                    lp: format!("{tbl_name},name=latte price=4.50"),
-                    time_seconds: 3,
-                },
             ],
         )
         .await;
 
-        // wait for snapshot to be created:
-        verify_snapshot_count(1, &wbuf.persister).await;
+        // start the replica, which replays the wal of the host:
+        let persister = Arc::new(Persister::new(Arc::clone(&obj_store), "test_host"));
+        let catalog = Arc::new(Catalog::from_inner(
+            persister.load_catalog().await.unwrap().unwrap().catalog,
+        ));
+        let last_cache =
+            LastCacheProvider::new_from_db_schema_provider(Arc::clone(&catalog) as _).unwrap();
+        let replica = WriteBufferImpl::new(
+            Arc::clone(&persister),
+            catalog,
+            Arc::new(last_cache),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+            crate::test_help::make_exec(),
+            WalConfig::test_config(),
+            WalBackend::ReadReplica(ReplicaConfig {
+                poll_interval: Duration::from_millis(10),
+            }),
+            Arc::new(metric::Registry::default()),
+            None,
+            RejectedWritesConfig::default(),
+        )
+        .await
+        .unwrap();
+        let replica_ctx = IOxSessionContext::with_testing();
+        register_iox_object_store(
+            replica_ctx.inner().runtime_env(),
+            "influxdb3",
+            Arc::clone(&obj_store),
+        );
 
-        // Now drop the write buffer, and create a new one that replays:
-        drop(wbuf);
-        let (wbuf, ctx) = setup(
+        // do another write on the host to get a snapshot, with a new column:
+        do_writes(
+            db_name,
+            &host,
+            &[TestWrite {
+                lp: format!("{tbl_name},name=latte price=4.50,size=\"large\""),
+                time_seconds: 3,
+            }],
+        )
+        .await;
+        verify_snapshot_count(1, &host.persister).await;
+
+        let expected = [
+            "+-----------+-------+-------+----------------------+",
+            "| name      | price | size  | time                 |",
+            "+-----------+-------+-------+----------------------+",
+            "| americano | 3.0   |       | 1970-01-01T00:00:02Z |",
+            "| espresso  | 2.5   |       | 1970-01-01T00:00:01Z |",
+            "| latte     | 4.5   | large | 1970-01-01T00:00:03Z |",
+            "+-----------+-------+-------+----------------------+",
+        ];
+        let batches = get_table_batches(&host, db_name, tbl_name, &host_ctx).await;
+        assert_batches_sorted_eq!(expected, &batches);
+
+        // the replica follows the host, including the files it persisted in the snapshot:
+        let mut checks = 0;
+        loop {
+            if !replica
+                .parquet_files(DbId::from(0), TableId::from(0))
+                .is_empty()
+                && replica
+                    .catalog()
+                    .db_schema(db_name)
+                    .and_then(|db| db.table_schema(tbl_name))
+                    .is_some_and(|schema| schema.field_by_name("size").is_some())
+            {
+                break;
+            }
+            checks += 1;
+            assert!(checks < 100, "replica did not follow the host");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let batches = get_table_batches(&replica, db_name, tbl_name, &replica_ctx).await;
+        assert_batches_sorted_eq!(expected, &batches);
+
+        // and rejects writes:
+        let err = replica
+            .write_lp(
+                NamespaceName::new(db_name).unwrap(),
+                &format!("{tbl_name},name=mocha price=5.00"),
+                Time::from_timestamp_nanos(4_000_000_000),
+                false,
+                Precision::Nanosecond,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NoWriteInReadOnly));
+    }
+
+    #[tokio::test]
+    async fn restore_host_to_an_earlier_wal_file() {
+        let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let (host, _) = setup_cache_optional(
             Time::from_timestamp_nanos(0),
             Arc::clone(&obj_store),
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
                 flush_interval: Duration::from_millis(10),
                 snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::from_secs(3600),
             },
+            false,
         )
         .await;
 
-        // Get the record batches from replayed buffer:
-        let batches = get_table_batches(&wbuf, db_name, tbl_name, &ctx).await;
-        assert_batches_sorted_eq!(
-            [
-                "+-----------+-------+----------------------+",
-                "| name      | price | time                 |",
-                "+-----------+-------+----------------------+",
-                "| americano | 3.0   | 1970-01-01T00:00:02Z |",
-                "| espresso  | 2.5   | 1970-01-01T00:00:01Z |",
-                "| latte     | 4.5   | 1970-01-01T00:00:03Z |",
-                "+-----------+-------+----------------------+",
+        // each write goes in its own wal file, and the third triggers a snapshot of the first two
+        let db_name = "coffee_shop";
+        let tbl_name = "menu";
+        do_writes(
+            db_name,
+            &host,
+            &[
+                TestWrite {
+                    lp: format!("{tbl_name},name=espresso price=2.50"),
+                    time_seconds: 1,
+                },
+                TestWrite {
+                    lp: format!("{tbl_name},name=americano price=3.00"),
+                    time_seconds: 2,
+                },
+                TestWrite {
+                    lp: format!("{tbl_name},name=latte price=4.50"),
+                    time_seconds: 3,
+                },
+                TestWrite {
+                    lp: format!("{tbl_name},name=mocha price=5.00"),
+                    time_seconds: 4,
+                },
             ],
-            &batches
+        )
+        .await;
+        verify_snapshot_count(1, &host.persister).await;
+        let mut checks = 0;
+        while influxdb3_wal::object_store::list_retained_wal_files(obj_store.as_ref(), "test_host")
+            .await
+            .unwrap()
+            .len()
+            < 2
+        {
+            checks += 1;
+            assert!(checks < 100, "snapshotted wal files were not retained");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        // the host itself can't be restored into:
+        let err = restore(
+            Arc::clone(&obj_store),
+            "test_host",
+            "test_host",
+            RestorePoint::WalFile(WalFileSequenceNumber::new(3)),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, crate::restore::Error::TargetNotEmpty(_)));
+
+        // restore to the wal file that triggered the snapshot, which starts from the snapshot:
+        let summary = restore(
+            Arc::clone(&obj_store),
+            "test_host",
+            "restored_at_3",
+            RestorePoint::WalFile(WalFileSequenceNumber::new(3)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            summary.snapshot_sequence_number,
+            Some(SnapshotSequenceNumber::new(1))
+        );
+        assert_eq!(summary.parquet_files, 1);
+        assert_eq!(summary.wal_files, vec![WalFileSequenceNumber::new(3)]);
+
+        // restore to before the snapshot, which replays the retained wal files:
+        let summary = restore(
+            Arc::clone(&obj_store),
+            "test_host",
+            "restored_at_2",
+            RestorePoint::WalFile(WalFileSequenceNumber::new(2)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.snapshot_sequence_number, None);
+        assert_eq!(summary.parquet_files, 0);
+        assert_eq!(
+            summary.wal_files,
+            vec![WalFileSequenceNumber::new(1), WalFileSequenceNumber::new(2)]
         );
+
+        // start the restored hosts and check that they have the writes up to the restore point:
+        for (restored_host, expected) in [
+            (
+                "restored_at_3",
+                vec![
+                    "+-----------+-------+----------------------+",
+                    "| name      | price | time                 |",
+                    "+-----------+-------+----------------------+",
+                    "| americano | 3.0   | 1970-01-01T00:00:02Z |",
+                    "| espresso  | 2.5   | 1970-01-01T00:00:01Z |",
+                    "| latte     | 4.5   | 1970-01-01T00:00:03Z |",
+                    "+-----------+-------+----------------------+",
+                ],
+            ),
+            (
+                "restored_at_2",
+                vec![
+                    "+-----------+-------+----------------------+",
+                    "| name      | price | time                 |",
+                    "+-----------+-------+----------------------+",
+                    "| americano | 3.0   | 1970-01-01T00:00:02Z |",
+                    "| espresso  | 2.5   | 1970-01-01T00:00:01Z |",
+                    "+-----------+-------+----------------------+",
+                ],
+            ),
+        ] {
+            let persister = Arc::new(Persister::new(Arc::clone(&obj_store), restored_host));
+            let catalog = Arc::new(persister.load_or_create_catalog().await.unwrap());
+            assert_eq!(&*catalog.host_id(), restored_host);
+            let last_cache =
+                LastCacheProvider::new_from_db_schema_provider(Arc::clone(&catalog) as _).unwrap();
+            let restored = WriteBufferImpl::new(
+                persister,
+                catalog,
+                Arc::new(last_cache),
+                Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+                crate::test_help::make_exec(),
+                WalConfig::test_config(),
+                WalBackend::ObjectStore,
+                Arc::new(metric::Registry::default()),
+                None,
+                RejectedWritesConfig::default(),
+            )
+            .await
+            .unwrap();
+            let ctx = IOxSessionContext::with_testing();
+            register_iox_object_store(
+                ctx.inner().runtime_env(),
+                "influxdb3",
+                Arc::clone(&obj_store),
+            );
+            let batches = get_table_batches(&restored, db_name, tbl_name, &ctx).await;
+            assert_batches_sorted_eq!(expected, &batches);
+        }
     }
 
     #[tokio::test]
@@ -1401,8 +3885,13 @@ mod tests {
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
                 flush_interval: Duration::from_millis(10),
                 snapshot_size: 2,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
             },
         )
         .await;
@@ -1453,8 +3942,13 @@ mod tests {
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
                 flush_interval: Duration::from_millis(10),
                 snapshot_size: 2,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
             },
         )
         .await;
@@ -1487,8 +3981,13 @@ mod tests {
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
                 flush_interval: Duration::from_millis(10),
                 snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
             },
         )
         .await;
@@ -1531,8 +4030,13 @@ mod tests {
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
                 flush_interval: Duration::from_millis(10),
                 snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
             },
         )
         .await;
@@ -1562,8 +4066,13 @@ mod tests {
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
                 flush_interval: Duration::from_millis(10),
                 snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
             },
         )
         .await;
@@ -1611,8 +4120,13 @@ mod tests {
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
                 flush_interval: Duration::from_millis(10),
                 snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
             },
         )
         .await;
@@ -1661,8 +4175,13 @@ mod tests {
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
                 flush_interval: Duration::from_millis(10),
                 snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
             },
         )
         .await;
@@ -1683,8 +4202,13 @@ mod tests {
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
                 flush_interval: Duration::from_millis(10),
                 snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
             },
             true,
         )
@@ -1789,8 +4313,13 @@ mod tests {
             WalConfig {
                 gen1_duration: Gen1Duration::new_1m(),
                 max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
                 flush_interval: Duration::from_millis(10),
                 snapshot_size: 1,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
             },
             false,
         )
@@ -1884,6 +4413,148 @@ mod tests {
         assert_eq!(0, test_store.head_request_count(&path));
     }
 
+    #[tokio::test]
+    async fn import_parquet_persists_a_snapshot_without_the_wal() {
+        use arrow::array::{ArrayRef, Float64Array, StringArray, TimestampNanosecondArray};
+        use arrow::datatypes::{DataType, Field, Schema as ArrowSchema, TimeUnit};
+        use parquet::arrow::ArrowWriter;
+
+        let obj_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let (wbuf, ctx) = setup_cache_optional(
+            Time::from_timestamp_nanos(0),
+            Arc::clone(&obj_store),
+            WalConfig {
+                gen1_duration: Gen1Duration::new_1m(),
+                max_write_buffer_size: 100,
+                max_write_buffer_bytes: 1024 * 1024,
+                buffer_full_timeout: Duration::from_secs(1),
+                flush_interval: Duration::from_millis(10),
+                snapshot_size: 100,
+                compression: WalCompression::None,
+                quarantine_corrupt_files: false,
+                snapshotted_wal_retention: Duration::ZERO,
+            },
+            false,
+        )
+        .await;
+        do_writes(
+            "foo",
+            &wbuf,
+            &[TestWrite {
+                lp: "cpu,host=c usage=9 120000000000",
+                time_seconds: 120,
+            }],
+        )
+        .await;
+
+        // the host column is not marked, so it takes its type from the table; the first two rows
+        // are duplicates, and the last is in the next gen1 chunk of time
+        let usage = Field::new("usage", DataType::Float64, true).with_metadata(
+            [(
+                schema::COLUMN_METADATA_KEY.to_string(),
+                "iox::column_type::field::float".to_string(),
+            )]
+            .into(),
+        );
+        let batch = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![
+                Field::new("host", DataType::Utf8, true),
+                usage,
+                Field::new(
+                    "time",
+                    DataType::Timestamp(TimeUnit::Nanosecond, None),
+                    false,
+                ),
+            ])),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "a", "b"])) as ArrayRef,
+                Arc::new(Float64Array::from(vec![1.0, 1.0, 3.0])),
+                Arc::new(TimestampNanosecondArray::from(vec![
+                    1_000_000_000,
+                    1_000_000_000,
+                    61_000_000_000,
+                ])),
+            ],
+        )
+        .unwrap();
+        let mut file = vec![];
+        let mut writer = ArrowWriter::try_new(&mut file, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let summary = wbuf
+            .import_parquet(
+                NamespaceName::new("foo").unwrap(),
+                "cpu",
+                ParquetImportSource::Bytes(Bytes::from(file.clone())),
+                Time::from_timestamp_nanos(0),
+            )
+            .await
+            .unwrap();
+        assert_eq!(summary.row_count, 3);
+        assert_eq!(summary.persisted_row_count, 2);
+        assert_eq!(summary.persisted_file_count, 2);
+        assert_eq!(summary.min_time, 1_000_000_000);
+        assert_eq!(summary.max_time, 61_000_000_000);
+
+        // the snapshot of the import is persisted, with a wal file number that is never written
+        let snapshots = wbuf.persister.load_snapshots(10).await.unwrap();
+        assert_eq!(snapshots.len(), 1);
+        let snapshot = &snapshots[0];
+        assert_eq!(
+            snapshot.snapshot_sequence_number,
+            summary.snapshot_sequence_number
+        );
+        assert_eq!(snapshot.row_count, 2);
+        do_writes(
+            "foo",
+            &wbuf,
+            &[TestWrite {
+                lp: "cpu,host=c usage=10 121000000000",
+                time_seconds: 121,
+            }],
+        )
+        .await;
+        assert!(wbuf.wal.last_wal_sequence_number().await > snapshot.wal_file_sequence_number);
+        // the catalog with the table is persisted along with it
+        let persisted_catalog = wbuf.persister.load_catalog().await.unwrap().unwrap();
+        assert!(Catalog::from_inner(persisted_catalog.catalog)
+            .db_schema("foo")
+            .is_some());
+
+        let batches = get_table_batches(&wbuf, "foo", "cpu", &ctx).await;
+        assert_batches_sorted_eq!(
+            [
+                "+------+----------------------+-------+",
+                "| host | time                 | usage |",
+                "+------+----------------------+-------+",
+                "| a    | 1970-01-01T00:00:01Z | 1.0   |",
+                "| b    | 1970-01-01T00:01:01Z | 3.0   |",
+                "| c    | 1970-01-01T00:02:00Z | 9.0   |",
+                "| c    | 1970-01-01T00:02:01Z | 10.0  |",
+                "+------+----------------------+-------+",
+            ],
+            &batches
+        );
+
+        // a file in the object store is imported from its path in the same way
+        let path = ObjPath::from("imports/cpu.parquet");
+        obj_store.put(&path, file.into()).await.unwrap();
+        let summary = wbuf
+            .import_parquet(
+                NamespaceName::new("foo").unwrap(),
+                "cpu",
+                ParquetImportSource::ObjectStore(path),
+                Time::from_timestamp_nanos(0),
+            )
+            .await
+            .unwrap();
+        assert_eq!(summary.row_count, 3);
+        assert_eq!(summary.persisted_row_count, 2);
+        assert_eq!(summary.persisted_file_count, 2);
+        assert_eq!(wbuf.persister.load_snapshots(10).await.unwrap().len(), 2);
+    }
+
     struct TestWrite<LP> {
         lp: LP,
         time_seconds: i64,
@@ -1993,7 +4664,10 @@ mod tests {
             Arc::clone(&time_provider),
             crate::test_help::make_exec(),
             wal_config,
+            WalBackend::ObjectStore,
+            Arc::new(metric::Registry::default()),
             parquet_cache,
+            RejectedWritesConfig::default(),
         )
         .await
         .unwrap();
@@ -2009,9 +4683,12 @@ mod tests {
         table_name: &str,
         ctx: &IOxSessionContext,
     ) -> Vec<RecordBatch> {
+        let state = ctx.inner().state();
         let chunks = write_buffer
-            .get_table_chunks(database_name, table_name, &[], None, &ctx.inner().state())
+            .get_table_chunks(database_name, table_name, &[], None, &state)
             .unwrap();
+        let (chunks, rewritten) =
+            crate::chunk::scan_rewritten_chunks(&state, chunks, None, &[]).unwrap();
         let mut batches = vec![];
         for chunk in chunks {
             let chunk = chunk
@@ -2020,6 +4697,13 @@ mod tests {
                 .await;
             batches.extend(chunk);
         }
+        for plan in rewritten {
+            batches.extend(
+                datafusion::physical_plan::collect(plan, ctx.inner().task_ctx())
+                    .await
+                    .unwrap(),
+            );
+        }
         batches
     }
 }
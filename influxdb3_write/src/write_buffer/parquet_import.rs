@@ -0,0 +1,220 @@
+//! Reads parquet files that are imported into a table. The rows of an import are validated against
+//! the catalog in the same way as record batches written over Flight, and then persisted directly
+//! to object store in a snapshot of their own, rather than being buffered and written to the WAL.
+//!
+//! Files persisted by InfluxDB 3 mark their columns as tags or fields in their Arrow schema. The
+//! columns of other files that are not marked take their type from the table they are imported
+//! into, if it has a column of the same name.
+
+use crate::write_buffer::{Error, Result};
+use arrow::datatypes::{Field, Schema};
+use arrow::record_batch::RecordBatch;
+use bytes::Bytes;
+use futures::TryStreamExt;
+use influxdb3_catalog::catalog::TableDefinition;
+use object_store::path::Path as ObjPath;
+use object_store::ObjectStore;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::async_reader::ParquetObjectReader;
+use parquet::arrow::ParquetRecordBatchStreamBuilder;
+use schema::{COLUMN_METADATA_KEY, TIME_COLUMN_NAME};
+use std::sync::Arc;
+
+/// Reads the record batches of a parquet file that is imported into a table, which is `None` if
+/// the table does not exist yet. Unmarked columns are marked with their type in the table.
+pub(crate) fn read_parquet_file(
+    file: Bytes,
+    table_def: Option<&TableDefinition>,
+) -> Result<Vec<RecordBatch>> {
+    let invalid = |e: &dyn std::fmt::Display| Error::InvalidParquetImport(e.to_string());
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+        .and_then(|builder| builder.build())
+        .map_err(|e| invalid(&e))?;
+    let batches = reader
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| invalid(&e))?;
+    mark_batches(batches, table_def)
+}
+
+/// Reads the record batches of a parquet file in the object store that is imported into a table,
+/// streaming it from the object store rather than reading all of it into memory at once.
+pub(crate) async fn read_parquet_object(
+    object_store: Arc<dyn ObjectStore>,
+    path: &ObjPath,
+    table_def: Option<&TableDefinition>,
+) -> Result<Vec<RecordBatch>> {
+    let invalid = |e: &dyn std::fmt::Display| Error::InvalidParquetImport(e.to_string());
+    let meta = object_store.head(path).await.map_err(|e| invalid(&e))?;
+    let stream = ParquetRecordBatchStreamBuilder::new(ParquetObjectReader::new(object_store, meta))
+        .await
+        .and_then(|builder| builder.build())
+        .map_err(|e| invalid(&e))?;
+    let batches = stream
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(|e| invalid(&e))?;
+    mark_batches(batches, table_def)
+}
+
+/// Checks that the record batches of a file have rows and a time column, and marks their
+/// unmarked columns with their type in the table
+fn mark_batches(
+    batches: Vec<RecordBatch>,
+    table_def: Option<&TableDefinition>,
+) -> Result<Vec<RecordBatch>> {
+    let invalid = |e: &dyn std::fmt::Display| Error::InvalidParquetImport(e.to_string());
+    let Some(file_schema) = batches.first().map(|batch| batch.schema()) else {
+        return Err(Error::InvalidParquetImport(
+            "the parquet file has no rows".to_string(),
+        ));
+    };
+    if file_schema.field_with_name(TIME_COLUMN_NAME).is_err() {
+        return Err(Error::InvalidParquetImport(format!(
+            "the parquet file has no {TIME_COLUMN_NAME} column"
+        )));
+    }
+
+    let Some(table_def) = table_def else {
+        return Ok(batches);
+    };
+    let schema = Arc::new(Schema::new_with_metadata(
+        file_schema
+            .fields()
+            .iter()
+            .map(|field| mark_column(field, table_def))
+            .collect::<Vec<_>>(),
+        file_schema.metadata().clone(),
+    ));
+    if schema == file_schema {
+        return Ok(batches);
+    }
+    batches
+        .into_iter()
+        .map(|batch| {
+            RecordBatch::try_new(Arc::clone(&schema), batch.columns().to_vec())
+                .map_err(|e| invalid(&e))
+        })
+        .collect()
+}
+
+/// The field of a column of the file, marked with the type of the column of the same name in the
+/// table if it is not marked already
+fn mark_column(field: &Arc<Field>, table_def: &TableDefinition) -> Arc<Field> {
+    if field.name() == TIME_COLUMN_NAME || field.metadata().contains_key(COLUMN_METADATA_KEY) {
+        return Arc::clone(field);
+    }
+    let Some(column_type) = table_def.field_type_by_name(field.name()) else {
+        return Arc::clone(field);
+    };
+    let mut metadata = field.metadata().clone();
+    metadata.insert(COLUMN_METADATA_KEY.to_string(), column_type.to_string());
+    Arc::new(field.as_ref().clone().with_metadata(metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{ArrayRef, Float64Array, StringArray, TimestampNanosecondArray};
+    use arrow::datatypes::{DataType, TimeUnit};
+    use influxdb3_id::TableId;
+    use parquet::arrow::ArrowWriter;
+    use schema::{InfluxColumnType, InfluxFieldType};
+
+    fn parquet_file(fields: Vec<Field>, columns: Vec<ArrayRef>) -> Bytes {
+        let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap();
+        let mut bytes = vec![];
+        let mut writer = ArrowWriter::try_new(&mut bytes, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        bytes.into()
+    }
+
+    #[test]
+    fn unmarked_columns_take_their_type_from_the_table() {
+        let usage = Field::new("usage", DataType::Float64, true).with_metadata(
+            [(
+                COLUMN_METADATA_KEY.to_string(),
+                "iox::column_type::field::float".to_string(),
+            )]
+            .into(),
+        );
+        let file = parquet_file(
+            vec![
+                Field::new("host", DataType::Utf8, true),
+                usage,
+                Field::new("region", DataType::Utf8, true),
+                Field::new(
+                    TIME_COLUMN_NAME,
+                    DataType::Timestamp(TimeUnit::Nanosecond, None),
+                    false,
+                ),
+            ],
+            vec![
+                Arc::new(StringArray::from(vec!["a"])),
+                Arc::new(Float64Array::from(vec![1.0])),
+                Arc::new(StringArray::from(vec!["us"])),
+                Arc::new(TimestampNanosecondArray::from(vec![1])),
+            ],
+        );
+        let table_def = TableDefinition::new(
+            TableId::new(),
+            "cpu".into(),
+            vec![
+                ("host".to_string(), InfluxColumnType::Tag),
+                (
+                    "usage".to_string(),
+                    InfluxColumnType::Field(InfluxFieldType::Float),
+                ),
+                (TIME_COLUMN_NAME.to_string(), InfluxColumnType::Timestamp),
+            ],
+            Option::<Vec<String>>::None,
+        )
+        .unwrap();
+
+        let batches = read_parquet_file(file.clone(), Some(&table_def)).unwrap();
+        let schema = batches[0].schema();
+        let marked = |name: &str| {
+            schema
+                .field_with_name(name)
+                .unwrap()
+                .metadata()
+                .get(COLUMN_METADATA_KEY)
+                .cloned()
+        };
+        assert_eq!(marked("host"), Some(InfluxColumnType::Tag.to_string()));
+        assert_eq!(
+            marked("usage"),
+            Some("iox::column_type::field::float".to_string())
+        );
+        // the table has no region column for it to take the type of
+        assert_eq!(marked("region"), None);
+
+        // without the table, the file is read as it is
+        let batches = read_parquet_file(file, None).unwrap();
+        assert_eq!(
+            batches[0]
+                .schema()
+                .field_with_name("host")
+                .unwrap()
+                .metadata()
+                .get(COLUMN_METADATA_KEY),
+            None
+        );
+    }
+
+    #[test]
+    fn files_without_a_time_column_are_rejected() {
+        let file = parquet_file(
+            vec![Field::new("usage", DataType::Float64, true)],
+            vec![Arc::new(Float64Array::from(vec![1.0]))],
+        );
+        let err = read_parquet_file(file, None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid parquet import: the parquet file has no time column"
+        );
+
+        let err = read_parquet_file(Bytes::from_static(b"not parquet"), None).unwrap_err();
+        assert!(matches!(err, Error::InvalidParquetImport(_)));
+    }
+}
@@ -2,11 +2,12 @@
 //! When queries come in they will combine whatever chunks exist from `QueryableBuffer` with
 //! the persisted files to get the full set of data to query.
 
-use crate::{ParquetFile, PersistedSnapshot};
+use crate::{ParquetFile, PersistedSnapshot, Tombstone};
 use hashbrown::HashMap;
 use influxdb3_id::DbId;
 use influxdb3_id::TableId;
 use influxdb3_telemetry::ParquetMetrics;
+use influxdb3_wal::WalFileSequenceNumber;
 use parking_lot::RwLock;
 
 type DatabaseToTables = HashMap<DbId, TableToFiles>;
@@ -56,6 +57,84 @@ impl PersistedFiles {
 
         files
     }
+
+    /// Add a tombstone for a delete, which applies to the files persisted before it
+    pub fn add_tombstone(&self, tombstone: Tombstone) {
+        let mut inner = self.inner.write();
+        inner.add_tombstone(tombstone);
+    }
+
+    /// Remove the files and tombstones of a database that was deleted
+    pub fn remove_database(&self, db_id: DbId) {
+        let mut inner = self.inner.write();
+        if let Some(tables) = inner.files.remove(&db_id) {
+            for files in tables.into_values() {
+                inner.remove_files_from_metrics(&files);
+            }
+        }
+        inner.tombstones.retain(|t| t.database_id != db_id);
+    }
+
+    /// Remove the files and tombstones of a table that was deleted
+    pub fn remove_table(&self, db_id: DbId, table_id: TableId) {
+        let mut inner = self.inner.write();
+        if let Some(files) = inner
+            .files
+            .get_mut(&db_id)
+            .and_then(|tables| tables.remove(&table_id))
+        {
+            inner.remove_files_from_metrics(&files);
+        }
+        inner
+            .tombstones
+            .retain(|t| t.database_id != db_id || t.table_id != table_id);
+    }
+
+    /// Whether the tombstone is still kept, as it is removed once it no longer applies to any
+    /// persisted file
+    pub fn has_tombstone(&self, tombstone: &Tombstone) -> bool {
+        self.inner.read().tombstones.contains(tombstone)
+    }
+
+    /// Remove the files of a database that only hold data older than its retention cutoff
+    pub fn remove_expired_files(&self, db_id: DbId, retention_cutoff_ns: i64) {
+        let mut inner = self.inner.write();
+        let mut removed = vec![];
+        if let Some(tables) = inner.files.get_mut(&db_id) {
+            for files in tables.values_mut() {
+                files.retain(|file| {
+                    let expired = file.max_time < retention_cutoff_ns;
+                    if expired {
+                        removed.push(file.clone());
+                    }
+                    !expired
+                });
+            }
+        }
+        inner.remove_files_from_metrics(&removed);
+        inner.remove_obsolete_tombstones();
+    }
+
+    /// The total size in bytes of the files persisted for a database
+    pub fn database_size_bytes(&self, db_id: DbId) -> u64 {
+        let inner = self.inner.read();
+        inner
+            .files
+            .get(&db_id)
+            .map(|tables| tables.values().flatten().map(|file| file.size_bytes).sum())
+            .unwrap_or_default()
+    }
+
+    /// Get the tombstones for a given database and table
+    pub fn get_tombstones(&self, db_id: DbId, table_id: TableId) -> Vec<Tombstone> {
+        let inner = self.inner.read();
+        inner
+            .tombstones
+            .iter()
+            .filter(|t| t.database_id == db_id && t.table_id == table_id)
+            .cloned()
+            .collect()
+    }
 }
 
 impl ParquetMetrics for PersistedFiles {
@@ -74,6 +153,11 @@ impl ParquetMetrics for PersistedFiles {
 struct Inner {
     /// The map of databases to tables to files
     pub files: DatabaseToTables,
+    /// The tombstones for deletes, which are applied to files at query time
+    pub tombstones: Vec<Tombstone>,
+    /// The last wal file covered by the snapshots that have been persisted, after which no more
+    /// files are persisted for the wal files up to it
+    pub last_wal_sequence_number: WalFileSequenceNumber,
     /// Overall count of the parquet files
     pub parquet_files_count: u64,
     /// Total size of all parquet files in MB
@@ -88,9 +172,14 @@ impl Inner {
         let mut size_in_mb = 0.0;
         let mut row_count = 0;
 
+        let mut tombstones = vec![];
+        let mut last_wal_sequence_number = WalFileSequenceNumber::default();
         let files = persisted_snapshots.into_iter().fold(
             hashbrown::HashMap::new(),
-            |mut files, persisted_snapshot| {
+            |mut files, mut persisted_snapshot| {
+                tombstones.append(&mut persisted_snapshot.tombstones);
+                last_wal_sequence_number =
+                    last_wal_sequence_number.max(persisted_snapshot.last_wal_sequence_number);
                 size_in_mb += as_mb(persisted_snapshot.parquet_size_bytes);
                 row_count += persisted_snapshot.row_count;
                 let parquet_files_added =
@@ -100,20 +189,70 @@ impl Inner {
             },
         );
 
-        Self {
+        let mut inner = Self {
             files,
+            tombstones,
+            last_wal_sequence_number,
             parquet_files_count: file_count,
             parquet_files_row_count: row_count,
             parquet_files_size_mb: size_in_mb,
-        }
+        };
+        inner.remove_obsolete_tombstones();
+        inner
     }
 
-    pub fn add_persisted_snapshot(&mut self, persisted_snapshot: PersistedSnapshot) {
+    pub fn add_persisted_snapshot(&mut self, mut persisted_snapshot: PersistedSnapshot) {
+        // the tombstones were added as the deletes were buffered, this is only needed if they
+        // were not
+        for tombstone in persisted_snapshot.tombstones.drain(..) {
+            self.add_tombstone(tombstone);
+        }
         self.parquet_files_row_count += persisted_snapshot.row_count;
         self.parquet_files_size_mb += as_mb(persisted_snapshot.parquet_size_bytes);
+        self.last_wal_sequence_number = self
+            .last_wal_sequence_number
+            .max(persisted_snapshot.last_wal_sequence_number);
         let file_count =
             update_persisted_files_with_snapshot(false, persisted_snapshot, &mut self.files);
         self.parquet_files_count += file_count;
+        self.remove_obsolete_tombstones();
+    }
+
+    pub fn add_tombstone(&mut self, tombstone: Tombstone) {
+        if !self.tombstones.contains(&tombstone) {
+            self.tombstones.push(tombstone);
+        }
+    }
+
+    /// Removes the tombstones that no persisted file of their table is old enough for, which is
+    /// once the wal file of their delete is covered by a snapshot, so that no more files are
+    /// persisted for it, and the files persisted up to it have been removed
+    fn remove_obsolete_tombstones(&mut self) {
+        let files = &self.files;
+        let last_wal_sequence_number = self.last_wal_sequence_number;
+        self.tombstones.retain(|t| {
+            t.wal_file_sequence_number > last_wal_sequence_number
+                || files
+                    .get(&t.database_id)
+                    .and_then(|tables| tables.get(&t.table_id))
+                    .is_some_and(|table_files| {
+                        table_files
+                            .iter()
+                            .any(|f| f.wal_file_sequence_number <= t.wal_file_sequence_number)
+                    })
+        });
+    }
+
+    fn remove_files_from_metrics(&mut self, files: &[ParquetFile]) {
+        // the metrics are not exact, as duplicate files are counted by some snapshots, so they
+        // are not allowed to go below zero
+        for file in files {
+            self.parquet_files_count = self.parquet_files_count.saturating_sub(1);
+            self.parquet_files_row_count =
+                self.parquet_files_row_count.saturating_sub(file.row_count);
+            self.parquet_files_size_mb =
+                (self.parquet_files_size_mb - as_mb(file.size_bytes)).max(0.0);
+        }
     }
 }
 
@@ -160,7 +299,7 @@ fn update_persisted_files_with_snapshot(
 mod tests {
 
     use influxdb3_catalog::catalog::SequenceNumber;
-    use influxdb3_wal::{SnapshotSequenceNumber, WalFileSequenceNumber};
+    use influxdb3_wal::{DeletePredicate, SnapshotSequenceNumber, WalFileSequenceNumber};
     use observability_deps::tracing::info;
     use pretty_assertions::assert_eq;
 
@@ -238,6 +377,138 @@ mod tests {
         assert_eq!(150, row_count);
     }
 
+    #[test_log::test(test)]
+    fn test_tombstones_from_snapshots_and_deletes() {
+        let tombstone = |table_id: u32, wal_id: u64| Tombstone {
+            database_id: DbId::from(0),
+            table_id: TableId::from(table_id),
+            wal_file_sequence_number: WalFileSequenceNumber::new(wal_id),
+            predicate: DeletePredicate {
+                min_time_ns: 0,
+                max_time_ns: 100,
+                tags: vec![],
+            },
+        };
+        let mut snapshots = build_persisted_snapshots();
+        snapshots[0].tombstones.push(tombstone(0, 1));
+        let persisted_files = PersistedFiles::new_from_persisted_snapshots(snapshots);
+        assert_eq!(
+            vec![tombstone(0, 1)],
+            persisted_files.get_tombstones(DbId::from(0), TableId::from(0))
+        );
+
+        // a tombstone added when the delete is buffered is not duplicated when the snapshot that
+        // contains it is added
+        persisted_files.add_tombstone(tombstone(0, 3));
+        persisted_files.add_tombstone(tombstone(1, 3));
+        let mut new_snapshot = build_snapshot(build_parquet_files(1), 3, 3, 3);
+        new_snapshot.tombstones.push(tombstone(0, 3));
+        persisted_files.add_persisted_snapshot_files(new_snapshot);
+        assert_eq!(
+            vec![tombstone(0, 1), tombstone(0, 3)],
+            persisted_files.get_tombstones(DbId::from(0), TableId::from(0))
+        );
+
+        // only files persisted up to the wal file of the delete, that overlap its time range, may
+        // contain deleted rows
+        let file = build_parquet_files(1).pop().unwrap();
+        assert!(tombstone(0, 1).applies_to(&file));
+        assert!(!tombstone(0, 0).applies_to(&file));
+        let mut later_tombstone = tombstone(0, 1);
+        later_tombstone.predicate.min_time_ns = 201;
+        later_tombstone.predicate.max_time_ns = 300;
+        assert!(!later_tombstone.applies_to(&file));
+    }
+
+    #[test_log::test(test)]
+    fn test_obsolete_tombstones_are_removed() {
+        let tombstone = |wal_id: u64| Tombstone {
+            database_id: DbId::from(0),
+            table_id: TableId::from(0),
+            wal_file_sequence_number: WalFileSequenceNumber::new(wal_id),
+            predicate: DeletePredicate {
+                min_time_ns: 0,
+                max_time_ns: 100,
+                tags: vec![],
+            },
+        };
+        // the files of the snapshots are persisted for wal file 1
+        let mut snapshots = build_persisted_snapshots();
+        snapshots[1].last_wal_sequence_number = WalFileSequenceNumber::new(1);
+        snapshots[1].tombstones.push(tombstone(1));
+        let persisted_files = PersistedFiles::new_from_persisted_snapshots(snapshots);
+        // a delete in a wal file that no snapshot covers yet may apply to files still to come
+        persisted_files.add_tombstone(tombstone(2));
+        assert_eq!(
+            vec![tombstone(1), tombstone(2)],
+            persisted_files.get_tombstones(DbId::from(0), TableId::from(0))
+        );
+
+        // once the files up to the wal file of a delete have expired, its tombstone is removed
+        persisted_files.remove_expired_files(DbId::from(0), 201);
+        assert_eq!(
+            vec![tombstone(2)],
+            persisted_files.get_tombstones(DbId::from(0), TableId::from(0))
+        );
+        assert!(!persisted_files.has_tombstone(&tombstone(1)));
+
+        // and once a snapshot covers the wal file of a delete, none are persisted for it later
+        let mut snapshot = build_snapshot(vec![], 3, 3, 3);
+        snapshot.last_wal_sequence_number = WalFileSequenceNumber::new(2);
+        persisted_files.add_persisted_snapshot_files(snapshot);
+        assert!(persisted_files
+            .get_tombstones(DbId::from(0), TableId::from(0))
+            .is_empty());
+    }
+
+    #[test_log::test(test)]
+    fn test_remove_table_and_database() {
+        let mut snapshots = build_persisted_snapshots();
+        for file in build_parquet_files(2) {
+            snapshots[0].add_parquet_file(DbId::from(0), TableId::from(1), file);
+        }
+        for file in build_parquet_files(3) {
+            snapshots[1].add_parquet_file(DbId::from(1), TableId::from(2), file);
+        }
+        let persisted_files = PersistedFiles::new_from_persisted_snapshots(snapshots);
+        persisted_files.add_tombstone(Tombstone {
+            database_id: DbId::from(0),
+            table_id: TableId::from(1),
+            wal_file_sequence_number: WalFileSequenceNumber::new(2),
+            predicate: DeletePredicate {
+                min_time_ns: 0,
+                max_time_ns: 100,
+                tags: vec![],
+            },
+        });
+        assert_eq!(15, persisted_files.get_metrics().0);
+
+        persisted_files.remove_table(DbId::from(0), TableId::from(1));
+        assert!(persisted_files
+            .get_files(DbId::from(0), TableId::from(1))
+            .is_empty());
+        assert!(persisted_files
+            .get_tombstones(DbId::from(0), TableId::from(1))
+            .is_empty());
+        assert_eq!(
+            10,
+            persisted_files
+                .get_files(DbId::from(0), TableId::from(0))
+                .len()
+        );
+        let (file_count, size_in_mb, row_count) = persisted_files.get_metrics();
+        assert_eq!((13, 130), (file_count, row_count));
+        assert!((size_in_mb - 0.65).abs() < 1e-9);
+
+        persisted_files.remove_database(DbId::from(1));
+        assert!(persisted_files
+            .get_files(DbId::from(1), TableId::from(2))
+            .is_empty());
+        let (file_count, size_in_mb, row_count) = persisted_files.get_metrics();
+        assert_eq!((10, 100), (file_count, row_count));
+        assert!((size_in_mb - 0.5).abs() < 1e-9);
+    }
+
     fn build_persisted_snapshots() -> Vec<PersistedSnapshot> {
         let mut all_persisted_snapshot_files = Vec::new();
         let parquet_files_1 = build_parquet_files(5);
@@ -279,6 +550,8 @@ mod tests {
                 chunk_time: 10,
                 min_time: 10,
                 max_time: 200,
+                wal_file_sequence_number: WalFileSequenceNumber::new(1),
+                column_ids: Default::default(),
             })
             .collect();
         parquet_files
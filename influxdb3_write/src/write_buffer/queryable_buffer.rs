@@ -1,11 +1,17 @@
-use crate::chunk::BufferChunk;
+use crate::chunk::{expired_rows_predicate, filter_deleted_rows, BufferChunk};
 use crate::last_cache::LastCacheProvider;
 use crate::parquet_cache::{CacheRequest, ParquetCacheOracle};
 use crate::paths::ParquetFilePath;
-use crate::persister::Persister;
+use crate::persister::{self, Persister};
 use crate::write_buffer::persisted_files::PersistedFiles;
+use crate::write_buffer::quotas::QuotaTracker;
+use crate::write_buffer::subscriptions::WriteSubscriptions;
 use crate::write_buffer::table_buffer::TableBuffer;
-use crate::{ParquetFile, ParquetFileId, PersistedSnapshot};
+use crate::write_buffer::validator::ValidatedBatches;
+use crate::{ParquetFile, ParquetFileId, PersistedSnapshot, Tombstone};
+use arrow::array::AsArray;
+use arrow::compute::{max, min};
+use arrow::datatypes::TimestampNanosecondType;
 use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
 use data_types::{ChunkId, ChunkOrder, PartitionKey, TimestampMinMax, TransitionPartitionId};
@@ -18,8 +24,11 @@ use influxdb3_catalog::{
     catalog::{Catalog, DatabaseSchema},
     DatabaseSchemaProvider,
 };
-use influxdb3_id::{DbId, TableId};
-use influxdb3_wal::{CatalogOp, SnapshotDetails, WalContents, WalFileNotifier, WalOp, WriteBatch};
+use influxdb3_id::{ColumnId, DbId, TableId};
+use influxdb3_wal::{
+    CatalogBatch, CatalogOp, ColumnRename, DeleteBatch, SnapshotDetails, SnapshotSequenceNumber,
+    WalContents, WalFileNotifier, WalFileSequenceNumber, WalOp, WriteBatch,
+};
 use iox_query::chunk_statistics::{create_chunk_statistics, NoColumnRanges};
 use iox_query::exec::Executor;
 use iox_query::frontend::reorg::ReorgPlanner;
@@ -29,8 +38,9 @@ use observability_deps::tracing::{error, info};
 use parking_lot::RwLock;
 use parquet::format::FileMetaData;
 use schema::sort::SortKey;
-use schema::Schema;
+use schema::{Schema, TIME_COLUMN_NAME};
 use std::any::Any;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::oneshot;
@@ -45,21 +55,32 @@ pub struct QueryableBuffer {
     persisted_files: Arc<PersistedFiles>,
     buffer: Arc<RwLock<BufferState>>,
     parquet_cache: Option<Arc<dyn ParquetCacheOracle>>,
+    /// The contents of each wal file are published here once they are buffered
+    write_subscriptions: Arc<WriteSubscriptions>,
+    /// Counts the series of the writes that are buffered, and provides the series estimates
+    /// persisted with each snapshot
+    quota_tracker: Arc<QuotaTracker>,
     /// Sends a notification to this watch channel whenever a snapshot info is persisted
     persisted_snapshot_notify_rx: tokio::sync::watch::Receiver<Option<PersistedSnapshot>>,
     persisted_snapshot_notify_tx: tokio::sync::watch::Sender<Option<PersistedSnapshot>>,
 }
 
 impl QueryableBuffer {
-    pub fn new(
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
         executor: Arc<Executor>,
         catalog: Arc<Catalog>,
         persister: Arc<Persister>,
         last_cache_provider: Arc<LastCacheProvider>,
         persisted_files: Arc<PersistedFiles>,
         parquet_cache: Option<Arc<dyn ParquetCacheOracle>>,
+        write_subscriptions: Arc<WriteSubscriptions>,
+        quota_tracker: Arc<QuotaTracker>,
     ) -> Self {
-        let buffer = Arc::new(RwLock::new(BufferState::new(Arc::clone(&catalog))));
+        let buffer = Arc::new(RwLock::new(BufferState::new(
+            Arc::clone(&catalog),
+            Arc::clone(&persisted_files),
+        )));
         let (persisted_snapshot_notify_tx, persisted_snapshot_notify_rx) =
             tokio::sync::watch::channel(None);
         Self {
@@ -70,16 +91,21 @@ impl QueryableBuffer {
             persisted_files,
             buffer,
             parquet_cache,
+            write_subscriptions,
+            quota_tracker,
             persisted_snapshot_notify_rx,
             persisted_snapshot_notify_tx,
         }
     }
 
+    /// Get the chunks of a table in the buffer. If the database has a retention cutoff, rows older
+    /// than it are left out.
     pub fn get_table_chunks(
         &self,
         db_schema: Arc<DatabaseSchema>,
         table_name: &str,
         filters: &[Expr],
+        retention_cutoff_ns: Option<i64>,
         _projection: Option<&Vec<usize>>,
         _ctx: &dyn Session,
     ) -> Result<Vec<Arc<dyn QueryChunk>>, DataFusionError> {
@@ -98,40 +124,60 @@ impl QueryableBuffer {
             return Ok(vec![]);
         };
 
-        Ok(table_buffer
+        let mut chunks: Vec<Arc<dyn QueryChunk>> = vec![];
+        for (gen_time, (mut ts_min_max, mut batches)) in table_buffer
             .partitioned_record_batches(Arc::clone(&arrow_schema), filters)
             .map_err(|e| DataFusionError::Execution(format!("error getting batches {}", e)))?
-            .into_iter()
-            .map(|(gen_time, (ts_min_max, batches))| {
-                let row_count = batches.iter().map(|b| b.num_rows()).sum::<usize>();
-                let chunk_stats = create_chunk_statistics(
-                    Some(row_count),
-                    &table_schema,
-                    Some(ts_min_max),
-                    &NoColumnRanges,
-                );
-                Arc::new(BufferChunk {
-                    batches,
-                    schema: table_schema.clone(),
-                    stats: Arc::new(chunk_stats),
-                    partition_id: TransitionPartitionId::new(
-                        data_types::TableId::new(0),
-                        &PartitionKey::from(gen_time.to_string()),
-                    ),
-                    sort_key: None,
-                    id: ChunkId::new(),
-                    chunk_order: ChunkOrder::new(i64::MAX),
-                }) as Arc<dyn QueryChunk>
-            })
-            .collect())
+        {
+            if let Some(cutoff) = retention_cutoff_ns {
+                if ts_min_max.max < cutoff {
+                    continue;
+                }
+                if ts_min_max.min < cutoff {
+                    let expired = expired_rows_predicate(cutoff);
+                    batches = batches
+                        .iter()
+                        .map(|batch| filter_deleted_rows(batch, &expired))
+                        .collect::<Result<_, _>>()?;
+                    ts_min_max = TimestampMinMax::new(cutoff, ts_min_max.max);
+                }
+            }
+            let row_count = batches.iter().map(|b| b.num_rows()).sum::<usize>();
+            let chunk_stats = create_chunk_statistics(
+                Some(row_count),
+                &table_schema,
+                Some(ts_min_max),
+                &NoColumnRanges,
+            );
+            chunks.push(Arc::new(BufferChunk {
+                batches,
+                schema: table_schema.clone(),
+                stats: Arc::new(chunk_stats),
+                partition_id: TransitionPartitionId::new(
+                    data_types::TableId::new(0),
+                    &PartitionKey::from(gen_time.to_string()),
+                ),
+                sort_key: None,
+                id: ChunkId::new(),
+                chunk_order: ChunkOrder::new(i64::MAX),
+            }));
+        }
+        Ok(chunks)
     }
 
     /// Called when the wal has persisted a new file. Buffer the contents in memory and update the last cache so the data is queryable.
     fn buffer_contents(&self, write: WalContents) {
+        let publication = self.write_subscriptions.to_publish(&write);
         let mut buffer = self.buffer.write();
         self.last_cache_provider.evict_expired_cache_entries();
         self.last_cache_provider.write_wal_contents_to_cache(&write);
-        buffer.buffer_ops(write.ops, &self.last_cache_provider);
+        buffer.buffer_ops(
+            write.ops,
+            write.wal_file_number,
+            &self.last_cache_provider,
+            &self.quota_tracker,
+        );
+        publication.publish();
     }
 
     /// Called when the wal has written a new file and is attempting to snapshot. Kicks off persistence of
@@ -145,7 +191,8 @@ impl QueryableBuffer {
             ?snapshot_details,
             "Buffering contents and persisting snapshotted data"
         );
-        let persist_jobs = {
+        let publication = self.write_subscriptions.to_publish(&write);
+        let (persist_jobs, tombstones) = {
             let mut buffer = self.buffer.write();
 
             let mut persisting_chunks = vec![];
@@ -156,8 +203,20 @@ impl QueryableBuffer {
                     let snapshot_chunks = table_buffer.snapshot(snapshot_details.end_time_marker);
 
                     for chunk in snapshot_chunks {
-                        let table_name =
-                            db_schema.table_id_to_name(*table_id).expect("table exists");
+                        let table_def = db_schema
+                            .table_definition_by_id(*table_id)
+                            .expect("table exists");
+                        let table_name = Arc::clone(&table_def.table_name);
+                        // the ids are recorded with the file so that its columns can be found
+                        // under their new names if they are renamed
+                        let column_ids = chunk
+                            .schema
+                            .iter()
+                            .filter_map(|(_, f)| {
+                                let id = table_def.schema.column_id_for_name(f.name())?;
+                                Some((Arc::from(f.name().as_str()), id))
+                            })
+                            .collect();
                         let persist_job = PersistJob {
                             database_id: *database_id,
                             table_id: *table_id,
@@ -176,6 +235,7 @@ impl QueryableBuffer {
                             schema: chunk.schema,
                             timestamp_min_max: chunk.timestamp_min_max,
                             sort_key: table_buffer.sort_key.clone(),
+                            column_ids,
                         };
 
                         persisting_chunks.push(persist_job);
@@ -185,10 +245,18 @@ impl QueryableBuffer {
 
             // we must buffer the ops after the snapshotting as this data should not be persisted
             // with this set of wal files
-            buffer.buffer_ops(write.ops, &self.last_cache_provider);
+            buffer.buffer_ops(
+                write.ops,
+                write.wal_file_number,
+                &self.last_cache_provider,
+                &self.quota_tracker,
+            );
 
-            persisting_chunks
+            // the tombstones for deletes up to and including this wal file are persisted with
+            // the snapshot, as the wal files they were in will be removed
+            (persisting_chunks, std::mem::take(&mut buffer.tombstones))
         };
+        publication.publish();
 
         let (sender, receiver) = oneshot::channel();
 
@@ -200,6 +268,8 @@ impl QueryableBuffer {
         let catalog = Arc::clone(&self.catalog);
         let notify_snapshot_tx = self.persisted_snapshot_notify_tx.clone();
         let parquet_cache = self.parquet_cache.clone();
+        // the series estimates include the writes in this wal file, which were just buffered
+        let series = self.quota_tracker.series_sketches();
 
         tokio::spawn(async move {
             // persist the catalog if it has been updated
@@ -220,6 +290,9 @@ impl QueryableBuffer {
                 {
                     Ok(_) => {
                         catalog.set_updated_false_if_sequence_matches(sequence_number);
+                        if let Err(e) = persister.delete_old_catalog_files().await {
+                            error!(%e, "Error deleting old catalog files");
+                        }
                         break;
                     }
                     Err(e) => {
@@ -241,6 +314,9 @@ impl QueryableBuffer {
                 wal_file_number,
                 catalog.sequence_number(),
             );
+            persisted_snapshot.tombstones = tombstones;
+            persisted_snapshot.last_wal_sequence_number = snapshot_details.last_wal_sequence_number;
+            persisted_snapshot.series = series;
             let mut cache_notifiers = vec![];
             for persist_job in persist_jobs {
                 let path = persist_job.path.to_string();
@@ -249,6 +325,7 @@ impl QueryableBuffer {
                 let chunk_time = persist_job.chunk_time;
                 let min_time = persist_job.timestamp_min_max.min;
                 let max_time = persist_job.timestamp_min_max.max;
+                let column_ids = persist_job.column_ids.clone();
 
                 let (size_bytes, meta, cache_notifier) = sort_dedupe_persist(
                     persist_job,
@@ -269,6 +346,8 @@ impl QueryableBuffer {
                         chunk_time,
                         min_time,
                         max_time,
+                        wal_file_sequence_number: wal_file_number,
+                        column_ids,
                     },
                 )
             }
@@ -314,6 +393,203 @@ impl QueryableBuffer {
         receiver
     }
 
+    /// Called by a read replica for a wal file that the host it follows snapshot on, once the host
+    /// has persisted the snapshot. The data that was persisted is removed from the buffer and
+    /// replaced by the files in the snapshot, rather than being persisted again.
+    pub(crate) fn buffer_contents_and_load_persisted_snapshot(
+        &self,
+        write: WalContents,
+        snapshot_details: SnapshotDetails,
+        persisted_snapshot: PersistedSnapshot,
+    ) {
+        let publication = self.write_subscriptions.to_publish(&write);
+        let mut buffer = self.buffer.write();
+        for table_map in buffer.db_to_table.values_mut() {
+            for table_buffer in table_map.values_mut() {
+                table_buffer.snapshot(snapshot_details.end_time_marker);
+                table_buffer.clear_snapshots();
+            }
+        }
+
+        // as when the host snapshot, the ops are buffered after the snapshotted data is removed
+        buffer.buffer_ops(
+            write.ops,
+            write.wal_file_number,
+            &self.last_cache_provider,
+            &self.quota_tracker,
+        );
+        // the host persisted the tombstones with the snapshot
+        buffer.tombstones.clear();
+        drop(buffer);
+        publication.publish();
+
+        self.persisted_files
+            .add_persisted_snapshot_files(persisted_snapshot.clone());
+        self.persisted_snapshot_notify_tx
+            .send(Some(persisted_snapshot))
+            .expect("persisted snapshot notify tx should not be closed");
+    }
+
+    /// Persists the record batches of a write that does not go through the wal, such as an import
+    /// of parquet files, in a snapshot of their own with the reserved wal file and snapshot
+    /// sequence numbers. The rows of each gen1 chunk of time are sorted, deduplicated and persisted
+    /// to a file as they are when snapshotted from the buffer. The catalog is persisted first, as
+    /// the changes that the write made to it are not in the wal either.
+    pub(crate) async fn persist_batches(
+        &self,
+        batches: ValidatedBatches,
+        wal_file_number: WalFileSequenceNumber,
+        snapshot_sequence_number: SnapshotSequenceNumber,
+    ) -> Result<PersistedSnapshot, persister::Error> {
+        if self.catalog.is_updated() {
+            let inner_catalog = self.catalog.clone_inner();
+            let sequence_number = inner_catalog.sequence_number();
+            self.persister
+                .persist_catalog(wal_file_number, &Catalog::from_inner(inner_catalog))
+                .await?;
+            self.catalog
+                .set_updated_false_if_sequence_matches(sequence_number);
+            if let Err(e) = self.persister.delete_old_catalog_files().await {
+                error!(%e, "Error deleting old catalog files");
+            }
+        }
+
+        let db_schema = self
+            .catalog
+            .db_schema_by_id(batches.database_id)
+            .expect("database of a validated write exists");
+        let table_def = db_schema
+            .table_definition_by_id(batches.table_id)
+            .expect("table of a validated write exists");
+        let mut persisted_snapshot = PersistedSnapshot::new(
+            self.persister.host_identifier_prefix().to_string(),
+            snapshot_sequence_number,
+            wal_file_number,
+            self.catalog.sequence_number(),
+        );
+        // the snapshot covers no wal files itself, so the files covered by the snapshot before it
+        // are still the last that have been
+        persisted_snapshot.last_wal_sequence_number = self
+            .persister
+            .load_snapshots(1)
+            .await?
+            .first()
+            .map(|s| s.last_wal_sequence_number)
+            .unwrap_or_default();
+        persisted_snapshot.series = self.quota_tracker.series_sketches();
+
+        let sort_key = SortKey::from(
+            table_def
+                .influx_schema()
+                .primary_key()
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>(),
+        );
+        let column_ids: BTreeMap<Arc<str>, ColumnId> = batches
+            .schema
+            .iter()
+            .filter_map(|(_, f)| {
+                let id = table_def.schema.column_id_for_name(f.name())?;
+                Some((Arc::from(f.name().as_str()), id))
+            })
+            .collect();
+        let mut cache_notifiers = vec![];
+        for (chunk_time, batch) in batches.chunks {
+            let times = batch
+                .column_by_name(TIME_COLUMN_NAME)
+                .expect("record batches of a table have a time column")
+                .as_primitive::<TimestampNanosecondType>();
+            let timestamp_min_max = TimestampMinMax::new(
+                min(times).expect("chunks have rows"),
+                max(times).expect("chunks have rows"),
+            );
+            let path = ParquetFilePath::new(
+                self.persister.host_identifier_prefix(),
+                db_schema.name.as_ref(),
+                db_schema.id.as_u32(),
+                table_def.table_name.as_ref(),
+                batches.table_id.as_u32(),
+                chunk_time,
+                wal_file_number,
+            );
+            let parquet_file_path = path.to_string();
+            let persist_job = PersistJob {
+                database_id: db_schema.id,
+                table_id: batches.table_id,
+                table_name: Arc::clone(&table_def.table_name),
+                chunk_time,
+                path,
+                batch,
+                schema: batches.schema.clone(),
+                timestamp_min_max,
+                sort_key: sort_key.clone(),
+                column_ids: column_ids.clone(),
+            };
+
+            let (size_bytes, meta, cache_notifier) = sort_dedupe_persist(
+                persist_job,
+                Arc::clone(&self.persister),
+                Arc::clone(&self.executor),
+                self.parquet_cache.clone(),
+            )
+            .await;
+            cache_notifiers.push(cache_notifier);
+            persisted_snapshot.add_parquet_file(
+                db_schema.id,
+                batches.table_id,
+                ParquetFile {
+                    id: ParquetFileId::new(),
+                    path: parquet_file_path,
+                    size_bytes,
+                    row_count: meta.num_rows as u64,
+                    chunk_time,
+                    min_time: timestamp_min_max.min,
+                    max_time: timestamp_min_max.max,
+                    wal_file_sequence_number: wal_file_number,
+                    column_ids: column_ids.clone(),
+                },
+            );
+        }
+
+        self.persister.persist_snapshot(&persisted_snapshot).await?;
+        for notifier in cache_notifiers.into_iter().flatten() {
+            let _ = notifier.await;
+        }
+        self.persisted_files
+            .add_persisted_snapshot_files(persisted_snapshot.clone());
+        self.persisted_snapshot_notify_tx
+            .send(Some(persisted_snapshot.clone()))
+            .expect("persisted snapshot notify tx should not be closed");
+
+        Ok(persisted_snapshot)
+    }
+
+    /// Removes everything from the buffer, used by a read replica before it replays the wal of the
+    /// host it follows from the start
+    pub(crate) fn clear(&self) {
+        let mut buffer = self.buffer.write();
+        *buffer = BufferState::new(Arc::clone(&self.catalog), Arc::clone(&self.persisted_files));
+    }
+
+    /// Applies a catalog batch that renames columns to the catalog, and renames the columns in
+    /// the buffer while holding its lock, so that the catalog and the buffer do not disagree on
+    /// the names of the columns when data is snapshotted.
+    pub(crate) fn apply_column_renames(
+        &self,
+        catalog_batch: &CatalogBatch,
+    ) -> influxdb3_catalog::catalog::Result<()> {
+        let mut buffer = self.buffer.write();
+        self.catalog.apply_catalog_batch(catalog_batch)?;
+        for op in &catalog_batch.ops {
+            if let CatalogOp::RenameColumn(column_rename) = op {
+                buffer.rename_column(catalog_batch.database_id, column_rename);
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn persisted_parquet_files(&self, db_id: DbId, table_id: TableId) -> Vec<ParquetFile> {
         self.persisted_files.get_files(db_id, table_id)
     }
@@ -349,40 +625,63 @@ impl WalFileNotifier for QueryableBuffer {
 pub struct BufferState {
     pub db_to_table: HashMap<DbId, TableIdToBufferMap>,
     catalog: Arc<Catalog>,
+    persisted_files: Arc<PersistedFiles>,
+    /// The tombstones for deletes that have been buffered since the last snapshot
+    tombstones: Vec<Tombstone>,
 }
 
 type TableIdToBufferMap = HashMap<TableId, TableBuffer>;
 
 impl BufferState {
-    pub fn new(catalog: Arc<Catalog>) -> Self {
+    pub fn new(catalog: Arc<Catalog>, persisted_files: Arc<PersistedFiles>) -> Self {
         Self {
             db_to_table: HashMap::new(),
             catalog,
+            persisted_files,
+            tombstones: vec![],
         }
     }
 
-    pub fn buffer_ops(&mut self, ops: Vec<WalOp>, last_cache_provider: &LastCacheProvider) {
+    pub(crate) fn buffer_ops(
+        &mut self,
+        ops: Vec<WalOp>,
+        wal_file_number: WalFileSequenceNumber,
+        last_cache_provider: &LastCacheProvider,
+        quota_tracker: &QuotaTracker,
+    ) {
         for op in ops {
             match op {
-                WalOp::Write(write_batch) => self.add_write_batch(write_batch),
+                WalOp::Write(write_batch) => {
+                    // the series of live writes were counted when they were validated, they are
+                    // counted again here so that the counts are rebuilt when the wal is replayed
+                    if let Some(db_schema) = self.catalog.db_schema_by_id(write_batch.database_id) {
+                        quota_tracker.record_series(&db_schema, &write_batch);
+                    }
+                    self.add_write_batch(write_batch)
+                }
+                WalOp::Delete(delete_batch) => {
+                    self.apply_delete_batch(delete_batch, wal_file_number)
+                }
                 WalOp::Catalog(catalog_batch) => {
                     self.catalog
                         .apply_catalog_batch(&catalog_batch)
                         .expect("catalog batch should apply");
 
-                    let db_schema = self
-                        .catalog
-                        .db_schema_by_id(catalog_batch.database_id)
-                        .expect("database should exist");
+                    let db_id = catalog_batch.database_id;
+                    let db_schema = self.catalog.db_schema_by_id(db_id);
 
                     for op in catalog_batch.ops {
                         match op {
                             CatalogOp::CreateLastCache(definition) => {
-                                let table_schema = db_schema
-                                    .table_schema_by_id(definition.table_id)
-                                    .expect("table should exist");
+                                // the table may have been deleted since, if this is being replayed
+                                let Some(table_schema) = db_schema
+                                    .as_ref()
+                                    .and_then(|db| db.table_schema_by_id(definition.table_id))
+                                else {
+                                    continue;
+                                };
                                 last_cache_provider.create_cache_from_definition(
-                                    db_schema.id,
+                                    db_id,
                                     &table_schema,
                                     &definition,
                                 );
@@ -390,14 +689,41 @@ impl BufferState {
                             CatalogOp::DeleteLastCache(cache) => {
                                 // we can ignore it if this doesn't exist for any reason
                                 let _ = last_cache_provider.delete_cache(
-                                    db_schema.id,
+                                    db_id,
                                     cache.table_id,
                                     &cache.name,
                                 );
                             }
+                            CatalogOp::DeleteDatabase(_) => {
+                                self.db_to_table.remove(&db_id);
+                                self.tombstones.retain(|t| t.database_id != db_id);
+                                last_cache_provider.delete_caches_for_db(db_id);
+                                self.persisted_files.remove_database(db_id);
+                            }
+                            CatalogOp::DeleteTable(table) => {
+                                if let Some(tables) = self.db_to_table.get_mut(&db_id) {
+                                    tables.remove(&table.table_id);
+                                }
+                                self.tombstones.retain(|t| {
+                                    t.database_id != db_id || t.table_id != table.table_id
+                                });
+                                last_cache_provider.delete_caches_for_table(db_id, table.table_id);
+                                self.persisted_files.remove_table(db_id, table.table_id);
+                            }
                             CatalogOp::AddFields(_) => (),
                             CatalogOp::CreateTable(_) => (),
                             CatalogOp::CreateDatabase(_) => (),
+                            CatalogOp::SetSchemaPolicy(_) => (),
+                            CatalogOp::RenameTable(_) => (),
+                            CatalogOp::SetRetentionPeriod(_) => (),
+                            CatalogOp::SetCoercionPolicy(_) => (),
+                            CatalogOp::SetQuotas(_) => (),
+                            CatalogOp::SetJsonMapping(_) => (),
+                            // the column was already renamed in the buffer when the rename was
+                            // applied, unless this is being replayed
+                            CatalogOp::RenameColumn(column_rename) => {
+                                self.rename_column(db_id, &column_rename)
+                            }
                         }
                     }
                 }
@@ -405,18 +731,67 @@ impl BufferState {
         }
     }
 
+    fn apply_delete_batch(
+        &mut self,
+        delete_batch: DeleteBatch,
+        wal_file_number: WalFileSequenceNumber,
+    ) {
+        if let Some(table_buffer) = self
+            .db_to_table
+            .get_mut(&delete_batch.database_id)
+            .and_then(|tables| tables.get_mut(&delete_batch.table_id))
+        {
+            if let Err(e) = table_buffer.delete(&delete_batch.predicate) {
+                error!(
+                    %e,
+                    table_name = %delete_batch.table_name,
+                    "error applying delete to table buffer"
+                );
+            }
+        }
+
+        let tombstone = Tombstone {
+            database_id: delete_batch.database_id,
+            table_id: delete_batch.table_id,
+            wal_file_sequence_number: wal_file_number,
+            predicate: delete_batch.predicate,
+        };
+        self.persisted_files.add_tombstone(tombstone.clone());
+        self.tombstones.push(tombstone);
+    }
+
+    fn rename_column(&mut self, db_id: DbId, column_rename: &ColumnRename) {
+        let Some(table_buffer) = self
+            .db_to_table
+            .get_mut(&db_id)
+            .and_then(|tables| tables.get_mut(&column_rename.table_id))
+        else {
+            return;
+        };
+        if let Err(e) =
+            table_buffer.rename_column(&column_rename.old_name, Arc::clone(&column_rename.new_name))
+        {
+            error!(
+                %e,
+                table_name = %column_rename.table_name,
+                "error renaming column in table buffer"
+            );
+        }
+    }
+
     fn add_write_batch(&mut self, write_batch: WriteBatch) {
-        let db_schema = self
-            .catalog
-            .db_schema_by_id(write_batch.database_id)
-            .expect("database should exist");
+        // the database or table may have been deleted after the write was validated, or before
+        // the wal file it is in is replayed, in which case its rows are dropped
+        let Some(db_schema) = self.catalog.db_schema_by_id(write_batch.database_id) else {
+            return;
+        };
         let database_buffer = self.db_to_table.entry(write_batch.database_id).or_default();
 
         for (table_id, table_chunks) in write_batch.table_chunks {
+            let Some(table_schema) = db_schema.table_definition_by_id(table_id) else {
+                continue;
+            };
             let table_buffer = database_buffer.entry(table_id).or_insert_with(|| {
-                let table_schema = db_schema
-                    .table_definition_by_id(table_id)
-                    .expect("table should exist");
                 let sort_key = table_schema
                     .influx_schema()
                     .primary_key()
@@ -445,6 +820,7 @@ struct PersistJob {
     schema: Schema,
     timestamp_min_max: TimestampMinMax,
     sort_key: SortKey,
+    column_ids: BTreeMap<Arc<str>, ColumnId>,
 }
 
 async fn sort_dedupe_persist(
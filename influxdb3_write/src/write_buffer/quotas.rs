@@ -0,0 +1,452 @@
+//! Tracks the usage of the database quotas that are not kept in the catalog: the series written to
+//! each database and the rate at which it is written to. The series are only tracked for databases
+//! that have a series quota, in a [`SeriesSketch`] that is persisted with each snapshot and
+//! rebuilt from the latest one and the wal on start. The write rate is only tracked in memory.
+
+use super::{Error, Result};
+use crate::SeriesSketch;
+use arrow::array::{Array, AsArray, RecordBatch};
+use arrow::compute::cast;
+use arrow::datatypes::DataType;
+use hashbrown::HashMap;
+use influxdb3_catalog::catalog::DatabaseSchema;
+use influxdb3_id::{DbId, TableId};
+use influxdb3_wal::{FieldData, Quota, Row, WriteBatch};
+use parking_lot::Mutex;
+use schema::{InfluxColumnType, Schema};
+use sha2::{Digest, Sha256};
+
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+
+#[derive(Debug, Default)]
+pub(crate) struct QuotaTracker {
+    databases: Mutex<HashMap<DbId, DatabaseUsage>>,
+}
+
+#[derive(Debug, Default)]
+struct DatabaseUsage {
+    /// The estimate of the series written to the database, if it has a series quota
+    series: Option<SeriesSketch>,
+    /// The second that `bytes_written` is counted for
+    second: i64,
+    /// The number of bytes written to the database during `second`
+    bytes_written: u64,
+}
+
+impl QuotaTracker {
+    /// Creates a tracker that starts from the series estimates persisted with the latest snapshot
+    pub(crate) fn new(series: impl IntoIterator<Item = (DbId, SeriesSketch)>) -> Self {
+        Self {
+            databases: Mutex::new(
+                series
+                    .into_iter()
+                    .map(|(db_id, sketch)| {
+                        (
+                            db_id,
+                            DatabaseUsage {
+                                series: Some(sketch),
+                                ..Default::default()
+                            },
+                        )
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Counts the bytes as written to the database in the current second, or returns an error
+    /// without counting them if that would exceed its write rate quota
+    pub(crate) fn add_write_bytes(
+        &self,
+        db_schema: &DatabaseSchema,
+        bytes: u64,
+        now_ns: i64,
+    ) -> Result<()> {
+        let second = now_ns.div_euclid(NANOS_PER_SECOND);
+        let mut databases = self.databases.lock();
+        let usage = databases.entry(db_schema.id).or_default();
+        if usage.second != second {
+            usage.second = second;
+            usage.bytes_written = 0;
+        }
+
+        let bytes_written = usage.bytes_written.saturating_add(bytes);
+        if let Some(limit) = db_schema.quotas.max_write_bytes_per_second {
+            if bytes_written > limit {
+                return Err(quota_exceeded(db_schema, Quota::WriteBytesPerSecond, limit));
+            }
+        }
+        usage.bytes_written = bytes_written;
+
+        Ok(())
+    }
+
+    /// Checks that the series of the rows in the batch would not exceed the series quota of its
+    /// database, returning the estimate of the series of the database with them added. They are
+    /// not counted until the estimate is passed to [`Self::add_series`], once the write is
+    /// accepted. Returns `None` if there are no new series to count, which is always the case for
+    /// a database without a series quota.
+    pub(crate) fn check_series(
+        &self,
+        db_schema: &DatabaseSchema,
+        batch: &WriteBatch,
+    ) -> Result<Option<SeriesSketch>> {
+        self.check_series_hashes(db_schema, series_hashes(batch))
+    }
+
+    /// Checks the series with the given hashes against the series quota of the database, as
+    /// [`Self::check_series`] does for the rows of a batch
+    pub(crate) fn check_series_hashes(
+        &self,
+        db_schema: &DatabaseSchema,
+        hashes: impl IntoIterator<Item = u64>,
+    ) -> Result<Option<SeriesSketch>> {
+        let mut databases = self.databases.lock();
+        let usage = databases.entry(db_schema.id).or_default();
+        let Some(limit) = db_schema.quotas.max_series else {
+            usage.series = None;
+            return Ok(None);
+        };
+
+        let mut series = usage.series.clone().unwrap_or_default();
+        let mut added = false;
+        for hash in hashes {
+            added |= series.insert(hash);
+        }
+        // writing to existing series is always allowed
+        if !added {
+            return Ok(None);
+        }
+        if series.estimate() > limit {
+            return Err(quota_exceeded(db_schema, Quota::Series, limit));
+        }
+
+        Ok(Some(series))
+    }
+
+    /// Counts the series of a write that was checked with [`Self::check_series`]. The series of
+    /// writes that were checked at the same time are all counted.
+    pub(crate) fn add_series(&self, db_id: DbId, series: &SeriesSketch) {
+        if let Some(usage) = self.databases.lock().get_mut(&db_id) {
+            usage
+                .series
+                .get_or_insert_with(Default::default)
+                .merge(series);
+        }
+    }
+
+    /// Adds the series of the rows in the batch to those written to its database without checking
+    /// its quota, for writes that are buffered from the wal, including when it is replayed on
+    /// start
+    pub(crate) fn record_series(&self, db_schema: &DatabaseSchema, batch: &WriteBatch) {
+        let mut databases = self.databases.lock();
+        let usage = databases.entry(db_schema.id).or_default();
+        if db_schema.quotas.max_series.is_none() {
+            usage.series = None;
+            return;
+        }
+        let series = usage.series.get_or_insert_with(Default::default);
+        for hash in series_hashes(batch) {
+            series.insert(hash);
+        }
+    }
+
+    /// The series estimates of the databases that have a series quota, to persist with a snapshot
+    pub(crate) fn series_sketches(&self) -> std::collections::HashMap<DbId, SeriesSketch> {
+        self.databases
+            .lock()
+            .iter()
+            .filter_map(|(db_id, usage)| Some((*db_id, usage.series.clone()?)))
+            .collect()
+    }
+
+    /// The estimated number of distinct series written to the database, which is only tracked if
+    /// it has a series quota
+    pub(crate) fn series_count(&self, db_id: DbId) -> u64 {
+        self.databases
+            .lock()
+            .get(&db_id)
+            .and_then(|usage| usage.series.as_ref())
+            .map(SeriesSketch::estimate)
+            .unwrap_or_default()
+    }
+
+    /// The number of bytes written to the database in the current second
+    pub(crate) fn bytes_written_this_second(&self, db_id: DbId, now_ns: i64) -> u64 {
+        self.databases
+            .lock()
+            .get(&db_id)
+            .filter(|usage| usage.second == now_ns.div_euclid(NANOS_PER_SECOND))
+            .map(|usage| usage.bytes_written)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn remove_database(&self, db_id: DbId) {
+        self.databases.lock().remove(&db_id);
+    }
+}
+
+pub(crate) fn quota_exceeded(db_schema: &DatabaseSchema, quota: Quota, limit: u64) -> Error {
+    Error::QuotaExceeded {
+        db_name: db_schema.name.to_string(),
+        quota,
+        limit,
+    }
+}
+
+/// The hashes of the series of the rows in the batch
+fn series_hashes(batch: &WriteBatch) -> impl Iterator<Item = u64> + '_ {
+    batch.table_chunks.iter().flat_map(|(table_id, chunks)| {
+        chunks
+            .chunk_time_to_chunk
+            .values()
+            .flat_map(|chunk| chunk.rows.iter())
+            .map(|row| series_hash(*table_id, row))
+    })
+}
+
+/// The hashes of the series of the rows of a record batch written to the table, whose tag columns
+/// are those marked as tags in its schema
+pub(crate) fn record_batch_series_hashes(
+    table_id: TableId,
+    schema: &Schema,
+    batch: &RecordBatch,
+) -> Vec<u64> {
+    let tags = schema
+        .iter()
+        .zip(batch.columns())
+        .filter(|((column_type, _), _)| *column_type == InfluxColumnType::Tag)
+        .map(|((_, field), array)| {
+            let values = cast(array, &DataType::Utf8).expect("tag columns cast to utf8");
+            (field.name().as_str(), values)
+        })
+        .collect::<Vec<_>>();
+    (0..batch.num_rows())
+        .map(|row| {
+            tags_hash(
+                table_id,
+                tags.iter()
+                    .filter(|(_, values)| values.is_valid(row))
+                    .map(|(name, values)| (*name, values.as_string::<i32>().value(row)))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+/// Identifies the series of a row by its table and the values of its tags. The hash is stable
+/// across versions, as it is persisted in snapshots as part of a [`SeriesSketch`].
+fn series_hash(table_id: TableId, row: &Row) -> u64 {
+    let tags = row
+        .fields
+        .iter()
+        .filter_map(|field| match &field.value {
+            FieldData::Tag(value) | FieldData::Key(value) => {
+                Some((field.name.as_ref(), value.as_str()))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    tags_hash(table_id, tags)
+}
+
+/// The hash of the series with the given tag values in the table
+fn tags_hash(table_id: TableId, mut tags: Vec<(&str, &str)>) -> u64 {
+    tags.sort_unstable();
+
+    let mut hasher = Sha256::new();
+    hasher.update(table_id.as_u32().to_le_bytes());
+    for (name, value) in tags {
+        // the lengths keep the boundaries between names and values from being ambiguous
+        hasher.update((name.len() as u64).to_le_bytes());
+        hasher.update(name);
+        hasher.update((value.len() as u64).to_le_bytes());
+        hasher.update(value);
+    }
+    let digest = hasher.finalize();
+    u64::from_le_bytes(digest[..8].try_into().expect("sha256 digest is 32 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use influxdb3_wal::{DatabaseQuotas, Field, TableChunk, TableChunks};
+    use std::sync::Arc;
+
+    fn db_schema(quotas: DatabaseQuotas) -> DatabaseSchema {
+        let mut db_schema = DatabaseSchema::new(DbId::from(0), "foo".into());
+        db_schema.quotas = quotas;
+        db_schema
+    }
+
+    fn write_batch(hosts: &[&str]) -> WriteBatch {
+        let rows = hosts
+            .iter()
+            .map(|host| Row {
+                time: 0,
+                fields: vec![
+                    Field {
+                        name: "host".into(),
+                        value: FieldData::Tag(host.to_string()),
+                    },
+                    Field {
+                        name: "usage".into(),
+                        value: FieldData::Float(1.0),
+                    },
+                ],
+            })
+            .collect();
+        let chunks = TableChunks {
+            min_time: 0,
+            max_time: 0,
+            chunk_time_to_chunk: [(0, TableChunk { rows })].into_iter().collect(),
+        };
+        WriteBatch {
+            database_id: DbId::from(0),
+            database_name: Arc::from("foo"),
+            table_chunks: [(TableId::from(0), chunks)].into_iter().collect(),
+            min_time_ns: 0,
+            max_time_ns: 0,
+        }
+    }
+
+    fn add_series(
+        tracker: &QuotaTracker,
+        db_schema: &DatabaseSchema,
+        batch: &WriteBatch,
+    ) -> Result<()> {
+        if let Some(series) = tracker.check_series(db_schema, batch)? {
+            tracker.add_series(db_schema.id, &series);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn series_quota() {
+        let tracker = QuotaTracker::default();
+        let db_schema = db_schema(DatabaseQuotas {
+            max_series: Some(2),
+            ..Default::default()
+        });
+
+        add_series(&tracker, &db_schema, &write_batch(&["a", "a", "b"])).unwrap();
+        assert_eq!(2, tracker.series_count(db_schema.id));
+
+        // writing to existing series is always allowed, but a new series is not:
+        add_series(&tracker, &db_schema, &write_batch(&["b"])).unwrap();
+        let err = add_series(&tracker, &db_schema, &write_batch(&["a", "c"])).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::QuotaExceeded {
+                quota: Quota::Series,
+                limit: 2,
+                ..
+            }
+        ));
+        assert_eq!(2, tracker.series_count(db_schema.id));
+    }
+
+    #[test]
+    fn checked_series_are_only_counted_once_added() {
+        let tracker = QuotaTracker::default();
+        let db_schema = db_schema(DatabaseQuotas {
+            max_series: Some(2),
+            ..Default::default()
+        });
+
+        let first = tracker
+            .check_series(&db_schema, &write_batch(&["a"]))
+            .unwrap()
+            .unwrap();
+        let second = tracker
+            .check_series(&db_schema, &write_batch(&["b"]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(0, tracker.series_count(db_schema.id));
+
+        tracker.add_series(db_schema.id, &first);
+        tracker.add_series(db_schema.id, &second);
+        assert_eq!(2, tracker.series_count(db_schema.id));
+        assert!(tracker
+            .check_series(&db_schema, &write_batch(&["a", "b"]))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn series_are_only_tracked_with_a_quota_and_survive_a_restart() {
+        let tracker = QuotaTracker::default();
+        let mut db_schema = db_schema(DatabaseQuotas::default());
+
+        add_series(&tracker, &db_schema, &write_batch(&["a", "b"])).unwrap();
+        assert_eq!(0, tracker.series_count(db_schema.id));
+        assert!(tracker.series_sketches().is_empty());
+
+        db_schema.quotas.max_series = Some(3);
+        tracker.record_series(&db_schema, &write_batch(&["a", "b"]));
+        assert_eq!(2, tracker.series_count(db_schema.id));
+
+        // a tracker started from the persisted estimates still enforces the quota
+        let tracker = QuotaTracker::new(tracker.series_sketches());
+        assert_eq!(2, tracker.series_count(db_schema.id));
+        add_series(&tracker, &db_schema, &write_batch(&["a", "c"])).unwrap();
+        add_series(&tracker, &db_schema, &write_batch(&["d"])).unwrap_err();
+        assert_eq!(3, tracker.series_count(db_schema.id));
+
+        // the estimate is dropped once the quota is removed
+        db_schema.quotas.max_series = None;
+        tracker.record_series(&db_schema, &write_batch(&["e"]));
+        assert!(tracker.series_sketches().is_empty());
+    }
+
+    #[test]
+    fn series_sketch_estimates_large_counts_closely() {
+        let mut sketch = SeriesSketch::default();
+        for n in 0..100_000 {
+            let batch = write_batch(&[&n.to_string()]);
+            for hash in series_hashes(&batch) {
+                sketch.insert(hash);
+            }
+        }
+        let estimate = sketch.estimate();
+        assert!(
+            (95_000..=105_000).contains(&estimate),
+            "estimate {estimate} is not within 5% of 100000"
+        );
+    }
+
+    #[test]
+    fn write_rate_quota() {
+        let tracker = QuotaTracker::default();
+        let db_schema = db_schema(DatabaseQuotas {
+            max_write_bytes_per_second: Some(100),
+            ..Default::default()
+        });
+
+        tracker.add_write_bytes(&db_schema, 60, 0).unwrap();
+        let err = tracker
+            .add_write_bytes(&db_schema, 60, NANOS_PER_SECOND - 1)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::QuotaExceeded {
+                quota: Quota::WriteBytesPerSecond,
+                limit: 100,
+                ..
+            }
+        ));
+        assert_eq!(
+            60,
+            tracker.bytes_written_this_second(db_schema.id, NANOS_PER_SECOND - 1)
+        );
+
+        // the count starts over in the next second:
+        assert_eq!(
+            0,
+            tracker.bytes_written_this_second(db_schema.id, NANOS_PER_SECOND)
+        );
+        tracker
+            .add_write_bytes(&db_schema, 60, NANOS_PER_SECOND)
+            .unwrap();
+    }
+}
@@ -0,0 +1,260 @@
+//! Keeps the lines that were rejected from writes, which are otherwise only returned to the client
+//! that made the write, so that operators can find and replay them. The most recently rejected
+//! lines are kept in memory, and can also be persisted to object store as newline-delimited JSON.
+
+use crate::paths::RejectedWritesFilePath;
+use crate::persister::{Persister, Result};
+use crate::WriteLineError;
+use iox_time::TimeProvider;
+use observability_deps::tracing::{debug, error};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The number of rejected lines kept in memory by default
+pub const DEFAULT_REJECTED_WRITES_CAPACITY: usize = 1_000;
+
+/// How often the rejected lines are persisted to object store, if they are persisted
+pub const REJECTED_WRITES_PERSIST_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy)]
+pub struct RejectedWritesConfig {
+    /// The number of rejected lines kept in memory, after which the oldest are dropped to make
+    /// room for new ones
+    pub capacity: usize,
+    /// Whether the rejected lines are also persisted to object store
+    pub persist: bool,
+}
+
+impl Default for RejectedWritesConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_REJECTED_WRITES_CAPACITY,
+            persist: false,
+        }
+    }
+}
+
+/// A line that was rejected from a write
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RejectedWrite {
+    /// The time of the write the line was in
+    pub time_ns: i64,
+    pub database_name: Arc<str>,
+    pub line_number: usize,
+    pub original_line: String,
+    pub error_message: String,
+}
+
+#[derive(Debug)]
+pub struct RejectedWrites {
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// The most recently rejected lines, oldest first
+    lines: VecDeque<RejectedWrite>,
+    /// The lines that have not been persisted yet, or `None` if they are not persisted
+    unpersisted: Option<VecDeque<RejectedWrite>>,
+}
+
+impl RejectedWrites {
+    pub fn new(config: RejectedWritesConfig) -> Self {
+        Self {
+            capacity: config.capacity,
+            inner: Mutex::new(Inner {
+                lines: VecDeque::new(),
+                unpersisted: config.persist.then(VecDeque::new),
+            }),
+        }
+    }
+
+    /// Create the store, and if its lines are persisted, start the background task that persists
+    /// them on an interval
+    pub fn new_with_background_task(
+        config: RejectedWritesConfig,
+        persister: Arc<Persister>,
+        time_provider: Arc<dyn TimeProvider>,
+    ) -> Arc<Self> {
+        let rejected_writes = Arc::new(Self::new(config));
+        if config.persist {
+            background_rejected_writes_persister(
+                Arc::clone(&rejected_writes),
+                persister,
+                time_provider,
+            );
+        }
+        rejected_writes
+    }
+
+    /// Add the lines rejected from a write to the database made at the given time
+    pub fn add(&self, database_name: &str, time_ns: i64, lines: &[WriteLineError]) {
+        if lines.is_empty() || self.capacity == 0 {
+            return;
+        }
+        let database_name: Arc<str> = database_name.into();
+        let mut inner = self.inner.lock();
+        for line in lines {
+            let rejected = RejectedWrite {
+                time_ns,
+                database_name: Arc::clone(&database_name),
+                line_number: line.line_number,
+                original_line: line.original_line.clone(),
+                error_message: line.error_message.clone(),
+            };
+            if let Some(unpersisted) = inner.unpersisted.as_mut() {
+                push_bounded(unpersisted, rejected.clone(), self.capacity);
+            }
+            push_bounded(&mut inner.lines, rejected, self.capacity);
+        }
+    }
+
+    /// The most recently rejected lines, oldest first
+    pub fn lines(&self) -> Vec<RejectedWrite> {
+        self.inner.lock().lines.iter().cloned().collect()
+    }
+
+    /// Persist the lines rejected since they were last persisted to a single file, named for the
+    /// given time. Returns the number of lines that were persisted. If this fails, the lines are
+    /// kept to be persisted the next time.
+    pub async fn persist(&self, persister: &Persister, time_ns: i64) -> Result<usize> {
+        let lines = match self.inner.lock().unpersisted.as_mut() {
+            Some(unpersisted) => std::mem::take(unpersisted),
+            None => return Ok(0),
+        };
+        if lines.is_empty() {
+            return Ok(0);
+        }
+
+        let mut contents = String::new();
+        for line in &lines {
+            contents.push_str(&serde_json::to_string(line).expect("rejected write serializes"));
+            contents.push('\n');
+        }
+        let path = RejectedWritesFilePath::new(persister.host_identifier_prefix(), time_ns);
+        if let Err(e) = persister
+            .object_store()
+            .put(path.as_ref(), contents.into_bytes().into())
+            .await
+        {
+            // put the lines back in front of any that were rejected in the meantime:
+            let mut inner = self.inner.lock();
+            if let Some(unpersisted) = inner.unpersisted.as_mut() {
+                let newer = std::mem::replace(unpersisted, lines);
+                for line in newer {
+                    push_bounded(unpersisted, line, self.capacity);
+                }
+            }
+            return Err(e.into());
+        }
+        debug!(path = %path.as_ref(), lines = lines.len(), "persisted rejected writes");
+
+        Ok(lines.len())
+    }
+}
+
+/// Push the line to the back of the queue, dropping the oldest lines to keep it within capacity
+fn push_bounded(lines: &mut VecDeque<RejectedWrite>, line: RejectedWrite, capacity: usize) {
+    while lines.len() >= capacity {
+        lines.pop_front();
+    }
+    lines.push_back(line);
+}
+
+/// Persists the rejected lines on an interval
+fn background_rejected_writes_persister(
+    rejected_writes: Arc<RejectedWrites>,
+    persister: Arc<Persister>,
+    time_provider: Arc<dyn TimeProvider>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REJECTED_WRITES_PERSIST_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let now_ns = time_provider.now().timestamp_nanos();
+            if let Err(e) = rejected_writes.persist(&persister, now_ns).await {
+                error!(%e, "error persisting rejected writes");
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::TryStreamExt;
+    use object_store::memory::InMemory;
+    use object_store::ObjectStore;
+    use pretty_assertions::assert_eq;
+
+    fn line(line_number: usize) -> WriteLineError {
+        WriteLineError {
+            original_line: format!("cpu,host=a usage= {line_number}"),
+            line_number,
+            error_message: "No fields were provided".to_string(),
+        }
+    }
+
+    #[test]
+    fn keeps_most_recent_lines() {
+        let rejected_writes = RejectedWrites::new(RejectedWritesConfig {
+            capacity: 2,
+            persist: false,
+        });
+        rejected_writes.add("foo", 1, &[line(1), line(2)]);
+        rejected_writes.add("bar", 2, &[line(1)]);
+
+        let lines = rejected_writes.lines();
+        assert_eq!(
+            vec![(1, "foo", 2), (2, "bar", 1)],
+            lines
+                .iter()
+                .map(|l| (l.time_ns, l.database_name.as_ref(), l.line_number))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn persists_lines_to_object_store() {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let persister = Persister::new(Arc::clone(&object_store), "test_host");
+        let rejected_writes = RejectedWrites::new(RejectedWritesConfig {
+            capacity: 10,
+            persist: true,
+        });
+        rejected_writes.add("foo", 1, &[line(1), line(3)]);
+
+        assert_eq!(2, rejected_writes.persist(&persister, 10).await.unwrap());
+        // the lines are only persisted once:
+        assert_eq!(0, rejected_writes.persist(&persister, 20).await.unwrap());
+
+        let files = object_store
+            .list(None)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(1, files.len());
+        assert_eq!(
+            RejectedWritesFilePath::new("test_host", 10).as_ref(),
+            &files[0].location
+        );
+        let contents = object_store
+            .get(&files[0].location)
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let persisted = std::str::from_utf8(&contents)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str::<RejectedWrite>(l).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(rejected_writes.lines(), persisted);
+    }
+}
@@ -0,0 +1,269 @@
+//! A read replica follows the WAL files and snapshots that another host writes to object store,
+//! so that queries can be served by separate processes. It replays the WAL files of the host into
+//! its own [`QueryableBuffer`], which also applies the catalog changes in them, and loads the
+//! persisted snapshots of the host into its [`PersistedFiles`] rather than persisting anything
+//! itself. All writes to a replica are rejected. A snapshot that the host persists outside of its
+//! WAL, such as an import of parquet files, reserves a wal file number that is never written, and
+//! the gap this leaves makes the replica reload from the latest snapshots of the host.
+
+use crate::persister::Persister;
+use crate::write_buffer::persisted_files::PersistedFiles;
+use crate::write_buffer::queryable_buffer::QueryableBuffer;
+use crate::write_buffer::Result;
+use crate::PersistedSnapshot;
+use influxdb3_catalog::catalog::Catalog;
+use influxdb3_wal::object_store::list_wal_files;
+use influxdb3_wal::serialize::verify_file_type_and_deserialize;
+use influxdb3_wal::{
+    QuarantinedWalFile, ReplicaConfig, SnapshotDetails, SnapshotInfo, SnapshotSequenceNumber, Wal,
+    WalContents, WalFileNotifier, WalFileSequenceNumber, WalOp,
+};
+use object_store::path::Path;
+use object_store::ObjectStore;
+use observability_deps::tracing::{debug, error, info, warn};
+use std::sync::Arc;
+use tokio::sync::{oneshot, Mutex, OwnedSemaphorePermit};
+
+/// The number of the most recent snapshots of the host that are searched for the one persisted
+/// for a wal file
+const SNAPSHOTS_TO_SEARCH: usize = 10;
+
+#[derive(Debug)]
+pub struct WalReplica {
+    persister: Arc<Persister>,
+    catalog: Arc<Catalog>,
+    queryable_buffer: Arc<QueryableBuffer>,
+    persisted_files: Arc<PersistedFiles>,
+    state: Mutex<ReplicaState>,
+}
+
+#[derive(Debug, Default)]
+struct ReplicaState {
+    /// The last wal file of the host that was replayed into the buffer
+    last_wal_file: Option<WalFileSequenceNumber>,
+    /// The last snapshot of the host that was loaded into the persisted files
+    last_snapshot: Option<SnapshotSequenceNumber>,
+}
+
+impl WalReplica {
+    /// Creates a new replica of the host that the persister is for. This replays the wal files
+    /// that exist for the host before returning, and then polls for new ones in the background.
+    pub(crate) async fn new(
+        persister: Arc<Persister>,
+        catalog: Arc<Catalog>,
+        queryable_buffer: Arc<QueryableBuffer>,
+        persisted_files: Arc<PersistedFiles>,
+        config: ReplicaConfig,
+        last_snapshot_sequence_number: Option<SnapshotSequenceNumber>,
+    ) -> Result<Arc<Self>> {
+        let replica = Arc::new(Self {
+            persister,
+            catalog,
+            queryable_buffer,
+            persisted_files,
+            state: Mutex::new(ReplicaState {
+                last_wal_file: None,
+                last_snapshot: last_snapshot_sequence_number,
+            }),
+        });
+        info!(
+            host = replica.persister.host_identifier_prefix(),
+            "replaying wal of host as a read replica"
+        );
+        replica.poll().await?;
+        background_replica_poll(Arc::clone(&replica), config);
+
+        Ok(replica)
+    }
+
+    /// Replays any wal files written by the host since the last poll. If the host removed wal
+    /// files before they were replayed, the replica reloads from the latest snapshots of the host
+    /// and replays its wal from the start.
+    pub async fn poll(&self) -> Result<()> {
+        let mut state = self.state.lock().await;
+        let object_store = self.persister.object_store();
+        let host = self.persister.host_identifier_prefix();
+
+        let mut files = vec![];
+        for meta in list_wal_files(object_store.as_ref(), host).await? {
+            let wal_file_number = WalFileSequenceNumber::try_from(&meta.location)?;
+            files.push((wal_file_number, meta.location));
+        }
+
+        if let (Some(last), Some((first_new, _))) = (
+            state.last_wal_file,
+            files.iter().find(|(n, _)| Some(*n) > state.last_wal_file),
+        ) {
+            if *first_new != last.next() {
+                warn!(
+                    host,
+                    last_replayed = %last,
+                    next_available = %first_new,
+                    "wal files were removed by the host before they were replayed, reloading \
+                    from the latest snapshots"
+                );
+                self.reload(&mut state).await?;
+            }
+        }
+
+        for (wal_file_number, path) in files {
+            if state.last_wal_file >= Some(wal_file_number) {
+                continue;
+            }
+            let Some(contents) = read_wal_file(object_store.as_ref(), &path).await? else {
+                // removed by a snapshot of the host since it was listed, the next poll reloads
+                break;
+            };
+
+            match contents.snapshot {
+                None => self.queryable_buffer.notify(contents),
+                Some(snapshot_details) => {
+                    let Some(persisted_snapshot) = self
+                        .find_persisted_snapshot(&snapshot_details, wal_file_number)
+                        .await?
+                    else {
+                        // the host has not finished persisting the snapshot, this file is
+                        // replayed once it has
+                        debug!(%wal_file_number, "waiting for host to persist snapshot");
+                        break;
+                    };
+                    self.queryable_buffer
+                        .buffer_contents_and_load_persisted_snapshot(
+                            contents,
+                            snapshot_details,
+                            persisted_snapshot,
+                        );
+                    state.last_snapshot = Some(snapshot_details.snapshot_sequence_number);
+                }
+            }
+            state.last_wal_file = Some(wal_file_number);
+        }
+
+        Ok(())
+    }
+
+    /// Finds the snapshot the host persisted for the wal file, if it has been persisted yet
+    async fn find_persisted_snapshot(
+        &self,
+        snapshot_details: &SnapshotDetails,
+        wal_file_number: WalFileSequenceNumber,
+    ) -> Result<Option<PersistedSnapshot>> {
+        let snapshots = self.persister.load_snapshots(SNAPSHOTS_TO_SEARCH).await?;
+        Ok(snapshots.into_iter().find(|s| {
+            s.snapshot_sequence_number == snapshot_details.snapshot_sequence_number
+                && s.wal_file_sequence_number == wal_file_number
+        }))
+    }
+
+    /// Loads the catalog and the snapshots that the host persisted since the last one that was
+    /// loaded, and clears the buffer so that the wal files of the host can be replayed from the
+    /// start, as they are when the host restarts.
+    async fn reload(&self, state: &mut ReplicaState) -> Result<()> {
+        if let Some(persisted_catalog) = self.persister.load_catalog().await? {
+            let mut inner = self.catalog.inner().write();
+            if persisted_catalog.catalog.sequence_number() > inner.sequence_number() {
+                *inner = persisted_catalog.catalog;
+            }
+        }
+
+        let mut snapshots = self
+            .persister
+            .load_snapshots(crate::write_buffer::N_SNAPSHOTS_TO_LOAD_ON_START)
+            .await?;
+        snapshots.retain(|s| Some(s.snapshot_sequence_number) > state.last_snapshot);
+        // snapshots are loaded most recent first
+        if let Some(latest) = snapshots.first() {
+            state.last_snapshot = Some(latest.snapshot_sequence_number);
+        }
+        for snapshot in snapshots.into_iter().rev() {
+            self.persisted_files.add_persisted_snapshot_files(snapshot);
+        }
+
+        self.queryable_buffer.clear();
+        state.last_wal_file = None;
+
+        Ok(())
+    }
+}
+
+/// Reads a wal file of the host, returning `None` if it no longer exists
+pub(crate) async fn read_wal_file(
+    object_store: &dyn ObjectStore,
+    path: &Path,
+) -> influxdb3_wal::Result<Option<WalContents>> {
+    let bytes = match object_store.get(path).await {
+        Ok(result) => result.bytes().await?,
+        Err(object_store::Error::NotFound { .. }) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Some(verify_file_type_and_deserialize(bytes)?))
+}
+
+/// Polls for new wal files and snapshots of the host on the configured interval
+fn background_replica_poll(
+    replica: Arc<WalReplica>,
+    config: ReplicaConfig,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.poll_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = replica.poll().await {
+                error!(%e, "error replaying wal of host into read replica");
+            }
+        }
+    })
+}
+
+#[async_trait::async_trait]
+impl Wal for WalReplica {
+    async fn buffer_op_unconfirmed(&self, _op: WalOp) -> influxdb3_wal::Result<()> {
+        Err(influxdb3_wal::Error::ReadReplica)
+    }
+
+    async fn write_ops(&self, _ops: Vec<WalOp>) -> influxdb3_wal::Result<()> {
+        Err(influxdb3_wal::Error::ReadReplica)
+    }
+
+    async fn flush_buffer(
+        &self,
+    ) -> Option<(
+        oneshot::Receiver<SnapshotDetails>,
+        SnapshotInfo,
+        OwnedSemaphorePermit,
+    )> {
+        None
+    }
+
+    async fn cleanup_snapshot(
+        &self,
+        _snapshot_details: SnapshotInfo,
+        snapshot_permit: OwnedSemaphorePermit,
+    ) {
+        // the host removes its own wal files
+        drop(snapshot_permit);
+    }
+
+    async fn last_wal_sequence_number(&self) -> WalFileSequenceNumber {
+        self.state.lock().await.last_wal_file.unwrap_or_default()
+    }
+
+    async fn last_snapshot_sequence_number(&self) -> SnapshotSequenceNumber {
+        self.state.lock().await.last_snapshot.unwrap_or_default()
+    }
+
+    async fn reserve_snapshot(
+        &self,
+    ) -> influxdb3_wal::Result<(WalFileSequenceNumber, SnapshotSequenceNumber)> {
+        Err(influxdb3_wal::Error::ReadReplica)
+    }
+
+    async fn shutdown(&self) {}
+
+    fn quarantined_files(&self) -> Vec<QuarantinedWalFile> {
+        vec![]
+    }
+}
@@ -0,0 +1,521 @@
+//! Subscriptions to the stream of writes accepted by the server. Every WAL file is published to
+//! subscribers once it has been persisted and loaded into the buffer, so that downstream systems
+//! see each accepted write without polling queries. A subscription can resume from an earlier WAL
+//! file, in which case the WAL files that are still retained in object store are replayed to it
+//! before it switches over to the live stream.
+
+use crate::persister::Persister;
+use crate::write_buffer::replica::read_wal_file;
+use crate::write_buffer::{Error, Result};
+use futures::Stream;
+use influxdb3_catalog::catalog::Catalog;
+use influxdb3_catalog::DatabaseSchemaProvider;
+use influxdb3_wal::object_store::list_wal_files_with_retained;
+use influxdb3_wal::{FieldData, WalContents, WalFileSequenceNumber, WalOp};
+use object_store::path::Path;
+use observability_deps::tracing::{error, warn};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::{broadcast, mpsc};
+
+/// The number of WAL files that are held for a slow subscriber before it is disconnected
+const LIVE_CHANNEL_CAPACITY: usize = 1_000;
+
+/// The number of WAL files worth of records buffered for a subscriber before the subscription
+/// waits for it to read them
+const SUBSCRIBER_CHANNEL_CAPACITY: usize = 16;
+
+#[derive(Debug)]
+pub struct WriteSubscriptions {
+    catalog: Arc<Catalog>,
+    persister: Arc<Persister>,
+    live_tx: broadcast::Sender<Arc<WalContents>>,
+    /// The last WAL file that was published
+    last_published: parking_lot::Mutex<Option<WalFileSequenceNumber>>,
+}
+
+/// A WAL file that is being buffered, to publish to subscribers once it has been
+#[derive(Debug)]
+pub(crate) struct Publication<'a> {
+    wal_file_number: WalFileSequenceNumber,
+    contents: Option<Arc<WalContents>>,
+    live_tx: &'a broadcast::Sender<Arc<WalContents>>,
+    last_published: parking_lot::MutexGuard<'a, Option<WalFileSequenceNumber>>,
+}
+
+impl Publication<'_> {
+    /// Publishes the contents of the WAL file to all subscribers
+    pub(crate) fn publish(mut self) {
+        if *self.last_published >= Some(self.wal_file_number) {
+            // a read replica that reloads replays files it already published
+            return;
+        }
+        *self.last_published = Some(self.wal_file_number);
+        if let Some(contents) = self.contents {
+            let _ = self.live_tx.send(contents);
+        }
+    }
+}
+
+/// Limits a subscription to the writes for a database and, optionally, a table in it
+#[derive(Debug, Clone, Default)]
+pub struct SubscriptionFilter {
+    pub database: Option<String>,
+    pub table: Option<String>,
+}
+
+/// A single row written to a table, as it is sent to subscribers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WriteRecord {
+    /// The WAL file the row was written in, which subscriptions can be resumed from
+    pub wal_file_number: WalFileSequenceNumber,
+    pub database: Arc<str>,
+    pub table: Arc<str>,
+    /// The time of the row in nanoseconds since the epoch
+    pub time: i64,
+    pub tags: BTreeMap<Arc<str>, String>,
+    pub fields: BTreeMap<Arc<str>, FieldValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum FieldValue {
+    String(String),
+    Integer(i64),
+    UInteger(u64),
+    Float(f64),
+    Boolean(bool),
+}
+
+impl WriteSubscriptions {
+    pub fn new(catalog: Arc<Catalog>, persister: Arc<Persister>) -> Self {
+        let (live_tx, _) = broadcast::channel(LIVE_CHANNEL_CAPACITY);
+        Self {
+            catalog,
+            persister,
+            live_tx,
+            last_published: Default::default(),
+        }
+    }
+
+    /// Starts publishing a WAL file, which is done once its contents have been buffered so that
+    /// any catalog changes in them have been applied. The contents are copied if there are any
+    /// subscribers to publish them to. New subscriptions wait until the file is published, so that
+    /// each of them either receives it live or replays it.
+    pub(crate) fn to_publish(&self, contents: &WalContents) -> Publication<'_> {
+        let last_published = self.last_published.lock();
+        Publication {
+            wal_file_number: contents.wal_file_number,
+            contents: (self.live_tx.receiver_count() > 0).then(|| Arc::new(contents.clone())),
+            live_tx: &self.live_tx,
+            last_published,
+        }
+    }
+
+    /// Subscribes to the writes that match the filter. If `from` is set, the writes in the WAL
+    /// files from that one on are sent first, which is an error if they are no longer retained.
+    pub async fn subscribe(
+        &self,
+        filter: SubscriptionFilter,
+        from: Option<WalFileSequenceNumber>,
+    ) -> Result<WriteSubscription> {
+        // subscribe to the live stream before listing the retained files, and while no file is
+        // being published, so that every file after the last published one is received live
+        let (live_rx, last_published) = {
+            let last_published = self.last_published.lock();
+            (self.live_tx.subscribe(), *last_published)
+        };
+
+        let replay = match (from, last_published) {
+            (Some(from), Some(last_published)) if from <= last_published => {
+                self.retained_wal_files(from, last_published).await?
+            }
+            _ => vec![],
+        };
+
+        let (tx, rx) = mpsc::channel(SUBSCRIBER_CHANNEL_CAPACITY);
+        tokio::spawn(run_subscription(
+            Arc::clone(&self.catalog),
+            Arc::clone(&self.persister),
+            filter,
+            replay,
+            last_published,
+            live_rx,
+            tx,
+        ));
+
+        Ok(WriteSubscription { rx })
+    }
+
+    /// Returns the paths of the retained WAL files from `from` through `to`, or an error if any of
+    /// them are no longer retained, or have not been uploaded to object store yet
+    async fn retained_wal_files(
+        &self,
+        from: WalFileSequenceNumber,
+        to: WalFileSequenceNumber,
+    ) -> Result<Vec<(WalFileSequenceNumber, Path)>> {
+        // files already covered by a snapshot are only available if snapshotted wal files are
+        // being retained
+        let mut files = list_wal_files_with_retained(
+            self.persister.object_store().as_ref(),
+            self.persister.host_identifier_prefix(),
+        )
+        .await?;
+        files.retain(|(n, _)| *n >= from && *n <= to);
+
+        // every file in the range has to be there, as a file that was quarantined or pruned
+        // leaves a hole in it
+        let mut expected = from;
+        for (n, _) in &files {
+            if *n != expected {
+                return Err(Error::WalFileNotRetained(expected));
+            }
+            expected = expected.next();
+        }
+        if expected <= to {
+            return Err(Error::WalFileNotRetained(expected));
+        }
+
+        Ok(files)
+    }
+}
+
+/// The records for each WAL file that matches the filter of a subscription
+#[derive(Debug)]
+pub struct WriteSubscription {
+    rx: mpsc::Receiver<Vec<WriteRecord>>,
+}
+
+impl Stream for WriteSubscription {
+    type Item = Vec<WriteRecord>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+/// Sends the records in the WAL files to replay and then those published live to the subscriber,
+/// until it goes away or falls too far behind
+async fn run_subscription(
+    catalog: Arc<Catalog>,
+    persister: Arc<Persister>,
+    filter: SubscriptionFilter,
+    replay: Vec<(WalFileSequenceNumber, Path)>,
+    last_published: Option<WalFileSequenceNumber>,
+    mut live_rx: broadcast::Receiver<Arc<WalContents>>,
+    tx: mpsc::Sender<Vec<WriteRecord>>,
+) {
+    let object_store = persister.object_store();
+    for (wal_file_number, path) in replay {
+        let contents = match read_wal_file(object_store.as_ref(), &path).await {
+            Ok(Some(contents)) => contents,
+            Ok(None) => {
+                // removed by a snapshot since it was listed, the subscriber has to resume from
+                // a later file
+                warn!(%wal_file_number, "wal file removed while replaying it to subscriber");
+                return;
+            }
+            Err(e) => {
+                error!(%e, %wal_file_number, "error reading wal file for subscriber");
+                return;
+            }
+        };
+        if !send_records(&catalog, &filter, &contents, &tx).await {
+            return;
+        }
+    }
+
+    loop {
+        let contents = match live_rx.recv().await {
+            Ok(contents) => contents,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!(
+                    skipped,
+                    "write subscriber fell behind and was disconnected, it can resume from the \
+                    last wal file it received"
+                );
+                return;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        // files up to the last published one when subscribing were replayed
+        if Some(contents.wal_file_number) <= last_published {
+            continue;
+        }
+        if !send_records(&catalog, &filter, &contents, &tx).await {
+            return;
+        }
+    }
+}
+
+/// Sends the records in the contents that match the filter, returning false if the subscriber
+/// has gone away
+async fn send_records(
+    catalog: &Catalog,
+    filter: &SubscriptionFilter,
+    contents: &WalContents,
+    tx: &mpsc::Sender<Vec<WriteRecord>>,
+) -> bool {
+    let records = write_records(catalog, filter, contents);
+    records.is_empty() || tx.send(records).await.is_ok()
+}
+
+/// Converts the writes in the WAL contents into records, keeping those that match the filter
+pub fn write_records(
+    catalog: &Catalog,
+    filter: &SubscriptionFilter,
+    contents: &WalContents,
+) -> Vec<WriteRecord> {
+    let mut records = vec![];
+    for op in &contents.ops {
+        let WalOp::Write(write_batch) = op else {
+            continue;
+        };
+        if filter
+            .database
+            .as_deref()
+            .is_some_and(|db| db != write_batch.database_name.as_ref())
+        {
+            continue;
+        }
+        let Some(db_schema) = catalog.db_schema_by_id(write_batch.database_id) else {
+            continue;
+        };
+
+        let mut tables = write_batch
+            .table_chunks
+            .iter()
+            .filter_map(|(table_id, chunks)| {
+                db_schema
+                    .table_id_to_name(*table_id)
+                    .map(|table_name| (table_name, chunks))
+            })
+            .filter(|(table_name, _)| {
+                filter
+                    .table
+                    .as_deref()
+                    .map_or(true, |table| table == table_name.as_ref())
+            })
+            .collect::<Vec<_>>();
+        tables.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (table_name, chunks) in tables {
+            let mut chunk_times = chunks.chunk_time_to_chunk.keys().collect::<Vec<_>>();
+            chunk_times.sort();
+            for chunk_time in chunk_times {
+                for row in &chunks.chunk_time_to_chunk[chunk_time].rows {
+                    let mut tags = BTreeMap::new();
+                    let mut fields = BTreeMap::new();
+                    for field in &row.fields {
+                        let name = Arc::clone(&field.name);
+                        match &field.value {
+                            FieldData::Timestamp(_) => {}
+                            FieldData::Key(v) | FieldData::Tag(v) => {
+                                tags.insert(name, v.clone());
+                            }
+                            FieldData::String(v) => {
+                                fields.insert(name, FieldValue::String(v.clone()));
+                            }
+                            FieldData::Integer(v) => {
+                                fields.insert(name, FieldValue::Integer(*v));
+                            }
+                            FieldData::UInteger(v) => {
+                                fields.insert(name, FieldValue::UInteger(*v));
+                            }
+                            FieldData::Float(v) => {
+                                fields.insert(name, FieldValue::Float(*v));
+                            }
+                            FieldData::Boolean(v) => {
+                                fields.insert(name, FieldValue::Boolean(*v));
+                            }
+                        }
+                    }
+                    records.push(WriteRecord {
+                        wal_file_number: contents.wal_file_number,
+                        database: Arc::clone(&write_batch.database_name),
+                        table: Arc::clone(&table_name),
+                        time: row.time,
+                        tags,
+                        fields,
+                    });
+                }
+            }
+        }
+    }
+
+    records
+}
+
+impl WriteRecord {
+    /// Formats the record as a line of line protocol, with a nanosecond timestamp
+    pub fn to_line_protocol(&self) -> String {
+        let mut line = escape(&self.table, &[',', ' ']);
+        for (tag, value) in &self.tags {
+            let _ = write!(
+                line,
+                ",{}={}",
+                escape(tag, &[',', '=', ' ']),
+                escape(value, &[',', '=', ' '])
+            );
+        }
+        for (i, (field, value)) in self.fields.iter().enumerate() {
+            line.push(if i == 0 { ' ' } else { ',' });
+            line.push_str(&escape(field, &[',', '=', ' ']));
+            line.push('=');
+            match value {
+                FieldValue::String(v) => {
+                    let _ = write!(line, "\"{}\"", escape(v, &['"']));
+                }
+                FieldValue::Integer(v) => {
+                    let _ = write!(line, "{v}i");
+                }
+                FieldValue::UInteger(v) => {
+                    let _ = write!(line, "{v}u");
+                }
+                FieldValue::Float(v) => {
+                    let _ = write!(line, "{v}");
+                }
+                FieldValue::Boolean(v) => {
+                    let _ = write!(line, "{v}");
+                }
+            }
+        }
+        let _ = write!(line, " {}", self.time);
+
+        line
+    }
+}
+
+/// Escapes the given characters and backslashes with a backslash
+fn escape(s: &str, chars: &[char]) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c == '\\' || chars.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+    use object_store::ObjectStore;
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn subscribing_waits_for_the_file_being_published() {
+        let catalog = Arc::new(Catalog::new("host".into(), "instance".into()));
+        let persister = Arc::new(Persister::new(Arc::new(InMemory::new()), "host"));
+        let subscriptions = Arc::new(WriteSubscriptions::new(catalog, persister));
+        let contents = WalContents {
+            min_timestamp_ns: 0,
+            max_timestamp_ns: 0,
+            wal_file_number: WalFileSequenceNumber::new(1),
+            ops: vec![],
+            snapshot: None,
+            persisted_time_ns: 0,
+        };
+
+        // there are no subscribers to send the file to live when it starts being published
+        let publication = subscriptions.to_publish(&contents);
+        assert!(publication.contents.is_none());
+        let subscribe = tokio::spawn({
+            let subscriptions = Arc::clone(&subscriptions);
+            async move {
+                subscriptions
+                    .subscribe(
+                        SubscriptionFilter::default(),
+                        Some(WalFileSequenceNumber::new(1)),
+                    )
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!subscribe.is_finished());
+        publication.publish();
+
+        // so the subscription that started meanwhile has to replay it, which it can't as the file
+        // was never written to object store
+        let err = subscribe.await.unwrap().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::WalFileNotRetained(n) if n == WalFileSequenceNumber::new(1)
+        ));
+    }
+
+    #[tokio::test]
+    async fn retained_wal_files_with_a_hole_cannot_be_replayed() {
+        use influxdb3_wal::object_store::{retained_wal_path, wal_path};
+
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let persister = Arc::new(Persister::new(Arc::clone(&object_store), "host"));
+        let catalog = Arc::new(Catalog::new("host".into(), "instance".into()));
+        let subscriptions = WriteSubscriptions::new(catalog, persister);
+        // file 2 was quarantined, and file 4 has not been snapshotted yet
+        for path in [
+            retained_wal_path("host", WalFileSequenceNumber::new(1)),
+            retained_wal_path("host", WalFileSequenceNumber::new(3)),
+            wal_path("host", WalFileSequenceNumber::new(4)),
+        ] {
+            object_store.put(&path, "".into()).await.unwrap();
+        }
+
+        let files = subscriptions
+            .retained_wal_files(WalFileSequenceNumber::new(3), WalFileSequenceNumber::new(4))
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![
+                retained_wal_path("host", WalFileSequenceNumber::new(3)),
+                wal_path("host", WalFileSequenceNumber::new(4)),
+            ],
+            files.into_iter().map(|(_, path)| path).collect::<Vec<_>>()
+        );
+        let err = subscriptions
+            .retained_wal_files(WalFileSequenceNumber::new(1), WalFileSequenceNumber::new(4))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::WalFileNotRetained(n) if n == WalFileSequenceNumber::new(2)
+        ));
+        // nor can files that have not been uploaded yet
+        let err = subscriptions
+            .retained_wal_files(WalFileSequenceNumber::new(3), WalFileSequenceNumber::new(5))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::WalFileNotRetained(n) if n == WalFileSequenceNumber::new(5)
+        ));
+    }
+
+    #[test]
+    fn record_to_line_protocol() {
+        let record = WriteRecord {
+            wal_file_number: WalFileSequenceNumber::new(1),
+            database: "db".into(),
+            table: "my table".into(),
+            time: 10,
+            tags: BTreeMap::from([("host".into(), "a,b".to_string())]),
+            fields: BTreeMap::from([
+                ("f1".into(), FieldValue::Integer(1)),
+                ("f2".into(), FieldValue::String("say \"hi\"".to_string())),
+                ("f3".into(), FieldValue::Float(1.5)),
+                ("f4".into(), FieldValue::Boolean(true)),
+                ("f5".into(), FieldValue::UInteger(2)),
+            ]),
+        };
+        assert_eq!(
+            record.to_line_protocol(),
+            "my\\ table,host=a\\,b f1=1i,f2=\"say \\\"hi\\\"\",f3=1.5,f4=true,f5=2u 10"
+        );
+    }
+}
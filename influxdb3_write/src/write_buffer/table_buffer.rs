@@ -1,16 +1,21 @@
 //! The in memory buffer of a table that can be quickly added to and queried
 
+use crate::chunk::filter_deleted_rows;
 use arrow::array::{
-    Array, ArrayBuilder, ArrayRef, BooleanBuilder, Float64Builder, GenericByteDictionaryBuilder,
-    Int64Builder, StringArray, StringBuilder, StringDictionaryBuilder, TimestampNanosecondBuilder,
-    UInt64Builder,
+    Array, ArrayBuilder, ArrayRef, AsArray, BooleanBuilder, Float64Builder,
+    GenericByteDictionaryBuilder, Int64Builder, StringArray, StringBuilder,
+    StringDictionaryBuilder, TimestampNanosecondBuilder, UInt64Builder,
+};
+use arrow::compute::cast;
+use arrow::datatypes::{
+    DataType, Field as ArrowField, Float64Type, GenericStringType, Int32Type, Int64Type,
+    Schema as ArrowSchema, SchemaRef, TimestampNanosecondType, UInt64Type,
 };
-use arrow::datatypes::{GenericStringType, Int32Type, SchemaRef};
 use arrow::record_batch::RecordBatch;
 use data_types::TimestampMinMax;
 use datafusion::logical_expr::{BinaryExpr, Expr};
 use hashbrown::HashMap;
-use influxdb3_wal::{FieldData, Row};
+use influxdb3_wal::{DeletePredicate, Field, FieldData, Row};
 use observability_deps::tracing::{debug, error, info};
 use schema::sort::SortKey;
 use schema::{InfluxColumnType, InfluxFieldType, Schema, SchemaBuilder};
@@ -197,6 +202,52 @@ impl TableBuffer {
     pub fn clear_snapshots(&mut self) {
         self.snapshotting_chunks.clear();
     }
+
+    /// Removes the rows that match the delete predicate from the buffered and snapshotting chunks
+    pub fn delete(&mut self, predicate: &DeletePredicate) -> Result<()> {
+        for chunk in self.chunk_time_to_chunks.values_mut() {
+            if chunk.timestamp_max >= predicate.min_time_ns
+                && chunk.timestamp_min <= predicate.max_time_ns
+            {
+                chunk.delete(predicate)?;
+            }
+        }
+        self.chunk_time_to_chunks
+            .retain(|_, chunk| chunk.row_count > 0);
+        // the snapshotting chunks are already being persisted, so the rows will also be removed
+        // from the persisted files by a tombstone, this only removes them until that is done
+        for chunk in &mut self.snapshotting_chunks {
+            chunk.record_batch = filter_deleted_rows(&chunk.record_batch, predicate)?;
+        }
+
+        Ok(())
+    }
+
+    /// Renames a column in the buffered and snapshotting chunks, so that they line up with the
+    /// catalog after the column has been renamed in it
+    pub fn rename_column(&mut self, old_name: &str, new_name: Arc<str>) -> Result<()> {
+        for chunk in self.chunk_time_to_chunks.values_mut() {
+            chunk.rename_column(old_name, Arc::clone(&new_name));
+        }
+        self.index.rename_column(old_name, Arc::clone(&new_name));
+        for chunk in &mut self.snapshotting_chunks {
+            chunk.rename_column(old_name, &new_name)?;
+        }
+        let sort_key = self
+            .sort_key
+            .to_columns()
+            .map(|c| {
+                if c == old_name {
+                    new_name.to_string()
+                } else {
+                    c.to_string()
+                }
+            })
+            .collect::<Vec<_>>();
+        self.sort_key = SortKey::from(sort_key);
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -207,6 +258,30 @@ pub struct SnapshotChunk {
     pub(crate) schema: Schema,
 }
 
+impl SnapshotChunk {
+    fn rename_column(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        if self.record_batch.column_by_name(old_name).is_none() {
+            return Ok(());
+        }
+        let mut schema_builder = SchemaBuilder::with_capacity(self.schema.len());
+        for (col_type, field) in self.schema.iter() {
+            let name = if field.name() == old_name {
+                new_name
+            } else {
+                field.name().as_str()
+            };
+            schema_builder.influx_column(name, col_type);
+        }
+        self.schema = schema_builder
+            .build()
+            .expect("should always be able to build schema");
+        self.record_batch =
+            RecordBatch::try_new(self.schema.as_arrow(), self.record_batch.columns().to_vec())?;
+
+        Ok(())
+    }
+}
+
 // Debug implementation for TableBuffer
 impl std::fmt::Debug for TableBuffer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -410,6 +485,81 @@ impl MutableTableChunk {
         TimestampMinMax::new(self.timestamp_min, self.timestamp_max)
     }
 
+    /// Removes the rows that match the delete predicate, by rebuilding the chunk from the rows
+    /// that are kept
+    fn delete(&mut self, predicate: &DeletePredicate) -> Result<()> {
+        let (fields, columns): (Vec<_>, Vec<_>) = self
+            .data
+            .iter()
+            .map(|(name, builder)| {
+                let column = builder.as_arrow();
+                (
+                    ArrowField::new(name.as_ref(), column.data_type().clone(), true),
+                    column,
+                )
+            })
+            .unzip();
+        let batch = RecordBatch::try_new(Arc::new(ArrowSchema::new(fields)), columns)?;
+        let kept = filter_deleted_rows(&batch, predicate)?;
+        if kept.num_rows() == batch.num_rows() {
+            return Ok(());
+        }
+
+        let mut rows: Vec<Row> = (0..kept.num_rows())
+            .map(|_| Row {
+                time: 0,
+                fields: Vec::with_capacity(self.data.len()),
+            })
+            .collect();
+        for ((name, builder), column) in self.data.iter().zip(kept.columns()) {
+            for (row, value) in rows.iter_mut().zip(builder.field_data(column)?) {
+                let Some(value) = value else {
+                    continue;
+                };
+                if let FieldData::Timestamp(time) = value {
+                    row.time = time;
+                }
+                row.fields.push(Field {
+                    name: Arc::clone(name),
+                    value,
+                });
+            }
+        }
+
+        // the empty builders are kept so that the chunk has the same columns as before
+        self.data = self
+            .data
+            .iter()
+            .map(|(name, builder)| (Arc::clone(name), builder.new_empty()))
+            .collect();
+        self.index = self.index.new_empty();
+        self.timestamp_min = i64::MAX;
+        self.timestamp_max = i64::MIN;
+        self.row_count = 0;
+        self.add_rows(rows);
+
+        Ok(())
+    }
+
+    fn rename_column(&mut self, old_name: &str, new_name: Arc<str>) {
+        let Some(builder) = self.data.remove(old_name) else {
+            return;
+        };
+        // rows with the new name can only be here if they were validated before the rename was
+        // applied, and there is no way to merge the two builders
+        if self.data.contains_key(&new_name) {
+            error!(
+                %old_name,
+                %new_name,
+                "buffered chunk already has a column with the new name of a renamed column"
+            );
+            self.data.insert(old_name.into(), builder);
+            return;
+        }
+        self.data.insert(Arc::clone(&new_name), builder);
+        self.index.rename_column(old_name, new_name);
+    }
+
     fn record_batch(&self, schema: SchemaRef, filter: &[Expr]) -> Result<RecordBatch> {
         let row_ids = self.index.get_rows_from_index_for_filter(filter);
 
@@ -488,6 +638,23 @@ impl BufferIndex {
         Self { columns }
     }
 
+    /// An index on the same columns with no rows in it
+    fn new_empty(&self) -> Self {
+        Self {
+            columns: self
+                .columns
+                .keys()
+                .map(|c| (Arc::clone(c), HashMap::new()))
+                .collect(),
+        }
+    }
+
+    fn rename_column(&mut self, old_name: &str, new_name: Arc<str>) {
+        if let Some(rows) = self.columns.remove(old_name) {
+            self.columns.insert(new_name, rows);
+        }
+    }
+
     fn add_row_if_indexed_column(&mut self, row_index: usize, column_name: &str, value: &str) {
         if let Some(column) = self.columns.get_mut(column_name) {
             column
@@ -546,6 +713,68 @@ pub enum Builder {
 }
 
 impl Builder {
+    /// A builder of the same type with no values in it
+    fn new_empty(&self) -> Self {
+        match self {
+            Self::Bool(_) => Self::Bool(BooleanBuilder::new()),
+            Self::I64(_) => Self::I64(Int64Builder::new()),
+            Self::F64(_) => Self::F64(Float64Builder::new()),
+            Self::U64(_) => Self::U64(UInt64Builder::new()),
+            Self::String(_) => Self::String(StringBuilder::new()),
+            Self::Tag(_) => Self::Tag(StringDictionaryBuilder::new()),
+            Self::Key(_) => Self::Key(StringDictionaryBuilder::new()),
+            Self::Time(_) => Self::Time(TimestampNanosecondBuilder::new()),
+        }
+    }
+
+    /// Converts an array that was built by a builder of this type back into the field data of
+    /// each of its rows, with `None` for nulls
+    fn field_data(&self, array: &ArrayRef) -> Result<Vec<Option<FieldData>>> {
+        let values = match self {
+            Self::Bool(_) => array
+                .as_boolean()
+                .iter()
+                .map(|v| v.map(FieldData::Boolean))
+                .collect(),
+            Self::I64(_) => array
+                .as_primitive::<Int64Type>()
+                .iter()
+                .map(|v| v.map(FieldData::Integer))
+                .collect(),
+            Self::F64(_) => array
+                .as_primitive::<Float64Type>()
+                .iter()
+                .map(|v| v.map(FieldData::Float))
+                .collect(),
+            Self::U64(_) => array
+                .as_primitive::<UInt64Type>()
+                .iter()
+                .map(|v| v.map(FieldData::UInteger))
+                .collect(),
+            Self::String(_) => array
+                .as_string::<i32>()
+                .iter()
+                .map(|v| v.map(|v| FieldData::String(v.to_string())))
+                .collect(),
+            Self::Tag(_) => cast(array, &DataType::Utf8)?
+                .as_string::<i32>()
+                .iter()
+                .map(|v| v.map(|v| FieldData::Tag(v.to_string())))
+                .collect(),
+            Self::Key(_) => cast(array, &DataType::Utf8)?
+                .as_string::<i32>()
+                .iter()
+                .map(|v| v.map(|v| FieldData::Key(v.to_string())))
+                .collect(),
+            Self::Time(_) => array
+                .as_primitive::<TimestampNanosecondType>()
+                .iter()
+                .map(|v| v.map(FieldData::Timestamp))
+                .collect(),
+        };
+        Ok(values)
+    }
+
     fn as_arrow(&self) -> ArrayRef {
         match self {
             Self::Bool(b) => Arc::new(b.finish_cloned()),
@@ -974,6 +1203,166 @@ mod tests {
         assert_eq!(size, 18094);
     }
 
+    #[test]
+    fn delete_rows() {
+        let mut table_buffer = TableBuffer::new(&["tag"], SortKey::empty());
+        let schema = SchemaBuilder::with_capacity(3)
+            .tag("tag")
+            .influx_field("value", InfluxFieldType::Integer)
+            .timestamp()
+            .build()
+            .unwrap();
+
+        let rows = [(1, "a"), (2, "b"), (3, "a"), (11, "a")]
+            .into_iter()
+            .map(|(time, tag)| Row {
+                time,
+                fields: vec![
+                    Field {
+                        name: "tag".into(),
+                        value: FieldData::Tag(tag.to_string()),
+                    },
+                    Field {
+                        name: "value".into(),
+                        value: FieldData::Integer(time),
+                    },
+                    Field {
+                        name: "time".into(),
+                        value: FieldData::Timestamp(time),
+                    },
+                ],
+            })
+            .collect::<Vec<_>>();
+        let (first, second) = rows.split_at(3);
+        table_buffer.buffer_chunk(0, first.to_vec());
+        table_buffer.buffer_chunk(10, second.to_vec());
+
+        // delete the rows for tag a up to time 2, which only matches the first row
+        table_buffer
+            .delete(&DeletePredicate {
+                min_time_ns: 0,
+                max_time_ns: 2,
+                tags: vec![("tag".into(), "a".into())],
+            })
+            .unwrap();
+        let batches = table_buffer.record_batches(schema.as_arrow(), &[]).unwrap();
+        assert_batches_sorted_eq!(
+            [
+                "+-----+-------+--------------------------------+",
+                "| tag | value | time                           |",
+                "+-----+-------+--------------------------------+",
+                "| a   | 11    | 1970-01-01T00:00:00.000000011Z |",
+                "| a   | 3     | 1970-01-01T00:00:00.000000003Z |",
+                "| b   | 2     | 1970-01-01T00:00:00.000000002Z |",
+                "+-----+-------+--------------------------------+",
+            ],
+            &batches
+        );
+
+        // the index is rebuilt for the rows that are kept
+        let filter = &[Expr::BinaryExpr(BinaryExpr {
+            left: Box::new(Expr::Column(Column {
+                relation: None,
+                name: "tag".to_string(),
+            })),
+            op: datafusion::logical_expr::Operator::Eq,
+            right: Box::new(Expr::Literal(datafusion::scalar::ScalarValue::Utf8(Some(
+                "a".to_string(),
+            )))),
+        })];
+        let a_rows = table_buffer
+            .chunk_time_to_chunks
+            .get(&0)
+            .unwrap()
+            .index
+            .get_rows_from_index_for_filter(filter)
+            .unwrap();
+        assert_eq!(a_rows, &[1]);
+
+        // deleting all of the rows in a chunk removes it
+        table_buffer
+            .delete(&DeletePredicate {
+                min_time_ns: 10,
+                max_time_ns: i64::MAX,
+                tags: vec![],
+            })
+            .unwrap();
+        assert!(!table_buffer.chunk_time_to_chunks.contains_key(&10));
+    }
+
+    #[test]
+    fn rename_columns() {
+        let mut table_buffer = TableBuffer::new(&["tag"], SortKey::empty());
+        let rows = [(1, "a"), (2, "b"), (11, "a")]
+            .into_iter()
+            .map(|(time, tag)| Row {
+                time,
+                fields: vec![
+                    Field {
+                        name: "tag".into(),
+                        value: FieldData::Tag(tag.to_string()),
+                    },
+                    Field {
+                        name: "value".into(),
+                        value: FieldData::Integer(time),
+                    },
+                    Field {
+                        name: "time".into(),
+                        value: FieldData::Timestamp(time),
+                    },
+                ],
+            })
+            .collect::<Vec<_>>();
+        let (first, second) = rows.split_at(2);
+        table_buffer.buffer_chunk(0, first.to_vec());
+        table_buffer.buffer_chunk(10, second.to_vec());
+        // the first chunk is being snapshotted, and the second is still buffered
+        table_buffer.snapshot(5);
+
+        table_buffer.rename_column("tag", "region".into()).unwrap();
+        table_buffer.rename_column("value", "count".into()).unwrap();
+
+        let schema = SchemaBuilder::with_capacity(3)
+            .tag("region")
+            .influx_field("count", InfluxFieldType::Integer)
+            .timestamp()
+            .build()
+            .unwrap();
+        let batches = table_buffer.record_batches(schema.as_arrow(), &[]).unwrap();
+        assert_batches_sorted_eq!(
+            [
+                "+--------+-------+--------------------------------+",
+                "| region | count | time                           |",
+                "+--------+-------+--------------------------------+",
+                "| a      | 1     | 1970-01-01T00:00:00.000000001Z |",
+                "| a      | 11    | 1970-01-01T00:00:00.000000011Z |",
+                "| b      | 2     | 1970-01-01T00:00:00.000000002Z |",
+                "+--------+-------+--------------------------------+",
+            ],
+            &batches
+        );
+
+        // the index follows the renamed tag
+        let filter = &[Expr::BinaryExpr(BinaryExpr {
+            left: Box::new(Expr::Column(Column {
+                relation: None,
+                name: "region".to_string(),
+            })),
+            op: datafusion::logical_expr::Operator::Eq,
+            right: Box::new(Expr::Literal(datafusion::scalar::ScalarValue::Utf8(Some(
+                "a".to_string(),
+            )))),
+        })];
+        let a_rows = table_buffer
+            .chunk_time_to_chunks
+            .get(&10)
+            .unwrap()
+            .index
+            .get_rows_from_index_for_filter(filter)
+            .unwrap();
+        assert_eq!(a_rows, &[0]);
+    }
+
     #[test]
     fn timestamp_min_max_works_when_empty() {
         let table_buffer = TableBuffer::new(&["tag"], SortKey::empty());
@@ -1,27 +1,37 @@
+use std::collections::BTreeMap;
 use std::{borrow::Cow, sync::Arc};
 
-use crate::{write_buffer::Result, Precision, WriteLineError};
+use crate::write_buffer::quotas::{record_batch_series_hashes, QuotaTracker};
+use crate::{write_buffer::Result, Precision, SeriesSketch, WriteLineError};
+use arrow::array::{Array, ArrayRef, AsArray, StringArray, TimestampNanosecondArray, UInt32Array};
+use arrow::compute::{cast, concat_batches, take_record_batch};
+use arrow::datatypes::{
+    DataType, Field as ArrowField, Float64Type, Int64Type, TimeUnit, TimestampMicrosecondType,
+    TimestampMillisecondType, TimestampNanosecondType, TimestampSecondType, UInt64Type,
+};
+use arrow::record_batch::RecordBatch;
 use data_types::{NamespaceName, Timestamp};
 use hashbrown::HashMap;
 use influxdb3_catalog::catalog::{
     influx_column_type_from_field_value, Catalog, DatabaseSchema, TableDefinition,
 };
 
-use influxdb3_id::TableId;
+use influxdb3_id::{DbId, TableId};
 use influxdb3_wal::{
-    CatalogBatch, CatalogOp, Field, FieldAdditions, FieldData, FieldDataType, FieldDefinition,
-    Gen1Duration, Row, TableChunks, WriteBatch,
+    CatalogBatch, CatalogOp, CoercionPolicy, Field, FieldAdditions, FieldData, FieldDataType,
+    FieldDefinition, Gen1Duration, Row, SchemaPolicy, TableChunks, WriteBatch,
 };
 use influxdb_line_protocol::{parse_lines, v3, FieldValue, ParsedLine};
 use iox_time::Time;
-use schema::{InfluxColumnType, TIME_COLUMN_NAME};
+use schema::{
+    InfluxColumnType, InfluxFieldType, Schema, SchemaBuilder, COLUMN_METADATA_KEY, TIME_COLUMN_NAME,
+};
 
 use super::Error;
 
 /// Type state for the [`WriteValidator`] after it has been initialized
 /// with the catalog.
 pub(crate) struct WithCatalog {
-    catalog: Arc<Catalog>,
     db_schema: Arc<DatabaseSchema>,
     time_now_ns: i64,
 }
@@ -36,8 +46,8 @@ pub(crate) struct LinesParsed<'raw, PL> {
     errors: Vec<WriteLineError>,
 }
 
-/// A state machine for validating v1 or v3 line protocol and updating
-/// the [`Catalog`] with new tables or schema changes.
+/// A state machine for validating v1 or v3 line protocol and collecting
+/// the new tables or schema changes to apply to the [`Catalog`].
 pub(crate) struct WriteValidator<State> {
     state: State,
 }
@@ -53,7 +63,6 @@ impl WriteValidator<WithCatalog> {
         let db_schema = catalog.db_or_create(db_name.as_str())?;
         Ok(WriteValidator {
             state: WithCatalog {
-                catalog,
                 db_schema,
                 time_now_ns,
             },
@@ -68,8 +77,9 @@ impl WriteValidator<WithCatalog> {
     ///
     /// # Implementation Note
     ///
-    /// If this function succeeds, then the catalog will receive an update, so
-    /// steps following this should be infallible.
+    /// The changes to the schema are not applied to the catalog here. They are returned in the
+    /// `catalog_updates` of the converted write, to be applied once the write has passed the
+    /// checks that follow validation, such as its series quota.
     pub(crate) fn v3_parse_lines_and_update_schema(
         self,
         lp: &str,
@@ -117,7 +127,6 @@ impl WriteValidator<WithCatalog> {
                 time_ns: self.state.time_now_ns,
                 ops: catalog_updates,
             };
-            self.state.catalog.apply_catalog_batch(&catalog_batch)?;
             Some(catalog_batch)
         };
 
@@ -148,8 +157,9 @@ impl WriteValidator<WithCatalog> {
     ///
     /// # Implementation Note
     ///
-    /// If this function succeeds, then the catalog will receive an update, so
-    /// steps following this should be infallible.
+    /// The changes to the schema are not applied to the catalog here. They are returned in the
+    /// `catalog_updates` of the converted write, to be applied once the write has passed the
+    /// checks that follow validation, such as its series quota.
     pub(crate) fn v1_parse_lines_and_update_schema(
         self,
         lp: &str,
@@ -190,9 +200,8 @@ impl WriteValidator<WithCatalog> {
             lines.push((line, lp_lines.next().unwrap()));
         }
 
-        // All lines are parsed and validated, so all steps after this
-        // are infallible, therefore, update the catalog if changes were
-        // made to the schema:
+        // All lines are parsed and validated, so collect the changes made
+        // to the schema into a batch for the catalog:
         let catalog_batch = if catalog_updates.is_empty() {
             None
         } else {
@@ -202,7 +211,6 @@ impl WriteValidator<WithCatalog> {
                 database_name: Arc::clone(&self.state.db_schema.name),
                 ops: catalog_updates,
             };
-            self.state.catalog.apply_catalog_batch(&catalog_batch)?;
             Some(catalog_batch)
         };
 
@@ -238,7 +246,7 @@ impl WriteValidator<WithCatalog> {
 fn validate_v3_line<'a>(
     db_schema: &mut Cow<'_, DatabaseSchema>,
     line_number: usize,
-    line: v3::ParsedLine<'a>,
+    mut line: v3::ParsedLine<'a>,
     raw_line: &str,
 ) -> Result<(v3::ParsedLine<'a>, Option<CatalogOp>), WriteLineError> {
     let mut catalog_op = None;
@@ -297,7 +305,9 @@ fn validate_v3_line<'a>(
         for (field_name, field_val) in line.field_set.iter() {
             if let Some(schema_col_type) = table_def.field_type_by_name(field_name) {
                 let field_col_type = influx_column_type_from_field_value(field_val);
-                if field_col_type != schema_col_type {
+                if field_col_type != schema_col_type
+                    && !can_coerce(table_def.coercion_policy, field_val, schema_col_type)
+                {
                     let field_name = field_name.to_string();
                     return Err(WriteLineError {
                         original_line: raw_line.to_string(),
@@ -318,6 +328,28 @@ fn validate_v3_line<'a>(
             }
         }
 
+        let dropped = apply_schema_policy(
+            db_schema.table_schema_policy(table_def),
+            &table_def.table_name,
+            &mut columns,
+        )
+        .map_err(|error_message| WriteLineError {
+            original_line: raw_line.to_string(),
+            line_number: line_number + 1,
+            error_message,
+        })?;
+        if !dropped.is_empty() {
+            line.field_set
+                .retain(|(name, _)| !dropped.iter().any(|d| d == name.as_str()));
+            if line.field_set.is_empty() {
+                return Err(WriteLineError {
+                    original_line: raw_line.to_string(),
+                    line_number: line_number + 1,
+                    error_message: all_fields_dropped_message(&table_def.table_name),
+                });
+            }
+        }
+
         // if we have new columns defined, add them to the db_schema table so that subsequent lines
         // won't try to add the same definitions. Collect these additions into a catalog op, which
         // will be applied to the catalog with any other ops after all lines in the write request
@@ -349,6 +381,13 @@ fn validate_v3_line<'a>(
             })?;
         }
     } else {
+        if !db_schema.schema_policy.is_open() {
+            return Err(WriteLineError {
+                original_line: raw_line.to_string(),
+                line_number: line_number + 1,
+                error_message: new_table_rejected_message(db_schema, table_name),
+            });
+        }
         let table_id = TableId::new();
         let mut columns = Vec::new();
         let mut key = Vec::new();
@@ -409,6 +448,92 @@ fn validate_v3_line<'a>(
     Ok((line, catalog_op))
 }
 
+/// Whether the [`CoercionPolicy`] of a table converts the field value to the type of the
+/// column it is written to
+fn can_coerce(
+    policy: CoercionPolicy,
+    value: &FieldValue<'_>,
+    column_type: InfluxColumnType,
+) -> bool {
+    let fits = match (value, column_type) {
+        (FieldValue::U64(v), InfluxColumnType::Field(InfluxFieldType::Integer)) => {
+            i64::try_from(*v).is_ok()
+        }
+        _ => true,
+    };
+    fits && policy.coerces(influx_column_type_from_field_value(value), column_type)
+}
+
+/// Convert the value to the type of its column, if that differs, counting the values that are
+/// converted. The validator has already checked that the values can be converted.
+fn coerce_field_data(
+    value: FieldData,
+    column_type: Option<InfluxColumnType>,
+    coerced_count: &mut usize,
+) -> FieldData {
+    let Some(InfluxColumnType::Field(field_type)) = column_type else {
+        return value;
+    };
+    let coerced = match (value, field_type) {
+        (FieldData::Integer(v), InfluxFieldType::Float) => FieldData::Float(v as f64),
+        (FieldData::UInteger(v), InfluxFieldType::Float) => FieldData::Float(v as f64),
+        (FieldData::UInteger(v), InfluxFieldType::Integer) => {
+            FieldData::Integer(i64::try_from(v).expect("value was validated to fit an integer"))
+        }
+        (FieldData::Integer(v), InfluxFieldType::String) => FieldData::String(v.to_string()),
+        (FieldData::UInteger(v), InfluxFieldType::String) => FieldData::String(v.to_string()),
+        (FieldData::Float(v), InfluxFieldType::String) => FieldData::String(v.to_string()),
+        (FieldData::Boolean(v), InfluxFieldType::String) => FieldData::String(v.to_string()),
+        (value, _) => return value,
+    };
+    *coerced_count += 1;
+    coerced
+}
+
+/// Enforce the [`SchemaPolicy`] of a table on the new columns that a line would add to it
+///
+/// Returns an error message if the policy rejects the line, otherwise the names of the columns
+/// that must be dropped from the line, in which case none are left to be added to the table.
+fn apply_schema_policy(
+    policy: SchemaPolicy,
+    table_name: &str,
+    new_columns: &mut Vec<(String, InfluxColumnType)>,
+) -> Result<Vec<String>, String> {
+    if new_columns.is_empty() {
+        return Ok(vec![]);
+    }
+    match policy {
+        SchemaPolicy::Open => Ok(vec![]),
+        SchemaPolicy::Locked => Err(format!(
+            "write to table {table_name} would add the columns [{columns}], but the schema of the \
+            table is locked",
+            columns = new_columns
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        )),
+        SchemaPolicy::DropUnknown => Ok(new_columns.drain(..).map(|(name, _)| name).collect()),
+    }
+}
+
+fn all_fields_dropped_message(table_name: &str) -> String {
+    format!(
+        "write to table {table_name} had no fields that are in the table schema, and the unknown \
+        fields were dropped by its {policy} schema policy",
+        policy = SchemaPolicy::DropUnknown,
+    )
+}
+
+fn new_table_rejected_message(db_schema: &DatabaseSchema, table_name: &str) -> String {
+    format!(
+        "table {table_name} does not exist, and cannot be created by a write to database \
+        {db_name}, which has a {policy} schema policy",
+        db_name = db_schema.name,
+        policy = db_schema.schema_policy,
+    )
+}
+
 /// Validate a line of line protocol against the given schema definition
 ///
 /// This is for scenarios where a write comes in for a table that exists, but may have
@@ -419,7 +544,7 @@ fn validate_v3_line<'a>(
 fn validate_v1_line<'a>(
     db_schema: &mut Cow<'_, DatabaseSchema>,
     line_number: usize,
-    line: ParsedLine<'a>,
+    mut line: ParsedLine<'a>,
 ) -> Result<(ParsedLine<'a>, Option<CatalogOp>), WriteLineError> {
     let mut catalog_op = None;
     let table_name = line.series.measurement.as_str();
@@ -445,7 +570,9 @@ fn validate_v1_line<'a>(
             // This field already exists, so check the incoming type matches existing type:
             if let Some(schema_col_type) = table_def.field_type_by_name(field_name) {
                 let field_col_type = influx_column_type_from_field_value(field_val);
-                if field_col_type != schema_col_type {
+                if field_col_type != schema_col_type
+                    && !can_coerce(table_def.coercion_policy, field_val, schema_col_type)
+                {
                     let field_name = field_name.to_string();
                     return Err(WriteLineError {
                         original_line: line.to_string(),
@@ -466,6 +593,33 @@ fn validate_v1_line<'a>(
             }
         }
 
+        let dropped = apply_schema_policy(
+            db_schema.table_schema_policy(table_def),
+            &table_def.table_name,
+            &mut columns,
+        )
+        .map_err(|error_message| WriteLineError {
+            original_line: line.to_string(),
+            line_number: line_number + 1,
+            error_message,
+        })?;
+        if !dropped.is_empty() {
+            let is_dropped = |name: &str| dropped.iter().any(|d| d == name);
+            let original_line = line.to_string();
+            if let Some(tag_set) = &mut line.series.tag_set {
+                tag_set.retain(|(name, _)| !is_dropped(name.as_str()));
+            }
+            line.field_set
+                .retain(|(name, _)| !is_dropped(name.as_str()));
+            if line.field_set.is_empty() {
+                return Err(WriteLineError {
+                    original_line,
+                    line_number: line_number + 1,
+                    error_message: all_fields_dropped_message(&table_def.table_name),
+                });
+            }
+        }
+
         // if we have new columns defined, add them to the db_schema table so that subsequent lines
         // won't try to add the same definitions. Collect these additions into a catalog op, which
         // will be applied to the catalog with any other ops after all lines in the write request
@@ -501,6 +655,13 @@ fn validate_v1_line<'a>(
             }));
         }
     } else {
+        if !db_schema.schema_policy.is_open() {
+            return Err(WriteLineError {
+                original_line: line.to_string(),
+                line_number: line_number + 1,
+                error_message: new_table_rejected_message(db_schema, table_name),
+            });
+        }
         let table_id = TableId::new();
         // This is a new table, so build up its columns:
         let mut columns = Vec::new();
@@ -564,6 +725,8 @@ pub(crate) struct ValidatedLines {
     pub(crate) field_count: usize,
     /// Number of index columns passed in, whether tags (v1) or series keys (v3)
     pub(crate) index_count: usize,
+    /// Number of field values that were converted to the type of their column
+    pub(crate) coerced_count: usize,
     /// Any errors that occurred while parsing the lines
     pub(crate) errors: Vec<WriteLineError>,
     /// Only valid lines will be converted into a WriteBatch
@@ -572,6 +735,80 @@ pub(crate) struct ValidatedLines {
     pub(crate) catalog_updates: Option<CatalogBatch>,
 }
 
+/// The rows of validated record batches split by gen1 chunk of time into record batches in the
+/// schema of the table, for a write that is persisted directly rather than buffered
+#[derive(Debug)]
+pub(crate) struct ValidatedBatches {
+    /// Number of rows passed in
+    pub(crate) row_count: usize,
+    /// Number of field values that were converted to the type of their column
+    pub(crate) coerced_count: usize,
+    pub(crate) database_id: DbId,
+    pub(crate) table_id: TableId,
+    /// The schema of the record batches of the chunks
+    pub(crate) schema: Schema,
+    /// The rows of each gen1 chunk of time, keyed by the time of the chunk
+    pub(crate) chunks: BTreeMap<i64, RecordBatch>,
+    /// If any catalog updates were made, they will be included here
+    pub(crate) catalog_updates: Option<CatalogBatch>,
+}
+
+/// A validated write, whose changes to the catalog are applied once its series have been checked
+/// against the series quota of its database
+pub(crate) trait ValidatedWrite {
+    fn database_id(&self) -> DbId;
+
+    fn catalog_updates(&self) -> Option<&CatalogBatch>;
+
+    /// Checks the series of the write against the series quota of its database, as
+    /// [`QuotaTracker::check_series`] does
+    fn check_series(
+        &self,
+        quota_tracker: &QuotaTracker,
+        db_schema: &DatabaseSchema,
+    ) -> Result<Option<SeriesSketch>>;
+}
+
+impl ValidatedWrite for ValidatedLines {
+    fn database_id(&self) -> DbId {
+        self.valid_data.database_id
+    }
+
+    fn catalog_updates(&self) -> Option<&CatalogBatch> {
+        self.catalog_updates.as_ref()
+    }
+
+    fn check_series(
+        &self,
+        quota_tracker: &QuotaTracker,
+        db_schema: &DatabaseSchema,
+    ) -> Result<Option<SeriesSketch>> {
+        quota_tracker.check_series(db_schema, &self.valid_data)
+    }
+}
+
+impl ValidatedWrite for ValidatedBatches {
+    fn database_id(&self) -> DbId {
+        self.database_id
+    }
+
+    fn catalog_updates(&self) -> Option<&CatalogBatch> {
+        self.catalog_updates.as_ref()
+    }
+
+    fn check_series(
+        &self,
+        quota_tracker: &QuotaTracker,
+        db_schema: &DatabaseSchema,
+    ) -> Result<Option<SeriesSketch>> {
+        let hashes = self
+            .chunks
+            .values()
+            .flat_map(|batch| record_batch_series_hashes(self.table_id, &self.schema, batch));
+        quota_tracker.check_series_hashes(db_schema, hashes)
+    }
+}
+
 impl<'lp> WriteValidator<LinesParsed<'lp, v3::ParsedLine<'lp>>> {
     /// Convert a set of valid parsed `v3` lines to a [`ValidatedLines`] which will
     /// be buffered and written to the WAL, if configured.
@@ -589,6 +826,7 @@ impl<'lp> WriteValidator<LinesParsed<'lp, v3::ParsedLine<'lp>>> {
         let line_count = self.state.lines.len();
         let mut field_count = 0;
         let mut series_key_count = 0;
+        let mut coerced_count = 0;
 
         for (line, _raw_line) in self.state.lines.into_iter() {
             field_count += line.field_set.len();
@@ -599,7 +837,7 @@ impl<'lp> WriteValidator<LinesParsed<'lp, v3::ParsedLine<'lp>>> {
                 .map(|sk| sk.len())
                 .unwrap_or(0);
 
-            convert_v3_parsed_line(
+            coerced_count += convert_v3_parsed_line(
                 Arc::clone(&self.state.db_schema),
                 line,
                 &mut table_chunks,
@@ -619,6 +857,7 @@ impl<'lp> WriteValidator<LinesParsed<'lp, v3::ParsedLine<'lp>>> {
             line_count,
             field_count,
             index_count: series_key_count,
+            coerced_count,
             errors: self.state.errors,
             valid_data: write_batch,
             catalog_updates: self.state.catalog_batch,
@@ -626,6 +865,8 @@ impl<'lp> WriteValidator<LinesParsed<'lp, v3::ParsedLine<'lp>>> {
     }
 }
 
+/// Convert the line into a row of its table, returning the number of field values that were
+/// converted to the type of their column
 fn convert_v3_parsed_line(
     db_schema: Arc<DatabaseSchema>,
     line: v3::ParsedLine<'_>,
@@ -633,7 +874,12 @@ fn convert_v3_parsed_line(
     ingest_time: Time,
     gen1_duration: Gen1Duration,
     precision: Precision,
-) {
+) -> usize {
+    let table_def = db_schema
+        .table_definition(line.series.measurement.as_str())
+        .expect("table should exist by this point");
+    let mut coerced_count = 0;
+
     // Set up row values:
     let mut fields = Vec::with_capacity(line.column_count() + 1);
 
@@ -649,9 +895,14 @@ fn convert_v3_parsed_line(
 
     // Add fields columns:
     for (name, val) in line.field_set {
+        let value = coerce_field_data(
+            val.into(),
+            table_def.field_type_by_name(name.as_str()),
+            &mut coerced_count,
+        );
         fields.push(Field {
             name: name.to_string().into(),
-            value: val.into(),
+            value,
         });
     }
 
@@ -668,11 +919,7 @@ fn convert_v3_parsed_line(
 
     // Add the row into the correct chunk in the table
     let chunk_time = gen1_duration.chunk_time_for_timestamp(Timestamp::new(time_value_nanos));
-    let table_name = line.series.measurement.as_str();
-    let table_id = db_schema
-        .table_name_to_id(table_name)
-        .expect("table should exist by this point");
-    let table_chunks = table_chunk_map.entry(table_id).or_default();
+    let table_chunks = table_chunk_map.entry(table_def.table_id).or_default();
     table_chunks.push_row(
         chunk_time,
         Row {
@@ -680,6 +927,8 @@ fn convert_v3_parsed_line(
             fields,
         },
     );
+
+    coerced_count
 }
 
 impl<'lp> WriteValidator<LinesParsed<'lp, ParsedLine<'lp>>> {
@@ -699,12 +948,13 @@ impl<'lp> WriteValidator<LinesParsed<'lp, ParsedLine<'lp>>> {
         let line_count = self.state.lines.len();
         let mut field_count = 0;
         let mut tag_count = 0;
+        let mut coerced_count = 0;
 
         for (line, _raw_line) in self.state.lines.into_iter() {
             field_count += line.field_set.len();
             tag_count += line.series.tag_set.as_ref().map(|t| t.len()).unwrap_or(0);
 
-            convert_v1_parsed_line(
+            coerced_count += convert_v1_parsed_line(
                 Arc::clone(&self.state.db_schema),
                 line,
                 &mut table_chunks,
@@ -724,6 +974,7 @@ impl<'lp> WriteValidator<LinesParsed<'lp, ParsedLine<'lp>>> {
             line_count,
             field_count,
             index_count: tag_count,
+            coerced_count,
             errors: self.state.errors,
             valid_data: write_batch,
             catalog_updates: self.state.catalog_batch,
@@ -731,6 +982,8 @@ impl<'lp> WriteValidator<LinesParsed<'lp, ParsedLine<'lp>>> {
     }
 }
 
+/// Convert the line into a row of its table, returning the number of field values that were
+/// converted to the type of their column
 fn convert_v1_parsed_line(
     db_schema: Arc<DatabaseSchema>,
     line: ParsedLine<'_>,
@@ -738,7 +991,12 @@ fn convert_v1_parsed_line(
     ingest_time: Time,
     gen1_duration: Gen1Duration,
     precision: Precision,
-) {
+) -> usize {
+    let table_def = db_schema
+        .table_definition(line.series.measurement.as_str())
+        .expect("table should exist by this point");
+    let mut coerced_count = 0;
+
     // now that we've ensured all columns exist in the schema, construct the actual row and values
     // while validating the column types match.
     let mut values = Vec::with_capacity(line.column_count() + 1);
@@ -765,7 +1023,11 @@ fn convert_v1_parsed_line(
         };
         let value = Field {
             name: field_name.to_string().into(),
-            value: field_data,
+            value: coerce_field_data(
+                field_data,
+                table_def.field_type_by_name(field_name.as_str()),
+                &mut coerced_count,
+            ),
         };
         values.push(value);
     }
@@ -783,11 +1045,7 @@ fn convert_v1_parsed_line(
         value: FieldData::Timestamp(time_value_nanos),
     });
 
-    let table_name: Arc<str> = line.series.measurement.to_string().into();
-    let table_id = db_schema
-        .table_name_to_id(table_name)
-        .expect("table should exist by this point");
-    let table_chunks = table_chunk_map.entry(table_id).or_default();
+    let table_chunks = table_chunk_map.entry(table_def.table_id).or_default();
     table_chunks.push_row(
         chunk_time,
         Row {
@@ -795,6 +1053,662 @@ fn convert_v1_parsed_line(
             fields: values,
         },
     );
+
+    coerced_count
+}
+
+/// Type state for the [`WriteValidator`] after it has validated Arrow record batches that are
+/// written to a table.
+pub(crate) struct BatchesValidated {
+    catalog: WithCatalog,
+    db_schema: Arc<DatabaseSchema>,
+    table_name: Arc<str>,
+    /// The role of each column of the batches
+    columns: Vec<BatchColumn>,
+    batches: Vec<RecordBatch>,
+    /// The timestamps of the rows of each batch in nanoseconds, if the batches have a time column
+    times_ns: Vec<Option<Vec<i64>>>,
+    catalog_batch: Option<CatalogBatch>,
+}
+
+/// The role of a column of a record batch in the table it is written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BatchColumn {
+    Tag,
+    Field(InfluxFieldType),
+    Time,
+    /// A new column that is dropped by the schema policy of the table
+    Dropped,
+}
+
+impl WriteValidator<WithCatalog> {
+    /// Validate Arrow record batches that are written to a table, and update the
+    /// [`DatabaseSchema`] if the table or any of its columns are new. Each column of the batches
+    /// is marked as a tag or a field by its [`COLUMN_METADATA_KEY`] metadata, the type of a field
+    /// is that of its Arrow column, and the timestamps of the rows are in the `time` column, if
+    /// the batches have one.
+    ///
+    /// Unlike line protocol, the batches are validated as a whole, so that an invalid row fails
+    /// the whole write.
+    ///
+    /// # Implementation Note
+    ///
+    /// The changes to the schema are not applied to the catalog here. They are returned in the
+    /// `catalog_updates` of the converted write, to be applied once the write has passed the
+    /// checks that follow validation, such as its series quota.
+    pub(crate) fn validate_record_batches_and_update_schema(
+        self,
+        table_name: &str,
+        batches: Vec<RecordBatch>,
+    ) -> Result<WriteValidator<BatchesValidated>> {
+        let invalid = Error::InvalidRecordBatch;
+        let arrow_schema = batches
+            .first()
+            .map(|batch| batch.schema())
+            .ok_or_else(|| invalid("no record batches were written".to_string()))?;
+        if batches.iter().any(|batch| batch.schema() != arrow_schema) {
+            return Err(invalid(
+                "the record batches do not all have the same schema".to_string(),
+            ));
+        }
+        let mut columns = arrow_schema
+            .fields()
+            .iter()
+            .map(|field| batch_column(field).map_err(invalid))
+            .collect::<Result<Vec<_>>>()?;
+        if !columns.iter().any(|c| matches!(c, BatchColumn::Field(_))) {
+            return Err(invalid(
+                "the record batches have no field columns".to_string(),
+            ));
+        }
+
+        let mut times_ns = Vec::with_capacity(batches.len());
+        for batch in &batches {
+            let times = match columns.iter().position(|c| *c == BatchColumn::Time) {
+                Some(i) => Some(timestamps_ns(batch.column(i)).ok_or_else(|| {
+                    invalid(format!(
+                        "the {TIME_COLUMN_NAME} column has null values or values that are out of \
+                        range as nanoseconds"
+                    ))
+                })?),
+                None => None,
+            };
+            times_ns.push(times);
+        }
+
+        let mut schema = Cow::Borrowed(self.state.db_schema.as_ref());
+        let catalog_op = if let Some(table_def) = schema.table_definition(table_name) {
+            if table_def.is_v3() {
+                return Err(invalid(format!(
+                    "record batches cannot be written to table {table_name}, which uses the v3 \
+                    data model"
+                )));
+            }
+            let mut new_columns = vec![];
+            for (field, column) in arrow_schema.fields().iter().zip(&columns) {
+                let column_type = match column {
+                    BatchColumn::Tag => InfluxColumnType::Tag,
+                    BatchColumn::Field(field_type) => InfluxColumnType::Field(*field_type),
+                    BatchColumn::Time | BatchColumn::Dropped => continue,
+                };
+                match table_def.field_type_by_name(field.name()) {
+                    None => new_columns.push((field.name().to_string(), column_type)),
+                    Some(existing) if existing == column_type => (),
+                    Some(existing)
+                        if table_def.coercion_policy.coerces(column_type, existing)
+                            && values_fit(&batches, field.name(), column_type, existing) => {}
+                    Some(existing) => {
+                        return Err(invalid(format!(
+                            "column {name} of table {table_name} has type {existing}, but the \
+                            record batches have type {column_type}",
+                            name = field.name(),
+                        )))
+                    }
+                }
+            }
+
+            let dropped = apply_schema_policy(
+                schema.table_schema_policy(table_def),
+                table_name,
+                &mut new_columns,
+            )
+            .map_err(invalid)?;
+            for (field, column) in arrow_schema.fields().iter().zip(columns.iter_mut()) {
+                if dropped.iter().any(|name| name == field.name()) {
+                    *column = BatchColumn::Dropped;
+                }
+            }
+
+            if new_columns.is_empty() {
+                None
+            } else {
+                let database_name = Arc::clone(&schema.name);
+                let database_id = schema.id;
+                let table_name = Arc::clone(&table_def.table_name);
+                let table_id = table_def.table_id;
+                let field_definitions = new_columns
+                    .iter()
+                    .map(|(name, influx_type)| FieldDefinition {
+                        name: name.as_str().into(),
+                        data_type: FieldDataType::from(influx_type),
+                    })
+                    .collect();
+
+                // unwrap is safe due to the surrounding if let condition:
+                let t = schema.to_mut().tables.get_mut(&table_id).unwrap();
+                t.add_columns(new_columns)
+                    .map_err(|e| invalid(e.to_string()))?;
+
+                Some(CatalogOp::AddFields(FieldAdditions {
+                    database_name,
+                    database_id,
+                    table_id,
+                    table_name,
+                    field_definitions,
+                }))
+            }
+        } else {
+            if !schema.schema_policy.is_open() {
+                return Err(invalid(new_table_rejected_message(&schema, table_name)));
+            }
+            let table_id = TableId::new();
+            // tags and fields are added in the order of the batch columns, and time last:
+            let mut new_columns = arrow_schema
+                .fields()
+                .iter()
+                .zip(&columns)
+                .filter_map(|(field, column)| match column {
+                    BatchColumn::Tag => Some((field.name().to_string(), InfluxColumnType::Tag)),
+                    BatchColumn::Field(field_type) => Some((
+                        field.name().to_string(),
+                        InfluxColumnType::Field(*field_type),
+                    )),
+                    BatchColumn::Time | BatchColumn::Dropped => None,
+                })
+                .collect::<Vec<_>>();
+            new_columns.push((TIME_COLUMN_NAME.to_string(), InfluxColumnType::Timestamp));
+
+            let table_name: Arc<str> = table_name.into();
+            let field_definitions = new_columns
+                .iter()
+                .map(|(name, influx_type)| FieldDefinition {
+                    name: name.as_str().into(),
+                    data_type: FieldDataType::from(influx_type),
+                })
+                .collect();
+            let catalog_op = CatalogOp::CreateTable(influxdb3_wal::TableDefinition {
+                table_id,
+                database_id: schema.id,
+                database_name: Arc::clone(&schema.name),
+                table_name: Arc::clone(&table_name),
+                field_definitions,
+                key: None,
+            });
+
+            let table = TableDefinition::new(
+                table_id,
+                Arc::clone(&table_name),
+                new_columns,
+                Option::<Vec<String>>::None,
+            )
+            .map_err(|e| invalid(e.to_string()))?;
+            schema.to_mut().tables.insert(table_id, table);
+            schema.to_mut().table_map.insert(table_id, table_name);
+
+            Some(catalog_op)
+        };
+
+        // fields can only be missing from here if they were all dropped by the schema policy:
+        if !columns.iter().any(|c| matches!(c, BatchColumn::Field(_))) {
+            return Err(invalid(all_fields_dropped_message(table_name)));
+        }
+        for (batch_idx, batch) in batches.iter().enumerate() {
+            for row in 0..batch.num_rows() {
+                let has_fields = columns.iter().enumerate().any(|(i, column)| {
+                    matches!(column, BatchColumn::Field(_)) && batch.column(i).is_valid(row)
+                });
+                if !has_fields {
+                    return Err(invalid(format!(
+                        "row {row} of record batch {batch_idx} has no field values"
+                    )));
+                }
+            }
+        }
+
+        let catalog_batch = match catalog_op {
+            Some(op) => {
+                let catalog_batch = CatalogBatch {
+                    database_id: self.state.db_schema.id,
+                    time_ns: self.state.time_now_ns,
+                    database_name: Arc::clone(&self.state.db_schema.name),
+                    ops: vec![op],
+                };
+                Some(catalog_batch)
+            }
+            None => None,
+        };
+
+        let db_schema = match schema {
+            Cow::Borrowed(_) => Arc::clone(&self.state.db_schema),
+            Cow::Owned(s) => Arc::new(s),
+        };
+
+        Ok(WriteValidator {
+            state: BatchesValidated {
+                catalog: self.state,
+                db_schema,
+                table_name: table_name.into(),
+                columns,
+                batches,
+                times_ns,
+                catalog_batch,
+            },
+        })
+    }
+}
+
+/// The role of a record batch column, from its name, metadata, and Arrow type
+fn batch_column(field: &ArrowField) -> Result<BatchColumn, String> {
+    let name = field.name();
+    if name == TIME_COLUMN_NAME {
+        return match field.data_type() {
+            DataType::Timestamp(_, _) => Ok(BatchColumn::Time),
+            data_type => Err(format!(
+                "the {TIME_COLUMN_NAME} column has type {data_type}, which is not a timestamp"
+            )),
+        };
+    }
+    let marked_type = field.metadata().get(COLUMN_METADATA_KEY).ok_or_else(|| {
+        format!(
+            "column {name} is not marked as a tag or field by its {COLUMN_METADATA_KEY} metadata"
+        )
+    })?;
+    let column_type = InfluxColumnType::try_from(marked_type.as_str()).map_err(|_| {
+        format!("column {name} is marked with an unknown column type {marked_type}")
+    })?;
+
+    let data_type = field.data_type();
+    match column_type {
+        InfluxColumnType::Tag if is_string_type(data_type) => Ok(BatchColumn::Tag),
+        InfluxColumnType::Field(field_type) if arrow_field_type(data_type) == Some(field_type) => {
+            Ok(BatchColumn::Field(field_type))
+        }
+        InfluxColumnType::Tag | InfluxColumnType::Field(_) => Err(format!(
+            "column {name} is marked as {column_type}, but has type {data_type}"
+        )),
+        InfluxColumnType::Timestamp => Err(format!(
+            "column {name} is marked as {column_type}, which only the {TIME_COLUMN_NAME} column \
+            can be"
+        )),
+    }
+}
+
+fn is_string_type(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Utf8 | DataType::LargeUtf8 => true,
+        DataType::Dictionary(_, value_type) => {
+            matches!(value_type.as_ref(), DataType::Utf8 | DataType::LargeUtf8)
+        }
+        _ => false,
+    }
+}
+
+/// The type of the field values in an Arrow column of the given type
+fn arrow_field_type(data_type: &DataType) -> Option<InfluxFieldType> {
+    match data_type {
+        DataType::Float64 => Some(InfluxFieldType::Float),
+        DataType::Int64 => Some(InfluxFieldType::Integer),
+        DataType::UInt64 => Some(InfluxFieldType::UInteger),
+        DataType::Utf8 | DataType::LargeUtf8 => Some(InfluxFieldType::String),
+        DataType::Boolean => Some(InfluxFieldType::Boolean),
+        _ => None,
+    }
+}
+
+/// Whether the values of the column can be coerced to the type of the column of the table, which
+/// only fails for unsigned integers that are too large to be integers
+fn values_fit(
+    batches: &[RecordBatch],
+    name: &str,
+    column_type: InfluxColumnType,
+    table_column_type: InfluxColumnType,
+) -> bool {
+    if column_type != InfluxColumnType::Field(InfluxFieldType::UInteger)
+        || table_column_type != InfluxColumnType::Field(InfluxFieldType::Integer)
+    {
+        return true;
+    }
+    batches.iter().all(|batch| {
+        batch.column_by_name(name).is_some_and(|array| {
+            array
+                .as_primitive::<UInt64Type>()
+                .iter()
+                .flatten()
+                .all(|v| i64::try_from(v).is_ok())
+        })
+    })
+}
+
+/// The timestamps of the time column in nanoseconds, or `None` if any is null or out of range
+fn timestamps_ns(array: &ArrayRef) -> Option<Vec<i64>> {
+    if array.null_count() > 0 {
+        return None;
+    }
+    let (values, nanos_per_unit) = match array.data_type() {
+        DataType::Timestamp(TimeUnit::Second, _) => (
+            array.as_primitive::<TimestampSecondType>().values(),
+            1_000_000_000,
+        ),
+        DataType::Timestamp(TimeUnit::Millisecond, _) => (
+            array.as_primitive::<TimestampMillisecondType>().values(),
+            1_000_000,
+        ),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => (
+            array.as_primitive::<TimestampMicrosecondType>().values(),
+            1_000,
+        ),
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            (array.as_primitive::<TimestampNanosecondType>().values(), 1)
+        }
+        _ => return None,
+    };
+    values
+        .iter()
+        .map(|time| time.checked_mul(nanos_per_unit))
+        .collect()
+}
+
+impl WriteValidator<BatchesValidated> {
+    /// Convert the rows of the validated record batches to a [`ValidatedLines`], with a line for
+    /// each row, which will be buffered and written to the WAL, if configured. Rows without a
+    /// time are written at the ingest time.
+    pub(crate) fn convert_batches_to_buffer(
+        self,
+        ingest_time: Time,
+        gen1_duration: Gen1Duration,
+    ) -> ValidatedLines {
+        let table_def = self
+            .state
+            .db_schema
+            .table_definition(Arc::clone(&self.state.table_name))
+            .expect("table should exist by this point");
+        let mut table_chunks = TableChunks::default();
+        let mut line_count = 0;
+        let mut field_count = 0;
+        let mut tag_count = 0;
+        let mut coerced_count = 0;
+
+        for (batch, times_ns) in self.state.batches.iter().zip(self.state.times_ns) {
+            let schema = batch.schema();
+            let columns = schema
+                .fields()
+                .iter()
+                .zip(batch.columns())
+                .zip(&self.state.columns)
+                .filter_map(|((field, array), column)| {
+                    let array = match column {
+                        BatchColumn::Tag | BatchColumn::Field(InfluxFieldType::String) => {
+                            cast(array, &DataType::Utf8).expect("string columns cast to utf8")
+                        }
+                        BatchColumn::Field(_) => Arc::clone(array),
+                        BatchColumn::Time | BatchColumn::Dropped => return None,
+                    };
+                    let name: Arc<str> = field.name().as_str().into();
+                    let column_type = table_def.field_type_by_name(&name);
+                    Some((name, *column, array, column_type))
+                })
+                .collect::<Vec<_>>();
+
+            for row in 0..batch.num_rows() {
+                let mut fields = Vec::with_capacity(columns.len() + 1);
+                for (name, column, array, column_type) in &columns {
+                    if array.is_null(row) {
+                        continue;
+                    }
+                    let value = match column {
+                        BatchColumn::Tag => {
+                            tag_count += 1;
+                            FieldData::Tag(array.as_string::<i32>().value(row).to_string())
+                        }
+                        BatchColumn::Field(field_type) => {
+                            field_count += 1;
+                            coerce_field_data(
+                                field_data(array, *field_type, row),
+                                *column_type,
+                                &mut coerced_count,
+                            )
+                        }
+                        BatchColumn::Time | BatchColumn::Dropped => continue,
+                    };
+                    fields.push(Field {
+                        name: Arc::clone(name),
+                        value,
+                    });
+                }
+
+                let time_value_nanos = times_ns
+                    .as_ref()
+                    .map(|times| times[row])
+                    .unwrap_or(ingest_time.timestamp_nanos());
+                fields.push(Field {
+                    name: TIME_COLUMN_NAME.to_string().into(),
+                    value: FieldData::Timestamp(time_value_nanos),
+                });
+
+                let chunk_time =
+                    gen1_duration.chunk_time_for_timestamp(Timestamp::new(time_value_nanos));
+                table_chunks.push_row(
+                    chunk_time,
+                    Row {
+                        time: time_value_nanos,
+                        fields,
+                    },
+                );
+                line_count += 1;
+            }
+        }
+
+        let write_batch = WriteBatch::new(
+            self.state.catalog.db_schema.id,
+            Arc::clone(&self.state.catalog.db_schema.name),
+            [(table_def.table_id, table_chunks)].into_iter().collect(),
+        );
+
+        ValidatedLines {
+            line_count,
+            field_count,
+            index_count: tag_count,
+            coerced_count,
+            errors: vec![],
+            valid_data: write_batch,
+            catalog_updates: self.state.catalog_batch,
+        }
+    }
+
+    /// Convert the validated record batches to a [`ValidatedBatches`], with their rows split by
+    /// gen1 chunk of time into record batches in the schema of the table, to be persisted
+    /// directly rather than buffered. Rows without a time are written at the ingest time.
+    pub(crate) fn convert_batches_to_chunks(
+        self,
+        ingest_time: Time,
+        gen1_duration: Gen1Duration,
+    ) -> ValidatedBatches {
+        let table_def = self
+            .state
+            .db_schema
+            .table_definition(Arc::clone(&self.state.table_name))
+            .expect("table should exist by this point");
+        let arrow_schema = self.state.batches[0].schema();
+
+        // the columns of the batches, with the types they have in the table, are ordered by name
+        // as they are in the chunks of the buffer. The time column has no index, as rows without
+        // a time are written at the ingest time.
+        let mut columns = arrow_schema
+            .fields()
+            .iter()
+            .zip(&self.state.columns)
+            .enumerate()
+            .filter_map(|(index, (field, column))| {
+                let column_type = match column {
+                    BatchColumn::Tag => InfluxColumnType::Tag,
+                    BatchColumn::Field(field_type) => table_def
+                        .field_type_by_name(field.name())
+                        .unwrap_or(InfluxColumnType::Field(*field_type)),
+                    BatchColumn::Time | BatchColumn::Dropped => return None,
+                };
+                Some((Some(index), field.name().as_str(), *column, column_type))
+            })
+            .chain([(
+                None,
+                TIME_COLUMN_NAME,
+                BatchColumn::Time,
+                InfluxColumnType::Timestamp,
+            )])
+            .collect::<Vec<_>>();
+        columns.sort_by_key(|(_, name, _, _)| *name);
+        let mut schema_builder = SchemaBuilder::with_capacity(columns.len());
+        for (_, name, _, column_type) in &columns {
+            schema_builder.influx_column(*name, *column_type);
+        }
+        let schema = schema_builder
+            .build()
+            .expect("validated columns make a valid schema");
+
+        let mut row_count = 0;
+        let mut coerced_count = 0;
+        let mut chunk_batches: BTreeMap<i64, Vec<RecordBatch>> = BTreeMap::new();
+        for (batch, times_ns) in self.state.batches.iter().zip(self.state.times_ns) {
+            let times_ns =
+                times_ns.unwrap_or_else(|| vec![ingest_time.timestamp_nanos(); batch.num_rows()]);
+            let arrays = columns
+                .iter()
+                .map(|(index, _, column, column_type)| match index {
+                    Some(index) => table_column(
+                        batch.column(*index),
+                        *column,
+                        *column_type,
+                        &mut coerced_count,
+                    ),
+                    None => Arc::new(TimestampNanosecondArray::from(times_ns.clone())) as ArrayRef,
+                })
+                .collect();
+            let batch = RecordBatch::try_new(schema.as_arrow(), arrays)
+                .expect("converted columns match the schema");
+            row_count += batch.num_rows();
+
+            let mut chunk_rows: BTreeMap<i64, Vec<u32>> = BTreeMap::new();
+            for (row, time_ns) in times_ns.iter().enumerate() {
+                let chunk_time = gen1_duration.chunk_time_for_timestamp(Timestamp::new(*time_ns));
+                chunk_rows.entry(chunk_time).or_default().push(row as u32);
+            }
+            if chunk_rows.len() == 1 {
+                let (chunk_time, _) = chunk_rows.pop_first().expect("there is one chunk");
+                chunk_batches.entry(chunk_time).or_default().push(batch);
+                continue;
+            }
+            for (chunk_time, rows) in chunk_rows {
+                let rows = take_record_batch(&batch, &UInt32Array::from(rows))
+                    .expect("rows are taken from the batch");
+                chunk_batches.entry(chunk_time).or_default().push(rows);
+            }
+        }
+        let chunks = chunk_batches
+            .into_iter()
+            .map(|(chunk_time, batches)| {
+                let batch = concat_batches(&schema.as_arrow(), &batches)
+                    .expect("batches of a chunk have the same schema");
+                (chunk_time, batch)
+            })
+            .collect();
+
+        ValidatedBatches {
+            row_count,
+            coerced_count,
+            database_id: self.state.catalog.db_schema.id,
+            table_id: table_def.table_id,
+            schema,
+            chunks,
+            catalog_updates: self.state.catalog_batch,
+        }
+    }
+}
+
+/// A column of a written record batch converted to the Arrow type of its column in the table,
+/// which is that of the column in the chunks of the buffer
+fn table_column(
+    array: &ArrayRef,
+    column: BatchColumn,
+    column_type: InfluxColumnType,
+    coerced_count: &mut usize,
+) -> ArrayRef {
+    let from = match column {
+        BatchColumn::Tag => {
+            let dictionary =
+                DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+            return cast(array, &dictionary).expect("tag columns cast to dictionaries");
+        }
+        BatchColumn::Field(field_type) => field_type,
+        BatchColumn::Time | BatchColumn::Dropped => return Arc::clone(array),
+    };
+    let array = match from {
+        InfluxFieldType::String => {
+            cast(array, &DataType::Utf8).expect("string columns cast to utf8")
+        }
+        _ => Arc::clone(array),
+    };
+    let to = match column_type {
+        InfluxColumnType::Field(to) if to != from => to,
+        _ => return array,
+    };
+    match to {
+        // values are converted to strings as they are for rows, rather than as Arrow casts them
+        InfluxFieldType::String => Arc::new(
+            (0..array.len())
+                .map(|row| {
+                    array.is_valid(row).then(|| {
+                        match coerce_field_data(
+                            field_data(&array, from, row),
+                            Some(column_type),
+                            coerced_count,
+                        ) {
+                            FieldData::String(value) => value,
+                            _ => unreachable!("values are coerced to strings"),
+                        }
+                    })
+                })
+                .collect::<StringArray>(),
+        ),
+        InfluxFieldType::Float | InfluxFieldType::Integer => {
+            *coerced_count += array.len() - array.null_count();
+            let data_type = if to == InfluxFieldType::Float {
+                DataType::Float64
+            } else {
+                DataType::Int64
+            };
+            cast(&array, &data_type).expect("values were validated to fit the column")
+        }
+        InfluxFieldType::UInteger | InfluxFieldType::Boolean => {
+            unreachable!("columns are not coerced to {to}")
+        }
+    }
+}
+
+/// The value of a non-null field in a record batch column, whose string values have been cast to
+/// `Utf8`
+fn field_data(array: &ArrayRef, field_type: InfluxFieldType, row: usize) -> FieldData {
+    match field_type {
+        InfluxFieldType::Float => FieldData::Float(array.as_primitive::<Float64Type>().value(row)),
+        InfluxFieldType::Integer => {
+            FieldData::Integer(array.as_primitive::<Int64Type>().value(row))
+        }
+        InfluxFieldType::UInteger => {
+            FieldData::UInteger(array.as_primitive::<UInt64Type>().value(row))
+        }
+        InfluxFieldType::String => {
+            FieldData::String(array.as_string::<i32>().value(row).to_string())
+        }
+        InfluxFieldType::Boolean => FieldData::Boolean(array.as_boolean().value(row)),
+    }
 }
 
 fn apply_precision_to_timestamp(precision: Precision, ts: i64) -> i64 {
@@ -821,10 +1735,20 @@ mod tests {
     use std::sync::Arc;
 
     use crate::{catalog::Catalog, write_buffer::Error, Precision};
+    use arrow::array::{
+        ArrayRef, BooleanArray, Float64Array, StringArray, TimestampMillisecondArray,
+    };
+    use arrow::datatypes::{DataType, Field as ArrowField, Schema, TimeUnit};
+    use arrow::record_batch::RecordBatch;
     use data_types::NamespaceName;
     use influxdb3_id::TableId;
-    use influxdb3_wal::Gen1Duration;
+    use influxdb3_wal::{
+        CatalogBatch, CatalogOp, CoercionPolicy, CoercionPolicyDefinition, FieldData, Gen1Duration,
+        SchemaPolicy, SchemaPolicyDefinition,
+    };
     use iox_time::Time;
+    use schema::{InfluxColumnType, InfluxFieldType, COLUMN_METADATA_KEY};
+    use test_helpers::assert_contains;
 
     use super::WriteValidator;
 
@@ -858,4 +1782,319 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn write_validator_v1_schema_policies() -> Result<(), Error> {
+        let namespace = NamespaceName::new("test").unwrap();
+        let catalog = Arc::new(Catalog::new(
+            "sample-host-id".into(),
+            "sample-instance-id".into(),
+        ));
+        let batch = catalog.create_table(
+            "test",
+            "cpu",
+            &["host"],
+            &[("usage", InfluxFieldType::Float)],
+            0,
+        )?;
+        let cpu_id = catalog
+            .db_schema("test")
+            .unwrap()
+            .table_name_to_id("cpu")
+            .unwrap();
+        let set_policy = |table_id: Option<TableId>, policy: SchemaPolicy| {
+            catalog.apply_catalog_batch(&CatalogBatch {
+                database_id: batch.database_id,
+                database_name: Arc::clone(&batch.database_name),
+                time_ns: 0,
+                ops: vec![CatalogOp::SetSchemaPolicy(SchemaPolicyDefinition {
+                    table_id,
+                    table_name: None,
+                    policy,
+                })],
+            })
+        };
+        let lp = "\
+            cpu,host=a usage=0.5 1\n\
+            cpu,host=a,region=us usage=0.5 2\n\
+            cpu,host=a usage=0.5,temp=20 3\n\
+            cpu,host=a temp=20 4\n\
+            mem,host=a used=1i 5";
+        let validate = || {
+            WriteValidator::initialize(namespace.clone(), Arc::clone(&catalog), 0)
+                .unwrap()
+                .v1_parse_lines_and_update_schema(lp, true)
+                .unwrap()
+                .convert_lines_to_buffer(
+                    Time::from_timestamp_nanos(0),
+                    Gen1Duration::new_5m(),
+                    Precision::Auto,
+                )
+        };
+
+        // a locked database rejects the lines with new columns, and for new tables:
+        set_policy(None, SchemaPolicy::Locked)?;
+        let result = validate();
+        assert_eq!(result.line_count, 1);
+        assert_eq!(
+            result
+                .errors
+                .iter()
+                .map(|e| e.line_number)
+                .collect::<Vec<_>>(),
+            vec![2, 3, 4, 5]
+        );
+        assert_contains!(&result.errors[0].error_message, "[region]");
+        assert_contains!(&result.errors[0].error_message, "locked");
+        assert!(result.catalog_updates.is_none());
+
+        // the policy of the table overrides that of the database, so unknown columns are dropped,
+        // unless they are all of the fields of the line:
+        set_policy(Some(cpu_id), SchemaPolicy::DropUnknown)?;
+        let result = validate();
+        assert_eq!(result.line_count, 3);
+        assert_eq!(result.field_count, 3);
+        assert_eq!(result.index_count, 3);
+        assert_eq!(
+            result
+                .errors
+                .iter()
+                .map(|e| e.line_number)
+                .collect::<Vec<_>>(),
+            vec![4, 5]
+        );
+        assert!(result.catalog_updates.is_none());
+        let cpu = catalog
+            .db_schema("test")
+            .unwrap()
+            .table_schema("cpu")
+            .unwrap();
+        assert!(cpu.field_type_by_name("region").is_none());
+        assert!(cpu.field_type_by_name("temp").is_none());
+
+        // an open table gets the new columns, while new tables are still rejected by the database:
+        set_policy(Some(cpu_id), SchemaPolicy::Open)?;
+        let result = validate();
+        assert_eq!(result.line_count, 4);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].line_number, 5);
+        assert!(result.catalog_updates.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_validator_v1_coercion_policy() -> Result<(), Error> {
+        let namespace = NamespaceName::new("test").unwrap();
+        let catalog = Arc::new(Catalog::new(
+            "sample-host-id".into(),
+            "sample-instance-id".into(),
+        ));
+        let batch = catalog.create_table(
+            "test",
+            "cpu",
+            &["host"],
+            &[
+                ("usage", InfluxFieldType::Float),
+                ("count", InfluxFieldType::Integer),
+                ("status", InfluxFieldType::String),
+            ],
+            0,
+        )?;
+        let cpu_id = catalog
+            .db_schema("test")
+            .unwrap()
+            .table_name_to_id("cpu")
+            .unwrap();
+        let lp = "\
+            cpu,host=a usage=1i,count=2u,status=true 1\n\
+            cpu,host=a count=18446744073709551615u 2\n\
+            cpu,host=a usage=0.5,count=3i,status=\"ok\" 3";
+        let validate = || {
+            WriteValidator::initialize(namespace.clone(), Arc::clone(&catalog), 0)
+                .unwrap()
+                .v1_parse_lines_and_update_schema(lp, true)
+                .unwrap()
+                .convert_lines_to_buffer(
+                    Time::from_timestamp_nanos(0),
+                    Gen1Duration::new_5m(),
+                    Precision::Nanosecond,
+                )
+        };
+
+        // values that do not match the type of their column are rejected by default:
+        let result = validate();
+        assert_eq!(result.line_count, 1);
+        assert_eq!(result.coerced_count, 0);
+        assert_contains!(&result.errors[0].error_message, "expected type");
+
+        catalog.apply_catalog_batch(&CatalogBatch {
+            database_id: batch.database_id,
+            database_name: Arc::clone(&batch.database_name),
+            time_ns: 0,
+            ops: vec![CatalogOp::SetCoercionPolicy(CoercionPolicyDefinition {
+                table_id: cpu_id,
+                table_name: "cpu".into(),
+                policy: CoercionPolicy {
+                    integer_to_float: true,
+                    uinteger_to_integer: true,
+                    any_to_string: true,
+                },
+            })],
+        })?;
+
+        // the values are converted, unless they do not fit the type of their column:
+        let result = validate();
+        assert_eq!(result.line_count, 2);
+        assert_eq!(result.coerced_count, 3);
+        assert_eq!(
+            result
+                .errors
+                .iter()
+                .map(|e| e.line_number)
+                .collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert!(result.catalog_updates.is_none());
+        let row = result.valid_data.table_chunks[&cpu_id]
+            .chunk_time_to_chunk
+            .values()
+            .flat_map(|chunk| chunk.rows.iter())
+            .find(|row| row.time == 1)
+            .unwrap();
+        let value_of = |name: &str| {
+            row.fields
+                .iter()
+                .find(|f| f.name.as_ref() == name)
+                .map(|f| f.value.clone())
+                .unwrap()
+        };
+        assert_eq!(FieldData::Float(1.0), value_of("usage"));
+        assert_eq!(FieldData::Integer(2), value_of("count"));
+        assert_eq!(FieldData::String("true".to_string()), value_of("status"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_validator_record_batches() -> Result<(), Error> {
+        let namespace = NamespaceName::new("test").unwrap();
+        let catalog = Arc::new(Catalog::new("host".into(), "instance".into()));
+        let field = |name: &str, data_type: DataType, column_type: Option<&str>| {
+            let metadata = column_type
+                .map(|t| [(COLUMN_METADATA_KEY.to_string(), t.to_string())].into())
+                .unwrap_or_default();
+            ArrowField::new(name, data_type, true).with_metadata(metadata)
+        };
+        let batch = |fields: Vec<ArrowField>, columns: Vec<ArrayRef>| {
+            RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap()
+        };
+        let host = field("host", DataType::Utf8, Some("iox::column_type::tag"));
+        let usage = field(
+            "usage",
+            DataType::Float64,
+            Some("iox::column_type::field::float"),
+        );
+        let time = field(
+            "time",
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            None,
+        );
+
+        // a new table is created with the columns of the batches:
+        let result = WriteValidator::initialize(namespace.clone(), Arc::clone(&catalog), 0)?
+            .validate_record_batches_and_update_schema(
+                "cpu",
+                vec![batch(
+                    vec![host.clone(), usage.clone(), time.clone()],
+                    vec![
+                        Arc::new(StringArray::from(vec![Some("a"), None])),
+                        Arc::new(Float64Array::from(vec![0.5, 0.7])),
+                        Arc::new(TimestampMillisecondArray::from(vec![1_000, 2_000])),
+                    ],
+                )],
+            )?
+            .convert_batches_to_buffer(Time::from_timestamp_nanos(0), Gen1Duration::new_5m());
+        assert_eq!(2, result.line_count);
+        assert_eq!(2, result.field_count);
+        assert_eq!(1, result.index_count);
+        assert!(matches!(
+            result.catalog_updates.as_ref().map(|batch| &batch.ops[..]),
+            Some([CatalogOp::CreateTable(_)])
+        ));
+        // the new table is added once the write applies the catalog updates
+        assert!(catalog
+            .db_schema("test")
+            .unwrap()
+            .table_definition("cpu")
+            .is_none());
+        catalog.apply_catalog_batch(result.catalog_updates.as_ref().unwrap())?;
+        let db_schema = catalog.db_schema("test").unwrap();
+        let table_def = db_schema.table_definition("cpu").unwrap();
+        assert_eq!(
+            Some(InfluxColumnType::Tag),
+            table_def.field_type_by_name("host")
+        );
+        assert_eq!(
+            Some(InfluxColumnType::Field(InfluxFieldType::Float)),
+            table_def.field_type_by_name("usage")
+        );
+        let chunks = result
+            .valid_data
+            .table_chunks
+            .get(&table_def.table_id)
+            .unwrap();
+        assert_eq!(
+            (1_000_000_000, 2_000_000_000),
+            (chunks.min_time, chunks.max_time)
+        );
+
+        // batches that cannot be written fail as a whole:
+        let test_cases = [
+            // a column that is not marked as a tag or field:
+            batch(
+                vec![usage.clone(), field("region", DataType::Utf8, None)],
+                vec![
+                    Arc::new(Float64Array::from(vec![0.5])),
+                    Arc::new(StringArray::from(vec!["us"])),
+                ],
+            ),
+            // a field of a different type than its column:
+            batch(
+                vec![field(
+                    "usage",
+                    DataType::Boolean,
+                    Some("iox::column_type::field::boolean"),
+                )],
+                vec![Arc::new(BooleanArray::from(vec![true]))],
+            ),
+            // a field marked with a type that is not its Arrow type:
+            batch(
+                vec![field(
+                    "usage",
+                    DataType::Float64,
+                    Some("iox::column_type::field::integer"),
+                )],
+                vec![Arc::new(Float64Array::from(vec![0.5]))],
+            ),
+            // a row without field values:
+            batch(
+                vec![host, usage],
+                vec![
+                    Arc::new(StringArray::from(vec!["a", "b"])),
+                    Arc::new(Float64Array::from(vec![Some(0.5), None])),
+                ],
+            ),
+        ];
+        for (i, batch) in test_cases.into_iter().enumerate() {
+            let result = WriteValidator::initialize(namespace.clone(), Arc::clone(&catalog), 0)?
+                .validate_record_batches_and_update_schema("cpu", vec![batch]);
+            assert!(
+                matches!(result, Err(Error::InvalidRecordBatch(_))),
+                "test case ({i}) failed"
+            );
+        }
+
+        Ok(())
+    }
 }